@@ -0,0 +1,293 @@
+//! Example Integrator
+//!
+//! A minimal program calling into Morpho Solana through CPI, kept in the
+//! workspace as living documentation and as a build-time check that the
+//! generated `morpho_solana::cpi` interface still fits real callers.
+//!
+//! ## Instructions
+//! - `deposit`: supply loan tokens on the signer's behalf
+//! - `borrow`: borrow against the signer's position, forwarding any
+//!   yield-adapter accounts Morpho needs to recall liquidity
+//! - `flash_loan_with_callback`: start a flash loan, run `on_flash_loan`,
+//!   then settle it, all inside one instruction
+//!
+//! Morpho's single-instruction `flash_loan` checks repayment before the
+//! borrower regains control, so programs with work to do between borrowing
+//! and repaying use the `flash_loan_start` / `flash_loan_end` pair as shown.
+//! Both calls pin the `PROGRAM_VERSION` this program was built against, so
+//! an incompatible Morpho upgrade fails the loan instead of running it.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+use morpho_solana::constants::{BPS, FLASH_LOAN_FEE_BPS, PROGRAM_VERSION};
+use morpho_solana::cpi::accounts::{
+    Borrow as MorphoBorrow, FlashLoanEnd as MorphoFlashLoanEnd,
+    FlashLoanStart as MorphoFlashLoanStart, Supply as MorphoSupply,
+};
+use morpho_solana::math::{checked_add, mul_div_up};
+use morpho_solana::program::MorphoSolana;
+
+declare_id!("Ag6cNnRpzHhiYEi52yp5koLG4aGbbor2PxXp6tiBG5fZ");
+
+#[program]
+pub mod example_integrator {
+    use super::*;
+
+    pub fn deposit(
+        ctx: Context<Deposit>,
+        market_id: [u8; 32],
+        assets: u128,
+        min_shares: u128,
+    ) -> Result<()> {
+        let accounts = &ctx.accounts;
+        morpho_solana::cpi::supply(
+            CpiContext::new(
+                accounts.morpho_program.to_account_info(),
+                MorphoSupply {
+                    supplier: accounts.user.to_account_info(),
+                    protocol_state: accounts.protocol_state.to_account_info(),
+                    market: accounts.market.to_account_info(),
+                    position: accounts.position.to_account_info(),
+                    on_behalf_of: accounts.user.to_account_info(),
+                    supplier_token_account: accounts.user_token_account.to_account_info(),
+                    loan_vault: accounts.loan_vault.to_account_info(),
+                    loan_mint: accounts.loan_mint.to_account_info(),
+                    token_program: accounts.token_program.to_account_info(),
+                    holding_exemption: None,
+                },
+            ),
+            market_id,
+            assets,
+            min_shares,
+        )
+    }
+
+    /// Remaining accounts are passed through to Morpho untouched
+    pub fn borrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExampleBorrow<'info>>,
+        market_id: [u8; 32],
+        assets: u128,
+        max_shares: u128,
+    ) -> Result<()> {
+        let accounts = &ctx.accounts;
+        morpho_solana::cpi::borrow(
+            CpiContext::new(
+                accounts.morpho_program.to_account_info(),
+                MorphoBorrow {
+                    caller: accounts.user.to_account_info(),
+                    protocol_state: accounts.protocol_state.to_account_info(),
+                    market: accounts.market.to_account_info(),
+                    position: accounts.position.to_account_info(),
+                    authorization: None,
+                    oracle: accounts.oracle.to_account_info(),
+                    loan_mint: accounts.loan_mint.to_account_info(),
+                    receiver: accounts.user.to_account_info(),
+                    receiver_token_account: accounts.user_token_account.to_account_info(),
+                    loan_vault: accounts.loan_vault.to_account_info(),
+                    token_program: accounts.token_program.to_account_info(),
+                    associated_token_program: accounts.associated_token_program.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                    risk_oracle: accounts.risk_oracle.as_ref().map(|a| a.to_account_info()),
+                    fallback_oracle: accounts.fallback_oracle.as_ref().map(|a| a.to_account_info()),
+                },
+            )
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec()),
+            market_id,
+            assets,
+            max_shares,
+        )
+    }
+
+    /// Borrow `amount` for the length of `on_flash_loan`
+    ///
+    /// The user's token account must hold the fee up front; whatever the
+    /// callback does, principal plus fee has to be back there before the
+    /// loan settles.
+    pub fn flash_loan_with_callback(
+        ctx: Context<FlashLoanWithCallback>,
+        market_id: [u8; 32],
+        amount: u128,
+    ) -> Result<()> {
+        let accounts = &ctx.accounts;
+        morpho_solana::cpi::flash_loan_start(
+            CpiContext::new(
+                accounts.morpho_program.to_account_info(),
+                MorphoFlashLoanStart {
+                    borrower: accounts.user.to_account_info(),
+                    protocol_state: accounts.protocol_state.to_account_info(),
+                    market: accounts.market.to_account_info(),
+                    borrower_token_account: accounts.user_token_account.to_account_info(),
+                    loan_vault: accounts.loan_vault.to_account_info(),
+                    loan_mint: accounts.loan_mint.to_account_info(),
+                    token_program: accounts.token_program.to_account_info(),
+                    allowlist_entry: None,
+                },
+            ),
+            market_id,
+            amount,
+            Some(PROGRAM_VERSION),
+        )?;
+
+        on_flash_loan(&mut ctx.accounts.user_token_account, amount)?;
+
+        let accounts = &ctx.accounts;
+        morpho_solana::cpi::flash_loan_end(
+            CpiContext::new(
+                accounts.morpho_program.to_account_info(),
+                MorphoFlashLoanEnd {
+                    borrower: accounts.user.to_account_info(),
+                    market: accounts.market.to_account_info(),
+                    borrower_token_account: accounts.user_token_account.to_account_info(),
+                    loan_vault: accounts.loan_vault.to_account_info(),
+                    loan_mint: accounts.loan_mint.to_account_info(),
+                    token_program: accounts.token_program.to_account_info(),
+                },
+            ),
+            market_id,
+            amount,
+            Some(PROGRAM_VERSION),
+        )
+    }
+}
+
+/// Tokens owed back when a flash loan of `amount` settles
+pub fn flash_loan_repayment(amount: u128) -> Result<u128> {
+    let fee = mul_div_up(amount, FLASH_LOAN_FEE_BPS as u128, BPS as u128)?;
+    checked_add(amount, fee)
+}
+
+/// Flash loan callback: the borrowed tokens are in `token_account`
+///
+/// A real integrator would arbitrage, refinance or liquidate here. The
+/// example only checks the loan arrived and that settling won't fail.
+fn on_flash_loan<'info>(
+    token_account: &mut InterfaceAccount<'info, TokenAccount>,
+    amount: u128,
+) -> Result<()> {
+    token_account.reload()?;
+    require!(
+        token_account.amount as u128 >= flash_loan_repayment(amount)?,
+        ExampleError::CannotRepayFlashLoan
+    );
+    msg!("flash loan of {} received", amount);
+    Ok(())
+}
+
+#[error_code]
+pub enum ExampleError {
+    #[msg("Token account can't cover flash loan principal plus fee")]
+    CannotRepayFlashLoan,
+}
+
+// ============================================================================
+// Accounts
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: Validated by Morpho
+    pub protocol_state: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Morpho
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+
+    /// CHECK: User's Morpho position, validated by Morpho
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Morpho
+    #[account(mut)]
+    pub user_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Morpho
+    #[account(mut)]
+    pub loan_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Morpho
+    pub loan_mint: UncheckedAccount<'info>,
+
+    /// CHECK: SPL Token or Token-2022, validated by Morpho
+    pub token_program: UncheckedAccount<'info>,
+
+    pub morpho_program: Program<'info, MorphoSolana>,
+}
+
+#[derive(Accounts)]
+pub struct ExampleBorrow<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: Validated by Morpho
+    pub protocol_state: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Morpho
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+
+    /// CHECK: User's Morpho position, validated by Morpho
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Morpho
+    pub oracle: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Morpho
+    pub loan_mint: UncheckedAccount<'info>,
+
+    /// CHECK: User's loan token ATA, created by Morpho if missing
+    #[account(mut)]
+    pub user_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Morpho
+    #[account(mut)]
+    pub loan_vault: UncheckedAccount<'info>,
+
+    /// CHECK: SPL Token or Token-2022, validated by Morpho
+    pub token_program: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Morpho
+    pub associated_token_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Market's risk oracle feed, if it has one
+    pub risk_oracle: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Market's fallback oracle, if it has one
+    pub fallback_oracle: Option<UncheckedAccount<'info>>,
+
+    pub morpho_program: Program<'info, MorphoSolana>,
+}
+
+#[derive(Accounts)]
+pub struct FlashLoanWithCallback<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: Validated by Morpho
+    pub protocol_state: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Morpho
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+
+    /// Read by the callback, so typed here
+    #[account(mut, token::authority = user)]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Validated by Morpho
+    #[account(mut)]
+    pub loan_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Morpho
+    pub loan_mint: UncheckedAccount<'info>,
+
+    /// CHECK: SPL Token or Token-2022, validated by Morpho
+    pub token_program: UncheckedAccount<'info>,
+
+    pub morpho_program: Program<'info, MorphoSolana>,
+}
@@ -0,0 +1,19 @@
+//! Example Integrator Tests
+//!
+//! The CPI wiring itself is checked at build time; these pin the example's
+//! repayment math to Morpho's.
+
+use example_integrator::flash_loan_repayment;
+use morpho_solana::constants::{BPS, FLASH_LOAN_FEE_BPS};
+use morpho_solana::math::mul_div_up;
+
+#[test]
+fn test_flash_loan_repayment_matches_morpho_fee() {
+    for amount in [1u128, 1_999, 2_000, 1_000_000_000, u64::MAX as u128] {
+        let fee = mul_div_up(amount, FLASH_LOAN_FEE_BPS as u128, BPS as u128).unwrap();
+        assert_eq!(flash_loan_repayment(amount).unwrap(), amount + fee);
+    }
+
+    // Fee rounds up, so even dust loans cost something
+    assert!(flash_loan_repayment(1).unwrap() > 1);
+}
@@ -0,0 +1,181 @@
+//! Internal invariant checks (`audit-assertions` feature)
+//!
+//! With the feature on, value-moving instructions finish with `audit!`,
+//! which re-checks the market's books (and the position's, when given):
+//!
+//! - debt never exceeds supply, fee shares never exceed all shares
+//! - a position's shares fit inside the market totals
+//! - a market vault passed in remaining accounts covers what the books say
+//!   it holds
+//!
+//! Vaults are optional so callers pick which to cross-check; they're found
+//! by address, so they can sit anywhere among the remaining accounts. A
+//! broken invariant fails the instruction with `AuditInvariantBroken` and
+//! logs which one. Without the feature `audit!` expands to nothing, so
+//! release builds pay no compute for it. The feature is for test clusters
+//! and fuzzing only and can't be combined with mainnet.
+
+#[cfg(all(feature = "audit-assertions", feature = "mainnet"))]
+compile_error!("the `audit-assertions` feature can't be combined with `mainnet`");
+
+/// Check the market's invariants, then the position's if one is given
+#[macro_export]
+macro_rules! audit {
+    ($market:expr, $remaining_accounts:expr $(, $position:expr)? $(,)?) => {
+        #[cfg(feature = "audit-assertions")]
+        {
+            $crate::audit::check_market(&$market, $remaining_accounts)?;
+            $( $crate::audit::check_position(&$market, &$position)?; )?
+        }
+    };
+}
+
+#[cfg(feature = "audit-assertions")]
+pub use checks::*;
+
+#[cfg(feature = "audit-assertions")]
+mod checks {
+    use anchor_lang::prelude::*;
+    use crate::constants::PROGRAM_SEED_PREFIX;
+    use crate::errors::MorphoError;
+    use crate::state::{Market, Position};
+
+    /// Offset of `amount` in SPL Token and Token-2022 accounts
+    const TOKEN_AMOUNT_OFFSET: usize = 64;
+
+    fn invariant(holds: bool, name: &str) -> Result<()> {
+        if !holds {
+            msg!("Audit invariant broken: {}", name);
+            return Err(MorphoError::AuditInvariantBroken.into());
+        }
+        Ok(())
+    }
+
+    /// Check the market's totals, and any of its vaults in `remaining_accounts`
+    pub fn check_market(market: &Market, remaining_accounts: &[AccountInfo]) -> Result<()> {
+        invariant(market.total_debt() <= market.total_supply_assets, "debt <= supply")?;
+        invariant(
+            market.pending_fee_shares <= market.total_supply_shares,
+            "fee shares <= supply shares",
+        )?;
+
+        let collateral_vault =
+            vault_balance(market, Market::COLLATERAL_VAULT_SEED, market.collateral_vault_bump, remaining_accounts)?;
+        if let Some(balance) = collateral_vault {
+            invariant(market.collateral_drift(balance)? >= 0, "collateral vault covers positions")?;
+        }
+        let loan_vault =
+            vault_balance(market, Market::LOAN_VAULT_SEED, market.loan_vault_bump, remaining_accounts)?;
+        if let Some(balance) = loan_vault {
+            // Mid flash loan the borrowed amount is out of the vault
+            let lent = if market.is_flash_loan_active() { market.flash_loan_amount } else { 0 };
+            let held = (balance as u128)
+                .saturating_add(market.loan_deployed)
+                .saturating_add(lent);
+            invariant(held >= market.available_liquidity(), "loan vault covers liquidity")?;
+        }
+        Ok(())
+    }
+
+    /// Check a position against its market's totals
+    pub fn check_position(market: &Market, position: &Position) -> Result<()> {
+        invariant(
+            position.supply_shares <= market.total_supply_shares,
+            "position supply shares <= total",
+        )?;
+        invariant(
+            position.borrow_shares <= market.total_borrow_shares,
+            "position borrow shares <= total",
+        )?;
+        invariant(
+            position.escrowed_supply_shares <= position.supply_shares,
+            "escrowed shares <= supply shares",
+        )?;
+        Ok(())
+    }
+
+    /// Balance of the market vault at `seed`, if it's among `accounts`
+    fn vault_balance(
+        market: &Market,
+        seed: &[u8],
+        bump: u8,
+        accounts: &[AccountInfo],
+    ) -> Result<Option<u64>> {
+        if accounts.is_empty() {
+            return Ok(None);
+        }
+        // A bump that doesn't derive an address can't be any vault's
+        let Ok(address) = Pubkey::create_program_address(
+            &[PROGRAM_SEED_PREFIX, seed, &market.market_id, &[bump]],
+            &crate::ID,
+        ) else {
+            return Ok(None);
+        };
+        let Some(vault) = accounts.iter().find(|account| account.key() == address) else {
+            return Ok(None);
+        };
+
+        let data = vault.try_borrow_data()?;
+        let amount = data
+            .get(TOKEN_AMOUNT_OFFSET..TOKEN_AMOUNT_OFFSET + 8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(MorphoError::AuditInvariantBroken)?;
+        Ok(Some(u64::from_le_bytes(amount)))
+    }
+}
+
+#[cfg(all(test, feature = "audit-assertions"))]
+mod tests {
+    use anchor_lang::prelude::*;
+    use anchor_lang::solana_program::account_info::AccountInfo;
+    use crate::constants::PROGRAM_SEED_PREFIX;
+    use crate::errors::MorphoError;
+    use crate::state::{Market, Position};
+    use super::*;
+
+    fn zeroed<T: AnchorDeserialize>(space: usize) -> T {
+        T::deserialize(&mut &vec![0u8; space - 8][..]).unwrap()
+    }
+
+    #[test]
+    fn test_broken_books_fail_the_audit() {
+        let mut market: Market = zeroed(Market::space());
+        let mut position: Position = zeroed(Position::space());
+        assert!(check_market(&market, &[]).is_ok());
+        assert!(check_position(&market, &position).is_ok());
+
+        market.total_borrow_assets = 1;
+        assert_eq!(check_market(&market, &[]).unwrap_err(), MorphoError::AuditInvariantBroken.into());
+        market.total_supply_assets = 1;
+        assert!(check_market(&market, &[]).is_ok());
+
+        position.supply_shares = 1;
+        assert_eq!(check_position(&market, &position).unwrap_err(), MorphoError::AuditInvariantBroken.into());
+    }
+
+    #[test]
+    fn test_collateral_vault_cross_check() {
+        let mut market: Market = zeroed(Market::space());
+        market.market_id = [3u8; 32];
+        let (vault, bump) = Pubkey::find_program_address(
+            &[PROGRAM_SEED_PREFIX, Market::COLLATERAL_VAULT_SEED, &market.market_id],
+            &crate::ID,
+        );
+        market.collateral_vault_bump = bump;
+        market.total_collateral = 100;
+
+        let owner = anchor_spl::token::ID;
+        let mut lamports = 0;
+        let mut data = vec![0u8; 165];
+        data[64..72].copy_from_slice(&99u64.to_le_bytes());
+        let account = AccountInfo::new(&vault, false, true, &mut lamports, &mut data, &owner, false, 0);
+
+        assert!(check_market(&market, &[]).is_ok(), "Vaults are only checked when passed");
+        assert_eq!(
+            check_market(&market, std::slice::from_ref(&account)).unwrap_err(),
+            MorphoError::AuditInvariantBroken.into()
+        );
+        market.total_collateral = 99;
+        assert!(check_market(&market, std::slice::from_ref(&account)).is_ok());
+    }
+}
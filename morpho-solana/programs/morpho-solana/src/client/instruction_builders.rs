@@ -0,0 +1,2939 @@
+//! Instruction builders for non-Anchor Rust clients
+//!
+//! Every builder returns a ready-to-sign `Instruction` with the account
+//! ordering the program expects. PDAs (protocol state, market, vaults,
+//! positions, authorizations, receiver ATAs) are resolved internally so
+//! callers only supply wallets, user token accounts, and amounts.
+//!
+//! Bundles and flash loans pin the `PROGRAM_VERSION` the client was built
+//! with, so they fail against an incompatible deployment.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use crate::constants::PROGRAM_VERSION;
+use crate::state::{
+    Market, MarketConfigUpdate, calculate_market_id, derive_authorization,
+    derive_collateral_vault, derive_config_proposal, derive_credit_line,
+    derive_flash_loan_allowlist_entry, derive_holding_period_exemption, derive_irm_update_proposal,
+    derive_liquidation_reference, derive_lltv_bounds, derive_loan_vault, derive_market, derive_market_metadata,
+    derive_oracle_update_proposal, derive_position, derive_protocol_state, derive_token_badge,
+};
+#[cfg(feature = "multi-collateral")]
+use crate::state::{
+    McMarket, calculate_mc_market_id, derive_mc_collateral_vault, derive_mc_loan_vault, derive_mc_market,
+    derive_mc_position,
+};
+#[cfg(feature = "devnet")]
+use crate::state::derive_clock_override;
+use crate::instructions::{AdminAction, BundleAction, PositionAdjustment};
+use crate::interfaces::{
+    derive_adaptive_curve_irm, derive_adaptive_curve_model, derive_clmm_twap_oracle, derive_composite_oracle,
+    derive_ewma_oracle, derive_price_relay, derive_twap_oracle,
+};
+use crate::{accounts as accts, instruction as ix};
+
+/// Static keys identifying a market and everything derived from them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarketKeys {
+    pub market_id: [u8; 32],
+    pub collateral_mint: Pubkey,
+    pub loan_mint: Pubkey,
+    pub oracle: Pubkey,
+    pub irm: Pubkey,
+    pub lltv: u64,
+    /// Token program owning both mints (SPL Token or Token-2022)
+    pub token_program: Pubkey,
+    /// Risk oracle feed borrows must pass (market config, not part of the id)
+    pub risk_oracle: Option<Pubkey>,
+    /// Secondary price feed (market config, not part of the id)
+    pub fallback_oracle: Option<Pubkey>,
+    /// `irm` is a program queried by CPI, so instructions that accrue pass
+    /// it as a remaining account (market config, not part of the id)
+    pub irm_cpi: bool,
+    /// `irm` is the adaptive curve model, so instructions that accrue pass
+    /// the market's curve state as a writable remaining account (derived
+    /// from `irm`)
+    pub irm_adaptive: bool,
+    /// Feed liquidations are checked against (market config, not part of the id)
+    pub reference_oracle: Option<Pubkey>,
+}
+
+impl MarketKeys {
+    /// Keys of a market as created: `market_id` hashes this tuple
+    ///
+    /// Migrations move a market's oracle or IRM without touching its id, so
+    /// for a migrated market use `from_market` or carry the creation keys
+    /// over with `after_oracle_update` / `after_irm_update`.
+    pub fn new(
+        collateral_mint: Pubkey,
+        loan_mint: Pubkey,
+        oracle: Pubkey,
+        irm: Pubkey,
+        lltv: u64,
+        token_program: Pubkey,
+    ) -> Self {
+        Self {
+            market_id: calculate_market_id(&collateral_mint, &loan_mint, &oracle, &irm, lltv),
+            collateral_mint,
+            loan_mint,
+            oracle,
+            irm,
+            lltv,
+            token_program,
+            risk_oracle: None,
+            fallback_oracle: None,
+            irm_cpi: false,
+            irm_adaptive: irm == derive_adaptive_curve_model(&crate::ID).0,
+            reference_oracle: None,
+        }
+    }
+
+    /// Pass `risk_oracle` to every borrow built from these keys
+    pub fn with_risk_oracle(mut self, risk_oracle: Pubkey) -> Self {
+        self.risk_oracle = Some(risk_oracle);
+        self
+    }
+
+    /// Pass `fallback_oracle` to every instruction built from these keys
+    /// that prices the position
+    pub fn with_fallback_oracle(mut self, fallback_oracle: Pubkey) -> Self {
+        self.fallback_oracle = Some(fallback_oracle);
+        self
+    }
+
+    /// Pass the IRM program to every instruction built from these keys that
+    /// accrues interest
+    pub fn with_irm_cpi(mut self) -> Self {
+        self.irm_cpi = true;
+        self
+    }
+
+    /// Pass `reference_oracle` to every liquidation built from these keys
+    pub fn with_reference_oracle(mut self, reference_oracle: Pubkey) -> Self {
+        self.reference_oracle = Some(reference_oracle);
+        self
+    }
+
+    /// Keys after `execute_oracle_update` moved the market to `oracle`
+    ///
+    /// The id, and every PDA seeded by it, still hash the creation oracle.
+    pub fn after_oracle_update(mut self, oracle: Pubkey) -> Self {
+        self.oracle = oracle;
+        self
+    }
+
+    /// Keys after `set_market_irm` moved the market to `irm`
+    ///
+    /// `irm_cpi` mirrors the market flag: set it when `irm` is an executable
+    /// program. The id still hashes the creation IRM.
+    pub fn after_irm_update(mut self, irm: Pubkey, irm_cpi: bool) -> Self {
+        self.irm = irm;
+        self.irm_cpi = irm_cpi;
+        self.irm_adaptive = irm == derive_adaptive_curve_model(&crate::ID).0;
+        self
+    }
+
+    /// Build keys from a fetched Market account
+    ///
+    /// The reference oracle lives in its own account; add it with
+    /// `with_reference_oracle`.
+    pub fn from_market(market: &Market, token_program: Pubkey) -> Self {
+        Self {
+            market_id: market.market_id,
+            collateral_mint: market.collateral_mint,
+            loan_mint: market.loan_mint,
+            oracle: market.oracle,
+            irm: market.irm,
+            lltv: market.lltv,
+            token_program,
+            risk_oracle: (market.risk_oracle != Pubkey::default()).then_some(market.risk_oracle),
+            fallback_oracle: (market.fallback_oracle != Pubkey::default()).then_some(market.fallback_oracle),
+            irm_cpi: market.irm_cpi,
+            irm_adaptive: market.irm_adaptive,
+            reference_oracle: None,
+        }
+    }
+
+    pub fn market(&self) -> Pubkey {
+        derive_market(&crate::ID, &self.market_id).0
+    }
+
+    pub fn collateral_vault(&self) -> Pubkey {
+        derive_collateral_vault(&crate::ID, &self.market_id).0
+    }
+
+    pub fn loan_vault(&self) -> Pubkey {
+        derive_loan_vault(&crate::ID, &self.market_id).0
+    }
+
+    pub fn position(&self, owner: &Pubkey) -> Pubkey {
+        derive_position(&crate::ID, &self.market_id, owner).0
+    }
+
+    pub fn credit_line(&self, supplier: &Pubkey, borrower: &Pubkey) -> Pubkey {
+        derive_credit_line(&crate::ID, &self.market_id, supplier, borrower).0
+    }
+
+    /// Loan token ATA for a wallet (the address borrow/withdraw pay out to)
+    pub fn loan_ata(&self, wallet: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(wallet, &self.loan_mint, &self.token_program)
+    }
+
+    /// Collateral token ATA for a wallet
+    pub fn collateral_ata(&self, wallet: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(wallet, &self.collateral_mint, &self.token_program)
+    }
+}
+
+fn protocol_state() -> Pubkey {
+    derive_protocol_state(&crate::ID).0
+}
+
+/// Authorization PDA when `caller` acts on someone else's position
+fn authorization_for(caller: &Pubkey, owner: &Pubkey) -> Option<Pubkey> {
+    if caller == owner {
+        None
+    } else {
+        Some(derive_authorization(&crate::ID, owner, caller).0)
+    }
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn with_irm(keys: &MarketKeys, ix: Instruction) -> Instruction {
+    if keys.irm_cpi {
+        append_irm(ix, keys.irm)
+    } else if keys.irm_adaptive {
+        append_adaptive_curve_irm(ix, &keys.market_id)
+    } else {
+        ix
+    }
+}
+
+/// Append an IRM program for the market's accrual to search for
+///
+/// Builders taking `MarketKeys` do this themselves; use it on those that
+/// only take a market id (`accrue_interest`, owner setters).
+pub fn append_irm(mut ix: Instruction, irm: Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(irm, false));
+    ix
+}
+
+/// Append an adaptive curve market's IRM state for its accrual to move
+///
+/// The `append_irm` of adaptive curve markets.
+pub fn append_adaptive_curve_irm(mut ix: Instruction, market_id: &[u8; 32]) -> Instruction {
+    ix.accounts.push(AccountMeta::new(derive_adaptive_curve_irm(&crate::ID, market_id).0, false));
+    ix
+}
+
+// ============================================================================
+// Admin
+// ============================================================================
+
+pub fn initialize(payer: Pubkey, owner: Pubkey, fee_recipient: Pubkey) -> Instruction {
+    build(
+        accts::Initialize {
+            payer,
+            protocol_state: protocol_state(),
+            system_program: system_program::ID,
+        },
+        ix::Initialize { owner, fee_recipient },
+    )
+}
+
+pub fn transfer_ownership(owner: Pubkey, new_owner: Pubkey) -> Instruction {
+    build(
+        accts::TransferOwnership { owner, protocol_state: protocol_state() },
+        ix::TransferOwnership { new_owner },
+    )
+}
+
+pub fn accept_ownership(pending_owner: Pubkey) -> Instruction {
+    build(
+        accts::AcceptOwnership { pending_owner, protocol_state: protocol_state() },
+        ix::AcceptOwnership {},
+    )
+}
+
+pub fn heartbeat(owner: Pubkey) -> Instruction {
+    build(
+        accts::Heartbeat { owner, protocol_state: protocol_state() },
+        ix::Heartbeat {},
+    )
+}
+
+pub fn set_recovery_key(owner: Pubkey, recovery_key: Pubkey, delay_days: u64) -> Instruction {
+    build(
+        accts::SetRecoveryKey { owner, protocol_state: protocol_state() },
+        ix::SetRecoveryKey { recovery_key, delay_days },
+    )
+}
+
+pub fn claim_ownership_recovery(recovery_key: Pubkey) -> Instruction {
+    build(
+        accts::ClaimOwnershipRecovery { recovery_key, protocol_state: protocol_state() },
+        ix::ClaimOwnershipRecovery {},
+    )
+}
+
+pub fn set_fee_recipient(owner: Pubkey, new_recipient: Pubkey) -> Instruction {
+    build(
+        accts::SetFeeRecipient { owner, protocol_state: protocol_state() },
+        ix::SetFeeRecipient { new_recipient },
+    )
+}
+
+pub fn set_protocol_paused(owner: Pubkey, paused: bool) -> Instruction {
+    build(
+        accts::SetProtocolPaused { owner, protocol_state: protocol_state() },
+        ix::SetProtocolPaused { paused },
+    )
+}
+
+pub fn set_flash_loans_enabled(owner: Pubkey, enabled: bool) -> Instruction {
+    build(
+        accts::SetFlashLoansEnabled { owner, protocol_state: protocol_state() },
+        ix::SetFlashLoansEnabled { enabled },
+    )
+}
+
+pub fn set_market_paused(owner: Pubkey, market_id: [u8; 32], paused: bool) -> Instruction {
+    build(
+        accts::SetMarketPaused {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetMarketPaused { market_id, paused },
+    )
+}
+
+pub fn enable_lltv(owner: Pubkey, lltv: u64) -> Instruction {
+    build(
+        accts::EnableLltv {
+            owner,
+            protocol_state: protocol_state(),
+            system_program: system_program::ID,
+        },
+        ix::EnableLltv { lltv },
+    )
+}
+
+pub fn set_lltv_bounds(owner: Pubkey, loan_mint: Pubkey, min_lltv: u64, max_lltv: u64) -> Instruction {
+    build(
+        accts::SetLltvBounds {
+            owner,
+            protocol_state: protocol_state(),
+            lltv_bounds: derive_lltv_bounds(&crate::ID, &loan_mint).0,
+            system_program: system_program::ID,
+        },
+        ix::SetLltvBounds { loan_mint, min_lltv, max_lltv },
+    )
+}
+
+pub fn clear_lltv_bounds(owner: Pubkey, loan_mint: Pubkey) -> Instruction {
+    build(
+        accts::ClearLltvBounds {
+            owner,
+            protocol_state: protocol_state(),
+            lltv_bounds: derive_lltv_bounds(&crate::ID, &loan_mint).0,
+        },
+        ix::ClearLltvBounds { loan_mint },
+    )
+}
+
+pub fn set_token_badge(owner: Pubkey, mint: Pubkey, tier: crate::state::TokenTier) -> Instruction {
+    build(
+        accts::SetTokenBadge {
+            owner,
+            protocol_state: protocol_state(),
+            token_badge: derive_token_badge(&crate::ID, &mint).0,
+            system_program: system_program::ID,
+        },
+        ix::SetTokenBadge { mint, tier },
+    )
+}
+
+pub fn clear_token_badge(owner: Pubkey, mint: Pubkey) -> Instruction {
+    build(
+        accts::ClearTokenBadge {
+            owner,
+            protocol_state: protocol_state(),
+            token_badge: derive_token_badge(&crate::ID, &mint).0,
+        },
+        ix::ClearTokenBadge { mint },
+    )
+}
+
+pub fn enable_irm(owner: Pubkey, irm: Pubkey) -> Instruction {
+    build(
+        accts::EnableIrm {
+            owner,
+            protocol_state: protocol_state(),
+            system_program: system_program::ID,
+        },
+        ix::EnableIrm { irm },
+    )
+}
+
+pub fn enable_yield_adapter(owner: Pubkey, adapter: Pubkey) -> Instruction {
+    build(
+        accts::EnableYieldAdapter { owner, protocol_state: protocol_state() },
+        ix::EnableYieldAdapter { adapter },
+    )
+}
+
+/// `Pubkey::default()` clears the allowlisted oracle program
+pub fn set_oracle_program(owner: Pubkey, oracle_program: Pubkey) -> Instruction {
+    build(
+        accts::SetOracleProgram { owner, protocol_state: protocol_state() },
+        ix::SetOracleProgram { oracle_program },
+    )
+}
+
+/// Append `oracle_program` to a priced instruction (borrow, withdraw
+/// collateral, liquidate...) on a market whose oracle is priced by CPI
+///
+/// The runtime only invokes programs listed in the calling instruction.
+pub fn with_oracle_program(mut ix: Instruction, oracle_program: Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(oracle_program, false));
+    ix
+}
+
+pub fn set_fee(owner: Pubkey, market_id: [u8; 32], fee: u64) -> Instruction {
+    build(
+        accts::SetFee {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetFee { market_id, fee },
+    )
+}
+
+pub fn set_interest_pause(owner: Pubkey, market_id: [u8; 32], duration: i64) -> Instruction {
+    build(
+        accts::SetInterestPause {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetInterestPause { market_id, duration },
+    )
+}
+
+pub fn set_supply_holding_period(owner: Pubkey, market_id: [u8; 32], period: i64) -> Instruction {
+    build(
+        accts::SetSupplyHoldingPeriod {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetSupplyHoldingPeriod { market_id, period },
+    )
+}
+
+pub fn set_bad_debt_rebate(owner: Pubkey, market_id: [u8; 32], rebate_bps: u16) -> Instruction {
+    build(
+        accts::SetBadDebtRebate {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetBadDebtRebate { market_id, rebate_bps },
+    )
+}
+
+pub fn set_supply_only(owner: Pubkey, market_id: [u8; 32], supply_only: bool) -> Instruction {
+    build(
+        accts::SetSupplyOnly {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetSupplyOnly { market_id, supply_only },
+    )
+}
+
+pub fn set_max_accrual_growth(owner: Pubkey, market_id: [u8; 32], max_growth_bps: u64) -> Instruction {
+    build(
+        accts::SetMaxAccrualGrowth {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetMaxAccrualGrowth { market_id, max_growth_bps },
+    )
+}
+
+pub fn set_share_price_floor(owner: Pubkey, market_id: [u8; 32], floor: u128) -> Instruction {
+    build(
+        accts::SetSharePriceFloor {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetSharePriceFloor { market_id, floor },
+    )
+}
+
+pub fn set_withdraw_only(owner: Pubkey, market_id: [u8; 32], withdraw_only: bool) -> Instruction {
+    build(
+        accts::SetWithdrawOnly {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetWithdrawOnly { market_id, withdraw_only },
+    )
+}
+
+pub fn set_max_oracle_age(owner: Pubkey, market_id: [u8; 32], max_oracle_age_secs: u64) -> Instruction {
+    build(
+        accts::SetMaxOracleAge {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetMaxOracleAge { market_id, max_oracle_age_secs },
+    )
+}
+
+/// `0` restores DEFAULT_ORACLE_CONF_BPS
+pub fn set_oracle_conf_bound(owner: Pubkey, market_id: [u8; 32], max_oracle_conf_bps: u64) -> Instruction {
+    build(
+        accts::SetOracleConfBound {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetOracleConfBound { market_id, max_oracle_conf_bps },
+    )
+}
+
+pub fn set_switchboard_normalization(owner: Pubkey, market_id: [u8; 32], normalize: bool) -> Instruction {
+    build(
+        accts::SetSwitchboardNormalization {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetSwitchboardNormalization { market_id, normalize },
+    )
+}
+
+/// `Pubkey::default()` removes the fallback
+pub fn set_fallback_oracle(owner: Pubkey, market_id: [u8; 32], fallback_oracle: Pubkey) -> Instruction {
+    build(
+        accts::SetFallbackOracle {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetFallbackOracle { market_id, fallback_oracle },
+    )
+}
+
+pub fn set_borrow_lltv_buffer(owner: Pubkey, market_id: [u8; 32], buffer: u64) -> Instruction {
+    build(
+        accts::SetBorrowLltvBuffer {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetBorrowLltvBuffer { market_id, buffer },
+    )
+}
+
+pub fn set_protocol_borrow_lltv_buffer(owner: Pubkey, buffer: u64) -> Instruction {
+    build(
+        accts::SetProtocolBorrowLltvBuffer { owner, protocol_state: protocol_state() },
+        ix::SetProtocolBorrowLltvBuffer { buffer },
+    )
+}
+
+pub fn set_risk_oracle(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    risk_oracle: Pubkey,
+    buffer_bps: u64,
+) -> Instruction {
+    build(
+        accts::SetRiskOracle {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetRiskOracle { market_id, risk_oracle, buffer_bps },
+    )
+}
+
+pub fn force_unlock_flash_loan(owner: Pubkey, market_id: [u8; 32]) -> Instruction {
+    build(
+        accts::ForceUnlockFlashLoan {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            loan_vault: derive_loan_vault(&crate::ID, &market_id).0,
+        },
+        ix::ForceUnlockFlashLoan { market_id },
+    )
+}
+
+pub fn sweep_surplus(owner: Pubkey, market_id: [u8; 32]) -> Instruction {
+    build(
+        accts::SweepSurplus {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            loan_vault: derive_loan_vault(&crate::ID, &market_id).0,
+        },
+        ix::SweepSurplus { market_id },
+    )
+}
+
+/// Sweep `source` (usually the market PDA's ATA for `mint`) to `destination`
+pub fn rescue_tokens(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    source: Pubkey,
+    destination: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+) -> Instruction {
+    build(
+        accts::RescueTokens {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            source,
+            destination,
+            mint,
+            token_program,
+        },
+        ix::RescueTokens { market_id },
+    )
+}
+
+pub fn migrate_protocol_state(owner: Pubkey) -> Instruction {
+    build(
+        accts::MigrateProtocolState {
+            owner,
+            protocol_state: protocol_state(),
+            system_program: system_program::ID,
+        },
+        ix::MigrateProtocolState {},
+    )
+}
+
+/// Grow a market created under an older layout to the current one
+pub fn migrate_market(payer: Pubkey, market_id: [u8; 32]) -> Instruction {
+    build(
+        accts::MigrateMarket {
+            payer,
+            market: derive_market(&crate::ID, &market_id).0,
+            system_program: system_program::ID,
+        },
+        ix::MigrateMarket { market_id },
+    )
+}
+
+/// Grow a position created under an older layout to the current one
+pub fn migrate_position(payer: Pubkey, market_id: [u8; 32], owner: Pubkey) -> Instruction {
+    build(
+        accts::MigratePosition {
+            payer,
+            position: derive_position(&crate::ID, &market_id, &owner).0,
+            system_program: system_program::ID,
+        },
+        ix::MigratePosition { market_id, owner },
+    )
+}
+
+/// Batch admin actions; every market touched by a market action is appended writable
+pub fn admin_batch(owner: Pubkey, actions: Vec<AdminAction>) -> Instruction {
+    let mut market_ids: Vec<[u8; 32]> = Vec::new();
+    for action in &actions {
+        if let AdminAction::SetMarketPaused { market_id, .. } | AdminAction::SetFee { market_id, .. } = action {
+            if !market_ids.contains(market_id) {
+                market_ids.push(*market_id);
+            }
+        }
+    }
+
+    let mut ix = build(
+        accts::AdminBatch {
+            owner,
+            protocol_state: protocol_state(),
+            system_program: system_program::ID,
+        },
+        ix::AdminBatch { actions },
+    );
+    ix.accounts.extend(
+        market_ids
+            .iter()
+            .map(|id| AccountMeta::new(derive_market(&crate::ID, id).0, false)),
+    );
+    ix
+}
+
+// ============================================================================
+// Market / Position
+// ============================================================================
+
+/// `oracle_heartbeat`: slots the oracle may go without an update;
+/// `max_oracle_age_secs`: seconds since the feed's last update (0 = off)
+pub fn create_market(
+    creator: Pubkey,
+    keys: &MarketKeys,
+    oracle_heartbeat: u64,
+    max_oracle_age_secs: u64,
+) -> Instruction {
+    build(
+        accts::CreateMarket {
+            creator,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            collateral_mint: keys.collateral_mint,
+            loan_mint: keys.loan_mint,
+            collateral_vault: keys.collateral_vault(),
+            loan_vault: keys.loan_vault(),
+            oracle: keys.oracle,
+            irm: keys.irm,
+            lltv_bounds: derive_lltv_bounds(&crate::ID, &keys.loan_mint).0,
+            collateral_badge: derive_token_badge(&crate::ID, &keys.collateral_mint).0,
+            token_program: keys.token_program,
+            system_program: system_program::ID,
+            fallback_oracle: keys.fallback_oracle,
+        },
+        ix::CreateMarket {
+            collateral_mint_key: keys.collateral_mint,
+            loan_mint_key: keys.loan_mint,
+            oracle_key: keys.oracle,
+            irm_key: keys.irm,
+            lltv: keys.lltv,
+            oracle_heartbeat,
+            max_oracle_age_secs,
+        },
+    )
+}
+
+/// Create an adaptive curve market's IRM state; send it right after
+/// `create_market`, before anything accrues
+pub fn init_adaptive_curve_irm(payer: Pubkey, market_id: [u8; 32]) -> Instruction {
+    build(
+        accts::InitAdaptiveCurveIrm {
+            payer,
+            market: derive_market(&crate::ID, &market_id).0,
+            adaptive_curve_irm: derive_adaptive_curve_irm(&crate::ID, &market_id).0,
+            system_program: system_program::ID,
+        },
+        ix::InitAdaptiveCurveIrm { market_id },
+    )
+}
+
+/// Close an empty market, sending market and vault rent to `rent_receiver`
+pub fn close_empty_market(owner: Pubkey, rent_receiver: Pubkey, keys: &MarketKeys) -> Instruction {
+    build(
+        accts::CloseEmptyMarket {
+            owner,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            collateral_vault: keys.collateral_vault(),
+            loan_vault: keys.loan_vault(),
+            rent_receiver,
+            token_program: keys.token_program,
+        },
+        ix::CloseEmptyMarket { market_id: keys.market_id },
+    )
+}
+
+/// `creator` must be the market's creator (the protocol owner for markets
+/// predating the creator field)
+pub fn create_market_metadata(
+    creator: Pubkey,
+    market_id: [u8; 32],
+    name: String,
+    description_uri: String,
+    curator: Pubkey,
+) -> Instruction {
+    build(
+        accts::CreateMarketMetadata {
+            creator,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            metadata: derive_market_metadata(&crate::ID, &market_id).0,
+            system_program: system_program::ID,
+        },
+        ix::CreateMarketMetadata { market_id, name, description_uri, curator },
+    )
+}
+
+pub fn update_market_metadata(
+    creator: Pubkey,
+    market_id: [u8; 32],
+    name: String,
+    description_uri: String,
+    curator: Pubkey,
+) -> Instruction {
+    build(
+        accts::UpdateMarketMetadata {
+            creator,
+            metadata: derive_market_metadata(&crate::ID, &market_id).0,
+        },
+        ix::UpdateMarketMetadata { market_id, name, description_uri, curator },
+    )
+}
+
+pub fn create_position(payer: Pubkey, owner: Pubkey, keys: &MarketKeys) -> Instruction {
+    build(
+        accts::CreatePosition {
+            payer,
+            owner,
+            market: keys.market(),
+            position: keys.position(&owner),
+            system_program: system_program::ID,
+        },
+        ix::CreatePosition { market_id: keys.market_id },
+    )
+}
+
+pub fn close_position(owner: Pubkey, rent_receiver: Pubkey, keys: &MarketKeys) -> Instruction {
+    build(
+        accts::ClosePosition {
+            owner,
+            rent_receiver,
+            position: keys.position(&owner),
+        },
+        ix::ClosePosition { market_id: keys.market_id },
+    )
+}
+
+/// Fold `source_owner`'s position into `destination_owner`'s; `caller` must
+/// be (or be authorized by) both owners
+pub fn merge_positions(
+    caller: Pubkey,
+    source_owner: Pubkey,
+    destination_owner: Pubkey,
+    rent_receiver: Pubkey,
+    keys: &MarketKeys,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::MergePositions {
+            caller,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            source_position: keys.position(&source_owner),
+            destination_position: keys.position(&destination_owner),
+            source_authorization: authorization_for(&caller, &source_owner),
+            destination_authorization: authorization_for(&caller, &destination_owner),
+            oracle: Some(keys.oracle),
+            rent_receiver,
+        },
+        ix::MergePositions { market_id: keys.market_id },
+    ))
+}
+
+// ============================================================================
+// Supply / Withdraw
+// ============================================================================
+
+/// `exempt`: `on_behalf_of` holds a holding period exemption
+pub fn supply(
+    supplier: Pubkey,
+    on_behalf_of: Pubkey,
+    supplier_token_account: Pubkey,
+    keys: &MarketKeys,
+    assets: u128,
+    min_shares: u128,
+    exempt: bool,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::Supply {
+            supplier,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            position: keys.position(&on_behalf_of),
+            on_behalf_of,
+            supplier_token_account,
+            loan_vault: keys.loan_vault(),
+            loan_mint: keys.loan_mint,
+            token_program: keys.token_program,
+            holding_exemption: exempt
+                .then(|| derive_holding_period_exemption(&crate::ID, &on_behalf_of).0),
+        },
+        ix::Supply { market_id: keys.market_id, assets, min_shares },
+    ))
+}
+
+pub fn exempt_from_holding_period(owner: Pubkey, account: Pubkey) -> Instruction {
+    build(
+        accts::ExemptFromHoldingPeriod {
+            owner,
+            protocol_state: protocol_state(),
+            holding_exemption: derive_holding_period_exemption(&crate::ID, &account).0,
+            system_program: system_program::ID,
+        },
+        ix::ExemptFromHoldingPeriod { account },
+    )
+}
+
+pub fn revoke_holding_period_exemption(owner: Pubkey, account: Pubkey) -> Instruction {
+    build(
+        accts::RevokeHoldingPeriodExemption {
+            owner,
+            protocol_state: protocol_state(),
+            holding_exemption: derive_holding_period_exemption(&crate::ID, &account).0,
+        },
+        ix::RevokeHoldingPeriodExemption { account },
+    )
+}
+
+/// Seed a market from a treasury account the owner controls
+pub fn seed_market(
+    owner: Pubkey,
+    fee_recipient: Pubkey,
+    treasury_token_account: Pubkey,
+    keys: &MarketKeys,
+    assets: u128,
+    lock_days: u32,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::SeedMarket {
+            owner,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            protocol_position: keys.position(&fee_recipient),
+            treasury_token_account,
+            loan_vault: keys.loan_vault(),
+            loan_mint: keys.loan_mint,
+            token_program: keys.token_program,
+        },
+        ix::SeedMarket { market_id: keys.market_id, assets, lock_days },
+    ))
+}
+
+/// Escrow `shares` of `owner`'s supply for at least `duration` seconds
+pub fn lock_supply_shares(owner: Pubkey, keys: &MarketKeys, shares: u128, duration: i64) -> Instruction {
+    build(
+        accts::LockSupplyShares {
+            owner,
+            protocol_state: protocol_state(),
+            position: keys.position(&owner),
+        },
+        ix::LockSupplyShares { market_id: keys.market_id, shares, duration },
+    )
+}
+
+/// Release `owner`'s expired supply escrow
+pub fn unlock_supply_shares(owner: Pubkey, keys: &MarketKeys) -> Instruction {
+    build(
+        accts::UnlockSupplyShares { owner, position: keys.position(&owner) },
+        ix::UnlockSupplyShares { market_id: keys.market_id },
+    )
+}
+
+/// Withdraw supply from `owner`'s position to `receiver`'s loan ATA
+pub fn withdraw(
+    caller: Pubkey,
+    owner: Pubkey,
+    receiver: Pubkey,
+    keys: &MarketKeys,
+    assets: u128,
+    shares: u128,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::Withdraw {
+            caller,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            position: keys.position(&owner),
+            authorization: authorization_for(&caller, &owner),
+            loan_mint: keys.loan_mint,
+            receiver,
+            receiver_token_account: keys.loan_ata(&receiver),
+            loan_vault: keys.loan_vault(),
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        ix::Withdraw { market_id: keys.market_id, assets, shares },
+    ))
+}
+
+// ============================================================================
+// Collateral
+// ============================================================================
+
+pub fn supply_collateral(
+    depositor: Pubkey,
+    on_behalf_of: Pubkey,
+    depositor_token_account: Pubkey,
+    keys: &MarketKeys,
+    amount: u128,
+) -> Instruction {
+    build(
+        accts::SupplyCollateral {
+            depositor,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            position: keys.position(&on_behalf_of),
+            on_behalf_of,
+            depositor_token_account,
+            collateral_vault: keys.collateral_vault(),
+            collateral_mint: keys.collateral_mint,
+            token_program: keys.token_program,
+        },
+        ix::SupplyCollateral { market_id: keys.market_id, amount },
+    )
+}
+
+pub fn withdraw_collateral(
+    caller: Pubkey,
+    owner: Pubkey,
+    receiver_token_account: Pubkey,
+    keys: &MarketKeys,
+    amount: u128,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::WithdrawCollateral {
+            caller,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            position: keys.position(&owner),
+            authorization: authorization_for(&caller, &owner),
+            oracle: keys.oracle,
+            receiver_token_account,
+            collateral_vault: keys.collateral_vault(),
+            collateral_mint: keys.collateral_mint,
+            token_program: keys.token_program,
+            fallback_oracle: keys.fallback_oracle,
+        },
+        ix::WithdrawCollateral { market_id: keys.market_id, amount },
+    ))
+}
+
+// ============================================================================
+// Borrow / Repay
+// ============================================================================
+
+/// Borrow against `owner`'s position, paying out to `receiver`'s loan ATA
+pub fn borrow(
+    caller: Pubkey,
+    owner: Pubkey,
+    receiver: Pubkey,
+    keys: &MarketKeys,
+    assets: u128,
+    max_shares: u128,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::Borrow {
+            caller,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            position: keys.position(&owner),
+            authorization: authorization_for(&caller, &owner),
+            oracle: keys.oracle,
+            loan_mint: keys.loan_mint,
+            receiver,
+            receiver_token_account: keys.loan_ata(&receiver),
+            loan_vault: keys.loan_vault(),
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            risk_oracle: keys.risk_oracle,
+            fallback_oracle: keys.fallback_oracle,
+        },
+        ix::Borrow { market_id: keys.market_id, assets, max_shares },
+    ))
+}
+
+pub fn repay(
+    repayer: Pubkey,
+    on_behalf_of: Pubkey,
+    repayer_token_account: Pubkey,
+    keys: &MarketKeys,
+    assets: u128,
+    shares: u128,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::Repay {
+            repayer,
+            market: keys.market(),
+            position: keys.position(&on_behalf_of),
+            on_behalf_of,
+            repayer_token_account,
+            loan_vault: keys.loan_vault(),
+            loan_mint: keys.loan_mint,
+            token_program: keys.token_program,
+        },
+        ix::Repay { market_id: keys.market_id, assets, shares },
+    ))
+}
+
+/// Bundle repay/borrow/collateral legs on `owner`'s position
+///
+/// The oracle is left out when the adjustment can't raise the position's LTV,
+/// so deleveraging still builds and lands while the feed is down.
+pub fn adjust_position(
+    caller: Pubkey,
+    owner: Pubkey,
+    caller_collateral_account: Pubkey,
+    caller_loan_account: Pubkey,
+    keys: &MarketKeys,
+    adjustment: PositionAdjustment,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::AdjustPosition {
+            caller,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            position: keys.position(&owner),
+            authorization: authorization_for(&caller, &owner),
+            oracle: adjustment.may_need_oracle().then_some(keys.oracle),
+            caller_collateral_account,
+            caller_loan_account,
+            collateral_vault: keys.collateral_vault(),
+            loan_vault: keys.loan_vault(),
+            collateral_mint: keys.collateral_mint,
+            loan_mint: keys.loan_mint,
+            token_program: keys.token_program,
+            risk_oracle: if adjustment.borrow_assets > 0 { keys.risk_oracle } else { None },
+        },
+        ix::AdjustPosition {
+            market_id: keys.market_id,
+            adjustment,
+            expected_version: Some(PROGRAM_VERSION),
+        },
+    ))
+}
+
+/// Simulate with `simulateTransaction` and decode the return data with
+/// `decode_view` (a `ViewResponse::BundleProjection`)
+pub fn dry_run_bundle(owner: Pubkey, keys: &MarketKeys, actions: Vec<BundleAction>) -> Instruction {
+    with_irm(
+        keys,
+        build(
+            accts::DryRunBundle {
+                protocol_state: protocol_state(),
+                market: keys.market(),
+                position: keys.position(&owner),
+                oracle: Some(keys.oracle),
+            },
+            ix::DryRunBundle { market_id: keys.market_id, actions },
+        ),
+    )
+}
+
+/// Report `wallet`'s positions in each of `markets` (at most MAX_EXPOSURE_MARKETS)
+pub fn get_exposure_report(wallet: Pubkey, markets: &[MarketKeys]) -> Instruction {
+    let mut ix = build(accts::GetExposureReport { wallet }, ix::GetExposureReport {});
+    for keys in markets {
+        ix.accounts.extend([
+            AccountMeta::new_readonly(keys.market(), false),
+            AccountMeta::new_readonly(keys.position(&wallet), false),
+            AccountMeta::new_readonly(keys.oracle, false),
+        ]);
+    }
+    ix
+}
+
+// ============================================================================
+// Liquidation
+// ============================================================================
+
+/// Where part of a liquidation's seized collateral goes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LiquidationOperator {
+    /// Operator's token account for the market's collateral
+    pub collateral_account: Pubkey,
+    /// Share of the seized collateral it receives (basis points)
+    pub share_bps: u64,
+}
+
+pub fn liquidate(
+    liquidator: Pubkey,
+    borrower: Pubkey,
+    liquidator_loan_account: Pubkey,
+    liquidator_collateral_account: Pubkey,
+    keys: &MarketKeys,
+    seized_assets: u128,
+) -> Instruction {
+    liquidate_with_operator(
+        liquidator,
+        borrower,
+        liquidator_loan_account,
+        liquidator_collateral_account,
+        keys,
+        seized_assets,
+        None,
+    )
+}
+
+/// `liquidate`, splitting the seized collateral with `operator`
+pub fn liquidate_with_operator(
+    liquidator: Pubkey,
+    borrower: Pubkey,
+    liquidator_loan_account: Pubkey,
+    liquidator_collateral_account: Pubkey,
+    keys: &MarketKeys,
+    seized_assets: u128,
+    operator: Option<LiquidationOperator>,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::Liquidate {
+            liquidator,
+            market: keys.market(),
+            borrower_position: keys.position(&borrower),
+            borrower,
+            oracle: keys.oracle,
+            liquidator_loan_account,
+            liquidator_collateral_account,
+            loan_vault: keys.loan_vault(),
+            collateral_vault: keys.collateral_vault(),
+            loan_mint: keys.loan_mint,
+            collateral_mint: keys.collateral_mint,
+            token_program: keys.token_program,
+            liquidation_reference: derive_liquidation_reference(&crate::ID, &keys.market_id).0,
+            reference_oracle: keys.reference_oracle,
+            fallback_oracle: keys.fallback_oracle,
+            operator_collateral_account: operator.map(|o| o.collateral_account),
+        },
+        ix::Liquidate {
+            market_id: keys.market_id,
+            seized_assets,
+            operator_share_bps: operator.map_or(0, |o| o.share_bps),
+        },
+    ))
+}
+
+/// Permissionless: socialize the debt of `borrower`'s collateral-less position
+pub fn settle_bad_debt(caller: Pubkey, borrower: Pubkey, keys: &MarketKeys) -> Instruction {
+    with_irm(keys, build(
+        accts::SettleBadDebt {
+            caller,
+            market: keys.market(),
+            borrower_position: keys.position(&borrower),
+            borrower,
+        },
+        ix::SettleBadDebt { market_id: keys.market_id },
+    ))
+}
+
+// ============================================================================
+// Flash Loans
+// ============================================================================
+
+/// `allowlisted`: pass the borrower's allowlist entry (allowlist-mode markets)
+fn flash_loan_start_accounts(
+    borrower: Pubkey,
+    borrower_token_account: Pubkey,
+    keys: &MarketKeys,
+    allowlisted: bool,
+) -> accts::FlashLoanStart {
+    accts::FlashLoanStart {
+        borrower,
+        protocol_state: protocol_state(),
+        market: keys.market(),
+        borrower_token_account,
+        loan_vault: keys.loan_vault(),
+        loan_mint: keys.loan_mint,
+        token_program: keys.token_program,
+        allowlist_entry: allowlisted.then(|| {
+            derive_flash_loan_allowlist_entry(&crate::ID, &keys.market_id, &borrower).0
+        }),
+    }
+}
+
+pub fn flash_loan(
+    borrower: Pubkey,
+    borrower_token_account: Pubkey,
+    keys: &MarketKeys,
+    amount: u128,
+    allowlisted: bool,
+) -> Instruction {
+    with_irm(keys, build(
+        flash_loan_start_accounts(borrower, borrower_token_account, keys, allowlisted),
+        ix::FlashLoan { market_id: keys.market_id, amount, expected_version: Some(PROGRAM_VERSION) },
+    ))
+}
+
+pub fn flash_loan_start(
+    borrower: Pubkey,
+    borrower_token_account: Pubkey,
+    keys: &MarketKeys,
+    amount: u128,
+    allowlisted: bool,
+) -> Instruction {
+    build(
+        flash_loan_start_accounts(borrower, borrower_token_account, keys, allowlisted),
+        ix::FlashLoanStart { market_id: keys.market_id, amount, expected_version: Some(PROGRAM_VERSION) },
+    )
+}
+
+pub fn flash_loan_end(
+    borrower: Pubkey,
+    borrower_token_account: Pubkey,
+    keys: &MarketKeys,
+    borrowed_amount: u128,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::FlashLoanEnd {
+            borrower,
+            market: keys.market(),
+            borrower_token_account,
+            loan_vault: keys.loan_vault(),
+            loan_mint: keys.loan_mint,
+            token_program: keys.token_program,
+        },
+        ix::FlashLoanEnd {
+            market_id: keys.market_id,
+            borrowed_amount,
+            expected_version: Some(PROGRAM_VERSION),
+        },
+    ))
+}
+
+/// Append the (market, loan vault) pair of each of `markets`, as the
+/// multi-market flash loan instructions take them
+fn with_flash_loan_legs(mut ix: Instruction, markets: &[MarketKeys]) -> Instruction {
+    for keys in markets {
+        ix.accounts.push(AccountMeta::new(keys.market(), false));
+        ix.accounts.push(AccountMeta::new(keys.loan_vault(), false));
+    }
+    ix
+}
+
+/// Flash loan `amount` drawn from `markets` in order (all lending the same
+/// loan mint)
+pub fn flash_loan_multi(
+    borrower: Pubkey,
+    borrower_token_account: Pubkey,
+    markets: &[MarketKeys],
+    amount: u128,
+) -> Instruction {
+    let keys = &markets[0];
+    with_flash_loan_legs(
+        build(
+            accts::FlashLoanMulti {
+                borrower,
+                protocol_state: protocol_state(),
+                borrower_token_account,
+                loan_mint: keys.loan_mint,
+                token_program: keys.token_program,
+            },
+            ix::FlashLoanMulti { amount, expected_version: Some(PROGRAM_VERSION) },
+        ),
+        markets,
+    )
+}
+
+/// Repay a `flash_loan_multi` over the same `markets`
+///
+/// Their IRM accounts follow the legs, for the accruals.
+pub fn flash_loan_multi_end(
+    borrower: Pubkey,
+    borrower_token_account: Pubkey,
+    markets: &[MarketKeys],
+) -> Instruction {
+    let keys = &markets[0];
+    let ix = with_flash_loan_legs(
+        build(
+            accts::FlashLoanMultiEnd {
+                borrower,
+                borrower_token_account,
+                loan_mint: keys.loan_mint,
+                token_program: keys.token_program,
+            },
+            ix::FlashLoanMultiEnd { expected_version: Some(PROGRAM_VERSION) },
+        ),
+        markets,
+    );
+    markets.iter().fold(ix, |ix, keys| with_irm(keys, ix))
+}
+
+pub fn set_flash_loan_allowlist_mode(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    allowlist_only: bool,
+) -> Instruction {
+    build(
+        accts::SetFlashLoanAllowlistMode {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetFlashLoanAllowlistMode { market_id, allowlist_only },
+    )
+}
+
+pub fn allow_flash_loan_borrower(owner: Pubkey, market_id: [u8; 32], borrower: Pubkey) -> Instruction {
+    build(
+        accts::AllowFlashLoanBorrower {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            allowlist_entry: derive_flash_loan_allowlist_entry(&crate::ID, &market_id, &borrower).0,
+            system_program: system_program::ID,
+        },
+        ix::AllowFlashLoanBorrower { market_id, borrower },
+    )
+}
+
+pub fn disallow_flash_loan_borrower(owner: Pubkey, market_id: [u8; 32], borrower: Pubkey) -> Instruction {
+    build(
+        accts::DisallowFlashLoanBorrower {
+            owner,
+            protocol_state: protocol_state(),
+            allowlist_entry: derive_flash_loan_allowlist_entry(&crate::ID, &market_id, &borrower).0,
+        },
+        ix::DisallowFlashLoanBorrower { market_id, borrower },
+    )
+}
+
+// ============================================================================
+// Yield Routing
+// ============================================================================
+
+pub fn set_collateral_yield_adapter(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    adapter: Pubkey,
+    max_staked_bps: u64,
+) -> Instruction {
+    build(
+        accts::SetCollateralYieldAdapter {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetCollateralYieldAdapter { market_id, adapter, max_staked_bps },
+    )
+}
+
+/// Stake idle collateral; `adapter_accounts` are forwarded to the adapter program
+pub fn stake_collateral(
+    owner: Pubkey,
+    keys: &MarketKeys,
+    adapter_program: Pubkey,
+    adapter_accounts: &[AccountMeta],
+    amount: u128,
+) -> Instruction {
+    let mut ix = build(
+        accts::StakeCollateral {
+            owner,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            collateral_vault: keys.collateral_vault(),
+            collateral_mint: keys.collateral_mint,
+            adapter_program,
+            token_program: keys.token_program,
+        },
+        ix::StakeCollateral { market_id: keys.market_id, amount },
+    );
+    ix.accounts.extend_from_slice(adapter_accounts);
+    ix
+}
+
+/// Recall staked collateral; `adapter_accounts` are forwarded to the adapter program
+pub fn unstake_collateral(
+    caller: Pubkey,
+    keys: &MarketKeys,
+    adapter_program: Pubkey,
+    adapter_accounts: &[AccountMeta],
+    amount: u128,
+) -> Instruction {
+    let mut ix = build(
+        accts::UnstakeCollateral {
+            caller,
+            market: keys.market(),
+            collateral_vault: keys.collateral_vault(),
+            collateral_mint: keys.collateral_mint,
+            adapter_program,
+            token_program: keys.token_program,
+        },
+        ix::UnstakeCollateral { market_id: keys.market_id, amount },
+    );
+    ix.accounts.extend_from_slice(adapter_accounts);
+    ix
+}
+
+pub fn set_loan_yield_adapter(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    adapter: Pubkey,
+    max_deployed_bps: u64,
+) -> Instruction {
+    build(
+        accts::SetLoanYieldAdapter {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetLoanYieldAdapter { market_id, adapter, max_deployed_bps },
+    )
+}
+
+/// Deploy idle loan liquidity; `adapter_accounts` are forwarded to the adapter program
+pub fn deploy_loan_liquidity(
+    owner: Pubkey,
+    keys: &MarketKeys,
+    adapter_program: Pubkey,
+    adapter_accounts: &[AccountMeta],
+    amount: u128,
+) -> Instruction {
+    let mut ix = build(
+        accts::DeployLoanLiquidity {
+            owner,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            loan_vault: keys.loan_vault(),
+            loan_mint: keys.loan_mint,
+            adapter_program,
+            token_program: keys.token_program,
+        },
+        ix::DeployLoanLiquidity { market_id: keys.market_id, amount },
+    );
+    ix.accounts.extend_from_slice(adapter_accounts);
+    ix
+}
+
+/// Recall deployed loan liquidity
+pub fn recall_loan_liquidity(
+    caller: Pubkey,
+    keys: &MarketKeys,
+    adapter_program: Pubkey,
+    adapter_accounts: &[AccountMeta],
+    amount: u128,
+) -> Instruction {
+    let mut ix = build(
+        accts::RecallLoanLiquidity {
+            caller,
+            market: keys.market(),
+            loan_vault: keys.loan_vault(),
+            loan_mint: keys.loan_mint,
+            token_program: keys.token_program,
+        },
+        ix::RecallLoanLiquidity { market_id: keys.market_id, amount },
+    );
+    ix.accounts.extend(adapter_remaining_accounts(adapter_program, adapter_accounts));
+    with_irm(keys, ix)
+}
+
+/// Remaining accounts for withdraw / borrow / recall on a market with deployed
+/// loan liquidity: append these so the program can recall a vault shortfall
+pub fn adapter_remaining_accounts(
+    adapter_program: Pubkey,
+    adapter_accounts: &[AccountMeta],
+) -> Vec<AccountMeta> {
+    let mut metas = vec![AccountMeta::new_readonly(adapter_program, false)];
+    metas.extend_from_slice(adapter_accounts);
+    metas
+}
+
+// ============================================================================
+// Governance
+// ============================================================================
+
+pub fn set_market_curator(owner: Pubkey, market_id: [u8; 32], curator: Pubkey) -> Instruction {
+    build(
+        accts::SetMarketCurator {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetMarketCurator { market_id, curator },
+    )
+}
+
+pub fn propose_market_config(
+    curator: Pubkey,
+    market_id: [u8; 32],
+    config: MarketConfigUpdate,
+) -> Instruction {
+    build(
+        accts::ProposeMarketConfig {
+            curator,
+            market: derive_market(&crate::ID, &market_id).0,
+            proposal: derive_config_proposal(&crate::ID, &market_id).0,
+            system_program: system_program::ID,
+        },
+        ix::ProposeMarketConfig { market_id, config },
+    )
+}
+
+pub fn approve_proposal(owner: Pubkey, market_id: [u8; 32], proposer: Pubkey) -> Instruction {
+    build(
+        accts::ApproveProposal {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            proposal: derive_config_proposal(&crate::ID, &market_id).0,
+            proposer,
+        },
+        ix::ApproveProposal { market_id },
+    )
+}
+
+/// Cancel as the proposer or reject as the owner
+pub fn cancel_proposal(authority: Pubkey, market_id: [u8; 32], proposer: Pubkey) -> Instruction {
+    build(
+        accts::CancelProposal {
+            authority,
+            protocol_state: protocol_state(),
+            proposal: derive_config_proposal(&crate::ID, &market_id).0,
+            proposer,
+        },
+        ix::CancelProposal { market_id },
+    )
+}
+
+pub fn propose_oracle_update(owner: Pubkey, market_id: [u8; 32], new_oracle: Pubkey) -> Instruction {
+    build(
+        accts::ProposeOracleUpdate {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            proposal: derive_oracle_update_proposal(&crate::ID, &market_id).0,
+            system_program: system_program::ID,
+        },
+        ix::ProposeOracleUpdate { market_id, new_oracle },
+    )
+}
+
+/// Execute the pending migration; `new_oracle` must be the proposed feed
+///
+/// The market keeps its id: build later instructions from
+/// `keys.after_oracle_update(new_oracle)`, not `MarketKeys::new`.
+pub fn execute_oracle_update(owner: Pubkey, market_id: [u8; 32], new_oracle: Pubkey) -> Instruction {
+    build(
+        accts::ExecuteOracleUpdate {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            proposal: derive_oracle_update_proposal(&crate::ID, &market_id).0,
+            new_oracle,
+        },
+        ix::ExecuteOracleUpdate { market_id },
+    )
+}
+
+pub fn cancel_oracle_update(owner: Pubkey, market_id: [u8; 32]) -> Instruction {
+    build(
+        accts::CancelOracleUpdate {
+            owner,
+            protocol_state: protocol_state(),
+            proposal: derive_oracle_update_proposal(&crate::ID, &market_id).0,
+        },
+        ix::CancelOracleUpdate { market_id },
+    )
+}
+
+pub fn propose_irm_update(owner: Pubkey, market_id: [u8; 32], new_irm: Pubkey) -> Instruction {
+    build(
+        accts::ProposeIrmUpdate {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            proposal: derive_irm_update_proposal(&crate::ID, &market_id).0,
+            system_program: system_program::ID,
+        },
+        ix::ProposeIrmUpdate { market_id, new_irm },
+    )
+}
+
+/// Execute the pending migration; `new_irm` must be the proposed IRM
+///
+/// `keys` still name the old IRM, which accrues the market up to the switch.
+/// The market keeps its id: build later instructions from
+/// `keys.after_irm_update(..)`, not `MarketKeys::new`.
+pub fn set_market_irm(owner: Pubkey, keys: &MarketKeys, new_irm: Pubkey) -> Instruction {
+    let ix = with_irm(
+        keys,
+        build(
+            accts::SetMarketIrm {
+                owner,
+                protocol_state: protocol_state(),
+                market: keys.market(),
+                proposal: derive_irm_update_proposal(&crate::ID, &keys.market_id).0,
+                new_irm,
+            },
+            ix::SetMarketIrm { market_id: keys.market_id },
+        ),
+    );
+    if new_irm == derive_adaptive_curve_model(&crate::ID).0 {
+        append_adaptive_curve_irm(ix, &keys.market_id)
+    } else {
+        ix
+    }
+}
+
+pub fn cancel_irm_update(owner: Pubkey, market_id: [u8; 32]) -> Instruction {
+    build(
+        accts::CancelIrmUpdate {
+            owner,
+            protocol_state: protocol_state(),
+            proposal: derive_irm_update_proposal(&crate::ID, &market_id).0,
+        },
+        ix::CancelIrmUpdate { market_id },
+    )
+}
+
+// ============================================================================
+// Credit Lines
+// ============================================================================
+
+pub fn set_credit_line(
+    supplier: Pubkey,
+    borrower: Pubkey,
+    keys: &MarketKeys,
+    limit: u128,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::SetCreditLine {
+            supplier,
+            market: keys.market(),
+            supplier_position: keys.position(&supplier),
+            credit_line: keys.credit_line(&supplier, &borrower),
+            system_program: system_program::ID,
+        },
+        ix::SetCreditLine { market_id: keys.market_id, borrower, limit },
+    ))
+}
+
+pub fn draw_credit(
+    borrower: Pubkey,
+    supplier: Pubkey,
+    receiver_token_account: Pubkey,
+    keys: &MarketKeys,
+    assets: u128,
+    max_shares: u128,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::DrawCredit {
+            borrower,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            credit_line: keys.credit_line(&supplier, &borrower),
+            supplier_position: keys.position(&supplier),
+            loan_mint: keys.loan_mint,
+            receiver_token_account,
+            loan_vault: keys.loan_vault(),
+            token_program: keys.token_program,
+            risk_oracle: keys.risk_oracle,
+        },
+        ix::DrawCredit { market_id: keys.market_id, assets, max_shares },
+    ))
+}
+
+pub fn repay_credit(
+    repayer: Pubkey,
+    supplier: Pubkey,
+    borrower: Pubkey,
+    repayer_token_account: Pubkey,
+    keys: &MarketKeys,
+    assets: u128,
+    shares: u128,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::RepayCredit {
+            repayer,
+            market: keys.market(),
+            credit_line: keys.credit_line(&supplier, &borrower),
+            repayer_token_account,
+            loan_vault: keys.loan_vault(),
+            loan_mint: keys.loan_mint,
+            token_program: keys.token_program,
+        },
+        ix::RepayCredit { market_id: keys.market_id, assets, shares },
+    ))
+}
+
+pub fn close_credit_line(supplier: Pubkey, borrower: Pubkey, keys: &MarketKeys) -> Instruction {
+    with_irm(keys, build(
+        accts::CloseCreditLine {
+            supplier,
+            market: keys.market(),
+            supplier_position: keys.position(&supplier),
+            credit_line: keys.credit_line(&supplier, &borrower),
+        },
+        ix::CloseCreditLine { market_id: keys.market_id },
+    ))
+}
+
+// ============================================================================
+// Oracle Divergence Guard
+// ============================================================================
+
+pub fn set_divergence_guard(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    reference_feeds: [Pubkey; 2],
+    max_divergence_bps: u64,
+) -> Instruction {
+    build(
+        accts::SetDivergenceGuard {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetDivergenceGuard { market_id, reference_feeds, max_divergence_bps },
+    )
+}
+
+/// Permissionless: freezes the market if `reference_feeds` diverge
+pub fn flag_oracle_divergence(
+    keeper: Pubkey,
+    market_id: [u8; 32],
+    reference_feeds: [Pubkey; 2],
+) -> Instruction {
+    build(
+        accts::FlagOracleDivergence {
+            keeper,
+            market: derive_market(&crate::ID, &market_id).0,
+            feed_a: reference_feeds[0],
+            feed_b: reference_feeds[1],
+        },
+        ix::FlagOracleDivergence { market_id },
+    )
+}
+
+pub fn clear_oracle_divergence(owner: Pubkey, market_id: [u8; 32]) -> Instruction {
+    build(
+        accts::ClearOracleDivergence {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::ClearOracleDivergence { market_id },
+    )
+}
+
+/// Check every liquidation of the market against `reference_oracle`
+pub fn set_liquidation_reference(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    reference_oracle: Pubkey,
+    max_divergence_bps: u64,
+) -> Instruction {
+    build(
+        accts::SetLiquidationReference {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            reference_oracle,
+            liquidation_reference: derive_liquidation_reference(&crate::ID, &market_id).0,
+            system_program: system_program::ID,
+        },
+        ix::SetLiquidationReference { market_id, max_divergence_bps },
+    )
+}
+
+pub fn clear_liquidation_reference(owner: Pubkey, market_id: [u8; 32]) -> Instruction {
+    build(
+        accts::ClearLiquidationReference {
+            owner,
+            protocol_state: protocol_state(),
+            liquidation_reference: derive_liquidation_reference(&crate::ID, &market_id).0,
+        },
+        ix::ClearLiquidationReference { market_id },
+    )
+}
+
+pub fn set_reputation_boost(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    boost_bps: u64,
+    min_repaid: u128,
+    min_age: i64,
+) -> Instruction {
+    build(
+        accts::SetReputationBoost {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetReputationBoost { market_id, boost_bps, min_repaid, min_age },
+    )
+}
+
+// ============================================================================
+// Liquidation Insurance
+// ============================================================================
+
+pub fn set_insurance_params(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    premium_bps: u64,
+    coverage_bps: u64,
+) -> Instruction {
+    build(
+        accts::SetInsuranceParams {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetInsuranceParams { market_id, premium_bps, coverage_bps },
+    )
+}
+
+pub fn set_position_insurance(owner: Pubkey, keys: &MarketKeys, insured: bool) -> Instruction {
+    build(
+        accts::SetPositionInsurance {
+            owner,
+            market: keys.market(),
+            position: keys.position(&owner),
+        },
+        ix::SetPositionInsurance { market_id: keys.market_id, insured },
+    )
+}
+
+pub fn charge_insurance_premium(owner: Pubkey, keys: &MarketKeys) -> Instruction {
+    build(
+        accts::ChargeInsurancePremium {
+            market: keys.market(),
+            position: keys.position(&owner),
+        },
+        ix::ChargeInsurancePremium { market_id: keys.market_id },
+    )
+}
+
+pub fn claim_insurance(owner: Pubkey, receiver_token_account: Pubkey, keys: &MarketKeys) -> Instruction {
+    build(
+        accts::ClaimInsurance {
+            owner,
+            market: keys.market(),
+            position: keys.position(&owner),
+            receiver_token_account,
+            collateral_vault: keys.collateral_vault(),
+            collateral_mint: keys.collateral_mint,
+            token_program: keys.token_program,
+        },
+        ix::ClaimInsurance { market_id: keys.market_id },
+    )
+}
+
+// ============================================================================
+// Stable-Rate Borrowing
+// ============================================================================
+
+pub fn set_stable_rate_params(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    enabled: bool,
+    spread_bps: u64,
+    rebalance_utilization_bps: u64,
+) -> Instruction {
+    build(
+        accts::SetStableRateParams {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SetStableRateParams { market_id, enabled, spread_bps, rebalance_utilization_bps },
+    )
+}
+
+pub fn borrow_stable(
+    caller: Pubkey,
+    owner: Pubkey,
+    receiver: Pubkey,
+    keys: &MarketKeys,
+    assets: u128,
+    max_rate: u128,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::BorrowStable {
+            caller,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            position: keys.position(&owner),
+            authorization: authorization_for(&caller, &owner),
+            oracle: keys.oracle,
+            loan_mint: keys.loan_mint,
+            receiver,
+            receiver_token_account: keys.loan_ata(&receiver),
+            loan_vault: keys.loan_vault(),
+            token_program: keys.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            risk_oracle: keys.risk_oracle,
+        },
+        ix::BorrowStable { market_id: keys.market_id, assets, max_rate },
+    ))
+}
+
+pub fn repay_stable(
+    repayer: Pubkey,
+    on_behalf_of: Pubkey,
+    repayer_token_account: Pubkey,
+    keys: &MarketKeys,
+    assets: u128,
+) -> Instruction {
+    with_irm(keys, build(
+        accts::RepayStable {
+            repayer,
+            market: keys.market(),
+            position: keys.position(&on_behalf_of),
+            on_behalf_of,
+            repayer_token_account,
+            loan_vault: keys.loan_vault(),
+            loan_mint: keys.loan_mint,
+            token_program: keys.token_program,
+        },
+        ix::RepayStable { market_id: keys.market_id, assets },
+    ))
+}
+
+pub fn rebalance_stable_rate(owner: Pubkey, keys: &MarketKeys) -> Instruction {
+    with_irm(keys, build(
+        accts::RebalanceStableRate {
+            market: keys.market(),
+            position: keys.position(&owner),
+        },
+        ix::RebalanceStableRate { market_id: keys.market_id },
+    ))
+}
+
+// ============================================================================
+// TWAP Oracles
+// ============================================================================
+
+pub fn create_twap_oracle(
+    payer: Pubkey,
+    source: Pubkey,
+    window: i64,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+) -> Instruction {
+    build(
+        accts::CreateTwapOracle {
+            payer,
+            source,
+            twap_oracle: derive_twap_oracle(&crate::ID, &source, window, collateral_decimals, loan_decimals).0,
+            system_program: system_program::ID,
+        },
+        ix::CreateTwapOracle { window, collateral_decimals, loan_decimals },
+    )
+}
+
+/// Sample `source` into the TWAP oracle at `twap_oracle`
+pub fn update_twap(twap_oracle: Pubkey, source: Pubkey) -> Instruction {
+    build(
+        accts::UpdateTwap { twap_oracle, source },
+        ix::UpdateTwap {},
+    )
+}
+
+// ============================================================================
+// CLMM TWAP Oracles
+// ============================================================================
+
+pub fn create_clmm_twap_oracle(
+    payer: Pubkey,
+    observation_state: Pubkey,
+    min_window: i64,
+    collateral_is_token0: bool,
+) -> Instruction {
+    build(
+        accts::CreateClmmTwapOracle {
+            payer,
+            observation_state,
+            clmm_twap_oracle: derive_clmm_twap_oracle(
+                &crate::ID,
+                &observation_state,
+                min_window,
+                collateral_is_token0,
+            ).0,
+            system_program: system_program::ID,
+        },
+        ix::CreateClmmTwapOracle { min_window, collateral_is_token0 },
+    )
+}
+
+/// Recompute the CLMM TWAP oracle at `clmm_twap_oracle` from `observation_state`
+pub fn update_clmm_twap(clmm_twap_oracle: Pubkey, observation_state: Pubkey) -> Instruction {
+    build(
+        accts::UpdateClmmTwap { clmm_twap_oracle, observation_state },
+        ix::UpdateClmmTwap {},
+    )
+}
+
+// ============================================================================
+// EWMA Oracles
+// ============================================================================
+
+/// Set the market's smoothing factor; `source` is the market's oracle when
+/// creating the EWMA, its recorded source after
+pub fn set_price_smoothing(owner: Pubkey, market_id: [u8; 32], source: Pubkey, smoothing_bps: u64) -> Instruction {
+    build(
+        accts::SetPriceSmoothing {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            source,
+            ewma_oracle: derive_ewma_oracle(&crate::ID, &market_id).0,
+            system_program: system_program::ID,
+        },
+        ix::SetPriceSmoothing { market_id, smoothing_bps },
+    )
+}
+
+/// Blend `source` into the market's EWMA oracle
+pub fn update_ewma(market_id: [u8; 32], source: Pubkey) -> Instruction {
+    build(
+        accts::UpdateEwma {
+            ewma_oracle: derive_ewma_oracle(&crate::ID, &market_id).0,
+            source,
+        },
+        ix::UpdateEwma {},
+    )
+}
+
+// ============================================================================
+// Composite Oracles
+// ============================================================================
+
+pub fn create_composite_oracle(
+    payer: Pubkey,
+    base_feed: Pubkey,
+    quote_feed: Pubkey,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+) -> Instruction {
+    build(
+        accts::CreateCompositeOracle {
+            payer,
+            base_feed,
+            quote_feed,
+            composite_oracle: derive_composite_oracle(
+                &crate::ID,
+                &base_feed,
+                &quote_feed,
+                collateral_decimals,
+                loan_decimals,
+            ).0,
+            system_program: system_program::ID,
+        },
+        ix::CreateCompositeOracle { collateral_decimals, loan_decimals },
+    )
+}
+
+/// Read both legs into the composite oracle at `composite_oracle`
+pub fn update_composite(composite_oracle: Pubkey, base_feed: Pubkey, quote_feed: Pubkey) -> Instruction {
+    build(
+        accts::UpdateComposite { composite_oracle, base_feed, quote_feed },
+        ix::UpdateComposite {},
+    )
+}
+
+// ============================================================================
+// Price Relay
+// ============================================================================
+
+/// Name `relayer` as the market's price relayer (default key disables it)
+pub fn set_price_relayer(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    relayer: Pubkey,
+    max_deviation_bps: u64,
+) -> Instruction {
+    build(
+        accts::SetPriceRelayer {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            price_relay: derive_price_relay(&crate::ID, &market_id).0,
+            system_program: system_program::ID,
+        },
+        ix::SetPriceRelayer { market_id, relayer, max_deviation_bps },
+    )
+}
+
+/// Push `price` into the market's relay, checked against `oracle`
+pub fn push_relayed_price(relayer: Pubkey, keys: &MarketKeys, price: u128) -> Instruction {
+    build(
+        accts::PushRelayedPrice {
+            relayer,
+            market: keys.market(),
+            price_relay: derive_price_relay(&crate::ID, &keys.market_id).0,
+            oracle: keys.oracle,
+        },
+        ix::PushRelayedPrice { market_id: keys.market_id, price },
+    )
+}
+
+// ============================================================================
+// Devnet Drills
+// ============================================================================
+
+#[cfg(feature = "devnet")]
+pub fn simulate_oracle_failure(
+    owner: Pubkey,
+    market_id: [u8; 32],
+    mode: crate::interfaces::OracleFailureMode,
+) -> Instruction {
+    build(
+        accts::SimulateOracleFailure {
+            owner,
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+        },
+        ix::SimulateOracleFailure { market_id, mode },
+    )
+}
+
+#[cfg(feature = "devnet")]
+pub fn set_clock_override(owner: Pubkey, unix_timestamp: i64) -> Instruction {
+    build(
+        accts::SetClockOverride {
+            owner,
+            protocol_state: protocol_state(),
+            clock_override: derive_clock_override(&crate::ID).0,
+            system_program: system_program::ID,
+        },
+        ix::SetClockOverride { unix_timestamp },
+    )
+}
+
+#[cfg(feature = "devnet")]
+pub fn clear_clock_override(owner: Pubkey) -> Instruction {
+    build(
+        accts::ClearClockOverride {
+            owner,
+            protocol_state: protocol_state(),
+            clock_override: derive_clock_override(&crate::ID).0,
+        },
+        ix::ClearClockOverride {},
+    )
+}
+
+/// Run `ix` at the override's timestamp; `authority` is the owner that
+/// set it and must sign
+#[cfg(feature = "devnet")]
+pub fn with_clock_override(mut ix: Instruction, authority: Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(derive_clock_override(&crate::ID).0, false));
+    ix.accounts.push(AccountMeta::new_readonly(authority, true));
+    ix
+}
+
+// ============================================================================
+// Multi-Collateral Markets (experimental)
+// ============================================================================
+
+/// Static keys of a multi-collateral market
+///
+/// Health-checked builders append `oracles` (every collateral slot's feed,
+/// in slot order) as remaining accounts.
+#[cfg(feature = "multi-collateral")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct McMarketKeys {
+    pub market_id: [u8; 32],
+    pub loan_mint: Pubkey,
+    /// Collateral mint per slot
+    pub collateral_mints: Vec<Pubkey>,
+    /// Collateral oracle per slot
+    pub oracles: Vec<Pubkey>,
+    /// Token program owning the loan and collateral mints
+    pub token_program: Pubkey,
+}
+
+#[cfg(feature = "multi-collateral")]
+impl McMarketKeys {
+    /// Build keys from a fetched McMarket account
+    pub fn from_market(market: &McMarket, token_program: Pubkey) -> Self {
+        let slots = &market.collaterals[..market.collateral_count as usize];
+        Self {
+            market_id: market.market_id,
+            loan_mint: market.loan_mint,
+            collateral_mints: slots.iter().map(|c| c.mint).collect(),
+            oracles: slots.iter().map(|c| c.oracle).collect(),
+            token_program,
+        }
+    }
+
+    pub fn market(&self) -> Pubkey {
+        derive_mc_market(&crate::ID, &self.market_id).0
+    }
+
+    pub fn loan_vault(&self) -> Pubkey {
+        derive_mc_loan_vault(&crate::ID, &self.market_id).0
+    }
+
+    pub fn collateral_vault(&self, index: u8) -> Pubkey {
+        derive_mc_collateral_vault(&crate::ID, &self.market_id, index).0
+    }
+
+    pub fn position(&self, owner: &Pubkey) -> Pubkey {
+        derive_mc_position(&crate::ID, &self.market_id, owner).0
+    }
+
+    fn with_oracles(&self, mut ix: Instruction) -> Instruction {
+        ix.accounts
+            .extend(self.oracles.iter().map(|oracle| AccountMeta::new_readonly(*oracle, false)));
+        ix
+    }
+
+    fn loan_transfer(&self, owner: Pubkey, owner_token_account: Pubkey) -> accts::McLoanTransfer {
+        accts::McLoanTransfer {
+            owner,
+            protocol_state: protocol_state(),
+            market: self.market(),
+            position: self.position(&owner),
+            owner_token_account,
+            loan_vault: self.loan_vault(),
+            loan_mint: self.loan_mint,
+            token_program: self.token_program,
+        }
+    }
+
+    fn collateral_transfer(&self, owner: Pubkey, index: u8, owner_token_account: Pubkey) -> accts::McCollateralTransfer {
+        accts::McCollateralTransfer {
+            owner,
+            protocol_state: protocol_state(),
+            market: self.market(),
+            position: self.position(&owner),
+            collateral_mint: self.collateral_mints[index as usize],
+            owner_token_account,
+            collateral_vault: self.collateral_vault(index),
+            token_program: self.token_program,
+        }
+    }
+}
+
+#[cfg(feature = "multi-collateral")]
+pub fn create_mc_market(
+    creator: Pubkey,
+    loan_mint: Pubkey,
+    irm: Pubkey,
+    nonce: u64,
+    token_program: Pubkey,
+) -> Instruction {
+    let market_id = calculate_mc_market_id(&creator, &loan_mint, &irm, nonce);
+    build(
+        accts::CreateMcMarket {
+            creator,
+            protocol_state: protocol_state(),
+            market: derive_mc_market(&crate::ID, &market_id).0,
+            loan_mint,
+            loan_vault: derive_mc_loan_vault(&crate::ID, &market_id).0,
+            token_program,
+            system_program: system_program::ID,
+        },
+        ix::CreateMcMarket { loan_mint_key: loan_mint, irm_key: irm, nonce },
+    )
+}
+
+/// `index` must be the market's next free slot (its current collateral count)
+#[cfg(feature = "multi-collateral")]
+pub fn add_mc_collateral(
+    creator: Pubkey,
+    keys: &McMarketKeys,
+    index: u8,
+    collateral_mint: Pubkey,
+    oracle: Pubkey,
+    lltv: u64,
+) -> Instruction {
+    build(
+        accts::AddMcCollateral {
+            creator,
+            protocol_state: protocol_state(),
+            market: keys.market(),
+            collateral_mint,
+            collateral_vault: keys.collateral_vault(index),
+            oracle,
+            token_program: keys.token_program,
+            system_program: system_program::ID,
+        },
+        ix::AddMcCollateral { market_id: keys.market_id, lltv },
+    )
+}
+
+#[cfg(feature = "multi-collateral")]
+pub fn create_mc_position(owner: Pubkey, keys: &McMarketKeys) -> Instruction {
+    build(
+        accts::CreateMcPosition {
+            owner,
+            market: keys.market(),
+            position: keys.position(&owner),
+            system_program: system_program::ID,
+        },
+        ix::CreateMcPosition { market_id: keys.market_id },
+    )
+}
+
+#[cfg(feature = "multi-collateral")]
+pub fn mc_supply(owner: Pubkey, keys: &McMarketKeys, owner_token_account: Pubkey, assets: u128) -> Instruction {
+    build(
+        keys.loan_transfer(owner, owner_token_account),
+        ix::McSupply { market_id: keys.market_id, assets },
+    )
+}
+
+#[cfg(feature = "multi-collateral")]
+pub fn mc_withdraw(owner: Pubkey, keys: &McMarketKeys, owner_token_account: Pubkey, shares: u128) -> Instruction {
+    build(
+        keys.loan_transfer(owner, owner_token_account),
+        ix::McWithdraw { market_id: keys.market_id, shares },
+    )
+}
+
+#[cfg(feature = "multi-collateral")]
+pub fn mc_borrow(owner: Pubkey, keys: &McMarketKeys, owner_token_account: Pubkey, assets: u128) -> Instruction {
+    keys.with_oracles(build(
+        keys.loan_transfer(owner, owner_token_account),
+        ix::McBorrow { market_id: keys.market_id, assets },
+    ))
+}
+
+#[cfg(feature = "multi-collateral")]
+pub fn mc_repay(owner: Pubkey, keys: &McMarketKeys, owner_token_account: Pubkey, shares: u128) -> Instruction {
+    build(
+        keys.loan_transfer(owner, owner_token_account),
+        ix::McRepay { market_id: keys.market_id, shares },
+    )
+}
+
+#[cfg(feature = "multi-collateral")]
+pub fn mc_supply_collateral(
+    owner: Pubkey,
+    keys: &McMarketKeys,
+    index: u8,
+    owner_token_account: Pubkey,
+    amount: u128,
+) -> Instruction {
+    build(
+        keys.collateral_transfer(owner, index, owner_token_account),
+        ix::McSupplyCollateral { market_id: keys.market_id, index, amount },
+    )
+}
+
+#[cfg(feature = "multi-collateral")]
+pub fn mc_withdraw_collateral(
+    owner: Pubkey,
+    keys: &McMarketKeys,
+    index: u8,
+    owner_token_account: Pubkey,
+    amount: u128,
+) -> Instruction {
+    keys.with_oracles(build(
+        keys.collateral_transfer(owner, index, owner_token_account),
+        ix::McWithdrawCollateral { market_id: keys.market_id, index, amount },
+    ))
+}
+
+/// Repay up to `assets` of `borrower`'s debt for collateral slot `index`
+#[cfg(feature = "multi-collateral")]
+pub fn mc_liquidate(
+    liquidator: Pubkey,
+    borrower: Pubkey,
+    liquidator_loan_account: Pubkey,
+    liquidator_collateral_account: Pubkey,
+    keys: &McMarketKeys,
+    index: u8,
+    assets: u128,
+) -> Instruction {
+    keys.with_oracles(build(
+        accts::McLiquidate {
+            liquidator,
+            market: keys.market(),
+            borrower_position: keys.position(&borrower),
+            borrower,
+            collateral_mint: keys.collateral_mints[index as usize],
+            loan_mint: keys.loan_mint,
+            liquidator_loan_account,
+            liquidator_collateral_account,
+            loan_vault: keys.loan_vault(),
+            collateral_vault: keys.collateral_vault(index),
+            token_program: keys.token_program,
+        },
+        ix::McLiquidate { market_id: keys.market_id, index, assets },
+    ))
+}
+
+// ============================================================================
+// Utility
+// ============================================================================
+
+/// `fee_recipient`: also claim pending fees into its position
+pub fn accrue_interest(market_id: [u8; 32], fee_recipient: Option<Pubkey>) -> Instruction {
+    build(
+        accts::AccrueInterest {
+            market: derive_market(&crate::ID, &market_id).0,
+            protocol_state: fee_recipient.map(|_| protocol_state()),
+            fee_position: fee_recipient
+                .map(|recipient| derive_position(&crate::ID, &market_id, &recipient).0),
+        },
+        ix::AccrueInterest { market_id },
+    )
+}
+
+/// Rank `market_ids` (at most MAX_ACCRUAL_PRIORITY_MARKETS) by accrual urgency
+pub fn get_accrual_priorities(market_ids: &[[u8; 32]]) -> Instruction {
+    let mut ix = build(
+        accts::GetAccrualPriorities { protocol_state: protocol_state() },
+        ix::GetAccrualPriorities {},
+    );
+    ix.accounts.extend(
+        market_ids
+            .iter()
+            .map(|id| AccountMeta::new_readonly(derive_market(&crate::ID, id).0, false)),
+    );
+    ix
+}
+
+pub fn reconcile_collateral(keys: &MarketKeys) -> Instruction {
+    build(
+        accts::ReconcileCollateral {
+            market: keys.market(),
+            collateral_vault: keys.collateral_vault(),
+        },
+        ix::ReconcileCollateral { market_id: keys.market_id },
+    )
+}
+
+pub fn get_protocol_status() -> Instruction {
+    build(
+        accts::GetProtocolStatus { protocol_state: protocol_state() },
+        ix::GetProtocolStatus {},
+    )
+}
+
+pub fn set_authorization(
+    payer: Pubkey,
+    authorizer: Pubkey,
+    authorized: Pubkey,
+    is_authorized: bool,
+    expires_at: i64,
+) -> Instruction {
+    build(
+        accts::SetAuthorization {
+            payer,
+            authorizer,
+            authorized,
+            authorization: derive_authorization(&crate::ID, &authorizer, &authorized).0,
+            system_program: system_program::ID,
+        },
+        ix::SetAuthorization { is_authorized, expires_at },
+    )
+}
+
+pub fn revoke_authorization(authorizer: Pubkey, authorized: Pubkey) -> Instruction {
+    build(
+        accts::RevokeAuthorization {
+            authorizer,
+            authorization: derive_authorization(&crate::ID, &authorizer, &authorized).0,
+        },
+        ix::RevokeAuthorization {},
+    )
+}
+
+/// Claim pending fee shares into the fee recipient's position
+pub fn claim_fees(fee_recipient: Pubkey, market_id: [u8; 32]) -> Instruction {
+    build(
+        accts::ClaimFees {
+            protocol_state: protocol_state(),
+            market: derive_market(&crate::ID, &market_id).0,
+            fee_position: derive_position(&crate::ID, &market_id, &fee_recipient).0,
+        },
+        ix::ClaimFees { market_id },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    fn test_keys() -> MarketKeys {
+        MarketKeys::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            8500,
+            anchor_spl::token::ID,
+        )
+    }
+
+    #[test]
+    fn test_oracle_update_keeps_market_id() {
+        let keys = test_keys();
+        let new_oracle = Pubkey::new_unique();
+        let moved = keys.after_oracle_update(new_oracle);
+
+        assert_eq!(moved.oracle, new_oracle);
+        assert_eq!(moved.market_id, keys.market_id);
+        assert_eq!(moved.market(), keys.market());
+        // Recomputing from the current tuple names a different market
+        let recomputed = MarketKeys::new(
+            keys.collateral_mint,
+            keys.loan_mint,
+            new_oracle,
+            keys.irm,
+            keys.lltv,
+            keys.token_program,
+        );
+        assert_ne!(recomputed.market(), keys.market());
+    }
+
+    #[test]
+    fn test_irm_update_keeps_market_id() {
+        let keys = test_keys().with_irm_cpi();
+        let model = derive_adaptive_curve_model(&crate::ID).0;
+        let moved = keys.after_irm_update(model, false);
+
+        assert_eq!(moved.irm, model);
+        assert!(moved.irm_adaptive);
+        assert!(!moved.irm_cpi);
+        assert_eq!(moved.market_id, keys.market_id);
+        assert_eq!(moved.market(), keys.market());
+    }
+
+    #[test]
+    fn test_borrow_resolves_pdas() {
+        let keys = test_keys();
+        let owner = Pubkey::new_unique();
+        let ix = borrow(owner, owner, owner, &keys, 1_000, 0);
+
+        assert_eq!(ix.program_id, crate::ID);
+        assert_eq!(&ix.data[..8], ix::Borrow::DISCRIMINATOR);
+        assert_eq!(ix.accounts[0].pubkey, owner);
+        assert!(ix.accounts[0].is_signer);
+        assert_eq!(ix.accounts[2].pubkey, keys.market());
+        assert_eq!(ix.accounts[3].pubkey, keys.position(&owner));
+        // Self-borrow: optional authorization slot is filled with the program id
+        assert_eq!(ix.accounts[4].pubkey, crate::ID);
+        assert_eq!(ix.accounts[8].pubkey, keys.loan_ata(&owner));
+    }
+
+    #[test]
+    fn test_delegated_withdraw_uses_authorization_pda() {
+        let keys = test_keys();
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+        let ix = withdraw(delegate, owner, delegate, &keys, 0, 1_000);
+
+        let (expected_auth, _) = derive_authorization(&crate::ID, &owner, &delegate);
+        assert_eq!(ix.accounts[3].pubkey, keys.position(&owner));
+        assert_eq!(ix.accounts[4].pubkey, expected_auth);
+    }
+
+    #[test]
+    fn test_merge_positions_authorizes_each_side() {
+        let keys = test_keys();
+        let owner = Pubkey::new_unique();
+        let old_wallet = Pubkey::new_unique();
+        let ix = merge_positions(owner, old_wallet, owner, owner, &keys);
+
+        assert_eq!(ix.accounts[3].pubkey, keys.position(&old_wallet));
+        assert_eq!(ix.accounts[4].pubkey, keys.position(&owner));
+        assert_eq!(ix.accounts[5].pubkey, derive_authorization(&crate::ID, &old_wallet, &owner).0);
+        // Caller owns the destination: no authorization needed there
+        assert_eq!(ix.accounts[6].pubkey, crate::ID);
+    }
+
+    #[test]
+    fn test_accrue_interest_piggybacks_fee_claim() {
+        let keys = test_keys();
+        let recipient = Pubkey::new_unique();
+
+        let plain = accrue_interest(keys.market_id, None);
+        assert_eq!(plain.accounts.len(), 3);
+        assert!(plain.accounts[1..].iter().all(|a| a.pubkey == crate::ID));
+
+        let claiming = accrue_interest(keys.market_id, Some(recipient));
+        assert_eq!(claiming.accounts[0].pubkey, keys.market());
+        assert_eq!(claiming.accounts[1].pubkey, protocol_state());
+        assert_eq!(claiming.accounts[2].pubkey, keys.position(&recipient));
+        assert!(claiming.accounts[2].is_writable);
+    }
+
+    #[test]
+    fn test_create_market_vaults() {
+        let keys = test_keys();
+        let creator = Pubkey::new_unique();
+        let ix = create_market(creator, &keys, crate::interfaces::MAX_ORACLE_STALENESS, 0);
+
+        assert_eq!(ix.accounts.len(), 14);
+        assert_eq!(ix.accounts[5].pubkey, keys.collateral_vault());
+        assert_eq!(ix.accounts[6].pubkey, keys.loan_vault());
+        // Bounds are looked up by loan token, whether or not any are set
+        assert_eq!(ix.accounts[9].pubkey, derive_lltv_bounds(&crate::ID, &keys.loan_mint).0);
+        // Badges by collateral, likewise
+        assert_eq!(ix.accounts[10].pubkey, derive_token_badge(&crate::ID, &keys.collateral_mint).0);
+    }
+
+    #[test]
+    fn test_risk_oracle_follows_keys() {
+        let feed = Pubkey::new_unique();
+        let keys = test_keys().with_risk_oracle(feed);
+        let owner = Pubkey::new_unique();
+
+        // The risk oracle slot comes just before the fallback oracle's
+        let risk_slot = |ix: &Instruction| ix.accounts[ix.accounts.len() - 2].pubkey;
+        let ix = borrow(owner, owner, owner, &keys, 1_000, 0);
+        assert_eq!(risk_slot(&ix), feed);
+
+        // Uncapped markets fill the optional slot with the program id
+        let ix = borrow(owner, owner, owner, &test_keys(), 1_000, 0);
+        assert_eq!(risk_slot(&ix), crate::ID);
+
+        // Pure repays never need it
+        let repay_only = PositionAdjustment { repay_assets: 1_000, ..Default::default() };
+        let ix = adjust_position(owner, owner, owner, owner, &keys, repay_only);
+        assert_eq!(ix.accounts.last().unwrap().pubkey, crate::ID);
+    }
+
+    #[test]
+    fn test_fallback_oracle_follows_keys() {
+        let fallback = Pubkey::new_unique();
+        let keys = test_keys().with_fallback_oracle(fallback);
+        let owner = Pubkey::new_unique();
+
+        for ix in [
+            borrow(owner, owner, owner, &keys, 1_000, 0),
+            withdraw_collateral(owner, owner, owner, &keys, 1_000),
+            create_market(owner, &keys, crate::interfaces::MAX_ORACLE_STALENESS, 0),
+        ] {
+            assert_eq!(ix.accounts.last().unwrap().pubkey, fallback);
+        }
+
+        // Liquidations keep the operator slot last
+        let fallback_slot = |ix: &Instruction| ix.accounts[ix.accounts.len() - 2].pubkey;
+        let ix = liquidate(owner, owner, owner, owner, &keys, 1_000);
+        assert_eq!(fallback_slot(&ix), fallback);
+        let ix = liquidate(owner, owner, owner, owner, &test_keys(), 1_000);
+        assert_eq!(fallback_slot(&ix), crate::ID);
+    }
+
+    #[test]
+    fn test_reference_oracle_follows_keys() {
+        let reference = Pubkey::new_unique();
+        let keys = test_keys().with_reference_oracle(reference);
+        let owner = Pubkey::new_unique();
+
+        // Reference PDA and oracle sit just ahead of the fallback slot
+        let ix = liquidate(owner, owner, owner, owner, &keys, 1_000);
+        let slots = &ix.accounts[ix.accounts.len() - 4..ix.accounts.len() - 2];
+        assert_eq!(slots[0].pubkey, derive_liquidation_reference(&crate::ID, &keys.market_id).0);
+        assert_eq!(slots[1].pubkey, reference);
+
+        let ix = liquidate(owner, owner, owner, owner, &test_keys(), 1_000);
+        assert_eq!(ix.accounts[ix.accounts.len() - 3].pubkey, crate::ID);
+
+        // Repays never carry it
+        assert!(repay(owner, owner, owner, &keys, 1_000, 0).accounts.iter().all(|a| a.pubkey != reference));
+    }
+
+    #[test]
+    fn test_irm_program_rides_along() {
+        let owner = Pubkey::new_unique();
+        let keys = test_keys();
+        let plain = repay(owner, owner, owner, &keys, 1_000, 0);
+        assert!(plain.accounts.iter().all(|a| a.pubkey != keys.irm));
+
+        let keys = keys.with_irm_cpi();
+        for ix in [
+            supply(owner, owner, owner, &keys, 1_000, 0, false),
+            repay(owner, owner, owner, &keys, 1_000, 0),
+            liquidate(owner, owner, owner, owner, &keys, 1_000),
+        ] {
+            let last = ix.accounts.last().unwrap();
+            assert_eq!(last.pubkey, keys.irm);
+            assert!(!last.is_writable && !last.is_signer);
+        }
+        assert_eq!(plain.accounts.len() + 1, repay(owner, owner, owner, &keys, 1_000, 0).accounts.len());
+    }
+
+    #[test]
+    fn test_set_market_irm_passes_old_and_new_irm() {
+        let owner = Pubkey::new_unique();
+        let keys = test_keys().with_irm_cpi();
+        let adaptive_model = derive_adaptive_curve_model(&crate::ID).0;
+
+        let ix = set_market_irm(owner, &keys, adaptive_model);
+        assert_eq!(ix.accounts[3].pubkey, derive_irm_update_proposal(&crate::ID, &keys.market_id).0);
+        assert_eq!(ix.accounts[4].pubkey, adaptive_model);
+        // The old IRM accrues up to the switch, the new state is restarted
+        let [old, new] = &ix.accounts[5..] else { panic!("expected both IRMs") };
+        assert_eq!(old.pubkey, keys.irm);
+        assert!(!old.is_writable);
+        assert_eq!(new.pubkey, derive_adaptive_curve_irm(&crate::ID, &keys.market_id).0);
+        assert!(new.is_writable);
+
+        // A program IRM is the new_irm account itself
+        let ix = set_market_irm(owner, &keys, Pubkey::new_unique());
+        assert_eq!(ix.accounts.len(), 6);
+    }
+
+    #[test]
+    fn test_adaptive_curve_state_rides_along() {
+        let owner = Pubkey::new_unique();
+        let base = test_keys();
+        let keys = MarketKeys::new(
+            base.collateral_mint,
+            base.loan_mint,
+            base.oracle,
+            derive_adaptive_curve_model(&crate::ID).0,
+            base.lltv,
+            base.token_program,
+        );
+        assert!(keys.irm_adaptive && !keys.irm_cpi);
+
+        let state = derive_adaptive_curve_irm(&crate::ID, &keys.market_id).0;
+        for ix in [
+            supply(owner, owner, owner, &keys, 1_000, 0, false),
+            repay(owner, owner, owner, &keys, 1_000, 0),
+            liquidate(owner, owner, owner, owner, &keys, 1_000),
+        ] {
+            let last = ix.accounts.last().unwrap();
+            assert_eq!(last.pubkey, state);
+            assert!(last.is_writable && !last.is_signer);
+        }
+        assert_eq!(init_adaptive_curve_irm(owner, keys.market_id).accounts[2].pubkey, state);
+    }
+
+    #[cfg(feature = "devnet")]
+    #[test]
+    fn test_clock_override_rides_along() {
+        let owner = Pubkey::new_unique();
+        let keys = test_keys();
+        let clock_override = derive_clock_override(&crate::ID).0;
+        assert_eq!(set_clock_override(owner, 1_900_000_000).accounts[2].pubkey, clock_override);
+        assert_eq!(clear_clock_override(owner).accounts[2].pubkey, clock_override);
+
+        let ix = with_clock_override(accrue_interest(keys.market_id, None), owner);
+        let [.., passed, authority] = &ix.accounts[..] else { panic!() };
+        assert_eq!(passed.pubkey, clock_override);
+        assert!(!passed.is_writable && !passed.is_signer);
+        assert_eq!(authority.pubkey, owner);
+        assert!(!authority.is_writable && authority.is_signer);
+    }
+
+    #[test]
+    fn test_flash_loan_multi_lists_each_market() {
+        let borrower = Pubkey::new_unique();
+        let first = test_keys();
+        let second = MarketKeys::new(
+            Pubkey::new_unique(),
+            first.loan_mint,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            first.lltv,
+            first.token_program,
+        )
+        .with_irm_cpi();
+        let markets = [first, second];
+
+        let start = flash_loan_multi(borrower, borrower, &markets, 1_000);
+        let legs: Vec<_> = start.accounts[5..].iter().map(|a| (a.pubkey, a.is_writable)).collect();
+        assert_eq!(
+            legs,
+            vec![
+                (first.market(), true),
+                (first.loan_vault(), true),
+                (second.market(), true),
+                (second.loan_vault(), true),
+            ]
+        );
+
+        // Only the end accrues, so only it carries the IRM program
+        let end = flash_loan_multi_end(borrower, borrower, &markets);
+        assert_eq!(end.accounts.len(), 4 + 4 + 1);
+        assert_eq!(end.accounts.last().unwrap().pubkey, second.irm);
+    }
+
+    #[test]
+    fn test_settle_bad_debt_needs_no_oracle() {
+        let keys = test_keys();
+        let (caller, borrower) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let ix = settle_bad_debt(caller, borrower, &keys);
+        assert_eq!(ix.accounts.len(), 4);
+        assert!(ix.accounts[0].is_signer);
+        assert_eq!(ix.accounts[2].pubkey, keys.position(&borrower));
+        assert!(ix.accounts.iter().all(|a| a.pubkey != keys.oracle));
+    }
+
+    #[test]
+    fn test_liquidation_operator_slot() {
+        let keys = test_keys();
+        let owner = Pubkey::new_unique();
+        let operator = LiquidationOperator { collateral_account: Pubkey::new_unique(), share_bps: 2_000 };
+
+        let ix = liquidate_with_operator(owner, owner, owner, owner, &keys, 1_000, Some(operator));
+        let slot = ix.accounts.last().unwrap();
+        assert_eq!(slot.pubkey, operator.collateral_account);
+        assert!(slot.is_writable);
+
+        let ix = liquidate(owner, owner, owner, owner, &keys, 1_000);
+        assert_eq!(ix.accounts.last().unwrap().pubkey, crate::ID);
+    }
+
+    #[test]
+    fn test_deleveraging_builders_omit_oracle() {
+        let keys = test_keys();
+        let owner = Pubkey::new_unique();
+        let no_oracle = |ix: &Instruction| ix.accounts.iter().all(|a| a.pubkey != keys.oracle);
+
+        assert!(no_oracle(&repay(owner, owner, owner, &keys, 1_000, 0)));
+        assert!(no_oracle(&supply_collateral(owner, owner, owner, &keys, 1_000)));
+
+        let deleverage = PositionAdjustment { repay_assets: 1_000, collateral_in: 10, ..Default::default() };
+        let ix = adjust_position(owner, owner, owner, owner, &keys, deleverage);
+        assert!(no_oracle(&ix));
+        // Omitted optional oracle slot is filled with the program id
+        assert_eq!(ix.accounts[5].pubkey, crate::ID);
+
+        let lever = PositionAdjustment { borrow_assets: 1_000, ..Default::default() };
+        let ix = adjust_position(owner, owner, owner, owner, &keys, lever);
+        assert_eq!(ix.accounts[5].pubkey, keys.oracle);
+    }
+
+    #[test]
+    fn test_credit_line_builders_share_pda() {
+        let keys = test_keys();
+        let supplier = Pubkey::new_unique();
+        let borrower = Pubkey::new_unique();
+        let line = keys.credit_line(&supplier, &borrower);
+
+        let set = set_credit_line(supplier, borrower, &keys, 1_000);
+        assert_eq!(set.accounts[2].pubkey, keys.position(&supplier));
+        assert_eq!(set.accounts[3].pubkey, line);
+
+        let draw = draw_credit(borrower, supplier, keys.loan_ata(&borrower), &keys, 100, 0);
+        assert!(draw.accounts[0].is_signer);
+        assert_eq!(draw.accounts[3].pubkey, line);
+        assert_eq!(draw.accounts[4].pubkey, keys.position(&supplier));
+        assert!(!draw.accounts[4].is_writable);
+
+        let close = close_credit_line(supplier, borrower, &keys);
+        assert_eq!(close.accounts[3].pubkey, line);
+        assert_ne!(line, keys.credit_line(&borrower, &supplier));
+    }
+
+    #[test]
+    fn test_admin_batch_appends_each_market_once() {
+        let keys = test_keys();
+        let owner = Pubkey::new_unique();
+        let ix = admin_batch(owner, vec![
+            AdminAction::EnableLltv { lltv: 9000 },
+            AdminAction::SetFee { market_id: keys.market_id, fee: 500 },
+            AdminAction::SetMarketPaused { market_id: keys.market_id, paused: true },
+        ]);
+
+        assert_eq!(ix.accounts.len(), 4);
+        assert_eq!(ix.accounts[3].pubkey, keys.market());
+        assert!(ix.accounts[3].is_writable);
+    }
+}
@@ -0,0 +1,247 @@
+//! Liquidation profitability estimates for bots
+//!
+//! Mirrors the accounting in `instructions::liquidate` exactly (same LIF,
+//! same rounding) and then values both legs in a common quote currency so
+//! a liquidator can decide whether a liquidation is worth sending.
+
+use anchor_lang::prelude::*;
+use crate::constants::BPS;
+use crate::interfaces::{
+    calculate_lif, calculate_seized_collateral, is_liquidatable_with_stable_debt, seize_within_max_lif,
+};
+use crate::math::{checked_add, mul_div_down, mul_div_up, to_assets_up, to_shares_down};
+use crate::state::{Market, Position};
+
+/// Quote-currency prices for the market's tokens
+///
+/// Prices are WAD-scaled quote units per WHOLE token (e.g. 2000 USD/SOL = 2000e18).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenPrices {
+    pub collateral_price: u128,
+    pub loan_price: u128,
+}
+
+/// Off-chain costs of executing a liquidation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LiquidationCosts {
+    /// Transaction + priority fees, WAD-scaled quote units
+    pub gas_cost: u128,
+    /// Cost of swapping seized collateral back into the loan token (basis points)
+    pub swap_fee_bps: u64,
+}
+
+/// Projected outcome of a liquidation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LiquidationEstimate {
+    /// Loan tokens the liquidator actually pays (after share rounding)
+    pub repaid_assets: u128,
+    /// Borrow shares burned
+    pub repaid_shares: u128,
+    /// Collateral tokens received
+    pub seized_collateral: u128,
+    /// Value of repaid loan tokens (WAD-scaled quote)
+    pub repay_value: u128,
+    /// Value of seized collateral (WAD-scaled quote)
+    pub seized_value: u128,
+    /// Swap fees + gas (WAD-scaled quote)
+    pub costs: u128,
+    /// seized_value - repay_value - costs (WAD-scaled quote, may be negative)
+    pub pnl: i128,
+}
+
+impl LiquidationEstimate {
+    pub fn is_profitable(&self) -> bool {
+        self.pnl > 0
+    }
+}
+
+/// Value `amount` raw token units at a WAD-scaled per-whole-token price
+pub fn token_value(amount: u128, price: u128, decimals: u8) -> Result<u128> {
+    mul_div_down(amount, price, 10u128.pow(decimals as u32))
+}
+
+/// Total debt of a position in loan tokens (rounded UP, as the program does)
+///
+/// Stable debt is counted as of the position's last stable accrual.
+pub fn position_debt(market: &Market, position: &Position) -> Result<u128> {
+    checked_add(
+        to_assets_up(
+            position.borrow_shares,
+            market.total_borrow_assets,
+            market.total_borrow_shares,
+        )?,
+        position.stable_borrow_assets,
+    )
+}
+
+/// Estimate the PnL of liquidating `position` by repaying `repay_assets`
+///
+/// `market` must already reflect accrued interest, and `now` is the time the
+/// liquidation lands (it decides the reputation boost). Returns `None` when
+/// the instruction would revert: the position is healthy at `oracle_price`,
+/// or the seize trips the guard (e.g. `repay_assets` well above the debt).
+pub fn estimate_liquidation_pnl(
+    market: &Market,
+    position: &Position,
+    oracle_price: u128,
+    repay_assets: u128,
+    prices: &TokenPrices,
+    costs: &LiquidationCosts,
+    now: i64,
+) -> Result<Option<LiquidationEstimate>> {
+    if repay_assets == 0 || !is_liquidatable_with_stable_debt(
+        position.collateral,
+        position.borrow_shares,
+        position.stable_borrow_assets,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+        oracle_price,
+        market.position_lltv(position, now),
+    )? {
+        return Ok(None);
+    }
+
+    // Same sequence as instructions::liquidate
+    let lif = calculate_lif(market.lltv);
+    let seized_collateral = calculate_seized_collateral(repay_assets, oracle_price, lif)?;
+    let seized_collateral = std::cmp::min(seized_collateral, position.collateral);
+
+    let repaid_shares = to_shares_down(repay_assets, market.total_borrow_assets, market.total_borrow_shares)?;
+    let repaid_shares = std::cmp::min(repaid_shares, position.borrow_shares);
+    let repaid_assets = to_assets_up(repaid_shares, market.total_borrow_assets, market.total_borrow_shares)?;
+    let stable_repaid = std::cmp::min(
+        repay_assets.saturating_sub(repaid_assets),
+        position.stable_borrow_assets,
+    );
+    let repaid_assets = checked_add(repaid_assets, stable_repaid)?;
+    if !seize_within_max_lif(seized_collateral, repaid_assets, oracle_price)? {
+        return Ok(None);
+    }
+
+    let repay_value = token_value(repaid_assets, prices.loan_price, market.loan_decimals)?;
+    let seized_value = token_value(seized_collateral, prices.collateral_price, market.collateral_decimals)?;
+
+    let swap_fee = mul_div_up(seized_value, costs.swap_fee_bps as u128, BPS as u128)?;
+    let total_costs = checked_add(swap_fee, costs.gas_cost)?;
+
+    let pnl = seized_value as i128 - repay_value as i128 - total_costs as i128;
+
+    Ok(Some(LiquidationEstimate {
+        repaid_assets,
+        repaid_shares,
+        seized_collateral,
+        repay_value,
+        seized_value,
+        costs: total_costs,
+        pnl,
+    }))
+}
+
+/// Estimate PnL for repaying the position's full debt
+pub fn estimate_full_liquidation_pnl(
+    market: &Market,
+    position: &Position,
+    oracle_price: u128,
+    prices: &TokenPrices,
+    costs: &LiquidationCosts,
+    now: i64,
+) -> Result<Option<LiquidationEstimate>> {
+    let debt = position_debt(market, position)?;
+    estimate_liquidation_pnl(market, position, oracle_price, debt, prices, costs, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{ORACLE_SCALE, WAD};
+
+    fn test_market() -> Market {
+        Market {
+            collateral_decimals: 6,
+            loan_decimals: 6,
+            lltv: 8500,
+            total_supply_assets: 10_000,
+            total_supply_shares: 10_000_000_000,
+            total_borrow_assets: 1_000,
+            total_borrow_shares: 1_000_000_000,
+            ..Market::default()
+        }
+    }
+
+    fn test_position(collateral: u128) -> Position {
+        Position {
+            bump: 0,
+            market_id: [0u8; 32],
+            owner: Pubkey::default(),
+            supply_shares: 0,
+            borrow_shares: 200_000_000,
+            collateral,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        }
+    }
+
+    const ONE_DOLLAR: TokenPrices = TokenPrices { collateral_price: WAD, loan_price: WAD };
+
+    #[test]
+    fn test_healthy_position_returns_none() {
+        // 300 collateral vs 200 debt at 1:1 is healthy at 85% LLTV
+        // (amounts kept tiny: collateral * 1e36 price must fit in u128)
+        let market = test_market();
+        let position = test_position(300);
+        let est = estimate_full_liquidation_pnl(
+            &market, &position, ORACLE_SCALE, &ONE_DOLLAR, &LiquidationCosts::default(), 0,
+        ).unwrap();
+        assert!(est.is_none());
+    }
+
+    #[test]
+    fn test_underwater_position_is_profitable_without_costs() {
+        // 220 collateral vs 200 debt: LTV ~91% > 85%
+        let market = test_market();
+        let position = test_position(220);
+        let est = estimate_liquidation_pnl(
+            &market, &position, ORACLE_SCALE, 100, &ONE_DOLLAR, &LiquidationCosts::default(), 0,
+        ).unwrap().unwrap();
+
+        assert!(est.seized_collateral > est.repaid_assets, "LIF gives a bonus");
+        assert!(est.is_profitable());
+    }
+
+    #[test]
+    fn test_costs_can_make_liquidation_unprofitable() {
+        let market = test_market();
+        let position = test_position(220);
+        let costs = LiquidationCosts { gas_cost: WAD, swap_fee_bps: 0 };
+        let est = estimate_liquidation_pnl(
+            &market, &position, ORACLE_SCALE, 100, &ONE_DOLLAR, &costs, 0,
+        ).unwrap().unwrap();
+
+        assert!(!est.is_profitable());
+        assert_eq!(
+            est.pnl,
+            est.seized_value as i128 - est.repay_value as i128 - WAD as i128
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Flash-funded liquidation transactions
+//!
+//! A liquidator without loan-token inventory can still liquidate in one
+//! atomic transaction:
+//!
+//! 1. `flash_loan_start` borrows the repay amount into the liquidator's
+//!    loan ATA
+//! 2. `liquidate` repays the borrower's debt and seizes collateral into the
+//!    liquidator's collateral ATA
+//! 3. the caller's swap instructions turn that collateral back into loan
+//!    tokens
+//! 4. `flash_loan_end` pulls principal + fee back out of the loan ATA
+//!
+//! The swap leg plus the bundle's own accounts easily overflow a legacy
+//! transaction, so the static per-market accounts are meant to live in an
+//! address lookup table created once per market.
+
+// `solana_program` 2.2 marks these re-exports deprecated in favour of the
+// standalone crates, which aren't dependencies of this program.
+#[allow(deprecated)]
+use anchor_lang::solana_program::address_lookup_table::{
+    instruction::{create_lookup_table, extend_lookup_table},
+    state::LOOKUP_TABLE_MAX_ADDRESSES,
+    AddressLookupTableAccount,
+};
+#[allow(deprecated)]
+use anchor_lang::solana_program::message::{v0, CompileError};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, Id};
+use anchor_spl::associated_token::AssociatedToken;
+use crate::constants::{BPS, FLASH_LOAN_FEE_BPS};
+use crate::math::mul_div_up;
+use crate::state::derive_protocol_state;
+use super::instruction_builders::{flash_loan_end, flash_loan_start, liquidate, MarketKeys};
+
+/// Everything needed to lay out one flash-funded liquidation
+#[derive(Clone, Debug)]
+pub struct LiquidationBundle {
+    pub liquidator: Pubkey,
+    pub borrower: Pubkey,
+    /// Collateral to seize (as passed to `liquidate`)
+    pub seized_assets: u128,
+    /// Loan tokens to flash-borrow; at least what `liquidate` will pull
+    pub flash_amount: u128,
+    /// Whether the market is in flash-loan allowlist mode
+    pub allowlisted: bool,
+    /// Collateral → loan token swap, run between `liquidate` and
+    /// `flash_loan_end`; it must leave `flash_amount` plus the flash fee
+    /// in the liquidator's loan ATA
+    pub swap_instructions: Vec<Instruction>,
+}
+
+/// Loan tokens the swap must return for `flash_loan_end` to succeed
+pub fn flash_repayment(flash_amount: u128) -> Result<u128> {
+    let fee = mul_div_up(flash_amount, FLASH_LOAN_FEE_BPS as u128, BPS as u128)?;
+    Ok(flash_amount.saturating_add(fee))
+}
+
+/// Instructions for the bundle, in execution order
+///
+/// Token flows go through the liquidator's ATAs for both mints; create
+/// them up front if they don't exist yet.
+pub fn liquidation_bundle_instructions(
+    keys: &MarketKeys,
+    bundle: &LiquidationBundle,
+) -> Vec<Instruction> {
+    let loan_account = keys.loan_ata(&bundle.liquidator);
+    let collateral_account = keys.collateral_ata(&bundle.liquidator);
+
+    let mut instructions = Vec::with_capacity(bundle.swap_instructions.len() + 3);
+    instructions.push(flash_loan_start(
+        bundle.liquidator,
+        loan_account,
+        keys,
+        bundle.flash_amount,
+        bundle.allowlisted,
+    ));
+    instructions.push(liquidate(
+        bundle.liquidator,
+        bundle.borrower,
+        loan_account,
+        collateral_account,
+        keys,
+        bundle.seized_assets,
+    ));
+    instructions.extend(bundle.swap_instructions.iter().cloned());
+    instructions.push(flash_loan_end(bundle.liquidator, loan_account, keys, bundle.flash_amount));
+    instructions
+}
+
+/// Accounts every liquidation bundle on this market touches, whoever the
+/// borrower is: worth keeping in the liquidator's lookup table
+///
+/// Borrower positions change per liquidation and stay out; signers can't
+/// be looked up anyway.
+pub fn liquidation_lookup_addresses(keys: &MarketKeys, liquidator: &Pubkey) -> Vec<Pubkey> {
+    vec![
+        crate::ID,
+        derive_protocol_state(&crate::ID).0,
+        keys.market(),
+        keys.loan_vault(),
+        keys.collateral_vault(),
+        keys.loan_mint,
+        keys.collateral_mint,
+        keys.oracle,
+        keys.token_program,
+        keys.loan_ata(liquidator),
+        keys.collateral_ata(liquidator),
+        system_program::ID,
+        AssociatedToken::id(),
+    ]
+}
+
+/// Addresses from `wanted` that `table` doesn't hold yet, in order
+pub fn missing_lookup_addresses(table: &AddressLookupTableAccount, wanted: &[Pubkey]) -> Vec<Pubkey> {
+    let mut missing: Vec<Pubkey> = Vec::new();
+    for address in wanted {
+        if !table.addresses.contains(address) && !missing.contains(address) {
+            missing.push(*address);
+        }
+    }
+    missing
+}
+
+/// Create a lookup table for `liquidator` on this market and fill it
+///
+/// Returns the table address and the create + extend instructions. The
+/// table is only usable from the slot after the extend lands.
+pub fn create_liquidation_lookup_table(
+    keys: &MarketKeys,
+    liquidator: Pubkey,
+    payer: Pubkey,
+    recent_slot: u64,
+) -> (Pubkey, Vec<Instruction>) {
+    let (create, table) = create_lookup_table(liquidator, payer, recent_slot);
+    let extend = extend_lookup_table(
+        table,
+        liquidator,
+        Some(payer),
+        liquidation_lookup_addresses(keys, &liquidator),
+    );
+    (table, vec![create, extend])
+}
+
+/// Top up an existing table with this market's accounts
+///
+/// Returns `None` when nothing is missing or the table has no room left.
+pub fn extend_liquidation_lookup_table(
+    table: &AddressLookupTableAccount,
+    keys: &MarketKeys,
+    liquidator: Pubkey,
+    payer: Pubkey,
+) -> Option<Instruction> {
+    let missing = missing_lookup_addresses(table, &liquidation_lookup_addresses(keys, &liquidator));
+    if missing.is_empty() || table.addresses.len() + missing.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return None;
+    }
+    Some(extend_lookup_table(table.key, liquidator, Some(payer), missing))
+}
+
+/// Compile the bundle into a v0 message paid by the liquidator
+///
+/// `lookup_tables` should include the table from
+/// `create_liquidation_lookup_table` plus any the swap venue publishes.
+pub fn liquidation_bundle_message(
+    keys: &MarketKeys,
+    bundle: &LiquidationBundle,
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> std::result::Result<v0::Message, CompileError> {
+    v0::Message::try_compile(
+        &bundle.liquidator,
+        &liquidation_bundle_instructions(keys, bundle),
+        lookup_tables,
+        recent_blockhash,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    fn test_keys() -> MarketKeys {
+        MarketKeys::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            8000,
+            anchor_spl::token::ID,
+        )
+    }
+
+    fn test_bundle(swap_instructions: Vec<Instruction>) -> LiquidationBundle {
+        LiquidationBundle {
+            liquidator: Pubkey::new_unique(),
+            borrower: Pubkey::new_unique(),
+            seized_assets: 1_000,
+            flash_amount: 900,
+            allowlisted: false,
+            swap_instructions,
+        }
+    }
+
+    #[test]
+    fn test_bundle_order_wraps_swap_in_flash_loan() {
+        let keys = test_keys();
+        let swap = Instruction { program_id: Pubkey::new_unique(), accounts: vec![], data: vec![7] };
+        let bundle = test_bundle(vec![swap.clone()]);
+
+        let ixs = liquidation_bundle_instructions(&keys, &bundle);
+        assert_eq!(ixs.len(), 4);
+        assert!(ixs[0].data.starts_with(crate::instruction::FlashLoanStart::DISCRIMINATOR));
+        assert!(ixs[1].data.starts_with(crate::instruction::Liquidate::DISCRIMINATOR));
+        assert_eq!(ixs[2], swap);
+        assert!(ixs[3].data.starts_with(crate::instruction::FlashLoanEnd::DISCRIMINATOR));
+
+        // Both flash legs settle through the liquidator's loan ATA
+        let loan_ata = keys.loan_ata(&bundle.liquidator);
+        assert!(ixs[0].accounts.iter().any(|m| m.pubkey == loan_ata));
+        assert!(ixs[3].accounts.iter().any(|m| m.pubkey == loan_ata));
+    }
+
+    #[test]
+    fn test_lookup_table_compresses_message() {
+        let keys = test_keys();
+        let bundle = test_bundle(vec![]);
+        let (table_key, setup) = create_liquidation_lookup_table(&keys, bundle.liquidator, bundle.liquidator, 1);
+        assert_eq!(setup.len(), 2);
+
+        let table = AddressLookupTableAccount {
+            key: table_key,
+            addresses: liquidation_lookup_addresses(&keys, &bundle.liquidator),
+        };
+        assert!(extend_liquidation_lookup_table(&table, &keys, bundle.liquidator, bundle.liquidator).is_none());
+
+        let plain = liquidation_bundle_message(&keys, &bundle, &[], Hash::default()).unwrap();
+        let compressed = liquidation_bundle_message(&keys, &bundle, &[table], Hash::default()).unwrap();
+        assert_eq!(compressed.address_table_lookups.len(), 1);
+        assert!(compressed.account_keys.len() < plain.account_keys.len());
+    }
+}
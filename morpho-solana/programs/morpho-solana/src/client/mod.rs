@@ -0,0 +1,14 @@
+//! Off-chain client helpers (enabled with the `client` feature)
+//!
+//! Nothing in here is used by the on-chain program; it exists so Rust bots
+//! and integrators can build transactions without an Anchor client.
+
+pub mod instruction_builders;
+pub mod liquidation;
+pub mod liquidation_bundle;
+pub mod repay_order;
+
+pub use instruction_builders::*;
+pub use liquidation::*;
+pub use liquidation_bundle::*;
+pub use repay_order::*;
@@ -0,0 +1,192 @@
+//! Repayment ordering across markets
+//!
+//! Every market is isolated, so a borrower with debt in several markets
+//! improves each position's health independently. Repaying `x` of a debt
+//! `D` at health `H = C·LLTV / D` raises it at `dH/dx = H / D`, so the best
+//! next token goes to the position with the highest `H / D` per quote unit
+//! of debt. The gain only grows as that debt shrinks, so once a position is
+//! first in line it stays there until cleared: repaying in this order, each
+//! position in full, is the greedy optimum the deleverage and auto-repay
+//! flows follow.
+
+use anchor_lang::prelude::*;
+use crate::constants::WAD;
+use crate::interfaces::health_factor;
+use crate::math::mul_div_down;
+use crate::state::{Market, Position};
+use super::liquidation::{position_debt, token_value};
+
+/// A borrower's position in one market, with the prices needed to rank it
+#[derive(Clone, Copy)]
+pub struct RepayCandidate<'a> {
+    /// Market state, already accrued
+    pub market: &'a Market,
+    pub position: &'a Position,
+    /// Oracle price (ORACLE_SCALE, loan per collateral)
+    pub oracle_price: u128,
+    /// Loan token price, WAD-scaled quote units per whole token
+    pub loan_price: u128,
+}
+
+/// Where a candidate falls in the repayment order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RepayPriority {
+    /// Index into the candidate slice
+    pub index: usize,
+    /// Debt in loan tokens (rounded up, stable debt included)
+    pub debt: u128,
+    /// Debt value (WAD-scaled quote)
+    pub debt_value: u128,
+    /// Current health factor (WAD-scaled)
+    pub health: u128,
+    /// Health gained per quote unit repaid (WAD-scaled)
+    pub score: u128,
+}
+
+/// Rank debt-carrying candidates, best health gain per quote unit first
+///
+/// Positions without debt are left out; ties go to the less healthy one.
+/// Health is taken at `now`, which decides any reputation boost.
+pub fn repay_order(candidates: &[RepayCandidate], now: i64) -> Result<Vec<RepayPriority>> {
+    let mut order = Vec::with_capacity(candidates.len());
+    for (index, candidate) in candidates.iter().enumerate() {
+        let debt = position_debt(candidate.market, candidate.position)?;
+        let debt_value = token_value(debt, candidate.loan_price, candidate.market.loan_decimals)?;
+        if debt == 0 || debt_value == 0 {
+            continue;
+        }
+
+        let health = health_factor(
+            candidate.position.collateral,
+            debt,
+            candidate.oracle_price,
+            candidate.market.position_lltv(candidate.position, now),
+        )?;
+        let score = mul_div_down(health, WAD, debt_value)?;
+        order.push(RepayPriority { index, debt, debt_value, health, score });
+    }
+
+    order.sort_by(|a, b| b.score.cmp(&a.score).then(a.health.cmp(&b.health)));
+    Ok(order)
+}
+
+/// Split a repayment budget (WAD-scaled quote) along `order`
+///
+/// Returns `(candidate index, loan tokens)` pairs; each position is repaid
+/// in full before the next, the last one partially.
+pub fn allocate_repay_budget(
+    order: &[RepayPriority],
+    budget_value: u128,
+) -> Result<Vec<(usize, u128)>> {
+    let mut remaining = budget_value;
+    let mut plan = Vec::new();
+    for priority in order {
+        if remaining == 0 {
+            break;
+        }
+        if remaining >= priority.debt_value {
+            plan.push((priority.index, priority.debt));
+            remaining -= priority.debt_value;
+            continue;
+        }
+
+        let amount = mul_div_down(priority.debt, remaining, priority.debt_value)?;
+        if amount > 0 {
+            plan.push((priority.index, amount));
+        }
+        break;
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::ORACLE_SCALE;
+
+    fn test_market(loan_decimals: u8) -> Market {
+        Market {
+            collateral_decimals: 6,
+            loan_decimals,
+            lltv: 8000,
+            total_supply_assets: 10_000,
+            total_supply_shares: 10_000_000_000,
+            total_borrow_assets: 1_000,
+            total_borrow_shares: 1_000_000_000,
+            ..Market::default()
+        }
+    }
+
+    fn test_position(collateral: u128, debt: u128) -> Position {
+        Position {
+            bump: 0,
+            market_id: [0u8; 32],
+            owner: Pubkey::default(),
+            supply_shares: 0,
+            borrow_shares: debt * 1_000_000,
+            collateral,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        }
+    }
+
+    #[test]
+    fn test_smaller_riskier_debt_goes_first() {
+        // (amounts kept tiny: collateral * 1e36 price must fit in u128)
+        let market = test_market(6);
+        let large = test_position(300, 200);
+        let small = test_position(120, 80);
+        let clear = test_position(100, 0);
+        let candidate = |position| RepayCandidate {
+            market: &market,
+            position,
+            oracle_price: ORACLE_SCALE,
+            loan_price: WAD,
+        };
+        let candidates = [candidate(&large), candidate(&clear), candidate(&small)];
+
+        let order = repay_order(&candidates, 0).unwrap();
+        assert_eq!(order.len(), 2, "Debt-free positions are skipped");
+        assert_eq!(order[0].index, 2);
+        assert_eq!(order[1].index, 0);
+        assert!(order[0].score > order[1].score);
+
+        // Enough for the small debt in full and a tenth of the large one
+        let budget = token_value(100, WAD, 6).unwrap();
+        let plan = allocate_repay_budget(&order, budget).unwrap();
+        assert_eq!(plan, vec![(2, 80), (0, 20)]);
+    }
+
+    #[test]
+    fn test_pricier_loan_token_costs_more_per_health_point() {
+        let market = test_market(6);
+        let position = test_position(300, 200);
+        let cheap = RepayCandidate { market: &market, position: &position, oracle_price: ORACLE_SCALE, loan_price: WAD };
+        let pricey = RepayCandidate { loan_price: 2 * WAD, ..cheap };
+
+        let order = repay_order(&[pricey, cheap], 0).unwrap();
+        assert_eq!(order[0].index, 1, "Same health gain for half the spend");
+        assert_eq!(order[0].health, order[1].health);
+    }
+}
@@ -1,4 +1,15 @@
 //! Protocol constants and configuration parameters
+//!
+//! Cluster-sensitive values are selected by Cargo feature: `mainnet`
+//! (the default when no cluster feature is enabled), `devnet`, or
+//! `localnet`. At most one cluster feature may be enabled.
+
+#[cfg(any(
+    all(feature = "mainnet", feature = "devnet"),
+    all(feature = "mainnet", feature = "localnet"),
+    all(feature = "devnet", feature = "localnet"),
+))]
+compile_error!("enable at most one of the `mainnet`, `devnet`, `localnet` features");
 
 /// Program-specific seed prefix for all PDAs
 pub const PROGRAM_SEED_PREFIX: &[u8] = b"morpho_v1";
@@ -12,9 +23,20 @@ pub const WAD: u128 = 1_000_000_000_000_000_000;
 pub const ORACLE_SCALE: u128 = 1_000_000_000_000_000_000_000_000_000_000_000_000;
 
 /// Minimum oracle price (prevents division issues)
-/// Set to 1e18 to allow for devnet testing with various oracle feeds
+/// Mainnet mirrors the 1e9 upper ratio bound: 1e-9 * ORACLE_SCALE
+#[cfg(not(any(feature = "devnet", feature = "localnet")))]
+pub const MIN_ORACLE_PRICE: u128 = 1_000_000_000_000_000_000_000_000_000; // 1e27
+
+/// Minimum oracle price (prevents division issues)
+/// Relaxed to 1e18 to allow devnet testing with various oracle feeds
+#[cfg(feature = "devnet")]
 pub const MIN_ORACLE_PRICE: u128 = 1_000_000_000_000_000_000; // 1e18
 
+/// Minimum oracle price (prevents division issues)
+/// Any non-zero price is accepted on local validators
+#[cfg(feature = "localnet")]
+pub const MIN_ORACLE_PRICE: u128 = 1;
+
 // Note: MAX_ORACLE_PRICE is computed at runtime via max_oracle_price() in interfaces/oracle.rs
 // to avoid compile-time overflow
 
@@ -34,17 +56,61 @@ pub const MAX_FEE: u64 = 2500;
 /// Basis points denominator
 pub const BPS: u64 = 10_000;
 
-/// Maximum number of whitelisted LLTVs
+/// LLTV capacity of the legacy fixed-size protocol state (migration only)
 pub const MAX_LLTVS: usize = 20;
 
-/// Maximum number of whitelisted IRMs
+// === Collateral Tier Constants ===
+
+/// Highest LLTV against tier A collateral (any whitelisted LLTV)
+pub const TIER_A_MAX_LLTV: u64 = BPS;
+
+/// Highest LLTV against tier B collateral (91.5%)
+pub const TIER_B_MAX_LLTV: u64 = 9_150;
+
+/// Highest LLTV against tier C and ungraded collateral (86%)
+pub const TIER_C_MAX_LLTV: u64 = 8_600;
+
+/// IRM capacity of the legacy fixed-size protocol state (migration only)
 pub const MAX_IRMS: usize = 10;
 
+/// Maximum number of sub-actions in a single admin_batch
+pub const MAX_ADMIN_BATCH_ACTIONS: usize = 16;
+
+/// Maximum number of actions in a single dry_run_bundle
+pub const MAX_DRY_RUN_ACTIONS: usize = 16;
+
+/// Max markets in one exposure report (80 bytes per market and per loan
+/// token keeps the report within the 1 KiB return data limit)
+pub const MAX_EXPOSURE_MARKETS: usize = 6;
+
+/// Max markets ranked by one get_accrual_priorities call (56 bytes each)
+pub const MAX_ACCRUAL_PRIORITY_MARKETS: usize = 16;
+
+/// Version of the program's instruction and account interface, bumped on
+/// every incompatible upgrade (see `check_expected_version`)
+pub const PROGRAM_VERSION: u16 = 1;
+
+/// Layout version of view instruction return data (`VersionedView`)
+pub const VIEW_RESPONSE_VERSION: u8 = 2;
+
+/// Maximum number of whitelisted yield adapters
+pub const MAX_YIELD_ADAPTERS: usize = 4;
+
+/// Longest lock seed_market may put on protocol-owned liquidity (days)
+pub const MAX_SEED_LOCK_DAYS: u32 = 365;
+
 // === Liquidation Constants ===
 
 /// Maximum Liquidation Incentive Factor (115% = 11500 scaled)
 pub const MAX_LIF: u64 = 11_500;
 
+/// Slack the liquidation seize guard allows above MAX_LIF (basis points)
+pub const SEIZE_GUARD_TOLERANCE_BPS: u64 = 10;
+
+/// Slots after a partial liquidation during which only the same liquidator
+/// may liquidate the position again, unless closing it out
+pub const LIQUIDATION_COOLDOWN_SLOTS: u64 = 5;
+
 /// LIF cursor (30% = 3000 scaled)
 pub const LIF_CURSOR: u64 = 3_000;
 
@@ -56,9 +122,37 @@ pub const LIF_BPS: u64 = 10_000;
 /// Seconds per year for rate conversions
 pub const SECONDS_PER_YEAR: u128 = 31_536_000;
 
+/// Seconds per day (lock durations)
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
 /// Maximum borrow rate per second (1000% APY cap)
+#[cfg(not(any(feature = "devnet", feature = "localnet")))]
 pub const MAX_BORROW_RATE_PER_SECOND: u128 = WAD * 10 / SECONDS_PER_YEAR;
 
+/// Maximum borrow rate per second (10000% APY cap, so test markets can be
+/// pushed to high utilization without hitting the cap)
+#[cfg(any(feature = "devnet", feature = "localnet"))]
+pub const MAX_BORROW_RATE_PER_SECOND: u128 = WAD * 100 / SECONDS_PER_YEAR;
+
+/// Compute units an external IRM may use per rate query
+pub const IRM_COMPUTE_BUDGET: u64 = 25_000;
+
+/// IRM return data size: one little-endian u128 rate
+pub const IRM_RETURN_DATA_LEN: usize = 16;
+
+/// Slots in a row an IRM may fail its rate query before its market is
+/// switched to withdraw-only
+pub const MAX_IRM_FAILURES: u8 = 3;
+
+/// Instruction an IRM program answers rate queries on: Anchor's
+/// `borrow_rate(utilization: u128)` sighash
+pub const IRM_BORROW_RATE_DISCRIMINATOR: [u8; 8] = [70, 111, 1, 166, 209, 117, 83, 195];
+
+// === Owner Recovery Constants ===
+
+/// Shortest owner silence that may unlock the recovery key (days)
+pub const MIN_OWNER_RECOVERY_DELAY_DAYS: u64 = 30;
+
 // === Safe Math Constants ===
 
 /// Maximum value that fits in u64
@@ -67,4 +161,86 @@ pub const MAX_U64: u128 = u64::MAX as u128;
 // === Flash Loan Constants ===
 
 /// Flash loan fee (0.05% = 5 basis points)
+#[cfg(not(feature = "localnet"))]
 pub const FLASH_LOAN_FEE_BPS: u64 = 5;
+
+/// Flash loan fee (free on local validators to keep test arithmetic exact)
+#[cfg(feature = "localnet")]
+pub const FLASH_LOAN_FEE_BPS: u64 = 0;
+
+/// Most markets one `flash_loan_multi` may draw from
+pub const MAX_FLASH_LOAN_MARKETS: usize = 6;
+
+// === Risk Oracle Constants ===
+
+/// Max age of a risk oracle's max-safe-debt figure (seconds)
+pub const MAX_RISK_ORACLE_AGE: i64 = SECONDS_PER_DAY;
+
+/// Max headroom the owner may grant over the risk oracle's figure (100%)
+pub const MAX_RISK_CAP_BUFFER_BPS: u64 = 10_000;
+
+// === Reputation Constants ===
+
+/// Max LLTV bonus a market may grant positions with a clean history (2%)
+pub const MAX_REPUTATION_LLTV_BOOST_BPS: u64 = 200;
+
+/// Longest age a market may require before positions earn the boost (1 year)
+pub const MAX_REPUTATION_MIN_AGE: i64 = 365 * SECONDS_PER_DAY;
+
+// === Interest Pause Constants ===
+
+/// Longest an owner may zero a market's borrow rate in one go
+pub const MAX_INTEREST_PAUSE_DURATION: i64 = 30 * SECONDS_PER_DAY;
+
+// === Accrual Circuit Breaker Constants ===
+
+/// Tightest accrual growth cap an owner may set (1% per accrual), so the
+/// breaker can't be used to starve suppliers of ordinary interest
+pub const MIN_ACCRUAL_GROWTH_CAP_BPS: u64 = 100;
+
+// === Supply Holding Period Constants ===
+
+/// Longest a market may hold newly supplied shares before they can be withdrawn
+pub const MAX_SUPPLY_HOLDING_PERIOD: i64 = 7 * SECONDS_PER_DAY;
+
+// === Supply Escrow Constants ===
+
+/// Longest supply shares may be escrowed for in one lock (4 years)
+pub const MAX_SUPPLY_ESCROW_DURATION: i64 = 4 * 365 * SECONDS_PER_DAY;
+
+// === Oracle Migration Constants ===
+
+/// Wait between proposing a market's new oracle and switching to it, so
+/// users who distrust the new feed can exit first
+pub const ORACLE_UPDATE_DELAY: i64 = 3 * SECONDS_PER_DAY;
+
+/// Wait between proposing a market's new IRM and switching to it, so users
+/// who distrust the new curve can exit first
+pub const IRM_UPDATE_DELAY: i64 = 3 * SECONDS_PER_DAY;
+
+/// Furthest an IRM switch may move a borrowed market's rate, relative to
+/// the old IRM's rate at the same utilization (basis points)
+pub const MAX_IRM_SWITCH_DEVIATION_BPS: u64 = 2_000;
+
+// === Bad Debt Rebate Constants ===
+
+/// Max share of realized bad debt a liquidator can be rebated (10%)
+pub const MAX_BAD_DEBT_REBATE_BPS: u16 = 1_000;
+
+// === Stable-Rate Constants ===
+
+/// Max yearly spread a stable loan pays over the variable rate (20%)
+pub const MAX_STABLE_RATE_SPREAD_BPS: u64 = 2_000;
+
+// === Insurance Constants ===
+
+/// Max yearly insurance premium a market may charge (10% of collateral)
+pub const MAX_INSURANCE_PREMIUM_BPS: u64 = 1_000;
+
+// === Market Metadata Constants ===
+
+/// Longest market display name (bytes)
+pub const MAX_MARKET_NAME_LEN: usize = 32;
+
+/// Longest market description URI (bytes)
+pub const MAX_MARKET_DESCRIPTION_URI_LEN: usize = 200;
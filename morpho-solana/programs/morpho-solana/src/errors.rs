@@ -15,6 +15,12 @@ pub enum MorphoError {
     #[msg("Authorization has been revoked")]
     AuthorizationRevoked = 6003,
 
+    #[msg("No recovery key is configured")]
+    RecoveryKeyNotSet = 6004,
+
+    #[msg("Owner heartbeat is still within the recovery delay")]
+    OwnerStillActive = 6005,
+
     // === Input Validation Errors (6010-6029) ===
     #[msg("Amount must be greater than zero")]
     ZeroAmount = 6010,
@@ -43,6 +49,15 @@ pub enum MorphoError {
     #[msg("Invalid market ID")]
     InvalidMarketId = 6018,
 
+    #[msg("Market metadata field exceeds its maximum length")]
+    MetadataTooLong = 6019,
+
+    #[msg("Caller was built against an incompatible program version")]
+    IncompatibleVersion = 6020,
+
+    #[msg("Receiver token account is neither a token account of the mint nor the receiver's uncreated ATA")]
+    InvalidReceiverTokenAccount = 6021,
+
     // === Market Errors (6030-6049) ===
     #[msg("Market already exists")]
     MarketExists = 6030,
@@ -59,11 +74,32 @@ pub enum MorphoError {
     #[msg("Parameter already enabled")]
     AlreadyEnabled = 6034,
 
-    #[msg("Maximum LLTVs reached")]
-    MaxLltvsReached = 6035,
+    #[msg("Market already uses the current layout")]
+    MarketAlreadyMigrated = 6035,
+
+    #[msg("Position already uses the current layout")]
+    PositionAlreadyMigrated = 6036,
+
+    #[msg("Protocol state already uses the current layout")]
+    ProtocolStateAlreadyMigrated = 6037,
+
+    #[msg("Market still holds assets, debt or collateral")]
+    MarketNotEmpty = 6038,
+
+    #[msg("LLTV is outside the bounds set for this loan token")]
+    LltvOutOfBounds = 6039,
+
+    #[msg("Market is supply-only and does not lend")]
+    BorrowingDisabled = 6040,
 
-    #[msg("Maximum IRMs reached")]
-    MaxIrmsReached = 6036,
+    #[msg("Supply shares can't be collateral in their own market")]
+    SelfCollateralization = 6041,
+
+    #[msg("Collateral mint is banned")]
+    MintBanned = 6042,
+
+    #[msg("LLTV is above the cap for this collateral's tier")]
+    LltvAboveTierCap = 6043,
 
     // === Balance Errors (6050-6069) ===
     #[msg("Insufficient supply balance")]
@@ -75,6 +111,12 @@ pub enum MorphoError {
     #[msg("Insufficient market liquidity")]
     InsufficientLiquidity = 6052,
 
+    #[msg("Supply shares are locked")]
+    SupplyLocked = 6053,
+
+    #[msg("Position has no escrowed supply shares")]
+    NoEscrowedSupply = 6054,
+
     // === Health Errors (6070-6079) ===
     #[msg("Position would become unhealthy")]
     PositionUnhealthy = 6070,
@@ -85,6 +127,21 @@ pub enum MorphoError {
     #[msg("Position is not empty, cannot close")]
     PositionNotEmpty = 6072,
 
+    #[msg("Position backs credit lines and cannot be merged away")]
+    PositionBacksCreditLines = 6073,
+
+    #[msg("Seized collateral is worth more than the max LIF allows; liquidation math suspect")]
+    LiquidationMathSuspect = 6074,
+
+    #[msg("Position was just partially liquidated by someone else; wait out the cooldown or close it fully")]
+    LiquidationCooldown = 6075,
+
+    #[msg("Operator share must be at most 100% and needs an operator collateral account")]
+    InvalidOperatorShare = 6076,
+
+    #[msg("Position has collateral left or no debt; no bad debt to settle")]
+    NotBadDebt = 6077,
+
     // === Pause Errors (6080-6089) ===
     #[msg("Protocol is paused")]
     ProtocolPaused = 6080,
@@ -92,6 +149,12 @@ pub enum MorphoError {
     #[msg("Market is paused")]
     MarketPaused = 6081,
 
+    #[msg("Market frozen: reference oracles diverged")]
+    MarketFrozen = 6082,
+
+    #[msg("Market is withdraw-only")]
+    MarketWithdrawOnly = 6083,
+
     // === Oracle Errors (6090-6109) ===
     #[msg("Oracle price is stale")]
     OracleStale = 6090,
@@ -117,6 +180,42 @@ pub enum MorphoError {
     #[msg("Oracle price below minimum")]
     OraclePriceTooLow = 6097,
 
+    #[msg("Reference oracles agree within the divergence threshold")]
+    OracleDivergenceNotDetected = 6098,
+
+    #[msg("Market has no oracle divergence guard")]
+    DivergenceGuardNotSet = 6099,
+
+    #[msg("Oracle missed its heartbeat: last update is older than the market allows")]
+    OracleHeartbeatMissed = 6100,
+
+    #[msg("Oracle heartbeat must be between 1 slot and MAX_ORACLE_HEARTBEAT")]
+    InvalidOracleHeartbeat = 6101,
+
+    #[msg("Oracle max age must be at most MAX_ORACLE_AGE_SECS")]
+    InvalidOracleMaxAge = 6102,
+
+    #[msg("Fallback oracle must differ from the primary oracle")]
+    InvalidFallbackOracle = 6103,
+
+    #[msg("TWAP samples don't cover a full window yet")]
+    TwapWindowNotCovered = 6104,
+
+    #[msg("TWAP window must be between MIN_TWAP_WINDOW and MAX_TWAP_WINDOW, over a non-TWAP feed")]
+    InvalidTwapConfig = 6105,
+
+    #[msg("TWAP was sampled too recently")]
+    TwapUpdateTooSoon = 6106,
+
+    #[msg("Oracle confidence interval is wider than the market allows")]
+    OracleConfidenceTooWide = 6107,
+
+    #[msg("Oracle confidence bound must be at most MAX_ORACLE_CONF_BPS")]
+    InvalidOracleConfBound = 6108,
+
+    #[msg("Composite oracle legs must be two distinct USD feeds, not composite, pool TWAP or EWMA oracles")]
+    InvalidCompositeConfig = 6109,
+
     // === IRM Errors (6110-6119) ===
     #[msg("IRM returned invalid rate")]
     IrmInvalidRate = 6110,
@@ -136,6 +235,9 @@ pub enum MorphoError {
     #[msg("IRM rate exceeds maximum")]
     IrmRateTooHigh = 6115,
 
+    #[msg("IRM exceeded its compute budget")]
+    IrmComputeBudgetExceeded = 6116,
+
     // === Math Errors (6120-6139) ===
     #[msg("Math overflow")]
     MathOverflow = 6120,
@@ -158,4 +260,156 @@ pub enum MorphoError {
 
     #[msg("Flash loan callback failed")]
     FlashLoanCallbackFailed = 6142,
+
+    #[msg("Flash loan end does not match the in-progress flash loan")]
+    FlashLoanMismatch = 6143,
+
+    #[msg("Loan vault balance does not cover market liquidity")]
+    FlashLoanVaultShortfall = 6144,
+
+    #[msg("Flash loans are disabled")]
+    FlashLoansDisabled = 6145,
+
+    #[msg("Flash loan borrower is not on this market's allowlist")]
+    FlashLoanNotAllowlisted = 6146,
+
+    #[msg("Only program-derived addresses can be allowlisted for flash loans")]
+    FlashLoanBorrowerNotPda = 6147,
+
+    // === Yield Adapter Errors (6150-6159) ===
+    #[msg("No yield adapter configured for this market")]
+    YieldAdapterNotSet = 6150,
+
+    #[msg("Yield adapter program does not match the market's adapter")]
+    InvalidYieldAdapter = 6151,
+
+    #[msg("Staking would exceed the market's yield adapter cap")]
+    YieldAdapterCapExceeded = 6152,
+
+    #[msg("Vault balance did not change as expected after adapter call")]
+    YieldAdapterBalanceMismatch = 6153,
+
+    #[msg("Cannot change adapter while funds are still deployed")]
+    YieldAdapterFundsDeployed = 6154,
+
+    #[msg("Yield adapter not whitelisted")]
+    YieldAdapterNotEnabled = 6155,
+
+    #[msg("Maximum number of yield adapters reached")]
+    MaxYieldAdaptersReached = 6156,
+
+    // === Governance Errors (6160-6169) ===
+    #[msg("Proposal does not change anything")]
+    EmptyProposal = 6160,
+
+    #[msg("Oracle update delay has not elapsed")]
+    OracleUpdateTimelocked = 6161,
+
+    #[msg("New oracle must differ from the market's oracle and fallback")]
+    InvalidOracleUpdate = 6162,
+
+    // === Credit Line Errors (6170-6179) ===
+    #[msg("Draw would exceed the credit line limit")]
+    CreditLimitExceeded = 6170,
+
+    #[msg("Credit line limit is below its outstanding debt")]
+    CreditLineLimitBelowDebt = 6171,
+
+    #[msg("Supply position does not cover delegated credit")]
+    CreditLineUnderfunded = 6172,
+
+    #[msg("Suppliers cannot extend credit to themselves")]
+    InvalidCreditLineBorrower = 6173,
+
+    // === Risk Oracle Errors (6180-6189) ===
+    #[msg("Borrow would exceed the risk oracle's max safe debt")]
+    RiskCapExceeded = 6180,
+
+    #[msg("Risk oracle account does not match market")]
+    InvalidRiskOracle = 6181,
+
+    #[msg("Risk oracle data is stale")]
+    RiskOracleStale = 6182,
+
+    #[msg("Risk oracle data malformed")]
+    RiskOracleInvalidData = 6183,
+
+    // === Insurance Errors (6190-6199) ===
+    #[msg("Market does not offer liquidation insurance")]
+    InsuranceNotOffered = 6190,
+
+    #[msg("No insurance reimbursement to claim")]
+    NoInsuranceClaim = 6191,
+
+    // === Stable-Rate Errors (6200-6209) ===
+    #[msg("Market does not offer stable-rate loans")]
+    StableBorrowDisabled = 6200,
+
+    #[msg("Stable rate cannot be rebalanced")]
+    StableRateNotRebalanceable = 6201,
+
+    // === View Errors (6210-6219) ===
+    #[msg("View return data has an unsupported version")]
+    UnsupportedViewVersion = 6210,
+
+    #[msg("View return data malformed")]
+    ViewDataMalformed = 6211,
+
+    // === Multi-Collateral Errors (6220-6229) ===
+    #[msg("Multi-collateral market already accepts the maximum number of collaterals")]
+    McCollateralLimitReached = 6220,
+
+    #[msg("Collateral slot is not in use in this multi-collateral market")]
+    InvalidMcCollateral = 6221,
+
+    #[msg("Collateral mint is already accepted by this multi-collateral market")]
+    DuplicateMcCollateral = 6222,
+
+    #[msg("Multi-collateral market already has supply; collaterals are fixed")]
+    McCollateralsFixed = 6223,
+
+    #[msg("Oracle accounts must match the market's collateral oracles, in slot order")]
+    McOracleMismatch = 6224,
+
+    // === Price Relay Errors (6230-6239) ===
+    #[msg("Relayer and max deviation must be set together, within MAX_RELAY_DEVIATION_BPS")]
+    InvalidPriceRelayConfig = 6230,
+
+    #[msg("Relayed price deviates too far from the primary oracle")]
+    RelayedPriceDeviation = 6231,
+
+    #[msg("No primary price to check the relayed price against yet")]
+    RelayReferenceMissing = 6232,
+
+    // === Audit Errors (6240-6249) ===
+    #[msg("Internal invariant broken (audit-assertions build)")]
+    AuditInvariantBroken = 6240,
+
+    // === CLMM Oracle Errors (6250-6259) ===
+    #[msg("Account is not a valid Raydium CLMM observation state")]
+    InvalidClmmObservations = 6250,
+
+    // === EWMA Oracle Errors (6260-6269) ===
+    #[msg("Smoothing must be within (0, BPS], over the market's own built-in feed")]
+    InvalidSmoothingConfig = 6260,
+
+    #[msg("EWMA updated too recently")]
+    EwmaUpdateTooSoon = 6261,
+
+    // === Liquidation Reference Errors (6270-6279) ===
+    #[msg("Reference oracle must be a feed other than the market's, with a tolerance within (0, BPS]")]
+    InvalidLiquidationReference = 6270,
+
+    #[msg("Market oracle and reference oracle disagree; liquidation blocked")]
+    LiquidationPriceDisputed = 6271,
+
+    // === IRM Migration Errors (6280-6289) ===
+    #[msg("IRM update is still timelocked")]
+    IrmUpdateTimelocked = 6280,
+
+    #[msg("New IRM must be enabled and differ from the market's IRM")]
+    InvalidIrmUpdate = 6281,
+
+    #[msg("New IRM's rate deviates too far from the old IRM's at switchover")]
+    IrmSwitchDeviation = 6282,
 }
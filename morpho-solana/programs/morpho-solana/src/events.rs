@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::state::{MarketConfigUpdate, TokenTier};
 
 // === Protocol Events ===
 
@@ -20,6 +21,26 @@ pub struct OwnershipTransferred {
     pub new_owner: Pubkey,
 }
 
+#[event]
+pub struct OwnerHeartbeat {
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecoveryKeySet {
+    pub recovery_key: Pubkey,
+    pub delay_days: u64,
+}
+
+/// The recovery key claimed ownership after the owner went silent
+#[event]
+pub struct OwnershipRecoveryClaimed {
+    pub silent_owner: Pubkey,
+    pub recovery_key: Pubkey,
+    pub last_owner_heartbeat: i64,
+}
+
 #[event]
 pub struct FeeRecipientSet {
     pub old_recipient: Pubkey,
@@ -31,16 +52,74 @@ pub struct ProtocolPausedSet {
     pub paused: bool,
 }
 
+#[event]
+pub struct FlashLoansEnabledSet {
+    pub enabled: bool,
+}
+
 #[event]
 pub struct LltvEnabled {
     pub lltv: u64,
 }
 
+/// LLTV bounds set for a loan token (cleared bounds report 0..=BPS)
+#[event]
+pub struct LltvBoundsSet {
+    pub loan_mint: Pubkey,
+    pub min_lltv: u64,
+    pub max_lltv: u64,
+}
+
+#[event]
+pub struct TokenBadgeSet {
+    pub mint: Pubkey,
+    pub tier: TokenTier,
+}
+
 #[event]
 pub struct IrmEnabled {
     pub irm: Pubkey,
 }
 
+#[event]
+pub struct AdaptiveCurveIrmInitialized {
+    pub market_id: [u8; 32],
+    pub adaptive_curve_irm: Pubkey,
+    /// Rate at target the curve starts from (per second, WAD)
+    pub rate_at_target: u128,
+}
+
+#[event]
+pub struct OracleProgramSet {
+    pub oracle_program: Pubkey,
+}
+
+#[event]
+pub struct YieldAdapterEnabled {
+    pub adapter: Pubkey,
+}
+
+#[event]
+pub struct ProtocolStateMigrated {
+    pub lltv_count: u8,
+    pub irm_count: u8,
+}
+
+#[event]
+pub struct MarketMigrated {
+    pub market_id: [u8; 32],
+    pub old_space: u32,
+    pub new_space: u32,
+}
+
+#[event]
+pub struct PositionMigrated {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub old_space: u32,
+    pub new_space: u32,
+}
+
 // === Market Events ===
 
 #[event]
@@ -51,6 +130,27 @@ pub struct MarketCreated {
     pub oracle: Pubkey,
     pub irm: Pubkey,
     pub lltv: u64,
+    pub market_index: u64,
+    pub oracle_heartbeat: u64,
+    pub max_oracle_age_secs: u64,
+    pub fallback_oracle: Pubkey,
+}
+
+/// A market's display metadata was published or changed
+#[event]
+pub struct MarketMetadataSet {
+    pub market_id: [u8; 32],
+    pub creator: Pubkey,
+    pub name: String,
+    pub description_uri: String,
+    pub curator: Pubkey,
+}
+
+#[event]
+pub struct MarketClosed {
+    pub market_id: [u8; 32],
+    pub rent_receiver: Pubkey,
+    pub market_index: u64,
 }
 
 #[event]
@@ -65,6 +165,297 @@ pub struct FeeSet {
     pub fee: u64,
 }
 
+/// Interest stops accruing until `paused_until` (a past time lifts the pause)
+#[event]
+pub struct InterestPauseSet {
+    pub market_id: [u8; 32],
+    pub paused_until: i64,
+}
+
+/// Newly supplied shares now stay locked for `period` seconds (0 = off)
+#[event]
+pub struct SupplyHoldingPeriodSet {
+    pub market_id: [u8; 32],
+    pub period: i64,
+}
+
+#[event]
+pub struct BadDebtRebateSet {
+    pub market_id: [u8; 32],
+    pub rebate_bps: u16,
+}
+
+#[event]
+pub struct SupplyOnlySet {
+    pub market_id: [u8; 32],
+    pub supply_only: bool,
+}
+
+#[event]
+pub struct MaxAccrualGrowthSet {
+    pub market_id: [u8; 32],
+    pub max_growth_bps: u64,
+}
+
+#[event]
+pub struct SharePriceFloorSet {
+    pub market_id: [u8; 32],
+    pub floor: u128,
+}
+
+#[event]
+pub struct WithdrawOnlySet {
+    pub market_id: [u8; 32],
+    pub withdraw_only: bool,
+}
+
+#[event]
+pub struct MaxOracleAgeSet {
+    pub market_id: [u8; 32],
+    pub max_oracle_age_secs: u64,
+}
+
+#[event]
+pub struct OracleConfBoundSet {
+    pub market_id: [u8; 32],
+    pub max_oracle_conf_bps: u64,
+}
+
+#[event]
+pub struct SwitchboardNormalizationSet {
+    pub market_id: [u8; 32],
+    pub normalize: bool,
+}
+
+#[event]
+pub struct FallbackOracleSet {
+    pub market_id: [u8; 32],
+    pub fallback_oracle: Pubkey,
+}
+
+/// A price came from the fallback oracle because the primary failed
+#[event]
+pub struct OracleFallbackUsed {
+    pub market_id: [u8; 32],
+    pub fallback_oracle: Pubkey,
+}
+
+#[event]
+pub struct BorrowLltvBufferSet {
+    pub market_id: [u8; 32],
+    pub buffer: u64,
+}
+
+#[event]
+pub struct ProtocolBorrowLltvBufferSet {
+    pub buffer: u64,
+}
+
+#[event]
+pub struct RiskOracleSet {
+    pub market_id: [u8; 32],
+    pub risk_oracle: Pubkey,
+    pub buffer_bps: u64,
+}
+
+#[event]
+pub struct DivergenceGuardSet {
+    pub market_id: [u8; 32],
+    pub reference_feeds: [Pubkey; 2],
+    pub max_divergence_bps: u64,
+}
+
+#[event]
+pub struct LiquidationReferenceSet {
+    pub market_id: [u8; 32],
+    /// Default = check cleared
+    pub reference_oracle: Pubkey,
+    pub max_divergence_bps: u64,
+}
+
+#[event]
+pub struct OracleDivergenceFlagged {
+    pub market_id: [u8; 32],
+    pub keeper: Pubkey,
+    pub price_a: u128,
+    pub price_b: u128,
+    pub divergence_bps: u64,
+}
+
+#[event]
+pub struct OracleDivergenceCleared {
+    pub market_id: [u8; 32],
+}
+
+#[event]
+pub struct ReputationBoostSet {
+    pub market_id: [u8; 32],
+    pub boost_bps: u64,
+    pub min_repaid: u128,
+    pub min_age: i64,
+}
+
+#[event]
+pub struct TwapOracleCreated {
+    pub twap_oracle: Pubkey,
+    pub source: Pubkey,
+    pub window: i64,
+}
+
+#[event]
+pub struct TwapUpdated {
+    pub twap_oracle: Pubkey,
+    pub price: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClmmTwapOracleCreated {
+    pub clmm_twap_oracle: Pubkey,
+    pub observation_state: Pubkey,
+    pub min_window: i64,
+    pub collateral_is_token0: bool,
+}
+
+#[event]
+pub struct ClmmTwapUpdated {
+    pub clmm_twap_oracle: Pubkey,
+    pub twap_tick: i32,
+    /// Timestamp of the newest observation averaged
+    pub observed_at: i64,
+}
+
+#[event]
+pub struct PriceSmoothingSet {
+    pub market_id: [u8; 32],
+    pub ewma_oracle: Pubkey,
+    pub source: Pubkey,
+    pub smoothing_bps: u64,
+}
+
+#[event]
+pub struct EwmaUpdated {
+    pub ewma_oracle: Pubkey,
+    /// Source price blended in
+    pub reading: u128,
+    /// Smoothed price after the update
+    pub price: u128,
+}
+
+#[event]
+pub struct PriceRelayerSet {
+    pub market_id: [u8; 32],
+    pub price_relay: Pubkey,
+    /// Default = relay disabled
+    pub relayer: Pubkey,
+    pub max_deviation_bps: u64,
+}
+
+#[event]
+pub struct RelayedPricePushed {
+    pub market_id: [u8; 32],
+    pub relayer: Pubkey,
+    pub price: u128,
+    /// Primary price the push was checked against
+    pub reference_price: u128,
+    /// Whether the primary was live, or `reference_price` is its last good price
+    pub primary_live: bool,
+}
+
+#[event]
+pub struct CompositeOracleCreated {
+    pub composite_oracle: Pubkey,
+    pub base_feed: Pubkey,
+    pub quote_feed: Pubkey,
+}
+
+#[event]
+pub struct CompositeUpdated {
+    pub composite_oracle: Pubkey,
+    pub base_price: u128,
+    pub quote_price: u128,
+    /// Publish time of the older leg
+    pub published_at: i64,
+}
+
+// === Insurance Events ===
+
+#[event]
+pub struct InsuranceParamsSet {
+    pub market_id: [u8; 32],
+    pub premium_bps: u64,
+    pub coverage_bps: u64,
+}
+
+#[event]
+pub struct PositionInsuranceSet {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub insured: bool,
+}
+
+#[event]
+pub struct InsurancePremiumCharged {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub premium: u128,
+    pub insurance_pool: u128,
+}
+
+/// Part of a liquidation penalty set aside for the liquidated owner
+#[event]
+pub struct InsuranceReimbursed {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub penalty: u128,
+    pub reimbursed: u128,
+}
+
+#[event]
+pub struct InsuranceClaimed {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub receiver: Pubkey,
+    pub amount: u128,
+}
+
+// === Stable-Rate Events ===
+
+#[event]
+pub struct StableRateParamsSet {
+    pub market_id: [u8; 32],
+    pub enabled: bool,
+    pub spread_bps: u64,
+    pub rebalance_utilization_bps: u64,
+}
+
+#[event]
+pub struct StableBorrow {
+    pub market_id: [u8; 32],
+    pub caller: Pubkey,
+    pub on_behalf_of: Pubkey,
+    pub receiver: Pubkey,
+    pub assets: u128,
+    /// Position's blended stable rate after the loan (per second, WAD)
+    pub stable_rate: u128,
+}
+
+#[event]
+pub struct StableRepay {
+    pub market_id: [u8; 32],
+    pub repayer: Pubkey,
+    pub on_behalf_of: Pubkey,
+    pub assets: u128,
+}
+
+#[event]
+pub struct StableRateRebalanced {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub old_rate: u128,
+    pub new_rate: u128,
+}
+
 // === Position Events ===
 
 #[event]
@@ -79,6 +470,29 @@ pub struct PositionClosed {
     pub owner: Pubkey,
 }
 
+/// Everything in `source_owner`'s position moved into `destination_owner`'s
+#[event]
+pub struct PositionsMerged {
+    pub market_id: [u8; 32],
+    pub caller: Pubkey,
+    pub source_owner: Pubkey,
+    pub destination_owner: Pubkey,
+    pub supply_shares: u128,
+    pub borrow_shares: u128,
+    pub collateral: u128,
+    pub stable_borrow_assets: u128,
+}
+
+/// A position gained or lost the market's reputation LLTV boost
+#[event]
+pub struct ReputationBoostChanged {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub boosted: bool,
+    /// LLTV the position is now held to
+    pub lltv: u64,
+}
+
 // === Supply Events ===
 
 #[event]
@@ -100,6 +514,44 @@ pub struct Withdraw {
     pub shares: u128,
 }
 
+#[event]
+pub struct MarketSeeded {
+    pub market_id: [u8; 32],
+    /// Owner of the protocol position credited with the shares
+    pub recipient: Pubkey,
+    pub assets: u128,
+    pub shares: u128,
+    pub locked_until: i64,
+}
+
+/// Supply shares escrowed for governance or boost weighting
+///
+/// Re-locking adds to the escrow and may push `unlock_at` out; it never
+/// pulls it in.
+#[event]
+pub struct SupplySharesLocked {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    /// Shares added by this lock
+    pub shares: u128,
+    /// Position's escrow after the lock
+    pub escrowed_shares: u128,
+    pub unlock_at: i64,
+}
+
+/// Escrowed supply shares released
+///
+/// Normally the whole escrow, by its owner after expiry; closing a credit
+/// line can also release the part its write-off burned.
+#[event]
+pub struct SupplySharesUnlocked {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub shares: u128,
+    /// Position's escrow left after the release
+    pub escrowed_shares: u128,
+}
+
 // === Collateral Events ===
 
 #[event]
@@ -140,6 +592,21 @@ pub struct Repay {
     pub shares: u128,
 }
 
+#[event]
+pub struct PositionAdjusted {
+    pub market_id: [u8; 32],
+    pub caller: Pubkey,
+    pub on_behalf_of: Pubkey,
+    pub collateral_in: u128,
+    pub collateral_out: u128,
+    pub borrow_assets: u128,
+    pub borrow_shares: u128,
+    pub repay_assets: u128,
+    pub repay_shares: u128,
+    /// False when the bundle couldn't raise LTV and the oracle was skipped
+    pub health_checked: bool,
+}
+
 // === Liquidation Events ===
 
 #[event]
@@ -152,6 +619,17 @@ pub struct Liquidation {
     pub seized_collateral: u128,
 }
 
+/// Part of a liquidation's seized collateral went to the liquidator's operator
+#[event]
+pub struct LiquidationOperatorPaid {
+    pub market_id: [u8; 32],
+    pub liquidator: Pubkey,
+    /// Operator's collateral token account
+    pub operator_collateral_account: Pubkey,
+    pub operator_share_bps: u64,
+    pub collateral: u128,
+}
+
 #[event]
 pub struct BadDebtRealized {
     pub market_id: [u8; 32],
@@ -160,8 +638,73 @@ pub struct BadDebtRealized {
     pub bad_debt_shares: u128,
 }
 
+/// Collateral vault balance (plus staked collateral) no longer matches
+/// what the market owes; positive drift is surplus
+#[event]
+pub struct CollateralDrift {
+    pub market_id: [u8; 32],
+    pub vault_balance: u64,
+    pub collateral_staked: u128,
+    pub collateral_owed: u128,
+    pub drift: i128,
+}
+
+/// Socialized bad debt pushed the supply share price under the market's
+/// floor; the market is now withdraw-only until governance clears it
+#[event]
+pub struct SharePriceFloorBreached {
+    pub market_id: [u8; 32],
+    pub supply_share_price: u128,
+    pub floor: u128,
+}
+
+/// Pending fee shares burned to rebate the liquidator that realized bad debt
+#[event]
+pub struct BadDebtRebatePaid {
+    pub market_id: [u8; 32],
+    pub liquidator: Pubkey,
+    pub fee_shares: u128,
+    pub assets: u128,
+}
+
 // === Interest Events ===
 
+/// An accrual would have grown debt past the market's cap; the excess was
+/// dropped instead of applied (likely an IRM fault)
+#[event]
+pub struct AbnormalAccrualCapped {
+    pub market_id: [u8; 32],
+    pub borrow_rate: u128,
+    pub applied_interest: u128,
+    pub capped_interest: u128,
+    pub max_growth_bps: u64,
+}
+
+/// The market's IRM failed a rate query; accrual used the cached rate
+#[event]
+pub struct IrmQueryFailed {
+    pub market_id: [u8; 32],
+    pub irm: Pubkey,
+    /// Error the query failed with
+    pub error_code: u32,
+    /// Rate applied instead (`rate_cache`)
+    pub fallback_rate: u128,
+    /// Slots in a row the IRM has now failed
+    pub failures: u8,
+}
+
+/// The IRM failed `MAX_IRM_FAILURES` slots in a row; the market is now
+/// withdraw-only until governance clears it (or switches IRM)
+#[event]
+pub struct IrmFailureWithdrawOnly {
+    pub market_id: [u8; 32],
+    pub irm: Pubkey,
+    pub failures: u8,
+}
+
+/// Heartbeat from the public `accrue_interest` instruction
+///
+/// Carries the market's lifetime totals so indexers get them without replay.
 #[event]
 pub struct InterestAccrued {
     pub market_id: [u8; 32],
@@ -169,6 +712,37 @@ pub struct InterestAccrued {
     pub fee_shares: u128,
     pub total_supply_assets: u128,
     pub total_borrow_assets: u128,
+    pub total_interest_accrued: u128,
+    pub total_fees_accrued: u128,
+    pub total_liquidated_collateral: u128,
+    pub total_bad_debt: u128,
+}
+
+/// Share prices after an accrual that changed totals (for APY history)
+///
+/// Prices are loan-token units per WAD (1e18) shares, virtual offsets included.
+#[event]
+pub struct SharePriceCheckpoint {
+    pub market_id: [u8; 32],
+    pub timestamp: i64,
+    pub supply_share_price: u128,
+    pub borrow_share_price: u128,
+}
+
+#[event]
+pub struct OracleFailureSimulated {
+    pub market_id: [u8; 32],
+    pub mode: u8,
+}
+
+#[event]
+pub struct ClockOverrideSet {
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct ClockOverrideCleared {
+    pub unix_timestamp: i64,
 }
 
 // === Fee Events ===
@@ -190,6 +764,214 @@ pub struct FlashLoan {
     pub fee: u128,
 }
 
+#[event]
+pub struct FlashLoanAllowlistModeSet {
+    pub market_id: [u8; 32],
+    pub allowlist_only: bool,
+}
+
+#[event]
+pub struct FlashLoanAllowlistUpdated {
+    pub market_id: [u8; 32],
+    pub borrower: Pubkey,
+    pub allowed: bool,
+}
+
+#[event]
+pub struct HoldingPeriodExemptionUpdated {
+    pub account: Pubkey,
+    pub exempt: bool,
+}
+
+#[event]
+pub struct FlashLoanForceUnlocked {
+    pub market_id: [u8; 32],
+    pub borrower: Pubkey,
+    pub amount: u128,
+    pub vault_balance: u128,
+}
+
+/// Loan-token surplus booked as pending protocol fees
+#[event]
+pub struct SurplusSwept {
+    pub market_id: [u8; 32],
+    pub surplus: u128,
+    pub fee_shares: u128,
+    pub total_surplus_swept: u128,
+}
+
+#[event]
+pub struct TokensRescued {
+    pub market_id: [u8; 32],
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+// === Yield Adapter Events ===
+
+#[event]
+pub struct CollateralYieldAdapterSet {
+    pub market_id: [u8; 32],
+    pub adapter: Pubkey,
+    pub max_staked_bps: u64,
+}
+
+#[event]
+pub struct CollateralStaked {
+    pub market_id: [u8; 32],
+    pub amount: u128,
+    pub total_staked: u128,
+}
+
+#[event]
+pub struct CollateralUnstaked {
+    pub market_id: [u8; 32],
+    pub caller: Pubkey,
+    pub amount: u128,
+    pub total_staked: u128,
+}
+
+#[event]
+pub struct LoanYieldAdapterSet {
+    pub market_id: [u8; 32],
+    pub adapter: Pubkey,
+    pub max_deployed_bps: u64,
+}
+
+#[event]
+pub struct LoanLiquidityDeployed {
+    pub market_id: [u8; 32],
+    pub amount: u128,
+    pub total_deployed: u128,
+}
+
+#[event]
+pub struct LoanLiquidityRecalled {
+    pub market_id: [u8; 32],
+    pub caller: Pubkey,
+    pub amount: u128,
+    /// Returned on top of `amount`, credited to suppliers
+    pub yield_assets: u128,
+    pub total_deployed: u128,
+}
+
+// === Governance Events ===
+
+#[event]
+pub struct MarketCuratorSet {
+    pub market_id: [u8; 32],
+    pub curator: Pubkey,
+}
+
+#[event]
+pub struct MarketConfigProposed {
+    pub market_id: [u8; 32],
+    pub proposer: Pubkey,
+    pub config: MarketConfigUpdate,
+}
+
+#[event]
+pub struct MarketConfigApplied {
+    pub market_id: [u8; 32],
+    pub proposer: Pubkey,
+    pub config: MarketConfigUpdate,
+}
+
+#[event]
+pub struct MarketConfigProposalCancelled {
+    pub market_id: [u8; 32],
+    pub proposer: Pubkey,
+    pub cancelled_by: Pubkey,
+}
+
+#[event]
+pub struct OracleUpdateProposed {
+    pub market_id: [u8; 32],
+    pub current_oracle: Pubkey,
+    pub new_oracle: Pubkey,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct OracleUpdated {
+    pub market_id: [u8; 32],
+    pub old_oracle: Pubkey,
+    pub new_oracle: Pubkey,
+}
+
+#[event]
+pub struct OracleUpdateCancelled {
+    pub market_id: [u8; 32],
+    pub new_oracle: Pubkey,
+}
+
+#[event]
+pub struct IrmUpdateProposed {
+    pub market_id: [u8; 32],
+    pub current_irm: Pubkey,
+    pub new_irm: Pubkey,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct IrmUpdated {
+    pub market_id: [u8; 32],
+    pub old_irm: Pubkey,
+    pub new_irm: Pubkey,
+    pub old_borrow_rate: u128,
+    pub new_borrow_rate: u128,
+}
+
+#[event]
+pub struct IrmUpdateCancelled {
+    pub market_id: [u8; 32],
+    pub new_irm: Pubkey,
+}
+
+// === Credit Line Events ===
+
+#[event]
+pub struct CreditLineSet {
+    pub market_id: [u8; 32],
+    pub supplier: Pubkey,
+    pub borrower: Pubkey,
+    pub limit: u128,
+}
+
+#[event]
+pub struct CreditDrawn {
+    pub market_id: [u8; 32],
+    pub supplier: Pubkey,
+    pub borrower: Pubkey,
+    pub receiver: Pubkey,
+    pub assets: u128,
+    pub shares: u128,
+}
+
+#[event]
+pub struct CreditRepaid {
+    pub market_id: [u8; 32],
+    pub supplier: Pubkey,
+    pub borrower: Pubkey,
+    pub repayer: Pubkey,
+    pub assets: u128,
+    pub shares: u128,
+}
+
+#[event]
+pub struct CreditLineClosed {
+    pub market_id: [u8; 32],
+    pub supplier: Pubkey,
+    pub borrower: Pubkey,
+    /// Outstanding debt written off when the line closed
+    pub written_off_assets: u128,
+    /// Supplier supply shares burned to cover it
+    pub burned_supply_shares: u128,
+    /// Part of the debt the supplier's position couldn't cover (socialized)
+    pub bad_debt_assets: u128,
+}
+
 // === Authorization Events ===
 
 #[event]
@@ -205,3 +987,85 @@ pub struct AuthorizationRevoked {
     pub authorizer: Pubkey,
     pub authorized: Pubkey,
 }
+
+// === Multi-Collateral Events ===
+
+#[event]
+pub struct McMarketCreated {
+    pub market_id: [u8; 32],
+    pub creator: Pubkey,
+    pub loan_mint: Pubkey,
+    pub irm: Pubkey,
+}
+
+#[event]
+pub struct McCollateralAdded {
+    pub market_id: [u8; 32],
+    pub index: u8,
+    pub mint: Pubkey,
+    pub oracle: Pubkey,
+    pub lltv: u64,
+}
+
+#[event]
+pub struct McSupply {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub assets: u128,
+    pub shares: u128,
+}
+
+#[event]
+pub struct McWithdraw {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub assets: u128,
+    pub shares: u128,
+}
+
+#[event]
+pub struct McCollateralSupplied {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub index: u8,
+    pub amount: u128,
+}
+
+#[event]
+pub struct McCollateralWithdrawn {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub index: u8,
+    pub amount: u128,
+}
+
+#[event]
+pub struct McBorrow {
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+    pub assets: u128,
+    pub shares: u128,
+}
+
+#[event]
+pub struct McRepay {
+    pub market_id: [u8; 32],
+    pub repayer: Pubkey,
+    pub owner: Pubkey,
+    pub assets: u128,
+    pub shares: u128,
+}
+
+#[event]
+pub struct McLiquidation {
+    pub market_id: [u8; 32],
+    pub liquidator: Pubkey,
+    pub borrower: Pubkey,
+    /// Collateral slot seized from
+    pub index: u8,
+    pub repaid_assets: u128,
+    pub repaid_shares: u128,
+    pub seized_collateral: u128,
+    /// Debt socialized once the position had no collateral left
+    pub bad_debt_assets: u128,
+}
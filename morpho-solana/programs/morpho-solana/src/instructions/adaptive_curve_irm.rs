@@ -0,0 +1,57 @@
+//! Adaptive curve IRM instructions
+//!
+//! A market created over the adaptive curve model can't accrue until its
+//! `AdaptiveCurveIrm` exists; anyone can create it, right after the market.
+//! A market being migrated onto the model needs one before the switch
+//! executes, so creation doesn't require the market to use the model yet.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::events::AdaptiveCurveIrmInitialized;
+use crate::interfaces::{AdaptiveCurveIrm, INITIAL_RATE_AT_TARGET};
+use crate::state::Market;
+use crate::time::current_clock;
+
+// ============================================================================
+// Init Adaptive Curve IRM
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct InitAdaptiveCurveIrm<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = AdaptiveCurveIrm::space(),
+        seeds = [PROGRAM_SEED_PREFIX, AdaptiveCurveIrm::SEED, &market_id],
+        bump,
+    )]
+    pub adaptive_curve_irm: Account<'info, AdaptiveCurveIrm>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Create a market's adaptive curve IRM state at the initial rate at target
+pub fn init_adaptive_curve_irm(ctx: Context<InitAdaptiveCurveIrm>, market_id: [u8; 32]) -> Result<()> {
+    let irm = &mut ctx.accounts.adaptive_curve_irm;
+    irm.bump = ctx.bumps.adaptive_curve_irm;
+    irm.market_id = market_id;
+    irm.rate_at_target = INITIAL_RATE_AT_TARGET;
+    irm.last_update = current_clock(ctx.remaining_accounts)?.unix_timestamp;
+
+    emit!(AdaptiveCurveIrmInitialized {
+        market_id,
+        adaptive_curve_irm: irm.key(),
+        rate_at_target: irm.rate_at_target,
+    });
+    Ok(())
+}
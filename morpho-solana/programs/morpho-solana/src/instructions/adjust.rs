@@ -0,0 +1,442 @@
+//! Bundled position adjustment
+//!
+//! Applies a repay, borrow, collateral deposit and collateral withdrawal to
+//! one position atomically, moving only the net amounts and health-checking
+//! once at the end. A bundle that can't raise the position's LTV (no net new
+//! debt shares, no net collateral out) skips the oracle entirely, so users can
+//! still deleverage while the feed is stale or down.
+//!
+//! CEI Pattern: Checks → Effects → Interactions
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked};
+use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::errors::MorphoError;
+use crate::events::PositionAdjusted;
+use crate::state::{ProtocolState, Market, MarketAction, Position, Authorization};
+use crate::math::{
+    checked_add, checked_sub, safe_u128_to_u64,
+    to_shares_up, to_shares_down, to_assets_up,
+    accrue_market_interest, accrue_stable_debt,
+};
+use crate::instructions::insurance::charge_insurance_premium;
+use crate::instructions::utils::check_expected_version;
+use crate::instructions::reputation::{record_repayment, sync_reputation_boost};
+use crate::instructions::yield_adapter::ensure_loan_vault_liquidity;
+use crate::interfaces::{enforce_risk_cap, ensure_position_healthy};
+use crate::time::current_clock;
+
+/// Amounts applied by `adjust_position` (0 = skip that leg)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PositionAdjustment {
+    /// Collateral deposited from the caller
+    pub collateral_in: u128,
+
+    /// Collateral withdrawn to the caller
+    pub collateral_out: u128,
+
+    /// Loan tokens borrowed to the caller
+    pub borrow_assets: u128,
+
+    /// Loan tokens repaid by the caller (capped at the position's debt)
+    pub repay_assets: u128,
+}
+
+impl PositionAdjustment {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Only repays (the one leg allowed while paused)
+    pub fn is_repay_only(&self) -> bool {
+        self.collateral_in == 0 && self.collateral_out == 0 && self.borrow_assets == 0
+    }
+
+    /// Mode-gated action of each non-empty leg
+    pub fn actions(&self) -> impl Iterator<Item = MarketAction> {
+        [
+            (self.collateral_in, MarketAction::SupplyCollateral),
+            (self.collateral_out, MarketAction::WithdrawCollateral),
+            (self.borrow_assets, MarketAction::Borrow),
+            (self.repay_assets, MarketAction::Repay),
+        ]
+        .into_iter()
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(_, action)| action)
+    }
+
+    /// Legs that take value out of the position need owner consent
+    pub fn requires_authorization(&self) -> bool {
+        self.collateral_out > 0 || self.borrow_assets > 0
+    }
+
+    /// Whether the bundle could need the oracle, before share rounding is known
+    ///
+    /// `false` guarantees `requires_health_check` will pass on-chain, so the
+    /// oracle account can be left out entirely.
+    pub fn may_need_oracle(&self) -> bool {
+        self.borrow_assets > 0 || self.collateral_out > self.collateral_in
+    }
+}
+
+/// Whether a bundle needs the oracle-backed health check
+///
+/// Skipped when the position ends debt-free, or when the bundle can't raise
+/// its LTV: debt shares don't grow and collateral doesn't shrink, so the
+/// result is no riskier than a state that already passed.
+///
+/// `debt_after` only matters as zero or not (e.g. borrow shares plus stable
+/// debt).
+pub fn requires_health_check(
+    debt_after: u128,
+    shares_minted: u128,
+    shares_burned: u128,
+    collateral_in: u128,
+    collateral_out: u128,
+) -> bool {
+    if debt_after == 0 {
+        return false;
+    }
+    shares_minted > shares_burned || collateral_out > collateral_in
+}
+
+/// Health-check an adjusted position, reading `oracle` only when needed
+///
+/// Returns whether the check ran. A bundle that can't raise LTV never
+/// touches the oracle, so a stale, mispriced or missing feed can't block it.
+/// New debt is held to `borrow_lltv` (`ProtocolState::position_borrow_lltv`).
+#[allow(clippy::too_many_arguments)]
+pub fn check_adjusted_health(
+    market: &Market,
+    position: &Position,
+    borrow_lltv: u64,
+    now: i64,
+    oracle: Option<&AccountInfo>,
+    shares_minted: u128,
+    shares_burned: u128,
+    collateral_in: u128,
+    collateral_out: u128,
+) -> Result<bool> {
+    if !requires_health_check(
+        position.borrow_shares.saturating_add(position.stable_borrow_assets),
+        shares_minted,
+        shares_burned,
+        collateral_in,
+        collateral_out,
+    ) {
+        return Ok(false);
+    }
+
+    // New debt is held to the buffered borrow LLTV, like `borrow`
+    let lltv = if shares_minted > 0 {
+        borrow_lltv
+    } else {
+        market.position_lltv(position, now)
+    };
+    ensure_position_healthy(market, position, lltv, oracle, None)
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct AdjustPosition<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    pub authorization: Option<Account<'info, Authorization>>,
+
+    /// CHECK: Oracle account, only required when the bundle can raise LTV
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        constraint = caller_collateral_account.mint == market.collateral_mint,
+    )]
+    pub caller_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = caller_loan_account.mint == market.loan_mint,
+    )]
+    pub caller_loan_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::COLLATERAL_VAULT_SEED, &market_id],
+        bump = market.collateral_vault_bump,
+    )]
+    pub collateral_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(constraint = collateral_mint.key() == market.collateral_mint @ MorphoError::InvalidMint)]
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(constraint = loan_mint.key() == market.loan_mint @ MorphoError::InvalidMint)]
+    pub loan_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Risk oracle feed, required when borrowing on a market that has one
+    pub risk_oracle: Option<UncheckedAccount<'info>>,
+}
+
+pub fn adjust_position<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AdjustPosition<'info>>,
+    market_id: [u8; 32],
+    adjustment: PositionAdjustment,
+    expected_version: Option<u16>,
+) -> Result<()> {
+    // ===== CHECKS =====
+    check_expected_version(expected_version)?;
+    require!(!adjustment.is_empty(), MorphoError::ZeroAmount);
+
+    // Repaying stays open while paused (helps users exit); every other leg
+    // follows its standalone instruction
+    if !adjustment.is_repay_only() {
+        require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    }
+    for action in adjustment.actions() {
+        ctx.accounts.market.check_action(action)?;
+    }
+
+    if adjustment.requires_authorization() {
+        validate_authorization(
+            &ctx.accounts.caller,
+            &ctx.accounts.position.owner,
+            ctx.accounts.authorization.as_ref(),
+        )?;
+    }
+
+    // Accrue interest
+    let clock = current_clock(ctx.remaining_accounts)?;
+
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+
+    let position = &mut ctx.accounts.position;
+    charge_insurance_premium(market, position, clock.unix_timestamp)?;
+    accrue_stable_debt(market, position, clock.unix_timestamp)?;
+
+    // ===== EFFECTS =====
+    // Repay first so a deleveraging bundle frees liquidity before borrowing
+    let (repay_assets, repay_shares) = if adjustment.repay_assets > 0 {
+        let s = to_shares_down(adjustment.repay_assets, market.total_borrow_assets, market.total_borrow_shares)?;
+        let s = std::cmp::min(s, position.borrow_shares);
+        let a = to_assets_up(s, market.total_borrow_assets, market.total_borrow_shares)?;
+        (a, s)
+    } else {
+        (0, 0)
+    };
+    position.borrow_shares = checked_sub(position.borrow_shares, repay_shares)?;
+    record_repayment(market, position, repay_assets, clock.unix_timestamp)?;
+    market.total_borrow_assets = checked_sub(market.total_borrow_assets, repay_assets)?;
+    market.total_borrow_shares = checked_sub(market.total_borrow_shares, repay_shares)?;
+
+    let borrow_shares = if adjustment.borrow_assets > 0 {
+        require!(
+            adjustment.borrow_assets <= market.available_liquidity(),
+            MorphoError::InsufficientLiquidity
+        );
+        let s = to_shares_up(adjustment.borrow_assets, market.total_borrow_assets, market.total_borrow_shares)?;
+        position.borrow_shares = checked_add(position.borrow_shares, s)?;
+        position.total_borrowed = checked_add(position.total_borrowed, adjustment.borrow_assets)?;
+        position.last_borrowed_at = clock.unix_timestamp;
+        market.total_borrow_assets = checked_add(market.total_borrow_assets, adjustment.borrow_assets)?;
+        market.total_borrow_shares = checked_add(market.total_borrow_shares, s)?;
+        s
+    } else {
+        0
+    };
+
+    position.collateral = checked_add(position.collateral, adjustment.collateral_in)?;
+    require!(
+        position.collateral >= adjustment.collateral_out,
+        MorphoError::InsufficientCollateral
+    );
+    position.collateral = checked_sub(position.collateral, adjustment.collateral_out)?;
+    market.total_collateral = checked_add(market.total_collateral, adjustment.collateral_in)?
+        .saturating_sub(adjustment.collateral_out);
+
+    // Health check AFTER all effects, only if the bundle can raise LTV
+    let borrow_lltv = ctx.accounts.protocol_state.position_borrow_lltv(market, position, clock.unix_timestamp);
+    let health_checked = check_adjusted_health(
+        market,
+        position,
+        borrow_lltv,
+        clock.unix_timestamp,
+        ctx.accounts.oracle.as_ref().map(|o| o.as_ref()),
+        borrow_shares,
+        repay_shares,
+        adjustment.collateral_in,
+        adjustment.collateral_out,
+    )?;
+    sync_reputation_boost(market, position, clock.unix_timestamp);
+
+    // Only net new debt answers to the risk cap, so deleveraging never trips it
+    if borrow_shares > repay_shares {
+        enforce_risk_cap(
+            market,
+            ctx.accounts.risk_oracle.as_ref().map(|o| o.as_ref()),
+            clock.unix_timestamp,
+        )?;
+    }
+
+    // ===== INTERACTIONS =====
+    let bump = market.bump;
+    let seeds = &[
+        PROGRAM_SEED_PREFIX,
+        Market::SEED,
+        market_id.as_ref(),
+        &[bump],
+    ];
+
+    // Loan token: only the net amount moves
+    if repay_assets > adjustment.borrow_assets {
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.caller_loan_account.to_account_info(),
+                    to: ctx.accounts.loan_vault.to_account_info(),
+                    authority: ctx.accounts.caller.to_account_info(),
+                    mint: ctx.accounts.loan_mint.to_account_info(),
+                },
+            ),
+            safe_u128_to_u64(repay_assets - adjustment.borrow_assets)?,
+            ctx.accounts.loan_mint.decimals,
+        )?;
+    } else if adjustment.borrow_assets > repay_assets {
+        let net_out = adjustment.borrow_assets - repay_assets;
+
+        // Recall deployed liquidity if the vault alone can't cover the transfer
+        // (remaining accounts: [adapter_program, ...adapter accounts])
+        ensure_loan_vault_liquidity(
+            ctx.accounts.caller.key(),
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.loan_vault,
+            &ctx.accounts.loan_mint.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.remaining_accounts,
+            net_out,
+        )?;
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.loan_vault.to_account_info(),
+                    to: ctx.accounts.caller_loan_account.to_account_info(),
+                    authority: ctx.accounts.market.to_account_info(),
+                    mint: ctx.accounts.loan_mint.to_account_info(),
+                },
+                &[seeds],
+            ),
+            safe_u128_to_u64(net_out)?,
+            ctx.accounts.loan_mint.decimals,
+        )?;
+    }
+
+    // Collateral: only the net amount moves
+    if adjustment.collateral_in > adjustment.collateral_out {
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.caller_collateral_account.to_account_info(),
+                    to: ctx.accounts.collateral_vault.to_account_info(),
+                    authority: ctx.accounts.caller.to_account_info(),
+                    mint: ctx.accounts.collateral_mint.to_account_info(),
+                },
+            ),
+            safe_u128_to_u64(adjustment.collateral_in - adjustment.collateral_out)?,
+            ctx.accounts.collateral_mint.decimals,
+        )?;
+    } else if adjustment.collateral_out > adjustment.collateral_in {
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.collateral_vault.to_account_info(),
+                    to: ctx.accounts.caller_collateral_account.to_account_info(),
+                    authority: ctx.accounts.market.to_account_info(),
+                    mint: ctx.accounts.collateral_mint.to_account_info(),
+                },
+                &[seeds],
+            ),
+            safe_u128_to_u64(adjustment.collateral_out - adjustment.collateral_in)?,
+            ctx.accounts.collateral_mint.decimals,
+        )?;
+    }
+
+    emit!(PositionAdjusted {
+        market_id,
+        caller: ctx.accounts.caller.key(),
+        on_behalf_of: ctx.accounts.position.owner,
+        collateral_in: adjustment.collateral_in,
+        collateral_out: adjustment.collateral_out,
+        borrow_assets: adjustment.borrow_assets,
+        borrow_shares,
+        repay_assets,
+        repay_shares,
+        health_checked,
+    });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.position);
+    crate::telemetry!(
+        "adjust_position",
+        market_id,
+        borrowed = adjustment.borrow_assets,
+        repaid = repay_assets,
+        health_checked = health_checked,
+    );
+
+    Ok(())
+}
+
+/// Validate authorization for delegated operations
+fn validate_authorization(
+    caller: &Signer,
+    owner: &Pubkey,
+    authorization: Option<&Account<Authorization>>,
+) -> Result<()> {
+    if caller.key() == *owner {
+        return Ok(());
+    }
+
+    let current_time = Clock::get()?.unix_timestamp;
+
+    if let Some(auth) = authorization {
+        if auth.authorizer == *owner
+            && auth.authorized == caller.key()
+            && auth.is_valid(current_time)
+        {
+            return Ok(());
+        }
+    }
+
+    Err(MorphoError::Unauthorized.into())
+}
@@ -2,15 +2,32 @@
 //! 
 //! - Initialize protocol
 //! - Two-step ownership transfer
-//! - Pause controls
-//! - Enable LLTVs and IRMs
+//! - Pause controls (global, per-market, flash loans only)
+//! - Enable LLTVs, IRMs and yield adapters
+//! - Bound the LLTVs each loan token's markets may use
 //! - Set fees
+//! - Set borrow LLTV buffers (per market, and for protocol-owned positions)
+//! - Set risk oracles (max-safe-debt borrow caps)
+//! - Set per-market oracle age limits and fallback oracles
+//! - Force-unlock stuck flash loans
+//! - Rescue tokens sent to a market PDA by mistake
+//! - Atomic batches of the above (for multisig proposals)
+//! - Migrate the protocol state to the dynamic whitelist layout
+//! - Migrate markets and positions created under older, shorter layouts
 
 use anchor_lang::prelude::*;
-use crate::constants::{PROGRAM_SEED_PREFIX, BPS, MAX_FEE};
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+use crate::constants::{
+    PROGRAM_SEED_PREFIX, BPS, MAX_FEE, MAX_ADMIN_BATCH_ACTIONS, MAX_RISK_CAP_BUFFER_BPS,
+    MIN_OWNER_RECOVERY_DELAY_DAYS, SECONDS_PER_DAY, MAX_INTEREST_PAUSE_DURATION,
+    MAX_SUPPLY_HOLDING_PERIOD, MAX_BAD_DEBT_REBATE_BPS, MIN_ACCRUAL_GROWTH_CAP_BPS,
+};
 use crate::errors::MorphoError;
 use crate::events::*;
-use crate::state::{ProtocolState, Market};
+use crate::interfaces::{is_adaptive_curve_irm, MAX_ORACLE_AGE_SECS, MAX_ORACLE_CONF_BPS};
+use crate::math::accrue_market_interest;
+use crate::state::{ProtocolState, ProtocolStateV1, Market, Position, LltvBounds, TokenBadge, TokenTier, is_clock_override};
+use crate::time::current_clock;
 
 // ============================================================================
 // Initialize
@@ -44,9 +61,17 @@ pub fn initialize(
     state.pending_owner = Pubkey::default();
     state.fee_recipient = fee_recipient;
     state.paused = false;
-    state.lltv_count = 0;
-    state.irm_count = 0;
+    state.enabled_lltvs = Vec::new();
+    state.enabled_irms = Vec::new();
     state.market_count = 0;
+    state.closed_market_count = 0;
+    state.recovery_key = Pubkey::default();
+    state.last_owner_heartbeat = Clock::get()?.unix_timestamp;
+    state.owner_recovery_delay = 0;
+    state.protocol_borrow_lltv_buffer = 0;
+    state.paused_market_count = 0;
+    state.yield_adapter_count = 0;
+    state.flash_loans_disabled = false;
 
     emit!(ProtocolInitialized { owner, fee_recipient });
     Ok(())
@@ -98,6 +123,7 @@ pub fn accept_ownership(ctx: Context<AcceptOwnership>) -> Result<()> {
 
     state.owner = state.pending_owner;
     state.pending_owner = Pubkey::default();
+    state.last_owner_heartbeat = Clock::get()?.unix_timestamp;
 
     emit!(OwnershipTransferred {
         previous_owner,
@@ -106,6 +132,118 @@ pub fn accept_ownership(ctx: Context<AcceptOwnership>) -> Result<()> {
     Ok(())
 }
 
+// ============================================================================
+// Owner Recovery (Dead-Man Switch)
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Heartbeat<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+}
+
+/// Prove the owner key is alive, restarting the recovery delay
+///
+/// Also cancels a pending recovery claim.
+pub fn heartbeat(ctx: Context<Heartbeat>) -> Result<()> {
+    let state = &mut ctx.accounts.protocol_state;
+    let now = Clock::get()?.unix_timestamp;
+    state.last_owner_heartbeat = now;
+    if state.recovery_key != Pubkey::default() && state.pending_owner == state.recovery_key {
+        state.pending_owner = Pubkey::default();
+    }
+
+    emit!(OwnerHeartbeat { owner: state.owner, timestamp: now });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetRecoveryKey<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+}
+
+/// Designate the recovery key and the owner silence that unlocks it
+///
+/// `Pubkey::default()` disables recovery. Counts as a heartbeat.
+pub fn set_recovery_key(
+    ctx: Context<SetRecoveryKey>,
+    recovery_key: Pubkey,
+    delay_days: u64,
+) -> Result<()> {
+    let disabled = recovery_key == Pubkey::default();
+    if !disabled {
+        require!(recovery_key != ctx.accounts.owner.key(), MorphoError::InvalidOwner);
+        require!(delay_days >= MIN_OWNER_RECOVERY_DELAY_DAYS, MorphoError::InvalidInput);
+    }
+    let delay_days = if disabled { 0 } else { delay_days };
+    let delay = i64::try_from(delay_days)
+        .ok()
+        .and_then(|days| days.checked_mul(SECONDS_PER_DAY))
+        .ok_or(MorphoError::InvalidInput)?;
+
+    let state = &mut ctx.accounts.protocol_state;
+    state.recovery_key = recovery_key;
+    state.owner_recovery_delay = delay;
+    state.last_owner_heartbeat = Clock::get()?.unix_timestamp;
+
+    emit!(RecoveryKeySet { recovery_key, delay_days });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClaimOwnershipRecovery<'info> {
+    pub recovery_key: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.recovery_key != Pubkey::default() @ MorphoError::RecoveryKeyNotSet,
+        constraint = protocol_state.recovery_key == recovery_key.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+}
+
+/// Start the two-step transfer to the recovery key after the owner went silent
+///
+/// The recovery key completes it with `accept_ownership`; an owner
+/// heartbeat in between cancels the claim.
+pub fn claim_ownership_recovery(ctx: Context<ClaimOwnershipRecovery>) -> Result<()> {
+    let state = &mut ctx.accounts.protocol_state;
+    require!(
+        state.can_recover_ownership(Clock::get()?.unix_timestamp),
+        MorphoError::OwnerStillActive
+    );
+
+    state.pending_owner = state.recovery_key;
+
+    emit!(OwnershipRecoveryClaimed {
+        silent_owner: state.owner,
+        recovery_key: state.recovery_key,
+        last_owner_heartbeat: state.last_owner_heartbeat,
+    });
+    emit!(OwnershipTransferStarted {
+        current_owner: state.owner,
+        pending_owner: state.recovery_key,
+    });
+    Ok(())
+}
+
 // ============================================================================
 // Fee Recipient
 // ============================================================================
@@ -157,12 +295,35 @@ pub fn set_protocol_paused(ctx: Context<SetProtocolPaused>, paused: bool) -> Res
     Ok(())
 }
 
+#[derive(Accounts)]
+pub struct SetFlashLoansEnabled<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+}
+
+/// Turn flash loans on or off without pausing lending
+///
+/// In-progress two-step loans can still be settled with flash_loan_end.
+pub fn set_flash_loans_enabled(ctx: Context<SetFlashLoansEnabled>, enabled: bool) -> Result<()> {
+    ctx.accounts.protocol_state.flash_loans_disabled = !enabled;
+    emit!(FlashLoansEnabledSet { enabled });
+    Ok(())
+}
+
 #[derive(Accounts)]
 #[instruction(market_id: [u8; 32])]
 pub struct SetMarketPaused<'info> {
     pub owner: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
         bump = protocol_state.bump,
         constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
@@ -182,7 +343,9 @@ pub fn set_market_paused(
     market_id: [u8; 32],
     paused: bool,
 ) -> Result<()> {
-    ctx.accounts.market.paused = paused;
+    let market = &mut ctx.accounts.market;
+    ctx.accounts.protocol_state.track_market_pause(market.paused, paused);
+    market.paused = paused;
     emit!(MarketPausedSet { market_id, paused });
     Ok(())
 }
@@ -193,6 +356,7 @@ pub fn set_market_paused(
 
 #[derive(Accounts)]
 pub struct EnableLltv<'info> {
+    #[account(mut)]
     pub owner: Signer<'info>,
 
     #[account(
@@ -200,8 +364,16 @@ pub struct EnableLltv<'info> {
         seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
         bump = protocol_state.bump,
         constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+        realloc = ProtocolState::space_for(
+            protocol_state.enabled_lltvs.len() + 1,
+            protocol_state.enabled_irms.len(),
+        ),
+        realloc::payer = owner,
+        realloc::zero = false,
     )]
     pub protocol_state: Account<'info, ProtocolState>,
+
+    pub system_program: Program<'info, System>,
 }
 
 pub fn enable_lltv(ctx: Context<EnableLltv>, lltv: u64) -> Result<()> {
@@ -213,6 +385,7 @@ pub fn enable_lltv(ctx: Context<EnableLltv>, lltv: u64) -> Result<()> {
 
 #[derive(Accounts)]
 pub struct EnableIrm<'info> {
+    #[account(mut)]
     pub owner: Signer<'info>,
 
     #[account(
@@ -220,8 +393,16 @@ pub struct EnableIrm<'info> {
         seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
         bump = protocol_state.bump,
         constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+        realloc = ProtocolState::space_for(
+            protocol_state.enabled_lltvs.len(),
+            protocol_state.enabled_irms.len() + 1,
+        ),
+        realloc::payer = owner,
+        realloc::zero = false,
     )]
     pub protocol_state: Account<'info, ProtocolState>,
+
+    pub system_program: Program<'info, System>,
 }
 
 pub fn enable_irm(ctx: Context<EnableIrm>, irm: Pubkey) -> Result<()> {
@@ -230,6 +411,206 @@ pub fn enable_irm(ctx: Context<EnableIrm>, irm: Pubkey) -> Result<()> {
     Ok(())
 }
 
+// ============================================================================
+// LLTV Bounds
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(loan_mint: Pubkey)]
+pub struct SetLltvBounds<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = LltvBounds::space(),
+        seeds = [PROGRAM_SEED_PREFIX, LltvBounds::SEED, loan_mint.as_ref()],
+        bump,
+    )]
+    pub lltv_bounds: Account<'info, LltvBounds>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Restrict new markets lending `loan_mint` to LLTVs in `min_lltv..=max_lltv`
+///
+/// Existing markets are unaffected.
+pub fn set_lltv_bounds(
+    ctx: Context<SetLltvBounds>,
+    loan_mint: Pubkey,
+    min_lltv: u64,
+    max_lltv: u64,
+) -> Result<()> {
+    require!(min_lltv <= max_lltv && max_lltv <= BPS, MorphoError::InvalidLltv);
+
+    let bounds = &mut ctx.accounts.lltv_bounds;
+    bounds.bump = ctx.bumps.lltv_bounds;
+    bounds.loan_mint = loan_mint;
+    bounds.min_lltv = min_lltv;
+    bounds.max_lltv = max_lltv;
+
+    emit!(LltvBoundsSet { loan_mint, min_lltv, max_lltv });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(loan_mint: Pubkey)]
+pub struct ClearLltvBounds<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PROGRAM_SEED_PREFIX, LltvBounds::SEED, loan_mint.as_ref()],
+        bump = lltv_bounds.bump,
+    )]
+    pub lltv_bounds: Account<'info, LltvBounds>,
+}
+
+/// Drop a loan token's bounds so any whitelisted LLTV is allowed again
+pub fn clear_lltv_bounds(_ctx: Context<ClearLltvBounds>, loan_mint: Pubkey) -> Result<()> {
+    emit!(LltvBoundsSet { loan_mint, min_lltv: 0, max_lltv: BPS });
+    Ok(())
+}
+
+// ============================================================================
+// Token Badges
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct SetTokenBadge<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = TokenBadge::space(),
+        seeds = [PROGRAM_SEED_PREFIX, TokenBadge::SEED, mint.as_ref()],
+        bump,
+    )]
+    pub token_badge: Account<'info, TokenBadge>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grade `mint`, capping the LLTV of new markets using it as collateral
+///
+/// Existing markets are unaffected.
+pub fn set_token_badge(ctx: Context<SetTokenBadge>, mint: Pubkey, tier: TokenTier) -> Result<()> {
+    let badge = &mut ctx.accounts.token_badge;
+    badge.bump = ctx.bumps.token_badge;
+    badge.mint = mint;
+    badge.tier = tier;
+
+    emit!(TokenBadgeSet { mint, tier });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct ClearTokenBadge<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PROGRAM_SEED_PREFIX, TokenBadge::SEED, mint.as_ref()],
+        bump = token_badge.bump,
+    )]
+    pub token_badge: Account<'info, TokenBadge>,
+}
+
+/// Drop a mint's badge, so it's held to tier C like any ungraded mint
+pub fn clear_token_badge(_ctx: Context<ClearTokenBadge>, mint: Pubkey) -> Result<()> {
+    emit!(TokenBadgeSet { mint, tier: TokenTier::C });
+    Ok(())
+}
+
+// ============================================================================
+// Enable Yield Adapter
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct EnableYieldAdapter<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+}
+
+pub fn enable_yield_adapter(ctx: Context<EnableYieldAdapter>, adapter: Pubkey) -> Result<()> {
+    ctx.accounts.protocol_state.add_yield_adapter(adapter)?;
+    emit!(YieldAdapterEnabled { adapter });
+    Ok(())
+}
+
+// ============================================================================
+// Set Oracle Program
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct SetOracleProgram<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+}
+
+/// Allowlist the external oracle program new markets may price through
+///
+/// `Pubkey::default()` clears it. Markets keep the program they were
+/// created with, so changing it never reprices a live market.
+pub fn set_oracle_program(ctx: Context<SetOracleProgram>, oracle_program: Pubkey) -> Result<()> {
+    require!(oracle_program != crate::ID, MorphoError::InvalidOracle);
+
+    ctx.accounts.protocol_state.oracle_program = oracle_program;
+    emit!(OracleProgramSet { oracle_program });
+    Ok(())
+}
+
 // ============================================================================
 // Set Fee
 // ============================================================================
@@ -256,7 +637,1126 @@ pub struct SetFee<'info> {
 
 pub fn set_fee(ctx: Context<SetFee>, market_id: [u8; 32], fee: u64) -> Result<()> {
     require!(fee <= MAX_FEE, MorphoError::FeeTooHigh);
+
+    // Interest up to now is charged at the old fee
+    accrue_market_interest(&mut ctx.accounts.market, &current_clock(ctx.remaining_accounts)?, ctx.remaining_accounts)?;
     ctx.accounts.market.fee = fee;
     emit!(FeeSet { market_id, fee });
     Ok(())
 }
+
+// ============================================================================
+// Set Interest Pause
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetInterestPause<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Zero the market's borrow rate for `duration` seconds (0 lifts the pause)
+///
+/// Operations carry on as normal; the pause simply expires. Used while an
+/// IRM's behaviour is being audited or disputed.
+pub fn set_interest_pause(
+    ctx: Context<SetInterestPause>,
+    market_id: [u8; 32],
+    duration: i64,
+) -> Result<()> {
+    require!(
+        (0..=MAX_INTEREST_PAUSE_DURATION).contains(&duration),
+        MorphoError::InvalidInput
+    );
+
+    // Interest up to now is charged at the current rate
+    let clock = current_clock(ctx.remaining_accounts)?;
+    accrue_market_interest(&mut ctx.accounts.market, &clock, ctx.remaining_accounts)?;
+    let paused_until = clock.unix_timestamp + duration;
+    ctx.accounts.market.interest_paused_until = paused_until;
+    ctx.accounts.market.interest_paused_from = clock.unix_timestamp;
+
+    emit!(InterestPauseSet { market_id, paused_until });
+    Ok(())
+}
+
+// ============================================================================
+// Set Supply Holding Period
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetSupplyHoldingPeriod<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Lock newly supplied shares for `period` seconds (0 turns it off)
+///
+/// Shares already in positions are unaffected; only later supplies wait.
+pub fn set_supply_holding_period(
+    ctx: Context<SetSupplyHoldingPeriod>,
+    market_id: [u8; 32],
+    period: i64,
+) -> Result<()> {
+    require!(
+        (0..=MAX_SUPPLY_HOLDING_PERIOD).contains(&period),
+        MorphoError::InvalidInput
+    );
+
+    ctx.accounts.market.min_supply_holding_period = period;
+    emit!(SupplyHoldingPeriodSet { market_id, period });
+    Ok(())
+}
+
+// ============================================================================
+// Set Bad Debt Rebate
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetBadDebtRebate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Rebate `rebate_bps` of realized bad debt to the liquidator that
+/// triggers socialization (0 turns it off)
+///
+/// Paid out of the market's pending fee shares, so it never costs
+/// suppliers more than the bad debt itself.
+pub fn set_bad_debt_rebate(
+    ctx: Context<SetBadDebtRebate>,
+    market_id: [u8; 32],
+    rebate_bps: u16,
+) -> Result<()> {
+    require!(rebate_bps <= MAX_BAD_DEBT_REBATE_BPS, MorphoError::InvalidInput);
+
+    ctx.accounts.market.bad_debt_rebate_bps = rebate_bps;
+    emit!(BadDebtRebateSet { market_id, rebate_bps });
+    Ok(())
+}
+
+// ============================================================================
+// Set Supply Only
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetSupplyOnly<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Turn a market into (or back from) a supply-only escrow
+///
+/// While set, every borrow path and flash loans are refused and the rate is
+/// zero. Only a market with no debt outstanding can switch over, so no
+/// borrower is left holding a loan that stops accruing.
+pub fn set_supply_only(
+    ctx: Context<SetSupplyOnly>,
+    market_id: [u8; 32],
+    supply_only: bool,
+) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    if supply_only {
+        require!(market.total_debt() == 0, MorphoError::MarketNotEmpty);
+        require!(market.flash_loan_lock == 0, MorphoError::MarketNotEmpty);
+    }
+
+    market.supply_only = supply_only;
+    emit!(SupplyOnlySet { market_id, supply_only });
+    Ok(())
+}
+
+// ============================================================================
+// Set Max Accrual Growth
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetMaxAccrualGrowth<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Cap how much a single accrual may grow the market's debt (0 turns it off)
+///
+/// Accrues first, so the new cap only applies from now on.
+pub fn set_max_accrual_growth(
+    ctx: Context<SetMaxAccrualGrowth>,
+    market_id: [u8; 32],
+    max_growth_bps: u64,
+) -> Result<()> {
+    require!(
+        max_growth_bps == 0 || max_growth_bps >= MIN_ACCRUAL_GROWTH_CAP_BPS,
+        MorphoError::InvalidInput
+    );
+
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &current_clock(ctx.remaining_accounts)?, ctx.remaining_accounts)?;
+    market.max_accrual_growth_bps = max_growth_bps;
+    emit!(MaxAccrualGrowthSet { market_id, max_growth_bps });
+    Ok(())
+}
+
+// ============================================================================
+// Set Share Price Floor
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetSharePriceFloor<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Set the supply share price below which bad debt makes the market
+/// withdraw-only (0 turns it off)
+///
+/// The floor must sit at or below the current price, so setting it can't
+/// trip the breaker by itself.
+pub fn set_share_price_floor(
+    ctx: Context<SetSharePriceFloor>,
+    market_id: [u8; 32],
+    floor: u128,
+) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &current_clock(ctx.remaining_accounts)?, ctx.remaining_accounts)?;
+    require!(floor <= market.supply_share_price()?, MorphoError::InvalidInput);
+
+    market.share_price_floor = floor;
+    emit!(SharePriceFloorSet { market_id, floor });
+    Ok(())
+}
+
+// ============================================================================
+// Set Withdraw Only
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetWithdrawOnly<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Put a market into (or take it out of) withdraw-only mode
+///
+/// Clearing it is how governance reopens a market after a share price
+/// floor breach; lower or turn off the floor first or the next
+/// socialization trips it again.
+pub fn set_withdraw_only(
+    ctx: Context<SetWithdrawOnly>,
+    market_id: [u8; 32],
+    withdraw_only: bool,
+) -> Result<()> {
+    ctx.accounts.market.withdraw_only = withdraw_only;
+    emit!(WithdrawOnlySet { market_id, withdraw_only });
+    Ok(())
+}
+
+// ============================================================================
+// Set Max Oracle Age
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetMaxOracleAge<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Set how old (in seconds, by the feed's own timestamp) a market's oracle
+/// price may be (0 = slot heartbeat only)
+///
+/// A stablecoin market can afford to wait out a slow feed; a long-tail
+/// collateral usually can't.
+pub fn set_max_oracle_age(
+    ctx: Context<SetMaxOracleAge>,
+    market_id: [u8; 32],
+    max_oracle_age_secs: u64,
+) -> Result<()> {
+    require!(max_oracle_age_secs <= MAX_ORACLE_AGE_SECS, MorphoError::InvalidOracleMaxAge);
+
+    ctx.accounts.market.max_oracle_age_secs = max_oracle_age_secs;
+    emit!(MaxOracleAgeSet { market_id, max_oracle_age_secs });
+    Ok(())
+}
+
+// ============================================================================
+// Set Oracle Confidence Bound
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetOracleConfBound<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Set the widest confidence interval (bps of the price) a market accepts
+/// from a Pyth oracle (0 = DEFAULT_ORACLE_CONF_BPS)
+///
+/// Applies to the fallback oracle too. Other feed types carry no
+/// confidence and ignore it.
+pub fn set_oracle_conf_bound(
+    ctx: Context<SetOracleConfBound>,
+    market_id: [u8; 32],
+    max_oracle_conf_bps: u64,
+) -> Result<()> {
+    require!(max_oracle_conf_bps <= MAX_ORACLE_CONF_BPS, MorphoError::InvalidOracleConfBound);
+
+    ctx.accounts.market.max_oracle_conf_bps = max_oracle_conf_bps;
+    emit!(OracleConfBoundSet { market_id, max_oracle_conf_bps });
+    Ok(())
+}
+
+// ============================================================================
+// Set Switchboard Normalization
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetSwitchboardNormalization<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Choose whether a market's Switchboard feeds publish whole-token prices
+/// (prescaled with its token decimals) or already prescaled ones
+///
+/// Applies to the fallback oracle too. Chainlink and Pyth answers are
+/// always whole-token prices and ignore it.
+pub fn set_switchboard_normalization(
+    ctx: Context<SetSwitchboardNormalization>,
+    market_id: [u8; 32],
+    normalize: bool,
+) -> Result<()> {
+    ctx.accounts.market.normalize_switchboard = normalize;
+    emit!(SwitchboardNormalizationSet { market_id, normalize });
+    Ok(())
+}
+
+// ============================================================================
+// Set Fallback Oracle
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetFallbackOracle<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Set the feed a market falls back to when its oracle fails
+///
+/// `Pubkey::default()` removes it. The fallback is held to the same
+/// heartbeat, age limit and bounds as the primary. The market's
+/// `PriceRelay` can serve as its fallback (see `set_price_relayer`).
+pub fn set_fallback_oracle(
+    ctx: Context<SetFallbackOracle>,
+    market_id: [u8; 32],
+    fallback_oracle: Pubkey,
+) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    require!(fallback_oracle != market.oracle, MorphoError::InvalidFallbackOracle);
+
+    market.fallback_oracle = fallback_oracle;
+    emit!(FallbackOracleSet { market_id, fallback_oracle });
+    Ok(())
+}
+
+// ============================================================================
+// Set Borrow LLTV Buffer
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetBorrowLltvBuffer<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Set the share of LLTV new borrows may reach (0 disables the buffer)
+pub fn set_borrow_lltv_buffer(
+    ctx: Context<SetBorrowLltvBuffer>,
+    market_id: [u8; 32],
+    buffer: u64,
+) -> Result<()> {
+    require!(buffer <= BPS, MorphoError::InvalidInput);
+    ctx.accounts.market.borrow_lltv_buffer = buffer;
+    emit!(BorrowLltvBufferSet { market_id, buffer });
+    Ok(())
+}
+
+// ============================================================================
+// Set Protocol Borrow LLTV Buffer
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct SetProtocolBorrowLltvBuffer<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+}
+
+/// Set the share of the borrow LLTV protocol-owned positions may reach
+/// in every market (0 disables the extra buffer)
+pub fn set_protocol_borrow_lltv_buffer(
+    ctx: Context<SetProtocolBorrowLltvBuffer>,
+    buffer: u64,
+) -> Result<()> {
+    require!(buffer <= BPS, MorphoError::InvalidInput);
+    ctx.accounts.protocol_state.protocol_borrow_lltv_buffer = buffer;
+    emit!(ProtocolBorrowLltvBufferSet { buffer });
+    Ok(())
+}
+
+// ============================================================================
+// Set Risk Oracle
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetRiskOracle<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Set the market's risk oracle and the headroom borrows get over its figure
+///
+/// `Pubkey::default()` removes the cap. Raising `buffer_bps` is how the owner
+/// overrides a figure they consider too conservative.
+pub fn set_risk_oracle(
+    ctx: Context<SetRiskOracle>,
+    market_id: [u8; 32],
+    risk_oracle: Pubkey,
+    buffer_bps: u64,
+) -> Result<()> {
+    require!(buffer_bps <= MAX_RISK_CAP_BUFFER_BPS, MorphoError::InvalidInput);
+    let market = &mut ctx.accounts.market;
+    market.risk_oracle = risk_oracle;
+    market.risk_cap_buffer_bps = buffer_bps;
+    emit!(RiskOracleSet { market_id, risk_oracle, buffer_bps });
+    Ok(())
+}
+
+// ============================================================================
+// Force Unlock Flash Loan
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct ForceUnlockFlashLoan<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+/// Clear a flash loan lock left behind by a start without a matching end
+///
+/// Only allowed once the vault again holds at least the market's accounted
+/// idle liquidity (deployed funds sit in the adapter, not the vault), i.e.
+/// the principal is back and unlocking can't hide a shortfall.
+pub fn force_unlock_flash_loan(ctx: Context<ForceUnlockFlashLoan>, market_id: [u8; 32]) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    require!(market.is_flash_loan_active(), MorphoError::FlashLoanCallbackFailed);
+
+    let vault_balance = ctx.accounts.loan_vault.amount as u128;
+    require!(
+        vault_balance >= market.idle_liquidity(),
+        MorphoError::FlashLoanVaultShortfall
+    );
+
+    let borrower = market.flash_loan_borrower;
+    let amount = market.flash_loan_amount;
+    market.unlock_flash_loan();
+
+    emit!(FlashLoanForceUnlocked {
+        market_id,
+        borrower,
+        amount,
+        vault_balance,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Sweep Surplus
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SweepSurplus<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+/// Book the loan vault's surplus over the market's accounted liquidity
+/// (see `Market::loan_surplus`) as pending protocol fees
+///
+/// No tokens move: the surplus joins supply and the fee recipient claims
+/// it as shares with `claim_fees`. A no-op when there's nothing to sweep.
+pub fn sweep_surplus(ctx: Context<SweepSurplus>, market_id: [u8; 32]) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    // Mid-flash-loan the vault is short by the principal
+    require!(!market.is_flash_loan_active(), MorphoError::FlashLoanInProgress);
+
+    // Fee shares are minted at the current share price
+    accrue_market_interest(market, &current_clock(ctx.remaining_accounts)?, ctx.remaining_accounts)?;
+    let surplus = market.loan_surplus(ctx.accounts.loan_vault.amount)?;
+    if surplus == 0 {
+        return Ok(());
+    }
+
+    let fee_shares = market.sweep_surplus(surplus)?;
+    emit!(SurplusSwept {
+        market_id,
+        surplus,
+        fee_shares,
+        total_surplus_swept: market.total_surplus_swept,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Rescue Tokens
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct RescueTokens<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// Token account owned by the market PDA; never one of the market's mints,
+    /// so the vaults can't be touched
+    #[account(
+        mut,
+        token::authority = market,
+        constraint = source.mint != market.collateral_mint
+            && source.mint != market.loan_mint @ MorphoError::InvalidMint,
+    )]
+    pub source: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = mint)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(constraint = mint.key() == source.mint @ MorphoError::InvalidMint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Sweep everything in `source` to `destination`
+///
+/// Recovers tokens users sent to a market's address (e.g. to an ATA of the
+/// market PDA). The market's own collateral and loan tokens are never
+/// rescuable, whatever account holds them.
+pub fn rescue_tokens(ctx: Context<RescueTokens>, market_id: [u8; 32]) -> Result<()> {
+    let amount = ctx.accounts.source.amount;
+    require!(amount > 0, MorphoError::ZeroAmount);
+
+    let bump = ctx.accounts.market.bump;
+    let seeds = &[
+        PROGRAM_SEED_PREFIX,
+        Market::SEED,
+        market_id.as_ref(),
+        &[bump],
+    ];
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.source.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.market.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )?;
+
+    emit!(TokensRescued {
+        market_id,
+        mint: ctx.accounts.mint.key(),
+        destination: ctx.accounts.destination.key(),
+        amount,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Admin Batch
+// ============================================================================
+
+/// A single sub-action of `admin_batch`
+///
+/// Each variant behaves exactly like the standalone instruction of the same name.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum AdminAction {
+    EnableLltv { lltv: u64 },
+    EnableIrm { irm: Pubkey },
+    SetProtocolPaused { paused: bool },
+    SetFlashLoansEnabled { enabled: bool },
+    SetMarketPaused { market_id: [u8; 32], paused: bool },
+    SetFee { market_id: [u8; 32], fee: u64 },
+}
+
+#[derive(Accounts)]
+pub struct AdminBatch<'info> {
+    /// Pays for any whitelist growth
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    pub system_program: Program<'info, System>,
+    // remaining_accounts: every (writable) Market touched by a market action,
+    // plus the IRM program or adaptive curve state of any such market whose
+    // fee is set
+}
+
+/// Apply several admin actions atomically
+///
+/// Lets a multisig push a coordinated parameter update as one proposal:
+/// either every action applies or the whole transaction reverts.
+pub fn admin_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AdminBatch<'info>>,
+    actions: Vec<AdminAction>,
+) -> Result<()> {
+    require!(
+        !actions.is_empty() && actions.len() <= MAX_ADMIN_BATCH_ACTIONS,
+        MorphoError::InvalidInput
+    );
+
+    // Load every market once up front so repeated actions on the same
+    // market see each other's writes
+    let mut markets = ctx
+        .remaining_accounts
+        .iter()
+        .filter(|info| {
+            !info.executable
+                && !is_adaptive_curve_irm(info).unwrap_or(false)
+                && !is_clock_override(info).unwrap_or(false)
+                && !info.is_signer
+        })
+        .map(|info| {
+            require!(info.is_writable, MorphoError::InvalidInput);
+            Account::<Market>::try_from(info)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let state = &mut ctx.accounts.protocol_state;
+    for action in actions {
+        match action {
+            AdminAction::EnableLltv { lltv } => {
+                require!(lltv > 0 && lltv <= BPS, MorphoError::InvalidLltv);
+                state.add_lltv(lltv)?;
+                emit!(LltvEnabled { lltv });
+            }
+            AdminAction::EnableIrm { irm } => {
+                state.add_irm(irm)?;
+                emit!(IrmEnabled { irm });
+            }
+            AdminAction::SetProtocolPaused { paused } => {
+                state.paused = paused;
+                emit!(ProtocolPausedSet { paused });
+            }
+            AdminAction::SetFlashLoansEnabled { enabled } => {
+                state.flash_loans_disabled = !enabled;
+                emit!(FlashLoansEnabledSet { enabled });
+            }
+            AdminAction::SetMarketPaused { market_id, paused } => {
+                let market = find_batch_market(&mut markets, &market_id)?;
+                state.track_market_pause(market.paused, paused);
+                market.paused = paused;
+                emit!(MarketPausedSet { market_id, paused });
+            }
+            AdminAction::SetFee { market_id, fee } => {
+                require!(fee <= MAX_FEE, MorphoError::FeeTooHigh);
+                let market = find_batch_market(&mut markets, &market_id)?;
+                accrue_market_interest(market, &current_clock(ctx.remaining_accounts)?, ctx.remaining_accounts)?;
+                market.fee = fee;
+                emit!(FeeSet { market_id, fee });
+            }
+        }
+    }
+
+    // Make room for any LLTVs/IRMs added above before Anchor writes the state back
+    let new_len = state.current_space();
+    resize_account(
+        &state.to_account_info(),
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        new_len,
+    )?;
+
+    for market in &markets {
+        market.exit(&crate::ID)?;
+    }
+    Ok(())
+}
+
+fn find_batch_market<'a, 'info>(
+    markets: &'a mut [Account<'info, Market>],
+    market_id: &[u8; 32],
+) -> Result<&'a mut Account<'info, Market>> {
+    markets
+        .iter_mut()
+        .find(|m| m.market_id == *market_id)
+        .ok_or_else(|| error!(MorphoError::MarketNotFound))
+}
+
+// ============================================================================
+// Migrate Protocol State
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct MigrateProtocolState<'info> {
+    /// Must be the owner recorded in the legacy account; receives the
+    /// rent freed by shrinking it
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: Still in the fixed-array layout, so it can't deserialize as
+    /// ProtocolState; discriminator, size and owner are checked in the handler
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump,
+    )]
+    pub protocol_state: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Convert a fixed-array protocol state to the dynamic whitelist layout
+///
+/// Must run once after upgrading a deployment created before the whitelists
+/// became dynamic; every other instruction fails to load the old layout.
+pub fn migrate_protocol_state(ctx: Context<MigrateProtocolState>) -> Result<()> {
+    let info = ctx.accounts.protocol_state.to_account_info();
+
+    // ===== CHECKS =====
+    let legacy = {
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() == ProtocolStateV1::SPACE,
+            MorphoError::ProtocolStateAlreadyMigrated
+        );
+        require!(
+            data[..8] == *ProtocolState::DISCRIMINATOR,
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        ProtocolStateV1::deserialize(&mut &data[8..])?
+    };
+    require!(legacy.owner == ctx.accounts.owner.key(), MorphoError::Unauthorized);
+
+    // ===== EFFECTS =====
+    let state = legacy.into_current();
+    resize_account(
+        &info,
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        state.current_space(),
+    )?;
+    state.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    emit!(ProtocolStateMigrated {
+        lltv_count: state.enabled_lltvs.len() as u8,
+        irm_count: state.enabled_irms.len() as u8,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Migrate Market
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct MigrateMarket<'info> {
+    /// Funds the rent of the grown account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Still in an older, shorter layout, so it can't deserialize as
+    /// Market; owner, discriminator and size are checked in the handler
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump,
+    )]
+    pub market: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grow a market created under an older layout to the current one
+///
+/// Every field added since the original layout sits after
+/// `flash_loan_lock`, where that layout kept zeroed reserved bytes, and each
+/// one reads zero as "off" or "not recorded". Zero-extending the account is
+/// therefore the whole migration. Anyone may run it; the market can't be
+/// loaded by any other instruction until it has.
+pub fn migrate_market(ctx: Context<MigrateMarket>, market_id: [u8; 32]) -> Result<()> {
+    let info = ctx.accounts.market.to_account_info();
+
+    // ===== CHECKS =====
+    require_keys_eq!(*info.owner, crate::ID, anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram);
+    let old_space = info.data_len();
+    require!(old_space < Market::space(), MorphoError::MarketAlreadyMigrated);
+    require!(
+        info.try_borrow_data()?.starts_with(Market::DISCRIMINATOR),
+        anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+    );
+
+    // ===== EFFECTS =====
+    resize_account(
+        &info,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        Market::space(),
+    )?;
+    // Must load now
+    let market = Market::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    require!(market.market_id == market_id, MorphoError::MarketNotFound);
+
+    emit!(MarketMigrated {
+        market_id,
+        old_space: old_space as u32,
+        new_space: Market::space() as u32,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Migrate Position
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32], owner: Pubkey)]
+pub struct MigratePosition<'info> {
+    /// Funds the rent of the grown account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Still in an older, shorter layout, so it can't deserialize as
+    /// Position; owner, discriminator and size are checked in the handler
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, owner.as_ref()],
+        bump,
+    )]
+    pub position: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grow a position created under an older layout to the current one
+///
+/// As with markets, every field added since the original layout sits in
+/// what were zeroed reserved bytes and reads zero as "none" or "never", so
+/// zero-extending is the whole migration. Anyone may run it.
+pub fn migrate_position(ctx: Context<MigratePosition>, market_id: [u8; 32], owner: Pubkey) -> Result<()> {
+    let info = ctx.accounts.position.to_account_info();
+
+    // ===== CHECKS =====
+    require_keys_eq!(*info.owner, crate::ID, anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram);
+    let old_space = info.data_len();
+    require!(old_space < Position::space(), MorphoError::PositionAlreadyMigrated);
+    require!(
+        info.try_borrow_data()?.starts_with(Position::DISCRIMINATOR),
+        anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+    );
+
+    // ===== EFFECTS =====
+    resize_account(
+        &info,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        Position::space(),
+    )?;
+    // Must load now; the seeds already tie it to `market_id` and `owner`
+    Position::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+
+    emit!(PositionMigrated {
+        market_id,
+        owner,
+        old_space: old_space as u32,
+        new_space: Position::space() as u32,
+    });
+    Ok(())
+}
+
+/// Resize a program account, keeping it exactly rent-exempt
+///
+/// `payer` funds growth and is refunded when the account shrinks. Grown
+/// bytes are zeroed.
+fn resize_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    if new_len == account.data_len() {
+        return Ok(());
+    }
+
+    let rent_minimum = Rent::get()?.minimum_balance(new_len);
+    let lamports = account.lamports();
+    if rent_minimum > lamports {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            rent_minimum - lamports,
+        )?;
+    } else if lamports > rent_minimum {
+        let refund = lamports - rent_minimum;
+        **account.try_borrow_mut_lamports()? -= refund;
+        **payer.try_borrow_mut_lamports()? += refund;
+    }
+
+    account.realloc(new_len, true)?;
+    Ok(())
+}
@@ -3,22 +3,30 @@
 //! CEI Pattern: Checks → Effects → Interactions
 
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked};
 use crate::constants::PROGRAM_SEED_PREFIX;
 use crate::errors::MorphoError;
 use crate::events;
-use crate::state::{ProtocolState, Market, Position, Authorization};
+use crate::state::{ProtocolState, Market, MarketAction, Position, Authorization};
 use crate::math::{
     checked_add, checked_sub, safe_u128_to_u64,
     to_shares_up, to_shares_down, to_assets_up,
-    accrue_interest_on_market,
+    accrue_market_interest, accrue_stable_debt,
 };
-use crate::interfaces::{get_borrow_rate_internal, get_oracle_price_validated, is_liquidatable};
+use crate::instructions::insurance::charge_insurance_premium;
+use crate::instructions::reputation::{record_repayment, sync_reputation_boost};
+use crate::instructions::utils::prepare_receiver_token_account;
+use crate::instructions::yield_adapter::ensure_loan_vault_liquidity;
+use crate::interfaces::{enforce_risk_cap, ensure_position_healthy};
+use crate::time::current_clock;
 
 // ============================================================================
 // Supply Collateral
 // ============================================================================
 
+/// Takes no oracle: adding collateral can't make a position less healthy, so
+/// it keeps working while the feed is down.
 #[derive(Accounts)]
 #[instruction(market_id: [u8; 32])]
 pub struct SupplyCollateral<'info> {
@@ -32,6 +40,7 @@ pub struct SupplyCollateral<'info> {
     pub protocol_state: Box<Account<'info, ProtocolState>>,
 
     #[account(
+        mut,
         seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
         bump = market.bump,
     )]
@@ -72,11 +81,12 @@ pub fn supply_collateral(
 ) -> Result<()> {
     // ===== CHECKS =====
     require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
-    require!(!ctx.accounts.market.paused, MorphoError::MarketPaused);
+    ctx.accounts.market.check_action(MarketAction::SupplyCollateral)?;
     require!(amount > 0, MorphoError::ZeroAmount);
 
     // ===== EFFECTS =====
     ctx.accounts.position.collateral = checked_add(ctx.accounts.position.collateral, amount)?;
+    ctx.accounts.market.total_collateral = checked_add(ctx.accounts.market.total_collateral, amount)?;
 
     // ===== INTERACTIONS =====
     let amount_u64 = safe_u128_to_u64(amount)?;
@@ -100,6 +110,8 @@ pub fn supply_collateral(
         on_behalf_of: ctx.accounts.on_behalf_of.key(),
         amount,
     });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.position);
+    crate::telemetry!("supply_collateral", market_id, amount = amount);
 
     Ok(())
 }
@@ -155,6 +167,9 @@ pub struct WithdrawCollateral<'info> {
     pub collateral_mint: InterfaceAccount<'info, Mint>,
 
     pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Market's fallback oracle, read only if the primary fails
+    pub fallback_oracle: Option<UncheckedAccount<'info>>,
 }
 
 pub fn withdraw_collateral(
@@ -164,7 +179,7 @@ pub fn withdraw_collateral(
 ) -> Result<()> {
     // ===== CHECKS =====
     require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
-    require!(!ctx.accounts.market.paused, MorphoError::MarketPaused);
+    ctx.accounts.market.check_action(MarketAction::WithdrawCollateral)?;
     require!(amount > 0, MorphoError::ZeroAmount);
 
     validate_authorization(
@@ -174,14 +189,12 @@ pub fn withdraw_collateral(
     )?;
 
     // Accrue interest
-    let borrow_rate = get_borrow_rate_internal(
-        ctx.accounts.market.total_supply_assets,
-        ctx.accounts.market.total_borrow_assets,
-    )?;
-    let current_time = Clock::get()?.unix_timestamp;
-    
+    let clock = current_clock(ctx.remaining_accounts)?;
+
     let market = &mut ctx.accounts.market;
-    accrue_interest_on_market(market, current_time, borrow_rate)?;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+    charge_insurance_premium(market, &mut ctx.accounts.position, clock.unix_timestamp)?;
+    accrue_stable_debt(market, &mut ctx.accounts.position, clock.unix_timestamp)?;
 
     require!(
         ctx.accounts.position.collateral >= amount,
@@ -190,25 +203,17 @@ pub fn withdraw_collateral(
 
     // ===== EFFECTS =====
     ctx.accounts.position.collateral = checked_sub(ctx.accounts.position.collateral, amount)?;
+    market.total_collateral = market.total_collateral.saturating_sub(amount);
 
-    // Health check AFTER effect, BEFORE interaction
-    if ctx.accounts.position.borrow_shares > 0 {
-        let oracle_price = get_oracle_price_validated(
-            &ctx.accounts.oracle.to_account_info(),
-            market,
-        )?;
-        require!(
-            !is_liquidatable(
-                ctx.accounts.position.collateral,
-                ctx.accounts.position.borrow_shares,
-                market.total_borrow_assets,
-                market.total_borrow_shares,
-                oracle_price,
-                market.lltv,
-            )?,
-            MorphoError::PositionUnhealthy
-        );
-    }
+    // Health check AFTER effect, BEFORE interaction (debt-free: no oracle read)
+    ensure_position_healthy(
+        market,
+        &ctx.accounts.position,
+        market.position_lltv(&ctx.accounts.position, clock.unix_timestamp),
+        Some(ctx.accounts.oracle.as_ref()),
+        ctx.accounts.fallback_oracle.as_ref().map(|o| o.as_ref()),
+    )?;
+    sync_reputation_boost(market, &mut ctx.accounts.position, clock.unix_timestamp);
 
     // ===== INTERACTIONS =====
     let amount_u64 = safe_u128_to_u64(amount)?;
@@ -242,6 +247,8 @@ pub fn withdraw_collateral(
         receiver: ctx.accounts.receiver_token_account.key(),
         amount,
     });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.position);
+    crate::telemetry!("withdraw_collateral", market_id, amount = amount);
 
     Ok(())
 }
@@ -281,11 +288,17 @@ pub struct Borrow<'info> {
     /// CHECK: Oracle account for health check
     pub oracle: UncheckedAccount<'info>,
 
-    #[account(
-        mut,
-        constraint = receiver_token_account.mint == market.loan_mint,
-    )]
-    pub receiver_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(constraint = loan_mint.key() == market.loan_mint @ MorphoError::InvalidMint)]
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Wallet receiving the loan tokens; only used to create its ATA
+    pub receiver: UncheckedAccount<'info>,
+
+    /// CHECK: Any token account of the loan mint, or the receiver's ATA, which
+    /// is created on the fly (paid by caller) if it doesn't exist yet; checked
+    /// by `prepare_receiver_token_account`
+    #[account(mut)]
+    pub receiver_token_account: UncheckedAccount<'info>,
 
     #[account(
         mut,
@@ -294,20 +307,26 @@ pub struct Borrow<'info> {
     )]
     pub loan_vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub loan_mint: InterfaceAccount<'info, Mint>,
-
     pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Risk oracle feed, required when the market has one
+    pub risk_oracle: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Market's fallback oracle, read only if the primary fails
+    pub fallback_oracle: Option<UncheckedAccount<'info>>,
 }
 
-pub fn borrow(
-    ctx: Context<Borrow>,
+pub fn borrow<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Borrow<'info>>,
     market_id: [u8; 32],
     assets: u128,
     max_shares: u128,
 ) -> Result<()> {
     // ===== CHECKS =====
     require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
-    require!(!ctx.accounts.market.paused, MorphoError::MarketPaused);
+    ctx.accounts.market.check_action(MarketAction::Borrow)?;
     require!(assets > 0, MorphoError::ZeroAmount);
 
     validate_authorization(
@@ -317,14 +336,12 @@ pub fn borrow(
     )?;
 
     // Accrue interest
-    let borrow_rate = get_borrow_rate_internal(
-        ctx.accounts.market.total_supply_assets,
-        ctx.accounts.market.total_borrow_assets,
-    )?;
-    let current_time = Clock::get()?.unix_timestamp;
-    
+    let clock = current_clock(ctx.remaining_accounts)?;
+
     let market = &mut ctx.accounts.market;
-    accrue_interest_on_market(market, current_time, borrow_rate)?;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+    charge_insurance_premium(market, &mut ctx.accounts.position, clock.unix_timestamp)?;
+    accrue_stable_debt(market, &mut ctx.accounts.position, clock.unix_timestamp)?;
 
     require!(
         assets <= market.available_liquidity(),
@@ -339,29 +356,53 @@ pub fn borrow(
 
     // ===== EFFECTS =====
     ctx.accounts.position.borrow_shares = checked_add(ctx.accounts.position.borrow_shares, shares)?;
+    ctx.accounts.position.total_borrowed = checked_add(ctx.accounts.position.total_borrowed, assets)?;
+    ctx.accounts.position.last_borrowed_at = clock.unix_timestamp;
     market.total_borrow_assets = checked_add(market.total_borrow_assets, assets)?;
     market.total_borrow_shares = checked_add(market.total_borrow_shares, shares)?;
 
-    // Health check AFTER effect
-    let oracle_price = get_oracle_price_validated(
-        &ctx.accounts.oracle.to_account_info(),
+    // Health check AFTER effect, against the buffered borrow LLTV
+    ensure_position_healthy(
         market,
+        &ctx.accounts.position,
+        ctx.accounts.protocol_state.position_borrow_lltv(market, &ctx.accounts.position, clock.unix_timestamp),
+        Some(ctx.accounts.oracle.as_ref()),
+        ctx.accounts.fallback_oracle.as_ref().map(|o| o.as_ref()),
+    )?;
+    sync_reputation_boost(market, &mut ctx.accounts.position, clock.unix_timestamp);
+
+    enforce_risk_cap(
+        market,
+        ctx.accounts.risk_oracle.as_ref().map(|o| o.as_ref()),
+        clock.unix_timestamp,
     )?;
-    require!(
-        !is_liquidatable(
-            ctx.accounts.position.collateral,
-            ctx.accounts.position.borrow_shares,
-            market.total_borrow_assets,
-            market.total_borrow_shares,
-            oracle_price,
-            market.lltv,
-        )?,
-        MorphoError::PositionUnhealthy
-    );
 
     // ===== INTERACTIONS =====
     let amount_u64 = safe_u128_to_u64(assets)?;
     let bump = market.bump;
+
+    // Recall deployed liquidity if the vault alone can't cover the transfer
+    // (remaining accounts: [adapter_program, ...adapter accounts])
+    ensure_loan_vault_liquidity(
+        ctx.accounts.caller.key(),
+        &mut ctx.accounts.market,
+        &mut ctx.accounts.loan_vault,
+        &ctx.accounts.loan_mint.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.remaining_accounts,
+        assets,
+    )?;
+
+    prepare_receiver_token_account(
+        &ctx.accounts.receiver_token_account.to_account_info(),
+        &ctx.accounts.receiver.to_account_info(),
+        &ctx.accounts.loan_mint.to_account_info(),
+        &ctx.accounts.caller.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.associated_token_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
     let seeds = &[
         PROGRAM_SEED_PREFIX,
         Market::SEED,
@@ -392,6 +433,8 @@ pub fn borrow(
         assets,
         shares,
     });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.position);
+    crate::telemetry!("borrow", market_id, assets = assets, shares = shares);
 
     Ok(())
 }
@@ -400,6 +443,8 @@ pub fn borrow(
 // Repay
 // ============================================================================
 
+/// Takes no oracle: repaying can't make a position less healthy, so borrowers
+/// can always deleverage while the feed is down.
 #[derive(Accounts)]
 #[instruction(market_id: [u8; 32])]
 pub struct Repay<'info> {
@@ -449,18 +494,16 @@ pub fn repay(
 ) -> Result<()> {
     // ===== CHECKS =====
     // Note: Repay allowed even when paused (helps users exit)
+    ctx.accounts.market.check_action(MarketAction::Repay)?;
     require!(assets > 0 || shares > 0, MorphoError::ZeroAmount);
     require!(!(assets > 0 && shares > 0), MorphoError::InvalidInput);
 
     // Accrue interest
-    let borrow_rate = get_borrow_rate_internal(
-        ctx.accounts.market.total_supply_assets,
-        ctx.accounts.market.total_borrow_assets,
-    )?;
-    let current_time = Clock::get()?.unix_timestamp;
-    
+    let clock = current_clock(ctx.remaining_accounts)?;
+
     let market = &mut ctx.accounts.market;
-    accrue_interest_on_market(market, current_time, borrow_rate)?;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+    charge_insurance_premium(market, &mut ctx.accounts.position, clock.unix_timestamp)?;
 
     let position = &ctx.accounts.position;
 
@@ -480,6 +523,8 @@ pub fn repay(
 
     // ===== EFFECTS =====
     ctx.accounts.position.borrow_shares = checked_sub(ctx.accounts.position.borrow_shares, burn_shares)?;
+    record_repayment(market, &mut ctx.accounts.position, repay_assets, clock.unix_timestamp)?;
+    sync_reputation_boost(market, &mut ctx.accounts.position, clock.unix_timestamp);
     market.total_borrow_assets = checked_sub(market.total_borrow_assets, repay_assets)?;
     market.total_borrow_shares = checked_sub(market.total_borrow_shares, burn_shares)?;
 
@@ -506,6 +551,8 @@ pub fn repay(
         assets: repay_assets,
         shares: burn_shares,
     });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.position);
+    crate::telemetry!("repay", market_id, assets = repay_assets, shares = burn_shares);
 
     Ok(())
 }
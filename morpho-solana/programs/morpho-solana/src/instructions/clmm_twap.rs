@@ -0,0 +1,128 @@
+//! CLMM TWAP oracle instructions
+//!
+//! Anyone can create a `ClmmTwapOracle` over a Raydium CLMM pool's
+//! observations and crank it; markets opt in by using the oracle account as
+//! their oracle. Each update recomputes the average from the pool's own
+//! observation ring, so the crank can't pick which prices go in.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::errors::MorphoError;
+use crate::events::{ClmmTwapOracleCreated, ClmmTwapUpdated};
+use crate::interfaces::{
+    observation_twap_tick, ClmmTwapOracle, MAX_TWAP_WINDOW, MIN_TWAP_WINDOW, RAYDIUM_CLMM_PROGRAM_ID,
+};
+
+// ============================================================================
+// Create CLMM TWAP Oracle
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(min_window: i64, collateral_is_token0: bool)]
+pub struct CreateClmmTwapOracle<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Pool's Raydium ObservationState, checked by owner and decoding
+    #[account(owner = RAYDIUM_CLMM_PROGRAM_ID @ MorphoError::InvalidClmmObservations)]
+    pub observation_state: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ClmmTwapOracle::space(),
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            ClmmTwapOracle::SEED,
+            observation_state.key().as_ref(),
+            &min_window.to_le_bytes(),
+            &[collateral_is_token0 as u8],
+        ],
+        bump,
+    )]
+    pub clmm_twap_oracle: Account<'info, ClmmTwapOracle>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Create a CLMM TWAP oracle, seeded with the pool's current average
+///
+/// The pool's observations must already span `min_window` seconds.
+pub fn create_clmm_twap_oracle(
+    ctx: Context<CreateClmmTwapOracle>,
+    min_window: i64,
+    collateral_is_token0: bool,
+) -> Result<()> {
+    // CHECKS
+    require!(
+        (MIN_TWAP_WINDOW..=MAX_TWAP_WINDOW).contains(&min_window),
+        MorphoError::InvalidTwapConfig
+    );
+
+    // EFFECTS
+    let oracle = &mut ctx.accounts.clmm_twap_oracle;
+    oracle.bump = ctx.bumps.clmm_twap_oracle;
+    oracle.observation_state = ctx.accounts.observation_state.key();
+    oracle.collateral_is_token0 = collateral_is_token0;
+    oracle.min_window = min_window;
+    record_twap(oracle, &ctx.accounts.observation_state.to_account_info())?;
+    // The average tick must price
+    oracle.price()?;
+
+    emit!(ClmmTwapOracleCreated {
+        clmm_twap_oracle: oracle.key(),
+        observation_state: oracle.observation_state,
+        min_window,
+        collateral_is_token0,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Update CLMM TWAP (permissionless crank)
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct UpdateClmmTwap<'info> {
+    #[account(
+        mut,
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            ClmmTwapOracle::SEED,
+            clmm_twap_oracle.observation_state.as_ref(),
+            &clmm_twap_oracle.min_window.to_le_bytes(),
+            &[clmm_twap_oracle.collateral_is_token0 as u8],
+        ],
+        bump = clmm_twap_oracle.bump,
+    )]
+    pub clmm_twap_oracle: Account<'info, ClmmTwapOracle>,
+
+    /// CHECK: Must be the oracle's observation state
+    #[account(
+        constraint = observation_state.key() == clmm_twap_oracle.observation_state @ MorphoError::InvalidOracle,
+    )]
+    pub observation_state: UncheckedAccount<'info>,
+}
+
+/// Recompute the average from the pool's observations
+pub fn update_clmm_twap(ctx: Context<UpdateClmmTwap>) -> Result<()> {
+    let oracle = &mut ctx.accounts.clmm_twap_oracle;
+
+    // CHECKS + EFFECTS
+    record_twap(oracle, &ctx.accounts.observation_state.to_account_info())?;
+
+    emit!(ClmmTwapUpdated {
+        clmm_twap_oracle: oracle.key(),
+        twap_tick: oracle.twap_tick,
+        observed_at: oracle.observed_at,
+    });
+    Ok(())
+}
+
+fn record_twap(oracle: &mut ClmmTwapOracle, observation_state: &AccountInfo) -> Result<()> {
+    let (twap_tick, observed_at) = observation_twap_tick(&observation_state.try_borrow_data()?, oracle.min_window)?;
+    oracle.twap_tick = twap_tick;
+    oracle.observed_at = observed_at;
+    oracle.last_update_slot = Clock::get()?.slot;
+    Ok(())
+}
@@ -0,0 +1,154 @@
+//! Composite oracle instructions
+//!
+//! Anyone can create a `CompositeOracle` over two USD feeds and crank it;
+//! markets opt in by using the composite account as their oracle. Each
+//! update reads both legs with the standard freshness checks, so a halted
+//! leg stops the composite from updating and it goes stale.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::errors::MorphoError;
+use crate::events::{CompositeOracleCreated, CompositeUpdated};
+use crate::interfaces::{
+    feed_publish_time, is_clmm_twap_oracle, is_composite_oracle, is_ewma_oracle, read_feed_price,
+    CompositeOracle,
+};
+
+// ============================================================================
+// Create Composite Oracle
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(collateral_decimals: u8, loan_decimals: u8)]
+pub struct CreateCompositeOracle<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Collateral token's USD feed, validated by reading it
+    pub base_feed: UncheckedAccount<'info>,
+
+    /// CHECK: Loan token's USD feed, validated by reading it
+    pub quote_feed: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = CompositeOracle::space(),
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            CompositeOracle::SEED,
+            base_feed.key().as_ref(),
+            quote_feed.key().as_ref(),
+            &[collateral_decimals, loan_decimals],
+        ],
+        bump,
+    )]
+    pub composite_oracle: Account<'info, CompositeOracle>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Create a composite oracle over `base_feed` / `quote_feed`, seeded with
+/// their current prices
+pub fn create_composite_oracle(
+    ctx: Context<CreateCompositeOracle>,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+) -> Result<()> {
+    // CHECKS
+    let base_feed = ctx.accounts.base_feed.to_account_info();
+    let quote_feed = ctx.accounts.quote_feed.to_account_info();
+    require!(base_feed.key() != quote_feed.key(), MorphoError::InvalidCompositeConfig);
+    // Legs are USD feeds; composites, pool TWAPs and EWMAs already price a pair
+    for leg in [&base_feed, &quote_feed] {
+        require!(
+            !is_composite_oracle(leg)? && !is_clmm_twap_oracle(leg)? && !is_ewma_oracle(leg)?,
+            MorphoError::InvalidCompositeConfig
+        );
+    }
+
+    // EFFECTS
+    let composite = &mut ctx.accounts.composite_oracle;
+    composite.bump = ctx.bumps.composite_oracle;
+    composite.base_feed = base_feed.key();
+    composite.quote_feed = quote_feed.key();
+    composite.collateral_decimals = collateral_decimals;
+    composite.loan_decimals = loan_decimals;
+    record_legs(composite, &base_feed, &quote_feed)?;
+    // Both legs must price, and so must their ratio
+    composite.price()?;
+
+    emit!(CompositeOracleCreated {
+        composite_oracle: composite.key(),
+        base_feed: composite.base_feed,
+        quote_feed: composite.quote_feed,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Update Composite (permissionless crank)
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct UpdateComposite<'info> {
+    #[account(
+        mut,
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            CompositeOracle::SEED,
+            composite_oracle.base_feed.as_ref(),
+            composite_oracle.quote_feed.as_ref(),
+            &[composite_oracle.collateral_decimals, composite_oracle.loan_decimals],
+        ],
+        bump = composite_oracle.bump,
+    )]
+    pub composite_oracle: Account<'info, CompositeOracle>,
+
+    /// CHECK: Must be the composite's base feed
+    #[account(constraint = base_feed.key() == composite_oracle.base_feed @ MorphoError::InvalidOracle)]
+    pub base_feed: UncheckedAccount<'info>,
+
+    /// CHECK: Must be the composite's quote feed
+    #[account(constraint = quote_feed.key() == composite_oracle.quote_feed @ MorphoError::InvalidOracle)]
+    pub quote_feed: UncheckedAccount<'info>,
+}
+
+/// Read both legs into the composite
+pub fn update_composite(ctx: Context<UpdateComposite>) -> Result<()> {
+    let composite = &mut ctx.accounts.composite_oracle;
+
+    // CHECKS + EFFECTS
+    record_legs(
+        composite,
+        &ctx.accounts.base_feed.to_account_info(),
+        &ctx.accounts.quote_feed.to_account_info(),
+    )?;
+
+    emit!(CompositeUpdated {
+        composite_oracle: composite.key(),
+        base_price: composite.base_price,
+        quote_price: composite.quote_price,
+        published_at: composite.published_at,
+    });
+    Ok(())
+}
+
+/// Read both legs as whole-token prices and stamp the composite with the
+/// older leg's publish time (now, if neither leg carries one)
+fn record_legs(
+    composite: &mut CompositeOracle,
+    base_feed: &AccountInfo,
+    quote_feed: &AccountInfo,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    composite.base_price = read_feed_price(base_feed, 0, 0)?;
+    composite.quote_price = read_feed_price(quote_feed, 0, 0)?;
+    composite.published_at = [feed_publish_time(base_feed)?, feed_publish_time(quote_feed)?]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(clock.unix_timestamp);
+    composite.last_update_slot = clock.slot;
+    Ok(())
+}
@@ -0,0 +1,521 @@
+//! Credit delegation instructions
+//!
+//! - Set a credit line (supplier delegates part of their supply to a borrower)
+//! - Draw credit (borrower borrows against the line, no collateral)
+//! - Repay credit (anyone, on behalf of the line)
+//! - Close a credit line (supplier; outstanding debt is written off against
+//!   the supplier's own supply position first)
+//!
+//! CEI Pattern: Checks → Effects → Interactions
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked};
+use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::errors::MorphoError;
+use crate::events::{CreditLineSet, CreditDrawn, CreditRepaid, CreditLineClosed, SupplySharesUnlocked};
+use crate::state::{ProtocolState, Market, MarketAction, Position, CreditLine};
+use crate::math::{
+    checked_add, checked_sub, safe_u128_to_u64,
+    to_shares_up, to_shares_down, to_assets_up, to_assets_down,
+    accrue_market_interest,
+};
+use crate::instructions::yield_adapter::ensure_loan_vault_liquidity;
+use crate::instructions::liquidate::enforce_share_price_floor;
+use crate::interfaces::{enforce_risk_cap, socialize_bad_debt};
+use crate::time::current_clock;
+
+// ============================================================================
+// Set Credit Line
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32], borrower: Pubkey)]
+pub struct SetCreditLine<'info> {
+    #[account(mut)]
+    pub supplier: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, supplier.key().as_ref()],
+        bump = supplier_position.bump,
+    )]
+    pub supplier_position: Box<Account<'info, Position>>,
+
+    #[account(
+        init_if_needed,
+        payer = supplier,
+        space = CreditLine::space(),
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            CreditLine::SEED,
+            &market_id,
+            supplier.key().as_ref(),
+            borrower.as_ref(),
+        ],
+        bump,
+    )]
+    pub credit_line: Box<Account<'info, CreditLine>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Open a credit line or change its limit
+///
+/// The limit is locked in the supplier's supply position; lowering it
+/// (never below the outstanding debt) releases the difference.
+pub fn set_credit_line(
+    ctx: Context<SetCreditLine>,
+    market_id: [u8; 32],
+    borrower: Pubkey,
+    limit: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    let supplier = ctx.accounts.supplier.key();
+    require!(borrower != supplier, MorphoError::InvalidCreditLineBorrower);
+
+    let clock = current_clock(ctx.remaining_accounts)?;
+
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+
+    let line = &mut ctx.accounts.credit_line;
+    let debt = to_assets_up(line.borrow_shares, market.total_borrow_assets, market.total_borrow_shares)?;
+    require!(limit >= debt, MorphoError::CreditLineLimitBelowDebt);
+
+    let position = &mut ctx.accounts.supplier_position;
+    let delegated = checked_add(checked_sub(position.credit_delegated, line.limit)?, limit)?;
+    require!(
+        to_assets_down(position.supply_shares, market.total_supply_assets, market.total_supply_shares)?
+            >= delegated,
+        MorphoError::CreditLineUnderfunded
+    );
+
+    // ===== EFFECTS =====
+    position.credit_delegated = delegated;
+
+    if line.supplier == Pubkey::default() {
+        line.bump = ctx.bumps.credit_line;
+        line.market_id = market_id;
+        line.supplier = supplier;
+        line.borrower = borrower;
+        line.borrow_shares = 0;
+        line.created_at = clock.unix_timestamp;
+    }
+    line.limit = limit;
+
+    emit!(CreditLineSet {
+        market_id,
+        supplier,
+        borrower,
+        limit,
+    });
+
+    Ok(())
+}
+
+// ============================================================================
+// Draw Credit
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct DrawCredit<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            CreditLine::SEED,
+            &market_id,
+            credit_line.supplier.as_ref(),
+            borrower.key().as_ref(),
+        ],
+        bump = credit_line.bump,
+    )]
+    pub credit_line: Box<Account<'info, CreditLine>>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, credit_line.supplier.as_ref()],
+        bump = supplier_position.bump,
+    )]
+    pub supplier_position: Box<Account<'info, Position>>,
+
+    #[account(constraint = loan_mint.key() == market.loan_mint @ MorphoError::InvalidMint)]
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = receiver_token_account.mint == market.loan_mint,
+    )]
+    pub receiver_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Risk oracle feed, required when the market has one
+    pub risk_oracle: Option<UncheckedAccount<'info>>,
+}
+
+/// Borrow against a credit line without collateral
+pub fn draw_credit<'info>(
+    ctx: Context<'_, '_, 'info, 'info, DrawCredit<'info>>,
+    market_id: [u8; 32],
+    assets: u128,
+    max_shares: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    ctx.accounts.market.check_action(MarketAction::Borrow)?;
+    require!(assets > 0, MorphoError::ZeroAmount);
+
+    // Accrue interest
+    let clock = current_clock(ctx.remaining_accounts)?;
+
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+
+    require!(
+        assets <= market.available_liquidity(),
+        MorphoError::InsufficientLiquidity
+    );
+
+    // The supplier's position must still back everything they delegated
+    let supplier_position = &ctx.accounts.supplier_position;
+    require!(
+        to_assets_down(
+            supplier_position.supply_shares,
+            market.total_supply_assets,
+            market.total_supply_shares,
+        )? >= supplier_position.credit_delegated,
+        MorphoError::CreditLineUnderfunded
+    );
+
+    // Calculate shares (round UP - borrower owes more)
+    let shares = to_shares_up(assets, market.total_borrow_assets, market.total_borrow_shares)?;
+    if max_shares > 0 {
+        require!(shares <= max_shares, MorphoError::SlippageExceeded);
+    }
+
+    // ===== EFFECTS =====
+    let line = &mut ctx.accounts.credit_line;
+    line.borrow_shares = checked_add(line.borrow_shares, shares)?;
+    market.total_borrow_assets = checked_add(market.total_borrow_assets, assets)?;
+    market.total_borrow_shares = checked_add(market.total_borrow_shares, shares)?;
+
+    // Limit check AFTER effect, on the line's full debt including accrued interest
+    require!(
+        to_assets_up(line.borrow_shares, market.total_borrow_assets, market.total_borrow_shares)?
+            <= line.limit,
+        MorphoError::CreditLimitExceeded
+    );
+
+    enforce_risk_cap(
+        market,
+        ctx.accounts.risk_oracle.as_ref().map(|o| o.as_ref()),
+        clock.unix_timestamp,
+    )?;
+
+    let supplier = line.supplier;
+
+    // ===== INTERACTIONS =====
+    let amount_u64 = safe_u128_to_u64(assets)?;
+    let bump = market.bump;
+
+    // Recall deployed liquidity if the vault alone can't cover the transfer
+    // (remaining accounts: [adapter_program, ...adapter accounts])
+    ensure_loan_vault_liquidity(
+        ctx.accounts.borrower.key(),
+        &mut ctx.accounts.market,
+        &mut ctx.accounts.loan_vault,
+        &ctx.accounts.loan_mint.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.remaining_accounts,
+        assets,
+    )?;
+
+    let seeds = &[
+        PROGRAM_SEED_PREFIX,
+        Market::SEED,
+        market_id.as_ref(),
+        &[bump],
+    ];
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.loan_vault.to_account_info(),
+                to: ctx.accounts.receiver_token_account.to_account_info(),
+                authority: ctx.accounts.market.to_account_info(),
+                mint: ctx.accounts.loan_mint.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount_u64,
+        ctx.accounts.loan_mint.decimals,
+    )?;
+
+    emit!(CreditDrawn {
+        market_id,
+        supplier,
+        borrower: ctx.accounts.borrower.key(),
+        receiver: ctx.accounts.receiver_token_account.key(),
+        assets,
+        shares,
+    });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.supplier_position);
+
+    Ok(())
+}
+
+// ============================================================================
+// Repay Credit
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct RepayCredit<'info> {
+    #[account(mut)]
+    pub repayer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            CreditLine::SEED,
+            &market_id,
+            credit_line.supplier.as_ref(),
+            credit_line.borrower.as_ref(),
+        ],
+        bump = credit_line.bump,
+    )]
+    pub credit_line: Box<Account<'info, CreditLine>>,
+
+    #[account(
+        mut,
+        constraint = repayer_token_account.mint == market.loan_mint,
+    )]
+    pub repayer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn repay_credit(
+    ctx: Context<RepayCredit>,
+    market_id: [u8; 32],
+    assets: u128,
+    shares: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    // Note: Repay allowed even when paused (helps users exit)
+    require!(assets > 0 || shares > 0, MorphoError::ZeroAmount);
+    require!(!(assets > 0 && shares > 0), MorphoError::InvalidInput);
+
+    // Accrue interest
+    let clock = current_clock(ctx.remaining_accounts)?;
+
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+
+    let line = &ctx.accounts.credit_line;
+
+    // Calculate amounts
+    let (repay_assets, burn_shares) = if assets > 0 {
+        let s = to_shares_down(assets, market.total_borrow_assets, market.total_borrow_shares)?;
+        let s = std::cmp::min(s, line.borrow_shares);
+        let a = to_assets_up(s, market.total_borrow_assets, market.total_borrow_shares)?;
+        (a, s)
+    } else {
+        let s = std::cmp::min(shares, line.borrow_shares);
+        let a = to_assets_up(s, market.total_borrow_assets, market.total_borrow_shares)?;
+        (a, s)
+    };
+
+    require!(burn_shares > 0, MorphoError::ZeroAmount);
+
+    // ===== EFFECTS =====
+    let line = &mut ctx.accounts.credit_line;
+    line.borrow_shares = checked_sub(line.borrow_shares, burn_shares)?;
+    market.total_borrow_assets = checked_sub(market.total_borrow_assets, repay_assets)?;
+    market.total_borrow_shares = checked_sub(market.total_borrow_shares, burn_shares)?;
+
+    let supplier = line.supplier;
+    let borrower = line.borrower;
+
+    // ===== INTERACTIONS =====
+    let amount_u64 = safe_u128_to_u64(repay_assets)?;
+    transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.repayer_token_account.to_account_info(),
+                to: ctx.accounts.loan_vault.to_account_info(),
+                authority: ctx.accounts.repayer.to_account_info(),
+                mint: ctx.accounts.loan_mint.to_account_info(),
+            },
+        ),
+        amount_u64,
+        ctx.accounts.loan_mint.decimals,
+    )?;
+
+    emit!(CreditRepaid {
+        market_id,
+        supplier,
+        borrower,
+        repayer: ctx.accounts.repayer.key(),
+        assets: repay_assets,
+        shares: burn_shares,
+    });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts);
+
+    Ok(())
+}
+
+// ============================================================================
+// Close Credit Line
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct CloseCreditLine<'info> {
+    #[account(mut)]
+    pub supplier: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, supplier.key().as_ref()],
+        bump = supplier_position.bump,
+    )]
+    pub supplier_position: Box<Account<'info, Position>>,
+
+    #[account(
+        mut,
+        close = supplier,
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            CreditLine::SEED,
+            &market_id,
+            supplier.key().as_ref(),
+            credit_line.borrower.as_ref(),
+        ],
+        bump = credit_line.bump,
+    )]
+    pub credit_line: Box<Account<'info, CreditLine>>,
+}
+
+/// Close a credit line, releasing its delegated supply
+///
+/// Any outstanding debt is written off: the supplier's supply shares are
+/// burned to cover it at the current share price, and only what their
+/// position can't cover is socialized like liquidation bad debt.
+pub fn close_credit_line(ctx: Context<CloseCreditLine>, market_id: [u8; 32]) -> Result<()> {
+    // ===== CHECKS =====
+    let clock = current_clock(ctx.remaining_accounts)?;
+
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+
+    let line = &ctx.accounts.credit_line;
+    let position = &mut ctx.accounts.supplier_position;
+
+    // ===== EFFECTS =====
+    let mut written_off_assets = 0;
+    let mut burned_supply_shares = 0;
+    let mut bad_debt_assets = 0;
+
+    if line.has_debt() {
+        // Price the supplier's cover BEFORE the write-off moves supply totals
+        let debt = to_assets_up(line.borrow_shares, market.total_borrow_assets, market.total_borrow_shares)?;
+        burned_supply_shares = std::cmp::min(
+            to_shares_up(debt, market.total_supply_assets, market.total_supply_shares)?,
+            position.supply_shares,
+        );
+        let covered = to_assets_down(
+            burned_supply_shares,
+            market.total_supply_assets,
+            market.total_supply_shares,
+        )?;
+
+        position.supply_shares = checked_sub(position.supply_shares, burned_supply_shares)?;
+        // Delegated supply backs the line first; escrow can't shield it
+        let escrow_released = position.escrowed_supply_shares.saturating_sub(position.supply_shares);
+        if escrow_released > 0 {
+            position.escrowed_supply_shares = position.supply_shares;
+            emit!(SupplySharesUnlocked {
+                market_id,
+                owner: position.owner,
+                shares: escrow_released,
+                escrowed_shares: position.escrowed_supply_shares,
+            });
+        }
+        market.total_supply_shares = checked_sub(market.total_supply_shares, burned_supply_shares)?;
+
+        written_off_assets = socialize_bad_debt(market, line.borrow_shares)?;
+        enforce_share_price_floor(market)?;
+        bad_debt_assets = written_off_assets.saturating_sub(covered);
+    }
+
+    position.credit_delegated = checked_sub(position.credit_delegated, line.limit)?;
+
+    emit!(CreditLineClosed {
+        market_id,
+        supplier: line.supplier,
+        borrower: line.borrower,
+        written_off_assets,
+        burned_supply_shares,
+        bad_debt_assets,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//! Devnet-only drill instructions (enabled with the `devnet` feature)
+//!
+//! - Force oracle failures to exercise every oracle error path end-to-end
+//! - Pin the time instructions run at, per instruction, via `ClockOverride`
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::errors::MorphoError;
+use crate::events::{ClockOverrideCleared, ClockOverrideSet, OracleFailureSimulated};
+use crate::interfaces::OracleFailureMode;
+use crate::state::{ClockOverride, ProtocolState, Market};
+
+// ============================================================================
+// Simulate Oracle Failure
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SimulateOracleFailure<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Make the oracle adapter fail for this market until reset with `OracleFailureMode::None`
+pub fn simulate_oracle_failure(
+    ctx: Context<SimulateOracleFailure>,
+    market_id: [u8; 32],
+    mode: OracleFailureMode,
+) -> Result<()> {
+    ctx.accounts.market.oracle_failure_mode = mode as u8;
+    emit!(OracleFailureSimulated { market_id, mode: mode as u8 });
+    Ok(())
+}
+
+// ============================================================================
+// Clock Override
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct SetClockOverride<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = ClockOverride::space(),
+        seeds = [PROGRAM_SEED_PREFIX, ClockOverride::SEED],
+        bump,
+    )]
+    pub clock_override: Account<'info, ClockOverride>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Make instructions that are passed the override run at `unix_timestamp`
+pub fn set_clock_override(ctx: Context<SetClockOverride>, unix_timestamp: i64) -> Result<()> {
+    let clock_override = &mut ctx.accounts.clock_override;
+    clock_override.bump = ctx.bumps.clock_override;
+    clock_override.authority = ctx.accounts.owner.key();
+    clock_override.unix_timestamp = unix_timestamp;
+    emit!(ClockOverrideSet { unix_timestamp });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClearClockOverride<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PROGRAM_SEED_PREFIX, ClockOverride::SEED],
+        bump = clock_override.bump,
+    )]
+    pub clock_override: Account<'info, ClockOverride>,
+}
+
+/// Close the override, returning every instruction to the sysvar clock
+pub fn clear_clock_override(ctx: Context<ClearClockOverride>) -> Result<()> {
+    emit!(ClockOverrideCleared { unix_timestamp: ctx.accounts.clock_override.unix_timestamp });
+    Ok(())
+}
@@ -0,0 +1,312 @@
+//! Oracle divergence guard
+//!
+//! The owner configures two reference feeds per market and a divergence
+//! threshold. Any keeper can then call `flag_oracle_divergence`; if the feeds
+//! disagree by more than the threshold the market is frozen: borrows and
+//! collateral withdrawals revert until the owner clears the flag. Repay,
+//! supply, collateral deposits and liquidations keep working.
+//!
+//! Separately, the owner may give a market a liquidation reference oracle
+//! (see `LiquidationReference`): liquidations then only go through while
+//! the market's price agrees with it.
+
+use anchor_lang::prelude::*;
+use crate::constants::{PROGRAM_SEED_PREFIX, BPS};
+use crate::errors::MorphoError;
+use crate::events::{DivergenceGuardSet, LiquidationReferenceSet, OracleDivergenceFlagged, OracleDivergenceCleared};
+use crate::interfaces::read_feed_price;
+use crate::math::mul_div_down;
+use crate::state::{ProtocolState, Market, LiquidationReference};
+
+/// Relative gap between two prices (basis points of the lower one)
+///
+/// Saturates at `u64::MAX` instead of overflowing on absurd gaps.
+pub fn price_divergence_bps(price_a: u128, price_b: u128) -> u64 {
+    let (low, high) = if price_a < price_b { (price_a, price_b) } else { (price_b, price_a) };
+    if low == 0 {
+        return if high == 0 { 0 } else { u64::MAX };
+    }
+
+    // Widened: a gap past ~3.4e34 (prescaled) would overflow `gap * BPS`
+    mul_div_down(high - low, BPS as u128, low)
+        .ok()
+        .and_then(|bps| u64::try_from(bps).ok())
+        .unwrap_or(u64::MAX)
+}
+
+// ============================================================================
+// Set Divergence Guard
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetDivergenceGuard<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Configure the reference feeds and threshold (default feeds turn the guard off)
+pub fn set_divergence_guard(
+    ctx: Context<SetDivergenceGuard>,
+    market_id: [u8; 32],
+    reference_feeds: [Pubkey; 2],
+    max_divergence_bps: u64,
+) -> Result<()> {
+    let disabled = reference_feeds == [Pubkey::default(); 2];
+    if !disabled {
+        require!(
+            reference_feeds[0] != Pubkey::default()
+                && reference_feeds[1] != Pubkey::default()
+                && reference_feeds[0] != reference_feeds[1],
+            MorphoError::InvalidOracle
+        );
+        require!(
+            max_divergence_bps > 0 && max_divergence_bps <= BPS,
+            MorphoError::InvalidInput
+        );
+    }
+
+    let market = &mut ctx.accounts.market;
+    market.reference_feeds = reference_feeds;
+    market.max_feed_divergence_bps = if disabled { 0 } else { max_divergence_bps };
+
+    emit!(DivergenceGuardSet {
+        market_id,
+        reference_feeds,
+        max_divergence_bps: market.max_feed_divergence_bps,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Flag Oracle Divergence
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct FlagOracleDivergence<'info> {
+    /// Any keeper
+    pub keeper: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// CHECK: First reference feed, must match market.reference_feeds[0]
+    #[account(constraint = feed_a.key() == market.reference_feeds[0] @ MorphoError::InvalidOracle)]
+    pub feed_a: UncheckedAccount<'info>,
+
+    /// CHECK: Second reference feed, must match market.reference_feeds[1]
+    #[account(constraint = feed_b.key() == market.reference_feeds[1] @ MorphoError::InvalidOracle)]
+    pub feed_b: UncheckedAccount<'info>,
+}
+
+/// Freeze the market if its reference feeds diverge beyond the threshold
+///
+/// Reverts when they agree, so keepers can't freeze a healthy market.
+pub fn flag_oracle_divergence(
+    ctx: Context<FlagOracleDivergence>,
+    market_id: [u8; 32],
+) -> Result<()> {
+    // ===== CHECKS =====
+    let market = &ctx.accounts.market;
+    require!(market.max_feed_divergence_bps > 0, MorphoError::DivergenceGuardNotSet);
+
+    let (collateral_decimals, loan_decimals) = (market.collateral_decimals, market.loan_decimals);
+    let price_a = read_feed_price(&ctx.accounts.feed_a.to_account_info(), collateral_decimals, loan_decimals)?;
+    let price_b = read_feed_price(&ctx.accounts.feed_b.to_account_info(), collateral_decimals, loan_decimals)?;
+    let divergence_bps = price_divergence_bps(price_a, price_b);
+    require!(
+        divergence_bps > market.max_feed_divergence_bps,
+        MorphoError::OracleDivergenceNotDetected
+    );
+
+    // ===== EFFECTS =====
+    ctx.accounts.market.divergence_frozen = true;
+
+    emit!(OracleDivergenceFlagged {
+        market_id,
+        keeper: ctx.accounts.keeper.key(),
+        price_a,
+        price_b,
+        divergence_bps,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Clear Oracle Divergence
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct ClearOracleDivergence<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Unfreeze the market once the owner is satisfied the feeds are sound
+pub fn clear_oracle_divergence(
+    ctx: Context<ClearOracleDivergence>,
+    market_id: [u8; 32],
+) -> Result<()> {
+    ctx.accounts.market.divergence_frozen = false;
+    emit!(OracleDivergenceCleared { market_id });
+    Ok(())
+}
+
+// ============================================================================
+// Liquidation Reference Oracle
+// ============================================================================
+
+/// Check a liquidation's price agrees with the market's reference price
+pub fn check_liquidation_price(
+    reference: &LiquidationReference,
+    price: u128,
+    reference_price: u128,
+) -> Result<()> {
+    let divergence_bps = price_divergence_bps(price, reference_price);
+    if divergence_bps > reference.max_divergence_bps {
+        msg!(
+            "Liquidation price disputed: {} bps from the reference, max {}",
+            divergence_bps,
+            reference.max_divergence_bps
+        );
+        return Err(MorphoError::LiquidationPriceDisputed.into());
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetLiquidationReference<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// CHECK: Reference feed, validated by reading it
+    pub reference_oracle: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = LiquidationReference::space(),
+        seeds = [PROGRAM_SEED_PREFIX, LiquidationReference::SEED, &market_id],
+        bump,
+    )]
+    pub liquidation_reference: Account<'info, LiquidationReference>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Require liquidations to agree with `reference_oracle` within
+/// `max_divergence_bps`
+///
+/// The reference must price now; it's read like any built-in feed, with
+/// the market's token decimals.
+pub fn set_liquidation_reference(
+    ctx: Context<SetLiquidationReference>,
+    market_id: [u8; 32],
+    max_divergence_bps: u64,
+) -> Result<()> {
+    // ===== CHECKS =====
+    let market = &ctx.accounts.market;
+    let reference_oracle = ctx.accounts.reference_oracle.to_account_info();
+    require!(
+        reference_oracle.key() != market.oracle
+            && max_divergence_bps > 0
+            && max_divergence_bps <= BPS,
+        MorphoError::InvalidLiquidationReference
+    );
+    read_feed_price(&reference_oracle, market.collateral_decimals, market.loan_decimals)?;
+
+    // ===== EFFECTS =====
+    let reference = &mut ctx.accounts.liquidation_reference;
+    reference.bump = ctx.bumps.liquidation_reference;
+    reference.market_id = market_id;
+    reference.reference_oracle = reference_oracle.key();
+    reference.max_divergence_bps = max_divergence_bps;
+
+    emit!(LiquidationReferenceSet {
+        market_id,
+        reference_oracle: reference.reference_oracle,
+        max_divergence_bps,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct ClearLiquidationReference<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PROGRAM_SEED_PREFIX, LiquidationReference::SEED, &market_id],
+        bump = liquidation_reference.bump,
+    )]
+    pub liquidation_reference: Account<'info, LiquidationReference>,
+}
+
+/// Drop the market's reference check, e.g. when the reference feed halts
+pub fn clear_liquidation_reference(
+    _ctx: Context<ClearLiquidationReference>,
+    market_id: [u8; 32],
+) -> Result<()> {
+    emit!(LiquidationReferenceSet {
+        market_id,
+        reference_oracle: Pubkey::default(),
+        max_divergence_bps: 0,
+    });
+    Ok(())
+}
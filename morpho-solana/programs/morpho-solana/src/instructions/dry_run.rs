@@ -0,0 +1,310 @@
+//! Bundle dry-run
+//!
+//! Runs the accounting of a proposed sequence of position actions against
+//! in-memory copies of the market and position: interest accrues, premiums
+//! are charged and each action is applied with the same rounding and checks
+//! as its standalone instruction. Nothing is transferred, written or
+//! emitted; the projected state comes back as return data
+//! (`ViewResponse::BundleProjection`).
+//!
+//! Authorization isn't checked (the caller isn't acting), and health is
+//! checked once at the end, like `adjust_position`.
+
+use anchor_lang::prelude::*;
+use crate::constants::{PROGRAM_SEED_PREFIX, MAX_DRY_RUN_ACTIONS};
+use crate::errors::MorphoError;
+use crate::state::{ProtocolState, Market, MarketAction, Position};
+use crate::math::{
+    checked_add, checked_sub, to_shares_up, to_shares_down, to_assets_up, to_assets_down,
+    accrue_interest_on_market, accrue_stable_debt, view_borrow_rate,
+};
+use crate::instructions::adjust::requires_health_check;
+use crate::instructions::insurance::insurance_premium;
+use crate::instructions::reputation::record_repayment;
+use crate::instructions::supply::hold_new_supply;
+use crate::instructions::view::{VersionedView, ViewResponse};
+use crate::interfaces::ensure_position_healthy;
+use crate::time::current_clock;
+
+/// One step of a dry-run bundle, mirroring the instruction of the same name
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BundleAction {
+    Supply { assets: u128 },
+    /// Exactly one of `assets` / `shares` is non-zero
+    Withdraw { assets: u128, shares: u128 },
+    SupplyCollateral { amount: u128 },
+    WithdrawCollateral { amount: u128 },
+    Borrow { assets: u128 },
+    /// Exactly one of `assets` / `shares` is non-zero
+    Repay { assets: u128, shares: u128 },
+}
+
+impl BundleAction {
+    /// Mode-gated action this step stands for
+    pub fn market_action(&self) -> MarketAction {
+        match self {
+            BundleAction::Supply { .. } => MarketAction::Supply,
+            BundleAction::Withdraw { .. } => MarketAction::Withdraw,
+            BundleAction::SupplyCollateral { .. } => MarketAction::SupplyCollateral,
+            BundleAction::WithdrawCollateral { .. } => MarketAction::WithdrawCollateral,
+            BundleAction::Borrow { .. } => MarketAction::Borrow,
+            BundleAction::Repay { .. } => MarketAction::Repay,
+        }
+    }
+}
+
+/// Projected state after a dry-run bundle
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BundleProjection {
+    pub supply_shares: u128,
+    pub borrow_shares: u128,
+    pub collateral: u128,
+
+    /// Supply shares valued in loan tokens (rounded down)
+    pub supply_assets: u128,
+
+    /// Debt in loan tokens (rounded up)
+    pub borrow_assets: u128,
+
+    pub total_supply_assets: u128,
+    pub total_supply_shares: u128,
+    pub total_borrow_assets: u128,
+    pub total_borrow_shares: u128,
+
+    /// Loan tokens the caller would send in (supplies + repays)
+    pub loan_in: u128,
+
+    /// Loan tokens the caller would receive (withdrawals + borrows)
+    pub loan_out: u128,
+
+    pub collateral_in: u128,
+    pub collateral_out: u128,
+
+    /// Whether the bundle needed the oracle-backed health check
+    pub health_checked: bool,
+
+    /// Interest accrued at the market's cached rate because its IRM (or
+    /// adaptive curve state) wasn't among the remaining accounts
+    pub rate_cached: bool,
+}
+
+/// Debt shares a bundle minted and burned, for the final health check
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BundleShares {
+    pub minted: u128,
+    pub burned: u128,
+}
+
+/// Apply `actions` to `market` and `position` (both already accrued)
+///
+/// Fails with the error the matching instruction would raise. The health
+/// check is left to the caller: `requires_health_check` on the returned
+/// shares and the projection's collateral flows says whether it's needed.
+pub fn simulate_bundle(
+    protocol_paused: bool,
+    market: &mut Market,
+    position: &mut Position,
+    actions: &[BundleAction],
+    now: i64,
+) -> Result<(BundleProjection, BundleShares)> {
+    let mut projection = BundleProjection::default();
+    let mut shares = BundleShares::default();
+
+    for action in actions {
+        // Repaying stays open while paused, like `repay`
+        if !matches!(action, BundleAction::Repay { .. }) {
+            require!(!protocol_paused, MorphoError::ProtocolPaused);
+        }
+        market.check_action(action.market_action())?;
+
+        match *action {
+            BundleAction::Supply { assets } => {
+                require!(assets > 0, MorphoError::ZeroAmount);
+                let s = to_shares_down(assets, market.total_supply_assets, market.total_supply_shares)?;
+                market.total_supply_assets = checked_add(market.total_supply_assets, assets)?;
+                market.total_supply_shares = checked_add(market.total_supply_shares, s)?;
+                position.supply_shares = checked_add(position.supply_shares, s)?;
+                hold_new_supply(market, position, s, now)?;
+                projection.loan_in = checked_add(projection.loan_in, assets)?;
+            }
+            BundleAction::Withdraw { assets, shares: withdraw_shares } => {
+                require!(assets > 0 || withdraw_shares > 0, MorphoError::ZeroAmount);
+                require!(!(assets > 0 && withdraw_shares > 0), MorphoError::InvalidInput);
+                let (a, s) = if assets > 0 {
+                    (assets, to_shares_up(assets, market.total_supply_assets, market.total_supply_shares)?)
+                } else {
+                    (to_assets_down(withdraw_shares, market.total_supply_assets, market.total_supply_shares)?, withdraw_shares)
+                };
+                require!(position.supply_shares >= s, MorphoError::InsufficientBalance);
+                require!(a <= market.available_liquidity(), MorphoError::InsufficientLiquidity);
+
+                position.supply_shares = checked_sub(position.supply_shares, s)?;
+                market.total_supply_assets = checked_sub(market.total_supply_assets, a)?;
+                market.total_supply_shares = checked_sub(market.total_supply_shares, s)?;
+                require!(
+                    position.supply_shares >= position.unwithdrawable_supply_shares_at(now),
+                    MorphoError::SupplyLocked
+                );
+                require!(
+                    to_assets_down(position.supply_shares, market.total_supply_assets, market.total_supply_shares)?
+                        >= position.credit_delegated,
+                    MorphoError::CreditLineUnderfunded
+                );
+                projection.loan_out = checked_add(projection.loan_out, a)?;
+            }
+            BundleAction::SupplyCollateral { amount } => {
+                require!(amount > 0, MorphoError::ZeroAmount);
+                position.collateral = checked_add(position.collateral, amount)?;
+                projection.collateral_in = checked_add(projection.collateral_in, amount)?;
+            }
+            BundleAction::WithdrawCollateral { amount } => {
+                require!(amount > 0, MorphoError::ZeroAmount);
+                require!(position.collateral >= amount, MorphoError::InsufficientCollateral);
+                position.collateral = checked_sub(position.collateral, amount)?;
+                projection.collateral_out = checked_add(projection.collateral_out, amount)?;
+            }
+            BundleAction::Borrow { assets } => {
+                require!(assets > 0, MorphoError::ZeroAmount);
+                require!(assets <= market.available_liquidity(), MorphoError::InsufficientLiquidity);
+                let s = to_shares_up(assets, market.total_borrow_assets, market.total_borrow_shares)?;
+                position.borrow_shares = checked_add(position.borrow_shares, s)?;
+                position.last_borrowed_at = now;
+                market.total_borrow_assets = checked_add(market.total_borrow_assets, assets)?;
+                market.total_borrow_shares = checked_add(market.total_borrow_shares, s)?;
+                shares.minted = checked_add(shares.minted, s)?;
+                projection.loan_out = checked_add(projection.loan_out, assets)?;
+            }
+            BundleAction::Repay { assets, shares: repay_shares } => {
+                require!(assets > 0 || repay_shares > 0, MorphoError::ZeroAmount);
+                require!(!(assets > 0 && repay_shares > 0), MorphoError::InvalidInput);
+                let s = if assets > 0 {
+                    to_shares_down(assets, market.total_borrow_assets, market.total_borrow_shares)?
+                } else {
+                    repay_shares
+                };
+                let s = std::cmp::min(s, position.borrow_shares);
+                require!(s > 0, MorphoError::ZeroAmount);
+                let a = to_assets_up(s, market.total_borrow_assets, market.total_borrow_shares)?;
+
+                position.borrow_shares = checked_sub(position.borrow_shares, s)?;
+                record_repayment(market, position, a, now)?;
+                market.total_borrow_assets = checked_sub(market.total_borrow_assets, a)?;
+                market.total_borrow_shares = checked_sub(market.total_borrow_shares, s)?;
+                shares.burned = checked_add(shares.burned, s)?;
+                projection.loan_in = checked_add(projection.loan_in, a)?;
+            }
+        }
+    }
+
+    projection.supply_shares = position.supply_shares;
+    projection.borrow_shares = position.borrow_shares;
+    projection.collateral = position.collateral;
+    projection.supply_assets = to_assets_down(
+        position.supply_shares,
+        market.total_supply_assets,
+        market.total_supply_shares,
+    )?;
+    projection.borrow_assets = to_assets_up(
+        position.borrow_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+    projection.total_supply_assets = market.total_supply_assets;
+    projection.total_supply_shares = market.total_supply_shares;
+    projection.total_borrow_assets = market.total_borrow_assets;
+    projection.total_borrow_shares = market.total_borrow_shares;
+    projection.health_checked = requires_health_check(
+        position.borrow_shares.saturating_add(position.stable_borrow_assets),
+        shares.minted,
+        shares.burned,
+        projection.collateral_in,
+        projection.collateral_out,
+    );
+    Ok((projection, shares))
+}
+
+// ============================================================================
+// Dry-Run Bundle
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct DryRunBundle<'info> {
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    /// CHECK: Oracle account, only required when the bundle can raise LTV
+    pub oracle: Option<UncheckedAccount<'info>>,
+    // remaining_accounts: the market's IRM program or adaptive curve state,
+    // to accrue at a fresh rate (optional)
+}
+
+/// Project `actions` on the position without moving tokens or saving state
+pub fn dry_run_bundle(
+    ctx: Context<DryRunBundle>,
+    _market_id: [u8; 32],
+    actions: Vec<BundleAction>,
+) -> Result<VersionedView> {
+    require!(
+        !actions.is_empty() && actions.len() <= MAX_DRY_RUN_ACTIONS,
+        MorphoError::InvalidInput
+    );
+
+    let clock = current_clock(ctx.remaining_accounts)?;
+    let mut market = Market::clone(&ctx.accounts.market);
+    let mut position = Position::clone(&ctx.accounts.position);
+
+    // Same accrual and premium as a real bundle, minus their events
+    let rate = view_borrow_rate(&market, ctx.remaining_accounts)?;
+    accrue_interest_on_market(&mut market, clock.unix_timestamp, rate.borrow_rate)?;
+    if position.insured {
+        let premium = insurance_premium(
+            position.collateral,
+            market.insurance_premium_bps,
+            clock.unix_timestamp - position.insurance_paid_until,
+        )?;
+        position.collateral -= std::cmp::min(premium, position.collateral);
+    }
+    accrue_stable_debt(&market, &mut position, clock.unix_timestamp)?;
+
+    let (mut projection, shares) = simulate_bundle(
+        ctx.accounts.protocol_state.paused,
+        &mut market,
+        &mut position,
+        &actions,
+        clock.unix_timestamp,
+    )?;
+
+    if projection.health_checked {
+        let lltv = if shares.minted > 0 {
+            ctx.accounts.protocol_state.position_borrow_lltv(&market, &position, clock.unix_timestamp)
+        } else {
+            market.position_lltv(&position, clock.unix_timestamp)
+        };
+        ensure_position_healthy(
+            &market,
+            &position,
+            lltv,
+            ctx.accounts.oracle.as_ref().map(|o| o.as_ref()),
+            None,
+        )?;
+    }
+
+    projection.rate_cached = rate.cached;
+    Ok(ViewResponse::BundleProjection(projection).into())
+}
@@ -0,0 +1,156 @@
+//! EWMA oracle instructions
+//!
+//! The owner sets up a market's `EwmaOracle` and tunes its smoothing factor;
+//! anyone can crank it. Each update reads the source with the standard
+//! freshness checks, so a halted source stops the average from updating
+//! and it goes stale.
+
+use anchor_lang::prelude::*;
+use crate::constants::{BPS, PROGRAM_SEED_PREFIX};
+use crate::errors::MorphoError;
+use crate::events::{EwmaUpdated, PriceSmoothingSet};
+use crate::interfaces::{is_ewma_oracle, read_feed_price, EwmaOracle, EWMA_UPDATE_INTERVAL};
+use crate::state::{ProtocolState, Market};
+
+// ============================================================================
+// Set Price Smoothing
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetPriceSmoothing<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    /// CHECK: The EWMA's source: the market's oracle on creation, the
+    /// recorded source after. Validated in the handler.
+    pub source: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = EwmaOracle::space(),
+        seeds = [PROGRAM_SEED_PREFIX, EwmaOracle::SEED, &market_id],
+        bump,
+    )]
+    pub ewma_oracle: Account<'info, EwmaOracle>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Set a market's smoothing factor, creating its EWMA over the market's
+/// current oracle the first time
+///
+/// The source is fixed at creation, so retuning an EWMA a market already
+/// prices from can't swap its feed past the oracle timelock. A new EWMA is
+/// seeded with the source's current price.
+pub fn set_price_smoothing(
+    ctx: Context<SetPriceSmoothing>,
+    market_id: [u8; 32],
+    smoothing_bps: u64,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(
+        smoothing_bps > 0 && smoothing_bps <= BPS,
+        MorphoError::InvalidSmoothingConfig
+    );
+
+    let market = &ctx.accounts.market;
+    let source = ctx.accounts.source.to_account_info();
+    let ewma = &mut ctx.accounts.ewma_oracle;
+    let creating = ewma.source == Pubkey::default();
+    if creating {
+        // Pegs never move and oracle programs aren't read by the crank
+        require!(
+            source.key() == market.oracle
+                && !market.oracle_pegged
+                && !market.oracle_program_priced
+                && !is_ewma_oracle(&source)?,
+            MorphoError::InvalidSmoothingConfig
+        );
+    } else {
+        require!(source.key() == ewma.source, MorphoError::InvalidOracle);
+    }
+
+    // ===== EFFECTS =====
+    if creating {
+        let clock = Clock::get()?;
+        ewma.bump = ctx.bumps.ewma_oracle;
+        ewma.market_id = market_id;
+        ewma.source = source.key();
+        ewma.collateral_decimals = market.collateral_decimals;
+        ewma.loan_decimals = market.loan_decimals;
+        ewma.price = read_feed_price(&source, market.collateral_decimals, market.loan_decimals)?;
+        ewma.last_update_slot = clock.slot;
+        ewma.last_update = clock.unix_timestamp;
+    }
+    ewma.smoothing_bps = smoothing_bps;
+
+    emit!(PriceSmoothingSet {
+        market_id,
+        ewma_oracle: ewma.key(),
+        source: ewma.source,
+        smoothing_bps,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Update EWMA (permissionless crank)
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct UpdateEwma<'info> {
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, EwmaOracle::SEED, &ewma_oracle.market_id],
+        bump = ewma_oracle.bump,
+    )]
+    pub ewma_oracle: Account<'info, EwmaOracle>,
+
+    /// CHECK: Must be the EWMA's source feed
+    #[account(constraint = source.key() == ewma_oracle.source @ MorphoError::InvalidOracle)]
+    pub source: UncheckedAccount<'info>,
+}
+
+/// Blend the source's latest price into the EWMA
+pub fn update_ewma(ctx: Context<UpdateEwma>) -> Result<()> {
+    let clock = Clock::get()?;
+    let ewma = &mut ctx.accounts.ewma_oracle;
+
+    // CHECKS
+    require!(
+        clock.unix_timestamp - ewma.last_update >= EWMA_UPDATE_INTERVAL,
+        MorphoError::EwmaUpdateTooSoon
+    );
+    let reading = read_feed_price(
+        &ctx.accounts.source.to_account_info(),
+        ewma.collateral_decimals,
+        ewma.loan_decimals,
+    )?;
+
+    // EFFECTS
+    ewma.price = ewma.blend(reading)?;
+    ewma.last_update_slot = clock.slot;
+    ewma.last_update = clock.unix_timestamp;
+
+    emit!(EwmaUpdated {
+        ewma_oracle: ewma.key(),
+        reading,
+        price: ewma.price,
+    });
+    Ok(())
+}
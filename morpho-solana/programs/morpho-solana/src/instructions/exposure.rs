@@ -0,0 +1,155 @@
+//! Wallet exposure report
+//!
+//! Values a wallet's positions across several markets in one call, for
+//! portfolio dashboards. Each market is accrued in memory up to now (as in
+//! `dry_run_bundle`), so the figures match what the next real instruction
+//! would see. Nothing is written or emitted; the report comes back as
+//! return data (`ViewResponse::ExposureReport`).
+//!
+//! Everything is in loan-token units. Markets lending different tokens
+//! can't be added up, so totals are kept per loan token.
+
+use anchor_lang::prelude::*;
+use crate::constants::{ORACLE_SCALE, MAX_EXPOSURE_MARKETS};
+use crate::errors::MorphoError;
+use crate::state::{Market, Position};
+use crate::math::{
+    checked_add, mul_div_down, to_assets_down, to_assets_up,
+    accrue_interest_on_market, accrue_stable_debt, view_borrow_rate,
+};
+use crate::instructions::insurance::insurance_premium;
+use crate::instructions::view::{VersionedView, ViewResponse};
+use crate::interfaces::get_oracle_price_validated;
+
+/// One position's exposure, in its market's loan token
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarketExposure {
+    pub market_id: [u8; 32],
+
+    /// Supply shares valued in loan tokens (rounded down)
+    pub supplied: u128,
+
+    /// Variable plus stable debt (rounded up)
+    pub borrowed: u128,
+
+    /// Collateral valued at the market's oracle (rounded down)
+    pub collateral_value: u128,
+
+    /// Interest accrued at the market's cached rate: IRM-program and
+    /// adaptive curve markets can't be resampled from this view
+    pub rate_cached: bool,
+}
+
+impl MarketExposure {
+    /// Value of a position in its (already accrued) market at `oracle_price`
+    pub fn of(market: &Market, position: &Position, oracle_price: u128) -> Result<Self> {
+        Ok(Self {
+            market_id: market.market_id,
+            supplied: to_assets_down(
+                position.supply_shares,
+                market.total_supply_assets,
+                market.total_supply_shares,
+            )?,
+            borrowed: checked_add(
+                to_assets_up(
+                    position.borrow_shares,
+                    market.total_borrow_assets,
+                    market.total_borrow_shares,
+                )?,
+                position.stable_borrow_assets,
+            )?,
+            collateral_value: mul_div_down(position.collateral, oracle_price, ORACLE_SCALE)?,
+            rate_cached: false,
+        })
+    }
+}
+
+/// Exposure summed over every reported market lending `loan_mint`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoanTokenExposure {
+    pub loan_mint: Pubkey,
+    pub supplied: u128,
+    pub borrowed: u128,
+    pub collateral_value: u128,
+}
+
+/// Per-market figures plus per-loan-token totals
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExposureReport {
+    /// In the order the markets were passed
+    pub markets: Vec<MarketExposure>,
+    /// One entry per distinct loan token, in order of first appearance
+    pub totals: Vec<LoanTokenExposure>,
+}
+
+impl ExposureReport {
+    /// Add one market's figures to the report and its loan token's total
+    pub fn add(&mut self, loan_mint: Pubkey, exposure: MarketExposure) -> Result<()> {
+        let index = match self.totals.iter().position(|t| t.loan_mint == loan_mint) {
+            Some(index) => index,
+            None => {
+                self.totals.push(LoanTokenExposure { loan_mint, ..Default::default() });
+                self.totals.len() - 1
+            }
+        };
+        let total = &mut self.totals[index];
+        total.supplied = checked_add(total.supplied, exposure.supplied)?;
+        total.borrowed = checked_add(total.borrowed, exposure.borrowed)?;
+        total.collateral_value = checked_add(total.collateral_value, exposure.collateral_value)?;
+        self.markets.push(exposure);
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Exposure Report
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct GetExposureReport<'info> {
+    /// CHECK: Wallet whose positions are reported; every position must belong to it
+    pub wallet: UncheckedAccount<'info>,
+    // remaining_accounts: (market, position, oracle) for each market reported
+}
+
+/// Report `wallet`'s exposure across the markets in remaining_accounts
+pub fn get_exposure_report<'info>(
+    ctx: Context<'_, '_, 'info, 'info, GetExposureReport<'info>>,
+) -> Result<VersionedView> {
+    let accounts = ctx.remaining_accounts;
+    require!(
+        !accounts.is_empty()
+            && accounts.len() % 3 == 0
+            && accounts.len() / 3 <= MAX_EXPOSURE_MARKETS,
+        MorphoError::InvalidInput
+    );
+
+    let clock = Clock::get()?;
+    let mut report = ExposureReport::default();
+    for triple in accounts.chunks(3) {
+        let mut market = Account::<Market>::try_from(&triple[0])?.into_inner();
+        let mut position = Account::<Position>::try_from(&triple[1])?.into_inner();
+        require!(position.owner == ctx.accounts.wallet.key(), MorphoError::InvalidOwner);
+        require!(position.market_id == market.market_id, MorphoError::InvalidMarketId);
+
+        // Same accrual as a real instruction, minus its events
+        let rate = view_borrow_rate(&market, &[])?;
+        accrue_interest_on_market(&mut market, clock.unix_timestamp, rate.borrow_rate)?;
+        if position.insured {
+            let premium = insurance_premium(
+                position.collateral,
+                market.insurance_premium_bps,
+                clock.unix_timestamp - position.insurance_paid_until,
+            )?;
+            position.collateral -= std::cmp::min(premium, position.collateral);
+        }
+        accrue_stable_debt(&market, &mut position, clock.unix_timestamp)?;
+
+        let oracle_price = get_oracle_price_validated(&triple[2], &market)?;
+        let mut exposure = MarketExposure::of(&market, &position, oracle_price)?;
+        exposure.rate_cached = rate.cached;
+        report.add(market.loan_mint, exposure)?;
+    }
+
+    Ok(ViewResponse::ExposureReport(report).into())
+}
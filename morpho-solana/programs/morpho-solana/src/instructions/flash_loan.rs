@@ -1,12 +1,21 @@
 //! Flash loan instruction with lock mechanism
+//!
+//! Markets can opt into allowlist mode, where only program-derived callers
+//! holding a `FlashLoanAllowlistEntry` may take flash loans.
+//!
+//! `flash_loan_multi` draws one loan from several markets of the same loan
+//! mint, locking each with the principal it lent, so a loan isn't capped at
+//! a single market's liquidity. `flash_loan_multi_end` repays every leg.
 
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked};
-use crate::constants::{PROGRAM_SEED_PREFIX, BPS, FLASH_LOAN_FEE_BPS};
+use crate::constants::{PROGRAM_SEED_PREFIX, BPS, FLASH_LOAN_FEE_BPS, MAX_FLASH_LOAN_MARKETS};
 use crate::errors::MorphoError;
-use crate::events::FlashLoan;
-use crate::state::{ProtocolState, Market};
-use crate::math::{checked_add, safe_u128_to_u64, mul_div_up};
+use crate::events::{FlashLoan, FlashLoanAllowlistModeSet, FlashLoanAllowlistUpdated};
+use crate::state::{ProtocolState, Market, MarketAction, FlashLoanAllowlistEntry};
+use crate::math::{checked_add, safe_u128_to_u64, mul_div_up, accrue_market_interest};
+use crate::instructions::utils::check_expected_version;
+use crate::time::current_clock;
 
 #[derive(Accounts)]
 #[instruction(market_id: [u8; 32])]
@@ -43,6 +52,29 @@ pub struct FlashLoanStart<'info> {
     pub loan_mint: InterfaceAccount<'info, Mint>,
 
     pub token_program: Interface<'info, TokenInterface>,
+
+    /// Borrower's allowlist entry (required when the market is in allowlist mode)
+    #[account(
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            FlashLoanAllowlistEntry::SEED,
+            &market_id,
+            borrower.key().as_ref(),
+        ],
+        bump = allowlist_entry.bump,
+    )]
+    pub allowlist_entry: Option<Account<'info, FlashLoanAllowlistEntry>>,
+}
+
+impl FlashLoanStart<'_> {
+    /// Enforce the market's flash loan allowlist, if enabled
+    fn check_allowlist(&self) -> Result<()> {
+        require!(
+            !self.market.flash_loan_allowlist_only || self.allowlist_entry.is_some(),
+            MorphoError::FlashLoanNotAllowlisted
+        );
+        Ok(())
+    }
 }
 
 /// Start a flash loan - transfers tokens out and locks the market
@@ -50,13 +82,17 @@ pub fn flash_loan_start(
     ctx: Context<FlashLoanStart>,
     market_id: [u8; 32],
     amount: u128,
+    expected_version: Option<u16>,
 ) -> Result<()> {
     // ===== CHECKS =====
+    check_expected_version(expected_version)?;
     require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
-    require!(!ctx.accounts.market.paused, MorphoError::MarketPaused);
+    ctx.accounts.market.check_action(MarketAction::FlashLoan)?;
+    require!(ctx.accounts.protocol_state.flash_loans_enabled(), MorphoError::FlashLoansDisabled);
+    ctx.accounts.check_allowlist()?;
     require!(amount > 0, MorphoError::ZeroAmount);
     require!(
-        amount <= ctx.accounts.market.available_liquidity(),
+        amount <= ctx.accounts.market.vault_liquidity(ctx.accounts.loan_vault.amount),
         MorphoError::InsufficientLiquidity
     );
     require!(
@@ -66,8 +102,8 @@ pub fn flash_loan_start(
 
     let market = &mut ctx.accounts.market;
     
-    // Set flash loan lock
-    market.flash_loan_lock = 1;
+    // Set flash loan lock, binding the loan to this borrower and amount
+    market.lock_flash_loan(ctx.accounts.borrower.key(), amount);
 
     // ===== INTERACTIONS =====
     let amount_u64 = safe_u128_to_u64(amount)?;
@@ -129,16 +165,27 @@ pub struct FlashLoanEnd<'info> {
 }
 
 /// End a flash loan - verifies repayment and unlocks the market
+///
+/// Intentionally NOT gated on protocol/market pause: a pause landing between
+/// start and end must never strand the lock. Instead, the end is bound to the
+/// recorded start (same borrower, same principal), so it cannot be used to
+/// unlock someone else's loan or settle for less than was taken out.
 pub fn flash_loan_end(
     ctx: Context<FlashLoanEnd>,
     market_id: [u8; 32],
     borrowed_amount: u128,
+    expected_version: Option<u16>,
 ) -> Result<()> {
     // ===== CHECKS =====
+    check_expected_version(expected_version)?;
     require!(
         ctx.accounts.market.is_flash_loan_active(),
         MorphoError::FlashLoanCallbackFailed
     );
+    require!(
+        ctx.accounts.market.matches_flash_loan(&ctx.accounts.borrower.key(), borrowed_amount),
+        MorphoError::FlashLoanMismatch
+    );
 
     // Calculate required repayment (principal + fee)
     let fee = mul_div_up(borrowed_amount, FLASH_LOAN_FEE_BPS as u128, BPS as u128)?;
@@ -163,12 +210,15 @@ pub fn flash_loan_end(
 
     // ===== EFFECTS (after successful repayment) =====
     let market = &mut ctx.accounts.market;
-    
+
+    // Accrue first so the fee doesn't shift utilization for the elapsed period
+    accrue_market_interest(market, &current_clock(ctx.remaining_accounts)?, ctx.remaining_accounts)?;
+
     // Fee goes to suppliers
     market.total_supply_assets = checked_add(market.total_supply_assets, fee)?;
     
     // Unlock flash loan
-    market.flash_loan_lock = 0;
+    market.unlock_flash_loan();
 
     emit!(FlashLoan {
         market_id,
@@ -176,6 +226,8 @@ pub fn flash_loan_end(
         amount: borrowed_amount,
         fee,
     });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts);
+    crate::telemetry!("flash_loan_end", market_id, amount = borrowed_amount, fee = fee);
 
     Ok(())
 }
@@ -186,12 +238,16 @@ pub fn flash_loan(
     ctx: Context<FlashLoanStart>,
     market_id: [u8; 32],
     amount: u128,
+    expected_version: Option<u16>,
 ) -> Result<()> {
+    check_expected_version(expected_version)?;
     require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
-    require!(!ctx.accounts.market.paused, MorphoError::MarketPaused);
+    ctx.accounts.market.check_action(MarketAction::FlashLoan)?;
+    require!(ctx.accounts.protocol_state.flash_loans_enabled(), MorphoError::FlashLoansDisabled);
+    ctx.accounts.check_allowlist()?;
     require!(amount > 0, MorphoError::ZeroAmount);
     require!(
-        amount <= ctx.accounts.market.available_liquidity(),
+        amount <= ctx.accounts.market.vault_liquidity(ctx.accounts.loan_vault.amount),
         MorphoError::InsufficientLiquidity
     );
 
@@ -232,8 +288,9 @@ pub fn flash_loan(
         MorphoError::FlashLoanNotRepaid
     );
 
-    // Fee to suppliers
+    // Fee to suppliers (accrue first so it doesn't shift elapsed-period utilization)
     let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &current_clock(ctx.remaining_accounts)?, ctx.remaining_accounts)?;
     market.total_supply_assets = checked_add(market.total_supply_assets, fee)?;
 
     emit!(FlashLoan {
@@ -242,6 +299,367 @@ pub fn flash_loan(
         amount,
         fee,
     });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts);
+    crate::telemetry!("flash_loan", market_id, amount = amount, fee = fee);
+
+    Ok(())
+}
+
+// ============================================================================
+// Multi-Market Flash Loan
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct FlashLoanMulti<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        constraint = borrower_token_account.mint == loan_mint.key() @ MorphoError::InvalidMint,
+    )]
+    pub borrower_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    // remaining_accounts: (market, loan vault) for each market to draw from,
+    // both writable, then any IRM accounts their accrual needs
+}
+
+#[derive(Accounts)]
+pub struct FlashLoanMultiEnd<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = borrower_token_account.mint == loan_mint.key() @ MorphoError::InvalidMint,
+    )]
+    pub borrower_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    // remaining_accounts: as for `flash_loan_multi`
+}
+
+/// One market a multi-market flash loan draws from
+struct FlashLoanLeg<'info> {
+    market: Account<'info, Market>,
+    loan_vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+/// Load the leading (market, loan vault) pairs of `accounts`
+///
+/// Pairs run until the first account that isn't a Market, so IRM accounts
+/// can follow. Markets must lend `loan_mint` and appear once.
+fn load_flash_loan_legs<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    loan_mint: &Pubkey,
+) -> Result<Vec<FlashLoanLeg<'info>>> {
+    let mut legs: Vec<FlashLoanLeg> = Vec::new();
+    for pair in accounts.chunks(2) {
+        let [market_info, vault_info] = pair else { break };
+        if market_info.owner != &crate::ID || !market_info.try_borrow_data()?.starts_with(Market::DISCRIMINATOR) {
+            break;
+        }
+        require!(market_info.is_writable && vault_info.is_writable, MorphoError::InvalidInput);
+
+        let market = Account::<Market>::try_from(market_info)?;
+        require!(market.loan_mint == *loan_mint, MorphoError::InvalidMint);
+        require!(
+            legs.iter().all(|leg| leg.market.key() != market.key()),
+            MorphoError::InvalidInput
+        );
+        let loan_vault = Pubkey::create_program_address(
+            &[PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market.market_id, &[market.loan_vault_bump]],
+            &crate::ID,
+        )
+        .map_err(|_| MorphoError::InvalidInput)?;
+        require!(vault_info.key() == loan_vault, MorphoError::InvalidInput);
+
+        legs.push(FlashLoanLeg {
+            market,
+            loan_vault: InterfaceAccount::<TokenAccount>::try_from(vault_info)?,
+        });
+    }
+    require!(
+        !legs.is_empty() && legs.len() <= MAX_FLASH_LOAN_MARKETS,
+        MorphoError::InvalidInput
+    );
+    Ok(legs)
+}
+
+/// Principal drawn from each market: in order, each lending up to the
+/// liquidity its vault holds until `amount` is covered
+pub fn split_flash_loan(amount: u128, liquidity: &[u128]) -> Result<Vec<u128>> {
+    let mut remaining = amount;
+    let principals = liquidity
+        .iter()
+        .map(|&available| {
+            let principal = std::cmp::min(remaining, available);
+            remaining -= principal;
+            principal
+        })
+        .collect();
+    require!(remaining == 0, MorphoError::InsufficientLiquidity);
+    Ok(principals)
+}
+
+/// Start a flash loan of `amount` drawn from several markets
+///
+/// Each market that lends is locked with its own principal, exactly as
+/// `flash_loan_start` would lock it, and is repaid with its own fee by
+/// `flash_loan_multi_end` (or per market by `flash_loan_end`). Markets past
+/// the point the amount is covered lend nothing and stay unlocked.
+/// Allowlist-mode markets only lend through `flash_loan_start`.
+pub fn flash_loan_multi<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FlashLoanMulti<'info>>,
+    amount: u128,
+    expected_version: Option<u16>,
+) -> Result<()> {
+    // ===== CHECKS =====
+    check_expected_version(expected_version)?;
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    require!(ctx.accounts.protocol_state.flash_loans_enabled(), MorphoError::FlashLoansDisabled);
+    require!(amount > 0, MorphoError::ZeroAmount);
+
+    let mut legs = load_flash_loan_legs(ctx.remaining_accounts, &ctx.accounts.loan_mint.key())?;
+    for leg in &legs {
+        leg.market.check_action(MarketAction::FlashLoan)?;
+        require!(!leg.market.flash_loan_allowlist_only, MorphoError::FlashLoanNotAllowlisted);
+        require!(!leg.market.is_flash_loan_active(), MorphoError::FlashLoanInProgress);
+    }
+    let liquidity: Vec<u128> = legs
+        .iter()
+        .map(|leg| leg.market.vault_liquidity(leg.loan_vault.amount))
+        .collect();
+    let principals = split_flash_loan(amount, &liquidity)?;
+
+    let borrower = ctx.accounts.borrower.key();
+    for (leg, principal) in legs.iter_mut().zip(principals) {
+        if principal == 0 {
+            continue;
+        }
+
+        // ===== EFFECTS =====
+        leg.market.lock_flash_loan(borrower, principal);
+
+        // ===== INTERACTIONS =====
+        let market_id = leg.market.market_id;
+        let seeds = &[
+            PROGRAM_SEED_PREFIX,
+            Market::SEED,
+            market_id.as_ref(),
+            &[leg.market.bump],
+        ];
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: leg.loan_vault.to_account_info(),
+                    to: ctx.accounts.borrower_token_account.to_account_info(),
+                    authority: leg.market.to_account_info(),
+                    mint: ctx.accounts.loan_mint.to_account_info(),
+                },
+                &[seeds],
+            ),
+            safe_u128_to_u64(principal)?,
+            ctx.accounts.loan_mint.decimals,
+        )?;
+        leg.market.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
 
+/// End a multi-market flash loan: repay every locked leg its principal plus
+/// fee and unlock it
+///
+/// Like `flash_loan_end`, not gated on pause. Legs not locked (lent nothing)
+/// are skipped; a locked leg must be the borrower's.
+pub fn flash_loan_multi_end<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FlashLoanMultiEnd<'info>>,
+    expected_version: Option<u16>,
+) -> Result<()> {
+    check_expected_version(expected_version)?;
+    let mut legs = load_flash_loan_legs(ctx.remaining_accounts, &ctx.accounts.loan_mint.key())?;
+    let borrower = ctx.accounts.borrower.key();
+    let clock = current_clock(ctx.remaining_accounts)?;
+
+    let mut repaid = false;
+    for leg in legs.iter_mut() {
+        // ===== CHECKS =====
+        if !leg.market.is_flash_loan_active() {
+            continue;
+        }
+        require!(leg.market.flash_loan_borrower == borrower, MorphoError::FlashLoanMismatch);
+        let principal = leg.market.flash_loan_amount;
+        let fee = mul_div_up(principal, FLASH_LOAN_FEE_BPS as u128, BPS as u128)?;
+
+        // ===== INTERACTIONS =====
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.borrower_token_account.to_account_info(),
+                    to: leg.loan_vault.to_account_info(),
+                    authority: ctx.accounts.borrower.to_account_info(),
+                    mint: ctx.accounts.loan_mint.to_account_info(),
+                },
+            ),
+            safe_u128_to_u64(checked_add(principal, fee)?)?,
+            ctx.accounts.loan_mint.decimals,
+        )?;
+
+        // ===== EFFECTS (after successful repayment) =====
+        let market = &mut leg.market;
+        accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+        market.total_supply_assets = checked_add(market.total_supply_assets, fee)?;
+        market.unlock_flash_loan();
+        market.exit(&crate::ID)?;
+        repaid = true;
+
+        emit!(FlashLoan {
+            market_id: market.market_id,
+            borrower,
+            amount: principal,
+            fee,
+        });
+        crate::audit!(leg.market, ctx.remaining_accounts);
+        crate::telemetry!("flash_loan_multi_end", leg.market.market_id, amount = principal, fee = fee);
+    }
+    require!(repaid, MorphoError::FlashLoanCallbackFailed);
+
+    Ok(())
+}
+
+// ============================================================================
+// Flash Loan Allowlist
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetFlashLoanAllowlistMode<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+pub fn set_flash_loan_allowlist_mode(
+    ctx: Context<SetFlashLoanAllowlistMode>,
+    market_id: [u8; 32],
+    allowlist_only: bool,
+) -> Result<()> {
+    ctx.accounts.market.flash_loan_allowlist_only = allowlist_only;
+    emit!(FlashLoanAllowlistModeSet { market_id, allowlist_only });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32], borrower: Pubkey)]
+pub struct AllowFlashLoanBorrower<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = FlashLoanAllowlistEntry::space(),
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            FlashLoanAllowlistEntry::SEED,
+            &market_id,
+            borrower.as_ref(),
+        ],
+        bump,
+    )]
+    pub allowlist_entry: Account<'info, FlashLoanAllowlistEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Allow a program-derived caller to take flash loans in allowlist mode
+pub fn allow_flash_loan_borrower(
+    ctx: Context<AllowFlashLoanBorrower>,
+    market_id: [u8; 32],
+    borrower: Pubkey,
+) -> Result<()> {
+    require!(!borrower.is_on_curve(), MorphoError::FlashLoanBorrowerNotPda);
+
+    let entry = &mut ctx.accounts.allowlist_entry;
+    entry.bump = ctx.bumps.allowlist_entry;
+    entry.market_id = market_id;
+    entry.borrower = borrower;
+
+    emit!(FlashLoanAllowlistUpdated { market_id, borrower, allowed: true });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32], borrower: Pubkey)]
+pub struct DisallowFlashLoanBorrower<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            FlashLoanAllowlistEntry::SEED,
+            &market_id,
+            borrower.as_ref(),
+        ],
+        bump = allowlist_entry.bump,
+    )]
+    pub allowlist_entry: Account<'info, FlashLoanAllowlistEntry>,
+}
+
+pub fn disallow_flash_loan_borrower(
+    _ctx: Context<DisallowFlashLoanBorrower>,
+    market_id: [u8; 32],
+    borrower: Pubkey,
+) -> Result<()> {
+    emit!(FlashLoanAllowlistUpdated { market_id, borrower, allowed: false });
     Ok(())
 }
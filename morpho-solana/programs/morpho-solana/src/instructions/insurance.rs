@@ -0,0 +1,324 @@
+//! Liquidation insurance
+//!
+//! Opt-in per position. Insured positions pay a yearly premium out of their
+//! collateral into the market's insurance pool; premiums are charged
+//! whenever the position is touched (or by anyone via
+//! `charge_insurance_premium`). When an insured position is liquidated, the
+//! pool reimburses `insurance_coverage_bps` of the liquidation penalty, which
+//! the owner then withdraws with `claim_insurance`.
+//!
+//! Premiums, the pool and pending claims are all collateral tokens that stay
+//! in the collateral vault until claimed.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked};
+use crate::constants::{PROGRAM_SEED_PREFIX, BPS, LIF_BPS, SECONDS_PER_YEAR, MAX_INSURANCE_PREMIUM_BPS};
+use crate::errors::MorphoError;
+use crate::events::{
+    InsuranceParamsSet, PositionInsuranceSet, InsurancePremiumCharged,
+    InsuranceReimbursed, InsuranceClaimed,
+};
+use crate::state::{ProtocolState, Market, Position};
+use crate::math::{checked_add, checked_sub, mul_div_down, safe_u128_to_u64};
+use crate::time::current_clock;
+
+/// Premium owed on `collateral` after `elapsed` seconds at `premium_bps` a year
+pub fn insurance_premium(collateral: u128, premium_bps: u64, elapsed: i64) -> Result<u128> {
+    if elapsed <= 0 {
+        return Ok(0);
+    }
+    mul_div_down(
+        collateral,
+        premium_bps as u128 * elapsed as u128,
+        BPS as u128 * SECONDS_PER_YEAR,
+    )
+}
+
+/// Collateral a liquidation seized on top of the repaid value (the incentive)
+pub fn liquidation_penalty(seized_collateral: u128, lif: u64) -> Result<u128> {
+    let repaid_value = mul_div_down(seized_collateral, LIF_BPS as u128, lif as u128)?;
+    checked_sub(seized_collateral, repaid_value)
+}
+
+/// Move the premium `position` owes since its last charge into the pool
+///
+/// A no-op for uninsured positions. Never takes more than the collateral.
+pub fn charge_insurance_premium(market: &mut Market, position: &mut Position, now: i64) -> Result<u128> {
+    if !position.insured {
+        return Ok(0);
+    }
+
+    let premium = insurance_premium(
+        position.collateral,
+        market.insurance_premium_bps,
+        now - position.insurance_paid_until,
+    )?;
+    let premium = std::cmp::min(premium, position.collateral);
+    position.insurance_paid_until = std::cmp::max(position.insurance_paid_until, now);
+    if premium == 0 {
+        return Ok(0);
+    }
+
+    position.collateral = checked_sub(position.collateral, premium)?;
+    market.total_collateral = market.total_collateral.saturating_sub(premium);
+    market.insurance_pool = checked_add(market.insurance_pool, premium)?;
+
+    emit!(InsurancePremiumCharged {
+        market_id: market.market_id,
+        owner: position.owner,
+        premium,
+        insurance_pool: market.insurance_pool,
+    });
+    Ok(premium)
+}
+
+/// Set aside the covered share of an insured position's liquidation penalty
+///
+/// Paid from the pool as far as it goes; returns the amount reimbursed.
+pub fn reimburse_liquidation_penalty(
+    market: &mut Market,
+    position: &mut Position,
+    seized_collateral: u128,
+    lif: u64,
+) -> Result<u128> {
+    if !position.insured || market.insurance_coverage_bps == 0 {
+        return Ok(0);
+    }
+
+    let penalty = liquidation_penalty(seized_collateral, lif)?;
+    let covered = mul_div_down(penalty, market.insurance_coverage_bps as u128, BPS as u128)?;
+    let reimbursed = std::cmp::min(covered, market.insurance_pool);
+    if reimbursed == 0 {
+        return Ok(0);
+    }
+
+    market.insurance_pool = checked_sub(market.insurance_pool, reimbursed)?;
+    position.insurance_claimable = checked_add(position.insurance_claimable, reimbursed)?;
+    market.total_insurance_claimable = checked_add(market.total_insurance_claimable, reimbursed)?;
+
+    emit!(InsuranceReimbursed {
+        market_id: market.market_id,
+        owner: position.owner,
+        penalty,
+        reimbursed,
+    });
+    Ok(reimbursed)
+}
+
+// ============================================================================
+// Set Insurance Params
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetInsuranceParams<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Set the yearly premium and penalty coverage (premium 0 stops new opt-ins)
+///
+/// A new premium only applies from each position's next charge onwards.
+pub fn set_insurance_params(
+    ctx: Context<SetInsuranceParams>,
+    market_id: [u8; 32],
+    premium_bps: u64,
+    coverage_bps: u64,
+) -> Result<()> {
+    require!(premium_bps <= MAX_INSURANCE_PREMIUM_BPS, MorphoError::InvalidInput);
+    require!(coverage_bps <= BPS, MorphoError::InvalidInput);
+
+    let market = &mut ctx.accounts.market;
+    market.insurance_premium_bps = premium_bps;
+    market.insurance_coverage_bps = coverage_bps;
+
+    emit!(InsuranceParamsSet {
+        market_id,
+        premium_bps,
+        coverage_bps,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Set Position Insurance
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetPositionInsurance<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, owner.key().as_ref()],
+        bump = position.bump,
+        constraint = position.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub position: Account<'info, Position>,
+}
+
+/// Opt in or out of liquidation insurance
+///
+/// Opting out settles premiums owed so far; opting in starts the clock.
+pub fn set_position_insurance(
+    ctx: Context<SetPositionInsurance>,
+    market_id: [u8; 32],
+    insured: bool,
+) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    let position = &mut ctx.accounts.position;
+    if insured && !position.insured {
+        require!(market.insurance_premium_bps > 0, MorphoError::InsuranceNotOffered);
+    }
+
+    let now = current_clock(ctx.remaining_accounts)?.unix_timestamp;
+    charge_insurance_premium(market, position, now)?;
+    position.insured = insured;
+    position.insurance_paid_until = now;
+
+    emit!(PositionInsuranceSet {
+        market_id,
+        owner: position.owner,
+        insured,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Charge Insurance Premium (Public)
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct ChargeInsurancePremium<'info> {
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+}
+
+pub fn charge_insurance_premium_ix(
+    ctx: Context<ChargeInsurancePremium>,
+    _market_id: [u8; 32],
+) -> Result<()> {
+    let now = current_clock(ctx.remaining_accounts)?.unix_timestamp;
+    charge_insurance_premium(&mut ctx.accounts.market, &mut ctx.accounts.position, now)?;
+    Ok(())
+}
+
+// ============================================================================
+// Claim Insurance
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct ClaimInsurance<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, owner.key().as_ref()],
+        bump = position.bump,
+        constraint = position.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    #[account(
+        mut,
+        constraint = receiver_token_account.mint == market.collateral_mint,
+    )]
+    pub receiver_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::COLLATERAL_VAULT_SEED, &market_id],
+        bump = market.collateral_vault_bump,
+    )]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Withdraw reimbursed liquidation penalties
+pub fn claim_insurance(ctx: Context<ClaimInsurance>, market_id: [u8; 32]) -> Result<()> {
+    // ===== CHECKS =====
+    let amount = ctx.accounts.position.insurance_claimable;
+    require!(amount > 0, MorphoError::NoInsuranceClaim);
+
+    // ===== EFFECTS =====
+    ctx.accounts.position.insurance_claimable = 0;
+    ctx.accounts.market.total_insurance_claimable =
+        ctx.accounts.market.total_insurance_claimable.saturating_sub(amount);
+
+    // ===== INTERACTIONS =====
+    let amount_u64 = safe_u128_to_u64(amount)?;
+    let bump = ctx.accounts.market.bump;
+    let seeds = &[
+        PROGRAM_SEED_PREFIX,
+        Market::SEED,
+        market_id.as_ref(),
+        &[bump],
+    ];
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.collateral_vault.to_account_info(),
+                to: ctx.accounts.receiver_token_account.to_account_info(),
+                authority: ctx.accounts.market.to_account_info(),
+                mint: ctx.accounts.collateral_mint.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount_u64,
+        ctx.accounts.collateral_mint.decimals,
+    )?;
+
+    emit!(InsuranceClaimed {
+        market_id,
+        owner: ctx.accounts.owner.key(),
+        receiver: ctx.accounts.receiver_token_account.key(),
+        amount,
+    });
+    Ok(())
+}
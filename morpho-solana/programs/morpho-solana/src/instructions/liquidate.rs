@@ -2,19 +2,24 @@
 
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked};
-use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::constants::{PROGRAM_SEED_PREFIX, BPS};
 use crate::errors::MorphoError;
-use crate::events::{Liquidation, BadDebtRealized};
-use crate::state::{Market, Position};
+use crate::events::{Liquidation, LiquidationOperatorPaid, BadDebtRealized, BadDebtRebatePaid, SharePriceFloorBreached};
+use crate::state::{LiquidationReference, Market, MarketAction, Position};
 use crate::math::{
-    checked_sub, safe_u128_to_u64,
-    to_shares_down, to_assets_up,
-    accrue_interest_on_market,
+    checked_add, checked_sub, safe_u128_to_u64, mul_div_down,
+    to_shares_down, to_assets_up, to_assets_down,
+    accrue_market_interest, accrue_stable_debt,
 };
+use crate::instructions::divergence::check_liquidation_price;
+use crate::instructions::insurance::{charge_insurance_premium, reimburse_liquidation_penalty};
+use crate::instructions::reputation::sync_reputation_boost;
 use crate::interfaces::{
-    get_borrow_rate_internal, get_oracle_price_validated, 
-    is_liquidatable, calculate_lif, calculate_seized_collateral, socialize_bad_debt,
+    get_oracle_price_with_fallback, read_feed_price,
+    is_liquidatable_with_stable_debt, calculate_lif, calculate_seized_collateral, seize_within_max_lif,
+    socialize_bad_debt, socialize_stable_bad_debt,
 };
+use crate::time::current_clock;
 
 #[derive(Accounts)]
 #[instruction(market_id: [u8; 32])]
@@ -72,44 +77,128 @@ pub struct Liquidate<'info> {
     pub collateral_mint: InterfaceAccount<'info, Mint>,
 
     pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Market's LiquidationReference PDA; may be uninitialized (no check)
+    #[account(seeds = [PROGRAM_SEED_PREFIX, LiquidationReference::SEED, &market_id], bump)]
+    pub liquidation_reference: UncheckedAccount<'info>,
+
+    /// CHECK: Market's reference oracle, required while a reference is set
+    pub reference_oracle: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Market's fallback oracle, read only if the primary fails
+    pub fallback_oracle: Option<UncheckedAccount<'info>>,
+
+    /// Receives `operator_share_bps` of the seized collateral (e.g. a
+    /// liquidation service running the liquidator)
+    #[account(
+        mut,
+        constraint = operator_collateral_account.mint == market.collateral_mint,
+    )]
+    pub operator_collateral_account: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+/// Split `seized_collateral` into (liquidator, operator) parts
+///
+/// The operator's part rounds down, so the liquidator keeps any dust.
+pub fn split_seized_collateral(seized_collateral: u128, operator_share_bps: u64) -> Result<(u128, u128)> {
+    require!(operator_share_bps <= BPS, MorphoError::InvalidOperatorShare);
+    let operator = mul_div_down(seized_collateral, operator_share_bps as u128, BPS as u128)?;
+    Ok((checked_sub(seized_collateral, operator)?, operator))
+}
+
+/// Fee shares to burn, and loan tokens they're worth, to rebate a
+/// liquidator whose liquidation realized `bad_debt`
+///
+/// `bad_debt_rebate_bps` of the bad debt, capped by the market's pending
+/// fee shares and by what the liquidator repaid. Call after socializing, so
+/// the shares are valued at the post-loss rate.
+pub fn bad_debt_rebate(market: &Market, bad_debt: u128, repaid_assets: u128) -> Result<(u128, u128)> {
+    let target = mul_div_down(bad_debt, market.bad_debt_rebate_bps as u128, BPS as u128)?;
+    let target = std::cmp::min(target, repaid_assets);
+    if target == 0 || market.pending_fee_shares == 0 {
+        return Ok((0, 0));
+    }
+
+    // Round against the liquidator at both steps
+    let shares = to_shares_down(target, market.total_supply_assets, market.total_supply_shares)?;
+    let shares = std::cmp::min(shares, market.pending_fee_shares);
+    let assets = to_assets_down(shares, market.total_supply_assets, market.total_supply_shares)?;
+    Ok((shares, assets))
+}
+
+/// Flip the market to withdraw-only if socialized losses pushed its supply
+/// share price under the floor
+///
+/// Call after every socialization. Returns whether it tripped just now.
+pub fn enforce_share_price_floor(market: &mut Market) -> Result<bool> {
+    if market.share_price_floor == 0 || market.withdraw_only {
+        return Ok(false);
+    }
+    let supply_share_price = market.supply_share_price()?;
+    if supply_share_price >= market.share_price_floor {
+        return Ok(false);
+    }
+
+    market.withdraw_only = true;
+    emit!(SharePriceFloorBreached {
+        market_id: market.market_id,
+        supply_share_price,
+        floor: market.share_price_floor,
+    });
+    Ok(true)
 }
 
 pub fn liquidate(
     ctx: Context<Liquidate>,
     market_id: [u8; 32],
     seized_assets: u128,  // Amount of loan tokens the liquidator wants to repay
+    operator_share_bps: u64,
 ) -> Result<()> {
     // ===== CHECKS =====
     // Note: Liquidation allowed even when paused (maintains protocol health)
+    ctx.accounts.market.check_action(MarketAction::Liquidate)?;
     require!(seized_assets > 0, MorphoError::ZeroAmount);
+    require!(
+        operator_share_bps == 0 || ctx.accounts.operator_collateral_account.is_some(),
+        MorphoError::InvalidOperatorShare
+    );
 
     // Accrue interest
-    let borrow_rate = get_borrow_rate_internal(
-        ctx.accounts.market.total_supply_assets,
-        ctx.accounts.market.total_borrow_assets,
-    )?;
-    let current_time = Clock::get()?.unix_timestamp;
-    
+    let clock = current_clock(ctx.remaining_accounts)?;
+
     let market = &mut ctx.accounts.market;
-    accrue_interest_on_market(market, current_time, borrow_rate)?;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+    charge_insurance_premium(market, &mut ctx.accounts.borrower_position, clock.unix_timestamp)?;
+    accrue_stable_debt(market, &mut ctx.accounts.borrower_position, clock.unix_timestamp)?;
 
     let position = &ctx.accounts.borrower_position;
 
-    // Get validated oracle price
-    let oracle_price = get_oracle_price_validated(
+    // Get validated oracle price, from the fallback if the primary is down
+    let oracle_price = get_oracle_price_with_fallback(
         &ctx.accounts.oracle.to_account_info(),
+        ctx.accounts.fallback_oracle.as_ref().map(|o| o.as_ref()),
         market,
     )?;
 
+    // A second feed must back the price, if the owner set one
+    if let Some(reference) = LiquidationReference::try_load(&ctx.accounts.liquidation_reference)? {
+        let reference_oracle = ctx.accounts.reference_oracle.as_ref()
+            .filter(|oracle| oracle.key() == reference.reference_oracle)
+            .ok_or(MorphoError::InvalidOracle)?;
+        let reference_price = read_feed_price(reference_oracle, market.collateral_decimals, market.loan_decimals)?;
+        check_liquidation_price(&reference, oracle_price, reference_price)?;
+    }
+
     // Verify position is liquidatable
     require!(
-        is_liquidatable(
+        is_liquidatable_with_stable_debt(
             position.collateral,
             position.borrow_shares,
+            position.stable_borrow_assets,
             market.total_borrow_assets,
             market.total_borrow_shares,
             oracle_price,
-            market.lltv,
+            market.position_lltv(position, clock.unix_timestamp),
         )?,
         MorphoError::PositionHealthy
     );
@@ -124,19 +213,56 @@ pub fn liquidate(
     let repaid_shares = std::cmp::min(repaid_shares, position.borrow_shares);
     let actual_seized_assets = to_assets_up(repaid_shares, market.total_borrow_assets, market.total_borrow_shares)?;
 
+    // Whatever the variable debt doesn't absorb repays stable debt
+    let stable_repaid = std::cmp::min(
+        seized_assets.saturating_sub(actual_seized_assets),
+        position.stable_borrow_assets,
+    );
+    let repaid_assets = checked_add(actual_seized_assets, stable_repaid)?;
+
+    // Sanity bound on the seize, independent of how the LIF was derived
+    require!(
+        seize_within_max_lif(seized_collateral, repaid_assets, oracle_price)?,
+        MorphoError::LiquidationMathSuspect
+    );
+
+    // Partial liquidations by a different liquidator wait out the cooldown;
+    // clearing all debt or all collateral closes the position and may go now
+    let full_close = seized_collateral == position.collateral
+        || (repaid_shares == position.borrow_shares && stable_repaid == position.stable_borrow_assets);
+    require!(
+        full_close || !position.liquidation_cooling_down(&ctx.accounts.liquidator.key(), clock.slot),
+        MorphoError::LiquidationCooldown
+    );
+
     // ===== EFFECTS =====
     let position = &mut ctx.accounts.borrower_position;
     position.borrow_shares = checked_sub(position.borrow_shares, repaid_shares)?;
+    if stable_repaid > 0 {
+        position.stable_borrow_assets = checked_sub(position.stable_borrow_assets, stable_repaid)?;
+        market.remove_stable_debt(stable_repaid, position.stable_rate, position.stable_borrow_assets == 0)?;
+    }
     position.collateral = checked_sub(position.collateral, seized_collateral)?;
+    market.total_collateral = market.total_collateral.saturating_sub(seized_collateral);
+    position.times_liquidated = position.times_liquidated.saturating_add(1);
+    position.last_liquidation_slot = clock.slot;
+    position.last_liquidator = ctx.accounts.liquidator.key();
 
     market.total_borrow_shares = checked_sub(market.total_borrow_shares, repaid_shares)?;
     market.total_borrow_assets = checked_sub(market.total_borrow_assets, actual_seized_assets)?;
+    market.total_liquidated_collateral = checked_add(market.total_liquidated_collateral, seized_collateral)?;
 
     // Bad debt handling: if no collateral left but still has debt
-    if position.collateral == 0 && position.borrow_shares > 0 {
+    let mut rebate = 0;
+    if position.collateral == 0 && position.has_debt() {
         let remaining_shares = position.borrow_shares;
-        let bad_debt = socialize_bad_debt(market, remaining_shares)?;
+        let bad_debt = checked_add(
+            socialize_bad_debt(market, remaining_shares)?,
+            socialize_stable_bad_debt(market, position.stable_borrow_assets, position.stable_rate)?,
+        )?;
         position.borrow_shares = 0;
+        position.stable_borrow_assets = 0;
+        enforce_share_price_floor(market)?;
 
         emit!(BadDebtRealized {
             market_id,
@@ -144,11 +270,33 @@ pub fn liquidate(
             bad_debt_assets: bad_debt,
             bad_debt_shares: remaining_shares,
         });
+
+        // Optional rebate for cleaning it up: burn pending fee shares and
+        // net their value off what the liquidator pays in
+        let (fee_shares, assets) = bad_debt_rebate(market, bad_debt, repaid_assets)?;
+        if fee_shares > 0 {
+            market.pending_fee_shares = checked_sub(market.pending_fee_shares, fee_shares)?;
+            market.total_supply_shares = checked_sub(market.total_supply_shares, fee_shares)?;
+            market.total_supply_assets = checked_sub(market.total_supply_assets, assets)?;
+            rebate = assets;
+
+            emit!(BadDebtRebatePaid {
+                market_id,
+                liquidator: ctx.accounts.liquidator.key(),
+                fee_shares,
+                assets,
+            });
+        }
+    }
+    reimburse_liquidation_penalty(market, position, seized_collateral, lif)?;
+    if position.stable_borrow_assets == 0 {
+        position.stable_rate = 0;
     }
+    sync_reputation_boost(market, position, clock.unix_timestamp);
 
     // ===== INTERACTIONS =====
-    // Liquidator repays loan tokens
-    let repay_amount = safe_u128_to_u64(actual_seized_assets)?;
+    // Liquidator repays loan tokens, less any bad debt rebate
+    let repay_amount = safe_u128_to_u64(checked_sub(repaid_assets, rebate)?)?;
     transfer_checked(
         CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
@@ -163,8 +311,9 @@ pub fn liquidate(
         ctx.accounts.loan_mint.decimals,
     )?;
 
-    // Liquidator receives collateral
-    let collateral_amount = safe_u128_to_u64(seized_collateral)?;
+    // Liquidator receives collateral, less the operator's share
+    let (liquidator_collateral, operator_collateral) =
+        split_seized_collateral(seized_collateral, operator_share_bps)?;
     let bump = market.bump;
     let seeds = &[
         PROGRAM_SEED_PREFIX,
@@ -184,18 +333,120 @@ pub fn liquidate(
             },
             &[seeds],
         ),
-        collateral_amount,
+        safe_u128_to_u64(liquidator_collateral)?,
         ctx.accounts.collateral_mint.decimals,
     )?;
 
+    if let (Some(operator_account), true) = (&ctx.accounts.operator_collateral_account, operator_collateral > 0) {
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.collateral_vault.to_account_info(),
+                    to: operator_account.to_account_info(),
+                    authority: ctx.accounts.market.to_account_info(),
+                    mint: ctx.accounts.collateral_mint.to_account_info(),
+                },
+                &[seeds],
+            ),
+            safe_u128_to_u64(operator_collateral)?,
+            ctx.accounts.collateral_mint.decimals,
+        )?;
+
+        emit!(LiquidationOperatorPaid {
+            market_id,
+            liquidator: ctx.accounts.liquidator.key(),
+            operator_collateral_account: operator_account.key(),
+            operator_share_bps,
+            collateral: operator_collateral,
+        });
+    }
+
     emit!(Liquidation {
         market_id,
         liquidator: ctx.accounts.liquidator.key(),
         borrower: ctx.accounts.borrower.key(),
-        repaid_assets: actual_seized_assets,
+        repaid_assets,
         repaid_shares,
         seized_collateral,
     });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.borrower_position);
+    crate::telemetry!(
+        "liquidate",
+        market_id,
+        repaid = repaid_assets,
+        seized = seized_collateral,
+    );
+
+    Ok(())
+}
+
+// ============================================================================
+// Settle Bad Debt (permissionless)
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SettleBadDebt<'info> {
+    /// Anyone
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, borrower.key().as_ref()],
+        bump = borrower_position.bump,
+    )]
+    pub borrower_position: Box<Account<'info, Position>>,
+
+    /// CHECK: Borrower whose debt is settled
+    pub borrower: UncheckedAccount<'info>,
+}
+
+/// Socialize the debt of a position with no collateral left
+///
+/// Covers positions drained outside the liquidation path (e.g. by insurance
+/// premiums or dust sweeps), whose debt nobody could otherwise clear.
+/// Accounts for it exactly as a liquidation ending in bad debt does, minus
+/// the rebate: there's no liquidator paying anything in.
+pub fn settle_bad_debt(ctx: Context<SettleBadDebt>, market_id: [u8; 32]) -> Result<()> {
+    // ===== CHECKS =====
+    ctx.accounts.market.check_action(MarketAction::Liquidate)?;
+
+    let clock = current_clock(ctx.remaining_accounts)?;
+    let market = &mut ctx.accounts.market;
+    let position = &mut ctx.accounts.borrower_position;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+    charge_insurance_premium(market, position, clock.unix_timestamp)?;
+    accrue_stable_debt(market, position, clock.unix_timestamp)?;
+    require!(position.collateral == 0 && position.has_debt(), MorphoError::NotBadDebt);
+
+    // ===== EFFECTS =====
+    let remaining_shares = position.borrow_shares;
+    let bad_debt = checked_add(
+        socialize_bad_debt(market, remaining_shares)?,
+        socialize_stable_bad_debt(market, position.stable_borrow_assets, position.stable_rate)?,
+    )?;
+    position.borrow_shares = 0;
+    position.stable_borrow_assets = 0;
+    position.stable_rate = 0;
+    enforce_share_price_floor(market)?;
+    sync_reputation_boost(market, position, clock.unix_timestamp);
+
+    emit!(BadDebtRealized {
+        market_id,
+        borrower: ctx.accounts.borrower.key(),
+        bad_debt_assets: bad_debt,
+        bad_debt_shares: remaining_shares,
+    });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.borrower_position);
+    crate::telemetry!("settle_bad_debt", market_id, bad_debt = bad_debt);
 
     Ok(())
 }
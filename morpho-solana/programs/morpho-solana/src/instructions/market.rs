@@ -1,11 +1,15 @@
-//! Market creation instruction
+//! Market creation and closing instructions
 
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface};
 use crate::constants::PROGRAM_SEED_PREFIX;
 use crate::errors::MorphoError;
-use crate::events::MarketCreated;
-use crate::state::{ProtocolState, Market, calculate_market_id};
+use crate::events::{MarketCreated, MarketClosed};
+use crate::state::{ProtocolState, Market, LltvBounds, TokenBadge, calculate_market_id, is_supply_share_mint};
+use crate::interfaces::{
+    MAX_ORACLE_HEARTBEAT, MAX_ORACLE_AGE_SECS, derive_adaptive_curve_model, derive_pegged_oracle,
+};
+use crate::time::current_clock;
 
 #[derive(Accounts)]
 #[instruction(
@@ -81,10 +85,25 @@ pub struct CreateMarket<'info> {
     #[account(constraint = irm.key() == irm_key)]
     pub irm: UncheckedAccount<'info>,
 
+    /// CHECK: Loan token's LltvBounds PDA; may be uninitialized (no bounds)
+    #[account(seeds = [PROGRAM_SEED_PREFIX, LltvBounds::SEED, loan_mint_key.as_ref()], bump)]
+    pub lltv_bounds: UncheckedAccount<'info>,
+
+    /// CHECK: Collateral token's TokenBadge PDA; may be uninitialized (tier C)
+    #[account(seeds = [PROGRAM_SEED_PREFIX, TokenBadge::SEED, collateral_mint_key.as_ref()], bump)]
+    pub collateral_badge: UncheckedAccount<'info>,
+
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: Secondary price feed, used when `oracle` fails (optional)
+    pub fallback_oracle: Option<UncheckedAccount<'info>>,
 }
 
+/// Create the market for this parameter tuple
+///
+/// Uniqueness is per creation tuple only: a market whose oracle or IRM was
+/// migrated onto this tuple keeps its original id, so both can exist.
 pub fn create_market(
     ctx: Context<CreateMarket>,
     collateral_mint_key: Pubkey,
@@ -92,12 +111,32 @@ pub fn create_market(
     oracle_key: Pubkey,
     irm_key: Pubkey,
     lltv: u64,
+    oracle_heartbeat: u64,
+    max_oracle_age_secs: u64,
 ) -> Result<()> {
     let state = &ctx.accounts.protocol_state;
 
     // Validate LLTV and IRM are whitelisted
     require!(state.is_lltv_enabled(lltv), MorphoError::LltvNotEnabled);
     require!(state.is_irm_enabled(&irm_key), MorphoError::IrmNotEnabled);
+    require!(
+        oracle_heartbeat > 0 && oracle_heartbeat <= MAX_ORACLE_HEARTBEAT,
+        MorphoError::InvalidOracleHeartbeat
+    );
+    require!(max_oracle_age_secs <= MAX_ORACLE_AGE_SECS, MorphoError::InvalidOracleMaxAge);
+    let fallback_oracle = ctx.accounts.fallback_oracle.as_ref().map_or(Pubkey::default(), |o| o.key());
+    require!(fallback_oracle != oracle_key, MorphoError::InvalidFallbackOracle);
+    if let Some(bounds) = LltvBounds::try_load(&ctx.accounts.lltv_bounds)? {
+        require!(bounds.contains(lltv), MorphoError::LltvOutOfBounds);
+    }
+    TokenBadge::load_tier(&ctx.accounts.collateral_badge)?.check_lltv(lltv)?;
+    require!(
+        !is_supply_share_mint(
+            &ctx.accounts.market.key(),
+            ctx.accounts.collateral_mint.mint_authority.into(),
+        ),
+        MorphoError::SelfCollateralization
+    );
 
     let market_id = calculate_market_id(
         &collateral_mint_key,
@@ -123,13 +162,76 @@ pub fn create_market(
     market.total_supply_shares = 0;
     market.total_borrow_assets = 0;
     market.total_borrow_shares = 0;
-    market.last_update = Clock::get()?.unix_timestamp;
+    market.last_update = current_clock(ctx.remaining_accounts)?.unix_timestamp;
     market.pending_fee_shares = 0;
     market.collateral_vault_bump = ctx.bumps.collateral_vault;
     market.loan_vault_bump = ctx.bumps.loan_vault;
     market.flash_loan_lock = 0;
+    market.flash_loan_borrower = Pubkey::default();
+    market.flash_loan_amount = 0;
+    market.collateral_yield_adapter = Pubkey::default();
+    market.collateral_staked = 0;
+    market.max_collateral_staked_bps = 0;
+    market.loan_yield_adapter = Pubkey::default();
+    market.loan_deployed = 0;
+    market.max_loan_deployed_bps = 0;
+    market.curator = Pubkey::default();
+    market.borrow_lltv_buffer = 0;
+    market.rate_cache_slot = 0;
+    market.rate_cache = 0;
+    market.flash_loan_allowlist_only = false;
+    market.oracle_failure_mode = 0;
+    market.risk_oracle = Pubkey::default();
+    market.risk_cap_buffer_bps = 0;
+    market.reference_feeds = [Pubkey::default(); 2];
+    market.max_feed_divergence_bps = 0;
+    market.divergence_frozen = false;
+    market.total_interest_accrued = 0;
+    market.total_fees_accrued = 0;
+    market.total_liquidated_collateral = 0;
+    market.total_bad_debt = 0;
+    market.reputation_lltv_boost_bps = 0;
+    market.reputation_min_repaid = 0;
+    market.insurance_premium_bps = 0;
+    market.insurance_coverage_bps = 0;
+    market.insurance_pool = 0;
+    market.interest_paused_until = 0;
+    market.interest_paused_from = 0;
+    market.stable_borrow_enabled = false;
+    market.stable_rate_spread_bps = 0;
+    market.stable_rebalance_utilization_bps = 0;
+    market.total_stable_borrow_assets = 0;
+    market.avg_stable_rate = 0;
+    market.stable_borrowers = 0;
+    market.creator = ctx.accounts.creator.key();
+    market.oracle_heartbeat = oracle_heartbeat;
+    market.min_supply_holding_period = 0;
+    market.bad_debt_rebate_bps = 0;
+    market.supply_only = false;
+    market.total_collateral = 0;
+    market.total_insurance_claimable = 0;
+    market.max_accrual_growth_bps = 0;
+    market.share_price_floor = 0;
+    market.withdraw_only = false;
+    market.max_oracle_age_secs = max_oracle_age_secs;
+    market.fallback_oracle = fallback_oracle;
+    // Oracle accounts owned by the allowlisted oracle program are priced by CPI
+    market.oracle_program_priced = state.is_oracle_program_enabled(ctx.accounts.oracle.owner);
+    market.oracle_pegged = oracle_key == derive_pegged_oracle(&crate::ID).0;
+    market.normalize_switchboard = false;
+    // A deployed IRM program is queried by CPI, the adaptive curve model
+    // reads the market's curve state; other IRM keys use the built-in curve
+    market.irm_cpi = ctx.accounts.irm.executable;
+    market.irm_adaptive = irm_key == derive_adaptive_curve_model(&crate::ID).0;
+    market.supply_rate_cache = 0;
+    market.utilization_cache = 0;
+    market.irm_failures = 0;
+    market.rate_cache_timestamp = 0;
+    market.reputation_min_age = 0;
 
-    ctx.accounts.protocol_state.market_count += 1;
+    let state = &mut ctx.accounts.protocol_state;
+    market.market_index = state.market_count;
+    state.market_count += 1;
 
     emit!(MarketCreated {
         market_id,
@@ -138,6 +240,99 @@ pub fn create_market(
         oracle: market.oracle,
         irm: market.irm,
         lltv: market.lltv,
+        market_index: market.market_index,
+        oracle_heartbeat,
+        max_oracle_age_secs,
+        fallback_oracle,
+    });
+
+    Ok(())
+}
+
+// ============================================================================
+// Close Empty Market
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct CloseEmptyMarket<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        close = rent_receiver,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+        constraint = market.is_empty() @ MorphoError::MarketNotEmpty,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::COLLATERAL_VAULT_SEED, &market_id],
+        bump = market.collateral_vault_bump,
+        constraint = collateral_vault.amount == 0 @ MorphoError::MarketNotEmpty,
+    )]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+        constraint = loan_vault.amount == 0 @ MorphoError::MarketNotEmpty,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Receives the market and vault rent - can be any account
+    #[account(mut)]
+    pub rent_receiver: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Close a market with nothing in it, refunding market and vault rent
+///
+/// Positions left behind are empty and can still be closed by their owners.
+/// The same market id can be created again afterwards.
+pub fn close_empty_market(ctx: Context<CloseEmptyMarket>, market_id: [u8; 32]) -> Result<()> {
+    let market_index = ctx.accounts.market.market_index;
+    let was_paused = ctx.accounts.market.paused;
+    let seeds = &[
+        PROGRAM_SEED_PREFIX,
+        Market::SEED,
+        market_id.as_ref(),
+        &[ctx.accounts.market.bump],
+    ];
+
+    // Vaults are owned by the market PDA, so it signs their closing
+    for vault in [&ctx.accounts.collateral_vault, &ctx.accounts.loan_vault] {
+        close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: vault.to_account_info(),
+                destination: ctx.accounts.rent_receiver.to_account_info(),
+                authority: ctx.accounts.market.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+    }
+
+    let state = &mut ctx.accounts.protocol_state;
+    state.closed_market_count += 1;
+    state.track_market_pause(was_paused, false);
+
+    emit!(MarketClosed {
+        market_id,
+        rent_receiver: ctx.accounts.rent_receiver.key(),
+        market_index,
     });
 
     Ok(())
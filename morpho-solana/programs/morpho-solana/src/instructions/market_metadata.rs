@@ -0,0 +1,123 @@
+//! Market metadata instructions
+//!
+//! Only the market's creator may publish or change its metadata. Markets
+//! created before the creator was recorded fall back to the protocol owner.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::errors::MorphoError;
+use crate::events::MarketMetadataSet;
+use crate::state::{ProtocolState, Market, MarketMetadata};
+
+/// Whether `signer` may publish metadata for `market`
+fn is_market_creator(market: &Market, protocol_state: &ProtocolState, signer: &Pubkey) -> bool {
+    if market.creator == Pubkey::default() {
+        protocol_state.owner == *signer
+    } else {
+        market.creator == *signer
+    }
+}
+
+// ============================================================================
+// Create Market Metadata
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct CreateMarketMetadata<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+        constraint = is_market_creator(&market, &protocol_state, &creator.key()) @ MorphoError::Unauthorized,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = MarketMetadata::space(),
+        seeds = [PROGRAM_SEED_PREFIX, MarketMetadata::SEED, &market_id],
+        bump,
+    )]
+    pub metadata: Box<Account<'info, MarketMetadata>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_market_metadata(
+    ctx: Context<CreateMarketMetadata>,
+    market_id: [u8; 32],
+    name: String,
+    description_uri: String,
+    curator: Pubkey,
+) -> Result<()> {
+    require!(MarketMetadata::fits(&name, &description_uri), MorphoError::MetadataTooLong);
+
+    let metadata = &mut ctx.accounts.metadata;
+    metadata.bump = ctx.bumps.metadata;
+    metadata.market_id = market_id;
+    metadata.creator = ctx.accounts.creator.key();
+    metadata.name = name;
+    metadata.description_uri = description_uri;
+    metadata.curator = curator;
+
+    emit!(MarketMetadataSet {
+        market_id,
+        creator: metadata.creator,
+        name: metadata.name.clone(),
+        description_uri: metadata.description_uri.clone(),
+        curator,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Update Market Metadata
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct UpdateMarketMetadata<'info> {
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, MarketMetadata::SEED, &market_id],
+        bump = metadata.bump,
+        constraint = metadata.creator == creator.key() @ MorphoError::Unauthorized,
+    )]
+    pub metadata: Box<Account<'info, MarketMetadata>>,
+}
+
+pub fn update_market_metadata(
+    ctx: Context<UpdateMarketMetadata>,
+    market_id: [u8; 32],
+    name: String,
+    description_uri: String,
+    curator: Pubkey,
+) -> Result<()> {
+    require!(MarketMetadata::fits(&name, &description_uri), MorphoError::MetadataTooLong);
+
+    let metadata = &mut ctx.accounts.metadata;
+    metadata.name = name;
+    metadata.description_uri = description_uri;
+    metadata.curator = curator;
+
+    emit!(MarketMetadataSet {
+        market_id,
+        creator: metadata.creator,
+        name: metadata.name.clone(),
+        description_uri: metadata.description_uri.clone(),
+        curator,
+    });
+    Ok(())
+}
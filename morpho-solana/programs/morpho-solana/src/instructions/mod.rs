@@ -2,18 +2,62 @@
 
 pub mod admin;
 pub mod market;
+pub mod market_metadata;
 pub mod position;
 pub mod supply;
 pub mod borrow;
+pub mod adjust;
+pub mod dry_run;
+pub mod exposure;
 pub mod liquidate;
 pub mod flash_loan;
+pub mod yield_adapter;
+pub mod proposal;
+pub mod credit_line;
+pub mod divergence;
+pub mod reputation;
+pub mod insurance;
+pub mod stable_rate;
+pub mod twap;
+pub mod composite;
+pub mod clmm_twap;
+pub mod ewma;
+pub mod adaptive_curve_irm;
+pub mod price_relay;
 pub mod utils;
+pub mod view;
+#[cfg(feature = "devnet")]
+pub mod devnet;
+#[cfg(feature = "multi-collateral")]
+pub mod multi_collateral;
 
 pub use admin::*;
 pub use market::*;
+pub use market_metadata::*;
 pub use position::*;
 pub use supply::*;
 pub use borrow::*;
+pub use adjust::*;
+pub use dry_run::*;
+pub use exposure::*;
 pub use liquidate::*;
 pub use flash_loan::*;
+pub use yield_adapter::*;
+pub use proposal::*;
+pub use credit_line::*;
+pub use divergence::*;
+pub use reputation::*;
+pub use insurance::*;
+pub use stable_rate::*;
+pub use twap::*;
+pub use composite::*;
+pub use clmm_twap::*;
+pub use ewma::*;
+pub use adaptive_curve_irm::*;
+pub use price_relay::*;
 pub use utils::*;
+pub use view::*;
+#[cfg(feature = "devnet")]
+pub use devnet::*;
+#[cfg(feature = "multi-collateral")]
+pub use multi_collateral::*;
@@ -0,0 +1,861 @@
+//! Multi-collateral markets (experimental, enabled with the `multi-collateral` feature)
+//!
+//! One loan token lent against up to MAX_MC_COLLATERALS collateral tokens,
+//! each with its own oracle and LLTV; see `state::multi_collateral`. These
+//! handlers only touch `McMarket`/`McPosition` accounts and their own vaults,
+//! so Blue markets behave the same with or without the feature.
+//!
+//! Kept deliberately small next to Blue markets: positions act only for
+//! their owner, rates come from the built-in curve with no fee, and there
+//! are no flash loans, yield adapters or curator controls.
+//!
+//! Health-checked instructions take every collateral oracle of the market as
+//! remaining accounts, in slot order.
+//!
+//! CEI Pattern: Checks → Effects → Interactions
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked};
+use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::errors::MorphoError;
+use crate::events::{
+    McMarketCreated, McCollateralAdded, McSupply, McWithdraw, McCollateralSupplied,
+    McCollateralWithdrawn, McBorrow, McRepay, McLiquidation,
+};
+use crate::interfaces::{
+    calculate_lif, calculate_seized_collateral, get_borrow_rate_internal, read_feed_price,
+    seize_within_max_lif,
+};
+use crate::math::{
+    checked_add, checked_sub, safe_u128_to_u64, to_assets_down, to_assets_up,
+    to_shares_down, to_shares_up, w_taylor_compounded, wad_mul_down,
+};
+use crate::state::{
+    ProtocolState, McMarket, McCollateral, McPosition, MAX_MC_COLLATERALS,
+    calculate_mc_market_id, is_mc_healthy, is_supply_share_mint,
+};
+use crate::time::current_clock;
+
+// ============================================================================
+// Shared Helpers
+// ============================================================================
+
+/// Accrue interest on a multi-collateral market up to `now`
+///
+/// Same compounding as Blue markets, at the built-in utilization curve, with
+/// no protocol fee. Returns the interest added.
+pub fn accrue_mc_interest(market: &mut McMarket, now: i64) -> Result<u128> {
+    if now <= market.last_update {
+        return Ok(0);
+    }
+    let elapsed = (now - market.last_update) as u128;
+    market.last_update = now;
+    if market.total_borrow_assets == 0 {
+        return Ok(0);
+    }
+
+    let rate = get_borrow_rate_internal(market.total_supply_assets, market.total_borrow_assets)?;
+    let interest = wad_mul_down(market.total_borrow_assets, w_taylor_compounded(rate, elapsed)?)?;
+    market.total_borrow_assets = checked_add(market.total_borrow_assets, interest)?;
+    market.total_supply_assets = checked_add(market.total_supply_assets, interest)?;
+    Ok(interest)
+}
+
+/// Prices for every collateral `position` holds, from the oracle accounts
+/// passed in slot order
+///
+/// All of the market's oracles must be passed, but only held slots are read.
+pub fn load_mc_prices(
+    market: &McMarket,
+    position: &McPosition,
+    oracles: &[AccountInfo],
+) -> Result<[u128; MAX_MC_COLLATERALS]> {
+    let count = market.collateral_count as usize;
+    require!(oracles.len() >= count, MorphoError::McOracleMismatch);
+
+    let mut prices = [0u128; MAX_MC_COLLATERALS];
+    for (i, config) in market.collaterals[..count].iter().enumerate() {
+        require!(oracles[i].key() == config.oracle, MorphoError::McOracleMismatch);
+        if position.collateral[i] > 0 {
+            prices[i] = read_feed_price(&oracles[i], config.decimals, market.loan_decimals)?;
+        }
+    }
+    Ok(prices)
+}
+
+/// Pay `amount` of `mint` out of a market vault
+fn transfer_from_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    market: &Account<'info, McMarket>,
+    market_id: &[u8; 32],
+    amount: u128,
+) -> Result<()> {
+    let seeds = &[
+        PROGRAM_SEED_PREFIX,
+        McMarket::SEED,
+        market_id.as_ref(),
+        &[market.bump],
+    ];
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: vault.to_account_info(),
+                to: to.to_account_info(),
+                authority: market.to_account_info(),
+                mint: mint.to_account_info(),
+            },
+            &[seeds],
+        ),
+        safe_u128_to_u64(amount)?,
+        mint.decimals,
+    )
+}
+
+/// Pay `amount` of `mint` from a user's account into a market vault
+fn transfer_to_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: &Signer<'info>,
+    amount: u128,
+) -> Result<()> {
+    transfer_checked(
+        CpiContext::new(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                to: vault.to_account_info(),
+                authority: authority.to_account_info(),
+                mint: mint.to_account_info(),
+            },
+        ),
+        safe_u128_to_u64(amount)?,
+        mint.decimals,
+    )
+}
+
+// ============================================================================
+// Create Market
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(loan_mint_key: Pubkey, irm_key: Pubkey, nonce: u64)]
+pub struct CreateMcMarket<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = McMarket::space(),
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            McMarket::SEED,
+            &calculate_mc_market_id(&creator.key(), &loan_mint_key, &irm_key, nonce),
+        ],
+        bump,
+    )]
+    pub market: Box<Account<'info, McMarket>>,
+
+    #[account(constraint = loan_mint.key() == loan_mint_key)]
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = creator,
+        token::mint = loan_mint,
+        token::authority = market,
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            McMarket::LOAN_VAULT_SEED,
+            &calculate_mc_market_id(&creator.key(), &loan_mint_key, &irm_key, nonce),
+        ],
+        bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Open a multi-collateral market for `loan_mint_key`; add collaterals next
+pub fn create_mc_market(
+    ctx: Context<CreateMcMarket>,
+    loan_mint_key: Pubkey,
+    irm_key: Pubkey,
+    nonce: u64,
+) -> Result<()> {
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    require!(ctx.accounts.protocol_state.is_irm_enabled(&irm_key), MorphoError::IrmNotEnabled);
+
+    let creator = ctx.accounts.creator.key();
+    let market_id = calculate_mc_market_id(&creator, &loan_mint_key, &irm_key, nonce);
+
+    let market = &mut ctx.accounts.market;
+    market.bump = ctx.bumps.market;
+    market.market_id = market_id;
+    market.creator = creator;
+    market.loan_mint = loan_mint_key;
+    market.loan_decimals = ctx.accounts.loan_mint.decimals;
+    market.loan_vault_bump = ctx.bumps.loan_vault;
+    market.irm = irm_key;
+    market.collateral_count = 0;
+    market.collaterals = [McCollateral::default(); MAX_MC_COLLATERALS];
+    market.total_supply_assets = 0;
+    market.total_supply_shares = 0;
+    market.total_borrow_assets = 0;
+    market.total_borrow_shares = 0;
+    market.last_update = current_clock(ctx.remaining_accounts)?.unix_timestamp;
+    market.reserved = [0u8; 64];
+
+    emit!(McMarketCreated {
+        market_id,
+        creator,
+        loan_mint: loan_mint_key,
+        irm: irm_key,
+    });
+
+    Ok(())
+}
+
+// ============================================================================
+// Add Collateral
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct AddMcCollateral<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, McMarket::SEED, &market_id],
+        bump = market.bump,
+        constraint = market.creator == creator.key() @ MorphoError::Unauthorized,
+    )]
+    pub market: Box<Account<'info, McMarket>>,
+
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    /// Vault for the next free slot
+    #[account(
+        init,
+        payer = creator,
+        token::mint = collateral_mint,
+        token::authority = market,
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            McMarket::COLLATERAL_VAULT_SEED,
+            &market_id,
+            &[market.collateral_count],
+        ],
+        bump,
+    )]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Price feed for the collateral; read once here to prove it works
+    pub oracle: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accept `collateral_mint` at `lltv` in the next free slot
+///
+/// Only before the market's first supply: lenders never see the collateral
+/// set change under them.
+pub fn add_mc_collateral(ctx: Context<AddMcCollateral>, market_id: [u8; 32], lltv: u64) -> Result<()> {
+    let market = &ctx.accounts.market;
+    let mint = ctx.accounts.collateral_mint.key();
+
+    require!(
+        (market.collateral_count as usize) < MAX_MC_COLLATERALS,
+        MorphoError::McCollateralLimitReached
+    );
+    require!(market.total_supply_shares == 0, MorphoError::McCollateralsFixed);
+    require!(ctx.accounts.protocol_state.is_lltv_enabled(lltv), MorphoError::LltvNotEnabled);
+    require!(mint != market.loan_mint, MorphoError::InvalidMint);
+    require!(
+        market.collaterals[..market.collateral_count as usize].iter().all(|c| c.mint != mint),
+        MorphoError::DuplicateMcCollateral
+    );
+    require!(
+        !is_supply_share_mint(&market.key(), ctx.accounts.collateral_mint.mint_authority.into()),
+        MorphoError::SelfCollateralization
+    );
+    read_feed_price(
+        &ctx.accounts.oracle.to_account_info(),
+        ctx.accounts.collateral_mint.decimals,
+        market.loan_decimals,
+    )?;
+
+    let market = &mut ctx.accounts.market;
+    let index = market.collateral_count;
+    market.collaterals[index as usize] = McCollateral {
+        mint,
+        oracle: ctx.accounts.oracle.key(),
+        lltv,
+        decimals: ctx.accounts.collateral_mint.decimals,
+        vault_bump: ctx.bumps.collateral_vault,
+        total: 0,
+    };
+    market.collateral_count += 1;
+
+    emit!(McCollateralAdded {
+        market_id,
+        index,
+        mint,
+        oracle: ctx.accounts.oracle.key(),
+        lltv,
+    });
+
+    Ok(())
+}
+
+// ============================================================================
+// Create Position
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct CreateMcPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, McMarket::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, McMarket>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = McPosition::space(),
+        seeds = [PROGRAM_SEED_PREFIX, McPosition::SEED, &market_id, owner.key().as_ref()],
+        bump,
+    )]
+    pub position: Box<Account<'info, McPosition>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_mc_position(ctx: Context<CreateMcPosition>, market_id: [u8; 32]) -> Result<()> {
+    let position = &mut ctx.accounts.position;
+    position.bump = ctx.bumps.position;
+    position.market_id = market_id;
+    position.owner = ctx.accounts.owner.key();
+    position.supply_shares = 0;
+    position.borrow_shares = 0;
+    position.collateral = [0; MAX_MC_COLLATERALS];
+    position.reserved = [0u8; 32];
+    Ok(())
+}
+
+// ============================================================================
+// Loan Token Instructions (supply, withdraw, borrow, repay)
+// ============================================================================
+
+/// Accounts shared by every instruction moving the loan token between the
+/// owner and the market
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct McLoanTransfer<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, McMarket::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, McMarket>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, McPosition::SEED, &market_id, owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, McPosition>>,
+
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == market.loan_mint @ MorphoError::InvalidMint,
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, McMarket::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(constraint = loan_mint.key() == market.loan_mint @ MorphoError::InvalidMint)]
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn mc_supply(ctx: Context<McLoanTransfer>, market_id: [u8; 32], assets: u128) -> Result<()> {
+    // ===== CHECKS =====
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    require!(assets > 0, MorphoError::ZeroAmount);
+
+    let market = &mut ctx.accounts.market;
+    accrue_mc_interest(market, current_clock(ctx.remaining_accounts)?.unix_timestamp)?;
+
+    // Round DOWN - supplier gets fewer shares
+    let shares = to_shares_down(assets, market.total_supply_assets, market.total_supply_shares)?;
+    require!(shares > 0, MorphoError::ZeroAmount);
+
+    // ===== EFFECTS =====
+    market.total_supply_assets = checked_add(market.total_supply_assets, assets)?;
+    market.total_supply_shares = checked_add(market.total_supply_shares, shares)?;
+    ctx.accounts.position.supply_shares = checked_add(ctx.accounts.position.supply_shares, shares)?;
+
+    // ===== INTERACTIONS =====
+    transfer_to_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.owner_token_account,
+        &ctx.accounts.loan_vault,
+        &ctx.accounts.loan_mint,
+        &ctx.accounts.owner,
+        assets,
+    )?;
+
+    emit!(McSupply { market_id, owner: ctx.accounts.owner.key(), assets, shares });
+    Ok(())
+}
+
+pub fn mc_withdraw(ctx: Context<McLoanTransfer>, market_id: [u8; 32], shares: u128) -> Result<()> {
+    // ===== CHECKS =====
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    require!(shares > 0, MorphoError::ZeroAmount);
+    require!(ctx.accounts.position.supply_shares >= shares, MorphoError::InsufficientBalance);
+
+    let market = &mut ctx.accounts.market;
+    accrue_mc_interest(market, current_clock(ctx.remaining_accounts)?.unix_timestamp)?;
+
+    // Round DOWN - withdrawer gets fewer assets
+    let assets = to_assets_down(shares, market.total_supply_assets, market.total_supply_shares)?;
+    require!(
+        assets <= market.vault_liquidity(ctx.accounts.loan_vault.amount),
+        MorphoError::InsufficientLiquidity
+    );
+
+    // ===== EFFECTS =====
+    market.total_supply_assets = checked_sub(market.total_supply_assets, assets)?;
+    market.total_supply_shares = checked_sub(market.total_supply_shares, shares)?;
+    ctx.accounts.position.supply_shares = checked_sub(ctx.accounts.position.supply_shares, shares)?;
+
+    // ===== INTERACTIONS =====
+    transfer_from_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.loan_vault,
+        &ctx.accounts.owner_token_account,
+        &ctx.accounts.loan_mint,
+        &ctx.accounts.market,
+        &market_id,
+        assets,
+    )?;
+
+    emit!(McWithdraw { market_id, owner: ctx.accounts.owner.key(), assets, shares });
+    Ok(())
+}
+
+/// Borrow against every collateral the position holds
+///
+/// Remaining accounts: the market's collateral oracles, in slot order.
+pub fn mc_borrow<'info>(
+    ctx: Context<'_, '_, 'info, 'info, McLoanTransfer<'info>>,
+    market_id: [u8; 32],
+    assets: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    require!(assets > 0, MorphoError::ZeroAmount);
+
+    let market = &mut ctx.accounts.market;
+    accrue_mc_interest(market, current_clock(ctx.remaining_accounts)?.unix_timestamp)?;
+    require!(
+        assets <= market.vault_liquidity(ctx.accounts.loan_vault.amount),
+        MorphoError::InsufficientLiquidity
+    );
+
+    // Round UP - borrower owes more shares
+    let shares = to_shares_up(assets, market.total_borrow_assets, market.total_borrow_shares)?;
+
+    // ===== EFFECTS =====
+    market.total_borrow_assets = checked_add(market.total_borrow_assets, assets)?;
+    market.total_borrow_shares = checked_add(market.total_borrow_shares, shares)?;
+    let position = &mut ctx.accounts.position;
+    position.borrow_shares = checked_add(position.borrow_shares, shares)?;
+
+    // Combined health check on the post-borrow state
+    let prices = load_mc_prices(market, position, ctx.remaining_accounts)?;
+    require!(is_mc_healthy(market, position, &prices)?, MorphoError::PositionUnhealthy);
+
+    // ===== INTERACTIONS =====
+    transfer_from_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.loan_vault,
+        &ctx.accounts.owner_token_account,
+        &ctx.accounts.loan_mint,
+        &ctx.accounts.market,
+        &market_id,
+        assets,
+    )?;
+
+    emit!(McBorrow { market_id, owner: ctx.accounts.owner.key(), assets, shares });
+    Ok(())
+}
+
+/// Repay `shares` of the owner's debt
+pub fn mc_repay(ctx: Context<McLoanTransfer>, market_id: [u8; 32], shares: u128) -> Result<()> {
+    // ===== CHECKS =====
+    // Repaying is allowed while paused
+    require!(shares > 0, MorphoError::ZeroAmount);
+    let shares = std::cmp::min(shares, ctx.accounts.position.borrow_shares);
+    require!(shares > 0, MorphoError::ZeroAmount);
+
+    let market = &mut ctx.accounts.market;
+    accrue_mc_interest(market, current_clock(ctx.remaining_accounts)?.unix_timestamp)?;
+
+    // Round UP - repayer pays more
+    let assets = to_assets_up(shares, market.total_borrow_assets, market.total_borrow_shares)?;
+
+    // ===== EFFECTS =====
+    market.total_borrow_shares = checked_sub(market.total_borrow_shares, shares)?;
+    market.total_borrow_assets = market.total_borrow_assets.saturating_sub(assets);
+    ctx.accounts.position.borrow_shares = checked_sub(ctx.accounts.position.borrow_shares, shares)?;
+
+    // ===== INTERACTIONS =====
+    transfer_to_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.owner_token_account,
+        &ctx.accounts.loan_vault,
+        &ctx.accounts.loan_mint,
+        &ctx.accounts.owner,
+        assets,
+    )?;
+
+    emit!(McRepay {
+        market_id,
+        repayer: ctx.accounts.owner.key(),
+        owner: ctx.accounts.owner.key(),
+        assets,
+        shares,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Collateral Instructions
+// ============================================================================
+
+/// Accounts shared by supplying and withdrawing collateral slot `index`
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32], index: u8)]
+pub struct McCollateralTransfer<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, McMarket::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, McMarket>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, McPosition::SEED, &market_id, owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, McPosition>>,
+
+    #[account(
+        constraint = market.collateral(index).map(|c| c.mint) == Some(collateral_mint.key())
+            @ MorphoError::InvalidMcCollateral,
+    )]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == collateral_mint.key() @ MorphoError::InvalidMint,
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, McMarket::COLLATERAL_VAULT_SEED, &market_id, &[index]],
+        bump = market.vault_bump(index),
+    )]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn mc_supply_collateral(
+    ctx: Context<McCollateralTransfer>,
+    market_id: [u8; 32],
+    index: u8,
+    amount: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    require!(amount > 0, MorphoError::ZeroAmount);
+
+    // ===== EFFECTS =====
+    let slot = index as usize;
+    let config = &mut ctx.accounts.market.collaterals[slot];
+    config.total = checked_add(config.total, amount)?;
+    let position = &mut ctx.accounts.position;
+    position.collateral[slot] = checked_add(position.collateral[slot], amount)?;
+
+    // ===== INTERACTIONS =====
+    transfer_to_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.owner_token_account,
+        &ctx.accounts.collateral_vault,
+        &ctx.accounts.collateral_mint,
+        &ctx.accounts.owner,
+        amount,
+    )?;
+
+    emit!(McCollateralSupplied { market_id, owner: ctx.accounts.owner.key(), index, amount });
+    Ok(())
+}
+
+/// Withdraw collateral, keeping the position healthy across all its collateral
+///
+/// Remaining accounts: the market's collateral oracles, in slot order.
+pub fn mc_withdraw_collateral<'info>(
+    ctx: Context<'_, '_, 'info, 'info, McCollateralTransfer<'info>>,
+    market_id: [u8; 32],
+    index: u8,
+    amount: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    require!(amount > 0, MorphoError::ZeroAmount);
+    let slot = index as usize;
+    require!(ctx.accounts.position.collateral[slot] >= amount, MorphoError::InsufficientCollateral);
+
+    let market = &mut ctx.accounts.market;
+    accrue_mc_interest(market, current_clock(ctx.remaining_accounts)?.unix_timestamp)?;
+
+    // ===== EFFECTS =====
+    market.collaterals[slot].total = checked_sub(market.collaterals[slot].total, amount)?;
+    let position = &mut ctx.accounts.position;
+    position.collateral[slot] = checked_sub(position.collateral[slot], amount)?;
+
+    if position.borrow_shares > 0 {
+        let prices = load_mc_prices(market, position, ctx.remaining_accounts)?;
+        require!(is_mc_healthy(market, position, &prices)?, MorphoError::PositionUnhealthy);
+    }
+
+    // ===== INTERACTIONS =====
+    transfer_from_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.collateral_vault,
+        &ctx.accounts.owner_token_account,
+        &ctx.accounts.collateral_mint,
+        &ctx.accounts.market,
+        &market_id,
+        amount,
+    )?;
+
+    emit!(McCollateralWithdrawn { market_id, owner: ctx.accounts.owner.key(), index, amount });
+    Ok(())
+}
+
+// ============================================================================
+// Liquidate
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32], index: u8)]
+pub struct McLiquidate<'info> {
+    pub liquidator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, McMarket::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, McMarket>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, McPosition::SEED, &market_id, borrower.key().as_ref()],
+        bump = borrower_position.bump,
+    )]
+    pub borrower_position: Box<Account<'info, McPosition>>,
+
+    /// CHECK: Owner of the liquidated position
+    pub borrower: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = market.collateral(index).map(|c| c.mint) == Some(collateral_mint.key())
+            @ MorphoError::InvalidMcCollateral,
+    )]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(constraint = loan_mint.key() == market.loan_mint @ MorphoError::InvalidMint)]
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = liquidator_loan_account.mint == market.loan_mint @ MorphoError::InvalidMint,
+    )]
+    pub liquidator_loan_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = liquidator_collateral_account.mint == collateral_mint.key() @ MorphoError::InvalidMint,
+    )]
+    pub liquidator_collateral_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, McMarket::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, McMarket::COLLATERAL_VAULT_SEED, &market_id, &[index]],
+        bump = market.vault_bump(index),
+    )]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Repay up to `assets` of an unhealthy position's debt for collateral slot
+/// `index` at that collateral's LIF
+///
+/// Once the position has no collateral left in any slot, its remaining debt
+/// is socialized across suppliers. Remaining accounts: the market's
+/// collateral oracles, in slot order.
+pub fn mc_liquidate<'info>(
+    ctx: Context<'_, '_, 'info, 'info, McLiquidate<'info>>,
+    market_id: [u8; 32],
+    index: u8,
+    assets: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    // Liquidation allowed even when paused (maintains protocol health)
+    require!(assets > 0, MorphoError::ZeroAmount);
+
+    let market = &mut ctx.accounts.market;
+    accrue_mc_interest(market, current_clock(ctx.remaining_accounts)?.unix_timestamp)?;
+
+    let position = &ctx.accounts.borrower_position;
+    let prices = load_mc_prices(market, position, ctx.remaining_accounts)?;
+    require!(!is_mc_healthy(market, position, &prices)?, MorphoError::PositionHealthy);
+
+    let slot = index as usize;
+    require!(position.collateral[slot] > 0, MorphoError::InsufficientCollateral);
+    let price = prices[slot];
+
+    let repaid_shares = to_shares_down(assets, market.total_borrow_assets, market.total_borrow_shares)?;
+    let repaid_shares = std::cmp::min(repaid_shares, position.borrow_shares);
+    require!(repaid_shares > 0, MorphoError::ZeroAmount);
+    let repaid_assets = to_assets_up(repaid_shares, market.total_borrow_assets, market.total_borrow_shares)?;
+
+    let lif = calculate_lif(market.collaterals[slot].lltv);
+    let seized_collateral = std::cmp::min(
+        calculate_seized_collateral(repaid_assets, price, lif)?,
+        position.collateral[slot],
+    );
+    require!(
+        seize_within_max_lif(seized_collateral, repaid_assets, price)?,
+        MorphoError::LiquidationMathSuspect
+    );
+
+    // ===== EFFECTS =====
+    let position = &mut ctx.accounts.borrower_position;
+    position.borrow_shares = checked_sub(position.borrow_shares, repaid_shares)?;
+    position.collateral[slot] = checked_sub(position.collateral[slot], seized_collateral)?;
+    market.collaterals[slot].total = checked_sub(market.collaterals[slot].total, seized_collateral)?;
+    market.total_borrow_shares = checked_sub(market.total_borrow_shares, repaid_shares)?;
+    market.total_borrow_assets = market.total_borrow_assets.saturating_sub(repaid_assets);
+
+    // Bad debt: no collateral left anywhere but debt remains
+    let mut bad_debt_assets = 0;
+    if position.borrow_shares > 0 && position.collateral.iter().all(|&c| c == 0) {
+        bad_debt_assets = std::cmp::min(
+            to_assets_up(position.borrow_shares, market.total_borrow_assets, market.total_borrow_shares)?,
+            market.total_borrow_assets,
+        );
+        market.total_borrow_shares = checked_sub(market.total_borrow_shares, position.borrow_shares)?;
+        market.total_borrow_assets = checked_sub(market.total_borrow_assets, bad_debt_assets)?;
+        market.total_supply_assets = market.total_supply_assets.saturating_sub(bad_debt_assets);
+        position.borrow_shares = 0;
+    }
+
+    // ===== INTERACTIONS =====
+    transfer_to_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.liquidator_loan_account,
+        &ctx.accounts.loan_vault,
+        &ctx.accounts.loan_mint,
+        &ctx.accounts.liquidator,
+        repaid_assets,
+    )?;
+    transfer_from_vault(
+        &ctx.accounts.token_program,
+        &ctx.accounts.collateral_vault,
+        &ctx.accounts.liquidator_collateral_account,
+        &ctx.accounts.collateral_mint,
+        &ctx.accounts.market,
+        &market_id,
+        seized_collateral,
+    )?;
+
+    emit!(McLiquidation {
+        market_id,
+        liquidator: ctx.accounts.liquidator.key(),
+        borrower: ctx.accounts.borrower.key(),
+        index,
+        repaid_assets,
+        repaid_shares,
+        seized_collateral,
+        bad_debt_assets,
+    });
+
+    Ok(())
+}
@@ -1,10 +1,16 @@
-//! Position management instructions (create, close)
+//! Position management instructions (create, close, merge)
 
 use anchor_lang::prelude::*;
 use crate::constants::PROGRAM_SEED_PREFIX;
 use crate::errors::MorphoError;
-use crate::events::{PositionCreated, PositionClosed};
-use crate::state::{Market, Position};
+use crate::events::{PositionCreated, PositionClosed, PositionsMerged};
+use crate::state::{ProtocolState, Market, MarketAction, Position, Authorization};
+use crate::math::{checked_add, accrue_market_interest, accrue_stable_debt};
+use crate::instructions::insurance::charge_insurance_premium;
+use crate::instructions::reputation::sync_reputation_boost;
+use crate::instructions::stable_rate::blended_stable_rate;
+use crate::interfaces::ensure_position_healthy;
+use crate::time::current_clock;
 
 // ============================================================================
 // Create Position
@@ -45,6 +51,28 @@ pub fn create_position(ctx: Context<CreatePosition>, market_id: [u8; 32]) -> Res
     position.supply_shares = 0;
     position.borrow_shares = 0;
     position.collateral = 0;
+    position.credit_delegated = 0;
+    position.locked_supply_shares = 0;
+    position.supply_locked_until = 0;
+    position.total_supplied = 0;
+    position.total_withdrawn = 0;
+    position.total_borrowed = 0;
+    position.total_repaid = 0;
+    position.times_liquidated = 0;
+    position.reputation_boosted = false;
+    position.insured = false;
+    position.insurance_paid_until = 0;
+    position.insurance_claimable = 0;
+    position.stable_borrow_assets = 0;
+    position.stable_rate = 0;
+    position.stable_last_update = 0;
+    position.last_liquidation_slot = 0;
+    position.last_liquidator = Pubkey::default();
+    position.escrowed_supply_shares = 0;
+    position.escrow_unlock_at = 0;
+    position.opened_at = Clock::get()?.unix_timestamp;
+    position.last_borrowed_at = 0;
+    position.seasoned_repaid = 0;
 
     emit!(PositionCreated {
         market_id,
@@ -60,7 +88,7 @@ pub fn create_position(ctx: Context<CreatePosition>, market_id: [u8; 32]) -> Res
 #[derive(Accounts)]
 #[instruction(market_id: [u8; 32])]
 pub struct ClosePosition<'info> {
-    #[account(mut)]
+    /// Position owner; may be a PDA signing via CPI
     pub owner: Signer<'info>,
 
     /// CHECK: Rent receiver - can be any account
@@ -85,3 +113,204 @@ pub fn close_position(ctx: Context<ClosePosition>, market_id: [u8; 32]) -> Resul
     });
     Ok(())
 }
+
+// ============================================================================
+// Merge Positions
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct MergePositions<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// Position being folded in and closed
+    #[account(
+        mut,
+        close = rent_receiver,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, source_position.owner.as_ref()],
+        bump = source_position.bump,
+    )]
+    pub source_position: Box<Account<'info, Position>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, destination_position.owner.as_ref()],
+        bump = destination_position.bump,
+        constraint = destination_position.owner != source_position.owner @ MorphoError::InvalidInput,
+    )]
+    pub destination_position: Box<Account<'info, Position>>,
+
+    /// Caller's authorization from the source owner (not needed for their own position)
+    pub source_authorization: Option<Account<'info, Authorization>>,
+
+    /// Caller's authorization from the destination owner (not needed for their own position)
+    pub destination_authorization: Option<Account<'info, Authorization>>,
+
+    /// CHECK: Oracle account, only required when the source carries debt
+    pub oracle: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Rent receiver - can be any account
+    #[account(mut)]
+    pub rent_receiver: UncheckedAccount<'info>,
+}
+
+/// Fold `source_position` into `destination_position` and close the source
+///
+/// Shares, collateral, debt and lifetime stats all carry over; stable debt
+/// keeps its blended rate. The caller must be able to act for both owners.
+pub fn merge_positions(ctx: Context<MergePositions>, market_id: [u8; 32]) -> Result<()> {
+    // ===== CHECKS =====
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    ctx.accounts.market.check_action(MarketAction::MergePositions)?;
+
+    validate_authorization(
+        &ctx.accounts.caller,
+        &ctx.accounts.source_position.owner,
+        ctx.accounts.source_authorization.as_ref(),
+    )?;
+    validate_authorization(
+        &ctx.accounts.caller,
+        &ctx.accounts.destination_position.owner,
+        ctx.accounts.destination_authorization.as_ref(),
+    )?;
+
+    // Credit lines name the supplier's position; it can't disappear under them
+    require!(
+        ctx.accounts.source_position.credit_delegated == 0,
+        MorphoError::PositionBacksCreditLines
+    );
+    // Escrow events are keyed by owner; merging would move weight silently
+    require!(
+        ctx.accounts.source_position.escrowed_supply_shares == 0,
+        MorphoError::SupplyLocked
+    );
+
+    let clock = current_clock(ctx.remaining_accounts)?;
+    let now = clock.unix_timestamp;
+    let market = &mut ctx.accounts.market;
+    let source = &mut ctx.accounts.source_position;
+    let destination = &mut ctx.accounts.destination_position;
+
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+    charge_insurance_premium(market, source, now)?;
+    charge_insurance_premium(market, destination, now)?;
+    accrue_stable_debt(market, source, now)?;
+    accrue_stable_debt(market, destination, now)?;
+
+    // Without incoming debt the merge can only raise the destination's health
+    let debt_moved = source.has_debt();
+    if debt_moved {
+        market.check_action(MarketAction::MergeDebt)?;
+    }
+
+    // ===== EFFECTS =====
+    if source.stable_borrow_assets > 0 {
+        if destination.stable_borrow_assets > 0 {
+            // Two stable borrowers become one; the aggregate is unchanged
+            market.stable_borrowers = market.stable_borrowers.saturating_sub(1);
+        }
+        destination.stable_rate = blended_stable_rate(
+            destination.stable_borrow_assets,
+            destination.stable_rate,
+            source.stable_borrow_assets,
+            source.stable_rate,
+        )?;
+        destination.stable_borrow_assets =
+            checked_add(destination.stable_borrow_assets, source.stable_borrow_assets)?;
+        destination.stable_last_update = now;
+    }
+
+    let source_locked = source.locked_supply_shares_at(now);
+    if source_locked > 0 {
+        destination.locked_supply_shares =
+            checked_add(destination.locked_supply_shares_at(now), source_locked)?;
+        destination.supply_locked_until =
+            std::cmp::max(destination.supply_locked_until, source.supply_locked_until);
+    }
+
+    destination.supply_shares = checked_add(destination.supply_shares, source.supply_shares)?;
+    destination.borrow_shares = checked_add(destination.borrow_shares, source.borrow_shares)?;
+    destination.collateral = checked_add(destination.collateral, source.collateral)?;
+    destination.insurance_claimable =
+        checked_add(destination.insurance_claimable, source.insurance_claimable)?;
+    destination.total_supplied = checked_add(destination.total_supplied, source.total_supplied)?;
+    destination.total_withdrawn = checked_add(destination.total_withdrawn, source.total_withdrawn)?;
+    destination.total_borrowed = checked_add(destination.total_borrowed, source.total_borrowed)?;
+    destination.total_repaid = checked_add(destination.total_repaid, source.total_repaid)?;
+    destination.seasoned_repaid = checked_add(destination.seasoned_repaid, source.seasoned_repaid)?;
+    destination.opened_at = std::cmp::min(destination.opened_at, source.opened_at);
+    // Moved debt keeps its age only as far as the newer of the two borrows
+    destination.last_borrowed_at = std::cmp::max(destination.last_borrowed_at, source.last_borrowed_at);
+    destination.times_liquidated = destination.times_liquidated.saturating_add(source.times_liquidated);
+    // Merging can't shake off a liquidation cooldown
+    if source.last_liquidation_slot > destination.last_liquidation_slot {
+        destination.last_liquidation_slot = source.last_liquidation_slot;
+        destination.last_liquidator = source.last_liquidator;
+    }
+    sync_reputation_boost(market, destination, now);
+
+    if debt_moved {
+        ensure_position_healthy(
+            market,
+            destination,
+            market.position_lltv(destination, now),
+            ctx.accounts.oracle.as_ref().map(|o| o.as_ref()),
+            None,
+        )?;
+    }
+
+    emit!(PositionsMerged {
+        market_id,
+        caller: ctx.accounts.caller.key(),
+        source_owner: source.owner,
+        destination_owner: destination.owner,
+        supply_shares: source.supply_shares,
+        borrow_shares: source.borrow_shares,
+        collateral: source.collateral,
+        stable_borrow_assets: source.stable_borrow_assets,
+    });
+
+    emit!(PositionClosed {
+        market_id,
+        owner: source.owner,
+    });
+    Ok(())
+}
+
+/// Validate authorization for delegated operations
+fn validate_authorization(
+    caller: &Signer,
+    owner: &Pubkey,
+    authorization: Option<&Account<Authorization>>,
+) -> Result<()> {
+    if caller.key() == *owner {
+        return Ok(());
+    }
+
+    let current_time = Clock::get()?.unix_timestamp;
+
+    if let Some(auth) = authorization {
+        if auth.authorizer == *owner
+            && auth.authorized == caller.key()
+            && auth.is_valid(current_time)
+        {
+            return Ok(());
+        }
+    }
+
+    Err(MorphoError::Unauthorized.into())
+}
@@ -0,0 +1,164 @@
+//! Trusted price relayer
+//!
+//! The owner names a relayer per market; the relayer pushes prices into the
+//! market's `PriceRelay`. A market uses the relay once the owner makes it
+//! the fallback oracle (`set_fallback_oracle`), so relayed prices only
+//! count while the primary feed is failing.
+
+use anchor_lang::prelude::*;
+use crate::constants::{PROGRAM_SEED_PREFIX, MIN_ORACLE_PRICE};
+use crate::errors::MorphoError;
+use crate::events::{PriceRelayerSet, RelayedPricePushed};
+use crate::instructions::divergence::price_divergence_bps;
+use crate::interfaces::{
+    get_oracle_price_validated, is_feed_failure, max_oracle_price, PriceRelay, MAX_RELAY_DEVIATION_BPS,
+};
+use crate::state::{ProtocolState, Market};
+
+// ============================================================================
+// Set Price Relayer
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetPriceRelayer<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = PriceRelay::space(),
+        seeds = [PROGRAM_SEED_PREFIX, PriceRelay::SEED, &market_id],
+        bump,
+    )]
+    pub price_relay: Account<'info, PriceRelay>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Name the key allowed to push prices for a market, and how far a push
+/// may stray from the primary oracle
+///
+/// `Pubkey::default()` with a zero deviation disables the relay. A new
+/// relayer starts from an empty cache; the last primary price seen is kept.
+pub fn set_price_relayer(
+    ctx: Context<SetPriceRelayer>,
+    market_id: [u8; 32],
+    relayer: Pubkey,
+    max_deviation_bps: u64,
+) -> Result<()> {
+    let disabling = relayer == Pubkey::default();
+    require!(
+        (disabling && max_deviation_bps == 0)
+            || (!disabling && max_deviation_bps > 0 && max_deviation_bps <= MAX_RELAY_DEVIATION_BPS),
+        MorphoError::InvalidPriceRelayConfig
+    );
+
+    let relay = &mut ctx.accounts.price_relay;
+    relay.bump = ctx.bumps.price_relay;
+    relay.market_id = market_id;
+    if relay.relayer != relayer {
+        relay.price = 0;
+        relay.last_update_slot = 0;
+        relay.last_update = 0;
+    }
+    relay.relayer = relayer;
+    relay.max_deviation_bps = max_deviation_bps;
+
+    emit!(PriceRelayerSet {
+        market_id,
+        price_relay: relay.key(),
+        relayer,
+        max_deviation_bps,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Push Relayed Price
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct PushRelayedPrice<'info> {
+    pub relayer: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, PriceRelay::SEED, &market_id],
+        bump = price_relay.bump,
+        constraint = price_relay.relayer != Pubkey::default()
+            && price_relay.relayer == relayer.key() @ MorphoError::Unauthorized,
+    )]
+    pub price_relay: Account<'info, PriceRelay>,
+
+    /// CHECK: Market's primary oracle, validated by get_oracle_price_validated
+    pub oracle: UncheckedAccount<'info>,
+}
+
+/// Cache a relayed price
+///
+/// Checked against the primary oracle when it prices, and against the last
+/// price it gave while it's failing. Pushing before the primary has ever
+/// been seen is rejected.
+pub fn push_relayed_price(
+    ctx: Context<PushRelayedPrice>,
+    market_id: [u8; 32],
+    price: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(price >= MIN_ORACLE_PRICE, MorphoError::OraclePriceTooLow);
+    require!(price <= max_oracle_price(), MorphoError::OraclePriceTooHigh);
+
+    let relay = &mut ctx.accounts.price_relay;
+    let (reference_price, primary_live) =
+        match get_oracle_price_validated(&ctx.accounts.oracle.to_account_info(), &ctx.accounts.market) {
+            Ok(primary) => (primary, true),
+            Err(err) if is_feed_failure(&err) => {
+                msg!("Primary oracle failed: {}", err);
+                require!(relay.reference_price > 0, MorphoError::RelayReferenceMissing);
+                (relay.reference_price, false)
+            }
+            Err(err) => return Err(err),
+        };
+    require!(
+        price_divergence_bps(price, reference_price) <= relay.max_deviation_bps,
+        MorphoError::RelayedPriceDeviation
+    );
+
+    // ===== EFFECTS =====
+    let clock = Clock::get()?;
+    relay.price = price;
+    relay.reference_price = reference_price;
+    relay.last_update_slot = clock.slot;
+    relay.last_update = clock.unix_timestamp;
+
+    emit!(RelayedPricePushed {
+        market_id,
+        relayer: relay.relayer,
+        price,
+        reference_price,
+        primary_live,
+    });
+    Ok(())
+}
@@ -0,0 +1,608 @@
+//! Market config proposals
+//!
+//! - Owner assigns a curator to a market
+//! - Curator proposes fee / cap changes
+//! - Owner approves (changes apply immediately) or either side cancels
+//! - Owner migrates a market's oracle after `ORACLE_UPDATE_DELAY`
+//! - Owner migrates a market's IRM after `IRM_UPDATE_DELAY`, if the new IRM
+//!   prices the market close to the old one at switchover
+
+use anchor_lang::prelude::*;
+use crate::constants::{
+    PROGRAM_SEED_PREFIX, ORACLE_UPDATE_DELAY, IRM_UPDATE_DELAY, MAX_IRM_SWITCH_DEVIATION_BPS,
+};
+use crate::errors::MorphoError;
+use crate::events::*;
+use crate::instructions::divergence::price_divergence_bps;
+use crate::interfaces::{
+    derive_adaptive_curve_model, derive_pegged_oracle, get_market_borrow_rate, get_oracle_price_validated,
+    restart_adaptive_curve_irm,
+};
+use crate::math::{accrue_market_interest, cache_borrow_rate, sample_borrow_rate};
+use crate::state::{
+    ProtocolState, Market, ConfigProposal, MarketConfigUpdate, OracleUpdateProposal, IrmUpdateProposal,
+};
+use crate::time::current_clock;
+
+// ============================================================================
+// Set Market Curator
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetMarketCurator<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Assign (or clear, with `Pubkey::default()`) the market's curator
+pub fn set_market_curator(
+    ctx: Context<SetMarketCurator>,
+    market_id: [u8; 32],
+    curator: Pubkey,
+) -> Result<()> {
+    ctx.accounts.market.curator = curator;
+    emit!(MarketCuratorSet { market_id, curator });
+    Ok(())
+}
+
+// ============================================================================
+// Propose Market Config
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct ProposeMarketConfig<'info> {
+    #[account(mut)]
+    pub curator: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+        constraint = market.curator != Pubkey::default()
+            && market.curator == curator.key() @ MorphoError::Unauthorized,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init,
+        payer = curator,
+        space = ConfigProposal::space(),
+        seeds = [PROGRAM_SEED_PREFIX, ConfigProposal::SEED, &market_id],
+        bump,
+    )]
+    pub proposal: Account<'info, ConfigProposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn propose_market_config(
+    ctx: Context<ProposeMarketConfig>,
+    market_id: [u8; 32],
+    config: MarketConfigUpdate,
+) -> Result<()> {
+    config.validate()?;
+
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.bump = ctx.bumps.proposal;
+    proposal.market_id = market_id;
+    proposal.proposer = ctx.accounts.curator.key();
+    proposal.created_at = Clock::get()?.unix_timestamp;
+    proposal.config = config;
+
+    emit!(MarketConfigProposed {
+        market_id,
+        proposer: proposal.proposer,
+        config,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Approve Proposal
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct ApproveProposal<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [PROGRAM_SEED_PREFIX, ConfigProposal::SEED, &market_id],
+        bump = proposal.bump,
+        has_one = proposer,
+    )]
+    pub proposal: Account<'info, ConfigProposal>,
+
+    /// CHECK: Rent refund destination, bound by has_one on proposal
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+/// Approve the pending proposal and apply it to the market
+pub fn approve_proposal(ctx: Context<ApproveProposal>, market_id: [u8; 32]) -> Result<()> {
+    let config = ctx.accounts.proposal.config;
+
+    // Interest up to now is charged under the old config
+    accrue_market_interest(&mut ctx.accounts.market, &current_clock(ctx.remaining_accounts)?, ctx.remaining_accounts)?;
+    config.apply(&mut ctx.accounts.market)?;
+
+    emit!(MarketConfigApplied {
+        market_id,
+        proposer: ctx.accounts.proposal.proposer,
+        config,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Cancel Proposal
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct CancelProposal<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [PROGRAM_SEED_PREFIX, ConfigProposal::SEED, &market_id],
+        bump = proposal.bump,
+        has_one = proposer,
+    )]
+    pub proposal: Account<'info, ConfigProposal>,
+
+    /// CHECK: Rent refund destination, bound by has_one on proposal
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+/// Withdraw (proposer) or reject (owner) the pending proposal
+pub fn cancel_proposal(ctx: Context<CancelProposal>, market_id: [u8; 32]) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(
+        authority == ctx.accounts.proposal.proposer || authority == ctx.accounts.protocol_state.owner,
+        MorphoError::Unauthorized
+    );
+
+    emit!(MarketConfigProposalCancelled {
+        market_id,
+        proposer: ctx.accounts.proposal.proposer,
+        cancelled_by: authority,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Propose Oracle Update
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct ProposeOracleUpdate<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = OracleUpdateProposal::space(),
+        seeds = [PROGRAM_SEED_PREFIX, OracleUpdateProposal::SEED, &market_id],
+        bump,
+    )]
+    pub proposal: Account<'info, OracleUpdateProposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Schedule the market's switch to `new_oracle`
+///
+/// Only one migration may be pending per market; cancel it to propose
+/// another.
+pub fn propose_oracle_update(
+    ctx: Context<ProposeOracleUpdate>,
+    market_id: [u8; 32],
+    new_oracle: Pubkey,
+) -> Result<()> {
+    let market = &ctx.accounts.market;
+    require!(
+        new_oracle != Pubkey::default()
+            && new_oracle != market.oracle
+            && new_oracle != market.fallback_oracle,
+        MorphoError::InvalidOracleUpdate
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.bump = ctx.bumps.proposal;
+    proposal.market_id = market_id;
+    proposal.new_oracle = new_oracle;
+    proposal.proposed_at = now;
+    proposal.executable_at = now.saturating_add(ORACLE_UPDATE_DELAY);
+
+    emit!(OracleUpdateProposed {
+        market_id,
+        current_oracle: market.oracle,
+        new_oracle,
+        executable_at: proposal.executable_at,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Execute Oracle Update
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct ExecuteOracleUpdate<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PROGRAM_SEED_PREFIX, OracleUpdateProposal::SEED, &market_id],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, OracleUpdateProposal>,
+
+    /// CHECK: The proposed oracle, validated by pricing the market with it
+    #[account(constraint = new_oracle.key() == proposal.new_oracle @ MorphoError::InvalidOracle)]
+    pub new_oracle: UncheckedAccount<'info>,
+}
+
+/// Switch the market to the proposed oracle once the delay has passed
+///
+/// The new oracle must price the market as it executes. `market_id` keeps
+/// hashing the oracle the market was created with.
+pub fn execute_oracle_update(ctx: Context<ExecuteOracleUpdate>, market_id: [u8; 32]) -> Result<()> {
+    // ===== CHECKS =====
+    require!(
+        ctx.accounts.proposal.is_executable(Clock::get()?.unix_timestamp),
+        MorphoError::OracleUpdateTimelocked
+    );
+    // The fallback may have been pointed at it since the proposal
+    require!(
+        ctx.accounts.proposal.new_oracle != ctx.accounts.market.fallback_oracle,
+        MorphoError::InvalidOracleUpdate
+    );
+
+    // ===== EFFECTS =====
+    let new_oracle = ctx.accounts.new_oracle.to_account_info();
+    let market = &mut ctx.accounts.market;
+    let old_oracle = market.oracle;
+    market.oracle = new_oracle.key();
+    // Same classification create_market gives a new market's oracle
+    market.oracle_program_priced = ctx.accounts.protocol_state.is_oracle_program_enabled(new_oracle.owner);
+    market.oracle_pegged = new_oracle.key() == derive_pegged_oracle(&crate::ID).0;
+
+    get_oracle_price_validated(&new_oracle, market)?;
+
+    emit!(OracleUpdated {
+        market_id,
+        old_oracle,
+        new_oracle: market.oracle,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Cancel Oracle Update
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct CancelOracleUpdate<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PROGRAM_SEED_PREFIX, OracleUpdateProposal::SEED, &market_id],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, OracleUpdateProposal>,
+}
+
+/// Drop the pending oracle migration
+pub fn cancel_oracle_update(ctx: Context<CancelOracleUpdate>, market_id: [u8; 32]) -> Result<()> {
+    emit!(OracleUpdateCancelled {
+        market_id,
+        new_oracle: ctx.accounts.proposal.new_oracle,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Propose IRM Update
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct ProposeIrmUpdate<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = IrmUpdateProposal::space(),
+        seeds = [PROGRAM_SEED_PREFIX, IrmUpdateProposal::SEED, &market_id],
+        bump,
+    )]
+    pub proposal: Account<'info, IrmUpdateProposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Schedule the market's switch to `new_irm`
+///
+/// The new IRM must be enabled, as for new markets. Only one migration may
+/// be pending per market; cancel it to propose another.
+pub fn propose_irm_update(
+    ctx: Context<ProposeIrmUpdate>,
+    market_id: [u8; 32],
+    new_irm: Pubkey,
+) -> Result<()> {
+    let market = &ctx.accounts.market;
+    require!(
+        new_irm != market.irm && ctx.accounts.protocol_state.is_irm_enabled(&new_irm),
+        MorphoError::InvalidIrmUpdate
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.bump = ctx.bumps.proposal;
+    proposal.market_id = market_id;
+    proposal.new_irm = new_irm;
+    proposal.proposed_at = now;
+    proposal.executable_at = now.saturating_add(IRM_UPDATE_DELAY);
+
+    emit!(IrmUpdateProposed {
+        market_id,
+        current_irm: market.irm,
+        new_irm,
+        executable_at: proposal.executable_at,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Set Market IRM (execute IRM update)
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetMarketIrm<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PROGRAM_SEED_PREFIX, IrmUpdateProposal::SEED, &market_id],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, IrmUpdateProposal>,
+
+    /// CHECK: The proposed IRM, validated by pricing the market with it
+    #[account(constraint = new_irm.key() == proposal.new_irm @ MorphoError::InvalidIrm)]
+    pub new_irm: UncheckedAccount<'info>,
+    // remaining_accounts: the old IRM program or adaptive curve state, and
+    // the market's adaptive curve state if switching onto the model
+}
+
+/// Switch the market to the proposed IRM once the delay has passed
+///
+/// Interest up to now accrues at the old IRM first. The new IRM's rate at
+/// the market's current utilization must then be within
+/// `MAX_IRM_SWITCH_DEVIATION_BPS` of the old one's, so the switch can't
+/// reprice existing borrows in one step. `market_id` keeps hashing the IRM
+/// the market was created with.
+pub fn set_market_irm<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SetMarketIrm<'info>>,
+    market_id: [u8; 32],
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(
+        ctx.accounts.proposal.is_executable(Clock::get()?.unix_timestamp),
+        MorphoError::IrmUpdateTimelocked
+    );
+    // The IRM may have been disabled since the proposal
+    require!(
+        ctx.accounts.protocol_state.is_irm_enabled(&ctx.accounts.proposal.new_irm),
+        MorphoError::InvalidIrmUpdate
+    );
+
+    // ===== EFFECTS =====
+    let new_irm = ctx.accounts.new_irm.to_account_info();
+    let mut irm_accounts = ctx.remaining_accounts.to_vec();
+    irm_accounts.push(new_irm.clone());
+
+    let clock = current_clock(ctx.remaining_accounts)?;
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &clock, &irm_accounts)?;
+    // Resample rather than reuse the accrual's rate: it was priced at the
+    // utilization before this accrual's interest
+    market.rate_cache_slot = 0;
+    let old_borrow_rate = sample_borrow_rate(market, &clock, &irm_accounts)?;
+
+    let old_irm = market.irm;
+    market.irm = new_irm.key();
+    // Same classification create_market gives a new market's IRM
+    market.irm_cpi = new_irm.executable;
+    market.irm_adaptive = new_irm.key() == derive_adaptive_curve_model(&crate::ID).0;
+    if market.irm_adaptive {
+        restart_adaptive_curve_irm(market, &irm_accounts, clock.unix_timestamp)?;
+    }
+
+    // Refills the rate caches at the new IRM's rate. Queried directly: a
+    // new IRM that faults must fail the switch, not fall back to the old rate
+    let new_borrow_rate = get_market_borrow_rate(market, &irm_accounts)?;
+    cache_borrow_rate(market, &clock, new_borrow_rate)?;
+    check_irm_switch(market, old_borrow_rate, new_borrow_rate)?;
+
+    emit!(IrmUpdated {
+        market_id,
+        old_irm,
+        new_irm: market.irm,
+        old_borrow_rate,
+        new_borrow_rate,
+    });
+    Ok(())
+}
+
+/// Check an IRM switch moves the market's borrow rate by at most
+/// `MAX_IRM_SWITCH_DEVIATION_BPS`
+///
+/// A market without debt has no borrows the switch could reprice, so any
+/// rate goes.
+pub fn check_irm_switch(market: &Market, old_borrow_rate: u128, new_borrow_rate: u128) -> Result<()> {
+    if market.total_debt() == 0 {
+        return Ok(());
+    }
+    let deviation_bps = price_divergence_bps(old_borrow_rate, new_borrow_rate);
+    if deviation_bps > MAX_IRM_SWITCH_DEVIATION_BPS {
+        msg!(
+            "IRM switch moves the borrow rate {} bps, max {}",
+            deviation_bps,
+            MAX_IRM_SWITCH_DEVIATION_BPS
+        );
+        return Err(MorphoError::IrmSwitchDeviation.into());
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Cancel IRM Update
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct CancelIrmUpdate<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PROGRAM_SEED_PREFIX, IrmUpdateProposal::SEED, &market_id],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, IrmUpdateProposal>,
+}
+
+/// Drop the pending IRM migration
+pub fn cancel_irm_update(ctx: Context<CancelIrmUpdate>, market_id: [u8; 32]) -> Result<()> {
+    emit!(IrmUpdateCancelled {
+        market_id,
+        new_irm: ctx.accounts.proposal.new_irm,
+    });
+    Ok(())
+}
@@ -0,0 +1,113 @@
+//! Reputation LLTV boost
+//!
+//! Opt-in per market: the owner sets a small LLTV bonus, the lifetime
+//! repaid debt a position needs to earn it, and a minimum age. Positions that
+//! were never liquidated, are at least that old, and have repaid at least
+//! that much of debt held for that long are health-checked (and liquidated)
+//! at `lltv + boost`. The liquidation incentive stays tied to the base LLTV.
+//!
+//! Repayments of debt borrowed within the window (the same transaction, a
+//! flash loan round trip) still count in `total_repaid` but not toward the
+//! boost, so repaid volume can't be churned into one.
+
+use anchor_lang::prelude::*;
+use crate::constants::{
+    PROGRAM_SEED_PREFIX, BPS, LIF_BPS, MAX_REPUTATION_LLTV_BOOST_BPS, MAX_REPUTATION_MIN_AGE,
+};
+use crate::errors::MorphoError;
+use crate::events::{ReputationBoostSet, ReputationBoostChanged};
+use crate::interfaces::calculate_lif;
+use crate::math::checked_add;
+use crate::state::{ProtocolState, Market, Position};
+
+/// Add `assets` repaid at `now` to `position`'s history
+///
+/// Only debt held for the market's `reputation_min_age` since the latest
+/// borrow counts toward the boost.
+pub fn record_repayment(market: &Market, position: &mut Position, assets: u128, now: i64) -> Result<()> {
+    position.total_repaid = checked_add(position.total_repaid, assets)?;
+    if market.is_debt_seasoned(position, now) {
+        position.seasoned_repaid = checked_add(position.seasoned_repaid, assets)?;
+    }
+    Ok(())
+}
+
+/// Record whether `position` currently earns the boost
+///
+/// Call after a position's effects; emits `ReputationBoostChanged` when the
+/// boost was gained or lost since the last sync.
+pub fn sync_reputation_boost(market: &Market, position: &mut Position, now: i64) {
+    let boosted = market.has_reputation_boost(position, now);
+    if boosted == position.reputation_boosted {
+        return;
+    }
+
+    position.reputation_boosted = boosted;
+    emit!(ReputationBoostChanged {
+        market_id: market.market_id,
+        owner: position.owner,
+        boosted,
+        lltv: market.position_lltv(position, now),
+    });
+}
+
+// ============================================================================
+// Set Reputation Boost
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetReputationBoost<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Configure the boost (0 turns it off)
+///
+/// The boosted LLTV must still leave room for the liquidation incentive:
+/// `(lltv + boost) * LIF` stays under 100%. An enabled boost needs a nonzero
+/// `min_age`, or same-transaction borrow and repay would earn it.
+pub fn set_reputation_boost(
+    ctx: Context<SetReputationBoost>,
+    market_id: [u8; 32],
+    boost_bps: u64,
+    min_repaid: u128,
+    min_age: i64,
+) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    require!(boost_bps <= MAX_REPUTATION_LLTV_BOOST_BPS, MorphoError::InvalidInput);
+    require!(
+        (0..=MAX_REPUTATION_MIN_AGE).contains(&min_age) && (boost_bps == 0 || min_age > 0),
+        MorphoError::InvalidInput
+    );
+    require!(
+        ((market.lltv + boost_bps) as u128) * (calculate_lif(market.lltv) as u128)
+            < (BPS as u128) * (LIF_BPS as u128),
+        MorphoError::InvalidInput
+    );
+
+    market.reputation_lltv_boost_bps = boost_bps;
+    market.reputation_min_repaid = min_repaid;
+    market.reputation_min_age = min_age;
+
+    emit!(ReputationBoostSet {
+        market_id,
+        boost_bps,
+        min_repaid,
+        min_age,
+    });
+    Ok(())
+}
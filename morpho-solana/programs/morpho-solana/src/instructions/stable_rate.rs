@@ -0,0 +1,471 @@
+//! Stable-rate borrowing
+//!
+//! Opt-in per market. A stable loan locks the variable rate at the time of
+//! borrowing plus the market's spread; further stable borrows blend into
+//! the position's rate by amount. Stable debt is kept apart from borrow
+//! shares: each position compounds its own principal at its own rate, and
+//! the market keeps the aggregate at the debt-weighted average rate so
+//! suppliers earn it through the usual accrual.
+//!
+//! When utilization reaches the market's rebalance threshold, anyone may
+//! lift a position's rate to the current stable offer, so cheap locked
+//! loans can't starve suppliers of yield.
+//!
+//! CEI Pattern: Checks → Effects → Interactions
+
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked};
+use crate::constants::{PROGRAM_SEED_PREFIX, BPS, WAD, MAX_STABLE_RATE_SPREAD_BPS};
+use crate::errors::MorphoError;
+use crate::events::{StableRateParamsSet, StableBorrow, StableRepay, StableRateRebalanced};
+use crate::state::{ProtocolState, Market, MarketAction, Position, Authorization};
+use crate::math::{
+    checked_add, checked_sub, mul_div_down, safe_u128_to_u64,
+    accrue_market_interest, accrue_stable_debt,
+};
+use crate::instructions::insurance::charge_insurance_premium;
+use crate::instructions::reputation::{record_repayment, sync_reputation_boost};
+use crate::instructions::yield_adapter::ensure_loan_vault_liquidity;
+use crate::interfaces::{
+    enforce_risk_cap, ensure_position_healthy,
+};
+use crate::time::current_clock;
+
+/// Rate a new stable loan locks in: the current variable rate plus the spread
+///
+/// The variable rate is the one this slot's accrual sampled, so call after
+/// `accrue_market_interest`; the IRM isn't queried twice.
+pub fn stable_rate_offer(market: &Market) -> Result<u128> {
+    checked_add(market.rate_cache, market.stable_rate_spread()?)
+}
+
+/// Rate of `debt` at `rate` after adding `amount` at `new_rate`
+pub fn blended_stable_rate(debt: u128, rate: u128, amount: u128, new_rate: u128) -> Result<u128> {
+    let total = checked_add(debt, amount)?;
+    if total == 0 {
+        return Ok(new_rate);
+    }
+    if new_rate >= rate {
+        checked_add(rate, mul_div_down(new_rate - rate, amount, total)?)
+    } else {
+        checked_sub(rate, mul_div_down(rate - new_rate, amount, total)?)
+    }
+}
+
+/// Whether the market is utilized enough for stable rates to be rebalanced up
+pub fn can_rebalance_stable_rates(market: &Market) -> bool {
+    market.stable_rebalance_utilization_bps > 0
+        && market.utilization()
+            >= market.stable_rebalance_utilization_bps as u128 * (WAD / BPS as u128)
+}
+
+// ============================================================================
+// Set Stable Rate Params
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetStableRateParams<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Offer (or stop offering) stable loans; existing loans keep their rates
+pub fn set_stable_rate_params(
+    ctx: Context<SetStableRateParams>,
+    market_id: [u8; 32],
+    enabled: bool,
+    spread_bps: u64,
+    rebalance_utilization_bps: u64,
+) -> Result<()> {
+    require!(spread_bps <= MAX_STABLE_RATE_SPREAD_BPS, MorphoError::InvalidInput);
+    require!(rebalance_utilization_bps <= BPS, MorphoError::InvalidInput);
+
+    let market = &mut ctx.accounts.market;
+    market.stable_borrow_enabled = enabled;
+    market.stable_rate_spread_bps = spread_bps;
+    market.stable_rebalance_utilization_bps = rebalance_utilization_bps;
+
+    emit!(StableRateParamsSet {
+        market_id,
+        enabled,
+        spread_bps,
+        rebalance_utilization_bps,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Borrow Stable
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct BorrowStable<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    pub authorization: Option<Account<'info, Authorization>>,
+
+    /// CHECK: Oracle account for health check
+    pub oracle: UncheckedAccount<'info>,
+
+    #[account(constraint = loan_mint.key() == market.loan_mint @ MorphoError::InvalidMint)]
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Wallet receiving the loan tokens (owner of receiver_token_account)
+    pub receiver: UncheckedAccount<'info>,
+
+    /// Receiver's ATA - created on the fly (paid by caller) if it doesn't exist yet
+    #[account(
+        init_if_needed,
+        payer = caller,
+        associated_token::mint = loan_mint,
+        associated_token::authority = receiver,
+        associated_token::token_program = token_program,
+    )]
+    pub receiver_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Risk oracle feed, required when the market has one
+    pub risk_oracle: Option<UncheckedAccount<'info>>,
+}
+
+/// Borrow at a locked rate (`max_rate` 0 = no slippage limit)
+pub fn borrow_stable<'info>(
+    ctx: Context<'_, '_, 'info, 'info, BorrowStable<'info>>,
+    market_id: [u8; 32],
+    assets: u128,
+    max_rate: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    ctx.accounts.market.check_action(MarketAction::Borrow)?;
+    require!(ctx.accounts.market.stable_borrow_enabled, MorphoError::StableBorrowDisabled);
+    require!(assets > 0, MorphoError::ZeroAmount);
+
+    validate_authorization(
+        &ctx.accounts.caller,
+        &ctx.accounts.position.owner,
+        ctx.accounts.authorization.as_ref(),
+    )?;
+
+    // Accrue interest
+    let clock = current_clock(ctx.remaining_accounts)?;
+
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+    let position = &mut ctx.accounts.position;
+    charge_insurance_premium(market, position, clock.unix_timestamp)?;
+    accrue_stable_debt(market, position, clock.unix_timestamp)?;
+
+    require!(
+        assets <= market.available_liquidity(),
+        MorphoError::InsufficientLiquidity
+    );
+
+    let rate = stable_rate_offer(market)?;
+    if max_rate > 0 {
+        require!(rate <= max_rate, MorphoError::SlippageExceeded);
+    }
+
+    // ===== EFFECTS =====
+    let new_borrower = position.stable_borrow_assets == 0;
+    market.add_stable_debt(assets, rate, new_borrower)?;
+    // The aggregate holds the position's debt at its old rate; move it to the blend
+    let blended = blended_stable_rate(position.stable_borrow_assets, position.stable_rate, assets, rate)?;
+    market.reprice_stable_debt(position.stable_borrow_assets, position.stable_rate, blended)?;
+    market.reprice_stable_debt(assets, rate, blended)?;
+    position.stable_borrow_assets = checked_add(position.stable_borrow_assets, assets)?;
+    position.stable_rate = blended;
+    position.total_borrowed = checked_add(position.total_borrowed, assets)?;
+    position.last_borrowed_at = clock.unix_timestamp;
+
+    // Health check AFTER effect, against the buffered borrow LLTV
+    ensure_position_healthy(
+        market,
+        position,
+        ctx.accounts.protocol_state.position_borrow_lltv(market, position, clock.unix_timestamp),
+        Some(ctx.accounts.oracle.as_ref()),
+        None,
+    )?;
+    sync_reputation_boost(market, position, clock.unix_timestamp);
+
+    enforce_risk_cap(
+        market,
+        ctx.accounts.risk_oracle.as_ref().map(|o| o.as_ref()),
+        clock.unix_timestamp,
+    )?;
+
+    // ===== INTERACTIONS =====
+    let amount_u64 = safe_u128_to_u64(assets)?;
+    let bump = market.bump;
+
+    // Recall deployed liquidity if the vault alone can't cover the transfer
+    // (remaining accounts: [adapter_program, ...adapter accounts])
+    ensure_loan_vault_liquidity(
+        ctx.accounts.caller.key(),
+        &mut ctx.accounts.market,
+        &mut ctx.accounts.loan_vault,
+        &ctx.accounts.loan_mint.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.remaining_accounts,
+        assets,
+    )?;
+
+    let seeds = &[
+        PROGRAM_SEED_PREFIX,
+        Market::SEED,
+        market_id.as_ref(),
+        &[bump],
+    ];
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.loan_vault.to_account_info(),
+                to: ctx.accounts.receiver_token_account.to_account_info(),
+                authority: ctx.accounts.market.to_account_info(),
+                mint: ctx.accounts.loan_mint.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount_u64,
+        ctx.accounts.loan_mint.decimals,
+    )?;
+
+    emit!(StableBorrow {
+        market_id,
+        caller: ctx.accounts.caller.key(),
+        on_behalf_of: ctx.accounts.position.owner,
+        receiver: ctx.accounts.receiver_token_account.key(),
+        assets,
+        stable_rate: ctx.accounts.position.stable_rate,
+    });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.position);
+
+    Ok(())
+}
+
+// ============================================================================
+// Repay Stable
+// ============================================================================
+
+/// Takes no oracle, like `repay`
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct RepayStable<'info> {
+    #[account(mut)]
+    pub repayer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, on_behalf_of.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    /// CHECK: Position owner
+    pub on_behalf_of: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = repayer_token_account.mint == market.loan_mint,
+    )]
+    pub repayer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Repay stable debt (capped at what the position owes)
+pub fn repay_stable(
+    ctx: Context<RepayStable>,
+    market_id: [u8; 32],
+    assets: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    // Note: Repay allowed even when paused (helps users exit)
+    require!(assets > 0, MorphoError::ZeroAmount);
+
+    // Accrue interest
+    let clock = current_clock(ctx.remaining_accounts)?;
+
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+    let position = &mut ctx.accounts.position;
+    charge_insurance_premium(market, position, clock.unix_timestamp)?;
+    accrue_stable_debt(market, position, clock.unix_timestamp)?;
+
+    let repay_assets = std::cmp::min(assets, position.stable_borrow_assets);
+    require!(repay_assets > 0, MorphoError::ZeroAmount);
+
+    // ===== EFFECTS =====
+    position.stable_borrow_assets = checked_sub(position.stable_borrow_assets, repay_assets)?;
+    let cleared = position.stable_borrow_assets == 0;
+    market.remove_stable_debt(repay_assets, position.stable_rate, cleared)?;
+    if cleared {
+        position.stable_rate = 0;
+    }
+    record_repayment(market, position, repay_assets, clock.unix_timestamp)?;
+    sync_reputation_boost(market, position, clock.unix_timestamp);
+
+    // ===== INTERACTIONS =====
+    let amount_u64 = safe_u128_to_u64(repay_assets)?;
+    transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.repayer_token_account.to_account_info(),
+                to: ctx.accounts.loan_vault.to_account_info(),
+                authority: ctx.accounts.repayer.to_account_info(),
+                mint: ctx.accounts.loan_mint.to_account_info(),
+            },
+        ),
+        amount_u64,
+        ctx.accounts.loan_mint.decimals,
+    )?;
+
+    emit!(StableRepay {
+        market_id,
+        repayer: ctx.accounts.repayer.key(),
+        on_behalf_of: ctx.accounts.on_behalf_of.key(),
+        assets: repay_assets,
+    });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.position);
+
+    Ok(())
+}
+
+// ============================================================================
+// Rebalance Stable Rate (Public)
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct RebalanceStableRate<'info> {
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+}
+
+/// Lift a position's stable rate to the current offer while utilization is high
+pub fn rebalance_stable_rate(ctx: Context<RebalanceStableRate>, market_id: [u8; 32]) -> Result<()> {
+    let clock = current_clock(ctx.remaining_accounts)?;
+
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+    let position = &mut ctx.accounts.position;
+    accrue_stable_debt(market, position, clock.unix_timestamp)?;
+
+    require!(position.stable_borrow_assets > 0, MorphoError::StableRateNotRebalanceable);
+    require!(can_rebalance_stable_rates(market), MorphoError::StableRateNotRebalanceable);
+    let new_rate = stable_rate_offer(market)?;
+    let old_rate = position.stable_rate;
+    require!(new_rate > old_rate, MorphoError::StableRateNotRebalanceable);
+
+    market.reprice_stable_debt(position.stable_borrow_assets, old_rate, new_rate)?;
+    position.stable_rate = new_rate;
+
+    emit!(StableRateRebalanced {
+        market_id,
+        owner: position.owner,
+        old_rate,
+        new_rate,
+    });
+    Ok(())
+}
+
+/// Validate authorization for delegated operations
+fn validate_authorization(
+    caller: &Signer,
+    owner: &Pubkey,
+    authorization: Option<&Account<Authorization>>,
+) -> Result<()> {
+    if caller.key() == *owner {
+        return Ok(());
+    }
+
+    let current_time = Clock::get()?.unix_timestamp;
+
+    if let Some(auth) = authorization {
+        if auth.authorizer == *owner
+            && auth.authorized == caller.key()
+            && auth.is_valid(current_time)
+        {
+            return Ok(());
+        }
+    }
+
+    Err(MorphoError::Unauthorized.into())
+}
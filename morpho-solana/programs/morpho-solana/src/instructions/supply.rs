@@ -3,17 +3,20 @@
 //! CEI Pattern: Checks → Effects → Interactions
 
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, transfer_checked, TransferChecked};
-use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::constants::{PROGRAM_SEED_PREFIX, SECONDS_PER_DAY, MAX_SEED_LOCK_DAYS, MAX_SUPPLY_ESCROW_DURATION};
 use crate::errors::MorphoError;
 use crate::events;
-use crate::state::{ProtocolState, Market, Position, Authorization};
+use crate::state::{ProtocolState, Market, MarketAction, Position, Authorization, HoldingPeriodExemption};
 use crate::math::{
-    checked_add, checked_sub, safe_u128_to_u64,
+    checked_add, checked_sub, mul_div_down, mul_div_up, safe_u128_to_u64,
     to_shares_down, to_shares_up, to_assets_down,
-    accrue_interest_on_market,
+    accrue_market_interest,
 };
-use crate::interfaces::get_borrow_rate_internal;
+use crate::instructions::utils::prepare_receiver_token_account;
+use crate::instructions::yield_adapter::ensure_loan_vault_liquidity;
+use crate::time::current_clock;
 
 // ============================================================================
 // Supply
@@ -64,6 +67,13 @@ pub struct Supply<'info> {
     pub loan_mint: InterfaceAccount<'info, Mint>,
 
     pub token_program: Interface<'info, TokenInterface>,
+
+    /// Position owner's holding period exemption, if it has one
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, HoldingPeriodExemption::SEED, on_behalf_of.key().as_ref()],
+        bump = holding_exemption.bump,
+    )]
+    pub holding_exemption: Option<Account<'info, HoldingPeriodExemption>>,
 }
 
 pub fn supply(
@@ -74,18 +84,14 @@ pub fn supply(
 ) -> Result<()> {
     // ===== CHECKS =====
     require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
-    require!(!ctx.accounts.market.paused, MorphoError::MarketPaused);
+    ctx.accounts.market.check_action(MarketAction::Supply)?;
     require!(assets > 0, MorphoError::ZeroAmount);
 
     // Accrue interest
-    let borrow_rate = get_borrow_rate_internal(
-        ctx.accounts.market.total_supply_assets,
-        ctx.accounts.market.total_borrow_assets,
-    )?;
-    let current_time = Clock::get()?.unix_timestamp;
-    
+    let clock = current_clock(ctx.remaining_accounts)?;
+
     let market = &mut ctx.accounts.market;
-    accrue_interest_on_market(market, current_time, borrow_rate)?;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
 
     // Calculate shares (round DOWN - user gets fewer shares)
     let shares = to_shares_down(
@@ -99,6 +105,10 @@ pub fn supply(
     market.total_supply_assets = checked_add(market.total_supply_assets, assets)?;
     market.total_supply_shares = checked_add(market.total_supply_shares, shares)?;
     ctx.accounts.position.supply_shares = checked_add(ctx.accounts.position.supply_shares, shares)?;
+    ctx.accounts.position.total_supplied = checked_add(ctx.accounts.position.total_supplied, assets)?;
+    if ctx.accounts.holding_exemption.is_none() {
+        hold_new_supply(market, &mut ctx.accounts.position, shares, clock.unix_timestamp)?;
+    }
 
     // ===== INTERACTIONS =====
     let amount_u64 = safe_u128_to_u64(assets)?;
@@ -123,6 +133,335 @@ pub fn supply(
         assets,
         shares,
     });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.position);
+    crate::telemetry!("supply", market_id, assets = assets, shares = shares);
+
+    Ok(())
+}
+
+/// Lock `shares` just supplied to `position` for the market's holding period
+///
+/// Shares still locked from earlier supplies share the one window, which
+/// ends at the share-weighted average of its old end and the new shares'.
+/// Anyone can supply on a position's behalf, so a supply only moves the
+/// old shares' unlock in proportion to its own size: dust can't keep a
+/// position locked.
+pub fn hold_new_supply(market: &Market, position: &mut Position, shares: u128, now: i64) -> Result<()> {
+    if market.min_supply_holding_period == 0 || shares == 0 {
+        return Ok(());
+    }
+    let locked = position.locked_supply_shares_at(now);
+    let remaining = (position.supply_locked_until - now).max(0) as u128;
+    let period = market.min_supply_holding_period as u128;
+    let total = checked_add(locked, shares)?;
+    // The new shares' part rounds up, the old shares' part down: dust
+    // can't round the window out by a second per supply
+    let held_for = checked_add(
+        mul_div_down(locked, remaining, total)?,
+        mul_div_up(shares, period, total)?,
+    )?;
+    position.locked_supply_shares = total;
+    position.supply_locked_until = now + held_for as i64;
+    Ok(())
+}
+
+// ============================================================================
+// Holding Period Exemptions
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(account: Pubkey)]
+pub struct ExemptFromHoldingPeriod<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = HoldingPeriodExemption::space(),
+        seeds = [PROGRAM_SEED_PREFIX, HoldingPeriodExemption::SEED, account.as_ref()],
+        bump,
+    )]
+    pub holding_exemption: Account<'info, HoldingPeriodExemption>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Let `account`'s supplies skip every market's holding period
+///
+/// Meant for protocol-owned integrations; shares already locked stay locked.
+pub fn exempt_from_holding_period(ctx: Context<ExemptFromHoldingPeriod>, account: Pubkey) -> Result<()> {
+    let exemption = &mut ctx.accounts.holding_exemption;
+    exemption.bump = ctx.bumps.holding_exemption;
+    exemption.account = account;
+
+    emit!(events::HoldingPeriodExemptionUpdated { account, exempt: true });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(account: Pubkey)]
+pub struct RevokeHoldingPeriodExemption<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [PROGRAM_SEED_PREFIX, HoldingPeriodExemption::SEED, account.as_ref()],
+        bump = holding_exemption.bump,
+    )]
+    pub holding_exemption: Account<'info, HoldingPeriodExemption>,
+}
+
+pub fn revoke_holding_period_exemption(
+    _ctx: Context<RevokeHoldingPeriodExemption>,
+    account: Pubkey,
+) -> Result<()> {
+    emit!(events::HoldingPeriodExemptionUpdated { account, exempt: false });
+    Ok(())
+}
+
+// ============================================================================
+// Seed Market
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SeedMarket<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// Protocol-owned position (the fee recipient's, same as claim_fees)
+    #[account(
+        mut,
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            Position::SEED,
+            &market_id,
+            protocol_state.fee_recipient.as_ref(),
+        ],
+        bump = protocol_position.bump,
+    )]
+    pub protocol_position: Box<Account<'info, Position>>,
+
+    /// Treasury token account funding the seed (owner is its authority)
+    #[account(
+        mut,
+        constraint = treasury_token_account.mint == market.loan_mint,
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Supply protocol-owned liquidity to bootstrap a market
+///
+/// Shares go to the fee recipient's position and are locked for `lock_days`.
+/// Seeding again adds to the locked shares and never shortens the lock.
+pub fn seed_market(
+    ctx: Context<SeedMarket>,
+    market_id: [u8; 32],
+    assets: u128,
+    lock_days: u32,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(assets > 0, MorphoError::ZeroAmount);
+    require!(lock_days <= MAX_SEED_LOCK_DAYS, MorphoError::InvalidInput);
+
+    // Accrue interest
+    let clock = current_clock(ctx.remaining_accounts)?;
+
+    let market = &mut ctx.accounts.market;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
+
+    // Calculate shares (round DOWN - same as a regular supply)
+    let shares = to_shares_down(
+        assets,
+        market.total_supply_assets,
+        market.total_supply_shares,
+    )?;
+    require!(shares > 0, MorphoError::ZeroAmount);
+
+    // ===== EFFECTS =====
+    market.total_supply_assets = checked_add(market.total_supply_assets, assets)?;
+    market.total_supply_shares = checked_add(market.total_supply_shares, shares)?;
+
+    let position = &mut ctx.accounts.protocol_position;
+    let locked_until = clock.unix_timestamp + lock_days as i64 * SECONDS_PER_DAY;
+    position.supply_shares = checked_add(position.supply_shares, shares)?;
+    position.total_supplied = checked_add(position.total_supplied, assets)?;
+    position.locked_supply_shares = checked_add(
+        position.locked_supply_shares_at(clock.unix_timestamp),
+        shares,
+    )?;
+    position.supply_locked_until = std::cmp::max(position.supply_locked_until, locked_until);
+
+    // ===== INTERACTIONS =====
+    let amount_u64 = safe_u128_to_u64(assets)?;
+    transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.treasury_token_account.to_account_info(),
+                to: ctx.accounts.loan_vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+                mint: ctx.accounts.loan_mint.to_account_info(),
+            },
+        ),
+        amount_u64,
+        ctx.accounts.loan_mint.decimals,
+    )?;
+
+    emit!(events::MarketSeeded {
+        market_id,
+        recipient: ctx.accounts.protocol_position.owner,
+        assets,
+        shares,
+        locked_until: ctx.accounts.protocol_position.supply_locked_until,
+    });
+
+    Ok(())
+}
+
+// ============================================================================
+// Supply Escrow
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct LockSupplyShares<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+}
+
+/// Escrow `shares` of the owner's supply for at least `duration` seconds
+///
+/// Escrowed shares keep earning interest but can't be withdrawn until
+/// `unlock_supply_shares`. Morpho gives them no other meaning; the events
+/// let external governance or boost programs weight them. Locking again
+/// adds to the escrow and never shortens it. Only the owner may lock, and
+/// shares still under a holding-period or seed lock can't be escrowed too.
+pub fn lock_supply_shares(
+    ctx: Context<LockSupplyShares>,
+    market_id: [u8; 32],
+    shares: u128,
+    duration: i64,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    require!(shares > 0, MorphoError::ZeroAmount);
+    require!(
+        duration > 0 && duration <= MAX_SUPPLY_ESCROW_DURATION,
+        MorphoError::InvalidInput
+    );
+
+    let now = current_clock(ctx.remaining_accounts)?.unix_timestamp;
+    let position = &mut ctx.accounts.position;
+    let free_shares = position.supply_shares
+        .saturating_sub(position.unwithdrawable_supply_shares_at(now));
+    require!(shares <= free_shares, MorphoError::InsufficientBalance);
+
+    // ===== EFFECTS =====
+    position.escrowed_supply_shares = checked_add(position.escrowed_supply_shares, shares)?;
+    position.escrow_unlock_at = std::cmp::max(position.escrow_unlock_at, now + duration);
+
+    emit!(events::SupplySharesLocked {
+        market_id,
+        owner: position.owner,
+        shares,
+        escrowed_shares: position.escrowed_supply_shares,
+        unlock_at: position.escrow_unlock_at,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct UnlockSupplyShares<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Box<Account<'info, Position>>,
+}
+
+/// Release the owner's whole escrow once it has expired
+///
+/// Works while paused: it moves no funds, only lets a later withdraw through.
+pub fn unlock_supply_shares(ctx: Context<UnlockSupplyShares>, market_id: [u8; 32]) -> Result<()> {
+    // ===== CHECKS =====
+    let position = &mut ctx.accounts.position;
+    let shares = position.escrowed_supply_shares;
+    require!(shares > 0, MorphoError::NoEscrowedSupply);
+    require!(
+        current_clock(ctx.remaining_accounts)?.unix_timestamp >= position.escrow_unlock_at,
+        MorphoError::SupplyLocked
+    );
+
+    // ===== EFFECTS =====
+    position.escrowed_supply_shares = 0;
+    position.escrow_unlock_at = 0;
+
+    emit!(events::SupplySharesUnlocked {
+        market_id,
+        owner: position.owner,
+        shares,
+        escrowed_shares: 0,
+    });
 
     Ok(())
 }
@@ -160,11 +499,17 @@ pub struct Withdraw<'info> {
     /// Optional authorization account
     pub authorization: Option<Account<'info, Authorization>>,
 
-    #[account(
-        mut,
-        constraint = receiver_token_account.mint == market.loan_mint,
-    )]
-    pub receiver_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(constraint = loan_mint.key() == market.loan_mint @ MorphoError::InvalidMint)]
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Wallet receiving the loan tokens; only used to create its ATA
+    pub receiver: UncheckedAccount<'info>,
+
+    /// CHECK: Any token account of the loan mint, or the receiver's ATA, which
+    /// is created on the fly (paid by caller) if it doesn't exist yet; checked
+    /// by `prepare_receiver_token_account`
+    #[account(mut)]
+    pub receiver_token_account: UncheckedAccount<'info>,
 
     #[account(
         mut,
@@ -173,20 +518,20 @@ pub struct Withdraw<'info> {
     )]
     pub loan_vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub loan_mint: InterfaceAccount<'info, Mint>,
-
     pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
-pub fn withdraw(
-    ctx: Context<Withdraw>,
+pub fn withdraw<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>,
     market_id: [u8; 32],
     assets: u128,
     shares: u128,
 ) -> Result<()> {
     // ===== CHECKS =====
     require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
-    require!(!ctx.accounts.market.paused, MorphoError::MarketPaused);
+    ctx.accounts.market.check_action(MarketAction::Withdraw)?;
     require!(assets > 0 || shares > 0, MorphoError::ZeroAmount);
     require!(!(assets > 0 && shares > 0), MorphoError::InvalidInput);
 
@@ -198,14 +543,10 @@ pub fn withdraw(
     )?;
 
     // Accrue interest
-    let borrow_rate = get_borrow_rate_internal(
-        ctx.accounts.market.total_supply_assets,
-        ctx.accounts.market.total_borrow_assets,
-    )?;
-    let current_time = Clock::get()?.unix_timestamp;
-    
+    let clock = current_clock(ctx.remaining_accounts)?;
+
     let market = &mut ctx.accounts.market;
-    accrue_interest_on_market(market, current_time, borrow_rate)?;
+    accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
 
     // Calculate amounts
     let (withdraw_assets, burn_shares) = if assets > 0 {
@@ -227,13 +568,54 @@ pub fn withdraw(
 
     // ===== EFFECTS =====
     ctx.accounts.position.supply_shares = checked_sub(ctx.accounts.position.supply_shares, burn_shares)?;
+    ctx.accounts.position.total_withdrawn = checked_add(ctx.accounts.position.total_withdrawn, withdraw_assets)?;
     market.total_supply_assets = checked_sub(market.total_supply_assets, withdraw_assets)?;
     market.total_supply_shares = checked_sub(market.total_supply_shares, burn_shares)?;
 
+    // Locked shares stay until their lock expires, escrowed ones until unlocked
+    require!(
+        ctx.accounts.position.supply_shares
+            >= ctx.accounts.position.unwithdrawable_supply_shares_at(clock.unix_timestamp),
+        MorphoError::SupplyLocked
+    );
+
+    // Supply delegated to credit lines stays locked
+    require!(
+        to_assets_down(
+            ctx.accounts.position.supply_shares,
+            market.total_supply_assets,
+            market.total_supply_shares,
+        )? >= ctx.accounts.position.credit_delegated,
+        MorphoError::CreditLineUnderfunded
+    );
+
     // ===== INTERACTIONS =====
     let amount_u64 = safe_u128_to_u64(withdraw_assets)?;
     let market_id_ref = market_id;
     let bump = market.bump;
+
+    // Recall deployed liquidity if the vault alone can't cover the transfer
+    // (remaining accounts: [adapter_program, ...adapter accounts])
+    ensure_loan_vault_liquidity(
+        ctx.accounts.caller.key(),
+        &mut ctx.accounts.market,
+        &mut ctx.accounts.loan_vault,
+        &ctx.accounts.loan_mint.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.remaining_accounts,
+        withdraw_assets,
+    )?;
+
+    prepare_receiver_token_account(
+        &ctx.accounts.receiver_token_account.to_account_info(),
+        &ctx.accounts.receiver.to_account_info(),
+        &ctx.accounts.loan_mint.to_account_info(),
+        &ctx.accounts.caller.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.associated_token_program.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
     let seeds = &[
         PROGRAM_SEED_PREFIX,
         Market::SEED,
@@ -264,6 +646,8 @@ pub fn withdraw(
         assets: withdraw_assets,
         shares: burn_shares,
     });
+    crate::audit!(ctx.accounts.market, ctx.remaining_accounts, ctx.accounts.position);
+    crate::telemetry!("withdraw", market_id, assets = withdraw_assets, shares = burn_shares);
 
     Ok(())
 }
@@ -0,0 +1,133 @@
+//! TWAP oracle instructions
+//!
+//! Anyone can create a `TwapOracle` over a feed and crank it; markets opt in
+//! by using the TWAP account as their oracle. Samples are spaced at least
+//! `window / TWAP_MAX_SAMPLES` apart so the ring always spans a full window.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::errors::MorphoError;
+use crate::events::{TwapOracleCreated, TwapUpdated};
+use crate::interfaces::{
+    is_twap_oracle, read_feed_price, TwapOracle, TwapSample, MAX_TWAP_WINDOW, MIN_TWAP_WINDOW,
+};
+
+// ============================================================================
+// Create TWAP Oracle
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(window: i64, collateral_decimals: u8, loan_decimals: u8)]
+pub struct CreateTwapOracle<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Underlying feed, validated by reading a first sample from it
+    pub source: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = TwapOracle::space(),
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            TwapOracle::SEED,
+            source.key().as_ref(),
+            &window.to_le_bytes(),
+            &[collateral_decimals, loan_decimals],
+        ],
+        bump,
+    )]
+    pub twap_oracle: Account<'info, TwapOracle>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Create a TWAP oracle over `source`, seeded with its current price
+///
+/// The TWAP can't price until its samples cover `window` seconds.
+pub fn create_twap_oracle(
+    ctx: Context<CreateTwapOracle>,
+    window: i64,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+) -> Result<()> {
+    // CHECKS
+    require!(
+        (MIN_TWAP_WINDOW..=MAX_TWAP_WINDOW).contains(&window),
+        MorphoError::InvalidTwapConfig
+    );
+    let source = ctx.accounts.source.to_account_info();
+    require!(!is_twap_oracle(&source)?, MorphoError::InvalidTwapConfig);
+    let price = read_feed_price(&source, collateral_decimals, loan_decimals)?;
+
+    // EFFECTS
+    let clock = Clock::get()?;
+    let twap = &mut ctx.accounts.twap_oracle;
+    twap.bump = ctx.bumps.twap_oracle;
+    twap.source = source.key();
+    twap.collateral_decimals = collateral_decimals;
+    twap.loan_decimals = loan_decimals;
+    twap.window = window;
+    twap.push(TwapSample { timestamp: clock.unix_timestamp, price }, clock.slot);
+
+    emit!(TwapOracleCreated {
+        twap_oracle: twap.key(),
+        source: twap.source,
+        window,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Update TWAP (permissionless crank)
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct UpdateTwap<'info> {
+    #[account(
+        mut,
+        seeds = [
+            PROGRAM_SEED_PREFIX,
+            TwapOracle::SEED,
+            twap_oracle.source.as_ref(),
+            &twap_oracle.window.to_le_bytes(),
+            &[twap_oracle.collateral_decimals, twap_oracle.loan_decimals],
+        ],
+        bump = twap_oracle.bump,
+    )]
+    pub twap_oracle: Account<'info, TwapOracle>,
+
+    /// CHECK: Must be the TWAP's source feed
+    #[account(constraint = source.key() == twap_oracle.source @ MorphoError::InvalidOracle)]
+    pub source: UncheckedAccount<'info>,
+}
+
+/// Sample the source feed into the TWAP
+pub fn update_twap(ctx: Context<UpdateTwap>) -> Result<()> {
+    let clock = Clock::get()?;
+    let twap = &mut ctx.accounts.twap_oracle;
+
+    // CHECKS
+    if let Some(latest) = twap.latest() {
+        require!(
+            clock.unix_timestamp - latest.timestamp >= twap.min_update_interval(),
+            MorphoError::TwapUpdateTooSoon
+        );
+    }
+    let price = read_feed_price(
+        &ctx.accounts.source.to_account_info(),
+        twap.collateral_decimals,
+        twap.loan_decimals,
+    )?;
+
+    // EFFECTS
+    twap.push(TwapSample { timestamp: clock.unix_timestamp, price }, clock.slot);
+
+    emit!(TwapUpdated {
+        twap_oracle: twap.key(),
+        price,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
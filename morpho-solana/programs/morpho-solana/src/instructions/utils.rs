@@ -1,12 +1,81 @@
-//! Utility instructions (accrue interest, set authorization, claim fees)
+//! Utility instructions (accrue interest, accrual priorities, collateral
+//! reconciliation, protocol status, set authorization, claim fees), the
+//! version handshake and receiver token account checks
 
 use anchor_lang::prelude::*;
-use crate::constants::PROGRAM_SEED_PREFIX;
+use anchor_spl::associated_token::{create, get_associated_token_address_with_program_id, Create};
+use anchor_spl::token_interface::TokenAccount;
+use crate::constants::{PROGRAM_SEED_PREFIX, MAX_ACCRUAL_PRIORITY_MARKETS, PROGRAM_VERSION};
 use crate::errors::MorphoError;
-use crate::events::{InterestAccrued, AuthorizationSet, AuthorizationRevoked, FeesClaimed};
+use crate::events::{InterestAccrued, AuthorizationSet, AuthorizationRevoked, FeesClaimed, CollateralDrift};
 use crate::state::{ProtocolState, Market, Position, Authorization};
-use crate::math::{checked_add, accrue_interest_on_market};
-use crate::interfaces::get_borrow_rate_internal;
+use crate::math::{checked_add, accrue_market_interest};
+use crate::instructions::view::{VersionedView, ViewResponse};
+use crate::time::current_clock;
+
+// ============================================================================
+// Receiver Token Accounts
+// ============================================================================
+
+/// Check the account loan tokens are sent to, creating it first if needed
+///
+/// Any token account of `mint` is accepted, ATA or not. An account that
+/// doesn't exist yet must be `receiver`'s associated token address, and is
+/// created there with `payer` covering the rent, so fresh wallets can
+/// borrow or withdraw without a setup transaction.
+pub fn prepare_receiver_token_account<'info>(
+    receiver_token_account: &AccountInfo<'info>,
+    receiver: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if !receiver_token_account.data_is_empty() {
+        require_keys_eq!(
+            *receiver_token_account.owner,
+            token_program.key(),
+            MorphoError::InvalidReceiverTokenAccount
+        );
+        let account = TokenAccount::try_deserialize(&mut &receiver_token_account.try_borrow_data()?[..])?;
+        require_keys_eq!(account.mint, *mint.key, MorphoError::InvalidMint);
+        return Ok(());
+    }
+
+    let ata = get_associated_token_address_with_program_id(receiver.key, mint.key, token_program.key);
+    require_keys_eq!(receiver_token_account.key(), ata, MorphoError::InvalidReceiverTokenAccount);
+    create(CpiContext::new(
+        associated_token_program.clone(),
+        Create {
+            payer: payer.clone(),
+            associated_token: receiver_token_account.clone(),
+            authority: receiver.clone(),
+            mint: mint.clone(),
+            system_program: system_program.clone(),
+            token_program: token_program.clone(),
+        },
+    ))
+}
+
+// ============================================================================
+// Version Handshake
+// ============================================================================
+
+/// Reject callers built against a different `PROGRAM_VERSION`
+///
+/// Bundles and flash loan callbacks take an optional `expected_version`, so
+/// integrator programs fail fast after an incompatible upgrade instead of
+/// running stale logic against changed accounts. `None` skips the check.
+pub fn check_expected_version(expected_version: Option<u16>) -> Result<()> {
+    if let Some(expected) = expected_version {
+        if expected != PROGRAM_VERSION {
+            msg!("Incompatible version: caller expects {}, program is {}", expected, PROGRAM_VERSION);
+            return Err(MorphoError::IncompatibleVersion.into());
+        }
+    }
+    Ok(())
+}
 
 // ============================================================================
 // Accrue Interest (Public)
@@ -21,17 +90,30 @@ pub struct AccrueInterest<'info> {
         bump = market.bump,
     )]
     pub market: Account<'info, Market>,
+
+    /// Pass with `fee_position` to claim pending fees in the same instruction
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Option<Account<'info, ProtocolState>>,
+
+    /// Fee recipient's position in this market
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Position::SEED, &market_id, fee_position.owner.as_ref()],
+        bump = fee_position.bump,
+    )]
+    pub fee_position: Option<Account<'info, Position>>,
 }
 
+/// Accrue interest, and claim pending fees when the fee position is passed
+/// (saves keepers a separate `claim_fees` per market)
 pub fn accrue_interest_ix(ctx: Context<AccrueInterest>, market_id: [u8; 32]) -> Result<()> {
-    let borrow_rate = get_borrow_rate_internal(
-        ctx.accounts.market.total_supply_assets,
-        ctx.accounts.market.total_borrow_assets,
-    )?;
-    let current_time = Clock::get()?.unix_timestamp;
+    let clock = current_clock(ctx.remaining_accounts)?;
 
     let market = &mut ctx.accounts.market;
-    let result = accrue_interest_on_market(market, current_time, borrow_rate)?;
+    let result = accrue_market_interest(market, &clock, ctx.remaining_accounts)?;
 
     emit!(InterestAccrued {
         market_id,
@@ -39,11 +121,187 @@ pub fn accrue_interest_ix(ctx: Context<AccrueInterest>, market_id: [u8; 32]) ->
         fee_shares: result.fee_shares,
         total_supply_assets: market.total_supply_assets,
         total_borrow_assets: market.total_borrow_assets,
+        total_interest_accrued: market.total_interest_accrued,
+        total_fees_accrued: market.total_fees_accrued,
+        total_liquidated_collateral: market.total_liquidated_collateral,
+        total_bad_debt: market.total_bad_debt,
     });
 
+    match (&ctx.accounts.protocol_state, &mut ctx.accounts.fee_position) {
+        (Some(protocol_state), Some(fee_position)) => {
+            require!(
+                fee_position.owner == protocol_state.fee_recipient,
+                MorphoError::Unauthorized
+            );
+            claim_pending_fees(market, fee_position, market_id)?;
+        }
+        (None, None) => {}
+        _ => return err!(MorphoError::InvalidInput),
+    }
+
     Ok(())
 }
 
+// ============================================================================
+// Accrual Priorities (View)
+// ============================================================================
+
+/// How urgently a market needs its interest accrued
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccrualPriority {
+    pub market_id: [u8; 32],
+    /// Seconds since the market last accrued
+    pub seconds_since_accrual: i64,
+    /// Variable plus stable debt interest accrues on
+    pub total_debt: u128,
+}
+
+/// Rank `markets` for a keeper's next accruals, most urgent first
+///
+/// Markets with debt come first, oldest accrual first; markets without debt
+/// accrue nothing and trail in the same order.
+pub fn accrual_priorities<'a>(markets: impl IntoIterator<Item = &'a Market>, now: i64) -> Vec<AccrualPriority> {
+    let mut priorities: Vec<AccrualPriority> = markets
+        .into_iter()
+        .map(|market| AccrualPriority {
+            market_id: market.market_id,
+            seconds_since_accrual: now.saturating_sub(market.last_update).max(0),
+            total_debt: market.total_debt(),
+        })
+        .collect();
+    priorities.sort_by(|a, b| {
+        (b.total_debt > 0)
+            .cmp(&(a.total_debt > 0))
+            .then(b.seconds_since_accrual.cmp(&a.seconds_since_accrual))
+    });
+    priorities
+}
+
+#[derive(Accounts)]
+pub struct GetAccrualPriorities<'info> {
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+    // remaining_accounts: the Markets to rank
+}
+
+/// Rank the markets in remaining_accounts by accrual urgency
+pub fn get_accrual_priorities<'info>(
+    ctx: Context<'_, '_, 'info, 'info, GetAccrualPriorities>,
+) -> Result<VersionedView> {
+    require!(
+        !ctx.remaining_accounts.is_empty()
+            && ctx.remaining_accounts.len() <= MAX_ACCRUAL_PRIORITY_MARKETS,
+        MorphoError::InvalidInput
+    );
+
+    let markets = ctx
+        .remaining_accounts
+        .iter()
+        .map(|info| Account::<Market>::try_from(info).map(Account::into_inner))
+        .collect::<Result<Vec<_>>>()?;
+    let priorities = accrual_priorities(&markets, Clock::get()?.unix_timestamp);
+    Ok(ViewResponse::AccrualPriorities(priorities).into())
+}
+
+// ============================================================================
+// Reconcile Collateral (Public)
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct ReconcileCollateral<'info> {
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, Market::COLLATERAL_VAULT_SEED, &market_id],
+        bump = market.collateral_vault_bump,
+    )]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+/// Check the collateral vault against the market's books
+///
+/// No interest path touches collateral, so vault balance plus staked
+/// collateral should always equal what's owed to positions and insurance.
+/// Returns the drift (see `Market::collateral_drift`) as
+/// `ViewResponse::CollateralDrift` and emits `CollateralDrift` when it
+/// isn't zero.
+pub fn reconcile_collateral(ctx: Context<ReconcileCollateral>, market_id: [u8; 32]) -> Result<VersionedView> {
+    let market = &ctx.accounts.market;
+    let vault_balance = ctx.accounts.collateral_vault.amount;
+    let drift = market.collateral_drift(vault_balance)?;
+
+    if drift != 0 {
+        emit!(CollateralDrift {
+            market_id,
+            vault_balance,
+            collateral_staked: market.collateral_staked,
+            collateral_owed: market.collateral_owed()?,
+            drift,
+        });
+    }
+
+    Ok(ViewResponse::CollateralDrift(drift).into())
+}
+
+// ============================================================================
+// Protocol Status (Public)
+// ============================================================================
+
+/// Protocol-wide health summary for monitoring
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolStatus {
+    /// Global pause
+    pub paused: bool,
+    pub flash_loans_enabled: bool,
+    /// Markets created and not yet closed
+    pub live_markets: u64,
+    /// Live markets individually paused
+    pub paused_markets: u64,
+    pub closed_markets: u64,
+    pub enabled_lltvs: u32,
+    pub enabled_irms: u32,
+    /// Whether a recovery key may claim ownership right now
+    pub ownership_recoverable: bool,
+}
+
+impl ProtocolStatus {
+    pub fn of(state: &ProtocolState, now: i64) -> Self {
+        Self {
+            paused: state.paused,
+            flash_loans_enabled: state.flash_loans_enabled(),
+            live_markets: state.live_market_count(),
+            paused_markets: state.paused_market_count,
+            closed_markets: state.closed_market_count,
+            enabled_lltvs: state.enabled_lltvs.len() as u32,
+            enabled_irms: state.enabled_irms.len() as u32,
+            ownership_recoverable: state.can_recover_ownership(now),
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct GetProtocolStatus<'info> {
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+}
+
+/// Summarize protocol health as `ViewResponse::ProtocolStatus`
+pub fn get_protocol_status(ctx: Context<GetProtocolStatus>) -> Result<VersionedView> {
+    let status = ProtocolStatus::of(&ctx.accounts.protocol_state, Clock::get()?.unix_timestamp);
+    Ok(ViewResponse::ProtocolStatus(status).into())
+}
+
 // ============================================================================
 // Set Authorization
 // ============================================================================
@@ -51,6 +309,9 @@ pub fn accrue_interest_ix(ctx: Context<AccrueInterest>, market_id: [u8; 32]) ->
 #[derive(Accounts)]
 pub struct SetAuthorization<'info> {
     #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Position owner granting access; may be a PDA signing via CPI
     pub authorizer: Signer<'info>,
 
     /// CHECK: Account to authorize
@@ -58,7 +319,7 @@ pub struct SetAuthorization<'info> {
 
     #[account(
         init_if_needed,
-        payer = authorizer,
+        payer = payer,
         space = Authorization::space(),
         seeds = [
             PROGRAM_SEED_PREFIX,
@@ -169,22 +430,24 @@ pub struct ClaimFees<'info> {
 }
 
 pub fn claim_fees(ctx: Context<ClaimFees>, market_id: [u8; 32]) -> Result<()> {
-    let pending = ctx.accounts.market.pending_fee_shares;
+    claim_pending_fees(&mut ctx.accounts.market, &mut ctx.accounts.fee_position, market_id)
+}
+
+/// Move the market's pending fee shares into the fee recipient's position
+fn claim_pending_fees(market: &mut Market, fee_position: &mut Position, market_id: [u8; 32]) -> Result<()> {
+    let pending = market.pending_fee_shares;
     
     if pending == 0 {
         return Ok(());
     }
 
     // Transfer pending fee shares to fee recipient's position
-    ctx.accounts.fee_position.supply_shares = checked_add(
-        ctx.accounts.fee_position.supply_shares,
-        pending,
-    )?;
-    ctx.accounts.market.pending_fee_shares = 0;
+    fee_position.supply_shares = checked_add(fee_position.supply_shares, pending)?;
+    market.pending_fee_shares = 0;
 
     emit!(FeesClaimed {
         market_id,
-        recipient: ctx.accounts.protocol_state.fee_recipient,
+        recipient: fee_position.owner,
         shares: pending,
     });
 
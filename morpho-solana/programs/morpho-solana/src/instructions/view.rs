@@ -0,0 +1,55 @@
+//! View return data
+//!
+//! Every view instruction returns a `VersionedView`: a version byte followed
+//! by a `ViewResponse` whose variant names the view that produced it. Clients
+//! decode any view's return data with `decode_view` and match on the
+//! variant. A layout change bumps `VIEW_RESPONSE_VERSION`, so old decoders
+//! fail loudly instead of misreading the bytes.
+//!
+//! Handlers build their result with `ViewResponse::X(..).into()`, which
+//! stamps the current version.
+
+use anchor_lang::prelude::*;
+use crate::constants::VIEW_RESPONSE_VERSION;
+use crate::errors::MorphoError;
+use crate::instructions::{AccrualPriority, BundleProjection, ExposureReport, ProtocolStatus};
+
+/// Payload of a view instruction, one variant per view
+///
+/// Variants are only ever appended; reordering them changes the encoding.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ViewResponse {
+    /// `dry_run_bundle`
+    BundleProjection(BundleProjection),
+    /// `get_exposure_report`
+    ExposureReport(ExposureReport),
+    /// `get_accrual_priorities`
+    AccrualPriorities(Vec<AccrualPriority>),
+    /// `reconcile_collateral`: vault balance minus collateral owed
+    CollateralDrift(i128),
+    /// `get_protocol_status`
+    ProtocolStatus(ProtocolStatus),
+}
+
+/// Return data of every view instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VersionedView {
+    pub version: u8,
+    pub response: ViewResponse,
+}
+
+impl From<ViewResponse> for VersionedView {
+    fn from(response: ViewResponse) -> Self {
+        Self { version: VIEW_RESPONSE_VERSION, response }
+    }
+}
+
+/// Decode a view instruction's return data
+///
+/// Rejects other versions and trailing bytes rather than guessing.
+pub fn decode_view(data: &[u8]) -> Result<ViewResponse> {
+    let version = *data.first().ok_or(MorphoError::ViewDataMalformed)?;
+    require!(version == VIEW_RESPONSE_VERSION, MorphoError::UnsupportedViewVersion);
+    let view = VersionedView::try_from_slice(data).map_err(|_| MorphoError::ViewDataMalformed)?;
+    Ok(view.response)
+}
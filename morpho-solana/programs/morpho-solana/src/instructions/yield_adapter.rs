@@ -0,0 +1,615 @@
+//! Yield routing for idle vault balances
+//!
+//! - Configure per-market collateral / loan yield adapters (owner only,
+//!   adapter must be whitelisted)
+//! - Stake idle collateral / deploy idle loan liquidity (owner only, capped)
+//! - Unstake collateral / recall loan liquidity (permissionless)
+//!
+//! Staked collateral is still owed to borrowers, so positions never change
+//! here. Availability is guaranteed two ways: at most `max_collateral_staked_bps`
+//! of the market's collateral can be deployed, and anyone (a withdrawing
+//! borrower, a liquidator) can recall collateral in the same transaction
+//! before their withdraw_collateral / liquidate instruction.
+//!
+//! Deployed loan liquidity still counts toward `available_liquidity`;
+//! withdraw and borrow recall the shortfall themselves when the vault alone
+//! can't cover them. Yield returned on recall is credited to suppliers.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::constants::{PROGRAM_SEED_PREFIX, BPS};
+use crate::errors::MorphoError;
+use crate::events::*;
+use crate::interfaces::{
+    invoke_yield_adapter, YieldAdapterAccounts, YIELD_ADAPTER_DEPOSIT, YIELD_ADAPTER_WITHDRAW,
+};
+use crate::math::{checked_add, checked_sub, safe_u128_to_u64, accrue_market_interest};
+use crate::state::{ProtocolState, Market, MarketAction};
+use crate::time::current_clock;
+
+// ============================================================================
+// Set Collateral Yield Adapter
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetCollateralYieldAdapter<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Opt a market into collateral yield routing, retune its cap, or disable it
+///
+/// Pass `Pubkey::default()` to disable. The adapter can only be swapped once
+/// everything staked in the old one has been recalled.
+pub fn set_collateral_yield_adapter(
+    ctx: Context<SetCollateralYieldAdapter>,
+    market_id: [u8; 32],
+    adapter: Pubkey,
+    max_staked_bps: u64,
+) -> Result<()> {
+    require!(max_staked_bps <= BPS, MorphoError::InvalidInput);
+    require!(
+        adapter == Pubkey::default() || ctx.accounts.protocol_state.is_yield_adapter_enabled(&adapter),
+        MorphoError::YieldAdapterNotEnabled
+    );
+
+    let market = &mut ctx.accounts.market;
+    require!(
+        market.collateral_staked == 0 || market.collateral_yield_adapter == adapter,
+        MorphoError::YieldAdapterFundsDeployed
+    );
+
+    market.collateral_yield_adapter = adapter;
+    market.max_collateral_staked_bps = if adapter == Pubkey::default() { 0 } else { max_staked_bps };
+
+    emit!(CollateralYieldAdapterSet {
+        market_id,
+        adapter,
+        max_staked_bps: market.max_collateral_staked_bps,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Stake / Unstake Collateral
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct StakeCollateral<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::COLLATERAL_VAULT_SEED, &market_id],
+        bump = market.collateral_vault_bump,
+    )]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(constraint = collateral_mint.key() == market.collateral_mint @ MorphoError::InvalidMint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Must be the market's configured collateral yield adapter
+    #[account(
+        executable,
+        constraint = adapter_program.key() == market.collateral_yield_adapter @ MorphoError::InvalidYieldAdapter,
+    )]
+    pub adapter_program: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Deploy `amount` of idle collateral into the market's yield adapter
+///
+/// Adapter-specific accounts are passed as remaining accounts.
+pub fn stake_collateral<'info>(
+    ctx: Context<'_, '_, 'info, 'info, StakeCollateral<'info>>,
+    market_id: [u8; 32],
+    amount: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    ctx.accounts.market.check_action(MarketAction::DeployToAdapter)?;
+    require!(ctx.accounts.market.has_collateral_yield_adapter(), MorphoError::YieldAdapterNotSet);
+    require!(amount > 0, MorphoError::ZeroAmount);
+
+    let vault_before = ctx.accounts.collateral_vault.amount as u128;
+    require!(amount <= vault_before, MorphoError::InsufficientCollateral);
+
+    let market = &mut ctx.accounts.market;
+    let new_staked = checked_add(market.collateral_staked, amount)?;
+    require!(
+        new_staked <= market.max_collateral_staked(vault_before)?,
+        MorphoError::YieldAdapterCapExceeded
+    );
+
+    // ===== EFFECTS =====
+    market.collateral_staked = new_staked;
+
+    // ===== INTERACTIONS =====
+    let bump = market.bump;
+    let seeds = &[
+        PROGRAM_SEED_PREFIX,
+        Market::SEED,
+        market_id.as_ref(),
+        &[bump],
+    ];
+
+    invoke_yield_adapter(
+        &YieldAdapterAccounts {
+            adapter_program: &ctx.accounts.adapter_program.to_account_info(),
+            market: &ctx.accounts.market.to_account_info(),
+            vault: &ctx.accounts.collateral_vault.to_account_info(),
+            mint: &ctx.accounts.collateral_mint.to_account_info(),
+            token_program: &ctx.accounts.token_program.to_account_info(),
+            extra_accounts: ctx.remaining_accounts,
+        },
+        YIELD_ADAPTER_DEPOSIT,
+        safe_u128_to_u64(amount)?,
+        &[seeds],
+    )?;
+
+    // The adapter must have taken exactly `amount`, no more
+    ctx.accounts.collateral_vault.reload()?;
+    let vault_after = ctx.accounts.collateral_vault.amount as u128;
+    require!(
+        vault_after == checked_sub(vault_before, amount)?,
+        MorphoError::YieldAdapterBalanceMismatch
+    );
+
+    emit!(CollateralStaked {
+        market_id,
+        amount,
+        total_staked: new_staked,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct UnstakeCollateral<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::COLLATERAL_VAULT_SEED, &market_id],
+        bump = market.collateral_vault_bump,
+    )]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(constraint = collateral_mint.key() == market.collateral_mint @ MorphoError::InvalidMint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Must be the market's configured collateral yield adapter
+    #[account(
+        executable,
+        constraint = adapter_program.key() == market.collateral_yield_adapter @ MorphoError::InvalidYieldAdapter,
+    )]
+    pub adapter_program: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Recall `amount` of staked collateral back into the vault
+///
+/// Permissionless and pause-exempt: it only ever increases vault liquidity.
+/// Anything the adapter returns above `amount` (accrued yield) stays in the vault.
+pub fn unstake_collateral<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UnstakeCollateral<'info>>,
+    market_id: [u8; 32],
+    amount: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(amount > 0, MorphoError::ZeroAmount);
+
+    let market = &mut ctx.accounts.market;
+    require!(amount <= market.collateral_staked, MorphoError::InsufficientBalance);
+
+    // ===== EFFECTS =====
+    market.collateral_staked = checked_sub(market.collateral_staked, amount)?;
+    let total_staked = market.collateral_staked;
+
+    // ===== INTERACTIONS =====
+    let vault_before = ctx.accounts.collateral_vault.amount as u128;
+    let bump = market.bump;
+    let seeds = &[
+        PROGRAM_SEED_PREFIX,
+        Market::SEED,
+        market_id.as_ref(),
+        &[bump],
+    ];
+
+    invoke_yield_adapter(
+        &YieldAdapterAccounts {
+            adapter_program: &ctx.accounts.adapter_program.to_account_info(),
+            market: &ctx.accounts.market.to_account_info(),
+            vault: &ctx.accounts.collateral_vault.to_account_info(),
+            mint: &ctx.accounts.collateral_mint.to_account_info(),
+            token_program: &ctx.accounts.token_program.to_account_info(),
+            extra_accounts: ctx.remaining_accounts,
+        },
+        YIELD_ADAPTER_WITHDRAW,
+        safe_u128_to_u64(amount)?,
+        &[seeds],
+    )?;
+
+    // The adapter must have returned at least `amount`
+    ctx.accounts.collateral_vault.reload()?;
+    let vault_after = ctx.accounts.collateral_vault.amount as u128;
+    require!(
+        vault_after >= checked_add(vault_before, amount)?,
+        MorphoError::YieldAdapterBalanceMismatch
+    );
+
+    emit!(CollateralUnstaked {
+        market_id,
+        caller: ctx.accounts.caller.key(),
+        amount,
+        total_staked,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Set Loan Yield Adapter
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct SetLoanYieldAdapter<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, Market>,
+}
+
+/// Opt a market into loan liquidity deployment, retune its cap, or disable it
+///
+/// Pass `Pubkey::default()` to disable. The adapter can only be swapped once
+/// everything deployed to the old one has been recalled.
+pub fn set_loan_yield_adapter(
+    ctx: Context<SetLoanYieldAdapter>,
+    market_id: [u8; 32],
+    adapter: Pubkey,
+    max_deployed_bps: u64,
+) -> Result<()> {
+    require!(max_deployed_bps <= BPS, MorphoError::InvalidInput);
+    require!(
+        adapter == Pubkey::default() || ctx.accounts.protocol_state.is_yield_adapter_enabled(&adapter),
+        MorphoError::YieldAdapterNotEnabled
+    );
+
+    let market = &mut ctx.accounts.market;
+    require!(
+        market.loan_deployed == 0 || market.loan_yield_adapter == adapter,
+        MorphoError::YieldAdapterFundsDeployed
+    );
+
+    market.loan_yield_adapter = adapter;
+    market.max_loan_deployed_bps = if adapter == Pubkey::default() { 0 } else { max_deployed_bps };
+
+    emit!(LoanYieldAdapterSet {
+        market_id,
+        adapter,
+        max_deployed_bps: market.max_loan_deployed_bps,
+    });
+    Ok(())
+}
+
+// ============================================================================
+// Deploy / Recall Loan Liquidity
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct DeployLoanLiquidity<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [PROGRAM_SEED_PREFIX, ProtocolState::SEED],
+        bump = protocol_state.bump,
+        constraint = protocol_state.owner == owner.key() @ MorphoError::Unauthorized,
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(constraint = loan_mint.key() == market.loan_mint @ MorphoError::InvalidMint)]
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Must be the market's configured loan yield adapter
+    #[account(
+        executable,
+        constraint = adapter_program.key() == market.loan_yield_adapter @ MorphoError::InvalidYieldAdapter,
+    )]
+    pub adapter_program: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Deploy `amount` of idle loan liquidity into the market's yield adapter
+///
+/// Adapter-specific accounts are passed as remaining accounts.
+pub fn deploy_loan_liquidity<'info>(
+    ctx: Context<'_, '_, 'info, 'info, DeployLoanLiquidity<'info>>,
+    market_id: [u8; 32],
+    amount: u128,
+) -> Result<()> {
+    // ===== CHECKS =====
+    require!(!ctx.accounts.protocol_state.paused, MorphoError::ProtocolPaused);
+    ctx.accounts.market.check_action(MarketAction::DeployToAdapter)?;
+    require!(ctx.accounts.market.has_loan_yield_adapter(), MorphoError::YieldAdapterNotSet);
+    require!(
+        !ctx.accounts.market.is_flash_loan_active(),
+        MorphoError::FlashLoanInProgress
+    );
+    require!(amount > 0, MorphoError::ZeroAmount);
+
+    let vault_before = ctx.accounts.loan_vault.amount as u128;
+    require!(amount <= vault_before, MorphoError::InsufficientLiquidity);
+
+    let market = &mut ctx.accounts.market;
+    let new_deployed = checked_add(market.loan_deployed, amount)?;
+    require!(
+        new_deployed <= market.max_loan_deployed()?,
+        MorphoError::YieldAdapterCapExceeded
+    );
+
+    // ===== EFFECTS =====
+    market.loan_deployed = new_deployed;
+
+    // ===== INTERACTIONS =====
+    let bump = market.bump;
+    let seeds = &[
+        PROGRAM_SEED_PREFIX,
+        Market::SEED,
+        market_id.as_ref(),
+        &[bump],
+    ];
+
+    invoke_yield_adapter(
+        &YieldAdapterAccounts {
+            adapter_program: &ctx.accounts.adapter_program.to_account_info(),
+            market: &ctx.accounts.market.to_account_info(),
+            vault: &ctx.accounts.loan_vault.to_account_info(),
+            mint: &ctx.accounts.loan_mint.to_account_info(),
+            token_program: &ctx.accounts.token_program.to_account_info(),
+            extra_accounts: ctx.remaining_accounts,
+        },
+        YIELD_ADAPTER_DEPOSIT,
+        safe_u128_to_u64(amount)?,
+        &[seeds],
+    )?;
+
+    // The adapter must have taken exactly `amount`, no more
+    ctx.accounts.loan_vault.reload()?;
+    let vault_after = ctx.accounts.loan_vault.amount as u128;
+    require!(
+        vault_after == checked_sub(vault_before, amount)?,
+        MorphoError::YieldAdapterBalanceMismatch
+    );
+
+    emit!(LoanLiquidityDeployed {
+        market_id,
+        amount,
+        total_deployed: new_deployed,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: [u8; 32])]
+pub struct RecallLoanLiquidity<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::SEED, &market_id],
+        bump = market.bump,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SEED_PREFIX, Market::LOAN_VAULT_SEED, &market_id],
+        bump = market.loan_vault_bump,
+    )]
+    pub loan_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(constraint = loan_mint.key() == market.loan_mint @ MorphoError::InvalidMint)]
+    pub loan_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Recall `amount` of deployed loan liquidity back into the vault
+///
+/// Permissionless and pause-exempt. Remaining accounts: [adapter_program, ...adapter accounts].
+pub fn recall_loan_liquidity<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RecallLoanLiquidity<'info>>,
+    market_id: [u8; 32],
+    amount: u128,
+) -> Result<()> {
+    require!(amount > 0, MorphoError::ZeroAmount);
+
+    // Accrue first: any yield credited below must not shift elapsed-period utilization
+    accrue_market_interest(&mut ctx.accounts.market, &current_clock(ctx.remaining_accounts)?, ctx.remaining_accounts)?;
+
+    let yield_assets = recall_loan_liquidity_into_vault(
+        &mut ctx.accounts.market,
+        &mut ctx.accounts.loan_vault,
+        &ctx.accounts.loan_mint.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.remaining_accounts,
+        amount,
+    )?;
+
+    emit!(LoanLiquidityRecalled {
+        market_id,
+        caller: ctx.accounts.caller.key(),
+        amount,
+        yield_assets,
+        total_deployed: ctx.accounts.market.loan_deployed,
+    });
+    Ok(())
+}
+
+/// Top up the loan vault so it holds at least `required` tokens
+///
+/// Used by withdraw and borrow: a no-op while the vault already covers the
+/// transfer, otherwise recalls the shortfall from the adapter passed in
+/// `adapter_accounts` ([adapter_program, ...adapter accounts]).
+pub fn ensure_loan_vault_liquidity<'info>(
+    caller: Pubkey,
+    market: &mut Account<'info, Market>,
+    loan_vault: &mut InterfaceAccount<'info, TokenAccount>,
+    loan_mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    adapter_accounts: &[AccountInfo<'info>],
+    required: u128,
+) -> Result<()> {
+    let vault_balance = loan_vault.amount as u128;
+    if vault_balance >= required || market.loan_deployed == 0 {
+        return Ok(());
+    }
+
+    let shortfall = checked_sub(required, vault_balance)?;
+    let yield_assets = recall_loan_liquidity_into_vault(
+        market,
+        loan_vault,
+        loan_mint,
+        token_program,
+        adapter_accounts,
+        shortfall,
+    )?;
+
+    emit!(LoanLiquidityRecalled {
+        market_id: market.market_id,
+        caller,
+        amount: shortfall,
+        yield_assets,
+        total_deployed: market.loan_deployed,
+    });
+    Ok(())
+}
+
+/// Pull `amount` of deployed principal back into the loan vault
+///
+/// Anything the adapter returns above `amount` is credited to suppliers.
+/// Returns that surplus. Callers must have accrued interest already.
+fn recall_loan_liquidity_into_vault<'info>(
+    market: &mut Account<'info, Market>,
+    loan_vault: &mut InterfaceAccount<'info, TokenAccount>,
+    loan_mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    adapter_accounts: &[AccountInfo<'info>],
+    amount: u128,
+) -> Result<u128> {
+    // ===== CHECKS =====
+    require!(market.has_loan_yield_adapter(), MorphoError::YieldAdapterNotSet);
+    let (adapter_program, extra_accounts) = adapter_accounts
+        .split_first()
+        .ok_or(MorphoError::InvalidYieldAdapter)?;
+    require!(
+        adapter_program.key() == market.loan_yield_adapter && adapter_program.executable,
+        MorphoError::InvalidYieldAdapter
+    );
+    require!(amount <= market.loan_deployed, MorphoError::InsufficientLiquidity);
+
+    // ===== EFFECTS =====
+    market.loan_deployed = checked_sub(market.loan_deployed, amount)?;
+
+    // ===== INTERACTIONS =====
+    let vault_before = loan_vault.amount as u128;
+    let market_id = market.market_id;
+    let bump = market.bump;
+    let seeds = &[
+        PROGRAM_SEED_PREFIX,
+        Market::SEED,
+        market_id.as_ref(),
+        &[bump],
+    ];
+
+    invoke_yield_adapter(
+        &YieldAdapterAccounts {
+            adapter_program,
+            market: &market.to_account_info(),
+            vault: &loan_vault.to_account_info(),
+            mint: loan_mint,
+            token_program,
+            extra_accounts,
+        },
+        YIELD_ADAPTER_WITHDRAW,
+        safe_u128_to_u64(amount)?,
+        &[seeds],
+    )?;
+
+    // The adapter must have returned at least `amount`
+    loan_vault.reload()?;
+    let vault_after = loan_vault.amount as u128;
+    let expected = checked_add(vault_before, amount)?;
+    require!(vault_after >= expected, MorphoError::YieldAdapterBalanceMismatch);
+
+    let yield_assets = checked_sub(vault_after, expected)?;
+    market.total_supply_assets = checked_add(market.total_supply_assets, yield_assets)?;
+
+    Ok(yield_assets)
+}
@@ -0,0 +1,223 @@
+//! Adaptive curve IRM (port of Morpho's AdaptiveCurveIrm)
+//!
+//! Markets created over the adaptive curve model address
+//! (`derive_adaptive_curve_model`) price borrows along a curve around a
+//! per-market rate at target utilization. The rate at target lives in the
+//! market's `AdaptiveCurveIrm` and drifts exponentially with the
+//! utilization error: up while the market runs above `TARGET_UTILIZATION`,
+//! down while below, at up to `ADJUSTMENT_SPEED` per year. The curve then
+//! scales it by 1/`CURVE_STEEPNESS` at 0% utilization and `CURVE_STEEPNESS`
+//! at 100%.
+//!
+//! Each rate sample moves the state forward, so the account must be passed
+//! (writable) among the remaining accounts of anything that accrues, and
+//! must be initialized (`init_adaptive_curve_irm`) before the market's
+//! first accrual. Markets migrated onto the model (`set_market_irm`) start
+//! their state over at `INITIAL_RATE_AT_TARGET`.
+
+use anchor_lang::prelude::*;
+use crate::constants::{MAX_BORROW_RATE_PER_SECOND, PROGRAM_SEED_PREFIX, SECONDS_PER_YEAR, WAD};
+use crate::errors::MorphoError;
+use crate::math::mul_div_down;
+use crate::state::Market;
+use crate::time::current_clock;
+
+const WAD_I: i128 = WAD as i128;
+
+/// Rate multiple between target and full utilization (WAD)
+pub const CURVE_STEEPNESS: i128 = 4 * WAD_I;
+
+/// Fastest drift of the rate at target, at full utilization error (per
+/// second, WAD)
+pub const ADJUSTMENT_SPEED: i128 = 50 * WAD_I / SECONDS_PER_YEAR as i128;
+
+/// Utilization the rate at target is defined at (WAD)
+pub const TARGET_UTILIZATION: i128 = WAD_I * 9 / 10;
+
+/// Rate at target a new market starts at: 4% APR (per second, WAD)
+pub const INITIAL_RATE_AT_TARGET: u128 = WAD * 4 / 100 / SECONDS_PER_YEAR;
+
+/// Floor of the rate at target: 0.1% APR (per second, WAD)
+pub const MIN_RATE_AT_TARGET: u128 = WAD / 1000 / SECONDS_PER_YEAR;
+
+/// Ceiling of the rate at target: 200% APR (per second, WAD)
+pub const MAX_RATE_AT_TARGET: u128 = WAD * 2 / SECONDS_PER_YEAR;
+
+/// ln(2) (WAD)
+const LN_2: i128 = 693_147_180_559_945_309;
+
+/// ln(1e-18) (WAD): exponents below it round to zero
+const LN_WEI: i128 = -41_446_531_673_892_822_312;
+
+/// Exponents are clamped here; the rate at target saturates long before
+const WEXP_UPPER_BOUND: i128 = 40 * WAD_I;
+
+/// Adaptive curve state of one market
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"adaptive_curve_irm", market_id]
+#[account]
+pub struct AdaptiveCurveIrm {
+    pub bump: u8,
+
+    /// Market the state prices
+    pub market_id: [u8; 32],
+
+    /// Borrow rate at target utilization (per second, WAD)
+    pub rate_at_target: u128,
+
+    /// Unix timestamp the rate at target was last moved to
+    pub last_update: i64,
+}
+
+impl AdaptiveCurveIrm {
+    pub const SEED: &'static [u8] = b"adaptive_curve_irm";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // market_id
+        16 +    // rate_at_target
+        8       // last_update
+    }
+
+    /// Borrow rate for the period since the last update at `utilization`
+    /// (WAD), moving the rate at target forward to `now`
+    ///
+    /// Like Morpho's, the period is priced at the average of the start, end
+    /// and twice the midpoint rate at target.
+    pub fn borrow_rate(&mut self, utilization: u128, now: i64) -> Result<u128> {
+        let err = utilization_error(utilization);
+        let elapsed = now.saturating_sub(self.last_update).max(0) as i128;
+        let speed = ADJUSTMENT_SPEED * err / WAD_I;
+        let linear_adaptation = speed * elapsed;
+
+        let start = self.rate_at_target;
+        let (average, end) = if linear_adaptation == 0 {
+            (start, start)
+        } else {
+            let end = new_rate_at_target(start, linear_adaptation)?;
+            let mid = new_rate_at_target(start, linear_adaptation / 2)?;
+            ((start + end + 2 * mid) / 4, end)
+        };
+
+        self.rate_at_target = end;
+        self.last_update = now;
+        Ok(std::cmp::min(curve(average, err)?, MAX_BORROW_RATE_PER_SECOND))
+    }
+}
+
+/// Distance of `utilization` from target, normalized to [-1, 1] (WAD)
+pub fn utilization_error(utilization: u128) -> i128 {
+    let utilization = std::cmp::min(utilization, WAD) as i128;
+    let norm = if utilization > TARGET_UTILIZATION { WAD_I - TARGET_UTILIZATION } else { TARGET_UTILIZATION };
+    (utilization - TARGET_UTILIZATION) * WAD_I / norm
+}
+
+/// Rate on the curve through `rate_at_target` at utilization error `err`
+pub fn curve(rate_at_target: u128, err: i128) -> Result<u128> {
+    let coefficient = if err < 0 { WAD_I - WAD_I * WAD_I / CURVE_STEEPNESS } else { CURVE_STEEPNESS - WAD_I };
+    let factor = coefficient * err / WAD_I + WAD_I;
+    mul_div_down(factor as u128, rate_at_target, WAD)
+}
+
+/// `start` grown by e^`linear_adaptation`, kept within the rate at target bounds
+pub fn new_rate_at_target(start: u128, linear_adaptation: i128) -> Result<u128> {
+    let rate = mul_div_down(start, w_exp(linear_adaptation), WAD)?;
+    Ok(rate.clamp(MIN_RATE_AT_TARGET, MAX_RATE_AT_TARGET))
+}
+
+/// e^`x` (WAD), within 1% like Morpho's `ExpLib`
+///
+/// Splits x = q·ln2 + r with |r| ≤ ln2/2 and takes 2^q times the second
+/// order expansion of e^r.
+pub fn w_exp(x: i128) -> u128 {
+    if x < LN_WEI {
+        return 0;
+    }
+    let x = std::cmp::min(x, WEXP_UPPER_BOUND);
+
+    let rounding = if x < 0 { -(LN_2 / 2) } else { LN_2 / 2 };
+    let q = (x + rounding) / LN_2;
+    let r = x - q * LN_2;
+    let exp_r = (WAD_I + r + r * r / WAD_I / 2) as u128;
+
+    if q >= 0 { exp_r << q } else { exp_r >> -q }
+}
+
+/// Whether `account` holds an `AdaptiveCurveIrm`
+pub fn is_adaptive_curve_irm(account: &AccountInfo) -> Result<bool> {
+    Ok(account.owner == &crate::ID
+        && account.try_borrow_data()?.starts_with(AdaptiveCurveIrm::DISCRIMINATOR))
+}
+
+/// Sample an adaptive curve market's rate, writing the moved state back
+///
+/// Finds the market's `AdaptiveCurveIrm` among `irm_accounts`; it must be
+/// writable.
+pub fn sample_adaptive_curve_rate(market: &Market, irm_accounts: &[AccountInfo]) -> Result<u128> {
+    let (account, mut irm) = find_adaptive_curve_irm(market, irm_accounts)?;
+    require!(account.is_writable, MorphoError::InvalidIrm);
+    let rate = irm.borrow_rate(market.utilization(), current_clock(irm_accounts)?.unix_timestamp)?;
+    irm.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    Ok(rate)
+}
+
+/// Put a market's `AdaptiveCurveIrm` back at the initial rate at target,
+/// as of `now`
+///
+/// For markets switching to the model: a state left from an earlier stint,
+/// or created long before the switch, would otherwise drift over the time
+/// it sat unused.
+pub fn restart_adaptive_curve_irm(market: &Market, irm_accounts: &[AccountInfo], now: i64) -> Result<()> {
+    let (account, mut irm) = find_adaptive_curve_irm(market, irm_accounts)?;
+    require!(account.is_writable, MorphoError::InvalidIrm);
+    irm.rate_at_target = INITIAL_RATE_AT_TARGET;
+    irm.last_update = now;
+    irm.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+/// An adaptive curve market's rate as of now, leaving its state untouched
+///
+/// For views; the state may be passed read-only.
+pub fn peek_adaptive_curve_rate(market: &Market, irm_accounts: &[AccountInfo]) -> Result<u128> {
+    let (_, mut irm) = find_adaptive_curve_irm(market, irm_accounts)?;
+    irm.borrow_rate(market.utilization(), current_clock(irm_accounts)?.unix_timestamp)
+}
+
+/// The market's `AdaptiveCurveIrm` among `irm_accounts`
+fn find_adaptive_curve_irm<'a, 'info>(
+    market: &Market,
+    irm_accounts: &'a [AccountInfo<'info>],
+) -> Result<(&'a AccountInfo<'info>, AdaptiveCurveIrm)> {
+    for account in irm_accounts {
+        if !is_adaptive_curve_irm(account)? {
+            continue;
+        }
+        let irm = AdaptiveCurveIrm::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+        if irm.market_id != market.market_id {
+            continue;
+        }
+        return Ok((account, irm));
+    }
+    Err(MorphoError::InvalidIrm.into())
+}
+
+/// Seed of the adaptive curve model address
+pub const ADAPTIVE_CURVE_MODEL_SEED: &[u8] = b"adaptive_curve_model";
+
+/// Derive the adaptive curve model address
+///
+/// A program address nothing is ever created at. Markets created with it as
+/// their IRM price along their `AdaptiveCurveIrm`.
+pub fn derive_adaptive_curve_model(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROGRAM_SEED_PREFIX, ADAPTIVE_CURVE_MODEL_SEED], program_id)
+}
+
+/// Derive a market's adaptive curve state PDA
+pub fn derive_adaptive_curve_irm(program_id: &Pubkey, market_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, AdaptiveCurveIrm::SEED, market_id],
+        program_id,
+    )
+}
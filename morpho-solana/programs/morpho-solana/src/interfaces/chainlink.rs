@@ -0,0 +1,113 @@
+//! Chainlink OCR2 feed adapter
+//!
+//! Decodes the `Transmissions` accounts the Chainlink store program keeps on
+//! Solana (the accounts Chainlink lists as a feed's address) so markets can
+//! use Chainlink feeds directly.
+//!
+//! Unlike Switchboard feeds, Chainlink answers are whole-token prices with
+//! the feed's own decimals (e.g. SOL/USD with 8), so they go through
+//! `normalize_price` with the market's token decimals before use.
+
+use anchor_lang::prelude::*;
+use crate::errors::MorphoError;
+use super::oracle::normalize_price;
+
+/// Chainlink store program, owner of every OCR2 feed account
+pub const CHAINLINK_STORE_PROGRAM_ID: Pubkey = pubkey!("HEvSKofvBgfaexv23kMabbYqxasxU3mQ4ibBMEmJWHny");
+
+/// Anchor discriminator of the store's `Transmissions` account
+pub const CHAINLINK_TRANSMISSIONS_DISCRIMINATOR: [u8; 8] = [96, 179, 69, 66, 128, 129, 73, 117];
+
+// `Transmissions` is a packed zero-copy account: an 8-byte discriminator, a
+// header padded to 192 bytes, then the live ring buffer of 48-byte rounds
+// (followed by a historical buffer this adapter doesn't read).
+const HEADER_START: usize = 8;
+const DECIMALS_OFFSET: usize = HEADER_START + 130;
+const LATEST_ROUND_ID_OFFSET: usize = HEADER_START + 135;
+const LIVE_LENGTH_OFFSET: usize = HEADER_START + 140;
+const LIVE_CURSOR_OFFSET: usize = HEADER_START + 144;
+const ROUNDS_START: usize = HEADER_START + 192;
+const ROUND_SIZE: usize = 48;
+
+/// Latest round of a Chainlink feed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainlinkRound {
+    pub round_id: u32,
+    /// Slot the round was written in
+    pub slot: u64,
+    pub timestamp: u32,
+    /// Whole-token price scaled by 10^decimals
+    pub answer: i128,
+    pub decimals: u8,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Decode the latest round from `Transmissions` account data
+///
+/// The caller checks the account is owned by CHAINLINK_STORE_PROGRAM_ID.
+pub fn parse_chainlink_round(data: &[u8]) -> Result<ChainlinkRound> {
+    require!(
+        data.len() >= ROUNDS_START && data[..8] == CHAINLINK_TRANSMISSIONS_DISCRIMINATOR,
+        MorphoError::OracleInvalidReturnData
+    );
+
+    let round_id = read_u32(data, LATEST_ROUND_ID_OFFSET);
+    let live_length = read_u32(data, LIVE_LENGTH_OFFSET) as usize;
+    let live_cursor = read_u32(data, LIVE_CURSOR_OFFSET) as usize;
+    require!(round_id > 0, MorphoError::OracleStale);
+    require!(
+        live_length > 0 && data.len() >= ROUNDS_START + live_length * ROUND_SIZE,
+        MorphoError::OracleInvalidReturnData
+    );
+
+    // The cursor points at the next slot to write
+    let latest = (live_cursor + live_length - 1) % live_length;
+    let round = &data[ROUNDS_START + latest * ROUND_SIZE..][..ROUND_SIZE];
+
+    Ok(ChainlinkRound {
+        round_id,
+        slot: u64::from_le_bytes(round[0..8].try_into().unwrap()),
+        timestamp: read_u32(round, 8),
+        answer: i128::from_le_bytes(round[16..32].try_into().unwrap()),
+        decimals: data[DECIMALS_OFFSET],
+    })
+}
+
+/// Convert a Chainlink answer into a prescaled price
+///
+/// `answer` loan tokens per collateral token, with `feed_decimals` decimals,
+/// becomes loan base units per collateral base unit times ORACLE_SCALE.
+pub fn chainlink_answer_to_price(
+    answer: i128,
+    feed_decimals: u8,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+) -> Result<u128> {
+    require!(answer > 0, MorphoError::OracleInvalidPrice);
+    require!(feed_decimals <= 36, MorphoError::OracleInvalidReturnData);
+    normalize_price(answer as u128, -(feed_decimals as i32), collateral_decimals, loan_decimals)
+}
+
+/// Read a Chainlink feed as a prescaled price
+///
+/// Rejects rounds older than `heartbeat` slots; bounds are checked by the
+/// caller like every other feed type.
+pub fn read_chainlink_price(
+    oracle_account: &AccountInfo,
+    heartbeat: u64,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+) -> Result<u128> {
+    let round = parse_chainlink_round(&oracle_account.try_borrow_data()?)?;
+
+    let age = Clock::get()?.slot.saturating_sub(round.slot);
+    if age > heartbeat {
+        msg!("Oracle heartbeat missed: expected <= {} slots, actual age {} slots", heartbeat, age);
+        return Err(MorphoError::OracleHeartbeatMissed.into());
+    }
+
+    chainlink_answer_to_price(round.answer, round.decimals, collateral_decimals, loan_decimals)
+}
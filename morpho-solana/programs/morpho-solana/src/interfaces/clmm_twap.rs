@@ -0,0 +1,204 @@
+//! CLMM pool TWAP oracle
+//!
+//! Long-tail tokens often trade in a concentrated-liquidity pool long before
+//! any feed covers them. Raydium CLMM pools record a ring of tick-cumulative
+//! observations (`ObservationState`); a `ClmmTwapOracle` averages the pool
+//! tick over at least `min_window` seconds of them, written by the
+//! permissionless `update_clmm_twap` crank. Used as a market's oracle it
+//! prices at 1.0001^tick, which is already in raw token units, so no
+//! decimals are involved.
+//!
+//! Orca Whirlpools record no price observations, so they can't back one.
+//! Observations are only written on swaps: a quiet pool's newest one ages,
+//! and the market's `max_oracle_age_secs` is checked against it.
+
+use anchor_lang::prelude::*;
+use crate::constants::{ORACLE_SCALE, PROGRAM_SEED_PREFIX, WAD};
+use crate::errors::MorphoError;
+use crate::math::mul_div_down;
+
+/// Raydium concentrated-liquidity program
+pub const RAYDIUM_CLMM_PROGRAM_ID: Pubkey = pubkey!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+
+/// Observations kept per Raydium pool
+pub const CLMM_OBSERVATION_COUNT: usize = 100;
+
+/// Widest tick a CLMM pool can reach (either sign)
+pub const CLMM_MAX_TICK: i32 = 443_636;
+
+/// Raydium `ObservationState` layout (packed): discriminator, initialized,
+/// recent_epoch, observation_index, pool_id, observations, padding
+const OBSERVATION_INDEX_OFFSET: usize = 8 + 1 + 8;
+const OBSERVATIONS_OFFSET: usize = OBSERVATION_INDEX_OFFSET + 2 + 32;
+/// block_timestamp (u32), tick_cumulative (i64), padding ([u64; 4])
+const OBSERVATION_SIZE: usize = 4 + 8 + 32;
+const OBSERVATION_STATE_LEN: usize = OBSERVATIONS_OFFSET + CLMM_OBSERVATION_COUNT * OBSERVATION_SIZE + 32;
+
+/// 1.0001, the price ratio between adjacent ticks (scaled by WAD)
+const TICK_BASE: u128 = 1_000_100_000_000_000_000;
+
+/// Average tick of a CLMM pool over its recent observations
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"clmm_twap_oracle", observation_state, min_window (LE), collateral_is_token0]
+#[account]
+pub struct ClmmTwapOracle {
+    pub bump: u8,
+
+    /// Pool's Raydium `ObservationState` account
+    pub observation_state: Pubkey,
+
+    /// Whether the collateral is the pool's token 0 (ticks price token 0
+    /// in token 1)
+    pub collateral_is_token0: bool,
+
+    /// Shortest span of observations an average may come from (seconds)
+    pub min_window: i64,
+
+    /// Average pool tick at the latest update
+    pub twap_tick: i32,
+
+    /// Timestamp of the newest observation in that average
+    pub observed_at: i64,
+
+    /// Slot of the latest update, checked against the market's heartbeat
+    pub last_update_slot: u64,
+}
+
+impl ClmmTwapOracle {
+    pub const SEED: &'static [u8] = b"clmm_twap_oracle";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // observation_state
+        1 +     // collateral_is_token0
+        8 +     // min_window
+        4 +     // twap_tick
+        8 +     // observed_at
+        8       // last_update_slot
+    }
+
+    /// Prescaled collateral/loan price from the stored tick
+    pub fn price(&self) -> Result<u128> {
+        let tick = if self.collateral_is_token0 { self.twap_tick } else { -self.twap_tick };
+        tick_to_price(tick)
+    }
+}
+
+/// 1.0001^tick scaled by ORACLE_SCALE
+pub fn tick_to_price(tick: i32) -> Result<u128> {
+    require!(tick.unsigned_abs() <= CLMM_MAX_TICK as u32, MorphoError::InvalidClmmObservations);
+
+    // Square-and-multiply over the tick's bits
+    let mut ratio = WAD;
+    let mut base = TICK_BASE;
+    let mut exponent = tick.unsigned_abs();
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            ratio = mul_div_down(ratio, base, WAD)?;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = mul_div_down(base, base, WAD)?;
+        }
+    }
+
+    if tick >= 0 {
+        mul_div_down(ORACLE_SCALE, ratio, WAD)
+    } else {
+        mul_div_down(ORACLE_SCALE, WAD, ratio)
+    }
+}
+
+/// One decoded observation: timestamp and tick cumulative
+fn observation_at(data: &[u8], index: usize) -> Result<(i64, i64)> {
+    let start = OBSERVATIONS_OFFSET + index * OBSERVATION_SIZE;
+    let timestamp = u32::from_le_bytes(
+        data[start..start + 4].try_into().map_err(|_| MorphoError::InvalidClmmObservations)?
+    );
+    let tick_cumulative = i64::from_le_bytes(
+        data[start + 4..start + 12].try_into().map_err(|_| MorphoError::InvalidClmmObservations)?
+    );
+    Ok((timestamp as i64, tick_cumulative))
+}
+
+/// Average tick over the newest observations spanning at least `min_window`
+/// seconds, and the newest observation's timestamp
+///
+/// `data` is a Raydium `ObservationState`. The span starts at the newest
+/// observation at least `min_window` older than the latest one, so it's
+/// never shorter than asked and as short as the ring allows.
+pub fn observation_twap_tick(data: &[u8], min_window: i64) -> Result<(i32, i64)> {
+    require!(data.len() >= OBSERVATION_STATE_LEN, MorphoError::InvalidClmmObservations);
+    let latest_index = u16::from_le_bytes(
+        data[OBSERVATION_INDEX_OFFSET..OBSERVATION_INDEX_OFFSET + 2]
+            .try_into()
+            .map_err(|_| MorphoError::InvalidClmmObservations)?
+    ) as usize;
+    require!(latest_index < CLMM_OBSERVATION_COUNT, MorphoError::InvalidClmmObservations);
+
+    let (latest_time, latest_cumulative) = observation_at(data, latest_index)?;
+    require!(latest_time > 0, MorphoError::TwapWindowNotCovered);
+
+    // Newest to oldest; unwritten slots have a zero timestamp
+    for back in 1..CLMM_OBSERVATION_COUNT {
+        let index = (latest_index + CLMM_OBSERVATION_COUNT - back) % CLMM_OBSERVATION_COUNT;
+        let (time, cumulative) = observation_at(data, index)?;
+        if time == 0 || time >= latest_time {
+            break;
+        }
+        let elapsed = latest_time - time;
+        if elapsed >= min_window {
+            let delta = latest_cumulative
+                .checked_sub(cumulative)
+                .ok_or(MorphoError::MathOverflow)?;
+            let tick = i32::try_from(delta.div_euclid(elapsed))
+                .map_err(|_| MorphoError::InvalidClmmObservations)?;
+            require!(tick.unsigned_abs() <= CLMM_MAX_TICK as u32, MorphoError::InvalidClmmObservations);
+            return Ok((tick, latest_time));
+        }
+    }
+
+    Err(MorphoError::TwapWindowNotCovered.into())
+}
+
+/// Whether `oracle_account` holds a `ClmmTwapOracle`
+pub fn is_clmm_twap_oracle(oracle_account: &AccountInfo) -> Result<bool> {
+    Ok(oracle_account.owner == &crate::ID
+        && oracle_account.try_borrow_data()?.starts_with(ClmmTwapOracle::DISCRIMINATOR))
+}
+
+/// Read a CLMM TWAP oracle's price
+///
+/// Rejects oracles last updated more than `heartbeat` slots ago; bounds are
+/// checked by the caller like every other feed type.
+pub fn read_clmm_twap_price(oracle_account: &AccountInfo, heartbeat: u64) -> Result<u128> {
+    let oracle = ClmmTwapOracle::try_deserialize(&mut &oracle_account.try_borrow_data()?[..])?;
+
+    let age = Clock::get()?.slot.saturating_sub(oracle.last_update_slot);
+    if age > heartbeat {
+        msg!("Oracle heartbeat missed: expected <= {} slots, actual age {} slots", heartbeat, age);
+        return Err(MorphoError::OracleHeartbeatMissed.into());
+    }
+
+    oracle.price()
+}
+
+/// Derive CLMM TWAP oracle PDA
+pub fn derive_clmm_twap_oracle(
+    program_id: &Pubkey,
+    observation_state: &Pubkey,
+    min_window: i64,
+    collateral_is_token0: bool,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PROGRAM_SEED_PREFIX,
+            ClmmTwapOracle::SEED,
+            observation_state.as_ref(),
+            &min_window.to_le_bytes(),
+            &[collateral_is_token0 as u8],
+        ],
+        program_id,
+    )
+}
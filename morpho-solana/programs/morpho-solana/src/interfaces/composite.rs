@@ -0,0 +1,113 @@
+//! Composite (cross-pair) oracle
+//!
+//! Most feeds quote against USD, so an A/B market rarely has an A/B feed.
+//! A `CompositeOracle` holds the latest readings of an A/USD ("base") and a
+//! B/USD ("quote") feed, written by the permissionless `update_composite`
+//! crank. Used as a market's oracle it prices at base / quote, prescaled
+//! with its token decimals.
+//!
+//! Legs are read as whole-token prices: Chainlink and Pyth feeds are, and a
+//! Switchboard or static leg must publish `usd_price * ORACLE_SCALE`. The
+//! composite is only as fresh as its older leg: the market heartbeat is
+//! checked against the crank slot and `max_oracle_age_secs` against the
+//! older leg's publish time.
+
+use anchor_lang::prelude::*;
+use crate::constants::{ORACLE_SCALE, PROGRAM_SEED_PREFIX};
+use crate::errors::MorphoError;
+use crate::math::mul_div_down;
+use super::oracle::prescale_price;
+
+/// Cross-pair price from two USD feeds
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"composite_oracle", base_feed, quote_feed, collateral_decimals, loan_decimals]
+#[account]
+pub struct CompositeOracle {
+    pub bump: u8,
+
+    /// Collateral token's USD feed
+    pub base_feed: Pubkey,
+
+    /// Loan token's USD feed
+    pub quote_feed: Pubkey,
+
+    /// Token decimals the ratio is prescaled with
+    pub collateral_decimals: u8,
+    pub loan_decimals: u8,
+
+    /// Latest whole-token USD prices of each leg (scaled by ORACLE_SCALE)
+    pub base_price: u128,
+    pub quote_price: u128,
+
+    /// Slot of the latest update, checked against the market's heartbeat
+    pub last_update_slot: u64,
+
+    /// Publish time of the older leg at the latest update
+    pub published_at: i64,
+}
+
+impl CompositeOracle {
+    pub const SEED: &'static [u8] = b"composite_oracle";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // base_feed
+        32 +    // quote_feed
+        1 +     // collateral_decimals
+        1 +     // loan_decimals
+        16 +    // base_price
+        16 +    // quote_price
+        8 +     // last_update_slot
+        8       // published_at
+    }
+
+    /// Prescaled collateral/loan price from the stored legs
+    pub fn price(&self) -> Result<u128> {
+        require!(self.quote_price > 0, MorphoError::OracleInvalidPrice);
+        let unit_price = mul_div_down(self.base_price, ORACLE_SCALE, self.quote_price)?;
+        prescale_price(unit_price, self.collateral_decimals, self.loan_decimals)
+    }
+}
+
+/// Whether `oracle_account` holds a `CompositeOracle`
+pub fn is_composite_oracle(oracle_account: &AccountInfo) -> Result<bool> {
+    Ok(oracle_account.owner == &crate::ID
+        && oracle_account.try_borrow_data()?.starts_with(CompositeOracle::DISCRIMINATOR))
+}
+
+/// Read a composite oracle's cross price
+///
+/// Rejects composites last updated more than `heartbeat` slots ago; bounds
+/// are checked by the caller like every other feed type.
+pub fn read_composite_price(oracle_account: &AccountInfo, heartbeat: u64) -> Result<u128> {
+    let composite = CompositeOracle::try_deserialize(&mut &oracle_account.try_borrow_data()?[..])?;
+
+    let age = Clock::get()?.slot.saturating_sub(composite.last_update_slot);
+    if age > heartbeat {
+        msg!("Oracle heartbeat missed: expected <= {} slots, actual age {} slots", heartbeat, age);
+        return Err(MorphoError::OracleHeartbeatMissed.into());
+    }
+
+    composite.price()
+}
+
+/// Derive composite oracle PDA
+pub fn derive_composite_oracle(
+    program_id: &Pubkey,
+    base_feed: &Pubkey,
+    quote_feed: &Pubkey,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PROGRAM_SEED_PREFIX,
+            CompositeOracle::SEED,
+            base_feed.as_ref(),
+            quote_feed.as_ref(),
+            &[collateral_decimals, loan_decimals],
+        ],
+        program_id,
+    )
+}
@@ -0,0 +1,106 @@
+//! EWMA price smoothing
+//!
+//! An `EwmaOracle` keeps an exponential moving average of a market's feed.
+//! The owner creates it over the market's current oracle and picks the
+//! smoothing factor: each `update_ewma` crank moves the average
+//! `smoothing_bps` of the way to the feed's latest price. The market
+//! starts pricing from it once the owner migrates its oracle to the EWMA
+//! account (`propose_oracle_update`), so a momentary wick only moves
+//! liquidation prices by a fraction of its size.
+//!
+//! Cranks are spaced at least `EWMA_UPDATE_INTERVAL` apart, so the factor
+//! means the same thing in time however often anyone cranks. An EWMA
+//! whose crank stops goes stale like any other feed.
+
+use anchor_lang::prelude::*;
+use crate::constants::{BPS, PROGRAM_SEED_PREFIX};
+use crate::errors::MorphoError;
+use crate::math::mul_div_down;
+
+/// Shortest spacing between EWMA updates (seconds)
+pub const EWMA_UPDATE_INTERVAL: i64 = 30;
+
+/// Exponential moving average of a market's feed
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"ewma_oracle", market_id]
+#[account]
+pub struct EwmaOracle {
+    pub bump: u8,
+
+    /// Market whose feed is smoothed
+    pub market_id: [u8; 32],
+
+    /// Underlying Chainlink, Pyth, Switchboard or Static Oracle feed, fixed
+    /// at creation
+    pub source: Pubkey,
+
+    /// Token decimals the source is read with
+    pub collateral_decimals: u8,
+    pub loan_decimals: u8,
+
+    /// Share of the gap to each new reading the average closes (basis
+    /// points, BPS = no smoothing)
+    pub smoothing_bps: u64,
+
+    /// Smoothed price (prescaled, see `oracle`)
+    pub price: u128,
+
+    /// Slot of the latest update, checked against the market's heartbeat
+    pub last_update_slot: u64,
+
+    /// Unix timestamp of the latest update
+    pub last_update: i64,
+}
+
+impl EwmaOracle {
+    pub const SEED: &'static [u8] = b"ewma_oracle";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // market_id
+        32 +    // source
+        1 +     // collateral_decimals
+        1 +     // loan_decimals
+        8 +     // smoothing_bps
+        16 +    // price
+        8 +     // last_update_slot
+        8       // last_update
+    }
+
+    /// Average after blending in `reading`
+    pub fn blend(&self, reading: u128) -> Result<u128> {
+        let step = mul_div_down(self.price.abs_diff(reading), self.smoothing_bps as u128, BPS as u128)?;
+        Ok(if reading >= self.price { self.price + step } else { self.price - step })
+    }
+}
+
+/// Whether `oracle_account` holds an `EwmaOracle`
+pub fn is_ewma_oracle(oracle_account: &AccountInfo) -> Result<bool> {
+    Ok(oracle_account.owner == &crate::ID
+        && oracle_account.try_borrow_data()?.starts_with(EwmaOracle::DISCRIMINATOR))
+}
+
+/// Read an EWMA oracle's smoothed price
+///
+/// Rejects EWMAs last updated more than `heartbeat` slots ago; bounds are
+/// checked by the caller like every other feed type.
+pub fn read_ewma_price(oracle_account: &AccountInfo, heartbeat: u64) -> Result<u128> {
+    let ewma = EwmaOracle::try_deserialize(&mut &oracle_account.try_borrow_data()?[..])?;
+
+    let age = Clock::get()?.slot.saturating_sub(ewma.last_update_slot);
+    if age > heartbeat {
+        msg!("Oracle heartbeat missed: expected <= {} slots, actual age {} slots", heartbeat, age);
+        return Err(MorphoError::OracleHeartbeatMissed.into());
+    }
+
+    Ok(ewma.price)
+}
+
+/// Derive EWMA oracle PDA
+pub fn derive_ewma_oracle(program_id: &Pubkey, market_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, EwmaOracle::SEED, market_id],
+        program_id,
+    )
+}
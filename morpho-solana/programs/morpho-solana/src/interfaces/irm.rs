@@ -3,11 +3,37 @@
 //! IRMs return: borrow rate per second (scaled 1e18 = WAD)
 //! 
 //! Example: 5% APY ≈ 1.58e-9 per second = 1_580_000_000 when scaled by WAD
+//!
+//! Markets created over a deployed IRM program (`Market::irm_cpi`) query it
+//! by CPI: `borrow_rate(utilization)` with the WAD-scaled utilization and no
+//! accounts, answered through return data. The program must be passed among
+//! the instruction's remaining accounts of anything that accrues. Markets
+//! created over the adaptive curve model read and move their
+//! `AdaptiveCurveIrm` instead (see `adaptive_curve_irm`). Other markets,
+//! including every market created before IRM CPI, price at the built-in
+//! curve (`get_borrow_rate_internal`).
+//!
+//! External IRMs are sandboxed: a query must stay within
+//! `IRM_COMPUTE_BUDGET`, and its return data must come from the market's IRM
+//! program, be exactly `IRM_RETURN_DATA_LEN` bytes and stay under the rate
+//! cap. A query failing any of these (`is_irm_fault`) doesn't fail the
+//! instruction: accrual falls back to the market's cached rate, and an IRM
+//! failing `MAX_IRM_FAILURES` slots in a row puts its market in withdraw-only
+//! mode (see `sample_borrow_rate`). An IRM that aborts inside the CPI still aborts the
+//! transaction; the runtime gives the caller no way to recover from that.
 
 use anchor_lang::prelude::*;
-use crate::constants::{WAD, SECONDS_PER_YEAR, MAX_BORROW_RATE_PER_SECOND};
+use anchor_lang::solana_program::compute_units::sol_remaining_compute_units;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::{get_return_data, invoke};
+use crate::constants::{
+    WAD, SECONDS_PER_YEAR, MAX_BORROW_RATE_PER_SECOND, IRM_BORROW_RATE_DISCRIMINATOR, IRM_COMPUTE_BUDGET,
+    IRM_RETURN_DATA_LEN,
+};
 use crate::errors::MorphoError;
 use crate::math::{mul_div_down, checked_add, wad_mul_down};
+use crate::state::Market;
+use super::adaptive_curve_irm::sample_adaptive_curve_rate;
 
 /// Linear (Kinked) IRM configuration
 #[account]
@@ -96,6 +122,90 @@ pub fn get_borrow_rate_internal(
     Ok(std::cmp::min(per_second, MAX_BORROW_RATE_PER_SECOND))
 }
 
+/// Per-second borrow rate the market's IRM gives at its current totals
+///
+/// Queries the IRM program by CPI for `irm_cpi` markets, finding it among
+/// `irm_accounts` by address; samples the market's `AdaptiveCurveIrm` for
+/// `irm_adaptive` markets; uses the built-in curve otherwise.
+pub fn get_market_borrow_rate(market: &Market, irm_accounts: &[AccountInfo]) -> Result<u128> {
+    if market.irm_adaptive {
+        return sample_adaptive_curve_rate(market, irm_accounts);
+    }
+    if !market.irm_cpi {
+        return get_borrow_rate_internal(market.total_supply_assets, market.total_debt());
+    }
+    let irm = irm_accounts
+        .iter()
+        .find(|account| account.key() == market.irm)
+        .ok_or(MorphoError::InvalidIrm)?;
+    query_irm_rate(irm, market.utilization())
+}
+
+/// Ask the IRM program at `irm` for its rate at `utilization` (WAD)
+pub fn query_irm_rate(irm: &AccountInfo, utilization: u128) -> Result<u128> {
+    // The market's IRM address no longer holding a program means it was closed
+    require!(irm.executable, MorphoError::IrmError);
+
+    let mut data = IRM_BORROW_RATE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&utilization.to_le_bytes());
+    let instruction = Instruction { program_id: irm.key(), accounts: vec![], data };
+
+    let remaining_before = sol_remaining_compute_units();
+    invoke(&instruction, std::slice::from_ref(irm)).map_err(|err| {
+        msg!("IRM query failed: {}", err);
+        MorphoError::IrmError
+    })?;
+    check_irm_compute(remaining_before, sol_remaining_compute_units())?;
+
+    decode_irm_return_data(irm.key, get_return_data())
+}
+
+/// Decode the rate an IRM query returned
+///
+/// `return_data` is what `get_return_data()` yields after the CPI. Rejects
+/// data set by any program other than `irm`, anything but a single u128, and
+/// rates above the cap (a sandboxed IRM is never silently clamped).
+pub fn decode_irm_return_data(irm: &Pubkey, return_data: Option<(Pubkey, Vec<u8>)>) -> Result<u128> {
+    let (program_id, data) = return_data.ok_or(MorphoError::IrmNoReturnData)?;
+    require!(program_id == *irm, MorphoError::IrmInvalidProgram);
+    require!(data.len() == IRM_RETURN_DATA_LEN, MorphoError::IrmInvalidReturnData);
+
+    let rate = u128::from_le_bytes(
+        data[..].try_into().map_err(|_| MorphoError::IrmInvalidReturnData)?
+    );
+    require!(rate <= MAX_BORROW_RATE_PER_SECOND, MorphoError::IrmRateTooHigh);
+    Ok(rate)
+}
+
+/// Whether `err` is the IRM's own fault rather than the caller's
+///
+/// A missing IRM account is the caller's to fix and still fails the
+/// instruction; everything the sandbox rejects is an IRM fault.
+pub fn is_irm_fault(err: &Error) -> bool {
+    [
+        MorphoError::IrmInvalidRate,
+        MorphoError::IrmError,
+        MorphoError::IrmNoReturnData,
+        MorphoError::IrmInvalidProgram,
+        MorphoError::IrmInvalidReturnData,
+        MorphoError::IrmRateTooHigh,
+        MorphoError::IrmComputeBudgetExceeded,
+    ]
+    .into_iter()
+    .any(|fault| *err == fault.into())
+}
+
+/// Check an IRM query stayed within its compute budget
+///
+/// Takes the remaining compute units read before and after the CPI.
+pub fn check_irm_compute(remaining_before: u64, remaining_after: u64) -> Result<()> {
+    require!(
+        remaining_before.saturating_sub(remaining_after) <= IRM_COMPUTE_BUDGET,
+        MorphoError::IrmComputeBudgetExceeded
+    );
+    Ok(())
+}
+
 // Example IRM configurations:
 // 
 // STABLE (USDC lending):
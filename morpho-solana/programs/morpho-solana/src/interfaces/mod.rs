@@ -1,7 +1,31 @@
-//! Interfaces for external integrations (Oracle, IRM)
+//! Interfaces for external integrations (Oracle, Chainlink and Pyth feeds,
+//! TWAP, CLMM TWAP, EWMA and composite oracles, relayed prices, external oracle programs,
+//! IRM, adaptive curve IRM, yield adapters, risk oracle)
 
 pub mod oracle;
+pub mod chainlink;
+pub mod pyth;
+pub mod oracle_program;
+pub mod twap;
+pub mod composite;
+pub mod clmm_twap;
+pub mod ewma;
+pub mod price_relay;
 pub mod irm;
+pub mod adaptive_curve_irm;
+pub mod yield_adapter;
+pub mod risk_oracle;
 
 pub use oracle::*;
+pub use chainlink::*;
+pub use pyth::*;
+pub use oracle_program::*;
+pub use twap::*;
+pub use composite::*;
+pub use clmm_twap::*;
+pub use ewma::*;
+pub use price_relay::*;
 pub use irm::*;
+pub use adaptive_curve_irm::*;
+pub use yield_adapter::*;
+pub use risk_oracle::*;
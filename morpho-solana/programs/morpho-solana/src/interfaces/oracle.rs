@@ -1,17 +1,40 @@
 //! Oracle interface with Switchboard integration
 //! 
-//! Oracles return: collateral tokens per 1 loan token (scaled 1e36 = ORACLE_SCALE)
+//! Oracles return a prescaled price: loan token base units per 1 collateral
+//! token base unit, scaled 1e36 = ORACLE_SCALE. Token decimals are already
+//! folded in, so the liquidation math never looks at them.
 //! 
 //! Example: If ETH = $2000 and USDC = $1:
-//! - For ETH/USDC market: oracle returns 2000 * 1e36
+//! - For ETH (9 decimals) / USDC (6 decimals): oracle returns
+//!   2000 * 1e36 * 1e6 / 1e9 = 2e33 (see `prescale_price`)
+//!
+//! Chainlink feeds publish whole-token prices and are prescaled with the
+//! market's decimals (see `chainlink`); so do Pyth feeds, which are also
+//! held to a confidence bound (see `pyth`). Switchboard values are taken
+//! as-is, so the feed must publish the prescaled price, unless the owner
+//! turns on `normalize_switchboard` for the market: then they're whole-token
+//! prices too. Every whole-token answer goes through `normalize_price`.
+//!
+//! Markets between two tokens pegged 1:1 (stable pairs, an LST against its
+//! own unit) can skip feeds entirely: created over the pegged oracle address
+//! (`derive_pegged_oracle`), they price at exactly ORACLE_SCALE, prescaled.
+//! Nothing ever lives at that address; callers pass it like any oracle.
 
 use anchor_lang::prelude::*;
 use switchboard_on_demand::on_demand::accounts::pull_feed::PullFeedAccountData;
-use rust_decimal::Decimal;
-use crate::constants::{ORACLE_SCALE, MIN_ORACLE_PRICE, BPS, WAD};
+use crate::constants::{ORACLE_SCALE, MIN_ORACLE_PRICE, BPS, WAD, PROGRAM_SEED_PREFIX};
 use crate::errors::MorphoError;
-use crate::state::Market;
-use crate::math::{mul_div_down, mul_div_up, to_assets_up};
+use crate::events::OracleFallbackUsed;
+use crate::state::{Market, Position};
+use crate::math::{checked_add, checked_mul, mul_div_down, mul_div_up, to_assets_up};
+use super::chainlink::{parse_chainlink_round, read_chainlink_price, CHAINLINK_STORE_PROGRAM_ID};
+use super::oracle_program::read_program_oracle_price;
+use super::pyth::{parse_pyth_price, read_pyth_price, PYTH_RECEIVER_PROGRAM_ID};
+use super::twap::{is_twap_oracle, read_twap_price, TwapOracle};
+use super::composite::{is_composite_oracle, read_composite_price, CompositeOracle};
+use super::clmm_twap::{is_clmm_twap_oracle, read_clmm_twap_price, ClmmTwapOracle};
+use super::ewma::{is_ewma_oracle, read_ewma_price, EwmaOracle};
+use super::price_relay::{is_price_relay, read_relayed_price, PriceRelay};
 
 /// Maximum oracle price (1 billion ratio) - computed at runtime to avoid const overflow
 pub fn max_oracle_price() -> u128 {
@@ -26,9 +49,44 @@ pub fn max_oracle_price() -> u128 {
 /// 50 slots ≈ 20 seconds
 pub const MAX_ORACLE_STALENESS: u64 = 50;
 
+/// Longest heartbeat a market may configure for its oracle (slots, ≈ 1 hour)
+pub const MAX_ORACLE_HEARTBEAT: u64 = 9_000;
+
+/// Longest wall-clock age a market may configure for its oracle (seconds)
+pub const MAX_ORACLE_AGE_SECS: u64 = 86_400;
+
+/// Widest confidence interval (bps of the price) accepted from markets that
+/// haven't set their own bound, and from reads outside a market
+pub const DEFAULT_ORACLE_CONF_BPS: u64 = 200;
+
+/// Widest confidence interval a market may configure (bps of the price)
+pub const MAX_ORACLE_CONF_BPS: u64 = 1_000;
+
 /// Minimum number of oracle samples required
 pub const MIN_ORACLE_SAMPLES: u32 = 1;
 
+/// Staleness limit for `market`'s oracle (slots)
+///
+/// Markets created before heartbeats were configurable use MAX_ORACLE_STALENESS.
+pub fn oracle_heartbeat(market: &Market) -> u64 {
+    if market.oracle_heartbeat == 0 {
+        MAX_ORACLE_STALENESS
+    } else {
+        market.oracle_heartbeat
+    }
+}
+
+/// Widest confidence interval `market` accepts from its oracle (bps)
+///
+/// Markets that never set a bound use DEFAULT_ORACLE_CONF_BPS.
+pub fn oracle_conf_bound(market: &Market) -> u64 {
+    if market.max_oracle_conf_bps == 0 {
+        DEFAULT_ORACLE_CONF_BPS
+    } else {
+        market.max_oracle_conf_bps
+    }
+}
+
 /// Get validated oracle price from Switchboard pull feed
 /// 
 /// # Arguments
@@ -38,7 +96,7 @@ pub const MIN_ORACLE_SAMPLES: u32 = 1;
 /// 
 /// # Security Checks
 /// 1. Oracle account matches market's configured oracle
-/// 2. Price data is fresh (within MAX_ORACLE_STALENESS slots)
+/// 2. Price data is fresh (within the market's oracle heartbeat)
 /// 3. Minimum number of oracle responses received
 /// 4. Price is within valid bounds (MIN_ORACLE_PRICE, max_oracle_price())
 pub fn get_switchboard_price_validated(
@@ -52,21 +110,59 @@ pub fn get_switchboard_price_validated(
         MorphoError::InvalidOracle
     );
 
-    // Parse Switchboard PullFeed account
     let data = oracle_account.try_borrow_data()?;
     let feed = PullFeedAccountData::parse(data)
         .map_err(|_| error!(MorphoError::OracleInvalidReturnData))?;
+    let (collateral_decimals, loan_decimals) = switchboard_decimals(market);
+    read_switchboard_price(&feed, clock, oracle_heartbeat(market), collateral_decimals, loan_decimals)
+}
+
+/// Token decimals `market`'s Switchboard answers are prescaled with
+///
+/// Equal decimals (no prescaling) unless the market normalizes them.
+fn switchboard_decimals(market: &Market) -> (u8, u8) {
+    if market.normalize_switchboard {
+        (market.collateral_decimals, market.loan_decimals)
+    } else {
+        (0, 0)
+    }
+}
+
+/// Read a parsed Switchboard PullFeed price with checks 2-4 of
+/// `get_switchboard_price_validated` (any feed, no market binding)
+///
+/// The answer is prescaled with the given decimals; pass equal ones for a
+/// feed that already publishes prescaled prices.
+fn read_switchboard_price(
+    feed: &PullFeedAccountData,
+    clock: &Clock,
+    heartbeat: u64,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+) -> Result<u128> {
+    // Check 2: the latest result is within the heartbeat
+    let age = clock.slot.saturating_sub(feed.result.slot);
+    if age > heartbeat {
+        msg!("Oracle heartbeat missed: expected <= {} slots, actual age {} slots", heartbeat, age);
+        return Err(MorphoError::OracleHeartbeatMissed.into());
+    }
 
-    // Check 2 & 3: Get validated price with staleness and sample checks
+    // Check 3: enough samples within the heartbeat (capped at the current
+    // slot, as Switchboard subtracts it from the clock unchecked)
     let price_decimal = feed.get_value(
         clock.slot,
-        MAX_ORACLE_STALENESS,
+        std::cmp::min(heartbeat, clock.slot),
         MIN_ORACLE_SAMPLES,
         true, // only_positive
     ).map_err(|_| error!(MorphoError::OracleStale))?;
 
-    // Convert Decimal to u128 scaled by ORACLE_SCALE
-    let price = decimal_to_oracle_scale(&price_decimal)?;
+    // Decimal is mantissa * 10^-scale
+    let price = normalize_price(
+        price_decimal.mantissa().unsigned_abs(),
+        -(price_decimal.scale() as i32),
+        collateral_decimals,
+        loan_decimals,
+    )?;
 
     // Check 4: Price sanity bounds
     require!(price >= MIN_ORACLE_PRICE, MorphoError::OraclePriceTooLow);
@@ -75,30 +171,48 @@ pub fn get_switchboard_price_validated(
     Ok(price)
 }
 
-/// Convert Switchboard Decimal to ORACLE_SCALE (1e36)
-/// 
-/// Switchboard returns prices as rust_decimal::Decimal.
-/// We need to scale this to our 1e36 ORACLE_SCALE.
-fn decimal_to_oracle_scale(decimal: &Decimal) -> Result<u128> {
-    // Get the mantissa (scaled integer value)
-    // Decimal stores value as mantissa * 10^-scale
-    let mantissa = decimal.mantissa();
-    let scale = decimal.scale();
-    
-    // Our ORACLE_SCALE is 1e36
-    // If Switchboard gives us a value like 2000.0 with scale 18
-    // We need: mantissa * 1e(36 - scale)
-    
-    let mantissa_u128 = mantissa.unsigned_abs();
-    
-    if scale <= 36 {
-        let scale_factor = 10u128.pow(36 - scale);
-        mantissa_u128.checked_mul(scale_factor)
-            .ok_or_else(|| error!(MorphoError::MathOverflow))
-    } else {
-        // Scale down if Switchboard uses more than 36 decimals (unlikely)
-        let scale_factor = 10u128.pow(scale - 36);
-        Ok(mantissa_u128 / scale_factor)
+// ============================================================================
+// Oracle Failure Drills (devnet)
+// ============================================================================
+
+/// Oracle failure a devnet drill can force on a market
+///
+/// Stored on `Market::oracle_failure_mode`; only builds with the `devnet`
+/// feature act on it, so mainnet builds can never be made to fail this way.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OracleFailureMode {
+    None = 0,
+    Stale = 1,
+    InvalidData = 2,
+    PriceTooLow = 3,
+    PriceTooHigh = 4,
+    WrongAccount = 5,
+}
+
+impl OracleFailureMode {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::None),
+            1 => Some(Self::Stale),
+            2 => Some(Self::InvalidData),
+            3 => Some(Self::PriceTooLow),
+            4 => Some(Self::PriceTooHigh),
+            5 => Some(Self::WrongAccount),
+            _ => None,
+        }
+    }
+
+    /// Error the oracle adapter returns while this mode is active
+    pub fn error(self) -> Option<MorphoError> {
+        match self {
+            Self::None => None,
+            Self::Stale => Some(MorphoError::OracleStale),
+            Self::InvalidData => Some(MorphoError::OracleInvalidReturnData),
+            Self::PriceTooLow => Some(MorphoError::OraclePriceTooLow),
+            Self::PriceTooHigh => Some(MorphoError::OraclePriceTooHigh),
+            Self::WrongAccount => Some(MorphoError::InvalidOracle),
+        }
     }
 }
 
@@ -126,36 +240,295 @@ impl StaticOracle {
 
 /// Get validated oracle price (supports both Switchboard and Static Oracle)
 /// 
-/// This function auto-detects the oracle type based on account size:
+/// This function auto-detects the oracle type:
+/// - Markets created over the pegged oracle address price at 1:1
+/// - `TwapOracle` accounts price at their time-weighted average
+/// - `CompositeOracle` accounts price at the ratio of their two USD legs
+/// - `PriceRelay` accounts price at their relayer's latest push
+/// - Accounts owned by the Chainlink store program are OCR2 feeds
+/// - Accounts owned by the Pyth receiver program are `PriceUpdateV2`s
+/// - Markets created over the allowlisted oracle program are priced by CPI
 /// - Large accounts (>1KB) are treated as Switchboard PullFeed
 /// - Small accounts are treated as StaticOracle (for testing)
 /// 
 /// # Security Checks
 /// 1. Oracle account matches market's configured oracle
 /// 2. Price is within valid bounds (MIN_ORACLE_PRICE, max_oracle_price())
+/// 3. The feed was published within the market's `max_oracle_age_secs`
+/// 4. Pyth prices' confidence interval is within the market's bound
+///
+/// See `get_oracle_price_with_fallback` for markets with a secondary feed.
 pub fn get_oracle_price_validated(
     oracle_account: &AccountInfo,
     market: &Market,
 ) -> Result<u128> {
+    // Devnet drills: fail exactly as a broken feed would
+    #[cfg(feature = "devnet")]
+    if let Some(err) = OracleFailureMode::from_u8(market.oracle_failure_mode).and_then(OracleFailureMode::error) {
+        return Err(err.into());
+    }
+
     // Check 1: Oracle account matches market configuration
     require!(
         oracle_account.key() == market.oracle,
         MorphoError::InvalidOracle
     );
 
+    read_market_feed(oracle_account, market)
+}
+
+/// `get_oracle_price_validated`, falling back to the market's secondary
+/// oracle when the primary feed fails
+///
+/// Only feed failures (stale, malformed or out-of-bounds prices) fall back;
+/// passing the wrong primary account still fails. Devnet failure drills
+/// count as feed failures, so they exercise the fallback too. If the
+/// fallback fails as well, its error is returned.
+pub fn get_oracle_price_with_fallback(
+    oracle_account: &AccountInfo,
+    fallback_account: Option<&AccountInfo>,
+    market: &Market,
+) -> Result<u128> {
+    let err = match get_oracle_price_validated(oracle_account, market) {
+        Ok(price) => return Ok(price),
+        Err(err) => err,
+    };
+    let fallback = match fallback_account {
+        Some(fallback) if market.fallback_oracle != Pubkey::default() && is_feed_failure(&err) => fallback,
+        _ => return Err(err),
+    };
+    require!(fallback.key() == market.fallback_oracle, MorphoError::InvalidOracle);
+
+    msg!("Primary oracle failed: {}", err);
+    let price = read_market_feed(fallback, market)?;
+    emit!(OracleFallbackUsed {
+        market_id: market.market_id,
+        fallback_oracle: market.fallback_oracle,
+    });
+    Ok(price)
+}
+
+/// Whether `err` means the feed is down or unusable, as opposed to the
+/// caller passing the wrong account
+pub fn is_feed_failure(err: &Error) -> bool {
+    [
+        MorphoError::OracleStale,
+        MorphoError::OracleHeartbeatMissed,
+        MorphoError::OracleInvalidPrice,
+        MorphoError::OracleInvalidReturnData,
+        MorphoError::OraclePriceTooLow,
+        MorphoError::OraclePriceTooHigh,
+        MorphoError::OracleConfidenceTooWide,
+        MorphoError::OracleNoReturnData,
+    ]
+    .into_iter()
+    .any(|failure| *err == failure.into())
+}
+
+/// Freshness and bounds checks of `get_oracle_price_validated`, using
+/// `market`'s limits, for an account the caller already bound to it
+fn read_market_feed(oracle_account: &AccountInfo, market: &Market) -> Result<u128> {
+    // A peg never goes stale; the account is only checked to be the market's
+    if market.oracle_pegged && oracle_account.key() == market.oracle {
+        return pegged_price(market.collateral_decimals, market.loan_decimals);
+    }
+
+    // External oracle programs own their freshness checks. The fallback is
+    // always read with the built-in decoders.
+    if market.oracle_program_priced && oracle_account.key() == market.oracle {
+        let price = read_program_oracle_price(oracle_account, market.collateral_decimals, market.loan_decimals)?;
+        require!(price >= MIN_ORACLE_PRICE, MorphoError::OraclePriceTooLow);
+        require!(price <= max_oracle_price(), MorphoError::OraclePriceTooHigh);
+        return Ok(price);
+    }
+
+    let price = read_feed_price_within(
+        oracle_account,
+        oracle_heartbeat(market),
+        oracle_conf_bound(market),
+        market.collateral_decimals,
+        market.loan_decimals,
+        market.normalize_switchboard,
+    )?;
+
+    // Wall-clock age, on top of the slot heartbeat
+    if market.max_oracle_age_secs > 0 {
+        if let Some(published) = feed_publish_time(oracle_account)? {
+            check_oracle_age(published, Clock::get()?.unix_timestamp, market.max_oracle_age_secs)?;
+        }
+    }
+
+    Ok(price)
+}
+
+/// Seed of the pegged oracle address
+pub const PEGGED_ORACLE_SEED: &[u8] = b"pegged_oracle";
+
+/// Derive the pegged oracle address
+///
+/// A program address nothing is ever created at. Markets created with it as
+/// their oracle price at `pegged_price`.
+pub fn derive_pegged_oracle(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROGRAM_SEED_PREFIX, PEGGED_ORACLE_SEED], program_id)
+}
+
+/// Prescaled price of one collateral token worth exactly one loan token
+pub fn pegged_price(collateral_decimals: u8, loan_decimals: u8) -> Result<u128> {
+    let price = prescale_price(ORACLE_SCALE, collateral_decimals, loan_decimals)?;
+    require!(price >= MIN_ORACLE_PRICE, MorphoError::OraclePriceTooLow);
+    require!(price <= max_oracle_price(), MorphoError::OraclePriceTooHigh);
+    Ok(price)
+}
+
+/// Reject a feed last published at `published` if it's more than
+/// `max_age_secs` old at `now`
+pub fn check_oracle_age(published: i64, now: i64, max_age_secs: u64) -> Result<()> {
+    let age = now.saturating_sub(published);
+    if age > max_age_secs as i64 {
+        msg!("Oracle stale: expected <= {} seconds, actual age {} seconds", max_age_secs, age);
+        return Err(MorphoError::OracleStale.into());
+    }
+    Ok(())
+}
+
+/// Unix timestamp of the feed's latest update
+///
+/// None for static oracles, which carry no update time.
+pub fn feed_publish_time(oracle_account: &AccountInfo) -> Result<Option<i64>> {
+    if is_twap_oracle(oracle_account)? {
+        let twap = TwapOracle::try_deserialize(&mut &oracle_account.try_borrow_data()?[..])?;
+        return Ok(twap.latest().map(|sample| sample.timestamp));
+    }
+    if is_composite_oracle(oracle_account)? {
+        let composite = CompositeOracle::try_deserialize(&mut &oracle_account.try_borrow_data()?[..])?;
+        return Ok(Some(composite.published_at));
+    }
+    if is_clmm_twap_oracle(oracle_account)? {
+        let oracle = ClmmTwapOracle::try_deserialize(&mut &oracle_account.try_borrow_data()?[..])?;
+        return Ok(Some(oracle.observed_at));
+    }
+    if is_ewma_oracle(oracle_account)? {
+        let ewma = EwmaOracle::try_deserialize(&mut &oracle_account.try_borrow_data()?[..])?;
+        return Ok(Some(ewma.last_update));
+    }
+    if is_price_relay(oracle_account)? {
+        let relay = PriceRelay::try_deserialize(&mut &oracle_account.try_borrow_data()?[..])?;
+        return Ok(Some(relay.last_update));
+    }
+    if *oracle_account.owner == CHAINLINK_STORE_PROGRAM_ID {
+        let round = parse_chainlink_round(&oracle_account.try_borrow_data()?)?;
+        return Ok(Some(round.timestamp as i64));
+    }
+    if *oracle_account.owner == PYTH_RECEIVER_PROGRAM_ID {
+        let price = parse_pyth_price(&oracle_account.try_borrow_data()?)?;
+        return Ok(Some(price.publish_time));
+    }
+
+    let data = oracle_account.try_borrow_data()?;
+    if data.len() >= 1000 {
+        if let Ok(feed) = PullFeedAccountData::parse(data) {
+            return Ok(Some(feed.last_update_timestamp));
+        }
+    }
+    Ok(None)
+}
+
+/// Read a price from any Chainlink, Pyth, Switchboard or Static Oracle feed,
+/// with the same freshness and bounds checks as `get_oracle_price_validated`
+///
+/// Does NOT check which feed it is; callers bind the account themselves.
+/// The token decimals only matter for Chainlink and Pyth feeds, whose
+/// whole-token answers get prescaled with them; Switchboard answers are
+/// taken as prescaled. Pyth prices are held to DEFAULT_ORACLE_CONF_BPS.
+pub fn read_feed_price(oracle_account: &AccountInfo, collateral_decimals: u8, loan_decimals: u8) -> Result<u128> {
+    read_feed_price_within(
+        oracle_account,
+        MAX_ORACLE_STALENESS,
+        DEFAULT_ORACLE_CONF_BPS,
+        collateral_decimals,
+        loan_decimals,
+        false,
+    )
+}
+
+/// `read_feed_price` with a staleness limit of `heartbeat` slots and a Pyth
+/// confidence bound of `max_conf_bps`, prescaling Switchboard answers with
+/// the token decimals too if `normalize_switchboard`
+///
+/// Static oracles carry no update slot and are never stale.
+fn read_feed_price_within(
+    oracle_account: &AccountInfo,
+    heartbeat: u64,
+    max_conf_bps: u64,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+    normalize_switchboard: bool,
+) -> Result<u128> {
+    if is_twap_oracle(oracle_account)? {
+        let price = read_twap_price(oracle_account, heartbeat)?;
+        require!(price >= MIN_ORACLE_PRICE, MorphoError::OraclePriceTooLow);
+        require!(price <= max_oracle_price(), MorphoError::OraclePriceTooHigh);
+        return Ok(price);
+    }
+
+    if is_composite_oracle(oracle_account)? {
+        let price = read_composite_price(oracle_account, heartbeat)?;
+        require!(price >= MIN_ORACLE_PRICE, MorphoError::OraclePriceTooLow);
+        require!(price <= max_oracle_price(), MorphoError::OraclePriceTooHigh);
+        return Ok(price);
+    }
+
+    if is_clmm_twap_oracle(oracle_account)? {
+        let price = read_clmm_twap_price(oracle_account, heartbeat)?;
+        require!(price >= MIN_ORACLE_PRICE, MorphoError::OraclePriceTooLow);
+        require!(price <= max_oracle_price(), MorphoError::OraclePriceTooHigh);
+        return Ok(price);
+    }
+
+    if is_ewma_oracle(oracle_account)? {
+        let price = read_ewma_price(oracle_account, heartbeat)?;
+        require!(price >= MIN_ORACLE_PRICE, MorphoError::OraclePriceTooLow);
+        require!(price <= max_oracle_price(), MorphoError::OraclePriceTooHigh);
+        return Ok(price);
+    }
+
+    if is_price_relay(oracle_account)? {
+        let price = read_relayed_price(oracle_account, heartbeat)?;
+        require!(price >= MIN_ORACLE_PRICE, MorphoError::OraclePriceTooLow);
+        require!(price <= max_oracle_price(), MorphoError::OraclePriceTooHigh);
+        return Ok(price);
+    }
+
+    if *oracle_account.owner == CHAINLINK_STORE_PROGRAM_ID {
+        let price = read_chainlink_price(oracle_account, heartbeat, collateral_decimals, loan_decimals)?;
+        require!(price >= MIN_ORACLE_PRICE, MorphoError::OraclePriceTooLow);
+        require!(price <= max_oracle_price(), MorphoError::OraclePriceTooHigh);
+        return Ok(price);
+    }
+
+    if *oracle_account.owner == PYTH_RECEIVER_PROGRAM_ID {
+        let price = read_pyth_price(oracle_account, heartbeat, max_conf_bps, collateral_decimals, loan_decimals)?;
+        require!(price >= MIN_ORACLE_PRICE, MorphoError::OraclePriceTooLow);
+        require!(price <= max_oracle_price(), MorphoError::OraclePriceTooHigh);
+        return Ok(price);
+    }
+
     let data = oracle_account.try_borrow_data()?;
     let data_len = data.len();
     
     // Try to parse as Switchboard PullFeed first (accounts are fairly large ~3KB)
     if data_len >= 1000 {
-        // Use slot-aware validation to avoid Switchboard underflow panics.
-        let clock = Clock::get()?;
-        if let Ok(price) = get_switchboard_price_validated(oracle_account, market, &clock) {
-            return Ok(price);
+        if let Ok(feed) = PullFeedAccountData::parse(oracle_account.try_borrow_data()?) {
+            // A real feed must pass its own checks; no static fallback
+            let clock = Clock::get()?;
+            let (collateral_decimals, loan_decimals) = if normalize_switchboard {
+                (collateral_decimals, loan_decimals)
+            } else {
+                (0, 0)
+            };
+            return read_switchboard_price(&feed, &clock, heartbeat, collateral_decimals, loan_decimals);
         }
-        // If Switchboard parsing fails, try static oracle
-        // Need to re-borrow data
-        let data = oracle_account.try_borrow_data()?;
+        // Not a Switchboard feed: try static oracle
         return parse_static_oracle_price(&data);
     }
     
@@ -184,20 +557,85 @@ fn parse_static_oracle_price(data: &[u8]) -> Result<u128> {
 // ============================================================================
 // Liquidation Math
 // ============================================================================
+//
+// Every price below is prescaled: loan base units per collateral base unit,
+// times ORACLE_SCALE. Use `prescale_price` to build one from a whole-token
+// price.
+
+/// Convert a whole-token price into the prescaled price the liquidation
+/// math takes
+///
+/// `unit_price` is loan tokens per 1 collateral token, scaled by
+/// ORACLE_SCALE, with no decimals applied. The result is
+/// `unit_price * 10^loan_decimals / 10^collateral_decimals`, rounded down.
+pub fn prescale_price(unit_price: u128, collateral_decimals: u8, loan_decimals: u8) -> Result<u128> {
+    let pow10 = |exp: u8| 10u128.checked_pow(exp as u32).ok_or(MorphoError::MathOverflow);
+    if loan_decimals >= collateral_decimals {
+        checked_mul(unit_price, pow10(loan_decimals - collateral_decimals)?)
+    } else {
+        Ok(unit_price / pow10(collateral_decimals - loan_decimals)?)
+    }
+}
+
+/// Prescaled price from a feed's whole-token answer `mantissa * 10^exponent`
+///
+/// The one place feed exponents meet token decimals: Chainlink, Pyth and
+/// normalized Switchboard answers all come through here, so markets with
+/// 0- or 2-decimal tokens price as correctly as 9-decimal ones. The
+/// exponent is applied before the decimals so no precision is lost to an
+/// early division.
+pub fn normalize_price(mantissa: u128, exponent: i32, collateral_decimals: u8, loan_decimals: u8) -> Result<u128> {
+    // ORACLE_SCALE is 10^36
+    let shift = 36i64 + exponent as i64;
+    let unit_price = if shift >= 0 {
+        let scale = u32::try_from(shift).ok().and_then(|s| 10u128.checked_pow(s)).ok_or(MorphoError::MathOverflow)?;
+        checked_mul(mantissa, scale)?
+    } else {
+        // A divisor past u128 leaves nothing of the answer
+        u32::try_from(-shift).ok().and_then(|s| 10u128.checked_pow(s)).map_or(0, |scale| mantissa / scale)
+    };
+    prescale_price(unit_price, collateral_decimals, loan_decimals)
+}
 
 /// Check if a position is liquidatable
 /// 
 /// A position is liquidatable when:
 /// borrowed_value > collateral_value * lltv
+///
+/// `collateral` is in collateral base units and `prescaled_price` already
+/// carries both tokens' decimals (see `prescale_price`).
 pub fn is_liquidatable(
     collateral: u128,
     borrow_shares: u128,
     total_borrow_assets: u128,
     total_borrow_shares: u128,
-    oracle_price: u128,
+    prescaled_price: u128,
     lltv: u64,
 ) -> Result<bool> {
-    if borrow_shares == 0 {
+    is_liquidatable_with_stable_debt(
+        collateral,
+        borrow_shares,
+        0,
+        total_borrow_assets,
+        total_borrow_shares,
+        prescaled_price,
+        lltv,
+    )
+}
+
+/// Check if a position is liquidatable, counting its stable debt too
+///
+/// `stable_debt` must already be accrued (see `accrue_stable_debt`).
+pub fn is_liquidatable_with_stable_debt(
+    collateral: u128,
+    borrow_shares: u128,
+    stable_debt: u128,
+    total_borrow_assets: u128,
+    total_borrow_shares: u128,
+    prescaled_price: u128,
+    lltv: u64,
+) -> Result<bool> {
+    if borrow_shares == 0 && stable_debt == 0 {
         return Ok(false);
     }
 
@@ -207,14 +645,53 @@ pub fn is_liquidatable(
         total_borrow_assets,
         total_borrow_shares,
     )?;
+    let borrowed = checked_add(borrowed, stable_debt)?;
 
-    // Max borrowable = collateral * price * lltv / ORACLE_SCALE / BPS
-    let collateral_value = mul_div_down(collateral, oracle_price, ORACLE_SCALE)?;
+    // Max borrowable = collateral * price * lltv / ORACLE_SCALE / BPS,
+    // already in loan base units
+    let collateral_value = mul_div_down(collateral, prescaled_price, ORACLE_SCALE)?;
     let max_borrow = mul_div_down(collateral_value, lltv as u128, BPS as u128)?;
 
     Ok(borrowed > max_borrow)
 }
 
+/// Require `position` to be healthy at `lltv`, pricing it only if it owes
+/// anything
+///
+/// Returns whether the oracle was read. Pass the position AFTER the
+/// instruction's effects: a position left without debt is healthy at any
+/// price, so a stale, broken or missing feed can never block it.
+///
+/// `fallback` is the market's secondary oracle, used if `oracle` fails (see
+/// `get_oracle_price_with_fallback`).
+pub fn ensure_position_healthy(
+    market: &Market,
+    position: &Position,
+    lltv: u64,
+    oracle: Option<&AccountInfo>,
+    fallback: Option<&AccountInfo>,
+) -> Result<bool> {
+    if !position.has_debt() {
+        return Ok(false);
+    }
+
+    let oracle = oracle.ok_or(MorphoError::InvalidOracle)?;
+    let oracle_price = get_oracle_price_with_fallback(oracle, fallback, market)?;
+    require!(
+        !is_liquidatable_with_stable_debt(
+            position.collateral,
+            position.borrow_shares,
+            position.stable_borrow_assets,
+            market.total_borrow_assets,
+            market.total_borrow_shares,
+            oracle_price,
+            lltv,
+        )?,
+        MorphoError::PositionUnhealthy
+    );
+    Ok(true)
+}
+
 /// Calculate health factor (scaled by WAD)
 /// 
 /// health > WAD means healthy
@@ -264,8 +741,7 @@ pub fn calculate_lif(lltv: u64) -> u64 {
 
     // BPS * BPS / denominator (scaled result)
     let lif = (LIF_BPS as u128)
-        .checked_mul(LIF_BPS as u128)
-        .unwrap_or(u128::MAX)
+        .saturating_mul(LIF_BPS as u128)
         .checked_div(denominator as u128)
         .unwrap_or(u128::MAX) as u64;
 
@@ -274,29 +750,57 @@ pub fn calculate_lif(lltv: u64) -> u64 {
 
 /// Calculate seized collateral for liquidation
 /// 
-/// seized = repaid_assets * oracle_price * LIF / ORACLE_SCALE / LIF_BPS
+/// seized = repaid_assets * ORACLE_SCALE / prescaled_price * LIF / LIF_BPS
+///
+/// `repaid_assets` is in loan base units and the result in collateral base
+/// units; `prescaled_price` does the decimal conversion between them (see
+/// `prescale_price`).
 pub fn calculate_seized_collateral(
     repaid_assets: u128,
-    oracle_price: u128,
+    prescaled_price: u128,
     lif: u64,
 ) -> Result<u128> {
     use crate::constants::LIF_BPS;
     
-    // collateral_value = repaid * price / ORACLE_SCALE
-    let collateral_value = mul_div_up(
+    // Collateral worth `repaid` at the oracle price (loan per collateral),
+    // the inverse of the valuation in `is_liquidatable`
+    let repaid_collateral = mul_div_up(
         repaid_assets,
-        oracle_price,
         ORACLE_SCALE,
+        prescaled_price,
     )?;
 
-    // seized = collateral_value * lif / LIF_BPS
+    // seized = repaid_collateral * lif / LIF_BPS
     mul_div_up(
-        collateral_value,
+        repaid_collateral,
         lif as u128,
         LIF_BPS as u128,
     )
 }
 
+/// Check seized collateral is worth at most `repaid_assets * MAX_LIF`
+///
+/// `calculate_seized_collateral` can only exceed that through a scaling bug
+/// (a mis-scaled price, a LIF above MAX_LIF), so `liquidate` aborts rather
+/// than pay out. Allows `SEIZE_GUARD_TOLERANCE_BPS` on top, plus two
+/// collateral units for the upward rounding of the seize.
+pub fn seize_within_max_lif(
+    seized_collateral: u128,
+    repaid_assets: u128,
+    prescaled_price: u128,
+) -> Result<bool> {
+    use crate::constants::{LIF_BPS, MAX_LIF, SEIZE_GUARD_TOLERANCE_BPS};
+
+    let seized_value = mul_div_down(seized_collateral, prescaled_price, ORACLE_SCALE)?;
+    let max_value = mul_div_up(
+        repaid_assets,
+        MAX_LIF as u128 * (BPS + SEIZE_GUARD_TOLERANCE_BPS) as u128,
+        LIF_BPS as u128 * BPS as u128,
+    )?;
+    let rounding = mul_div_up(2, prescaled_price, ORACLE_SCALE)?;
+    Ok(seized_value <= checked_add(max_value, rounding)?)
+}
+
 /// Socialize bad debt across all suppliers
 /// 
 /// Called when liquidation leaves position with debt but no collateral.
@@ -324,9 +828,28 @@ pub fn socialize_bad_debt(
 
     // Remove from supply side (socializes loss)
     market.total_supply_assets = market.total_supply_assets.saturating_sub(bad_debt);
+    market.total_bad_debt = market.total_bad_debt.saturating_add(bad_debt);
 
     // Note: total_supply_shares stays the same
     // Each share is now worth slightly less
 
     Ok(bad_debt)
 }
+
+/// Socialize a position's unpaid stable debt across suppliers
+///
+/// Returns the bad debt in loan token units.
+pub fn socialize_stable_bad_debt(
+    market: &mut Market,
+    stable_debt: u128,
+    stable_rate: u128,
+) -> Result<u128> {
+    if stable_debt == 0 {
+        return Ok(0);
+    }
+
+    market.remove_stable_debt(stable_debt, stable_rate, true)?;
+    market.total_supply_assets = market.total_supply_assets.saturating_sub(stable_debt);
+    market.total_bad_debt = market.total_bad_debt.saturating_add(stable_debt);
+    Ok(stable_debt)
+}
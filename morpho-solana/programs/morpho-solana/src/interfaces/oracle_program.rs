@@ -0,0 +1,71 @@
+//! External oracle program interface
+//!
+//! Lets a market price off any program that implements a single
+//! `get_price` instruction instead of a feed layout Morpho decodes itself.
+//! Morpho CPIs into the program that owns the market's oracle account:
+//!
+//! - data: `ORACLE_GET_PRICE_DISCRIMINATOR`, collateral decimals, loan decimals
+//! - accounts: the oracle account (read-only)
+//! - return data: the prescaled price as a little-endian u128
+//!
+//! The program must be the protocol's allowlisted oracle program when the
+//! market is created (see `ProtocolState::oracle_program`), and must appear
+//! among the calling instruction's accounts so the runtime can invoke it.
+//! It owns freshness; Morpho still applies its price bounds. Only the
+//! primary oracle is priced this way; fallbacks use the built-in decoders.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{get_return_data, invoke};
+use crate::errors::MorphoError;
+
+/// Instruction discriminator of `get_price` (Anchor's `global:get_price`)
+pub const ORACLE_GET_PRICE_DISCRIMINATOR: [u8; 8] = [238, 38, 193, 106, 228, 32, 210, 33];
+
+/// Exact return data length of a `get_price` call (one u128)
+pub const ORACLE_RETURN_DATA_LEN: usize = 16;
+
+/// Instruction data of a `get_price` call
+pub fn oracle_get_price_data(collateral_decimals: u8, loan_decimals: u8) -> Vec<u8> {
+    let mut data = ORACLE_GET_PRICE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&[collateral_decimals, loan_decimals]);
+    data
+}
+
+/// Decode the price a `get_price` call returned
+///
+/// `return_data` is what `get_return_data()` yields after the CPI. Rejects
+/// data set by any program other than `oracle_program` and anything but a
+/// single u128; bounds are checked by the caller like every other feed type.
+pub fn decode_oracle_return_data(
+    oracle_program: &Pubkey,
+    return_data: Option<(Pubkey, Vec<u8>)>,
+) -> Result<u128> {
+    let (program_id, data) = return_data.ok_or(MorphoError::OracleNoReturnData)?;
+    require!(program_id == *oracle_program, MorphoError::OracleInvalidProgram);
+    require!(data.len() == ORACLE_RETURN_DATA_LEN, MorphoError::OracleInvalidReturnData);
+
+    Ok(u128::from_le_bytes(
+        data[..].try_into().map_err(|_| MorphoError::OracleInvalidReturnData)?
+    ))
+}
+
+/// Price `oracle_account` by calling `get_price` on the program that owns it
+///
+/// The caller checks the market was created over this account, while its
+/// owner was the allowlisted oracle program.
+pub fn read_program_oracle_price(
+    oracle_account: &AccountInfo,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+) -> Result<u128> {
+    let oracle_program = *oracle_account.owner;
+    let ix = Instruction {
+        program_id: oracle_program,
+        accounts: vec![AccountMeta::new_readonly(oracle_account.key(), false)],
+        data: oracle_get_price_data(collateral_decimals, loan_decimals),
+    };
+    invoke(&ix, std::slice::from_ref(oracle_account))?;
+
+    decode_oracle_return_data(&oracle_program, get_return_data())
+}
@@ -0,0 +1,91 @@
+//! Relayed price cache
+//!
+//! A `PriceRelay` holds a price pushed by a market's trusted relayer. The
+//! owner points the market's fallback oracle at it, so it only prices when
+//! the primary feed fails. Each push must sit within `max_deviation_bps` of
+//! the primary, or of the last primary price seen while the primary is
+//! down, so the relayer can bridge a halted feed but never walk the price
+//! far from where the feed left it.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::errors::MorphoError;
+
+/// Widest gap a relayed price may have to its reference (10%)
+pub const MAX_RELAY_DEVIATION_BPS: u64 = 1_000;
+
+/// Market-local price cache fed by a trusted relayer
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"price_relay", market_id]
+#[account]
+pub struct PriceRelay {
+    pub bump: u8,
+
+    /// Market whose fallback this relay serves
+    pub market_id: [u8; 32],
+
+    /// Only key allowed to push prices (default = none)
+    pub relayer: Pubkey,
+
+    /// Widest gap to the reference price a push may have (basis points)
+    pub max_deviation_bps: u64,
+
+    /// Latest relayed price (prescaled, see `oracle`)
+    pub price: u128,
+
+    /// Last primary price a push was checked against
+    pub reference_price: u128,
+
+    /// Slot of the latest push, checked against the market's heartbeat
+    pub last_update_slot: u64,
+
+    /// Unix timestamp of the latest push
+    pub last_update: i64,
+}
+
+impl PriceRelay {
+    pub const SEED: &'static [u8] = b"price_relay";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // market_id
+        32 +    // relayer
+        8 +     // max_deviation_bps
+        16 +    // price
+        16 +    // reference_price
+        8 +     // last_update_slot
+        8       // last_update
+    }
+}
+
+/// Whether `oracle_account` holds a `PriceRelay`
+pub fn is_price_relay(oracle_account: &AccountInfo) -> Result<bool> {
+    Ok(oracle_account.owner == &crate::ID
+        && oracle_account.try_borrow_data()?.starts_with(PriceRelay::DISCRIMINATOR))
+}
+
+/// Read a relay's latest price
+///
+/// Rejects relays never pushed to, or last pushed more than `heartbeat`
+/// slots ago; bounds are checked by the caller like every other feed type.
+pub fn read_relayed_price(oracle_account: &AccountInfo, heartbeat: u64) -> Result<u128> {
+    let relay = PriceRelay::try_deserialize(&mut &oracle_account.try_borrow_data()?[..])?;
+    require!(relay.price > 0, MorphoError::OracleInvalidPrice);
+
+    let age = Clock::get()?.slot.saturating_sub(relay.last_update_slot);
+    if age > heartbeat {
+        msg!("Oracle heartbeat missed: expected <= {} slots, actual age {} slots", heartbeat, age);
+        return Err(MorphoError::OracleHeartbeatMissed.into());
+    }
+
+    Ok(relay.price)
+}
+
+/// Derive price relay PDA
+pub fn derive_price_relay(program_id: &Pubkey, market_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, PriceRelay::SEED, market_id],
+        program_id,
+    )
+}
@@ -0,0 +1,110 @@
+//! Pyth pull-oracle adapter
+//!
+//! Decodes the `PriceUpdateV2` accounts the Pyth receiver program posts so
+//! markets can use Pyth feeds directly. Only fully verified updates are
+//! accepted.
+//!
+//! Pyth publishes a confidence interval with every price. A wide interval
+//! means publishers disagree, and pricing at the midpoint would let anyone
+//! liquidate healthy positions on noise, so prices whose interval is wider
+//! than the market's bound are rejected outright.
+
+use anchor_lang::prelude::*;
+use crate::constants::BPS;
+use crate::errors::MorphoError;
+use super::oracle::normalize_price;
+
+/// Pyth receiver program, owner of every `PriceUpdateV2` account
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+/// Anchor discriminator of the receiver's `PriceUpdateV2` account
+pub const PYTH_PRICE_UPDATE_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+// `PriceUpdateV2` is Borsh: discriminator, write authority, then a
+// `VerificationLevel` enum (`Partial { num_signatures: u8 }` = [0, n],
+// `Full` = [1]) that shifts the price message after it.
+const VERIFICATION_LEVEL_OFFSET: usize = 8 + 32;
+const VERIFICATION_FULL: u8 = 1;
+// Price message after a `Full` tag: feed id, price, conf, exponent,
+// publish time, previous publish time, EMA price and conf; then the slot
+// the update was posted in.
+const MESSAGE_START: usize = VERIFICATION_LEVEL_OFFSET + 1;
+const PRICE_OFFSET: usize = MESSAGE_START + 32;
+const CONF_OFFSET: usize = PRICE_OFFSET + 8;
+const EXPONENT_OFFSET: usize = CONF_OFFSET + 8;
+const PUBLISH_TIME_OFFSET: usize = EXPONENT_OFFSET + 4;
+const POSTED_SLOT_OFFSET: usize = PUBLISH_TIME_OFFSET + 4 * 8;
+const PRICE_UPDATE_LEN: usize = POSTED_SLOT_OFFSET + 8;
+
+/// Latest price of a Pyth feed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PythPrice {
+    /// Whole-token price scaled by 10^exponent
+    pub price: i64,
+    /// Confidence interval, in the same units as `price`
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    /// Slot the update was posted in
+    pub posted_slot: u64,
+}
+
+/// Decode a `PriceUpdateV2` account
+///
+/// The caller checks the account is owned by PYTH_RECEIVER_PROGRAM_ID.
+/// Partially verified updates are rejected.
+pub fn parse_pyth_price(data: &[u8]) -> Result<PythPrice> {
+    require!(
+        data.len() >= PRICE_UPDATE_LEN && data[..8] == PYTH_PRICE_UPDATE_DISCRIMINATOR,
+        MorphoError::OracleInvalidReturnData
+    );
+    require!(
+        data[VERIFICATION_LEVEL_OFFSET] == VERIFICATION_FULL,
+        MorphoError::OracleInvalidReturnData
+    );
+
+    Ok(PythPrice {
+        price: i64::from_le_bytes(data[PRICE_OFFSET..PRICE_OFFSET + 8].try_into().unwrap()),
+        conf: u64::from_le_bytes(data[CONF_OFFSET..CONF_OFFSET + 8].try_into().unwrap()),
+        exponent: i32::from_le_bytes(data[EXPONENT_OFFSET..EXPONENT_OFFSET + 4].try_into().unwrap()),
+        publish_time: i64::from_le_bytes(data[PUBLISH_TIME_OFFSET..PUBLISH_TIME_OFFSET + 8].try_into().unwrap()),
+        posted_slot: u64::from_le_bytes(data[POSTED_SLOT_OFFSET..POSTED_SLOT_OFFSET + 8].try_into().unwrap()),
+    })
+}
+
+/// Reject a price whose confidence interval is wider than `max_conf_bps`
+/// of the price
+pub fn check_pyth_confidence(price: &PythPrice, max_conf_bps: u64) -> Result<()> {
+    require!(price.price > 0, MorphoError::OracleInvalidPrice);
+    let conf_bps = (price.conf as u128) * (BPS as u128) / (price.price as u128);
+    if conf_bps > max_conf_bps as u128 {
+        msg!("Oracle confidence too wide: expected <= {} bps, actual {} bps", max_conf_bps, conf_bps);
+        return Err(MorphoError::OracleConfidenceTooWide.into());
+    }
+    Ok(())
+}
+
+/// Read a Pyth feed as a prescaled price
+///
+/// Rejects updates posted more than `heartbeat` slots ago and prices whose
+/// confidence interval exceeds `max_conf_bps`; bounds are checked by the
+/// caller like every other feed type.
+pub fn read_pyth_price(
+    oracle_account: &AccountInfo,
+    heartbeat: u64,
+    max_conf_bps: u64,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+) -> Result<u128> {
+    let price = parse_pyth_price(&oracle_account.try_borrow_data()?)?;
+
+    let age = Clock::get()?.slot.saturating_sub(price.posted_slot);
+    if age > heartbeat {
+        msg!("Oracle heartbeat missed: expected <= {} slots, actual age {} slots", heartbeat, age);
+        return Err(MorphoError::OracleHeartbeatMissed.into());
+    }
+    check_pyth_confidence(&price, max_conf_bps)?;
+
+    // Whole-token price of price * 10^exponent
+    normalize_price(price.price as u128, price.exponent, collateral_decimals, loan_decimals)
+}
@@ -0,0 +1,89 @@
+//! Risk oracle interface
+//!
+//! A risk oracle publishes the most debt a market can carry and still be
+//! liquidated safely (e.g. from an off-chain model of collateral liquidity).
+//! Borrows enforce it as a soft cap: total borrows may reach the published
+//! figure plus the market's owner-set `risk_cap_buffer_bps` headroom.
+//!
+//! Feed layout (matches `RiskOracleFeed`):
+//! [8 discriminator][1 bump][16 max_safe_debt][8 updated_at][32 admin]
+
+use anchor_lang::prelude::*;
+use crate::constants::{BPS, MAX_RISK_ORACLE_AGE};
+use crate::errors::MorphoError;
+use crate::state::Market;
+use crate::math::mul_div_down;
+
+/// Max-safe-debt feed written by a risk oracle publisher
+#[account]
+pub struct RiskOracleFeed {
+    pub bump: u8,
+    /// Max total debt (loan token units) the market can safely carry
+    pub max_safe_debt: u128,
+    /// Unix timestamp the figure was published at
+    pub updated_at: i64,
+    /// Publisher who can update the figure
+    pub admin: Pubkey,
+}
+
+impl RiskOracleFeed {
+    pub const SEED: &'static [u8] = b"risk_oracle";
+
+    pub fn space() -> usize {
+        8 + 1 + 16 + 8 + 32
+    }
+}
+
+/// Parse `(max_safe_debt, updated_at)` from risk oracle feed data
+pub fn parse_risk_oracle_feed(data: &[u8]) -> Result<(u128, i64)> {
+    // Skip discriminator (8 bytes) and bump (1 byte)
+    if data.len() < 33 {
+        return Err(MorphoError::RiskOracleInvalidData.into());
+    }
+
+    let max_safe_debt = u128::from_le_bytes(
+        data[9..25].try_into().map_err(|_| MorphoError::RiskOracleInvalidData)?
+    );
+    let updated_at = i64::from_le_bytes(
+        data[25..33].try_into().map_err(|_| MorphoError::RiskOracleInvalidData)?
+    );
+
+    Ok((max_safe_debt, updated_at))
+}
+
+/// Total borrows allowed for a published max safe debt plus `buffer_bps` headroom
+pub fn risk_debt_cap(max_safe_debt: u128, buffer_bps: u64) -> Result<u128> {
+    mul_div_down(max_safe_debt, (BPS + buffer_bps) as u128, BPS as u128)
+}
+
+/// Enforce the market's risk cap on its current total borrows
+///
+/// Call AFTER the borrow's effects. A no-op when the market has no risk
+/// oracle; otherwise the matching feed must be passed and fresh.
+pub fn enforce_risk_cap(
+    market: &Market,
+    risk_oracle: Option<&AccountInfo>,
+    now: i64,
+) -> Result<()> {
+    if market.risk_oracle == Pubkey::default() {
+        return Ok(());
+    }
+
+    let risk_oracle = risk_oracle.ok_or(MorphoError::InvalidRiskOracle)?;
+    require!(
+        risk_oracle.key() == market.risk_oracle,
+        MorphoError::InvalidRiskOracle
+    );
+
+    let (max_safe_debt, updated_at) = parse_risk_oracle_feed(&risk_oracle.try_borrow_data()?)?;
+    require!(
+        now.saturating_sub(updated_at) <= MAX_RISK_ORACLE_AGE,
+        MorphoError::RiskOracleStale
+    );
+
+    require!(
+        market.total_debt() <= risk_debt_cap(max_safe_debt, market.risk_cap_buffer_bps)?,
+        MorphoError::RiskCapExceeded
+    );
+    Ok(())
+}
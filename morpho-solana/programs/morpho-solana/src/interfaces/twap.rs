@@ -0,0 +1,166 @@
+//! Time-weighted average price oracle
+//!
+//! A `TwapOracle` keeps a ring of recent samples of an underlying feed,
+//! written by the permissionless `update_twap` crank. Used as a market's
+//! oracle it prices at the average over its window, so a single-slot spike
+//! in the underlying feed can't trigger liquidations on its own.
+//!
+//! Each sample's price is taken to hold until the next sample (or now, for
+//! the latest). Until the samples cover a full window the TWAP refuses to
+//! price; a TWAP whose crank stops goes stale like any other feed.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::errors::MorphoError;
+use crate::math::{checked_add, mul_div_down};
+
+/// Samples kept per TWAP oracle
+pub const TWAP_MAX_SAMPLES: usize = 16;
+
+/// Shortest averaging window (seconds)
+pub const MIN_TWAP_WINDOW: i64 = 60;
+
+/// Longest averaging window (seconds, 1 day)
+pub const MAX_TWAP_WINDOW: i64 = 86_400;
+
+/// One reading of the underlying feed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TwapSample {
+    /// Unix timestamp of the reading
+    pub timestamp: i64,
+    /// Prescaled price (see `oracle`)
+    pub price: u128,
+}
+
+/// Time-weighted average of an underlying price feed
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"twap_oracle", source, window (LE), collateral_decimals, loan_decimals]
+#[account]
+pub struct TwapOracle {
+    pub bump: u8,
+
+    /// Underlying Chainlink, Switchboard or Static Oracle feed
+    pub source: Pubkey,
+
+    /// Token decimals the source is read with (Chainlink answers are
+    /// prescaled with them)
+    pub collateral_decimals: u8,
+    pub loan_decimals: u8,
+
+    /// Seconds the price is averaged over
+    pub window: i64,
+
+    /// Slot of the latest sample, checked against the market's heartbeat
+    pub last_update_slot: u64,
+
+    /// Samples in use, and the index the next one is written to
+    pub sample_count: u8,
+    pub cursor: u8,
+    pub samples: [TwapSample; TWAP_MAX_SAMPLES],
+}
+
+impl TwapOracle {
+    pub const SEED: &'static [u8] = b"twap_oracle";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // source
+        1 +     // collateral_decimals
+        1 +     // loan_decimals
+        8 +     // window
+        8 +     // last_update_slot
+        1 +     // sample_count
+        1 +     // cursor
+        24 * TWAP_MAX_SAMPLES // samples
+    }
+
+    /// Shortest spacing between samples, so the ring always spans a window
+    pub fn min_update_interval(&self) -> i64 {
+        self.window / TWAP_MAX_SAMPLES as i64
+    }
+
+    /// Latest sample, if any
+    pub fn latest(&self) -> Option<&TwapSample> {
+        if self.sample_count == 0 {
+            return None;
+        }
+        let index = (self.cursor as usize + TWAP_MAX_SAMPLES - 1) % TWAP_MAX_SAMPLES;
+        Some(&self.samples[index])
+    }
+
+    /// Record a sample, overwriting the oldest once the ring is full
+    pub fn push(&mut self, sample: TwapSample, slot: u64) {
+        self.samples[self.cursor as usize] = sample;
+        self.cursor = ((self.cursor as usize + 1) % TWAP_MAX_SAMPLES) as u8;
+        self.sample_count = std::cmp::min(self.sample_count as usize + 1, TWAP_MAX_SAMPLES) as u8;
+        self.last_update_slot = slot;
+    }
+
+    /// Average price over the `window` seconds up to `now`
+    pub fn twap(&self, now: i64) -> Result<u128> {
+        let window_start = now.saturating_sub(self.window);
+        let mut end = now;
+        let mut twap = 0u128;
+
+        // Newest to oldest, each sample holding until the next one
+        for i in 1..=self.sample_count as usize {
+            let sample = &self.samples[(self.cursor as usize + TWAP_MAX_SAMPLES - i) % TWAP_MAX_SAMPLES];
+            let start = std::cmp::max(sample.timestamp, window_start);
+            if end > start {
+                let weighted = mul_div_down(sample.price, (end - start) as u128, self.window as u128)?;
+                twap = checked_add(twap, weighted)?;
+            }
+            if sample.timestamp <= window_start {
+                return Ok(twap);
+            }
+            end = sample.timestamp;
+        }
+
+        // History doesn't reach back a full window yet
+        Err(MorphoError::TwapWindowNotCovered.into())
+    }
+}
+
+/// Whether `oracle_account` holds a `TwapOracle`
+pub fn is_twap_oracle(oracle_account: &AccountInfo) -> Result<bool> {
+    Ok(oracle_account.owner == &crate::ID
+        && oracle_account.try_borrow_data()?.starts_with(TwapOracle::DISCRIMINATOR))
+}
+
+/// Read a TWAP oracle's average price
+///
+/// Rejects TWAPs whose latest sample is older than `heartbeat` slots;
+/// bounds are checked by the caller like every other feed type.
+pub fn read_twap_price(oracle_account: &AccountInfo, heartbeat: u64) -> Result<u128> {
+    let twap = TwapOracle::try_deserialize(&mut &oracle_account.try_borrow_data()?[..])?;
+    let clock = Clock::get()?;
+
+    let age = clock.slot.saturating_sub(twap.last_update_slot);
+    if age > heartbeat {
+        msg!("Oracle heartbeat missed: expected <= {} slots, actual age {} slots", heartbeat, age);
+        return Err(MorphoError::OracleHeartbeatMissed.into());
+    }
+
+    twap.twap(clock.unix_timestamp)
+}
+
+/// Derive TWAP oracle PDA
+pub fn derive_twap_oracle(
+    program_id: &Pubkey,
+    source: &Pubkey,
+    window: i64,
+    collateral_decimals: u8,
+    loan_decimals: u8,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PROGRAM_SEED_PREFIX,
+            TwapOracle::SEED,
+            source.as_ref(),
+            &window.to_le_bytes(),
+            &[collateral_decimals, loan_decimals],
+        ],
+        program_id,
+    )
+}
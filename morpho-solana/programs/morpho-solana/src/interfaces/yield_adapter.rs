@@ -0,0 +1,75 @@
+//! Yield adapter interface
+//!
+//! Adapters are external programs that put idle vault tokens to work
+//! (staking programs, conservative lending venues). The market PDA signs
+//! the CPI; the adapter moves tokens out of the vault on deposit and back
+//! into it on withdraw. Callers verify the vault balance delta afterwards,
+//! so a misbehaving adapter can't silently short the vault.
+//!
+//! Instruction data: [tag: u8][amount: u64 LE]
+//! Accounts: [market (signer), vault (writable), mint, token_program, ...extra]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+/// Move `amount` from the vault into the adapter
+pub const YIELD_ADAPTER_DEPOSIT: u8 = 0;
+
+/// Return `amount` from the adapter to the vault
+pub const YIELD_ADAPTER_WITHDRAW: u8 = 1;
+
+/// Accounts every adapter call receives, in order
+pub struct YieldAdapterAccounts<'a, 'info> {
+    pub adapter_program: &'a AccountInfo<'info>,
+    pub market: &'a AccountInfo<'info>,
+    pub vault: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+    /// Adapter-specific accounts (stake pool, reserve, ...) forwarded as-is
+    pub extra_accounts: &'a [AccountInfo<'info>],
+}
+
+/// CPI into a yield adapter with the market PDA as signer
+pub fn invoke_yield_adapter(
+    accounts: &YieldAdapterAccounts,
+    tag: u8,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(accounts.market.key(), true),
+        AccountMeta::new(accounts.vault.key(), false),
+        AccountMeta::new_readonly(accounts.mint.key(), false),
+        AccountMeta::new_readonly(accounts.token_program.key(), false),
+    ];
+    metas.extend(accounts.extra_accounts.iter().map(|a| {
+        if a.is_writable {
+            AccountMeta::new(a.key(), a.is_signer)
+        } else {
+            AccountMeta::new_readonly(a.key(), a.is_signer)
+        }
+    }));
+
+    let mut data = Vec::with_capacity(9);
+    data.push(tag);
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    let ix = Instruction {
+        program_id: accounts.adapter_program.key(),
+        accounts: metas,
+        data,
+    };
+
+    let mut infos = vec![
+        accounts.market.clone(),
+        accounts.vault.clone(),
+        accounts.mint.clone(),
+        accounts.token_program.clone(),
+    ];
+    infos.extend(accounts.extra_accounts.iter().cloned());
+    infos.push(accounts.adapter_program.clone());
+
+    invoke_signed(&ix, &infos, signer_seeds)?;
+    Ok(())
+}
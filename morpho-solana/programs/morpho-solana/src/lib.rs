@@ -10,8 +10,17 @@
 //! - Two-step ownership transfer
 //! - Protocol and per-market pause controls
 //! - Flash loans with lock mechanism
+//! - Opt-in yield routing for idle collateral and loan liquidity
+//! - Curator config proposals approved by the owner
+//! - Timelocked oracle migration for markets whose feed dies
+//! - Timelocked IRM migration, bounded by the rate change at switchover
 //! - Liquidation with LIF-based incentives and bad debt socialization
 
+// Anchor generates a CPI wrapper per instruction at the crate root, taking
+// the context plus every argument, so long instructions can't opt out one
+// by one
+#![allow(clippy::too_many_arguments)]
+
 use anchor_lang::prelude::*;
 
 pub mod constants;
@@ -21,11 +30,25 @@ pub mod math;
 pub mod state;
 pub mod interfaces;
 pub mod instructions;
+pub mod telemetry;
+pub mod audit;
+pub mod time;
+
+#[cfg(feature = "client")]
+pub mod client;
+
+#[cfg(not(target_os = "solana"))]
+pub mod test_id;
 
 use instructions::*;
 
+#[cfg(not(feature = "test-id"))]
 declare_id!("HW3AsZnx6An5KP5r17iaqSw3guFwbF1GMDr5a75Auf57");
 
+// Localnet ID whose keypair is checked in (see `test_id`)
+#[cfg(feature = "test-id")]
+declare_id!("7cPHtvtzZCFc44HZoZwMd2nemBayrfNof9MTFX6AjjEQ");
+
 #[program]
 pub mod morpho_solana {
     use super::*;
@@ -53,6 +76,22 @@ pub mod morpho_solana {
         instructions::admin::accept_ownership(ctx)
     }
 
+    pub fn heartbeat(ctx: Context<Heartbeat>) -> Result<()> {
+        instructions::admin::heartbeat(ctx)
+    }
+
+    pub fn set_recovery_key(
+        ctx: Context<SetRecoveryKey>,
+        recovery_key: Pubkey,
+        delay_days: u64,
+    ) -> Result<()> {
+        instructions::admin::set_recovery_key(ctx, recovery_key, delay_days)
+    }
+
+    pub fn claim_ownership_recovery(ctx: Context<ClaimOwnershipRecovery>) -> Result<()> {
+        instructions::admin::claim_ownership_recovery(ctx)
+    }
+
     pub fn set_fee_recipient(
         ctx: Context<SetFeeRecipient>,
         new_recipient: Pubkey,
@@ -64,6 +103,13 @@ pub mod morpho_solana {
         instructions::admin::set_protocol_paused(ctx, paused)
     }
 
+    pub fn set_flash_loans_enabled(
+        ctx: Context<SetFlashLoansEnabled>,
+        enabled: bool,
+    ) -> Result<()> {
+        instructions::admin::set_flash_loans_enabled(ctx, enabled)
+    }
+
     pub fn set_market_paused(
         ctx: Context<SetMarketPaused>,
         market_id: [u8; 32],
@@ -80,10 +126,185 @@ pub mod morpho_solana {
         instructions::admin::enable_irm(ctx, irm)
     }
 
+    pub fn set_lltv_bounds(
+        ctx: Context<SetLltvBounds>,
+        loan_mint: Pubkey,
+        min_lltv: u64,
+        max_lltv: u64,
+    ) -> Result<()> {
+        instructions::admin::set_lltv_bounds(ctx, loan_mint, min_lltv, max_lltv)
+    }
+
+    pub fn clear_lltv_bounds(ctx: Context<ClearLltvBounds>, loan_mint: Pubkey) -> Result<()> {
+        instructions::admin::clear_lltv_bounds(ctx, loan_mint)
+    }
+
+    pub fn set_token_badge(ctx: Context<SetTokenBadge>, mint: Pubkey, tier: state::TokenTier) -> Result<()> {
+        instructions::admin::set_token_badge(ctx, mint, tier)
+    }
+
+    pub fn clear_token_badge(ctx: Context<ClearTokenBadge>, mint: Pubkey) -> Result<()> {
+        instructions::admin::clear_token_badge(ctx, mint)
+    }
+
+    pub fn enable_yield_adapter(ctx: Context<EnableYieldAdapter>, adapter: Pubkey) -> Result<()> {
+        instructions::admin::enable_yield_adapter(ctx, adapter)
+    }
+
+    pub fn set_oracle_program(ctx: Context<SetOracleProgram>, oracle_program: Pubkey) -> Result<()> {
+        instructions::admin::set_oracle_program(ctx, oracle_program)
+    }
+
+    pub fn migrate_protocol_state(ctx: Context<MigrateProtocolState>) -> Result<()> {
+        instructions::admin::migrate_protocol_state(ctx)
+    }
+
+    pub fn migrate_market(ctx: Context<MigrateMarket>, market_id: [u8; 32]) -> Result<()> {
+        instructions::admin::migrate_market(ctx, market_id)
+    }
+
+    pub fn migrate_position(ctx: Context<MigratePosition>, market_id: [u8; 32], owner: Pubkey) -> Result<()> {
+        instructions::admin::migrate_position(ctx, market_id, owner)
+    }
+
+    pub fn admin_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AdminBatch<'info>>,
+        actions: Vec<AdminAction>,
+    ) -> Result<()> {
+        instructions::admin::admin_batch(ctx, actions)
+    }
+
     pub fn set_fee(ctx: Context<SetFee>, market_id: [u8; 32], fee: u64) -> Result<()> {
         instructions::admin::set_fee(ctx, market_id, fee)
     }
 
+    pub fn set_interest_pause(
+        ctx: Context<SetInterestPause>,
+        market_id: [u8; 32],
+        duration: i64,
+    ) -> Result<()> {
+        instructions::admin::set_interest_pause(ctx, market_id, duration)
+    }
+
+    pub fn set_supply_holding_period(
+        ctx: Context<SetSupplyHoldingPeriod>,
+        market_id: [u8; 32],
+        period: i64,
+    ) -> Result<()> {
+        instructions::admin::set_supply_holding_period(ctx, market_id, period)
+    }
+
+    pub fn set_bad_debt_rebate(
+        ctx: Context<SetBadDebtRebate>,
+        market_id: [u8; 32],
+        rebate_bps: u16,
+    ) -> Result<()> {
+        instructions::admin::set_bad_debt_rebate(ctx, market_id, rebate_bps)
+    }
+
+    pub fn set_supply_only(
+        ctx: Context<SetSupplyOnly>,
+        market_id: [u8; 32],
+        supply_only: bool,
+    ) -> Result<()> {
+        instructions::admin::set_supply_only(ctx, market_id, supply_only)
+    }
+
+    pub fn set_max_accrual_growth(
+        ctx: Context<SetMaxAccrualGrowth>,
+        market_id: [u8; 32],
+        max_growth_bps: u64,
+    ) -> Result<()> {
+        instructions::admin::set_max_accrual_growth(ctx, market_id, max_growth_bps)
+    }
+
+    pub fn set_share_price_floor(
+        ctx: Context<SetSharePriceFloor>,
+        market_id: [u8; 32],
+        floor: u128,
+    ) -> Result<()> {
+        instructions::admin::set_share_price_floor(ctx, market_id, floor)
+    }
+
+    pub fn set_withdraw_only(
+        ctx: Context<SetWithdrawOnly>,
+        market_id: [u8; 32],
+        withdraw_only: bool,
+    ) -> Result<()> {
+        instructions::admin::set_withdraw_only(ctx, market_id, withdraw_only)
+    }
+
+    pub fn set_max_oracle_age(
+        ctx: Context<SetMaxOracleAge>,
+        market_id: [u8; 32],
+        max_oracle_age_secs: u64,
+    ) -> Result<()> {
+        instructions::admin::set_max_oracle_age(ctx, market_id, max_oracle_age_secs)
+    }
+
+    pub fn set_oracle_conf_bound(
+        ctx: Context<SetOracleConfBound>,
+        market_id: [u8; 32],
+        max_oracle_conf_bps: u64,
+    ) -> Result<()> {
+        instructions::admin::set_oracle_conf_bound(ctx, market_id, max_oracle_conf_bps)
+    }
+
+    pub fn set_switchboard_normalization(
+        ctx: Context<SetSwitchboardNormalization>,
+        market_id: [u8; 32],
+        normalize: bool,
+    ) -> Result<()> {
+        instructions::admin::set_switchboard_normalization(ctx, market_id, normalize)
+    }
+
+    pub fn set_fallback_oracle(
+        ctx: Context<SetFallbackOracle>,
+        market_id: [u8; 32],
+        fallback_oracle: Pubkey,
+    ) -> Result<()> {
+        instructions::admin::set_fallback_oracle(ctx, market_id, fallback_oracle)
+    }
+
+    pub fn set_borrow_lltv_buffer(
+        ctx: Context<SetBorrowLltvBuffer>,
+        market_id: [u8; 32],
+        buffer: u64,
+    ) -> Result<()> {
+        instructions::admin::set_borrow_lltv_buffer(ctx, market_id, buffer)
+    }
+
+    pub fn set_protocol_borrow_lltv_buffer(
+        ctx: Context<SetProtocolBorrowLltvBuffer>,
+        buffer: u64,
+    ) -> Result<()> {
+        instructions::admin::set_protocol_borrow_lltv_buffer(ctx, buffer)
+    }
+
+    pub fn set_risk_oracle(
+        ctx: Context<SetRiskOracle>,
+        market_id: [u8; 32],
+        risk_oracle: Pubkey,
+        buffer_bps: u64,
+    ) -> Result<()> {
+        instructions::admin::set_risk_oracle(ctx, market_id, risk_oracle, buffer_bps)
+    }
+
+    pub fn force_unlock_flash_loan(
+        ctx: Context<ForceUnlockFlashLoan>,
+        market_id: [u8; 32],
+    ) -> Result<()> {
+        instructions::admin::force_unlock_flash_loan(ctx, market_id)
+    }
+
+    pub fn sweep_surplus(ctx: Context<SweepSurplus>, market_id: [u8; 32]) -> Result<()> {
+        instructions::admin::sweep_surplus(ctx, market_id)
+    }
+
+    pub fn rescue_tokens(ctx: Context<RescueTokens>, market_id: [u8; 32]) -> Result<()> {
+        instructions::admin::rescue_tokens(ctx, market_id)
+    }
+
     // =========================================================================
     // Market Instructions
     // =========================================================================
@@ -95,6 +316,8 @@ pub mod morpho_solana {
         oracle_key: Pubkey,
         irm_key: Pubkey,
         lltv: u64,
+        oracle_heartbeat: u64,
+        max_oracle_age_secs: u64,
     ) -> Result<()> {
         instructions::market::create_market(
             ctx,
@@ -103,9 +326,35 @@ pub mod morpho_solana {
             oracle_key,
             irm_key,
             lltv,
+            oracle_heartbeat,
+            max_oracle_age_secs,
         )
     }
 
+    pub fn close_empty_market(ctx: Context<CloseEmptyMarket>, market_id: [u8; 32]) -> Result<()> {
+        instructions::market::close_empty_market(ctx, market_id)
+    }
+
+    pub fn create_market_metadata(
+        ctx: Context<CreateMarketMetadata>,
+        market_id: [u8; 32],
+        name: String,
+        description_uri: String,
+        curator: Pubkey,
+    ) -> Result<()> {
+        instructions::market_metadata::create_market_metadata(ctx, market_id, name, description_uri, curator)
+    }
+
+    pub fn update_market_metadata(
+        ctx: Context<UpdateMarketMetadata>,
+        market_id: [u8; 32],
+        name: String,
+        description_uri: String,
+        curator: Pubkey,
+    ) -> Result<()> {
+        instructions::market_metadata::update_market_metadata(ctx, market_id, name, description_uri, curator)
+    }
+
     // =========================================================================
     // Position Instructions
     // =========================================================================
@@ -118,6 +367,10 @@ pub mod morpho_solana {
         instructions::position::close_position(ctx, market_id)
     }
 
+    pub fn merge_positions(ctx: Context<MergePositions>, market_id: [u8; 32]) -> Result<()> {
+        instructions::position::merge_positions(ctx, market_id)
+    }
+
     // =========================================================================
     // Supply Instructions
     // =========================================================================
@@ -131,8 +384,41 @@ pub mod morpho_solana {
         instructions::supply::supply(ctx, market_id, assets, min_shares)
     }
 
-    pub fn withdraw(
-        ctx: Context<Withdraw>,
+    pub fn exempt_from_holding_period(ctx: Context<ExemptFromHoldingPeriod>, account: Pubkey) -> Result<()> {
+        instructions::supply::exempt_from_holding_period(ctx, account)
+    }
+
+    pub fn revoke_holding_period_exemption(
+        ctx: Context<RevokeHoldingPeriodExemption>,
+        account: Pubkey,
+    ) -> Result<()> {
+        instructions::supply::revoke_holding_period_exemption(ctx, account)
+    }
+
+    pub fn seed_market(
+        ctx: Context<SeedMarket>,
+        market_id: [u8; 32],
+        assets: u128,
+        lock_days: u32,
+    ) -> Result<()> {
+        instructions::supply::seed_market(ctx, market_id, assets, lock_days)
+    }
+
+    pub fn lock_supply_shares(
+        ctx: Context<LockSupplyShares>,
+        market_id: [u8; 32],
+        shares: u128,
+        duration: i64,
+    ) -> Result<()> {
+        instructions::supply::lock_supply_shares(ctx, market_id, shares, duration)
+    }
+
+    pub fn unlock_supply_shares(ctx: Context<UnlockSupplyShares>, market_id: [u8; 32]) -> Result<()> {
+        instructions::supply::unlock_supply_shares(ctx, market_id)
+    }
+
+    pub fn withdraw<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>,
         market_id: [u8; 32],
         assets: u128,
         shares: u128,
@@ -164,8 +450,8 @@ pub mod morpho_solana {
     // Borrow Instructions
     // =========================================================================
 
-    pub fn borrow(
-        ctx: Context<Borrow>,
+    pub fn borrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Borrow<'info>>,
         market_id: [u8; 32],
         assets: u128,
         max_shares: u128,
@@ -182,6 +468,29 @@ pub mod morpho_solana {
         instructions::borrow::repay(ctx, market_id, assets, shares)
     }
 
+    pub fn adjust_position<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AdjustPosition<'info>>,
+        market_id: [u8; 32],
+        adjustment: PositionAdjustment,
+        expected_version: Option<u16>,
+    ) -> Result<()> {
+        instructions::adjust::adjust_position(ctx, market_id, adjustment, expected_version)
+    }
+
+    pub fn dry_run_bundle(
+        ctx: Context<DryRunBundle>,
+        market_id: [u8; 32],
+        actions: Vec<BundleAction>,
+    ) -> Result<VersionedView> {
+        instructions::dry_run::dry_run_bundle(ctx, market_id, actions)
+    }
+
+    pub fn get_exposure_report<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetExposureReport<'info>>,
+    ) -> Result<VersionedView> {
+        instructions::exposure::get_exposure_report(ctx)
+    }
+
     // =========================================================================
     // Liquidation Instructions
     // =========================================================================
@@ -190,8 +499,13 @@ pub mod morpho_solana {
         ctx: Context<Liquidate>,
         market_id: [u8; 32],
         seized_assets: u128,
+        operator_share_bps: u64,
     ) -> Result<()> {
-        instructions::liquidate::liquidate(ctx, market_id, seized_assets)
+        instructions::liquidate::liquidate(ctx, market_id, seized_assets, operator_share_bps)
+    }
+
+    pub fn settle_bad_debt(ctx: Context<SettleBadDebt>, market_id: [u8; 32]) -> Result<()> {
+        instructions::liquidate::settle_bad_debt(ctx, market_id)
     }
 
     // =========================================================================
@@ -202,24 +516,350 @@ pub mod morpho_solana {
         ctx: Context<FlashLoanStart>,
         market_id: [u8; 32],
         amount: u128,
+        expected_version: Option<u16>,
     ) -> Result<()> {
-        instructions::flash_loan::flash_loan(ctx, market_id, amount)
+        instructions::flash_loan::flash_loan(ctx, market_id, amount, expected_version)
     }
 
     pub fn flash_loan_start(
         ctx: Context<FlashLoanStart>,
         market_id: [u8; 32],
         amount: u128,
+        expected_version: Option<u16>,
     ) -> Result<()> {
-        instructions::flash_loan::flash_loan_start(ctx, market_id, amount)
+        instructions::flash_loan::flash_loan_start(ctx, market_id, amount, expected_version)
     }
 
     pub fn flash_loan_end(
         ctx: Context<FlashLoanEnd>,
         market_id: [u8; 32],
         borrowed_amount: u128,
+        expected_version: Option<u16>,
+    ) -> Result<()> {
+        instructions::flash_loan::flash_loan_end(ctx, market_id, borrowed_amount, expected_version)
+    }
+
+    pub fn flash_loan_multi<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FlashLoanMulti<'info>>,
+        amount: u128,
+        expected_version: Option<u16>,
+    ) -> Result<()> {
+        instructions::flash_loan::flash_loan_multi(ctx, amount, expected_version)
+    }
+
+    pub fn flash_loan_multi_end<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FlashLoanMultiEnd<'info>>,
+        expected_version: Option<u16>,
+    ) -> Result<()> {
+        instructions::flash_loan::flash_loan_multi_end(ctx, expected_version)
+    }
+
+    pub fn set_flash_loan_allowlist_mode(
+        ctx: Context<SetFlashLoanAllowlistMode>,
+        market_id: [u8; 32],
+        allowlist_only: bool,
+    ) -> Result<()> {
+        instructions::flash_loan::set_flash_loan_allowlist_mode(ctx, market_id, allowlist_only)
+    }
+
+    pub fn allow_flash_loan_borrower(
+        ctx: Context<AllowFlashLoanBorrower>,
+        market_id: [u8; 32],
+        borrower: Pubkey,
+    ) -> Result<()> {
+        instructions::flash_loan::allow_flash_loan_borrower(ctx, market_id, borrower)
+    }
+
+    pub fn disallow_flash_loan_borrower(
+        ctx: Context<DisallowFlashLoanBorrower>,
+        market_id: [u8; 32],
+        borrower: Pubkey,
+    ) -> Result<()> {
+        instructions::flash_loan::disallow_flash_loan_borrower(ctx, market_id, borrower)
+    }
+
+    // =========================================================================
+    // Yield Routing Instructions
+    // =========================================================================
+
+    pub fn set_collateral_yield_adapter(
+        ctx: Context<SetCollateralYieldAdapter>,
+        market_id: [u8; 32],
+        adapter: Pubkey,
+        max_staked_bps: u64,
+    ) -> Result<()> {
+        instructions::yield_adapter::set_collateral_yield_adapter(ctx, market_id, adapter, max_staked_bps)
+    }
+
+    pub fn stake_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, StakeCollateral<'info>>,
+        market_id: [u8; 32],
+        amount: u128,
+    ) -> Result<()> {
+        instructions::yield_adapter::stake_collateral(ctx, market_id, amount)
+    }
+
+    pub fn unstake_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnstakeCollateral<'info>>,
+        market_id: [u8; 32],
+        amount: u128,
+    ) -> Result<()> {
+        instructions::yield_adapter::unstake_collateral(ctx, market_id, amount)
+    }
+
+    pub fn set_loan_yield_adapter(
+        ctx: Context<SetLoanYieldAdapter>,
+        market_id: [u8; 32],
+        adapter: Pubkey,
+        max_deployed_bps: u64,
+    ) -> Result<()> {
+        instructions::yield_adapter::set_loan_yield_adapter(ctx, market_id, adapter, max_deployed_bps)
+    }
+
+    pub fn deploy_loan_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DeployLoanLiquidity<'info>>,
+        market_id: [u8; 32],
+        amount: u128,
+    ) -> Result<()> {
+        instructions::yield_adapter::deploy_loan_liquidity(ctx, market_id, amount)
+    }
+
+    pub fn recall_loan_liquidity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecallLoanLiquidity<'info>>,
+        market_id: [u8; 32],
+        amount: u128,
+    ) -> Result<()> {
+        instructions::yield_adapter::recall_loan_liquidity(ctx, market_id, amount)
+    }
+
+    // =========================================================================
+    // Governance Instructions
+    // =========================================================================
+
+    pub fn set_market_curator(
+        ctx: Context<SetMarketCurator>,
+        market_id: [u8; 32],
+        curator: Pubkey,
+    ) -> Result<()> {
+        instructions::proposal::set_market_curator(ctx, market_id, curator)
+    }
+
+    pub fn propose_market_config(
+        ctx: Context<ProposeMarketConfig>,
+        market_id: [u8; 32],
+        config: state::MarketConfigUpdate,
+    ) -> Result<()> {
+        instructions::proposal::propose_market_config(ctx, market_id, config)
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>, market_id: [u8; 32]) -> Result<()> {
+        instructions::proposal::approve_proposal(ctx, market_id)
+    }
+
+    pub fn cancel_proposal(ctx: Context<CancelProposal>, market_id: [u8; 32]) -> Result<()> {
+        instructions::proposal::cancel_proposal(ctx, market_id)
+    }
+
+    pub fn propose_oracle_update(
+        ctx: Context<ProposeOracleUpdate>,
+        market_id: [u8; 32],
+        new_oracle: Pubkey,
+    ) -> Result<()> {
+        instructions::proposal::propose_oracle_update(ctx, market_id, new_oracle)
+    }
+
+    pub fn execute_oracle_update(ctx: Context<ExecuteOracleUpdate>, market_id: [u8; 32]) -> Result<()> {
+        instructions::proposal::execute_oracle_update(ctx, market_id)
+    }
+
+    pub fn cancel_oracle_update(ctx: Context<CancelOracleUpdate>, market_id: [u8; 32]) -> Result<()> {
+        instructions::proposal::cancel_oracle_update(ctx, market_id)
+    }
+
+    pub fn propose_irm_update(
+        ctx: Context<ProposeIrmUpdate>,
+        market_id: [u8; 32],
+        new_irm: Pubkey,
+    ) -> Result<()> {
+        instructions::proposal::propose_irm_update(ctx, market_id, new_irm)
+    }
+
+    pub fn set_market_irm<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SetMarketIrm<'info>>,
+        market_id: [u8; 32],
     ) -> Result<()> {
-        instructions::flash_loan::flash_loan_end(ctx, market_id, borrowed_amount)
+        instructions::proposal::set_market_irm(ctx, market_id)
+    }
+
+    pub fn cancel_irm_update(ctx: Context<CancelIrmUpdate>, market_id: [u8; 32]) -> Result<()> {
+        instructions::proposal::cancel_irm_update(ctx, market_id)
+    }
+
+    // =========================================================================
+    // Credit Line Instructions
+    // =========================================================================
+
+    pub fn set_credit_line(
+        ctx: Context<SetCreditLine>,
+        market_id: [u8; 32],
+        borrower: Pubkey,
+        limit: u128,
+    ) -> Result<()> {
+        instructions::credit_line::set_credit_line(ctx, market_id, borrower, limit)
+    }
+
+    pub fn draw_credit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DrawCredit<'info>>,
+        market_id: [u8; 32],
+        assets: u128,
+        max_shares: u128,
+    ) -> Result<()> {
+        instructions::credit_line::draw_credit(ctx, market_id, assets, max_shares)
+    }
+
+    pub fn repay_credit(
+        ctx: Context<RepayCredit>,
+        market_id: [u8; 32],
+        assets: u128,
+        shares: u128,
+    ) -> Result<()> {
+        instructions::credit_line::repay_credit(ctx, market_id, assets, shares)
+    }
+
+    pub fn close_credit_line(ctx: Context<CloseCreditLine>, market_id: [u8; 32]) -> Result<()> {
+        instructions::credit_line::close_credit_line(ctx, market_id)
+    }
+
+    // =========================================================================
+    // Oracle Divergence Guard
+    // =========================================================================
+
+    pub fn set_divergence_guard(
+        ctx: Context<SetDivergenceGuard>,
+        market_id: [u8; 32],
+        reference_feeds: [Pubkey; 2],
+        max_divergence_bps: u64,
+    ) -> Result<()> {
+        instructions::divergence::set_divergence_guard(ctx, market_id, reference_feeds, max_divergence_bps)
+    }
+
+    pub fn flag_oracle_divergence(
+        ctx: Context<FlagOracleDivergence>,
+        market_id: [u8; 32],
+    ) -> Result<()> {
+        instructions::divergence::flag_oracle_divergence(ctx, market_id)
+    }
+
+    pub fn clear_oracle_divergence(
+        ctx: Context<ClearOracleDivergence>,
+        market_id: [u8; 32],
+    ) -> Result<()> {
+        instructions::divergence::clear_oracle_divergence(ctx, market_id)
+    }
+
+    pub fn set_liquidation_reference(
+        ctx: Context<SetLiquidationReference>,
+        market_id: [u8; 32],
+        max_divergence_bps: u64,
+    ) -> Result<()> {
+        instructions::divergence::set_liquidation_reference(ctx, market_id, max_divergence_bps)
+    }
+
+    pub fn clear_liquidation_reference(
+        ctx: Context<ClearLiquidationReference>,
+        market_id: [u8; 32],
+    ) -> Result<()> {
+        instructions::divergence::clear_liquidation_reference(ctx, market_id)
+    }
+
+    // =========================================================================
+    // Reputation Boost
+    // =========================================================================
+
+    pub fn set_reputation_boost(
+        ctx: Context<SetReputationBoost>,
+        market_id: [u8; 32],
+        boost_bps: u64,
+        min_repaid: u128,
+        min_age: i64,
+    ) -> Result<()> {
+        instructions::reputation::set_reputation_boost(ctx, market_id, boost_bps, min_repaid, min_age)
+    }
+
+    // =========================================================================
+    // Liquidation Insurance
+    // =========================================================================
+
+    pub fn set_insurance_params(
+        ctx: Context<SetInsuranceParams>,
+        market_id: [u8; 32],
+        premium_bps: u64,
+        coverage_bps: u64,
+    ) -> Result<()> {
+        instructions::insurance::set_insurance_params(ctx, market_id, premium_bps, coverage_bps)
+    }
+
+    pub fn set_position_insurance(
+        ctx: Context<SetPositionInsurance>,
+        market_id: [u8; 32],
+        insured: bool,
+    ) -> Result<()> {
+        instructions::insurance::set_position_insurance(ctx, market_id, insured)
+    }
+
+    pub fn charge_insurance_premium(
+        ctx: Context<ChargeInsurancePremium>,
+        market_id: [u8; 32],
+    ) -> Result<()> {
+        instructions::insurance::charge_insurance_premium_ix(ctx, market_id)
+    }
+
+    pub fn claim_insurance(ctx: Context<ClaimInsurance>, market_id: [u8; 32]) -> Result<()> {
+        instructions::insurance::claim_insurance(ctx, market_id)
+    }
+
+    // =========================================================================
+    // Stable-Rate Borrowing
+    // =========================================================================
+
+    pub fn set_stable_rate_params(
+        ctx: Context<SetStableRateParams>,
+        market_id: [u8; 32],
+        enabled: bool,
+        spread_bps: u64,
+        rebalance_utilization_bps: u64,
+    ) -> Result<()> {
+        instructions::stable_rate::set_stable_rate_params(
+            ctx,
+            market_id,
+            enabled,
+            spread_bps,
+            rebalance_utilization_bps,
+        )
+    }
+
+    pub fn borrow_stable<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BorrowStable<'info>>,
+        market_id: [u8; 32],
+        assets: u128,
+        max_rate: u128,
+    ) -> Result<()> {
+        instructions::stable_rate::borrow_stable(ctx, market_id, assets, max_rate)
+    }
+
+    pub fn repay_stable(
+        ctx: Context<RepayStable>,
+        market_id: [u8; 32],
+        assets: u128,
+    ) -> Result<()> {
+        instructions::stable_rate::repay_stable(ctx, market_id, assets)
+    }
+
+    pub fn rebalance_stable_rate(
+        ctx: Context<RebalanceStableRate>,
+        market_id: [u8; 32],
+    ) -> Result<()> {
+        instructions::stable_rate::rebalance_stable_rate(ctx, market_id)
     }
 
     // =========================================================================
@@ -230,6 +870,20 @@ pub mod morpho_solana {
         instructions::utils::accrue_interest_ix(ctx, market_id)
     }
 
+    pub fn get_accrual_priorities<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetAccrualPriorities>,
+    ) -> Result<VersionedView> {
+        instructions::utils::get_accrual_priorities(ctx)
+    }
+
+    pub fn reconcile_collateral(ctx: Context<ReconcileCollateral>, market_id: [u8; 32]) -> Result<VersionedView> {
+        instructions::utils::reconcile_collateral(ctx, market_id)
+    }
+
+    pub fn get_protocol_status(ctx: Context<GetProtocolStatus>) -> Result<VersionedView> {
+        instructions::utils::get_protocol_status(ctx)
+    }
+
     pub fn set_authorization(
         ctx: Context<SetAuthorization>,
         is_authorized: bool,
@@ -245,4 +899,203 @@ pub mod morpho_solana {
     pub fn claim_fees(ctx: Context<ClaimFees>, market_id: [u8; 32]) -> Result<()> {
         instructions::utils::claim_fees(ctx, market_id)
     }
+
+    // =========================================================================
+    // Adaptive Curve IRM
+    // =========================================================================
+
+    pub fn init_adaptive_curve_irm(ctx: Context<InitAdaptiveCurveIrm>, market_id: [u8; 32]) -> Result<()> {
+        instructions::adaptive_curve_irm::init_adaptive_curve_irm(ctx, market_id)
+    }
+
+    // =========================================================================
+    // TWAP Oracles
+    // =========================================================================
+
+    pub fn create_twap_oracle(
+        ctx: Context<CreateTwapOracle>,
+        window: i64,
+        collateral_decimals: u8,
+        loan_decimals: u8,
+    ) -> Result<()> {
+        instructions::twap::create_twap_oracle(ctx, window, collateral_decimals, loan_decimals)
+    }
+
+    pub fn update_twap(ctx: Context<UpdateTwap>) -> Result<()> {
+        instructions::twap::update_twap(ctx)
+    }
+
+    // =========================================================================
+    // CLMM TWAP Oracles
+    // =========================================================================
+
+    pub fn create_clmm_twap_oracle(
+        ctx: Context<CreateClmmTwapOracle>,
+        min_window: i64,
+        collateral_is_token0: bool,
+    ) -> Result<()> {
+        instructions::clmm_twap::create_clmm_twap_oracle(ctx, min_window, collateral_is_token0)
+    }
+
+    pub fn update_clmm_twap(ctx: Context<UpdateClmmTwap>) -> Result<()> {
+        instructions::clmm_twap::update_clmm_twap(ctx)
+    }
+
+    // =========================================================================
+    // EWMA Oracles
+    // =========================================================================
+
+    pub fn set_price_smoothing(
+        ctx: Context<SetPriceSmoothing>,
+        market_id: [u8; 32],
+        smoothing_bps: u64,
+    ) -> Result<()> {
+        instructions::ewma::set_price_smoothing(ctx, market_id, smoothing_bps)
+    }
+
+    pub fn update_ewma(ctx: Context<UpdateEwma>) -> Result<()> {
+        instructions::ewma::update_ewma(ctx)
+    }
+
+    // =========================================================================
+    // Composite Oracles
+    // =========================================================================
+
+    pub fn create_composite_oracle(
+        ctx: Context<CreateCompositeOracle>,
+        collateral_decimals: u8,
+        loan_decimals: u8,
+    ) -> Result<()> {
+        instructions::composite::create_composite_oracle(ctx, collateral_decimals, loan_decimals)
+    }
+
+    pub fn update_composite(ctx: Context<UpdateComposite>) -> Result<()> {
+        instructions::composite::update_composite(ctx)
+    }
+
+    // =========================================================================
+    // Price Relay
+    // =========================================================================
+
+    pub fn set_price_relayer(
+        ctx: Context<SetPriceRelayer>,
+        market_id: [u8; 32],
+        relayer: Pubkey,
+        max_deviation_bps: u64,
+    ) -> Result<()> {
+        instructions::price_relay::set_price_relayer(ctx, market_id, relayer, max_deviation_bps)
+    }
+
+    pub fn push_relayed_price(
+        ctx: Context<PushRelayedPrice>,
+        market_id: [u8; 32],
+        price: u128,
+    ) -> Result<()> {
+        instructions::price_relay::push_relayed_price(ctx, market_id, price)
+    }
+
+    // =========================================================================
+    // Devnet Drills
+    // =========================================================================
+
+    #[cfg(feature = "devnet")]
+    pub fn simulate_oracle_failure(
+        ctx: Context<SimulateOracleFailure>,
+        market_id: [u8; 32],
+        mode: interfaces::OracleFailureMode,
+    ) -> Result<()> {
+        instructions::devnet::simulate_oracle_failure(ctx, market_id, mode)
+    }
+
+    #[cfg(feature = "devnet")]
+    pub fn set_clock_override(ctx: Context<SetClockOverride>, unix_timestamp: i64) -> Result<()> {
+        instructions::devnet::set_clock_override(ctx, unix_timestamp)
+    }
+
+    #[cfg(feature = "devnet")]
+    pub fn clear_clock_override(ctx: Context<ClearClockOverride>) -> Result<()> {
+        instructions::devnet::clear_clock_override(ctx)
+    }
+
+    // =========================================================================
+    // Multi-Collateral Markets (experimental)
+    // =========================================================================
+
+    #[cfg(feature = "multi-collateral")]
+    pub fn create_mc_market(
+        ctx: Context<CreateMcMarket>,
+        loan_mint_key: Pubkey,
+        irm_key: Pubkey,
+        nonce: u64,
+    ) -> Result<()> {
+        instructions::multi_collateral::create_mc_market(ctx, loan_mint_key, irm_key, nonce)
+    }
+
+    #[cfg(feature = "multi-collateral")]
+    pub fn add_mc_collateral(
+        ctx: Context<AddMcCollateral>,
+        market_id: [u8; 32],
+        lltv: u64,
+    ) -> Result<()> {
+        instructions::multi_collateral::add_mc_collateral(ctx, market_id, lltv)
+    }
+
+    #[cfg(feature = "multi-collateral")]
+    pub fn create_mc_position(ctx: Context<CreateMcPosition>, market_id: [u8; 32]) -> Result<()> {
+        instructions::multi_collateral::create_mc_position(ctx, market_id)
+    }
+
+    #[cfg(feature = "multi-collateral")]
+    pub fn mc_supply(ctx: Context<McLoanTransfer>, market_id: [u8; 32], assets: u128) -> Result<()> {
+        instructions::multi_collateral::mc_supply(ctx, market_id, assets)
+    }
+
+    #[cfg(feature = "multi-collateral")]
+    pub fn mc_withdraw(ctx: Context<McLoanTransfer>, market_id: [u8; 32], shares: u128) -> Result<()> {
+        instructions::multi_collateral::mc_withdraw(ctx, market_id, shares)
+    }
+
+    #[cfg(feature = "multi-collateral")]
+    pub fn mc_borrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, McLoanTransfer<'info>>,
+        market_id: [u8; 32],
+        assets: u128,
+    ) -> Result<()> {
+        instructions::multi_collateral::mc_borrow(ctx, market_id, assets)
+    }
+
+    #[cfg(feature = "multi-collateral")]
+    pub fn mc_repay(ctx: Context<McLoanTransfer>, market_id: [u8; 32], shares: u128) -> Result<()> {
+        instructions::multi_collateral::mc_repay(ctx, market_id, shares)
+    }
+
+    #[cfg(feature = "multi-collateral")]
+    pub fn mc_supply_collateral(
+        ctx: Context<McCollateralTransfer>,
+        market_id: [u8; 32],
+        index: u8,
+        amount: u128,
+    ) -> Result<()> {
+        instructions::multi_collateral::mc_supply_collateral(ctx, market_id, index, amount)
+    }
+
+    #[cfg(feature = "multi-collateral")]
+    pub fn mc_withdraw_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, McCollateralTransfer<'info>>,
+        market_id: [u8; 32],
+        index: u8,
+        amount: u128,
+    ) -> Result<()> {
+        instructions::multi_collateral::mc_withdraw_collateral(ctx, market_id, index, amount)
+    }
+
+    #[cfg(feature = "multi-collateral")]
+    pub fn mc_liquidate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, McLiquidate<'info>>,
+        market_id: [u8; 32],
+        index: u8,
+        assets: u128,
+    ) -> Result<()> {
+        instructions::multi_collateral::mc_liquidate(ctx, market_id, index, assets)
+    }
 }
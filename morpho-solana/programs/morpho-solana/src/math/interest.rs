@@ -2,12 +2,19 @@
 //! 
 //! Interest is compounded using Taylor series approximation.
 //! Fee shares are tracked separately for later claiming.
+//!
+//! Rate sampling follows Morpho Blue: the IRM is queried with the totals as
+//! they stood at `last_update`, and that single rate applies to the whole
+//! elapsed period. Every instruction that changes totals (or the fee) accrues
+//! first, so the pre-accrual totals are exactly the last-update state.
 
 use anchor_lang::prelude::*;
-use crate::constants::BPS;
-use crate::state::Market;
+use crate::constants::{BPS, MAX_IRM_FAILURES};
+use crate::state::{Market, Position};
+use crate::events::{SharePriceCheckpoint, AbnormalAccrualCapped, IrmFailureWithdrawOnly, IrmQueryFailed};
+use crate::interfaces::{get_market_borrow_rate, is_irm_fault, peek_adaptive_curve_rate};
 use super::safe_math::{checked_add, checked_sub};
-use super::wad::{w_taylor_compounded, wad_mul_down, mul_div_down};
+use super::wad::{w_taylor_compounded, wad_mul_down, wad_mul_up, mul_div_down};
 use super::shares::to_shares_down;
 
 /// Result of interest accrual
@@ -17,6 +24,8 @@ pub struct AccrualResult {
     pub interest: u128,
     /// Fee shares minted (if fee > 0)
     pub fee_shares: u128,
+    /// Interest dropped by the market's accrual growth cap
+    pub capped: u128,
 }
 
 /// Accrue interest on a market
@@ -37,31 +46,52 @@ pub fn accrue_interest_on_market(
 ) -> Result<AccrualResult> {
     // No time has passed
     if current_time <= market.last_update {
-        return Ok(AccrualResult { interest: 0, fee_shares: 0 });
+        return Ok(AccrualResult { interest: 0, fee_shares: 0, capped: 0 });
     }
     
+    // A rate pause zeroes the rate for the part of the period it covers
+    if market.interest_paused_until > market.last_update {
+        market.last_update = std::cmp::min(current_time, market.interest_paused_until);
+    }
+
     let elapsed = (current_time - market.last_update) as u128;
     
     // No borrows = no interest
-    if elapsed == 0 || market.total_borrow_assets == 0 {
+    if elapsed == 0 || market.total_debt() == 0 {
         market.last_update = current_time;
-        return Ok(AccrualResult { interest: 0, fee_shares: 0 });
+        return Ok(AccrualResult { interest: 0, fee_shares: 0, capped: 0 });
     }
     
     // Calculate interest using Taylor expansion
     let interest_factor = w_taylor_compounded(borrow_rate, elapsed)?;
     
     // Interest amount = borrow * factor / WAD
-    let interest = wad_mul_down(market.total_borrow_assets, interest_factor)?;
+    let variable_interest = wad_mul_down(market.total_borrow_assets, interest_factor)?;
+
+    // Stable debt compounds at the average of the positions' locked rates
+    let stable_interest = wad_mul_down(
+        market.total_stable_borrow_assets,
+        w_taylor_compounded(market.avg_stable_rate, elapsed)?,
+    )?;
+
+    // Circuit breaker: no single accrual may grow a debt pool past the cap
+    let (variable_interest, variable_capped) =
+        cap_accrual_growth(variable_interest, market.total_borrow_assets, market.max_accrual_growth_bps)?;
+    let (stable_interest, stable_capped) =
+        cap_accrual_growth(stable_interest, market.total_stable_borrow_assets, market.max_accrual_growth_bps)?;
+    let capped = checked_add(variable_capped, stable_capped)?;
+    let interest = checked_add(variable_interest, stable_interest)?;
     
     if interest == 0 {
         market.last_update = current_time;
-        return Ok(AccrualResult { interest: 0, fee_shares: 0 });
+        return Ok(AccrualResult { interest: 0, fee_shares: 0, capped });
     }
     
     // Update totals (interest goes to both supply and borrow)
-    market.total_borrow_assets = checked_add(market.total_borrow_assets, interest)?;
+    market.total_borrow_assets = checked_add(market.total_borrow_assets, variable_interest)?;
+    market.total_stable_borrow_assets = checked_add(market.total_stable_borrow_assets, stable_interest)?;
     market.total_supply_assets = checked_add(market.total_supply_assets, interest)?;
+    market.total_interest_accrued = checked_add(market.total_interest_accrued, interest)?;
     
     // Calculate and track fee shares
     let mut fee_shares = 0u128;
@@ -73,6 +103,8 @@ pub fn accrue_interest_on_market(
         )?;
         
         if fee_amount > 0 {
+            market.total_fees_accrued = checked_add(market.total_fees_accrued, fee_amount)?;
+
             // Fee shares minted - calculate based on state BEFORE adding fee
             // This is correct because the fee is taken from the interest
             fee_shares = to_shares_down(
@@ -97,37 +129,211 @@ pub fn accrue_interest_on_market(
     
     market.last_update = current_time;
     
-    Ok(AccrualResult { interest, fee_shares })
+    Ok(AccrualResult { interest, fee_shares, capped })
+}
+
+/// Split `interest` on `debt` into what a `max_growth_bps` cap lets
+/// through and what it drops (0 = no cap)
+pub fn cap_accrual_growth(interest: u128, debt: u128, max_growth_bps: u64) -> Result<(u128, u128)> {
+    if max_growth_bps == 0 {
+        return Ok((interest, 0));
+    }
+    let max_interest = mul_div_down(debt, max_growth_bps as u128, BPS as u128)?;
+    if interest <= max_interest {
+        return Ok((interest, 0));
+    }
+    Ok((max_interest, interest - max_interest))
+}
+
+/// Bring a position's stable debt up to `current_time` at its locked rate
+///
+/// Time covered by the market's interest pause is skipped, as in
+/// `accrue_interest_on_market`. Rounds up (the borrower owes more).
+pub fn accrue_stable_debt(market: &Market, position: &mut Position, current_time: i64) -> Result<u128> {
+    let start = position.stable_last_update;
+    if current_time <= start {
+        return Ok(0);
+    }
+    position.stable_last_update = current_time;
+
+    let paused = std::cmp::min(current_time, market.interest_paused_until)
+        - std::cmp::max(start, market.interest_paused_from);
+    let elapsed = (current_time - start - paused.max(0)) as u128;
+    if elapsed == 0 || position.stable_borrow_assets == 0 {
+        return Ok(0);
+    }
+
+    let interest = wad_mul_up(
+        position.stable_borrow_assets,
+        w_taylor_compounded(position.stable_rate, elapsed)?,
+    )?;
+    position.stable_borrow_assets = checked_add(position.stable_borrow_assets, interest)?;
+    Ok(interest)
+}
+
+/// Accrue interest on a market, sampling the IRM at last-update utilization
+///
+/// This is the entry point instructions use; it must run before anything
+/// that reads or writes market totals or changes the fee. Instructions pass
+/// their remaining accounts, where IRM-program markets find their IRM.
+///
+/// Emits a `SharePriceCheckpoint` whenever the accrual changed totals.
+pub fn accrue_market_interest(market: &mut Market, clock: &Clock, irm_accounts: &[AccountInfo]) -> Result<AccrualResult> {
+    let borrow_rate = sample_borrow_rate(market, clock, irm_accounts)?;
+    let result = accrue_interest_on_market(market, clock.unix_timestamp, borrow_rate)?;
+
+    if result.capped > 0 {
+        emit!(AbnormalAccrualCapped {
+            market_id: market.market_id,
+            borrow_rate,
+            applied_interest: result.interest,
+            capped_interest: result.capped,
+            max_growth_bps: market.max_accrual_growth_bps,
+        });
+    }
+
+    if result.interest > 0 {
+        emit!(SharePriceCheckpoint {
+            market_id: market.market_id,
+            timestamp: clock.unix_timestamp,
+            supply_share_price: market.supply_share_price()?,
+            borrow_share_price: market.borrow_share_price()?,
+        });
+    }
+
+    Ok(result)
+}
+
+/// Borrow rate for the current accrual, cached per slot and timestamp
+///
+/// The first accrual in a slot samples the IRM BEFORE accruing (the elapsed
+/// period's own interest must not feed back into its rate) and stores the
+/// result, with the supply rate and utilization it was sampled at; later instructions on the same market in that slot reuse it
+/// instead of querying the IRM again. Reuse is exact: the cache is keyed on
+/// the slot and timestamp together, so only the first instruction at that
+/// time accrues anything. (A devnet clock override can give one slot
+/// several timestamps; each gets its own sample.)
+///
+/// An IRM fault (`is_irm_fault`) doesn't fail the instruction, so a broken
+/// IRM can't trap users: the slot keeps the last good rate and the failure
+/// is counted. After `MAX_IRM_FAILURES` failing slots in a row the market
+/// turns withdraw-only.
+///
+/// Supply-only markets always get a zero rate.
+pub fn sample_borrow_rate(market: &mut Market, clock: &Clock, irm_accounts: &[AccountInfo]) -> Result<u128> {
+    if market.supply_only {
+        return Ok(0);
+    }
+    if market.rate_cache_slot == clock.slot
+        && market.rate_cache_timestamp == clock.unix_timestamp
+        && clock.slot != 0
+    {
+        return Ok(market.rate_cache);
+    }
+
+    match get_market_borrow_rate(market, irm_accounts) {
+        Ok(borrow_rate) => {
+            cache_borrow_rate(market, clock, borrow_rate)?;
+            Ok(borrow_rate)
+        }
+        Err(err) if is_irm_fault(&err) => Ok(record_irm_failure(market, clock, &err)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Store a freshly sampled borrow rate, and what goes with it, as the
+/// rate for `clock`'s slot and timestamp
+pub fn cache_borrow_rate(market: &mut Market, clock: &Clock, borrow_rate: u128) -> Result<()> {
+    market.rate_cache = borrow_rate;
+    market.supply_rate_cache = market.supply_rate(borrow_rate)?;
+    market.utilization_cache = market.utilization();
+    market.rate_cache_slot = clock.slot;
+    market.rate_cache_timestamp = clock.unix_timestamp;
+    market.irm_failures = 0;
+    Ok(())
+}
+
+/// Keep the cached rate for `clock` after a failed IRM query, and
+/// switch the market to withdraw-only once the IRM keeps failing
+///
+/// Returns the rate the accrual falls back to.
+fn record_irm_failure(market: &mut Market, clock: &Clock, err: &Error) -> u128 {
+    let error_code = match err {
+        Error::AnchorError(err) => err.error_code_number,
+        Error::ProgramError(_) => 0,
+    };
+    market.rate_cache_slot = clock.slot;
+    market.rate_cache_timestamp = clock.unix_timestamp;
+    market.irm_failures = market.irm_failures.saturating_add(1);
+    emit!(IrmQueryFailed {
+        market_id: market.market_id,
+        irm: market.irm,
+        error_code,
+        fallback_rate: market.rate_cache,
+        failures: market.irm_failures,
+    });
+
+    if market.irm_failures >= MAX_IRM_FAILURES && !market.withdraw_only {
+        market.withdraw_only = true;
+        emit!(IrmFailureWithdrawOnly {
+            market_id: market.market_id,
+            irm: market.irm,
+            failures: market.irm_failures,
+        });
+    }
+    market.rate_cache
+}
+
+/// Borrow rate a view accrues at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewRate {
+    /// Per second, WAD
+    pub borrow_rate: u128,
+    /// Reused from `rate_cache` (sampled at `rate_cache_slot`) because the
+    /// IRM couldn't be sampled here
+    pub cached: bool,
+}
+
+/// `sample_borrow_rate` for views, which save nothing
+///
+/// IRM-program markets query their IRM, and adaptive curve markets read
+/// their state without moving it, when `irm_accounts` carries them. Without
+/// those accounts, or if the IRM faults, the view falls back to the cached
+/// rate and says so.
+pub fn view_borrow_rate(market: &Market, irm_accounts: &[AccountInfo]) -> Result<ViewRate> {
+    if market.supply_only {
+        return Ok(ViewRate { borrow_rate: 0, cached: false });
+    }
+    let sampled = if market.irm_adaptive {
+        peek_adaptive_curve_rate(market, irm_accounts)
+    } else {
+        get_market_borrow_rate(market, irm_accounts)
+    };
+    match sampled {
+        Ok(borrow_rate) => Ok(ViewRate { borrow_rate, cached: false }),
+        Err(_) if market.irm_cpi || market.irm_adaptive => {
+            Ok(ViewRate { borrow_rate: market.rate_cache, cached: true })
+        }
+        Err(err) => Err(err),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::constants::WAD;
+    use crate::interfaces::get_borrow_rate_internal;
 
     fn create_test_market() -> Market {
         Market {
-            bump: 0,
-            market_id: [0u8; 32],
-            collateral_mint: Pubkey::default(),
-            loan_mint: Pubkey::default(),
-            collateral_decimals: 9,
-            loan_decimals: 6,
-            oracle: Pubkey::default(),
-            irm: Pubkey::default(),
-            lltv: 8500,
-            paused: false,
-            fee: 0,
             total_supply_assets: 1_000_000_000_000,
             total_supply_shares: 1_000_000_000_000_000_000,
             total_borrow_assets: 500_000_000_000,
             total_borrow_shares: 500_000_000_000_000_000,
-            last_update: 0,
-            pending_fee_shares: 0,
-            collateral_vault_bump: 0,
-            loan_vault_bump: 0,
-            flash_loan_lock: 0,
-            reserved: [0u8; 127],
+            collateral_decimals: 9,
+            loan_decimals: 6,
+            lltv: 8500,
+            ..Market::default()
         }
     }
 
@@ -162,4 +368,85 @@ mod tests {
         assert!(result.interest > 0);
         assert!(market.total_borrow_assets > initial_borrow);
     }
+
+    #[test]
+    fn test_accrual_samples_rate_at_last_update() {
+        let mut market = create_test_market();
+        let mut expected = create_test_market();
+
+        let rate_at_last_update = get_borrow_rate_internal(
+            expected.total_supply_assets,
+            expected.total_borrow_assets,
+        ).unwrap();
+        let expected_result = accrue_interest_on_market(&mut expected, 31_536_000, rate_at_last_update).unwrap();
+
+        let result = accrue_market_interest(&mut market, &clock_at(31_536_000, 100), &[]).unwrap();
+        assert_eq!(result, expected_result);
+        assert_eq!(market.total_borrow_assets, expected.total_borrow_assets);
+
+        // Accruing again in the same second is a no-op
+        let again = accrue_market_interest(&mut market, &clock_at(31_536_000, 100), &[]).unwrap();
+        assert_eq!(again.interest, 0);
+    }
+
+    #[test]
+    fn test_lifetime_totals_accumulate() {
+        let mut market = create_test_market();
+        market.fee = 1000; // 10%
+        let rate = WAD / 20 / 31_536_000;
+
+        let first = accrue_interest_on_market(&mut market, 15_768_000, rate).unwrap();
+        let second = accrue_interest_on_market(&mut market, 31_536_000, rate).unwrap();
+
+        assert_eq!(market.total_interest_accrued, first.interest + second.interest);
+        assert!(market.total_fees_accrued > 0);
+        assert!(market.total_fees_accrued <= market.total_interest_accrued / 10);
+    }
+
+    fn clock_at(unix_timestamp: i64, slot: u64) -> Clock {
+        Clock { slot, unix_timestamp, ..Clock::default() }
+    }
+
+    #[test]
+    fn test_rate_cached_within_slot() {
+        let mut market = create_test_market();
+        let first = sample_borrow_rate(&mut market, &clock_at(0, 7), &[]).unwrap();
+        assert_eq!(market.rate_cache_slot, 7);
+
+        // Totals move within the slot: the cached rate is reused
+        market.total_borrow_assets = market.total_supply_assets;
+        assert_eq!(sample_borrow_rate(&mut market, &clock_at(0, 7), &[]).unwrap(), first);
+
+        // Next slot re-samples at the new utilization
+        assert!(sample_borrow_rate(&mut market, &clock_at(0, 8), &[]).unwrap() > first);
+    }
+
+    #[test]
+    fn test_rate_resampled_at_new_timestamp_within_slot() {
+        let mut market = create_test_market();
+        let first = sample_borrow_rate(&mut market, &clock_at(100, 7), &[]).unwrap();
+
+        // An overridden clock moves the time without moving the slot
+        market.total_borrow_assets = market.total_supply_assets;
+        assert!(sample_borrow_rate(&mut market, &clock_at(200, 7), &[]).unwrap() > first);
+        assert_eq!(market.rate_cache_timestamp, 200);
+    }
+
+    #[test]
+    fn test_sampling_caches_supply_rate_and_utilization() {
+        let mut market = create_test_market();
+        market.fee = 1000;
+        let borrow_rate = sample_borrow_rate(&mut market, &clock_at(0, 7), &[]).unwrap();
+
+        // Half the supply is borrowed; suppliers keep 90% after the fee
+        assert_eq!(market.utilization_cache, WAD / 2);
+        assert_eq!(market.supply_rate_cache, borrow_rate / 2 * 9 / 10);
+
+        // Stable debt adds its own interest to the supply rate
+        market.total_stable_borrow_assets = 100_000_000_000;
+        market.avg_stable_rate = borrow_rate * 2;
+        sample_borrow_rate(&mut market, &clock_at(0, 8), &[]).unwrap();
+        assert_eq!(market.utilization_cache, WAD * 6 / 10);
+        assert!(market.supply_rate_cache > market.rate_cache / 2 * 9 / 10);
+    }
 }
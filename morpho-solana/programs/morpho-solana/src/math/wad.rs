@@ -2,6 +2,10 @@
 //! 
 //! All calculations use u128 with WAD scaling.
 //! Order of operations is designed to minimize precision loss.
+//!
+//! `mul_div_*` only fail when the final result doesn't fit in u128: an
+//! `a * b` product past u128 falls back to 256-bit long division, so e.g.
+//! u64::MAX collateral at a 1e36-scaled price still values cleanly.
 
 use anchor_lang::prelude::*;
 use crate::errors::MorphoError;
@@ -24,8 +28,10 @@ pub fn mul_div_down(a: u128, b: u128, c: u128) -> Result<u128> {
         return Ok(0);
     }
     
-    let product = checked_mul(a, b)?;
-    Ok(product / c)
+    match a.checked_mul(b) {
+        Some(product) => Ok(product / c),
+        None => Ok(wide_mul_div(a, b, c)?.0),
+    }
 }
 
 /// Multiply then divide, rounding UP
@@ -44,14 +50,56 @@ pub fn mul_div_up(a: u128, b: u128, c: u128) -> Result<u128> {
         return Ok(0);
     }
     
-    let product = checked_mul(a, b)?;
-    // (product + c - 1) / c = ceil division
-    let result = product
-        .checked_add(c - 1)
-        .ok_or(MorphoError::MathOverflow)?
-        / c;
-    
-    Ok(result)
+    let (quotient, remainder) = match a.checked_mul(b) {
+        Some(product) => (product / c, product % c),
+        None => wide_mul_div(a, b, c)?,
+    };
+    // Ceil division: bump on any remainder
+    if remainder == 0 {
+        Ok(quotient)
+    } else {
+        checked_add(quotient, 1)
+    }
+}
+
+/// Full 256-bit `a * b` as (high, low) 128-bit halves
+fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    // Middle column, including the carry out of the low limb
+    let cross = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let low = (cross << 64) | (lo_lo & MASK);
+    let high = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (cross >> 64);
+    (high, low)
+}
+
+/// `(a * b) / c` and `(a * b) % c` through a 256-bit product
+///
+/// Errors when the quotient doesn't fit in u128 (high half >= c).
+fn wide_mul_div(a: u128, b: u128, c: u128) -> Result<(u128, u128)> {
+    let (high, low) = wide_mul(a, b);
+    require!(high < c, MorphoError::MathOverflow);
+
+    // Shift-subtract long division; `remainder < c` holds on entry to every
+    // step, so a set carry bit means the shifted remainder exceeds c
+    let mut quotient = 0u128;
+    let mut remainder = high;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        if carry == 1 || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            quotient |= 1 << bit;
+        }
+    }
+    Ok((quotient, remainder))
 }
 
 /// WAD multiplication (a * b / WAD), rounded down
@@ -78,39 +126,66 @@ pub fn wad_div_up(a: u128, b: u128) -> Result<u128> {
     mul_div_up(a, WAD, b)
 }
 
-/// Calculate compound interest factor using Taylor expansion
-/// e^(rate * time) - 1 ≈ rt + (rt)²/2 + (rt)³/6
-/// 
+/// Largest exponent expanded directly; bigger ones are halved down to it
+/// and the growth squared back up
+const TAYLOR_PIECE: u128 = WAD / 16;
+
+/// Exponent compounded exactly; growth past it is linear, so stale markets
+/// at extreme rates never overflow
+const MAX_COMPOUNDED_EXPONENT: u128 = 20 * WAD;
+
+/// Calculate compound interest factor: e^(rate * time) - 1
+///
+/// Expands rt + (rt)²/2 + (rt)³/6 + (rt)⁴/24 over rt / 2^k, the largest
+/// halving within `TAYLOR_PIECE`, then squares the growth k times. Within
+/// 0.01 bps of exact for any rate up to the cap over a year; a plain
+/// three-term expansion is ~100 bps short at the cap over 30 days. Every
+/// step rounds down, so borrowers are never charged past e^rt.
+///
 /// This gives the interest FACTOR to multiply against principal.
-/// 
+///
 /// # Arguments
 /// * `rate` - Per-second interest rate (WAD-scaled)
 /// * `time` - Time elapsed in seconds
 pub fn w_taylor_compounded(rate: u128, time: u128) -> Result<u128> {
-    // rt (first term) - scaled by WAD
+    // rt - scaled by WAD
     let rt = checked_mul(rate, time)?;
-    
+
     if rt == 0 {
         return Ok(0);
     }
-    
+
+    let compounded = std::cmp::min(rt, MAX_COMPOUNDED_EXPONENT);
+    let mut halvings = 0;
+    while compounded >> halvings > TAYLOR_PIECE {
+        halvings += 1;
+    }
+
+    let mut growth = checked_add(WAD, taylor_expansion(compounded >> halvings)?)?;
+    for _ in 0..halvings {
+        growth = wad_mul_down(growth, growth)?;
+    }
+    if rt > compounded {
+        growth = wad_mul_down(growth, checked_add(WAD, rt - compounded)?)?;
+    }
+
+    Ok(growth - WAD)
+}
+
+/// rt + (rt)²/2 + (rt)³/6 + (rt)⁴/24, each term rounded down
+fn taylor_expansion(rt: u128) -> Result<u128> {
     // (rt)² / WAD
     let rt_squared = wad_mul_down(rt, rt)?;
-    
-    // (rt)² / 2 (second term)
-    let second_term = rt_squared / 2;
-    
+
     // (rt)³ / WAD / WAD = rt_squared * rt / WAD
-    let rt_cubed_over_wad = wad_mul_down(rt_squared, rt)?;
-    
-    // (rt)³ / 6 (third term)
-    let third_term = rt_cubed_over_wad / 6;
-    
-    // Sum all terms: rt + rt²/2 + rt³/6
-    let result = checked_add(rt, second_term)?;
-    let result = checked_add(result, third_term)?;
-    
-    Ok(result)
+    let rt_cubed = wad_mul_down(rt_squared, rt)?;
+
+    // (rt)⁴ / WAD / WAD / WAD
+    let rt_fourth = wad_mul_down(rt_cubed, rt)?;
+
+    let result = checked_add(rt, rt_squared / 2)?;
+    let result = checked_add(result, rt_cubed / 6)?;
+    checked_add(result, rt_fourth / 24)
 }
 
 #[cfg(test)]
@@ -137,6 +212,25 @@ mod tests {
         assert_eq!(mul_div_up(100, 200, 200).unwrap(), 100);
     }
 
+    #[test]
+    fn test_mul_div_wide_product() {
+        // u128::MAX * u128::MAX / u128::MAX needs the 256-bit path
+        assert_eq!(mul_div_down(u128::MAX, u128::MAX, u128::MAX).unwrap(), u128::MAX);
+        assert_eq!(mul_div_up(u128::MAX, u128::MAX, u128::MAX).unwrap(), u128::MAX);
+
+        // (2^127 * 3) / 2 = 3 * 2^126, exact
+        assert_eq!(mul_div_down(1 << 127, 3, 2).unwrap(), 3 << 126);
+
+        // (2^128 - 1) * 2^64 / 2^65 rounds the half away
+        let a = u128::MAX;
+        assert_eq!(mul_div_down(a, 1 << 64, 1 << 65).unwrap(), a >> 1);
+        assert_eq!(mul_div_up(a, 1 << 64, 1 << 65).unwrap(), (a >> 1) + 1);
+
+        // Quotient past u128 still overflows
+        assert!(mul_div_down(u128::MAX, 2, 1).is_err());
+        assert!(mul_div_up(u128::MAX, u128::MAX, u128::MAX - 1).is_err());
+    }
+
     #[test]
     fn test_wad_mul() {
         let half_wad = WAD / 2;
@@ -163,4 +257,65 @@ mod tests {
         // Zero time should give zero factor
         assert_eq!(w_taylor_compounded(rate, 0).unwrap(), 0);
     }
+
+    /// Shortfall of `factor` from exact e^rt - 1, in bps of the grown debt
+    fn shortfall_bps(rate: u128, time: u128, factor: u128) -> f64 {
+        let exact = ((rate * time) as f64 / WAD as f64).exp();
+        let got = 1.0 + factor as f64 / WAD as f64;
+        (exact - got) / exact * 10_000.0
+    }
+
+    #[test]
+    fn test_compounding_tracks_exact_exponential() {
+        use crate::constants::SECONDS_PER_YEAR;
+
+        // 1% to 1000% APR, one second to a year
+        for yearly in [WAD / 100, WAD / 20, WAD / 5, WAD, WAD * 10] {
+            for time in [1, 3_600, 86_400, 30 * 86_400, SECONDS_PER_YEAR] {
+                let rate = yearly / SECONDS_PER_YEAR;
+                let shortfall = shortfall_bps(rate, time, w_taylor_compounded(rate, time).unwrap());
+                assert!(
+                    (-1e-9..0.01).contains(&shortfall),
+                    "{} APR over {}s: {} bps",
+                    yearly as f64 / WAD as f64,
+                    time,
+                    shortfall
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_three_term_taylor_short_at_rate_cap() {
+        use crate::constants::MAX_BORROW_RATE_PER_SECOND;
+
+        let three_term = |rate: u128, time: u128| {
+            let rt = rate * time;
+            let rt_squared = wad_mul_down(rt, rt).unwrap();
+            rt + rt_squared / 2 + wad_mul_down(rt_squared, rt).unwrap() / 6
+        };
+
+        // Fine at everyday rates...
+        let rate = WAD / 20 / 31_536_000;
+        assert!(shortfall_bps(rate, 86_400, three_term(rate, 86_400)) < 1e-6);
+
+        // ...but over a bps short at the cap over 30 days, which the
+        // halved four-term expansion fixes
+        let time = 30 * 86_400;
+        let rate = MAX_BORROW_RATE_PER_SECOND;
+        assert!(shortfall_bps(rate, time, three_term(rate, time)) > 1.0);
+        assert!(shortfall_bps(rate, time, w_taylor_compounded(rate, time).unwrap()) < 0.01);
+    }
+
+    #[test]
+    fn test_compounding_linear_past_max_exponent() {
+        // Ten years at 1000% APR: e^100 would overflow, so growth past e^20
+        // is linear instead
+        let rate = WAD * 10 / 31_536_000;
+        let factor = w_taylor_compounded(rate, 10 * 31_536_000).unwrap();
+        let at_max = 20f64.exp();
+        let rt = (rate * 10 * 31_536_000) as f64 / WAD as f64;
+        let expected = at_max * (1.0 + rt - 20.0) - 1.0;
+        assert!((factor as f64 / WAD as f64 / expected - 1.0).abs() < 1e-6, "{} vs {}", factor as f64 / WAD as f64, expected);
+    }
 }
@@ -0,0 +1,48 @@
+//! Clock override for devnet drills
+//!
+//! With the `devnet` feature, the protocol owner can park a timestamp in the
+//! `ClockOverride` PDA. Any instruction that has it among its remaining
+//! accounts, signed for by the owner that set it, then reads that timestamp
+//! instead of the Clock sysvar's (see `crate::time`), so a test can step one market forward without warping
+//! the validator clock for every other. Other builds never create it and
+//! ignore it if passed.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+
+/// Timestamp instructions read in place of the sysvar's
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"clock_override"]
+#[account]
+pub struct ClockOverride {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Owner that set the override; only instructions it signs run at it
+    pub authority: Pubkey,
+
+    /// Unix timestamp handed out as the current time
+    pub unix_timestamp: i64,
+}
+
+impl ClockOverride {
+    pub const SEED: &'static [u8] = b"clock_override";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // authority
+        8       // unix_timestamp
+    }
+}
+
+/// Whether `account` holds a `ClockOverride`
+pub fn is_clock_override(account: &AccountInfo) -> Result<bool> {
+    Ok(account.owner == &crate::ID
+        && account.try_borrow_data()?.starts_with(ClockOverride::DISCRIMINATOR))
+}
+
+/// Derive the clock override PDA
+pub fn derive_clock_override(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROGRAM_SEED_PREFIX, ClockOverride::SEED], program_id)
+}
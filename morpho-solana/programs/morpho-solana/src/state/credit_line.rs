@@ -0,0 +1,79 @@
+//! Credit line account
+//!
+//! A supplier delegates part of their supply position to one borrower, who
+//! may then borrow up to the line's limit without posting collateral. The
+//! delegated amount stays locked in the supplier's position, so a defaulted
+//! line is written off against that supplier rather than the market.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+
+/// Uncollateralized credit line from a supplier to a borrower
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_credit_line", market_id, supplier, borrower]
+#[account]
+pub struct CreditLine {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Market the line draws liquidity from
+    pub market_id: [u8; 32],
+
+    /// Supplier backing the line with their supply position
+    pub supplier: Pubkey,
+
+    /// Borrower allowed to draw on the line
+    pub borrower: Pubkey,
+
+    /// Max debt (loan token units) the borrower may draw up to
+    pub limit: u128,
+
+    /// Borrow shares drawn on the line (included in market borrow totals)
+    pub borrow_shares: u128,
+
+    /// Line creation timestamp
+    pub created_at: i64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl CreditLine {
+    pub const SEED: &'static [u8] = b"morpho_credit_line";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // market_id
+        32 +    // supplier
+        32 +    // borrower
+        16 +    // limit
+        16 +    // borrow_shares
+        8 +     // created_at
+        32      // reserved
+    }
+
+    /// Check if the borrower owes anything on the line
+    pub fn has_debt(&self) -> bool {
+        self.borrow_shares > 0
+    }
+}
+
+/// Derive credit line PDA
+pub fn derive_credit_line(
+    program_id: &Pubkey,
+    market_id: &[u8; 32],
+    supplier: &Pubkey,
+    borrower: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PROGRAM_SEED_PREFIX,
+            CreditLine::SEED,
+            market_id,
+            supplier.as_ref(),
+            borrower.as_ref(),
+        ],
+        program_id,
+    )
+}
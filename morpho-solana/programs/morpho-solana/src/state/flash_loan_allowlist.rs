@@ -0,0 +1,55 @@
+//! Flash loan allowlist entry
+//!
+//! Markets in allowlist mode only lend flash loans to callers holding an
+//! entry. Entries are only granted to program-derived addresses, so every
+//! allowed caller is an audited integration program rather than an EOA.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+
+/// Flash loan allowlist entry (existence = allowed)
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_flash_allow", market_id, borrower]
+#[account]
+pub struct FlashLoanAllowlistEntry {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Market the entry applies to
+    pub market_id: [u8; 32],
+
+    /// Allowed flash loan borrower (a PDA signing via CPI)
+    pub borrower: Pubkey,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl FlashLoanAllowlistEntry {
+    pub const SEED: &'static [u8] = b"morpho_flash_allow";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // market_id
+        32 +    // borrower
+        32      // reserved
+    }
+}
+
+/// Derive flash loan allowlist entry PDA
+pub fn derive_flash_loan_allowlist_entry(
+    program_id: &Pubkey,
+    market_id: &[u8; 32],
+    borrower: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PROGRAM_SEED_PREFIX,
+            FlashLoanAllowlistEntry::SEED,
+            market_id,
+            borrower.as_ref(),
+        ],
+        program_id,
+    )
+}
@@ -0,0 +1,43 @@
+//! Supply holding period exemption
+//!
+//! Protocol-owned integrations (e.g. the first-party vault program) supply
+//! and withdraw on their depositors' behalf and shouldn't sit out a market's
+//! holding period. The owner grants them an entry; its existence exempts
+//! every position the account owns, in every market.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+
+/// Holding period exemption (existence = exempt)
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_hold_exempt", account]
+#[account]
+pub struct HoldingPeriodExemption {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Position owner whose supplies skip the holding period
+    pub account: Pubkey,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl HoldingPeriodExemption {
+    pub const SEED: &'static [u8] = b"morpho_hold_exempt";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // account
+        32      // reserved
+    }
+}
+
+/// Derive holding period exemption PDA
+pub fn derive_holding_period_exemption(program_id: &Pubkey, account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, HoldingPeriodExemption::SEED, account.as_ref()],
+        program_id,
+    )
+}
@@ -0,0 +1,64 @@
+//! Second-price check at liquidation
+//!
+//! A market may name a reference oracle that every liquidation reads next
+//! to its own oracle. The two prices must agree within the configured
+//! tolerance, so a single manipulated feed can't mark healthy positions
+//! liquidatable. Only `liquidate` reads the reference; repays, supplies and
+//! collateral deposits never touch it. A reference that fails to price
+//! blocks liquidations, so the owner should clear the check if it halts.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+
+/// Reference oracle liquidations of `market_id` are checked against
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_liquidation_reference", market_id]
+#[account]
+pub struct LiquidationReference {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Market the check applies to
+    pub market_id: [u8; 32],
+
+    /// Chainlink, Pyth, Switchboard or Static Oracle feed for the same pair
+    pub reference_oracle: Pubkey,
+
+    /// Widest gap to the reference a liquidation price may have (basis points)
+    pub max_divergence_bps: u64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl LiquidationReference {
+    pub const SEED: &'static [u8] = b"morpho_liquidation_reference";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // market_id
+        32 +    // reference_oracle
+        8 +     // max_divergence_bps
+        32      // reserved
+    }
+
+    /// Read the check stored at `account`, if the owner has set one
+    ///
+    /// `account` must already be checked to be the market's reference PDA.
+    pub fn try_load(account: &AccountInfo) -> Result<Option<Self>> {
+        if account.owner != &crate::ID || account.data_is_empty() {
+            return Ok(None);
+        }
+        let data = account.try_borrow_data()?;
+        Ok(Some(Self::try_deserialize(&mut &data[..])?))
+    }
+}
+
+/// Derive liquidation reference PDA for a market
+pub fn derive_liquidation_reference(program_id: &Pubkey, market_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, LiquidationReference::SEED, market_id],
+        program_id,
+    )
+}
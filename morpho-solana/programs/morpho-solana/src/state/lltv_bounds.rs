@@ -0,0 +1,66 @@
+//! Per-loan-token LLTV bounds
+//!
+//! Restricts which whitelisted LLTVs new markets may use for a given loan
+//! token, so a token class can be held to its own risk tier (e.g. stables up
+//! to 96.5%, volatile loan tokens capped at 86%). Loan tokens without an
+//! entry can use any whitelisted LLTV.
+
+use anchor_lang::prelude::*;
+use crate::constants::PROGRAM_SEED_PREFIX;
+
+/// LLTV range markets lending `loan_mint` may be created with
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_lltv_bounds", loan_mint]
+#[account]
+pub struct LltvBounds {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Loan token the bounds apply to
+    pub loan_mint: Pubkey,
+
+    /// Lowest LLTV allowed (inclusive, basis points)
+    pub min_lltv: u64,
+
+    /// Highest LLTV allowed (inclusive, basis points)
+    pub max_lltv: u64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl LltvBounds {
+    pub const SEED: &'static [u8] = b"morpho_lltv_bounds";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // loan_mint
+        8 +     // min_lltv
+        8 +     // max_lltv
+        32      // reserved
+    }
+
+    pub fn contains(&self, lltv: u64) -> bool {
+        lltv >= self.min_lltv && lltv <= self.max_lltv
+    }
+
+    /// Read the bounds stored at `account`, if the owner has set any
+    ///
+    /// `account` must already be checked to be the loan mint's bounds PDA.
+    pub fn try_load(account: &AccountInfo) -> Result<Option<Self>> {
+        if account.owner != &crate::ID || account.data_is_empty() {
+            return Ok(None);
+        }
+        let data = account.try_borrow_data()?;
+        Ok(Some(Self::try_deserialize(&mut &data[..])?))
+    }
+}
+
+/// Derive LLTV bounds PDA for a loan token
+pub fn derive_lltv_bounds(program_id: &Pubkey, loan_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, LltvBounds::SEED, loan_mint.as_ref()],
+        program_id,
+    )
+}
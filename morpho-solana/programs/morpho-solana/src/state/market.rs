@@ -6,13 +6,16 @@
 
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::keccak;
-use crate::constants::{PROGRAM_SEED_PREFIX, WAD, BPS};
-use crate::math::{mul_div_down, checked_sub};
+use crate::constants::{PROGRAM_SEED_PREFIX, WAD, BPS, SECONDS_PER_YEAR};
+use crate::errors::MorphoError;
+use crate::math::{mul_div_down, checked_add, checked_sub, to_assets_down, to_shares_down};
+use super::Position;
 
 /// Individual lending market state
 /// 
 /// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_market", market_id]
 #[account]
+#[derive(Default)]
 pub struct Market {
     /// PDA bump seed
     pub bump: u8,
@@ -83,8 +86,279 @@ pub struct Market {
     /// Flash loan lock (non-zero means flash loan in progress)
     pub flash_loan_lock: u8,
 
-    /// Reserved for future use
-    pub reserved: [u8; 127],
+    /// Borrower of the in-progress two-step flash loan
+    pub flash_loan_borrower: Pubkey,
+
+    /// Principal of the in-progress two-step flash loan
+    pub flash_loan_amount: u128,
+
+    // === Collateral Yield Routing ===
+
+    /// Adapter program that idle collateral may be staked into (default = disabled)
+    pub collateral_yield_adapter: Pubkey,
+
+    /// Collateral currently deployed to the adapter (still owed to borrowers)
+    pub collateral_staked: u128,
+
+    /// Max share of total collateral that may be staked (basis points)
+    pub max_collateral_staked_bps: u64,
+
+    // === Loan Liquidity Routing ===
+
+    /// Adapter program that idle loan liquidity may be deployed into (default = disabled)
+    pub loan_yield_adapter: Pubkey,
+
+    /// Loan tokens currently deployed to the adapter (principal, still counted as liquidity)
+    pub loan_deployed: u128,
+
+    /// Max share of available liquidity that may be deployed (basis points)
+    pub max_loan_deployed_bps: u64,
+
+    // === Curation ===
+
+    /// Curator allowed to propose config changes (default = none)
+    pub curator: Pubkey,
+
+    // === Borrow Buffer ===
+
+    /// Share of LLTV new borrows may reach (basis points, 0 = full LLTV)
+    /// e.g. 9500 with an 85% LLTV caps new borrows at 80.75% LTV
+    pub borrow_lltv_buffer: u64,
+
+    // === Rate Cache ===
+
+    /// Slot the cached borrow rate was sampled in
+    pub rate_cache_slot: u64,
+
+    /// Borrow rate (per second, WAD) sampled at `rate_cache_slot`
+    pub rate_cache: u128,
+
+    /// Restrict flash loans to allowlisted program-derived callers
+    pub flash_loan_allowlist_only: bool,
+
+    /// Forced oracle failure for devnet drills (OracleFailureMode, 0 = none)
+    /// Only honored by builds with the `devnet` feature
+    pub oracle_failure_mode: u8,
+
+    /// Creation order across the protocol (0-based, from ProtocolState.market_count)
+    pub market_index: u64,
+
+    // === Risk Oracle ===
+
+    /// Account publishing the market's max safe debt (default = none)
+    pub risk_oracle: Pubkey,
+
+    /// Headroom new borrows may take over the risk oracle's figure (basis points)
+    /// e.g. 1000 lets total borrows reach 110% of the reported max safe debt
+    pub risk_cap_buffer_bps: u64,
+
+    // === Oracle Divergence Guard ===
+
+    /// Feeds cross-checked by `flag_oracle_divergence` (default = guard off)
+    pub reference_feeds: [Pubkey; 2],
+
+    /// Divergence between the reference feeds that trips the guard (basis points)
+    pub max_feed_divergence_bps: u64,
+
+    /// Borrows and collateral withdrawals frozen until the owner clears the flag
+    pub divergence_frozen: bool,
+
+    // === Lifetime Stats ===
+
+    /// Interest accrued since creation (loan token units, fees included)
+    pub total_interest_accrued: u128,
+
+    /// Protocol fees accrued since creation (loan token units)
+    pub total_fees_accrued: u128,
+
+    /// Collateral seized by liquidations since creation (collateral token units)
+    pub total_liquidated_collateral: u128,
+
+    /// Bad debt socialized to suppliers since creation (loan token units)
+    pub total_bad_debt: u128,
+
+    // === Reputation Boost ===
+
+    /// LLTV bonus for positions with a clean history (basis points, 0 = off)
+    pub reputation_lltv_boost_bps: u64,
+
+    /// Lifetime repaid debt a never-liquidated position needs to earn the boost
+    pub reputation_min_repaid: u128,
+
+    // === Liquidation Insurance ===
+
+    /// Yearly premium insured positions pay (basis points of collateral, 0 = not offered)
+    pub insurance_premium_bps: u64,
+
+    /// Share of the liquidation penalty reimbursed to insured positions (basis points)
+    pub insurance_coverage_bps: u64,
+
+    /// Premiums held for reimbursements (collateral token units, in the collateral vault)
+    pub insurance_pool: u128,
+
+    // === Interest Pause ===
+
+    /// No interest accrues before this time (audit/incident mode, 0 = never paused)
+    pub interest_paused_until: i64,
+
+    /// When the current (or last) interest pause was set
+    pub interest_paused_from: i64,
+
+    // === Stable-Rate Borrowing ===
+
+    /// Whether positions may take stable-rate loans
+    pub stable_borrow_enabled: bool,
+
+    /// Yearly premium over the variable rate that new stable loans lock in (basis points)
+    pub stable_rate_spread_bps: u64,
+
+    /// Utilization at or above which stable rates may be rebalanced up (basis points, 0 = never)
+    pub stable_rebalance_utilization_bps: u64,
+
+    /// Stable debt across all positions, accrued at `avg_stable_rate`
+    pub total_stable_borrow_assets: u128,
+
+    /// Debt-weighted average of the positions' locked rates (per second, WAD-scaled)
+    pub avg_stable_rate: u128,
+
+    /// Positions holding stable debt
+    pub stable_borrowers: u64,
+
+    /// Account that created the market; the only one who may publish its
+    /// `MarketMetadata` (default for markets predating the field)
+    pub creator: Pubkey,
+
+    /// Longest the oracle may go without a fresh update (slots, 0 = MAX_ORACLE_STALENESS)
+    pub oracle_heartbeat: u64,
+
+    /// Seconds newly supplied shares stay locked, so just-in-time supply
+    /// can't capture a large pending accrual and leave (0 = off)
+    pub min_supply_holding_period: i64,
+
+    /// Share of socialized bad debt paid back to the liquidator that
+    /// realized it, out of pending fees (0 = off)
+    pub bad_debt_rebate_bps: u16,
+
+    /// No borrowing of any kind and a zero rate: the market is only an
+    /// escrow for supplied loan tokens
+    pub supply_only: bool,
+
+    /// Sum of every position's collateral
+    ///
+    /// This and `total_insurance_claimable` saturate at zero on the way
+    /// down, so books that predate them can't brick withdrawals; the gap
+    /// shows up as surplus in `collateral_drift` instead.
+    pub total_collateral: u128,
+
+    /// Insurance reimbursements set aside for positions but not yet claimed
+    pub total_insurance_claimable: u128,
+
+    /// Most a single accrual may grow either debt pool, in bps of that
+    /// pool; anything beyond is dropped (0 = no cap)
+    pub max_accrual_growth_bps: u64,
+
+    /// Supply share price (as `supply_share_price`) below which socialized
+    /// bad debt flips the market to withdraw-only (0 = off)
+    pub share_price_floor: u128,
+
+    /// No new supply or borrowing until governance clears it
+    pub withdraw_only: bool,
+
+    /// Oldest oracle update (by the feed's own timestamp) a price may come
+    /// from, in seconds; checked on top of `oracle_heartbeat` (0 = off)
+    pub max_oracle_age_secs: u64,
+
+    /// Secondary price feed borrows, collateral withdrawals and
+    /// liquidations fall back to when `oracle` is stale or returns an
+    /// unusable price (default = none)
+    pub fallback_oracle: Pubkey,
+
+    /// Widest confidence interval, in bps of the price, accepted from a
+    /// Pyth oracle (0 = DEFAULT_ORACLE_CONF_BPS)
+    pub max_oracle_conf_bps: u64,
+
+    /// Loan-token surplus (rounding dust, donations) swept into protocol
+    /// fees since creation (loan token units)
+    pub total_surplus_swept: u128,
+
+    /// `oracle` is priced by CPI into the program that owns it, which was
+    /// the protocol's allowlisted oracle program at creation
+    pub oracle_program_priced: bool,
+
+    /// `oracle` is the pegged oracle address: prices at a fixed 1:1 with no feed
+    pub oracle_pegged: bool,
+
+    /// Switchboard feeds publish whole-token prices, prescaled with the
+    /// token decimals like Chainlink and Pyth (false = already prescaled)
+    pub normalize_switchboard: bool,
+
+    /// `irm` is a program queried by CPI for every rate; otherwise the
+    /// built-in curve prices borrows
+    pub irm_cpi: bool,
+
+    /// `irm` is the adaptive curve model address: rates follow the
+    /// market's `AdaptiveCurveIrm`, which every accrual updates
+    pub irm_adaptive: bool,
+
+    /// Supply rate (per second, WAD, net of the fee) sampled with
+    /// `rate_cache`, for indexers and UIs
+    pub supply_rate_cache: u128,
+
+    /// Utilization (WAD) `rate_cache` was sampled at
+    pub utilization_cache: u128,
+
+    /// Slots in a row the IRM has failed its rate query (reset by any
+    /// successful query)
+    pub irm_failures: u8,
+
+    /// Unix timestamp the cached borrow rate was sampled at; keys the cache
+    /// with `rate_cache_slot`, since an overridden clock can give one slot
+    /// several timestamps
+    pub rate_cache_timestamp: i64,
+
+    /// How long a position must have been open, and debt held before its
+    /// repayment counts, for the reputation boost (seconds)
+    pub reputation_min_age: i64,
+}
+
+/// An operation gated by the market's modes
+///
+/// Every handler asks `Market::check_action` before touching the books, so
+/// what paused, frozen, supply-only and withdraw-only markets allow lives
+/// in one place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarketAction {
+    Supply,
+    Withdraw,
+    SupplyCollateral,
+    WithdrawCollateral,
+    /// Any new debt: variable, stable, credit line
+    Borrow,
+    Repay,
+    Liquidate,
+    FlashLoan,
+    /// Merging positions without debt
+    MergePositions,
+    /// Merging a position that carries debt
+    MergeDebt,
+    /// Moving vault funds into a yield adapter
+    DeployToAdapter,
+}
+
+impl MarketAction {
+    pub const ALL: [MarketAction; 11] = [
+        MarketAction::Supply,
+        MarketAction::Withdraw,
+        MarketAction::SupplyCollateral,
+        MarketAction::WithdrawCollateral,
+        MarketAction::Borrow,
+        MarketAction::Repay,
+        MarketAction::Liquidate,
+        MarketAction::FlashLoan,
+        MarketAction::MergePositions,
+        MarketAction::MergeDebt,
+        MarketAction::DeployToAdapter,
+    ];
 }
 
 impl Market {
@@ -114,7 +388,67 @@ impl Market {
         1 +     // collateral_vault_bump
         1 +     // loan_vault_bump
         1 +     // flash_loan_lock
-        127     // reserved
+        32 +    // flash_loan_borrower
+        16 +    // flash_loan_amount
+        32 +    // collateral_yield_adapter
+        16 +    // collateral_staked
+        8 +     // max_collateral_staked_bps
+        32 +    // loan_yield_adapter
+        16 +    // loan_deployed
+        8 +     // max_loan_deployed_bps
+        32 +    // curator
+        8 +     // borrow_lltv_buffer
+        8 +     // rate_cache_slot
+        16 +    // rate_cache
+        1 +     // flash_loan_allowlist_only
+        1 +     // oracle_failure_mode
+        8 +     // market_index
+        32 +    // risk_oracle
+        8 +     // risk_cap_buffer_bps
+        64 +    // reference_feeds
+        8 +     // max_feed_divergence_bps
+        1 +     // divergence_frozen
+        16 +    // total_interest_accrued
+        16 +    // total_fees_accrued
+        16 +    // total_liquidated_collateral
+        16 +    // total_bad_debt
+        8 +     // reputation_lltv_boost_bps
+        16 +    // reputation_min_repaid
+        8 +     // insurance_premium_bps
+        8 +     // insurance_coverage_bps
+        16 +    // insurance_pool
+        8 +     // interest_paused_until
+        8 +     // interest_paused_from
+        1 +     // stable_borrow_enabled
+        8 +     // stable_rate_spread_bps
+        8 +     // stable_rebalance_utilization_bps
+        16 +    // total_stable_borrow_assets
+        16 +    // avg_stable_rate
+        8 +     // stable_borrowers
+        32 +    // creator
+        8 +     // oracle_heartbeat
+        8 +     // min_supply_holding_period
+        2 +     // bad_debt_rebate_bps
+        1 +     // supply_only
+        16 +    // total_collateral
+        16 +    // total_insurance_claimable
+        8 +     // max_accrual_growth_bps
+        16 +    // share_price_floor
+        1 +     // withdraw_only
+        8 +     // max_oracle_age_secs
+        32 +    // fallback_oracle
+        8 +     // max_oracle_conf_bps
+        16 +    // total_surplus_swept
+        1 +     // oracle_program_priced
+        1 +     // oracle_pegged
+        1 +     // normalize_switchboard
+        1 +     // irm_cpi
+        1 +     // irm_adaptive
+        16 +    // supply_rate_cache
+        16 +    // utilization_cache
+        1 +     // irm_failures
+        8 +     // rate_cache_timestamp
+        8       // reputation_min_age
     }
 
     /// Calculate utilization rate (scaled by WAD = 1e18)
@@ -123,15 +457,95 @@ impl Market {
             return 0;
         }
         mul_div_down(
-            self.total_borrow_assets,
+            self.total_debt(),
             WAD,
             self.total_supply_assets,
         ).unwrap_or(0)
     }
 
+    /// Per-second rate suppliers earn while variable debt pays
+    /// `borrow_rate` (WAD): variable and stable interest spread over the
+    /// supply, net of the fee
+    pub fn supply_rate(&self, borrow_rate: u128) -> Result<u128> {
+        if self.total_supply_assets == 0 {
+            return Ok(0);
+        }
+        let gross = checked_add(
+            mul_div_down(borrow_rate, self.total_borrow_assets, self.total_supply_assets)?,
+            mul_div_down(self.avg_stable_rate, self.total_stable_borrow_assets, self.total_supply_assets)?,
+        )?;
+        mul_div_down(gross, (BPS - self.fee) as u128, BPS as u128)
+    }
+
+    /// Variable plus stable debt (loan token units)
+    pub fn total_debt(&self) -> u128 {
+        self.total_borrow_assets.saturating_add(self.total_stable_borrow_assets)
+    }
+
+    /// Loan-token units one WAD of supply shares is worth
+    pub fn supply_share_price(&self) -> Result<u128> {
+        to_assets_down(WAD, self.total_supply_assets, self.total_supply_shares)
+    }
+
+    /// Loan-token units one WAD of borrow shares owes
+    pub fn borrow_share_price(&self) -> Result<u128> {
+        to_assets_down(WAD, self.total_borrow_assets, self.total_borrow_shares)
+    }
+
     /// Get available liquidity (supply - borrows)
     pub fn available_liquidity(&self) -> u128 {
-        checked_sub(self.total_supply_assets, self.total_borrow_assets).unwrap_or(0)
+        checked_sub(self.total_supply_assets, self.total_debt()).unwrap_or(0)
+    }
+
+    /// Liquidity expected to sit in the loan vault rather than an adapter
+    pub fn idle_liquidity(&self) -> u128 {
+        self.available_liquidity().saturating_sub(self.loan_deployed)
+    }
+
+    /// Liquidity that can leave the vault without first recalling deployed funds
+    pub fn vault_liquidity(&self, vault_balance: u64) -> u128 {
+        self.available_liquidity().min(vault_balance as u128)
+    }
+
+    /// Nothing supplied, borrowed, staked, deployed or owed in fees
+    ///
+    /// Vault balances are checked separately by `close_empty_market`.
+    pub fn is_empty(&self) -> bool {
+        self.total_supply_assets == 0
+            && self.total_supply_shares == 0
+            && self.total_borrow_assets == 0
+            && self.total_borrow_shares == 0
+            && self.total_stable_borrow_assets == 0
+            && self.pending_fee_shares == 0
+            && self.collateral_staked == 0
+            && self.loan_deployed == 0
+            && self.flash_loan_lock == 0
+            && self.insurance_pool == 0
+    }
+
+    /// Check `action` is open in the market's current modes
+    ///
+    /// Modes are checked paused, frozen, supply-only, withdraw-only, so a
+    /// market in several reports the first that blocks. Repaying and
+    /// liquidating stay open in every mode so positions can always exit.
+    pub fn check_action(&self, action: MarketAction) -> Result<()> {
+        use MarketAction::*;
+        if self.paused {
+            require!(matches!(action, Repay | Liquidate), MorphoError::MarketPaused);
+        }
+        if self.divergence_frozen {
+            require!(
+                !matches!(action, WithdrawCollateral | Borrow | MergeDebt),
+                MorphoError::MarketFrozen
+            );
+        }
+        if self.supply_only {
+            require!(!matches!(action, Borrow | FlashLoan), MorphoError::BorrowingDisabled);
+        }
+        if self.withdraw_only {
+            require!(!matches!(action, Supply | Borrow), MorphoError::MarketWithdrawOnly);
+        }
+        Ok(())
     }
 
     /// Check if market is operational (not paused)
@@ -143,12 +557,226 @@ impl Market {
     pub fn is_flash_loan_active(&self) -> bool {
         self.flash_loan_lock != 0
     }
+
+    /// Lock the market for a two-step flash loan and record who owes what
+    pub fn lock_flash_loan(&mut self, borrower: Pubkey, amount: u128) {
+        self.flash_loan_lock = 1;
+        self.flash_loan_borrower = borrower;
+        self.flash_loan_amount = amount;
+    }
+
+    /// Release the flash loan lock and clear the recorded loan
+    pub fn unlock_flash_loan(&mut self) {
+        self.flash_loan_lock = 0;
+        self.flash_loan_borrower = Pubkey::default();
+        self.flash_loan_amount = 0;
+    }
+
+    /// LLTV a position must stay under right after a borrow
+    ///
+    /// Liquidation still triggers at `lltv`; the buffer only keeps users from
+    /// borrowing straight to the liquidation edge.
+    pub fn borrow_lltv(&self) -> u64 {
+        self.buffered_lltv(self.lltv)
+    }
+
+    fn buffered_lltv(&self, lltv: u64) -> u64 {
+        if self.borrow_lltv_buffer == 0 {
+            return lltv;
+        }
+        ((lltv as u128 * self.borrow_lltv_buffer as u128) / BPS as u128) as u64
+    }
+
+    /// Check if `position` has earned the reputation boost at `now`
+    ///
+    /// A clean history means never liquidated, open for at least
+    /// `reputation_min_age`, and at least `reputation_min_repaid` of seasoned
+    /// debt repaid (see `Position::seasoned_repaid`): volume borrowed and
+    /// repaid within the window, e.g. in one transaction, earns nothing.
+    pub fn has_reputation_boost(&self, position: &Position, now: i64) -> bool {
+        self.reputation_lltv_boost_bps > 0
+            && position.times_liquidated == 0
+            && now.saturating_sub(position.opened_at) >= self.reputation_min_age
+            && position.seasoned_repaid >= self.reputation_min_repaid
+    }
+
+    /// Whether debt last borrowed by `position` has been held long enough at
+    /// `now` for its repayment to count toward the reputation boost
+    pub fn is_debt_seasoned(&self, position: &Position, now: i64) -> bool {
+        now.saturating_sub(position.last_borrowed_at) >= self.reputation_min_age
+    }
+
+    /// LLTV `position` is liquidated at, reputation boost included
+    pub fn position_lltv(&self, position: &Position, now: i64) -> u64 {
+        if self.has_reputation_boost(position, now) {
+            self.lltv + self.reputation_lltv_boost_bps
+        } else {
+            self.lltv
+        }
+    }
+
+    /// LLTV `position` must stay under right after a borrow, boost included
+    pub fn position_borrow_lltv(&self, position: &Position, now: i64) -> u64 {
+        self.buffered_lltv(self.position_lltv(position, now))
+    }
+
+    /// Stable-rate spread as a per-second rate (WAD-scaled)
+    pub fn stable_rate_spread(&self) -> Result<u128> {
+        mul_div_down(
+            self.stable_rate_spread_bps as u128,
+            WAD,
+            BPS as u128 * SECONDS_PER_YEAR,
+        )
+    }
+
+    /// Add `amount` of stable debt locked at `rate` to the aggregate
+    ///
+    /// `new_borrower` is set when the position held no stable debt before.
+    pub fn add_stable_debt(&mut self, amount: u128, rate: u128, new_borrower: bool) -> Result<()> {
+        let total = checked_add(self.total_stable_borrow_assets, amount)?;
+        // avg' = avg + (rate - avg) * amount / total'
+        self.avg_stable_rate = if rate >= self.avg_stable_rate {
+            checked_add(self.avg_stable_rate, mul_div_down(rate - self.avg_stable_rate, amount, total)?)?
+        } else {
+            checked_sub(self.avg_stable_rate, mul_div_down(self.avg_stable_rate - rate, amount, total)?)?
+        };
+        self.total_stable_borrow_assets = total;
+        if new_borrower {
+            self.stable_borrowers += 1;
+        }
+        Ok(())
+    }
+
+    /// Take `amount` of stable debt locked at `rate` out of the aggregate
+    ///
+    /// The aggregate compounds at the average rate, so it drifts from the sum
+    /// of the positions. Debt beyond the aggregate is interest it missed and
+    /// goes to suppliers; once the last stable borrower clears, whatever is
+    /// left was never owed and comes back out of supply.
+    pub fn remove_stable_debt(&mut self, amount: u128, rate: u128, borrower_cleared: bool) -> Result<()> {
+        let removed = std::cmp::min(amount, self.total_stable_borrow_assets);
+        let total = self.total_stable_borrow_assets - removed;
+        self.total_supply_assets = checked_add(self.total_supply_assets, amount - removed)?;
+        if borrower_cleared {
+            self.stable_borrowers = self.stable_borrowers.saturating_sub(1);
+        }
+
+        if self.stable_borrowers == 0 || total == 0 {
+            self.total_supply_assets = self.total_supply_assets.saturating_sub(total);
+            self.total_stable_borrow_assets = 0;
+            self.avg_stable_rate = 0;
+            return Ok(());
+        }
+
+        // avg' = avg + (avg - rate) * removed / total'
+        self.avg_stable_rate = if self.avg_stable_rate >= rate {
+            checked_add(self.avg_stable_rate, mul_div_down(self.avg_stable_rate - rate, removed, total)?)?
+        } else {
+            self.avg_stable_rate.saturating_sub(mul_div_down(rate - self.avg_stable_rate, removed, total)?)
+        };
+        self.total_stable_borrow_assets = total;
+        Ok(())
+    }
+
+    /// Move `amount` of stable debt from `old_rate` to `new_rate`
+    pub fn reprice_stable_debt(&mut self, amount: u128, old_rate: u128, new_rate: u128) -> Result<()> {
+        let total = self.total_stable_borrow_assets;
+        if total == 0 {
+            return Ok(());
+        }
+        let amount = std::cmp::min(amount, total);
+        self.avg_stable_rate = if new_rate >= old_rate {
+            checked_add(self.avg_stable_rate, mul_div_down(new_rate - old_rate, amount, total)?)?
+        } else {
+            self.avg_stable_rate.saturating_sub(mul_div_down(old_rate - new_rate, amount, total)?)
+        };
+        Ok(())
+    }
+
+    /// Check if idle collateral may be routed to a yield adapter
+    pub fn has_collateral_yield_adapter(&self) -> bool {
+        self.collateral_yield_adapter != Pubkey::default()
+    }
+
+    /// Largest amount of collateral that may be staked, given what's in the vault
+    ///
+    /// The remainder always stays liquid in the vault for withdrawals and
+    /// liquidations; anything beyond it can be recalled permissionlessly.
+    pub fn max_collateral_staked(&self, vault_balance: u128) -> Result<u128> {
+        let total = checked_add(vault_balance, self.collateral_staked)?;
+        mul_div_down(total, self.max_collateral_staked_bps as u128, BPS as u128)
+    }
+
+    /// Collateral the vault owes: positions, the insurance pool and
+    /// unclaimed reimbursements
+    pub fn collateral_owed(&self) -> Result<u128> {
+        checked_add(
+            checked_add(self.total_collateral, self.insurance_pool)?,
+            self.total_insurance_claimable,
+        )
+    }
+
+    /// Vault balance plus staked collateral, minus what's owed
+    ///
+    /// Zero when the books balance. Positive is surplus (e.g. a donation or
+    /// adapter yield); negative means the vault can't cover its positions.
+    pub fn collateral_drift(&self, vault_balance: u64) -> Result<i128> {
+        let held = checked_add(vault_balance as u128, self.collateral_staked)?;
+        let owed = self.collateral_owed()?;
+        let signed = |amount: u128| i128::try_from(amount).map_err(|_| error!(MorphoError::MathOverflow));
+        Ok(signed(held)? - signed(owed)?)
+    }
+
+    /// Loan tokens held beyond what the market owes suppliers
+    ///
+    /// Vault balance plus deployed liquidity, minus `available_liquidity`.
+    /// Every share conversion rounds in the protocol's favor, so the dust
+    /// collects here (along with any donations) until swept.
+    pub fn loan_surplus(&self, vault_balance: u64) -> Result<u128> {
+        let held = checked_add(vault_balance as u128, self.loan_deployed)?;
+        Ok(held.saturating_sub(self.available_liquidity()))
+    }
+
+    /// Book `surplus` loan tokens as supply owned by the protocol
+    ///
+    /// Mints its value in pending fee shares at the current share price (rounded
+    /// down), so other suppliers' share price doesn't drop. Returns the shares.
+    pub fn sweep_surplus(&mut self, surplus: u128) -> Result<u128> {
+        let shares = to_shares_down(surplus, self.total_supply_assets, self.total_supply_shares)?;
+        self.total_supply_assets = checked_add(self.total_supply_assets, surplus)?;
+        self.total_supply_shares = checked_add(self.total_supply_shares, shares)?;
+        self.pending_fee_shares = checked_add(self.pending_fee_shares, shares)?;
+        self.total_surplus_swept = checked_add(self.total_surplus_swept, surplus)?;
+        Ok(shares)
+    }
+
+    /// Check if idle loan liquidity may be deployed to a yield adapter
+    pub fn has_loan_yield_adapter(&self) -> bool {
+        self.loan_yield_adapter != Pubkey::default()
+    }
+
+    /// Largest amount of loan liquidity that may be deployed at once
+    pub fn max_loan_deployed(&self) -> Result<u128> {
+        mul_div_down(self.available_liquidity(), self.max_loan_deployed_bps as u128, BPS as u128)
+    }
+
+    /// Check that a flash_loan_end call settles the in-progress loan
+    pub fn matches_flash_loan(&self, borrower: &Pubkey, amount: u128) -> bool {
+        self.is_flash_loan_active()
+            && self.flash_loan_borrower == *borrower
+            && self.flash_loan_amount == amount
+    }
 }
 
 /// Calculate unique market identifier
 /// 
 /// Matches Morpho Blue's Id derivation using keccak256 hash
 /// of the market parameters.
+///
+/// The id hashes the parameters a market was created with. Oracle and IRM
+/// migrations keep it (and the market PDA), so it does not identify the
+/// market's current tuple: a tuple a migrated market has moved onto can
+/// still be used by `create_market`, giving a second, separate market.
 pub fn calculate_market_id(
     collateral_mint: &Pubkey,
     loan_mint: &Pubkey,
@@ -185,6 +813,16 @@ pub fn verify_market_id(
     market_id == &expected
 }
 
+/// Check if a mint with `mint_authority` could carry `market`'s supply shares
+///
+/// Policy: supply shares never count as collateral in their own market,
+/// since borrowing against them lets a supplier loop the same liquidity.
+/// Any share token (e.g. a future wrapper) is minted by the market PDA, so
+/// `create_market` rejects collateral mints under that authority.
+pub fn is_supply_share_mint(market: &Pubkey, mint_authority: Option<Pubkey>) -> bool {
+    mint_authority == Some(*market)
+}
+
 /// Derive market PDA
 pub fn derive_market(program_id: &Pubkey, market_id: &[u8; 32]) -> (Pubkey, u8) {
     Pubkey::find_program_address(
@@ -0,0 +1,64 @@
+//! Market display metadata
+//!
+//! Markets are identified by a hash of their parameters, which tells a UI
+//! nothing about what they're for. The market's creator may publish a name,
+//! a URI pointing at a longer description and the curator to credit, so
+//! frontends can label markets without an off-chain registry.
+
+use anchor_lang::prelude::*;
+use crate::constants::{PROGRAM_SEED_PREFIX, MAX_MARKET_NAME_LEN, MAX_MARKET_DESCRIPTION_URI_LEN};
+
+/// Human-readable information about one market
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_market_metadata", market_id]
+#[account]
+pub struct MarketMetadata {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Market described
+    pub market_id: [u8; 32],
+
+    /// Market creator, the only account allowed to update this
+    pub creator: Pubkey,
+
+    /// Display name (at most MAX_MARKET_NAME_LEN bytes)
+    pub name: String,
+
+    /// Link to a longer description (at most MAX_MARKET_DESCRIPTION_URI_LEN bytes)
+    pub description_uri: String,
+
+    /// Curator credited in UIs (informational, grants no permissions)
+    pub curator: Pubkey,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl MarketMetadata {
+    pub const SEED: &'static [u8] = b"morpho_market_metadata";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // market_id
+        32 +    // creator
+        4 + MAX_MARKET_NAME_LEN +               // name
+        4 + MAX_MARKET_DESCRIPTION_URI_LEN +    // description_uri
+        32 +    // curator
+        32      // reserved
+    }
+
+    /// Whether `name` and `description_uri` fit in the account
+    pub fn fits(name: &str, description_uri: &str) -> bool {
+        name.len() <= MAX_MARKET_NAME_LEN && description_uri.len() <= MAX_MARKET_DESCRIPTION_URI_LEN
+    }
+}
+
+/// Derive market metadata PDA
+pub fn derive_market_metadata(program_id: &Pubkey, market_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, MarketMetadata::SEED, market_id],
+        program_id,
+    )
+}
@@ -2,10 +2,32 @@
 
 pub mod protocol;
 pub mod market;
+pub mod market_metadata;
 pub mod position;
 pub mod authorization;
+pub mod proposal;
+pub mod flash_loan_allowlist;
+pub mod holding_exemption;
+pub mod credit_line;
+pub mod lltv_bounds;
+pub mod token_badge;
+pub mod liquidation_reference;
+pub mod clock_override;
+#[cfg(feature = "multi-collateral")]
+pub mod multi_collateral;
 
 pub use protocol::*;
 pub use market::*;
+pub use market_metadata::*;
 pub use position::*;
 pub use authorization::*;
+pub use proposal::*;
+pub use flash_loan_allowlist::*;
+pub use holding_exemption::*;
+pub use credit_line::*;
+pub use lltv_bounds::*;
+pub use token_badge::*;
+pub use liquidation_reference::*;
+pub use clock_override::*;
+#[cfg(feature = "multi-collateral")]
+pub use multi_collateral::*;
@@ -0,0 +1,250 @@
+//! Multi-collateral market accounts (experimental, `multi-collateral` feature)
+//!
+//! A loan token lent against up to MAX_MC_COLLATERALS collateral tokens,
+//! each with its own oracle and LLTV. A position is healthy while its debt
+//! stays within the sum of every collateral's value times that collateral's
+//! LLTV. Nothing here is shared with `Market`/`Position`: Blue markets never
+//! read or write these accounts.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use crate::constants::{PROGRAM_SEED_PREFIX, ORACLE_SCALE, BPS};
+use crate::math::{checked_add, mul_div_down, to_assets_up};
+
+/// Most collateral tokens one multi-collateral market accepts
+pub const MAX_MC_COLLATERALS: usize = 4;
+
+/// One accepted collateral token
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct McCollateral {
+    pub mint: Pubkey,
+
+    /// Price feed (prescaled, loan base units per collateral base unit)
+    pub oracle: Pubkey,
+
+    /// Share of this collateral's value that may be borrowed against (BPS)
+    pub lltv: u64,
+
+    pub decimals: u8,
+
+    /// Bump of this collateral's vault PDA
+    pub vault_bump: u8,
+
+    /// Collateral deposited across all positions (raw tokens)
+    pub total: u128,
+}
+
+impl McCollateral {
+    pub const SPACE: usize = 32 + 32 + 8 + 1 + 1 + 16;
+}
+
+/// Multi-collateral market
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_mc_market", market_id]
+#[account]
+pub struct McMarket {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// See `calculate_mc_market_id`
+    pub market_id: [u8; 32],
+
+    /// May add collaterals until the market takes its first supply
+    pub creator: Pubkey,
+
+    pub loan_mint: Pubkey,
+    pub loan_decimals: u8,
+    pub loan_vault_bump: u8,
+
+    /// Whitelisted IRM (rates come from the built-in curve, as in Blue markets)
+    pub irm: Pubkey,
+
+    /// Slots of `collaterals` in use, filled in order
+    pub collateral_count: u8,
+    pub collaterals: [McCollateral; MAX_MC_COLLATERALS],
+
+    pub total_supply_assets: u128,
+    pub total_supply_shares: u128,
+    pub total_borrow_assets: u128,
+    pub total_borrow_shares: u128,
+
+    /// Last interest accrual timestamp
+    pub last_update: i64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 64],
+}
+
+impl McMarket {
+    pub const SEED: &'static [u8] = b"morpho_mc_market";
+    pub const COLLATERAL_VAULT_SEED: &'static [u8] = b"morpho_mc_collateral_vault";
+    pub const LOAN_VAULT_SEED: &'static [u8] = b"morpho_mc_loan_vault";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // market_id
+        32 +    // creator
+        32 +    // loan_mint
+        1 +     // loan_decimals
+        1 +     // loan_vault_bump
+        32 +    // irm
+        1 +     // collateral_count
+        McCollateral::SPACE * MAX_MC_COLLATERALS + // collaterals
+        16 +    // total_supply_assets
+        16 +    // total_supply_shares
+        16 +    // total_borrow_assets
+        16 +    // total_borrow_shares
+        8 +     // last_update
+        64      // reserved
+    }
+
+    /// Collateral in slot `index`, if that slot is in use
+    pub fn collateral(&self, index: u8) -> Option<&McCollateral> {
+        self.collaterals[..self.collateral_count as usize].get(index as usize)
+    }
+
+    /// Vault bump for slot `index` (0 for unused slots, which no vault matches)
+    pub fn vault_bump(&self, index: u8) -> u8 {
+        self.collateral(index).map_or(0, |c| c.vault_bump)
+    }
+
+    pub fn available_liquidity(&self) -> u128 {
+        self.total_supply_assets.saturating_sub(self.total_borrow_assets)
+    }
+
+    /// Liquidity the loan vault can pay out right now
+    ///
+    /// Multi-collateral markets never deploy to a yield adapter, so there is
+    /// nothing to recall; this only guards against the vault running short
+    /// of the accounted liquidity.
+    pub fn vault_liquidity(&self, vault_balance: u64) -> u128 {
+        self.available_liquidity().min(vault_balance as u128)
+    }
+}
+
+/// A user's position in one multi-collateral market
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_mc_position", market_id, owner]
+#[account]
+pub struct McPosition {
+    /// PDA bump seed
+    pub bump: u8,
+
+    pub market_id: [u8; 32],
+    pub owner: Pubkey,
+
+    pub supply_shares: u128,
+    pub borrow_shares: u128,
+
+    /// Collateral per market slot (raw tokens)
+    pub collateral: [u128; MAX_MC_COLLATERALS],
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl McPosition {
+    pub const SEED: &'static [u8] = b"morpho_mc_position";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // market_id
+        32 +    // owner
+        16 +    // supply_shares
+        16 +    // borrow_shares
+        16 * MAX_MC_COLLATERALS + // collateral
+        32      // reserved
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.supply_shares == 0
+            && self.borrow_shares == 0
+            && self.collateral.iter().all(|&c| c == 0)
+    }
+}
+
+/// Debt `position` may carry: Σ collateral_i × price_i × lltv_i
+///
+/// `prices` holds the prescaled price of each market slot; unused slots and
+/// slots the position holds nothing in are skipped.
+pub fn mc_max_borrow(
+    market: &McMarket,
+    position: &McPosition,
+    prices: &[u128; MAX_MC_COLLATERALS],
+) -> Result<u128> {
+    let mut max_borrow = 0u128;
+    for (i, config) in market.collaterals[..market.collateral_count as usize].iter().enumerate() {
+        if position.collateral[i] == 0 {
+            continue;
+        }
+        let value = mul_div_down(position.collateral[i], prices[i], ORACLE_SCALE)?;
+        max_borrow = checked_add(max_borrow, mul_div_down(value, config.lltv as u128, BPS as u128)?)?;
+    }
+    Ok(max_borrow)
+}
+
+/// Check `position`'s debt is covered by its combined collateral
+pub fn is_mc_healthy(
+    market: &McMarket,
+    position: &McPosition,
+    prices: &[u128; MAX_MC_COLLATERALS],
+) -> Result<bool> {
+    if position.borrow_shares == 0 {
+        return Ok(true);
+    }
+    let borrowed = to_assets_up(
+        position.borrow_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )?;
+    Ok(borrowed <= mc_max_borrow(market, position, prices)?)
+}
+
+/// Multi-collateral market ID
+///
+/// Collaterals are added after creation, so the ID binds the loan side and a
+/// creator-chosen nonce. The domain tag keeps it out of Blue's ID space.
+pub fn calculate_mc_market_id(creator: &Pubkey, loan_mint: &Pubkey, irm: &Pubkey, nonce: u64) -> [u8; 32] {
+    let mut data = Vec::with_capacity(16 + 32 * 3 + 8);
+    data.extend_from_slice(b"multi_collateral");
+    data.extend_from_slice(creator.as_ref());
+    data.extend_from_slice(loan_mint.as_ref());
+    data.extend_from_slice(irm.as_ref());
+    data.extend_from_slice(&nonce.to_le_bytes());
+
+    keccak::hash(&data).to_bytes()
+}
+
+/// Derive multi-collateral market PDA
+pub fn derive_mc_market(program_id: &Pubkey, market_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, McMarket::SEED, market_id],
+        program_id,
+    )
+}
+
+/// Derive the vault PDA for collateral slot `index`
+pub fn derive_mc_collateral_vault(program_id: &Pubkey, market_id: &[u8; 32], index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, McMarket::COLLATERAL_VAULT_SEED, market_id, &[index]],
+        program_id,
+    )
+}
+
+/// Derive multi-collateral loan vault PDA
+pub fn derive_mc_loan_vault(program_id: &Pubkey, market_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, McMarket::LOAN_VAULT_SEED, market_id],
+        program_id,
+    )
+}
+
+/// Derive multi-collateral position PDA
+pub fn derive_mc_position(program_id: &Pubkey, market_id: &[u8; 32], owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, McPosition::SEED, market_id, owner.as_ref()],
+        program_id,
+    )
+}
@@ -4,7 +4,7 @@
 //! borrow, and collateral positions.
 
 use anchor_lang::prelude::*;
-use crate::constants::PROGRAM_SEED_PREFIX;
+use crate::constants::{PROGRAM_SEED_PREFIX, LIQUIDATION_COOLDOWN_SLOTS};
 
 /// User position in a specific market
 /// 
@@ -30,8 +30,90 @@ pub struct Position {
     /// Collateral does not earn interest in Morpho Blue
     pub collateral: u128,
 
+    /// Supply (loan token units) committed to credit lines this owner extends
+    /// Withdrawals may not take the supply position below this amount
+    pub credit_delegated: u128,
+
+    /// Protocol-owned supply shares (from seed_market) that can't be withdrawn yet
+    pub locked_supply_shares: u128,
+
+    /// Timestamp the locked supply shares become withdrawable
+    pub supply_locked_until: i64,
+
+    // === Lifetime Stats ===
+    // Track the owner's history for reputation scoring (e.g. credit lines)
+
+    /// Loan tokens supplied since creation
+    pub total_supplied: u128,
+
+    /// Loan tokens withdrawn from supply since creation
+    pub total_withdrawn: u128,
+
+    /// Loan tokens borrowed since creation
+    pub total_borrowed: u128,
+
+    /// Debt repaid by the owner (or on their behalf) since creation
+    /// Liquidations are counted in `times_liquidated` instead
+    pub total_repaid: u128,
+
+    /// Liquidations taken against this position
+    pub times_liquidated: u64,
+
+    /// Whether the market's reputation LLTV boost applied at the last sync
+    pub reputation_boosted: bool,
+
+    /// Opted into the market's liquidation insurance
+    pub insured: bool,
+
+    /// Timestamp insurance premiums have been charged up to
+    pub insurance_paid_until: i64,
+
+    /// Reimbursed liquidation penalty awaiting `claim_insurance` (collateral token units)
+    pub insurance_claimable: u128,
+
+    // === Stable-Rate Debt ===
+
+    /// Stable debt as of `stable_last_update` (loan token units, interest included)
+    pub stable_borrow_assets: u128,
+
+    /// Locked per-second rate on the stable debt (WAD-scaled)
+    pub stable_rate: u128,
+
+    /// Timestamp the stable debt was last accrued
+    pub stable_last_update: i64,
+
+    // === Liquidation Cooldown ===
+
+    /// Slot of the latest liquidation (0 = never liquidated)
+    pub last_liquidation_slot: u64,
+
+    /// Who performed it
+    pub last_liquidator: Pubkey,
+
+    // === Governance Escrow ===
+
+    /// Supply shares the owner escrowed with `lock_supply_shares`
+    /// Stay in the position until `unlock_supply_shares`, even after expiry
+    pub escrowed_supply_shares: u128,
+
+    /// Timestamp the escrowed shares may be unlocked
+    pub escrow_unlock_at: i64,
+
+    // === Reputation History ===
+
+    /// Timestamp the position was created (0 for positions migrated from
+    /// older layouts)
+    pub opened_at: i64,
+
+    /// Timestamp of the latest borrow
+    pub last_borrowed_at: i64,
+
+    /// Debt repaid after being held for the market's `reputation_min_age`;
+    /// only this counts toward the reputation boost
+    pub seasoned_repaid: u128,
+
     /// Reserved for future use
-    pub reserved: [u8; 64],
+    pub reserved: [u8; 6],
 }
 
 impl Position {
@@ -45,14 +127,39 @@ impl Position {
         16 +    // supply_shares
         16 +    // borrow_shares
         16 +    // collateral
-        64      // reserved
+        16 +    // credit_delegated
+        16 +    // locked_supply_shares
+        8 +     // supply_locked_until
+        16 +    // total_supplied
+        16 +    // total_withdrawn
+        16 +    // total_borrowed
+        16 +    // total_repaid
+        8 +     // times_liquidated
+        1 +     // reputation_boosted
+        1 +     // insured
+        8 +     // insurance_paid_until
+        16 +    // insurance_claimable
+        16 +    // stable_borrow_assets
+        16 +    // stable_rate
+        8 +     // stable_last_update
+        8 +     // last_liquidation_slot
+        32 +    // last_liquidator
+        16 +    // escrowed_supply_shares
+        8 +     // escrow_unlock_at
+        8 +     // opened_at
+        8 +     // last_borrowed_at
+        16 +    // seasoned_repaid
+        6       // reserved
     }
 
     /// Check if position has any activity
     pub fn is_empty(&self) -> bool {
         self.supply_shares == 0 && 
         self.borrow_shares == 0 && 
-        self.collateral == 0
+        self.collateral == 0 &&
+        self.credit_delegated == 0 &&
+        self.insurance_claimable == 0 &&
+        self.stable_borrow_assets == 0
     }
 
     /// Check if position can be closed (empty and initialized)
@@ -62,7 +169,35 @@ impl Position {
 
     /// Check if position has any debt
     pub fn has_debt(&self) -> bool {
-        self.borrow_shares > 0
+        self.borrow_shares > 0 || self.stable_borrow_assets > 0
+    }
+
+    /// Supply shares that must stay in the position at `now`
+    pub fn locked_supply_shares_at(&self, now: i64) -> u128 {
+        if now < self.supply_locked_until {
+            self.locked_supply_shares
+        } else {
+            0
+        }
+    }
+
+    /// Supply shares withdrawals must leave in the position at `now`
+    ///
+    /// Locked shares and escrowed shares are tracked apart: the lock lapses
+    /// on its own, the escrow only when its owner unlocks it.
+    pub fn unwithdrawable_supply_shares_at(&self, now: i64) -> u128 {
+        self.locked_supply_shares_at(now).saturating_add(self.escrowed_supply_shares)
+    }
+
+    /// Whether `liquidator` must wait before partially liquidating at `slot`
+    ///
+    /// Keeps competing liquidators from shredding a position into dust with
+    /// back-to-back partial liquidations, each charging the LIF. The last
+    /// liquidator may continue, and full closes are never held back.
+    pub fn liquidation_cooling_down(&self, liquidator: &Pubkey, slot: u64) -> bool {
+        self.last_liquidation_slot > 0
+            && self.last_liquidator != *liquidator
+            && slot < self.last_liquidation_slot.saturating_add(LIQUIDATION_COOLDOWN_SLOTS)
     }
 
     /// Check if position has any collateral
@@ -0,0 +1,216 @@
+//! Market proposal accounts
+//!
+//! A market's curator proposes parameter changes; the protocol owner approves
+//! them, at which point they apply to the market. At most one proposal is
+//! pending per market.
+//!
+//! Oracle and IRM migrations are proposed by the owner alone and only
+//! execute after `ORACLE_UPDATE_DELAY` / `IRM_UPDATE_DELAY`.
+
+use anchor_lang::prelude::*;
+use crate::constants::{PROGRAM_SEED_PREFIX, BPS, MAX_FEE};
+use crate::errors::MorphoError;
+use super::Market;
+
+/// Parameter changes carried by a proposal (`None` = leave unchanged)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarketConfigUpdate {
+    /// New protocol fee (basis points)
+    pub fee: Option<u64>,
+
+    /// New cap on staked collateral (basis points)
+    pub max_collateral_staked_bps: Option<u64>,
+
+    /// New cap on deployed loan liquidity (basis points)
+    pub max_loan_deployed_bps: Option<u64>,
+}
+
+impl MarketConfigUpdate {
+    pub const SPACE: usize = 3 * (1 + 8);
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check every field against the same bounds the direct setters use
+    pub fn validate(&self) -> Result<()> {
+        require!(!self.is_empty(), MorphoError::EmptyProposal);
+        if let Some(fee) = self.fee {
+            require!(fee <= MAX_FEE, MorphoError::FeeTooHigh);
+        }
+        if let Some(bps) = self.max_collateral_staked_bps {
+            require!(bps <= BPS, MorphoError::InvalidInput);
+        }
+        if let Some(bps) = self.max_loan_deployed_bps {
+            require!(bps <= BPS, MorphoError::InvalidInput);
+        }
+        Ok(())
+    }
+
+    /// Apply the update to a market
+    ///
+    /// Yield caps only apply to markets with the matching adapter configured.
+    pub fn apply(&self, market: &mut Market) -> Result<()> {
+        self.validate()?;
+        if let Some(fee) = self.fee {
+            market.fee = fee;
+        }
+        if let Some(bps) = self.max_collateral_staked_bps {
+            require!(market.has_collateral_yield_adapter(), MorphoError::YieldAdapterNotSet);
+            market.max_collateral_staked_bps = bps;
+        }
+        if let Some(bps) = self.max_loan_deployed_bps {
+            require!(market.has_loan_yield_adapter(), MorphoError::YieldAdapterNotSet);
+            market.max_loan_deployed_bps = bps;
+        }
+        Ok(())
+    }
+}
+
+/// Pending config proposal for one market
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_proposal", market_id]
+#[account]
+pub struct ConfigProposal {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Market this proposal targets
+    pub market_id: [u8; 32],
+
+    /// Curator that created the proposal (receives rent back on close)
+    pub proposer: Pubkey,
+
+    /// Proposal creation timestamp
+    pub created_at: i64,
+
+    /// Proposed changes
+    pub config: MarketConfigUpdate,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl ConfigProposal {
+    pub const SEED: &'static [u8] = b"morpho_proposal";
+
+    pub fn space() -> usize {
+        8 +                             // discriminator
+        1 +                             // bump
+        32 +                            // market_id
+        32 +                            // proposer
+        8 +                             // created_at
+        MarketConfigUpdate::SPACE +     // config
+        32                              // reserved
+    }
+}
+
+/// Derive config proposal PDA
+pub fn derive_config_proposal(program_id: &Pubkey, market_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, ConfigProposal::SEED, market_id],
+        program_id,
+    )
+}
+
+/// Pending switch of a market's oracle
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_oracle_update", market_id]
+#[account]
+pub struct OracleUpdateProposal {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Market whose oracle is being replaced
+    pub market_id: [u8; 32],
+
+    /// Feed the market will price from
+    pub new_oracle: Pubkey,
+
+    /// Proposal creation timestamp
+    pub proposed_at: i64,
+
+    /// Earliest time the update may execute
+    pub executable_at: i64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl OracleUpdateProposal {
+    pub const SEED: &'static [u8] = b"morpho_oracle_update";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // market_id
+        32 +    // new_oracle
+        8 +     // proposed_at
+        8 +     // executable_at
+        32      // reserved
+    }
+
+    /// Whether the delay has run out at `now`
+    pub fn is_executable(&self, now: i64) -> bool {
+        now >= self.executable_at
+    }
+}
+
+/// Derive oracle update proposal PDA
+pub fn derive_oracle_update_proposal(program_id: &Pubkey, market_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, OracleUpdateProposal::SEED, market_id],
+        program_id,
+    )
+}
+
+/// Pending switch of a market's IRM
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_irm_update", market_id]
+#[account]
+pub struct IrmUpdateProposal {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Market whose IRM is being replaced
+    pub market_id: [u8; 32],
+
+    /// IRM the market will price borrows with
+    pub new_irm: Pubkey,
+
+    /// Proposal creation timestamp
+    pub proposed_at: i64,
+
+    /// Earliest time the update may execute
+    pub executable_at: i64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl IrmUpdateProposal {
+    pub const SEED: &'static [u8] = b"morpho_irm_update";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // market_id
+        32 +    // new_irm
+        8 +     // proposed_at
+        8 +     // executable_at
+        32      // reserved
+    }
+
+    /// Whether the delay has run out at `now`
+    pub fn is_executable(&self, now: i64) -> bool {
+        now >= self.executable_at
+    }
+}
+
+/// Derive IRM update proposal PDA
+pub fn derive_irm_update_proposal(program_id: &Pubkey, market_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, IrmUpdateProposal::SEED, market_id],
+        program_id,
+    )
+}
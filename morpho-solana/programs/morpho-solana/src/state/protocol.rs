@@ -2,10 +2,15 @@
 //! 
 //! Single global account managing protocol-wide settings,
 //! whitelisted parameters, and ownership.
+//!
+//! The LLTV and IRM whitelists are unbounded `Vec`s stored last in the
+//! account; the account is reallocated as they grow. Accounts created with
+//! the original fixed-array layout are converted by `migrate_protocol_state`.
 
 use anchor_lang::prelude::*;
-use crate::constants::{MAX_LLTVS, MAX_IRMS, PROGRAM_SEED_PREFIX};
+use crate::constants::{BPS, MAX_LLTVS, MAX_IRMS, MAX_YIELD_ADAPTERS, PROGRAM_SEED_PREFIX};
 use crate::errors::MorphoError;
+use crate::state::{Market, Position};
 
 /// Protocol-wide state account
 /// 
@@ -27,88 +32,267 @@ pub struct ProtocolState {
     /// Global pause flag - stops all user operations across all markets
     pub paused: bool,
 
-    /// Number of enabled LLTVs (active count in the array)
-    pub lltv_count: u8,
+    /// Total markets created (for stats)
+    pub market_count: u64,
 
-    /// Whitelisted LLTV values (basis points, e.g., 8500 = 85%)
-    /// Fixed-size array for predictable account size
-    pub enabled_lltvs: [u64; MAX_LLTVS],
+    /// Number of enabled yield adapters
+    pub yield_adapter_count: u8,
 
-    /// Number of enabled IRMs
-    pub irm_count: u8,
+    /// Whitelisted yield adapter programs (venues idle vault funds may be routed to)
+    pub enabled_yield_adapters: [Pubkey; MAX_YIELD_ADAPTERS],
 
-    /// Whitelisted IRM program addresses
-    /// Fixed-size array for predictable account size
-    pub enabled_irms: [Pubkey; MAX_IRMS],
+    /// Flash loan kill switch, independent of the global pause
+    /// Stored inverted so the zeroed bytes of older accounts read as "on"
+    pub flash_loans_disabled: bool,
 
-    /// Total markets created (for stats)
-    pub market_count: u64,
+    /// Markets closed by `close_empty_market` (live = market_count - closed)
+    pub closed_market_count: u64,
+
+    /// Key that may claim ownership once the owner goes silent (default = none)
+    pub recovery_key: Pubkey,
+
+    /// Last owner heartbeat (Unix timestamp)
+    pub last_owner_heartbeat: i64,
+
+    /// Owner silence (seconds) after which the recovery key may claim ownership
+    pub owner_recovery_delay: i64,
+
+    /// Share of the borrow LLTV protocol-owned positions may borrow up to
+    /// (basis points, 0 = same as everyone)
+    pub protocol_borrow_lltv_buffer: u64,
+
+    /// Live markets individually paused by the owner
+    ///
+    /// Only counts pauses made since the field was added; pauses before
+    /// that are missed, so unpausing those saturates at zero.
+    pub paused_market_count: u64,
+
+    /// External oracle program new markets may price through by CPI (see
+    /// `interfaces::oracle_program`; default = none)
+    ///
+    /// The reserved space fits one; several providers can sit behind a
+    /// router program.
+    pub oracle_program: Pubkey,
 
     /// Reserved for future upgrades
-    pub reserved: [u8; 256],
+    pub reserved: [u8; 22],
+
+    /// Whitelisted LLTV values (basis points, e.g., 8500 = 85%)
+    /// Kept after the fixed-size fields so their offsets never move
+    pub enabled_lltvs: Vec<u64>,
+
+    /// Whitelisted IRM program addresses
+    pub enabled_irms: Vec<Pubkey>,
 }
 
 impl ProtocolState {
     pub const SEED: &'static [u8] = b"morpho_protocol";
 
+    /// Account size with empty LLTV and IRM lists (used at initialization)
     pub fn space() -> usize {
+        Self::space_for(0, 0)
+    }
+
+    /// Account size holding `lltvs` LLTVs and `irms` IRMs
+    pub fn space_for(lltvs: usize, irms: usize) -> usize {
         8 +                     // discriminator
         1 +                     // bump
         32 +                    // owner
         32 +                    // pending_owner
         32 +                    // fee_recipient
         1 +                     // paused
-        1 +                     // lltv_count
-        (8 * MAX_LLTVS) +       // enabled_lltvs
-        1 +                     // irm_count
-        (32 * MAX_IRMS) +       // enabled_irms
         8 +                     // market_count
-        256                     // reserved
+        1 +                     // yield_adapter_count
+        (32 * MAX_YIELD_ADAPTERS) + // enabled_yield_adapters
+        1 +                     // flash_loans_disabled
+        8 +                     // closed_market_count
+        32 +                    // recovery_key
+        8 +                     // last_owner_heartbeat
+        8 +                     // owner_recovery_delay
+        8 +                     // protocol_borrow_lltv_buffer
+        8 +                     // paused_market_count
+        32 +                    // oracle_program
+        22 +                    // reserved
+        4 + (8 * lltvs) +       // enabled_lltvs
+        4 + (32 * irms)         // enabled_irms
+    }
+
+    /// Size this account needs for its current lists
+    pub fn current_space(&self) -> usize {
+        Self::space_for(self.enabled_lltvs.len(), self.enabled_irms.len())
+    }
+
+    /// Check if the recovery key may claim ownership at `now`
+    pub fn can_recover_ownership(&self, now: i64) -> bool {
+        self.recovery_key != Pubkey::default()
+            && now >= self.last_owner_heartbeat.saturating_add(self.owner_recovery_delay)
+    }
+
+    /// Markets created and not yet closed
+    pub fn live_market_count(&self) -> u64 {
+        self.market_count.saturating_sub(self.closed_market_count)
+    }
+
+    /// Keep `paused_market_count` in step with a market going from
+    /// `was_paused` to `paused`
+    pub fn track_market_pause(&mut self, was_paused: bool, paused: bool) {
+        match (was_paused, paused) {
+            (false, true) => self.paused_market_count = self.paused_market_count.saturating_add(1),
+            (true, false) => self.paused_market_count = self.paused_market_count.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    /// Check if `position` is protocol-owned
+    ///
+    /// That is the fee recipient's position, where `seed_market` liquidity
+    /// and claimed fees land.
+    pub fn is_protocol_owned(&self, position: &Position) -> bool {
+        position.owner == self.fee_recipient
+    }
+
+    /// LLTV `position` must stay under right after a borrow
+    ///
+    /// Protocol-owned positions are held to `protocol_borrow_lltv_buffer` of
+    /// the market's borrow LLTV, so the treasury can't lever up against its
+    /// own liquidity. Liquidation treats every position alike.
+    pub fn position_borrow_lltv(&self, market: &Market, position: &Position, now: i64) -> u64 {
+        let lltv = market.position_borrow_lltv(position, now);
+        if self.protocol_borrow_lltv_buffer == 0 || !self.is_protocol_owned(position) {
+            return lltv;
+        }
+        ((lltv as u128 * self.protocol_borrow_lltv_buffer as u128) / BPS as u128) as u64
     }
 
     /// Check if an LLTV value is whitelisted
     pub fn is_lltv_enabled(&self, lltv: u64) -> bool {
-        self.enabled_lltvs[..self.lltv_count as usize].contains(&lltv)
+        self.enabled_lltvs.contains(&lltv)
+    }
+
+    /// Check flash loans haven't been switched off
+    pub fn flash_loans_enabled(&self) -> bool {
+        !self.flash_loans_disabled
     }
 
     /// Check if an IRM program is whitelisted
     pub fn is_irm_enabled(&self, irm: &Pubkey) -> bool {
-        self.enabled_irms[..self.irm_count as usize].contains(irm)
+        self.enabled_irms.contains(irm)
+    }
+
+    /// Check if `program` is the allowlisted external oracle program
+    pub fn is_oracle_program_enabled(&self, program: &Pubkey) -> bool {
+        self.oracle_program != Pubkey::default() && self.oracle_program == *program
+    }
+
+    /// Check if a yield adapter program is whitelisted
+    pub fn is_yield_adapter_enabled(&self, adapter: &Pubkey) -> bool {
+        self.enabled_yield_adapters[..self.yield_adapter_count as usize].contains(adapter)
     }
 
     /// Add a new LLTV to the whitelist
+    ///
+    /// The caller must make room for it (see `current_space`).
     pub fn add_lltv(&mut self, lltv: u64) -> Result<()> {
-        require!(
-            (self.lltv_count as usize) < MAX_LLTVS,
-            MorphoError::MaxLltvsReached
-        );
         require!(
             !self.is_lltv_enabled(lltv),
             MorphoError::AlreadyEnabled
         );
 
-        self.enabled_lltvs[self.lltv_count as usize] = lltv;
-        self.lltv_count += 1;
+        self.enabled_lltvs.push(lltv);
         Ok(())
     }
 
     /// Add a new IRM to the whitelist
+    ///
+    /// The caller must make room for it (see `current_space`).
     pub fn add_irm(&mut self, irm: Pubkey) -> Result<()> {
         require!(
-            (self.irm_count as usize) < MAX_IRMS,
-            MorphoError::MaxIrmsReached
+            !self.is_irm_enabled(&irm),
+            MorphoError::AlreadyEnabled
+        );
+
+        self.enabled_irms.push(irm);
+        Ok(())
+    }
+
+    /// Add a new yield adapter to the whitelist
+    ///
+    /// Morpho itself is never an adapter: routing a market's vaults into
+    /// Morpho supply would let its supply shares back its own collateral.
+    pub fn add_yield_adapter(&mut self, adapter: Pubkey) -> Result<()> {
+        require!(adapter != crate::ID, MorphoError::SelfCollateralization);
+        require!(
+            (self.yield_adapter_count as usize) < MAX_YIELD_ADAPTERS,
+            MorphoError::MaxYieldAdaptersReached
         );
         require!(
-            !self.is_irm_enabled(&irm),
+            !self.is_yield_adapter_enabled(&adapter),
             MorphoError::AlreadyEnabled
         );
 
-        self.enabled_irms[self.irm_count as usize] = irm;
-        self.irm_count += 1;
+        self.enabled_yield_adapters[self.yield_adapter_count as usize] = adapter;
+        self.yield_adapter_count += 1;
         Ok(())
     }
 }
 
+/// Protocol state as laid out before the whitelists became dynamic
+///
+/// Only read by `migrate_protocol_state`.
+#[derive(AnchorDeserialize)]
+pub struct ProtocolStateV1 {
+    pub bump: u8,
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub paused: bool,
+    pub lltv_count: u8,
+    pub enabled_lltvs: [u64; MAX_LLTVS],
+    pub irm_count: u8,
+    pub enabled_irms: [Pubkey; MAX_IRMS],
+    pub market_count: u64,
+    pub yield_adapter_count: u8,
+    pub enabled_yield_adapters: [Pubkey; MAX_YIELD_ADAPTERS],
+    pub flash_loans_disabled: bool,
+    pub reserved: [u8; 126],
+}
+
+impl ProtocolStateV1 {
+    /// Size of a fixed-layout account (852 bytes). Dynamic layouts are 378
+    /// bytes plus a multiple of 8, so the length alone identifies an
+    /// unmigrated account.
+    pub const SPACE: usize = 8 + 1 + 32 + 32 + 32 + 1
+        + 1 + (8 * MAX_LLTVS)
+        + 1 + (32 * MAX_IRMS)
+        + 8 + 1 + (32 * MAX_YIELD_ADAPTERS) + 1 + 126;
+
+    /// Convert to the dynamic layout, keeping only the active entries
+    pub fn into_current(self) -> ProtocolState {
+        ProtocolState {
+            bump: self.bump,
+            owner: self.owner,
+            pending_owner: self.pending_owner,
+            fee_recipient: self.fee_recipient,
+            paused: self.paused,
+            market_count: self.market_count,
+            yield_adapter_count: self.yield_adapter_count,
+            enabled_yield_adapters: self.enabled_yield_adapters,
+            flash_loans_disabled: self.flash_loans_disabled,
+            closed_market_count: 0,
+            recovery_key: Pubkey::default(),
+            last_owner_heartbeat: 0,
+            owner_recovery_delay: 0,
+            protocol_borrow_lltv_buffer: 0,
+            paused_market_count: 0,
+            oracle_program: Pubkey::default(),
+            // The legacy layout never wrote its reserved bytes
+            reserved: [0u8; 22],
+            enabled_lltvs: self.enabled_lltvs[..self.lltv_count as usize].to_vec(),
+            enabled_irms: self.enabled_irms[..self.irm_count as usize].to_vec(),
+        }
+    }
+}
+
 /// Derive protocol state PDA
 pub fn derive_protocol_state(program_id: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
@@ -0,0 +1,87 @@
+//! Collateral quality tiers
+//!
+//! The owner grades mints into tiers, and new markets may only lend against
+//! a collateral up to its tier's LLTV cap. Banned mints can't be collateral
+//! at all. Mints without a badge are held to tier C, so a permissionless
+//! market can't pair a top LLTV with an unvetted token.
+
+use anchor_lang::prelude::*;
+use crate::constants::{PROGRAM_SEED_PREFIX, TIER_A_MAX_LLTV, TIER_B_MAX_LLTV, TIER_C_MAX_LLTV};
+use crate::errors::MorphoError;
+
+/// Quality grade of a collateral mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenTier {
+    A,
+    B,
+    C,
+    Banned,
+}
+
+impl TokenTier {
+    /// Highest LLTV a new market may use against collateral of this tier
+    pub fn max_lltv(&self) -> Option<u64> {
+        match self {
+            TokenTier::A => Some(TIER_A_MAX_LLTV),
+            TokenTier::B => Some(TIER_B_MAX_LLTV),
+            TokenTier::C => Some(TIER_C_MAX_LLTV),
+            TokenTier::Banned => None,
+        }
+    }
+
+    /// Check a new market may lend against this tier at `lltv`
+    pub fn check_lltv(&self, lltv: u64) -> Result<()> {
+        let max_lltv = self.max_lltv().ok_or(MorphoError::MintBanned)?;
+        require!(lltv <= max_lltv, MorphoError::LltvAboveTierCap);
+        Ok(())
+    }
+}
+
+/// Owner-assigned grade of a mint
+///
+/// PDA Seeds: [PROGRAM_SEED_PREFIX, b"morpho_token_badge", mint]
+#[account]
+pub struct TokenBadge {
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Mint the badge grades
+    pub mint: Pubkey,
+
+    /// Assigned tier
+    pub tier: TokenTier,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl TokenBadge {
+    pub const SEED: &'static [u8] = b"morpho_token_badge";
+
+    pub fn space() -> usize {
+        8 +     // discriminator
+        1 +     // bump
+        32 +    // mint
+        1 +     // tier
+        32      // reserved
+    }
+
+    /// Tier of the badge stored at `account`, tier C if there's none
+    ///
+    /// `account` must already be checked to be the mint's badge PDA.
+    pub fn load_tier(account: &AccountInfo) -> Result<TokenTier> {
+        if account.owner != &crate::ID || account.data_is_empty() {
+            return Ok(TokenTier::C);
+        }
+        let data = account.try_borrow_data()?;
+        Ok(Self::try_deserialize(&mut &data[..])?.tier)
+    }
+}
+
+/// Derive token badge PDA for a mint
+pub fn derive_token_badge(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PROGRAM_SEED_PREFIX, TokenBadge::SEED, mint.as_ref()],
+        program_id,
+    )
+}
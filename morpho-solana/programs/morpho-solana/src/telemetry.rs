@@ -0,0 +1,53 @@
+//! Structured telemetry logs (`telemetry` feature)
+//!
+//! With the feature on, core instructions log one compact line each:
+//!
+//! `tm op=borrow mkt=1a2b3c4d cu_left=171234 assets=1000000 shares=999999`
+//!
+//! `mkt` is the first 4 bytes of the market id (hex) and `cu_left` the
+//! compute units remaining when the line was written. Without the feature
+//! `telemetry!` expands to nothing, so release builds pay no compute for it.
+//! The feature is for test clusters only and can't be combined with mainnet.
+
+#[cfg(all(feature = "telemetry", not(any(feature = "devnet", feature = "localnet"))))]
+compile_error!("the `telemetry` feature requires `devnet` or `localnet`");
+
+/// Log a telemetry line for `op` on `market_id` with `key = value` amounts
+#[macro_export]
+macro_rules! telemetry {
+    ($op:expr, $market_id:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        #[cfg(feature = "telemetry")]
+        {
+            ::anchor_lang::prelude::msg!(
+                concat!("tm op={} mkt={} cu_left={}" $(, " ", stringify!($key), "={}")*),
+                $op,
+                $crate::telemetry::short_id(&$market_id),
+                $crate::telemetry::remaining_compute_units(),
+                $($value),*
+            );
+        }
+    };
+}
+
+/// First 4 bytes of a market id, hex-encoded
+#[cfg(feature = "telemetry")]
+pub fn short_id(market_id: &[u8; 32]) -> String {
+    market_id[..4].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(feature = "telemetry")]
+pub fn remaining_compute_units() -> u64 {
+    anchor_lang::solana_program::compute_units::sol_remaining_compute_units()
+}
+
+#[cfg(all(test, feature = "telemetry"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_id() {
+        let mut market_id = [0xffu8; 32];
+        market_id[..4].copy_from_slice(&[0x1a, 0x2b, 0x03, 0x00]);
+        assert_eq!(short_id(&market_id), "1a2b0300");
+    }
+}
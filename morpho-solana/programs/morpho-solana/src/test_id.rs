@@ -0,0 +1,59 @@
+//! Deterministic program ID for local tests
+//!
+//! Building with `--features test-id` swaps the program's `declare_id!` for
+//! `TEST_PROGRAM_ID`, whose keypair is checked in at `KEYPAIR_PATH` so a
+//! local validator can deploy to the same address every run:
+//!
+//! ```text
+//! anchor build -- --features test-id
+//! solana program deploy target/deploy/morpho_solana.so \
+//!     --program-id programs/morpho-solana/tests/fixtures/test-id-keypair.json
+//! ```
+//!
+//! Tests should address the program as `crate::ID` and load the binary
+//! built with the same features, e.g.
+//! `svm.add_program(morpho_solana::ID, &program_bytes()?)`. The keypair
+//! secures nothing; never deploy a `test-id` build outside localnet.
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::*;
+
+/// Program ID of `test-id` builds
+pub const TEST_PROGRAM_ID: Pubkey = pubkey!("7cPHtvtzZCFc44HZoZwMd2nemBayrfNof9MTFX6AjjEQ");
+
+/// Solana CLI keypair (JSON byte array) behind `TEST_PROGRAM_ID`
+pub const KEYPAIR_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/test-id-keypair.json");
+
+/// Where `anchor build` leaves the program binary
+pub fn program_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy/morpho_solana.so")
+}
+
+/// Program binary to load into LiteSVM under `crate::ID`
+pub fn program_bytes() -> std::io::Result<Vec<u8>> {
+    std::fs::read(program_path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keypair_backs_test_program_id() {
+        let json = std::fs::read_to_string(KEYPAIR_PATH).unwrap();
+        let bytes: Vec<u8> = json
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(',')
+            .map(|b| b.trim().parse().unwrap())
+            .collect();
+        // Secret key followed by the public key
+        assert_eq!(bytes.len(), 64);
+        assert_eq!(Pubkey::try_from(&bytes[32..]).unwrap(), TEST_PROGRAM_ID);
+
+        #[cfg(feature = "test-id")]
+        assert_eq!(crate::ID, TEST_PROGRAM_ID);
+    }
+}
@@ -0,0 +1,48 @@
+//! Time source for market bookkeeping
+//!
+//! Handlers that accrue interest or age a position read the time through
+//! `current_clock` rather than `Clock::get`. With the `devnet` feature, a
+//! `ClockOverride` anywhere among the instruction's remaining accounts
+//! replaces the sysvar's timestamp for that instruction only, provided the
+//! override's authority signs among them too; the slot and epoch stay the
+//! sysvar's. Oracle cranks, governance timelocks and owner
+//! heartbeats always read the sysvar, since they're checked against time
+//! outside the program. Without the feature the override is ignored.
+
+use anchor_lang::prelude::*;
+use crate::errors::MorphoError;
+use crate::state::{is_clock_override, ClockOverride};
+
+/// The clock an instruction runs at
+pub fn current_clock(remaining_accounts: &[AccountInfo]) -> Result<Clock> {
+    let clock = Clock::get()?;
+    #[cfg(feature = "devnet")]
+    if let Some(unix_timestamp) = override_timestamp(remaining_accounts)? {
+        return Ok(Clock { unix_timestamp, ..clock });
+    }
+    #[cfg(not(feature = "devnet"))]
+    let _ = remaining_accounts;
+    Ok(clock)
+}
+
+/// Timestamp of the `ClockOverride` among `accounts`, if one was passed
+///
+/// Only the owner's drill instruction creates one, so a program-owned
+/// account with its discriminator is the override PDA. Passing it without
+/// its authority as a signer fails rather than running at the real time.
+pub fn override_timestamp(accounts: &[AccountInfo]) -> Result<Option<i64>> {
+    for account in accounts {
+        if !is_clock_override(account)? {
+            continue;
+        }
+        let clock_override = ClockOverride::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+        require!(
+            accounts
+                .iter()
+                .any(|signer| signer.is_signer && signer.key() == clock_override.authority),
+            MorphoError::Unauthorized
+        );
+        return Ok(Some(clock_override.unix_timestamp));
+    }
+    Ok(None)
+}
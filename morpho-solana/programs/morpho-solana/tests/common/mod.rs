@@ -0,0 +1,17 @@
+//! Account fixtures shared by the integration test crates
+//!
+//! Tests start from `test_market()` and override only the fields they
+//! exercise (`Market { fee: 1000, ..test_market() }`), so new Market fields
+//! only need a default here.
+
+use morpho_solana::state::Market;
+
+/// An empty 85% LLTV market lending a 6-decimal token against a 9-decimal one
+pub fn test_market() -> Market {
+    Market {
+        collateral_decimals: 9,
+        loan_decimals: 6,
+        lltv: 8500,
+        ..Market::default()
+    }
+}
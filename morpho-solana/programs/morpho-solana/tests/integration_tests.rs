@@ -3,6 +3,8 @@
 //! Comprehensive end-to-end tests for the Morpho Blue lending protocol on Solana.
 //! Uses LiteSVM for fast, in-process Solana runtime execution.
 
+mod common;
+
 use litesvm::LiteSVM;
 use anchor_lang::solana_program::{
     clock::Clock,
@@ -13,14 +15,19 @@ use anchor_lang::solana_program::{
 use morpho_solana::constants::{
     PROGRAM_SEED_PREFIX, BPS, WAD, ORACLE_SCALE, MAX_FEE, FLASH_LOAN_FEE_BPS,
     VIRTUAL_SHARES, VIRTUAL_ASSETS, MAX_LIF, LIF_BPS, MAX_LLTVS, MAX_IRMS, LIF_CURSOR,
+    MIN_OWNER_RECOVERY_DELAY_DAYS, SECONDS_PER_DAY, MAX_MARKET_NAME_LEN, ORACLE_UPDATE_DELAY,
 };
 use morpho_solana::state::{
-    ProtocolState, Market, Position, Authorization,
+    ProtocolState, ProtocolStateV1, Market, MarketAction, MarketMetadata, Position, Authorization, MarketConfigUpdate,
     calculate_market_id, derive_protocol_state, derive_market,
-    derive_position,
+    derive_position, HoldingPeriodExemption, derive_holding_period_exemption,
 };
 use morpho_solana::math::*;
-use morpho_solana::interfaces::calculate_lif;
+use morpho_solana::test_id::program_bytes;
+use common::test_market;
+use morpho_solana::interfaces::{
+    calculate_lif, calculate_seized_collateral, health_factor, is_liquidatable, prescale_price,
+};
 
 use solana_sdk::signature::{Keypair, Signer as SolanaSigner};
 use solana_sdk::transaction::Transaction;
@@ -52,10 +59,9 @@ const LLTV_80_PERCENT: u64 = 8000;
 // Test Environment
 // ============================================================================
 
-/// Program ID for Morpho Solana (must match declare_id! in lib.rs)
+/// Program ID for Morpho Solana (`test_id::TEST_PROGRAM_ID` under `test-id`)
 fn program_id() -> Pubkey {
-    // Using a deterministic ID for testing
-    "MorphoXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX".parse().unwrap()
+    morpho_solana::ID
 }
 
 /// Test environment containing LiteSVM and test accounts
@@ -104,8 +110,8 @@ impl TestEnv {
 
         // Load the Morpho program
         let program_id = program_id();
-        let program_bytes = include_bytes!("../../../target/deploy/morpho_solana.so");
-        svm.add_program(program_id, program_bytes);
+        let program_bytes = program_bytes().expect("program binary missing; run `anchor build`");
+        svm.add_program(program_id, &program_bytes);
 
         TestEnv {
             svm,
@@ -413,6 +419,21 @@ mod unit_tests {
 
         assert_ne!(pos1, pos2, "Different owners should have different position PDAs");
     }
+
+    #[test]
+    fn test_holding_exemption_pda_is_protocol_wide() {
+        let program_id = Pubkey::new_unique();
+        let vault_program = Pubkey::new_unique();
+
+        let (exemption, _) = derive_holding_period_exemption(&program_id, &vault_program);
+        let (position, _) = derive_position(&program_id, &[1u8; 32], &vault_program);
+        let (other, _) = derive_holding_period_exemption(&program_id, &Pubkey::new_unique());
+
+        // One entry per account, shared by every market
+        assert_ne!(exemption, position);
+        assert_ne!(exemption, other);
+        assert_eq!(HoldingPeriodExemption::space(), 8 + 1 + 32 + 32);
+    }
 }
 
 // ============================================================================
@@ -474,27 +495,12 @@ mod math_validation_tests {
     fn test_interest_accrual_over_time() {
         // Create a mock market state
         let mut market = Market {
-            bump: 0,
-            market_id: [0u8; 32],
-            collateral_mint: Pubkey::default(),
-            loan_mint: Pubkey::default(),
-            collateral_decimals: 9,
-            loan_decimals: 6,
-            oracle: Pubkey::default(),
-            irm: Pubkey::default(),
-            lltv: 8500,
-            paused: false,
-            fee: 1000, // 10% fee
-            total_supply_assets: 10_000_000_000_000, // 10M
-            total_supply_shares: 10_000_000_000_000_000_000, // 10e18
-            total_borrow_assets: 5_000_000_000_000, // 5M borrowed
-            total_borrow_shares: 5_000_000_000_000_000_000, // 5e18
-            last_update: 0,
-            pending_fee_shares: 0,
-            collateral_vault_bump: 0,
-            loan_vault_bump: 0,
-            flash_loan_lock: 0,
-            reserved: [0u8; 127],
+            fee: 1000,
+            total_supply_assets: 10_000_000_000_000,
+            total_supply_shares: 10_000_000_000_000_000_000,
+            total_borrow_assets: 5_000_000_000_000,
+            total_borrow_shares: 5_000_000_000_000_000_000,
+            ..test_market()
         };
 
         let initial_supply = market.total_supply_assets;
@@ -518,27 +524,10 @@ mod math_validation_tests {
     #[test]
     fn test_no_interest_when_no_borrows() {
         let mut market = Market {
-            bump: 0,
-            market_id: [0u8; 32],
-            collateral_mint: Pubkey::default(),
-            loan_mint: Pubkey::default(),
-            collateral_decimals: 9,
-            loan_decimals: 6,
-            oracle: Pubkey::default(),
-            irm: Pubkey::default(),
-            lltv: 8500,
-            paused: false,
             fee: 1000,
             total_supply_assets: 10_000_000_000_000,
             total_supply_shares: 10_000_000_000_000_000_000,
-            total_borrow_assets: 0, // No borrows
-            total_borrow_shares: 0,
-            last_update: 0,
-            pending_fee_shares: 0,
-            collateral_vault_bump: 0,
-            loan_vault_bump: 0,
-            flash_loan_lock: 0,
-            reserved: [0u8; 127],
+            ..test_market()
         };
 
         let rate = WAD / 10 / 31_536_000;
@@ -567,30 +556,250 @@ mod math_validation_tests {
         assert!(lif_85 > lif_90, "Higher LLTV should have lower LIF");
     }
 
+    #[test]
+    fn test_seize_guard_catches_scaling_bugs() {
+        use morpho_solana::interfaces::seize_within_max_lif;
+
+        // ETH (9 decimals) at $2000 against USDC (6 decimals), prescaled
+        let price = 2 * ORACLE_SCALE;
+        for lltv in [3_850u64, 8_500, 9_800] {
+            for repaid in [1u128, 999, 2_000_000_000, 1_000_000_000_000] {
+                let seized = calculate_seized_collateral(repaid, price, calculate_lif(lltv)).unwrap();
+                assert!(seize_within_max_lif(seized, repaid, price).unwrap(), "lltv {} repaid {}", lltv, repaid);
+            }
+        }
+
+        // A LIF past MAX_LIF, or a price consumed with 3 decimals missing
+        let repaid = 2_000_000_000;
+        let greedy = calculate_seized_collateral(repaid, price, MAX_LIF + 100).unwrap();
+        assert!(!seize_within_max_lif(greedy, repaid, price).unwrap());
+        let misscaled = calculate_seized_collateral(repaid, price / 1_000, calculate_lif(8_500)).unwrap();
+        assert!(!seize_within_max_lif(misscaled, repaid, price).unwrap());
+    }
+
+    #[test]
+    fn test_prescaled_price_across_decimal_pairs() {
+        // (collateral decimals, loan decimals, whole-token price,
+        //  repaid loan units, collateral units that covers at par,
+        //  collateral units held, max debt in loan units at 85% LLTV)
+        let pairs = [
+            // 0.1 loan per collateral: 100 loan units per collateral unit
+            (6u8, 9u8, ORACLE_SCALE / 10, 200u128, 2u128, 2u128, 170u128),
+            // 200 loan per collateral: 0.2 loan units per collateral unit
+            (9, 6, 200 * ORACLE_SCALE, 3, 15, 100, 17),
+            // 1e-7 loan per indivisible collateral: 100 loan units each
+            (0, 9, ORACLE_SCALE / 10_000_000, 200, 2, 2, 170),
+            // 0.02 loan per collateral: 20 loan units per collateral unit
+            (9, 12, ORACLE_SCALE / 50, 60, 3, 10, 170),
+        ];
+
+        for (collateral_decimals, loan_decimals, unit_price, repaid, seized, collateral, max_borrow) in pairs {
+            let pair = format!("{collateral_decimals}/{loan_decimals}");
+            let price = prescale_price(unit_price, collateral_decimals, loan_decimals).unwrap();
+
+            // Without a bonus the seizure is worth exactly the repayment
+            assert_eq!(calculate_seized_collateral(repaid, price, LIF_BPS).unwrap(), seized, "{pair}");
+            // The bonus is applied on top, in collateral units
+            let lif = calculate_lif(8500);
+            assert_eq!(
+                calculate_seized_collateral(repaid, price, lif).unwrap(),
+                mul_div_up(seized, lif as u128, LIF_BPS as u128).unwrap(),
+                "{pair}"
+            );
+
+            // The liquidation threshold values collateral the same way
+            let shares = |debt: u128| debt * VIRTUAL_SHARES;
+            assert!(
+                !is_liquidatable(collateral, shares(max_borrow), max_borrow, shares(max_borrow), price, 8500).unwrap(),
+                "{pair}"
+            );
+            let over = max_borrow + 1;
+            assert!(is_liquidatable(collateral, shares(over), over, shares(over), price, 8500).unwrap(), "{pair}");
+        }
+
+        // Sub-unit prices round down, and absurd decimal gaps overflow
+        assert_eq!(prescale_price(5 * ORACLE_SCALE, 12, 0).unwrap(), 5 * ORACLE_SCALE / 1_000_000_000_000);
+        assert!(prescale_price(ORACLE_SCALE, 0, 40).is_err());
+    }
+
+    #[test]
+    fn test_normalize_price_across_exponents_and_decimals() {
+        use morpho_solana::interfaces::normalize_price;
+
+        // 1 whole collateral (0 decimals) = 1.5 loan tokens (2 decimals):
+        // 150 loan units per collateral unit, however the feed writes 1.5
+        let expected = 150 * ORACLE_SCALE;
+        assert_eq!(normalize_price(150_000, -5, 0, 2).unwrap(), expected); // Pyth-style
+        assert_eq!(normalize_price(1_500_000_000_000_000_000, -18, 0, 2).unwrap(), expected); // Switchboard Decimal
+        assert_eq!(normalize_price(15, -1, 0, 2).unwrap(), expected);
+
+        // $150 SOL (9 decimals) against a 2-decimal token, and a positive exponent
+        assert_eq!(normalize_price(150, 0, 9, 2).unwrap(), 150 * ORACLE_SCALE / 10_000_000);
+        assert_eq!(normalize_price(15, 1, 9, 2).unwrap(), 150 * ORACLE_SCALE / 10_000_000);
+
+        // Already prescaled answers pass through with equal decimals
+        assert_eq!(normalize_price(2, -3, 6, 6).unwrap(), 2 * ORACLE_SCALE / 1_000);
+
+        // Exponents past the scale vanish or overflow instead of wrapping
+        assert_eq!(normalize_price(1, -80, 6, 6).unwrap(), 0);
+        assert!(normalize_price(1, 10, 6, 6).is_err());
+    }
+
+    #[test]
+    fn test_chainlink_round_decoding_and_scaling() {
+        use morpho_solana::interfaces::{
+            chainlink_answer_to_price, parse_chainlink_round, CHAINLINK_TRANSMISSIONS_DISCRIMINATOR,
+        };
+
+        // Transmissions account: discriminator, 192-byte packed header, 3 live rounds
+        let mut data = vec![0u8; 8 + 192 + 3 * 48];
+        data[..8].copy_from_slice(&CHAINLINK_TRANSMISSIONS_DISCRIMINATOR);
+        data[8 + 130] = 8; // decimals
+        data[8 + 135..8 + 139].copy_from_slice(&42u32.to_le_bytes()); // latest_round_id
+        data[8 + 140..8 + 144].copy_from_slice(&3u32.to_le_bytes()); // live_length
+        let mut write_round = |index: usize, slot: u64, answer: i128| {
+            let round = &mut data[200 + index * 48..][..48];
+            round[0..8].copy_from_slice(&slot.to_le_bytes());
+            round[16..32].copy_from_slice(&answer.to_le_bytes());
+        };
+        write_round(2, 900, 140_00000000);
+        write_round(0, 1_000, 150_00000000);
+
+        // Cursor 1: the latest round was written to slot 0
+        data[8 + 144..8 + 148].copy_from_slice(&1u32.to_le_bytes());
+        let round = parse_chainlink_round(&data).unwrap();
+        assert_eq!((round.round_id, round.slot, round.answer, round.decimals), (42, 1_000, 150_00000000, 8));
+
+        // Cursor 0 wraps back to the end of the ring
+        data[8 + 144..8 + 148].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(parse_chainlink_round(&data).unwrap().answer, 140_00000000);
+
+        // $150 SOL (9 decimals) in USDC (6 decimals) matches a prescaled whole-token price
+        assert_eq!(
+            chainlink_answer_to_price(150_00000000, 8, 9, 6).unwrap(),
+            prescale_price(150 * ORACLE_SCALE, 9, 6).unwrap()
+        );
+        assert!(chainlink_answer_to_price(0, 8, 9, 6).is_err());
+        assert!(chainlink_answer_to_price(-1, 8, 9, 6).is_err());
+
+        // Foreign accounts and feeds without rounds are rejected
+        let mut bad = data.clone();
+        bad[0] ^= 1;
+        assert!(parse_chainlink_round(&bad).is_err());
+        data[8 + 135..8 + 139].copy_from_slice(&0u32.to_le_bytes());
+        assert!(parse_chainlink_round(&data).is_err());
+        assert!(parse_chainlink_round(&data[..100]).is_err());
+    }
+
+    #[test]
+    fn test_pyth_price_decoding_and_confidence_bound() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::{
+            check_pyth_confidence, parse_pyth_price, PYTH_PRICE_UPDATE_DISCRIMINATOR,
+        };
+
+        // PriceUpdateV2: discriminator, write authority, `Full` tag, price message, posted slot
+        let mut data = vec![0u8; 8 + 32 + 1 + 84 + 8];
+        data[..8].copy_from_slice(&PYTH_PRICE_UPDATE_DISCRIMINATOR);
+        data[40] = 1;
+        let message = 41;
+        data[message + 32..message + 40].copy_from_slice(&150_00000000i64.to_le_bytes()); // price
+        data[message + 40..message + 48].copy_from_slice(&1_50000000u64.to_le_bytes()); // conf: 1%
+        data[message + 48..message + 52].copy_from_slice(&(-8i32).to_le_bytes()); // exponent
+        data[message + 52..message + 60].copy_from_slice(&1_700_000_000i64.to_le_bytes()); // publish_time
+        data[message + 84..message + 92].copy_from_slice(&1_000u64.to_le_bytes()); // posted_slot
+        let price = parse_pyth_price(&data).unwrap();
+        assert_eq!(
+            (price.price, price.conf, price.exponent, price.publish_time, price.posted_slot),
+            (150_00000000, 1_50000000, -8, 1_700_000_000, 1_000)
+        );
+
+        // A 1% interval passes a 1% bound, not a 0.5% one
+        check_pyth_confidence(&price, 100).unwrap();
+        let err = check_pyth_confidence(&price, 50).unwrap_err();
+        assert_eq!(err, MorphoError::OracleConfidenceTooWide.into());
+
+        // Partially verified updates and foreign accounts are rejected
+        let mut partial = data.clone();
+        partial[40] = 0;
+        assert!(parse_pyth_price(&partial).is_err());
+        data[0] ^= 1;
+        assert!(parse_pyth_price(&data).is_err());
+        assert!(parse_pyth_price(&data[..100]).is_err());
+    }
+
+    #[test]
+    fn test_oracle_program_return_data() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::{
+            decode_oracle_return_data, oracle_get_price_data, ORACLE_GET_PRICE_DISCRIMINATOR,
+        };
+
+        let data = oracle_get_price_data(9, 6);
+        assert_eq!(data[..8], ORACLE_GET_PRICE_DISCRIMINATOR);
+        assert_eq!(data[8..], [9, 6]);
+
+        let program = Pubkey::new_unique();
+        let price = 2 * ORACLE_SCALE;
+        let returned = Some((program, price.to_le_bytes().to_vec()));
+        assert_eq!(decode_oracle_return_data(&program, returned.clone()).unwrap(), price);
+
+        // Missing data, data from another program, and malformed data
+        let err = decode_oracle_return_data(&program, None).unwrap_err();
+        assert_eq!(err, MorphoError::OracleNoReturnData.into());
+        let err = decode_oracle_return_data(&Pubkey::new_unique(), returned).unwrap_err();
+        assert_eq!(err, MorphoError::OracleInvalidProgram.into());
+        let err = decode_oracle_return_data(&program, Some((program, vec![0u8; 8]))).unwrap_err();
+        assert_eq!(err, MorphoError::OracleInvalidReturnData.into());
+    }
+
+    #[test]
+    fn test_pegged_oracle_prices_one_to_one() {
+        use morpho_solana::interfaces::{derive_pegged_oracle, pegged_price};
+
+        // Same decimals: exactly ORACLE_SCALE
+        assert_eq!(pegged_price(6, 6).unwrap(), ORACLE_SCALE);
+
+        // USDC (6) borrowed against a 9-decimal LST: 1 collateral unit buys 1e-3 loan units
+        let price = pegged_price(9, 6).unwrap();
+        assert_eq!(price, ORACLE_SCALE / 1_000);
+        assert_eq!(calculate_seized_collateral(1_000_000, price, LIF_BPS).unwrap(), 1_000_000_000);
+
+        // Off the curve: no key can ever sign for or create a feed there
+        let (pegged, _) = derive_pegged_oracle(&morpho_solana::ID);
+        assert!(!pegged.is_on_curve());
+    }
+
+    #[test]
+    fn test_whale_collateral_valuation_cannot_overflow() {
+        let collateral = u64::MAX as u128;
+        let lltv = 8500u64;
+
+        for price in [ORACLE_SCALE, 300 * ORACLE_SCALE] {
+            // collateral * price is far past u128; the valuation itself isn't
+            let value = mul_div_down(collateral, price, ORACLE_SCALE).unwrap();
+            assert_eq!(value, collateral * (price / ORACLE_SCALE));
+            let max_borrow = mul_div_down(value, lltv as u128, BPS as u128).unwrap();
+
+            let shares = |debt: u128| debt * VIRTUAL_SHARES;
+            assert!(!is_liquidatable(collateral, shares(max_borrow), max_borrow, shares(max_borrow), price, lltv).unwrap());
+            let over = max_borrow + 1;
+            assert!(is_liquidatable(collateral, shares(over), over, shares(over), price, lltv).unwrap());
+
+            // Health factor and a full seizure stay computable too
+            assert!(health_factor(collateral, max_borrow, price, lltv).unwrap() >= WAD);
+            assert_eq!(calculate_seized_collateral(value, price, LIF_BPS).unwrap(), collateral);
+        }
+    }
+
     #[test]
     fn test_utilization_calculation() {
         let market = Market {
-            bump: 0,
-            market_id: [0u8; 32],
-            collateral_mint: Pubkey::default(),
-            loan_mint: Pubkey::default(),
-            collateral_decimals: 9,
-            loan_decimals: 6,
-            oracle: Pubkey::default(),
-            irm: Pubkey::default(),
-            lltv: 8500,
-            paused: false,
-            fee: 0,
-            total_supply_assets: 1_000_000_000_000, // 1M
+            total_supply_assets: 1_000_000_000_000,
             total_supply_shares: 1_000_000_000_000_000_000,
-            total_borrow_assets: 500_000_000_000, // 500K borrowed = 50% utilization
+            total_borrow_assets: 500_000_000_000,
             total_borrow_shares: 500_000_000_000_000_000,
-            last_update: 0,
-            pending_fee_shares: 0,
-            collateral_vault_bump: 0,
-            loan_vault_bump: 0,
-            flash_loan_lock: 0,
-            reserved: [0u8; 127],
+            ..test_market()
         };
 
         let utilization = market.utilization();
@@ -603,27 +812,11 @@ mod math_validation_tests {
     #[test]
     fn test_available_liquidity() {
         let market = Market {
-            bump: 0,
-            market_id: [0u8; 32],
-            collateral_mint: Pubkey::default(),
-            loan_mint: Pubkey::default(),
-            collateral_decimals: 9,
-            loan_decimals: 6,
-            oracle: Pubkey::default(),
-            irm: Pubkey::default(),
-            lltv: 8500,
-            paused: false,
-            fee: 0,
             total_supply_assets: 1_000_000,
             total_supply_shares: 1_000_000_000_000,
             total_borrow_assets: 400_000,
             total_borrow_shares: 400_000_000_000,
-            last_update: 0,
-            pending_fee_shares: 0,
-            collateral_vault_bump: 0,
-            loan_vault_bump: 0,
-            flash_loan_lock: 0,
-            reserved: [0u8; 127],
+            ..test_market()
         };
 
         let liquidity = market.available_liquidity();
@@ -631,6 +824,7 @@ mod math_validation_tests {
     }
 
     #[test]
+    #[cfg(not(feature = "localnet"))]
     fn test_flash_loan_fee_calculation() {
         let borrowed = 1_000_000_000u128; // 1000 USDC
 
@@ -696,202 +890,2204 @@ mod state_tests {
     }
 
     #[test]
-    fn test_market_space() {
-        let space = Market::space();
-        assert!(space > 200, "Market should have substantial size");
-        assert!(space < 1000, "Market shouldn't be too large");
+    fn test_protocol_state_space_tracks_lists() {
+        assert_eq!(
+            ProtocolState::space_for(3, 2),
+            ProtocolState::space() + 3 * 8 + 2 * 32,
+            "Each LLTV adds 8 bytes, each IRM 32"
+        );
+        // No dynamic size collides with the legacy size used to detect it
+        for lltvs in 0..=MAX_LLTVS {
+            for irms in 0..=MAX_IRMS {
+                assert_ne!(ProtocolState::space_for(lltvs, irms), ProtocolStateV1::SPACE);
+            }
+        }
     }
 
     #[test]
-    fn test_position_space() {
-        let space = Position::space();
-        assert!(space > 100, "Position should have substantial size");
-        assert!(space < 500, "Position shouldn't be too large");
+    fn test_legacy_protocol_state_migrates() {
+        use anchor_lang::{AnchorDeserialize, AnchorSerialize, AccountSerialize};
+
+        let owner = Pubkey::new_unique();
+        let irm = Pubkey::new_unique();
+        let mut lltvs = [0u64; MAX_LLTVS];
+        lltvs[0] = 8500;
+        lltvs[1] = 9000;
+        let mut irms = [Pubkey::default(); MAX_IRMS];
+        irms[0] = irm;
+
+        // Fixed-array layout, minus the discriminator
+        let mut legacy = Vec::new();
+        255u8.serialize(&mut legacy).unwrap();
+        owner.serialize(&mut legacy).unwrap();
+        Pubkey::default().serialize(&mut legacy).unwrap();
+        owner.serialize(&mut legacy).unwrap();
+        false.serialize(&mut legacy).unwrap();
+        2u8.serialize(&mut legacy).unwrap();
+        lltvs.serialize(&mut legacy).unwrap();
+        1u8.serialize(&mut legacy).unwrap();
+        irms.serialize(&mut legacy).unwrap();
+        7u64.serialize(&mut legacy).unwrap();
+        0u8.serialize(&mut legacy).unwrap();
+        [Pubkey::default(); 4].serialize(&mut legacy).unwrap();
+        // The kill switch sits in bytes deployed accounts never wrote
+        0u8.serialize(&mut legacy).unwrap();
+        [0u8; 126].serialize(&mut legacy).unwrap();
+        assert_eq!(legacy.len() + 8, ProtocolStateV1::SPACE);
+
+        let state = ProtocolStateV1::deserialize(&mut legacy.as_slice()).unwrap().into_current();
+        assert_eq!(state.owner, owner);
+        assert_eq!(state.market_count, 7);
+        assert_eq!(state.live_market_count(), 7, "legacy state never closed a market");
+        assert!(state.flash_loans_enabled(), "upgrading leaves flash loans on");
+        assert_eq!(state.enabled_lltvs, vec![8500, 9000]);
+        assert_eq!(state.enabled_irms, vec![irm]);
+        assert!(state.is_lltv_enabled(9000) && !state.is_lltv_enabled(0));
+        assert!(!state.can_recover_ownership(i64::MAX), "legacy state has no recovery key");
+
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), state.current_space());
     }
 
     #[test]
-    fn test_authorization_space() {
-        let space = Authorization::space();
-        assert!(space > 50, "Authorization should have substantial size");
-        assert!(space < 200, "Authorization shouldn't be too large");
+    fn test_owner_recovery_window() {
+        use anchor_lang::AnchorDeserialize;
+
+        let mut legacy = vec![0u8; ProtocolStateV1::SPACE - 8];
+        legacy[0] = 255;
+        let mut state = ProtocolStateV1::deserialize(&mut legacy.as_slice()).unwrap().into_current();
+        let delay = MIN_OWNER_RECOVERY_DELAY_DAYS as i64 * SECONDS_PER_DAY;
+        state.last_owner_heartbeat = 1_000;
+        state.owner_recovery_delay = delay;
+        assert!(!state.can_recover_ownership(i64::MAX), "no recovery key configured");
+
+        state.recovery_key = Pubkey::new_unique();
+        assert!(!state.can_recover_ownership(1_000 + delay - 1), "owner still within the delay");
+        assert!(state.can_recover_ownership(1_000 + delay));
+
+        state.last_owner_heartbeat = 1_000 + delay;
+        assert!(!state.can_recover_ownership(1_000 + delay), "a heartbeat restarts the delay");
     }
 
     #[test]
-    fn test_position_is_empty() {
-        let empty_position = Position {
+    fn test_protocol_status_tracks_paused_markets() {
+        use anchor_lang::AnchorDeserialize;
+        use morpho_solana::instructions::ProtocolStatus;
+
+        let mut legacy = vec![0u8; ProtocolStateV1::SPACE - 8];
+        legacy[0] = 255;
+        let mut state = ProtocolStateV1::deserialize(&mut legacy.as_slice()).unwrap().into_current();
+        state.market_count = 5;
+        state.closed_market_count = 1;
+
+        state.track_market_pause(false, true);
+        state.track_market_pause(false, true);
+        state.track_market_pause(true, true);
+        assert_eq!(state.paused_market_count, 2, "Re-pausing a paused market isn't counted");
+        state.track_market_pause(true, false);
+        state.track_market_pause(false, false);
+        assert_eq!(state.paused_market_count, 1);
+
+        let status = ProtocolStatus::of(&state, 0);
+        assert_eq!((status.live_markets, status.paused_markets, status.closed_markets), (4, 1, 1));
+        assert!(!status.paused && !status.ownership_recoverable);
+
+        // Markets paused before the count existed unpause without underflow
+        state.track_market_pause(true, false);
+        state.track_market_pause(true, false);
+        assert_eq!(state.paused_market_count, 0);
+    }
+
+    #[test]
+    fn test_protocol_owned_borrow_lltv() {
+        use anchor_lang::AnchorDeserialize;
+
+        let mut legacy = vec![0u8; ProtocolStateV1::SPACE - 8];
+        legacy[0] = 255;
+        let mut state = ProtocolStateV1::deserialize(&mut legacy.as_slice()).unwrap().into_current();
+        state.fee_recipient = Pubkey::new_unique();
+
+        let mut market = test_market();
+        market.borrow_lltv_buffer = 9000;
+        let mut position = Position {
             bump: 1,
             market_id: [0u8; 32],
             owner: Pubkey::new_unique(),
             supply_shares: 0,
             borrow_shares: 0,
             collateral: 0,
-            reserved: [0u8; 64],
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
         };
 
-        assert!(empty_position.is_empty(), "Position with all zeros should be empty");
+        // Disabled: the treasury borrows like anyone else
+        assert_eq!(state.position_borrow_lltv(&market, &position, 0), 7650);
+        position.owner = state.fee_recipient;
+        assert_eq!(state.position_borrow_lltv(&market, &position, 0), 7650);
 
-        let non_empty_position = Position {
+        // Buffer stacks on the market's own borrow buffer
+        state.protocol_borrow_lltv_buffer = 8000;
+        assert!(state.is_protocol_owned(&position));
+        assert_eq!(state.position_borrow_lltv(&market, &position, 0), 6120);
+        assert_eq!(market.position_lltv(&position, 0), 8500, "Liquidated at the market LLTV");
+
+        position.owner = Pubkey::new_unique();
+        assert_eq!(state.position_borrow_lltv(&market, &position, 0), 7650);
+    }
+
+    #[test]
+    fn test_debt_free_withdrawal_ignores_broken_oracle() {
+        use anchor_lang::solana_program::account_info::AccountInfo;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::ensure_position_healthy;
+
+        let mut market = test_market();
+        market.oracle = Pubkey::new_unique();
+        market.total_borrow_assets = 1_000_000;
+        market.total_borrow_shares = 1_000_000 * VIRTUAL_SHARES;
+
+        // Borrower who repaid everything, withdrawing their last collateral
+        let mut position = Position {
             bump: 1,
             market_id: [0u8; 32],
             owner: Pubkey::new_unique(),
-            supply_shares: 100,
+            supply_shares: 0,
             borrow_shares: 0,
             collateral: 0,
-            reserved: [0u8; 64],
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 5_000,
+            total_repaid: 5_000,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
         };
 
-        assert!(!non_empty_position.is_empty(), "Position with supply shares should not be empty");
+        // Garbage feed data, and a feed that isn't the market's at all
+        let program_id = morpho_solana::ID;
+        let (mut lamports, mut wrong_lamports) = (0u64, 0u64);
+        let mut data = [0xffu8; 7];
+        let mut wrong_data = [0u8; 57];
+        let broken = AccountInfo::new(
+            &market.oracle, false, false, &mut lamports, &mut data, &program_id, false, 0,
+        );
+        let wrong_key = Pubkey::new_unique();
+        let wrong = AccountInfo::new(
+            &wrong_key, false, false, &mut wrong_lamports, &mut wrong_data, &program_id, false, 0,
+        );
+
+        for oracle in [Some(&broken), Some(&wrong), None] {
+            assert!(!ensure_position_healthy(&market, &position, 8500, oracle, None).unwrap());
+        }
+
+        // Any debt left, variable or stable, still needs a working feed
+        position.collateral = 1_000_000;
+        position.borrow_shares = VIRTUAL_SHARES;
+        let err = ensure_position_healthy(&market, &position, 8500, Some(&broken), None).unwrap_err();
+        assert_eq!(err, MorphoError::OracleInvalidReturnData.into());
+        let err = ensure_position_healthy(&market, &position, 8500, Some(&wrong), None).unwrap_err();
+        assert_eq!(err, MorphoError::InvalidOracle.into());
+
+        position.borrow_shares = 0;
+        position.stable_borrow_assets = 1;
+        assert!(ensure_position_healthy(&market, &position, 8500, None, None).is_err());
     }
 
     #[test]
-    fn test_position_has_debt() {
-        let position_with_debt = Position {
-            bump: 1,
-            market_id: [0u8; 32],
-            owner: Pubkey::new_unique(),
-            supply_shares: 0,
-            borrow_shares: 1000,
-            collateral: 5000,
-            reserved: [0u8; 64],
-        };
+    fn test_supply_shares_never_collateralize() {
+        use anchor_lang::AnchorDeserialize;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::state::is_supply_share_mint;
+
+        // A mint issued by the market PDA can only ever hold its supply shares
+        let market = derive_market(&morpho_solana::ID, &[7u8; 32]).0;
+        assert!(is_supply_share_mint(&market, Some(market)));
+        assert!(!is_supply_share_mint(&market, Some(Pubkey::new_unique())));
+        assert!(!is_supply_share_mint(&market, None), "Fixed-supply mints are fine");
+
+        // Nor can vault funds be routed back into Morpho supply
+        let mut legacy = vec![0u8; ProtocolStateV1::SPACE - 8];
+        legacy[0] = 255;
+        let mut state = ProtocolStateV1::deserialize(&mut legacy.as_slice()).unwrap().into_current();
+        let err = state.add_yield_adapter(morpho_solana::ID).unwrap_err();
+        assert_eq!(err, MorphoError::SelfCollateralization.into());
+        assert!(!state.is_yield_adapter_enabled(&morpho_solana::ID));
+        state.add_yield_adapter(Pubkey::new_unique()).unwrap();
+    }
 
-        assert!(position_with_debt.has_debt(), "Position with borrow shares should have debt");
-        assert!(position_with_debt.has_collateral(), "Position should have collateral");
+    #[test]
+    fn test_market_space() {
+        let space = Market::space();
+        assert!(space > 200, "Market should have substantial size");
+        assert_eq!(space, 1045, "Market layout changed; check migrate_market still covers it");
     }
 
     #[test]
-    fn test_authorization_validity() {
-        let current_time = 1000i64;
+    fn test_original_market_layout_migrates() {
+        use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+        use anchor_lang::{AccountDeserialize, AnchorSerialize, Discriminator};
 
-        // Valid authorization (no expiry)
-        let valid_auth = Authorization {
-            bump: 1,
-            authorizer: Pubkey::new_unique(),
-            authorized: Pubkey::new_unique(),
-            is_authorized: true,
-            is_revoked: false,
-            expires_at: 0, // No expiry
-            reserved: [0u8; 32],
-        };
-        assert!(valid_auth.is_valid(current_time), "Should be valid with no expiry");
+        let market_id = [7u8; 32];
+        let oracle = Pubkey::new_unique();
 
-        // Valid authorization (not expired)
-        let future_auth = Authorization {
-            bump: 1,
-            authorizer: Pubkey::new_unique(),
-            authorized: Pubkey::new_unique(),
-            is_authorized: true,
-            is_revoked: false,
-            expires_at: 2000, // Future expiry
-            reserved: [0u8; 32],
+        // Original layout: the fields up to flash_loan_lock, then 127 zeroed
+        // reserved bytes (406 bytes with the discriminator)
+        let mut data = Market::DISCRIMINATOR.to_vec();
+        255u8.serialize(&mut data).unwrap();
+        market_id.serialize(&mut data).unwrap();
+        for key in [Pubkey::new_unique(), Pubkey::new_unique()] {
+            key.serialize(&mut data).unwrap();
+        }
+        9u8.serialize(&mut data).unwrap();
+        6u8.serialize(&mut data).unwrap();
+        oracle.serialize(&mut data).unwrap();
+        Pubkey::new_unique().serialize(&mut data).unwrap();
+        8500u64.serialize(&mut data).unwrap();
+        false.serialize(&mut data).unwrap();
+        1000u64.serialize(&mut data).unwrap();
+        for total in [1_000_000u128, 1_000_000_000_000, 400_000, 400_000_000_000] {
+            total.serialize(&mut data).unwrap();
+        }
+        1_700_000_000i64.serialize(&mut data).unwrap();
+        42u128.serialize(&mut data).unwrap();
+        [254u8, 253, 0].serialize(&mut data).unwrap();
+        data.extend_from_slice(&[0u8; 127]);
+        assert_eq!(data.len(), 406);
+
+        // Unreadable as it stands...
+        assert!(Market::try_deserialize(&mut data.as_slice()).is_err());
+
+        // ...and complete once zero-extended, as migrate_market does in a
+        // single realloc
+        assert!(Market::space() - data.len() <= MAX_PERMITTED_DATA_INCREASE);
+        data.resize(Market::space(), 0);
+        let market = Market::try_deserialize(&mut data.as_slice()).unwrap();
+        assert_eq!(market.market_id, market_id);
+        assert_eq!(market.oracle, oracle);
+        assert_eq!((market.lltv, market.fee), (8500, 1000));
+        assert_eq!((market.total_supply_assets, market.total_borrow_shares), (1_000_000, 400_000_000_000));
+        assert_eq!((market.last_update, market.pending_fee_shares), (1_700_000_000, 42));
+        assert_eq!((market.collateral_vault_bump, market.loan_vault_bump), (254, 253));
+        assert!(!market.is_flash_loan_active());
+        assert_eq!(market.loan_deployed, 0);
+        assert!(!market.irm_cpi && !market.irm_adaptive, "prices at the built-in curve, as it always did");
+        assert!(!market.withdraw_only && !market.supply_only);
+    }
+
+    #[test]
+    fn test_original_position_layout_migrates() {
+        use anchor_lang::{AccountDeserialize, AnchorSerialize, Discriminator};
+
+        let market_id = [7u8; 32];
+        let owner = Pubkey::new_unique();
+
+        // Original layout: shares and collateral, then 64 zeroed reserved
+        // bytes (185 bytes with the discriminator)
+        let mut data = Position::DISCRIMINATOR.to_vec();
+        254u8.serialize(&mut data).unwrap();
+        market_id.serialize(&mut data).unwrap();
+        owner.serialize(&mut data).unwrap();
+        for amount in [5_000_000u128, 2_000_000, 3_000] {
+            amount.serialize(&mut data).unwrap();
+        }
+        data.extend_from_slice(&[0u8; 64]);
+        assert_eq!(data.len(), 185);
+
+        assert!(Position::try_deserialize(&mut data.as_slice()).is_err());
+
+        // Zero-extended, as migrate_position does
+        data.resize(Position::space(), 0);
+        let position = Position::try_deserialize(&mut data.as_slice()).unwrap();
+        assert_eq!((position.market_id, position.owner), (market_id, owner));
+        assert_eq!(
+            (position.supply_shares, position.borrow_shares, position.collateral),
+            (5_000_000, 2_000_000, 3_000)
+        );
+        assert_eq!(position.stable_borrow_assets, 0);
+        assert!(!position.insured);
+        assert_eq!(position.unwithdrawable_supply_shares_at(0), 0);
+        assert!(!position.liquidation_cooling_down(&Pubkey::new_unique(), 1));
+    }
+
+    #[test]
+    fn test_position_space() {
+        let space = Position::space();
+        assert!(space > 100, "Position should have substantial size");
+        assert!(space < 500, "Position shouldn't be too large");
+    }
+
+    #[cfg(feature = "multi-collateral")]
+    #[test]
+    fn test_multi_collateral_combined_health() {
+        use anchor_lang::AnchorSerialize;
+        use morpho_solana::constants::SECONDS_PER_YEAR;
+        use morpho_solana::instructions::accrue_mc_interest;
+        use morpho_solana::state::{
+            is_mc_healthy, mc_max_borrow, McCollateral, McMarket, McPosition, MAX_MC_COLLATERALS,
         };
-        assert!(future_auth.is_valid(current_time), "Should be valid before expiry");
 
-        // Expired authorization
-        let expired_auth = Authorization {
-            bump: 1,
-            authorizer: Pubkey::new_unique(),
-            authorized: Pubkey::new_unique(),
-            is_authorized: true,
-            is_revoked: false,
-            expires_at: 500, // Past expiry
+        let slot = |lltv| McCollateral { mint: Pubkey::new_unique(), lltv, ..Default::default() };
+        let mut market = McMarket {
+            bump: 255,
+            market_id: [1u8; 32],
+            creator: Pubkey::new_unique(),
+            loan_mint: Pubkey::new_unique(),
+            loan_decimals: 6,
+            loan_vault_bump: 255,
+            irm: Pubkey::new_unique(),
+            collateral_count: 2,
+            collaterals: [slot(8_000), slot(5_000), McCollateral::default(), McCollateral::default()],
+            total_supply_assets: 10_000,
+            total_supply_shares: 10_000 * 1_000_000,
+            total_borrow_assets: 1_000,
+            total_borrow_shares: 1_000 * 1_000_000,
+            last_update: 0,
+            reserved: [0u8; 64],
+        };
+        let mut position = McPosition {
+            bump: 255,
+            market_id: market.market_id,
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 0,
+            collateral: [0; MAX_MC_COLLATERALS],
             reserved: [0u8; 32],
         };
-        assert!(!expired_auth.is_valid(current_time), "Should be invalid after expiry");
+        assert_eq!(McMarket::space(), 8 + market.try_to_vec().unwrap().len());
+        assert_eq!(McPosition::space(), 8 + position.try_to_vec().unwrap().len());
+        assert!(market.collateral(1).is_some() && market.collateral(2).is_none());
+
+        // 1_000 at price 1 and 80% LLTV plus 400 at price 2 and 50% LLTV
+        position.collateral = [1_000, 400, 0, 0];
+        let prices = [ORACLE_SCALE, 2 * ORACLE_SCALE, 0, 0];
+        assert_eq!(mc_max_borrow(&market, &position, &prices).unwrap(), 800 + 400);
+
+        position.borrow_shares = 1_200 * 1_000_000;
+        assert!(is_mc_healthy(&market, &position, &prices).unwrap());
+        position.borrow_shares += 1_000_000;
+        assert!(!is_mc_healthy(&market, &position, &prices).unwrap());
+
+        // A slot falling in price drags the combined check down with it
+        position.borrow_shares = 1_000 * 1_000_000;
+        assert!(is_mc_healthy(&market, &position, &prices).unwrap());
+        let crashed = [ORACLE_SCALE, ORACLE_SCALE / 2, 0, 0];
+        assert!(!is_mc_healthy(&market, &position, &crashed).unwrap());
+
+        // Interest grows both sides of the book and never runs backwards
+        let interest = accrue_mc_interest(&mut market, SECONDS_PER_YEAR as i64).unwrap();
+        assert!(interest > 0);
+        assert_eq!(market.total_borrow_assets, 1_000 + interest);
+        assert_eq!(market.total_supply_assets, 10_000 + interest);
+        assert_eq!(accrue_mc_interest(&mut market, 1).unwrap(), 0);
+
+        // Borrows and withdrawals stop at what the vault actually holds
+        let available = market.available_liquidity();
+        assert_eq!(market.vault_liquidity(u64::MAX), available);
+        assert_eq!(market.vault_liquidity(500), 500);
+    }
 
-        // Revoked authorization
-        let revoked_auth = Authorization {
-            bump: 1,
-            authorizer: Pubkey::new_unique(),
-            authorized: Pubkey::new_unique(),
-            is_authorized: true,
-            is_revoked: true,
-            expires_at: 0,
+    #[test]
+    fn test_market_metadata_limits() {
+        let longest_name = "x".repeat(MAX_MARKET_NAME_LEN);
+        assert!(MarketMetadata::fits(&longest_name, "ipfs://desc"));
+        assert!(!MarketMetadata::fits(&format!("{longest_name}x"), ""));
+        assert!(!MarketMetadata::fits("SOL/USDC 86%", &"u".repeat(1_000)));
+        assert!(MarketMetadata::space() < 500, "Metadata shouldn't be too large");
+    }
+
+    #[test]
+    fn test_oracle_heartbeat_per_market() {
+        use morpho_solana::interfaces::{oracle_heartbeat, MAX_ORACLE_STALENESS};
+
+        let mut market = test_market();
+        assert_eq!(oracle_heartbeat(&market), MAX_ORACLE_STALENESS, "Older markets keep the global limit");
+
+        market.oracle_heartbeat = 1_500;
+        assert_eq!(oracle_heartbeat(&market), 1_500);
+    }
+
+    #[test]
+    fn test_oracle_update_waits_out_its_delay() {
+        use morpho_solana::state::{derive_config_proposal, derive_oracle_update_proposal, OracleUpdateProposal};
+
+        let market_id = [7u8; 32];
+        let (pda, _) = derive_oracle_update_proposal(&morpho_solana::ID, &market_id);
+        assert_ne!(pda, derive_config_proposal(&morpho_solana::ID, &market_id).0, "Curator proposals can't block a migration");
+        assert_ne!(pda, derive_oracle_update_proposal(&morpho_solana::ID, &[8u8; 32]).0);
+        assert_eq!(OracleUpdateProposal::space(), 8 + 1 + 32 + 32 + 8 + 8 + 32);
+
+        let proposed_at = 1_700_000_000;
+        let proposal = OracleUpdateProposal {
+            bump: 0,
+            market_id,
+            new_oracle: Pubkey::new_unique(),
+            proposed_at,
+            executable_at: proposed_at + ORACLE_UPDATE_DELAY,
             reserved: [0u8; 32],
         };
-        assert!(!revoked_auth.is_valid(current_time), "Should be invalid when revoked");
+        assert!(!proposal.is_executable(proposed_at));
+        assert!(!proposal.is_executable(proposed_at + ORACLE_UPDATE_DELAY - 1));
+        assert!(proposal.is_executable(proposed_at + ORACLE_UPDATE_DELAY));
+    }
 
-        // Not authorized
-        let not_auth = Authorization {
-            bump: 1,
-            authorizer: Pubkey::new_unique(),
-            authorized: Pubkey::new_unique(),
-            is_authorized: false,
-            is_revoked: false,
-            expires_at: 0,
+    #[test]
+    fn test_irm_update_waits_out_its_delay() {
+        use morpho_solana::constants::IRM_UPDATE_DELAY;
+        use morpho_solana::state::{derive_irm_update_proposal, derive_oracle_update_proposal, IrmUpdateProposal};
+
+        let market_id = [7u8; 32];
+        let (pda, _) = derive_irm_update_proposal(&morpho_solana::ID, &market_id);
+        assert_ne!(pda, derive_oracle_update_proposal(&morpho_solana::ID, &market_id).0, "Both migrations can be pending");
+        assert_eq!(IrmUpdateProposal::space(), 8 + 1 + 32 + 32 + 8 + 8 + 32);
+
+        let proposed_at = 1_700_000_000;
+        let proposal = IrmUpdateProposal {
+            bump: 0,
+            market_id,
+            new_irm: Pubkey::new_unique(),
+            proposed_at,
+            executable_at: proposed_at + IRM_UPDATE_DELAY,
             reserved: [0u8; 32],
         };
-        assert!(!not_auth.is_valid(current_time), "Should be invalid when not authorized");
+        assert!(!proposal.is_executable(proposed_at + IRM_UPDATE_DELAY - 1));
+        assert!(proposal.is_executable(proposed_at + IRM_UPDATE_DELAY));
     }
 
     #[test]
-    fn test_market_operational_check() {
-        let mut market = Market {
+    fn test_oracle_max_age_per_market() {
+        use anchor_lang::solana_program::account_info::AccountInfo;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::{check_oracle_age, get_oracle_price_validated};
+
+        // A day-old stablecoin print passes a day's limit, not an hour's
+        check_oracle_age(1_000, 1_000 + 86_400, 86_400).unwrap();
+        let err = check_oracle_age(1_000, 1_000 + 86_400, 3_600).unwrap_err();
+        assert_eq!(err, MorphoError::OracleStale.into());
+        // Timestamps ahead of the validator clock aren't stale
+        check_oracle_age(2_000, 1_000, 60).unwrap();
+
+        // Static oracles carry no timestamp, so the limit never applies
+        let mut market = test_market();
+        market.oracle = Pubkey::new_unique();
+        market.max_oracle_age_secs = 1;
+        let program_id = morpho_solana::ID;
+        let mut lamports = 0u64;
+        let mut data = [0u8; 57];
+        data[9..25].copy_from_slice(&ORACLE_SCALE.to_le_bytes());
+        let oracle = AccountInfo::new(
+            &market.oracle, false, false, &mut lamports, &mut data, &program_id, false, 0,
+        );
+        assert_eq!(get_oracle_price_validated(&oracle, &market).unwrap(), ORACLE_SCALE);
+    }
+
+    #[test]
+    fn test_twap_dampens_price_spikes() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::{TwapOracle, TwapSample, TWAP_MAX_SAMPLES};
+
+        let mut twap = TwapOracle {
             bump: 0,
-            market_id: [0u8; 32],
-            collateral_mint: Pubkey::default(),
-            loan_mint: Pubkey::default(),
+            source: Pubkey::new_unique(),
             collateral_decimals: 9,
             loan_decimals: 6,
-            oracle: Pubkey::default(),
-            irm: Pubkey::default(),
-            lltv: 8500,
-            paused: false,
-            fee: 0,
-            total_supply_assets: 0,
-            total_supply_shares: 0,
-            total_borrow_assets: 0,
-            total_borrow_shares: 0,
-            last_update: 0,
-            pending_fee_shares: 0,
-            collateral_vault_bump: 0,
-            loan_vault_bump: 0,
-            flash_loan_lock: 0,
-            reserved: [0u8; 127],
+            window: 1_600,
+            last_update_slot: 0,
+            sample_count: 0,
+            cursor: 0,
+            samples: [TwapSample::default(); TWAP_MAX_SAMPLES],
         };
-
-        assert!(market.is_operational(), "Market should be operational when not paused");
-
-        market.paused = true;
-        assert!(!market.is_operational(), "Market should not be operational when paused");
+        assert_eq!(twap.min_update_interval(), 100);
+        assert!(twap.latest().is_none());
+
+        // One sample can't cover the window yet
+        twap.push(TwapSample { timestamp: 1_000, price: ORACLE_SCALE }, 1);
+        let err = twap.twap(2_000).unwrap_err();
+        assert_eq!(err, MorphoError::TwapWindowNotCovered.into());
+        assert_eq!(twap.twap(2_600).unwrap(), ORACLE_SCALE);
+
+        // A 10x print held for 160s of a 1600s window moves the TWAP by 90%, not 900%
+        twap.push(TwapSample { timestamp: 2_440, price: 10 * ORACLE_SCALE }, 2);
+        assert_eq!(twap.latest().unwrap().price, 10 * ORACLE_SCALE);
+        assert_eq!(twap.twap(2_600).unwrap(), ORACLE_SCALE * 19 / 10);
+
+        // The ring keeps the newest samples once full
+        for i in 0..TWAP_MAX_SAMPLES as i64 {
+            twap.push(TwapSample { timestamp: 3_000 + 100 * i, price: 2 * ORACLE_SCALE }, 3);
+        }
+        assert_eq!(twap.sample_count as usize, TWAP_MAX_SAMPLES);
+        assert_eq!(twap.latest().unwrap().timestamp, 4_500);
+        assert_eq!(twap.twap(4_600).unwrap(), 2 * ORACLE_SCALE);
     }
 
     #[test]
-    fn test_flash_loan_lock() {
-        let mut market = Market {
+    fn test_composite_oracle_crosses_usd_feeds() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::CompositeOracle;
+
+        // SOL (9 decimals) at $150 against a 6-decimal token at $0.50
+        let mut composite = CompositeOracle {
             bump: 0,
-            market_id: [0u8; 32],
-            collateral_mint: Pubkey::default(),
-            loan_mint: Pubkey::default(),
+            base_feed: Pubkey::new_unique(),
+            quote_feed: Pubkey::new_unique(),
             collateral_decimals: 9,
             loan_decimals: 6,
-            oracle: Pubkey::default(),
-            irm: Pubkey::default(),
-            lltv: 8500,
-            paused: false,
-            fee: 0,
-            total_supply_assets: 0,
-            total_supply_shares: 0,
-            total_borrow_assets: 0,
-            total_borrow_shares: 0,
-            last_update: 0,
-            pending_fee_shares: 0,
-            collateral_vault_bump: 0,
-            loan_vault_bump: 0,
-            flash_loan_lock: 0,
-            reserved: [0u8; 127],
+            base_price: 150 * ORACLE_SCALE,
+            quote_price: ORACLE_SCALE / 2,
+            last_update_slot: 0,
+            published_at: 0,
         };
+        assert_eq!(composite.price().unwrap(), prescale_price(300 * ORACLE_SCALE, 9, 6).unwrap());
 
-        assert!(!market.is_flash_loan_active(), "Flash loan should not be active initially");
+        // Same legs, same decimals: parity
+        composite.quote_price = composite.base_price;
+        composite.loan_decimals = 9;
+        assert_eq!(composite.price().unwrap(), ORACLE_SCALE);
 
-        market.flash_loan_lock = 1;
-        assert!(market.is_flash_loan_active(), "Flash loan should be active when lock is set");
+        composite.quote_price = 0;
+        assert_eq!(composite.price().unwrap_err(), MorphoError::OracleInvalidPrice.into());
     }
-}
 
-// ============================================================================
-// Constants Validation Tests
-// ============================================================================
+    #[test]
+    fn test_clmm_twap_averages_pool_ticks() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::{observation_twap_tick, tick_to_price, ClmmTwapOracle, CLMM_MAX_TICK};
+
+        assert_eq!(tick_to_price(0).unwrap(), ORACLE_SCALE);
+        // 1.0001^23027 is 10.0 to within a few bps either way
+        let ten = tick_to_price(23_027).unwrap();
+        assert!(ten.abs_diff(10 * ORACLE_SCALE) < 10 * ORACLE_SCALE / 1_000);
+        let tenth = tick_to_price(-23_027).unwrap();
+        assert!(tenth.abs_diff(ORACLE_SCALE / 10) < ORACLE_SCALE / 10 / 1_000);
+        assert!(tick_to_price(-CLMM_MAX_TICK).is_ok());
+        assert_eq!(tick_to_price(CLMM_MAX_TICK + 1).unwrap_err(), MorphoError::InvalidClmmObservations.into());
+
+        // Raydium ObservationState: index at 17, observations of 44 bytes from 51
+        let mut data = vec![0u8; 51 + 100 * 44 + 32];
+        let mut observe = |index: usize, timestamp: u32, tick_cumulative: i64| {
+            let start = 51 + index * 44;
+            data[start..start + 4].copy_from_slice(&timestamp.to_le_bytes());
+            data[start + 4..start + 12].copy_from_slice(&tick_cumulative.to_le_bytes());
+        };
+        // Tick 500 for 100s, then -300 for 100s
+        observe(0, 1_000, 0);
+        observe(1, 1_100, 50_000);
+        observe(2, 1_200, 20_000);
+        data[17..19].copy_from_slice(&2u16.to_le_bytes());
+
+        assert_eq!(observation_twap_tick(&data, 100).unwrap(), (-300, 1_200), "Shortest span that covers the window");
+        assert_eq!(observation_twap_tick(&data, 150).unwrap(), (100, 1_200));
+        assert_eq!(
+            observation_twap_tick(&data, 250).unwrap_err(),
+            MorphoError::TwapWindowNotCovered.into(),
+            "Unwritten slots end the history"
+        );
+        assert_eq!(observation_twap_tick(&data[..100], 60).unwrap_err(), MorphoError::InvalidClmmObservations.into());
+
+        // Ticks price token 0 in token 1; a token-1 collateral inverts them
+        let mut oracle = ClmmTwapOracle {
+            bump: 0,
+            observation_state: Pubkey::new_unique(),
+            collateral_is_token0: true,
+            min_window: 150,
+            twap_tick: 23_027,
+            observed_at: 1_200,
+            last_update_slot: 0,
+        };
+        assert_eq!(oracle.price().unwrap(), ten);
+        oracle.collateral_is_token0 = false;
+        assert_eq!(oracle.price().unwrap(), tenth);
+    }
+
+    #[test]
+    fn test_ewma_smooths_toward_readings() {
+        use anchor_lang::AccountSerialize;
+        use morpho_solana::interfaces::EwmaOracle;
+
+        let mut ewma = EwmaOracle {
+            bump: 255,
+            market_id: [7u8; 32],
+            source: Pubkey::new_unique(),
+            collateral_decimals: 9,
+            loan_decimals: 6,
+            smoothing_bps: 2_000,
+            price: 100 * ORACLE_SCALE,
+            last_update_slot: 1,
+            last_update: 1,
+        };
+        let mut data = Vec::new();
+        ewma.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), EwmaOracle::space());
+
+        // A 50% wick moves a 20% EWMA by 10%, either way
+        assert_eq!(ewma.blend(50 * ORACLE_SCALE).unwrap(), 90 * ORACLE_SCALE);
+        assert_eq!(ewma.blend(150 * ORACLE_SCALE).unwrap(), 110 * ORACLE_SCALE);
+        assert_eq!(ewma.blend(100 * ORACLE_SCALE).unwrap(), 100 * ORACLE_SCALE);
+
+        // Repeated readings converge on them
+        for _ in 0..50 {
+            ewma.price = ewma.blend(50 * ORACLE_SCALE).unwrap();
+        }
+        assert!(ewma.price - 50 * ORACLE_SCALE < ORACLE_SCALE / 1_000);
+
+        // Full weight is no smoothing at all
+        ewma.smoothing_bps = 10_000;
+        assert_eq!(ewma.blend(3 * ORACLE_SCALE).unwrap(), 3 * ORACLE_SCALE);
+    }
+
+    #[test]
+    fn test_price_relay_layout_and_deviation_cap() {
+        use anchor_lang::AccountSerialize;
+        use morpho_solana::instructions::price_divergence_bps;
+        use morpho_solana::interfaces::{derive_price_relay, PriceRelay, MAX_RELAY_DEVIATION_BPS};
+
+        let relay = PriceRelay {
+            bump: 255,
+            market_id: [7u8; 32],
+            relayer: Pubkey::new_unique(),
+            max_deviation_bps: MAX_RELAY_DEVIATION_BPS,
+            price: ORACLE_SCALE,
+            reference_price: ORACLE_SCALE,
+            last_update_slot: 1,
+            last_update: 1,
+        };
+        let mut data = Vec::new();
+        relay.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), PriceRelay::space());
+
+        // One relay per market
+        let program = morpho_solana::ID;
+        assert_ne!(derive_price_relay(&program, &[1u8; 32]).0, derive_price_relay(&program, &[2u8; 32]).0);
+
+        // At the cap a relayer may move 10% off the primary, not more
+        let reference = 100 * ORACLE_SCALE;
+        assert!(price_divergence_bps(110 * ORACLE_SCALE, reference) <= MAX_RELAY_DEVIATION_BPS);
+        assert!(price_divergence_bps(111 * ORACLE_SCALE, reference) > MAX_RELAY_DEVIATION_BPS);
+    }
+
+    #[test]
+    fn test_fallback_oracle_covers_failing_primary() {
+        use anchor_lang::solana_program::account_info::AccountInfo;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::get_oracle_price_with_fallback;
+
+        let (primary_key, fallback_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut market = test_market();
+        market.oracle = primary_key;
+        market.fallback_oracle = fallback_key;
+        let program_id = morpho_solana::ID;
+        let (mut broken_lamports, mut fallback_lamports, mut other_lamports) = (0u64, 0u64, 0u64);
+        let mut broken_data = [0xffu8; 7];
+        let mut fallback_data = [0u8; 57];
+        fallback_data[9..25].copy_from_slice(&(2 * ORACLE_SCALE).to_le_bytes());
+        let mut other_data = fallback_data;
+        let primary = AccountInfo::new(
+            &primary_key, false, false, &mut broken_lamports, &mut broken_data, &program_id, false, 0,
+        );
+        let fallback = AccountInfo::new(
+            &fallback_key, false, false, &mut fallback_lamports, &mut fallback_data, &program_id, false, 0,
+        );
+        let other_key = Pubkey::new_unique();
+        let other = AccountInfo::new(
+            &other_key, false, false, &mut other_lamports, &mut other_data, &program_id, false, 0,
+        );
+
+        // A broken primary falls back to the registered secondary
+        let price = get_oracle_price_with_fallback(&primary, Some(&fallback), &market).unwrap();
+        assert_eq!(price, 2 * ORACLE_SCALE);
+
+        // Without the secondary (passed or registered) the primary's error stands
+        let err = get_oracle_price_with_fallback(&primary, None, &market).unwrap_err();
+        assert_eq!(err, MorphoError::OracleInvalidReturnData.into());
+        let err = get_oracle_price_with_fallback(&primary, Some(&other), &market).unwrap_err();
+        assert_eq!(err, MorphoError::InvalidOracle.into(), "Only the registered fallback counts");
+
+        // Passing the wrong primary is a caller mistake, not an outage
+        let err = get_oracle_price_with_fallback(&other, Some(&fallback), &market).unwrap_err();
+        assert_eq!(err, MorphoError::InvalidOracle.into());
+
+        market.fallback_oracle = Pubkey::default();
+        let err = get_oracle_price_with_fallback(&primary, Some(&fallback), &market).unwrap_err();
+        assert_eq!(err, MorphoError::OracleInvalidReturnData.into());
+    }
+
+    #[test]
+    fn test_authorization_space() {
+        let space = Authorization::space();
+        assert!(space > 50, "Authorization should have substantial size");
+        assert!(space < 200, "Authorization shouldn't be too large");
+    }
+
+    #[test]
+    fn test_lltv_bounds() {
+        use morpho_solana::state::LltvBounds;
+
+        // Volatile loan token class: capped at 86%
+        let bounds = LltvBounds {
+            bump: 0,
+            loan_mint: Pubkey::new_unique(),
+            min_lltv: 0,
+            max_lltv: 8600,
+            reserved: [0u8; 32],
+        };
+        assert!(bounds.contains(7700));
+        assert!(bounds.contains(8600), "Max is inclusive");
+        assert!(!bounds.contains(9150));
+        assert_eq!(LltvBounds::space(), 8 + 1 + 32 + 8 + 8 + 32);
+    }
+
+    #[test]
+    fn test_liquidation_reference_tolerance() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::instructions::check_liquidation_price;
+        use morpho_solana::state::LiquidationReference;
+
+        let reference = LiquidationReference {
+            bump: 0,
+            market_id: [0u8; 32],
+            reference_oracle: Pubkey::new_unique(),
+            max_divergence_bps: 200,
+            reserved: [0u8; 32],
+        };
+        let price = 100 * ORACLE_SCALE;
+        assert!(check_liquidation_price(&reference, price, 102 * ORACLE_SCALE).is_ok(), "Tolerance is inclusive");
+        // Measured against the lower price: 100 vs 98 is ~204 bps
+        assert!(check_liquidation_price(&reference, price, 98 * ORACLE_SCALE).is_err());
+        // A primary printing a 10% wick can't liquidate on its own
+        assert_eq!(
+            check_liquidation_price(&reference, 90 * ORACLE_SCALE, price).unwrap_err(),
+            MorphoError::LiquidationPriceDisputed.into()
+        );
+        assert_eq!(LiquidationReference::space(), 8 + 1 + 32 + 32 + 8 + 32);
+    }
+
+    #[test]
+    fn test_token_tier_caps() {
+        use morpho_solana::errors::MorphoError;
+        use anchor_lang::solana_program::account_info::AccountInfo;
+        use morpho_solana::state::{TokenBadge, TokenTier};
+
+        assert!(TokenTier::A.check_lltv(9800).is_ok());
+        assert!(TokenTier::B.check_lltv(9150).is_ok(), "Cap is inclusive");
+        assert_eq!(TokenTier::B.check_lltv(9450).unwrap_err(), MorphoError::LltvAboveTierCap.into());
+        assert_eq!(TokenTier::C.check_lltv(9150).unwrap_err(), MorphoError::LltvAboveTierCap.into());
+        assert_eq!(TokenTier::Banned.check_lltv(0).unwrap_err(), MorphoError::MintBanned.into());
+
+        // A mint nobody graded is held to tier C
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![];
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(TokenBadge::load_tier(&account).unwrap(), TokenTier::C);
+        assert_eq!(TokenBadge::space(), 8 + 1 + 32 + 1 + 32);
+    }
+
+    #[test]
+    fn test_market_is_empty() {
+        let mut market = test_market();
+        assert!(market.is_empty(), "Fresh market can be closed");
+
+        // Unclaimed fees or liquidity parked in an adapter keep it open
+        market.pending_fee_shares = 1;
+        assert!(!market.is_empty());
+        market.pending_fee_shares = 0;
+        market.loan_deployed = 1;
+        assert!(!market.is_empty());
+    }
+
+    #[test]
+    fn test_position_is_empty() {
+        let empty_position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        assert!(empty_position.is_empty(), "Position with all zeros should be empty");
+
+        let non_empty_position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 100,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        assert!(!non_empty_position.is_empty(), "Position with supply shares should not be empty");
+
+        let delegating_position = Position {
+            supply_shares: 0,
+            credit_delegated: 500,
+            ..non_empty_position
+        };
+
+        assert!(!delegating_position.is_empty(), "Position backing credit lines should not be empty");
+
+        let settled_position = Position {
+            supply_shares: 0,
+            total_supplied: 1_000,
+            total_withdrawn: 1_000,
+            total_borrowed: 400,
+            total_repaid: 400,
+            times_liquidated: 1,
+            ..non_empty_position
+        };
+
+        assert!(settled_position.is_empty(), "Lifetime stats don't keep a position open");
+    }
+
+    #[test]
+    fn test_seeded_supply_lock_expires() {
+        let seeded = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 1_000,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 800,
+            supply_locked_until: 86_400,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        assert_eq!(seeded.locked_supply_shares_at(0), 800);
+        assert_eq!(seeded.locked_supply_shares_at(86_399), 800);
+        assert_eq!(seeded.locked_supply_shares_at(86_400), 0, "Lock ends at supply_locked_until");
+    }
+
+    #[test]
+    fn test_escrowed_supply_outlives_its_lock() {
+        let seeded = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 1_000,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 300,
+            supply_locked_until: 86_400,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 500,
+            escrow_unlock_at: 3_600,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        assert_eq!(seeded.unwithdrawable_supply_shares_at(0), 800);
+        assert_eq!(
+            seeded.unwithdrawable_supply_shares_at(86_400),
+            500,
+            "Escrow stays after the seed lock and its own expiry, until unlocked"
+        );
+
+        let unlocked = Position { escrowed_supply_shares: 0, escrow_unlock_at: 0, ..seeded };
+        assert_eq!(unlocked.unwithdrawable_supply_shares_at(86_400), 0);
+    }
+
+    #[test]
+    fn test_position_has_debt() {
+        let position_with_debt = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 1000,
+            collateral: 5000,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        assert!(position_with_debt.has_debt(), "Position with borrow shares should have debt");
+        assert!(position_with_debt.has_collateral(), "Position should have collateral");
+    }
+
+    #[test]
+    fn test_liquidation_cooldown_only_holds_back_other_liquidators() {
+        use morpho_solana::constants::LIQUIDATION_COOLDOWN_SLOTS;
+
+        let mut position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 1000,
+            collateral: 5000,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(!position.liquidation_cooling_down(&second, 1), "Never liquidated");
+
+        position.last_liquidation_slot = 1_000;
+        position.last_liquidator = first;
+        assert!(position.liquidation_cooling_down(&second, 1_000));
+        assert!(position.liquidation_cooling_down(&second, 1_000 + LIQUIDATION_COOLDOWN_SLOTS - 1));
+        assert!(!position.liquidation_cooling_down(&second, 1_000 + LIQUIDATION_COOLDOWN_SLOTS));
+        assert!(!position.liquidation_cooling_down(&first, 1_000), "The last liquidator may continue");
+    }
+
+    #[test]
+    fn test_authorization_validity() {
+        let current_time = 1000i64;
+
+        // Valid authorization (no expiry)
+        let valid_auth = Authorization {
+            bump: 1,
+            authorizer: Pubkey::new_unique(),
+            authorized: Pubkey::new_unique(),
+            is_authorized: true,
+            is_revoked: false,
+            expires_at: 0, // No expiry
+            reserved: [0u8; 32],
+        };
+        assert!(valid_auth.is_valid(current_time), "Should be valid with no expiry");
+
+        // Valid authorization (not expired)
+        let future_auth = Authorization {
+            bump: 1,
+            authorizer: Pubkey::new_unique(),
+            authorized: Pubkey::new_unique(),
+            is_authorized: true,
+            is_revoked: false,
+            expires_at: 2000, // Future expiry
+            reserved: [0u8; 32],
+        };
+        assert!(future_auth.is_valid(current_time), "Should be valid before expiry");
+
+        // Expired authorization
+        let expired_auth = Authorization {
+            bump: 1,
+            authorizer: Pubkey::new_unique(),
+            authorized: Pubkey::new_unique(),
+            is_authorized: true,
+            is_revoked: false,
+            expires_at: 500, // Past expiry
+            reserved: [0u8; 32],
+        };
+        assert!(!expired_auth.is_valid(current_time), "Should be invalid after expiry");
+
+        // Revoked authorization
+        let revoked_auth = Authorization {
+            bump: 1,
+            authorizer: Pubkey::new_unique(),
+            authorized: Pubkey::new_unique(),
+            is_authorized: true,
+            is_revoked: true,
+            expires_at: 0,
+            reserved: [0u8; 32],
+        };
+        assert!(!revoked_auth.is_valid(current_time), "Should be invalid when revoked");
+
+        // Not authorized
+        let not_auth = Authorization {
+            bump: 1,
+            authorizer: Pubkey::new_unique(),
+            authorized: Pubkey::new_unique(),
+            is_authorized: false,
+            is_revoked: false,
+            expires_at: 0,
+            reserved: [0u8; 32],
+        };
+        assert!(!not_auth.is_valid(current_time), "Should be invalid when not authorized");
+    }
+
+    #[test]
+    fn test_market_operational_check() {
+        let mut market = test_market();
+
+        assert!(market.is_operational(), "Market should be operational when not paused");
+
+        market.paused = true;
+        assert!(!market.is_operational(), "Market should not be operational when paused");
+    }
+
+    #[test]
+    fn test_market_mode_action_matrix() {
+        use morpho_solana::errors::MorphoError;
+        use MarketAction::*;
+
+        // Each mode, the error it reports and the actions it blocks, in
+        // check order; anything not listed stays open in that mode
+        let modes: [(fn(&mut Market), MorphoError, &[MarketAction]); 4] = [
+            (
+                |m| m.paused = true,
+                MorphoError::MarketPaused,
+                &[
+                    Supply, Withdraw, SupplyCollateral, WithdrawCollateral, Borrow,
+                    FlashLoan, MergePositions, MergeDebt, DeployToAdapter,
+                ],
+            ),
+            (|m| m.divergence_frozen = true, MorphoError::MarketFrozen, &[WithdrawCollateral, Borrow, MergeDebt]),
+            (|m| m.supply_only = true, MorphoError::BorrowingDisabled, &[Borrow, FlashLoan]),
+            (|m| m.withdraw_only = true, MorphoError::MarketWithdrawOnly, &[Supply, Borrow]),
+        ];
+
+        // Every combination of modes, including none
+        for combo in 0..(1u8 << modes.len()) {
+            let mut market = test_market();
+            let active: Vec<_> = modes.iter().enumerate()
+                .filter(|(i, _)| combo & (1 << i) != 0)
+                .map(|(_, mode)| mode)
+                .collect();
+            for (enable, _, _) in &active {
+                enable(&mut market);
+            }
+
+            for action in MarketAction::ALL {
+                let expected = active.iter()
+                    .find(|(_, _, blocked)| blocked.contains(&action))
+                    .map_or(Ok(()), |(_, error, _)| Err((*error).into()));
+                assert_eq!(
+                    market.check_action(action), expected,
+                    "{:?} with modes {:04b}", action, combo
+                );
+            }
+        }
+
+        // Exits are open in every mode at once
+        let mut market = test_market();
+        for (enable, _, _) in &modes {
+            enable(&mut market);
+        }
+        assert!(market.check_action(Repay).is_ok());
+        assert!(market.check_action(Liquidate).is_ok());
+    }
+
+    #[test]
+    fn test_flash_loan_lock() {
+        let mut market = test_market();
+
+        assert!(!market.is_flash_loan_active(), "Flash loan should not be active initially");
+
+        market.flash_loan_lock = 1;
+        assert!(market.is_flash_loan_active(), "Flash loan should be active when lock is set");
+    }
+
+    #[test]
+    fn test_flash_loan_end_binding() {
+        let mut market = test_market();
+        let borrower = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+
+        assert!(!market.matches_flash_loan(&borrower, 0), "Nothing to end without a start");
+
+        market.lock_flash_loan(borrower, 1_000_000);
+        assert!(market.matches_flash_loan(&borrower, 1_000_000), "Borrower settles own loan");
+        assert!(!market.matches_flash_loan(&attacker, 1_000_000), "Other signers cannot end it");
+        assert!(!market.matches_flash_loan(&borrower, 1), "Cannot settle for less than borrowed");
+
+        // Pausing mid-loan must not affect the ability to end it
+        market.paused = true;
+        assert!(market.matches_flash_loan(&borrower, 1_000_000), "End allowed while paused");
+
+        market.unlock_flash_loan();
+        assert!(!market.is_flash_loan_active(), "Lock released");
+        assert_eq!(market.flash_loan_borrower, Pubkey::default());
+        assert_eq!(market.flash_loan_amount, 0);
+    }
+
+    #[test]
+    fn test_collateral_staking_cap() {
+        let mut market = test_market();
+        assert!(!market.has_collateral_yield_adapter());
+        assert_eq!(market.max_collateral_staked(1_000).unwrap(), 0, "Disabled market stakes nothing");
+
+        market.collateral_yield_adapter = Pubkey::new_unique();
+        market.max_collateral_staked_bps = 7000;
+        assert!(market.has_collateral_yield_adapter());
+        assert_eq!(market.max_collateral_staked(1_000).unwrap(), 700);
+
+        // Cap is measured against vault + already staked, so it doesn't shrink as we stake
+        market.collateral_staked = 700;
+        assert_eq!(market.max_collateral_staked(300).unwrap(), 700);
+    }
+
+    #[test]
+    fn test_loan_deployment_cap() {
+        let mut market = test_market();
+        market.total_supply_assets = 10_000;
+        market.total_borrow_assets = 6_000;
+        assert!(!market.has_loan_yield_adapter());
+        assert_eq!(market.max_loan_deployed().unwrap(), 0);
+
+        market.loan_yield_adapter = Pubkey::new_unique();
+        market.max_loan_deployed_bps = 5000;
+        // Half of the 4_000 idle liquidity; deployed funds still count as liquidity
+        assert_eq!(market.max_loan_deployed().unwrap(), 2_000);
+        market.loan_deployed = 2_000;
+        assert_eq!(market.available_liquidity(), 4_000);
+
+        // Only the undeployed half sits in the vault for flash loans and unlocks
+        assert_eq!(market.idle_liquidity(), 2_000);
+        assert_eq!(market.vault_liquidity(2_000), 2_000);
+        assert_eq!(market.vault_liquidity(5_000), 4_000);
+    }
+
+    #[test]
+    fn test_market_config_update_apply() {
+        let mut market = test_market();
+
+        assert!(MarketConfigUpdate::default().validate().is_err(), "Empty proposal rejected");
+        let too_high = MarketConfigUpdate { fee: Some(MAX_FEE + 1), ..Default::default() };
+        assert!(too_high.validate().is_err());
+
+        let fee_only = MarketConfigUpdate { fee: Some(1000), ..Default::default() };
+        fee_only.apply(&mut market).unwrap();
+        assert_eq!(market.fee, 1000);
+        assert_eq!(market.max_loan_deployed_bps, 0, "Untouched fields stay as they were");
+
+        // Caps need the matching adapter to be configured
+        let cap = MarketConfigUpdate { max_loan_deployed_bps: Some(2000), ..Default::default() };
+        assert!(cap.apply(&mut market).is_err());
+        market.loan_yield_adapter = Pubkey::new_unique();
+        cap.apply(&mut market).unwrap();
+        assert_eq!(market.max_loan_deployed_bps, 2000);
+    }
+
+    #[test]
+    fn test_share_prices_grow_with_interest() {
+        let mut market = test_market();
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = 1_000_000 * VIRTUAL_SHARES;
+        market.total_borrow_assets = 500_000;
+        market.total_borrow_shares = 500_000 * VIRTUAL_SHARES;
+
+        let supply_before = market.supply_share_price().unwrap();
+        let borrow_before = market.borrow_share_price().unwrap();
+        assert_eq!(supply_before, WAD / VIRTUAL_SHARES, "1 asset per 1e6 shares at start");
+
+        let rate = WAD / 10 / 31_536_000;
+        accrue_interest_on_market(&mut market, 31_536_000, rate).unwrap();
+        assert!(market.supply_share_price().unwrap() > supply_before);
+        assert!(market.borrow_share_price().unwrap() > borrow_before);
+    }
+
+    #[test]
+    fn test_interest_pause_skips_paused_time() {
+        let mut market = test_market();
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = 1_000_000 * VIRTUAL_SHARES;
+        market.total_borrow_assets = 500_000;
+        market.total_borrow_shares = 500_000 * VIRTUAL_SHARES;
+        let rate = WAD / 10 / 31_536_000;
+
+        market.interest_paused_until = 1_000;
+        let paused = accrue_interest_on_market(&mut market, 1_000, rate).unwrap();
+        assert_eq!(paused.interest, 0, "No interest while the rate is paused");
+        assert_eq!(market.last_update, 1_000);
+
+        // Straddling the expiry only charges the time after it
+        let mut reference = market.clone();
+        market.interest_paused_until = 1_500;
+        let straddled = accrue_interest_on_market(&mut market, 1_000_000, rate).unwrap();
+        reference.last_update = 1_500;
+        let expected = accrue_interest_on_market(&mut reference, 1_000_000, rate).unwrap();
+        assert!(straddled.interest > 0);
+        assert_eq!(straddled, expected);
+    }
+
+    #[test]
+    fn test_accrual_growth_cap_drops_abnormal_interest() {
+        let mut market = test_market();
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = 1_000_000 * VIRTUAL_SHARES;
+        market.total_borrow_assets = 500_000;
+        market.total_borrow_shares = 500_000 * VIRTUAL_SHARES;
+        market.max_accrual_growth_bps = 100;
+
+        // An ordinary rate stays under the cap untouched
+        let normal_rate = WAD / 10 / 31_536_000;
+        let mut reference = market.clone();
+        reference.max_accrual_growth_bps = 0;
+        let normal = accrue_interest_on_market(&mut market.clone(), 86_400, normal_rate).unwrap();
+        assert_eq!(normal, accrue_interest_on_market(&mut reference, 86_400, normal_rate).unwrap());
+        assert_eq!(normal.capped, 0);
+
+        // A runaway rate only grows debt by 1% and the rest is dropped
+        let runaway_rate = WAD / 1_000;
+        let mut reference = market.clone();
+        reference.max_accrual_growth_bps = 0;
+        let uncapped = accrue_interest_on_market(&mut reference, 86_400, runaway_rate).unwrap().interest;
+        let result = accrue_interest_on_market(&mut market, 86_400, runaway_rate).unwrap();
+        assert_eq!(result.interest, 5_000);
+        assert_eq!(market.total_borrow_assets, 505_000);
+        assert_eq!(market.total_supply_assets, 1_005_000);
+        assert_eq!(result.capped, uncapped - 5_000);
+        assert_eq!(market.last_update, 86_400, "The period is still consumed");
+    }
+
+    #[test]
+    fn test_borrow_lltv_buffer() {
+        let mut market = test_market();
+        assert_eq!(market.borrow_lltv(), 8500, "No buffer: borrow up to LLTV");
+
+        market.borrow_lltv_buffer = 9500;
+        assert_eq!(market.borrow_lltv(), 8075);
+
+        // 82 borrowed against 100 collateral at 1:1: fine for liquidation, too close for a new borrow
+        let (collateral, debt) = (100u128, 82u128);
+        let shares = debt * VIRTUAL_SHARES;
+        assert!(!is_liquidatable(collateral, shares, debt, shares, ORACLE_SCALE, market.lltv).unwrap());
+        assert!(is_liquidatable(collateral, shares, debt, shares, ORACLE_SCALE, market.borrow_lltv()).unwrap());
+    }
+
+    #[test]
+    fn test_reputation_lltv_boost() {
+        let mut market = test_market();
+        let mut position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 5_000,
+            total_repaid: 5_000,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+        assert_eq!(market.position_lltv(&position, 0), 8500, "Boost is opt-in per market");
+
+        market.reputation_lltv_boost_bps = 100;
+        market.reputation_min_repaid = 10_000;
+        assert!(!market.has_reputation_boost(&position, 0), "Not enough repaid yet");
+
+        position.total_repaid = 10_000;
+        assert!(!market.has_reputation_boost(&position, 0), "Only seasoned repayments count");
+        position.seasoned_repaid = 10_000;
+        assert_eq!(market.position_lltv(&position, 0), 8600);
+
+        // The borrow buffer applies on top of the boosted LLTV
+        market.borrow_lltv_buffer = 9500;
+        assert_eq!(market.position_borrow_lltv(&position, 0), 8170);
+
+        // A young position waits out the market's minimum age
+        market.reputation_min_age = 86_400;
+        position.opened_at = 1_000;
+        assert!(!market.has_reputation_boost(&position, 87_399));
+        assert!(market.has_reputation_boost(&position, 87_400));
+
+        position.times_liquidated = 1;
+        assert_eq!(market.position_lltv(&position, 87_400), 8500, "A liquidation forfeits the boost");
+    }
+
+    #[test]
+    fn test_churned_repayments_earn_no_reputation() {
+        use morpho_solana::instructions::{record_repayment, simulate_bundle, BundleAction};
+
+        let mut market = test_market();
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = 1_000_000 * VIRTUAL_SHARES;
+        market.reputation_lltv_boost_bps = 100;
+        market.reputation_min_repaid = 10_000;
+        market.reputation_min_age = 86_400;
+        let mut position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        // Borrow and repay in one transaction, over and over: volume, no reputation
+        let now = 100_000;
+        let churn = [BundleAction::Borrow { assets: 10_000 }, BundleAction::Repay { assets: 10_000, shares: 0 }];
+        for _ in 0..5 {
+            simulate_bundle(false, &mut market, &mut position, &churn, now).unwrap();
+        }
+        assert!(position.total_repaid >= 50_000 - 5);
+        assert_eq!(position.seasoned_repaid, 0);
+        assert!(!market.has_reputation_boost(&position, now));
+
+        // A third party repaying within the window doesn't season it either
+        let borrow = [BundleAction::Borrow { assets: 10_000 }];
+        simulate_bundle(false, &mut market, &mut position, &borrow, now).unwrap();
+        record_repayment(&market, &mut position, 5_000, now + 86_399).unwrap();
+        assert_eq!(position.seasoned_repaid, 0);
+
+        // Debt held for the full window does count once repaid
+        let repay = [BundleAction::Repay { assets: 0, shares: position.borrow_shares }];
+        simulate_bundle(false, &mut market, &mut position, &repay, now + 86_400).unwrap();
+        assert!(position.seasoned_repaid >= 10_000);
+        assert!(market.has_reputation_boost(&position, now + 86_400));
+    }
+
+    #[test]
+    fn test_liquidation_insurance() {
+        use morpho_solana::instructions::{
+            charge_insurance_premium, insurance_premium, liquidation_penalty,
+            reimburse_liquidation_penalty,
+        };
+
+        // 5% a year on 1_000_000 collateral: 50_000 after a year, half after six months
+        assert_eq!(insurance_premium(1_000_000, 500, 31_536_000).unwrap(), 50_000);
+        assert_eq!(insurance_premium(1_000_000, 500, 15_768_000).unwrap(), 25_000);
+        assert_eq!(insurance_premium(1_000_000, 500, 0).unwrap(), 0);
+
+        // LIF 105%: of 10_500 seized, 500 is the penalty
+        assert_eq!(liquidation_penalty(10_500, 10_500).unwrap(), 500);
+
+        let mut market = test_market();
+        market.insurance_premium_bps = 500;
+        market.insurance_coverage_bps = 5_000;
+        let mut position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 0,
+            collateral: 1_000_000,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        // Uninsured positions pay nothing and get nothing back
+        assert_eq!(charge_insurance_premium(&mut market, &mut position, 31_536_000).unwrap(), 0);
+        assert_eq!(reimburse_liquidation_penalty(&mut market, &mut position, 10_500, 10_500).unwrap(), 0);
+
+        position.insured = true;
+        assert_eq!(charge_insurance_premium(&mut market, &mut position, 31_536_000).unwrap(), 50_000);
+        assert_eq!(position.collateral, 950_000);
+        assert_eq!(market.insurance_pool, 50_000);
+        assert_eq!(position.insurance_paid_until, 31_536_000);
+        assert_eq!(charge_insurance_premium(&mut market, &mut position, 31_536_000).unwrap(), 0, "Already paid up");
+
+        // Half the penalty comes back, capped by what's in the pool
+        assert_eq!(reimburse_liquidation_penalty(&mut market, &mut position, 10_500, 10_500).unwrap(), 250);
+        assert_eq!(position.insurance_claimable, 250);
+        assert_eq!(market.insurance_pool, 49_750);
+        assert!(!position.is_empty(), "Unclaimed reimbursements keep the position open");
+
+        market.insurance_pool = 100;
+        assert_eq!(reimburse_liquidation_penalty(&mut market, &mut position, 10_500, 10_500).unwrap(), 100);
+        assert_eq!(market.insurance_pool, 0);
+    }
+
+    #[test]
+    fn test_stable_rate_accounting() {
+        use morpho_solana::instructions::{blended_stable_rate, can_rebalance_stable_rates};
+        use morpho_solana::interfaces::is_liquidatable_with_stable_debt;
+        use morpho_solana::math::accrue_stable_debt;
+
+        let rate = WAD / 10 / 31_536_000;
+        assert_eq!(blended_stable_rate(0, 0, 1_000, rate).unwrap(), rate);
+        assert_eq!(blended_stable_rate(1_000, rate, 1_000, 3 * rate).unwrap(), 2 * rate);
+
+        let mut market = test_market();
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = 1_000_000 * VIRTUAL_SHARES;
+        market.add_stable_debt(100_000, rate, true).unwrap();
+        market.add_stable_debt(300_000, 3 * rate, true).unwrap();
+        assert_eq!(market.avg_stable_rate, rate * 10 / 4);
+        assert_eq!(market.stable_borrowers, 2);
+        assert_eq!(market.available_liquidity(), 600_000, "Stable debt uses liquidity too");
+
+        // Suppliers earn stable interest through the usual accrual
+        let accrual = accrue_interest_on_market(&mut market, 31_536_000, 0).unwrap();
+        assert!(accrual.interest > 0);
+        assert_eq!(market.total_stable_borrow_assets, 400_000 + accrual.interest);
+        assert_eq!(market.total_supply_assets, 1_000_000 + accrual.interest);
+
+        // Positions compound at their own locked rate, skipping paused time
+        let mut position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 100_000,
+            stable_rate: rate,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+        let mut paused = position.clone();
+        accrue_stable_debt(&market, &mut position, 31_536_000).unwrap();
+        assert!(position.stable_borrow_assets > 110_000, "10% a year, compounded");
+        market.interest_paused_from = 0;
+        market.interest_paused_until = 31_536_000;
+        assert_eq!(accrue_stable_debt(&market, &mut paused, 31_536_000).unwrap(), 0);
+        assert_eq!(paused.stable_last_update, 31_536_000);
+        assert!(position.has_debt() && !position.is_empty());
+
+        // Stable debt counts toward health: 100 collateral at 1:1 and 85% LLTV
+        assert!(!is_liquidatable_with_stable_debt(100, 0, 85, 0, 0, ORACLE_SCALE, 8500).unwrap());
+        assert!(is_liquidatable_with_stable_debt(100, 0, 86, 0, 0, ORACLE_SCALE, 8500).unwrap());
+
+        // Rebalancing needs the utilization threshold
+        assert!(!can_rebalance_stable_rates(&market), "Off by default");
+        market.stable_rebalance_utilization_bps = 3_000;
+        assert!(can_rebalance_stable_rates(&market));
+
+        // The last borrower out settles the aggregate's drift with suppliers
+        market.remove_stable_debt(100_000, rate, true).unwrap();
+        assert_eq!(market.stable_borrowers, 1);
+        let supply_before = market.total_supply_assets;
+        let left = market.total_stable_borrow_assets;
+        market.remove_stable_debt(left - 7, 3 * rate, true).unwrap();
+        assert_eq!(market.total_stable_borrow_assets, 0);
+        assert_eq!(market.avg_stable_rate, 0);
+        assert_eq!(market.total_supply_assets, supply_before - 7, "Phantom interest written off");
+    }
+
+    #[test]
+    fn test_dry_run_bundle_accounting() {
+        use morpho_solana::instructions::{simulate_bundle, BundleAction};
+
+        let mut market = test_market();
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = 1_000_000 * VIRTUAL_SHARES;
+        let mut position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        let actions = [
+            BundleAction::SupplyCollateral { amount: 10_000 },
+            BundleAction::Borrow { assets: 5_000 },
+            BundleAction::Repay { assets: 2_000, shares: 0 },
+        ];
+        let (projection, shares) = simulate_bundle(false, &mut market, &mut position, &actions, 0).unwrap();
+        assert_eq!(projection.collateral, 10_000);
+        assert_eq!(projection.borrow_assets, 3_000);
+        assert_eq!(projection.loan_out, 5_000);
+        assert_eq!(projection.loan_in, 2_000);
+        assert_eq!(shares.minted, 5_000 * VIRTUAL_SHARES);
+        assert_eq!(projection.total_borrow_assets, 3_000);
+        assert!(projection.health_checked, "Net new debt needs the oracle");
+
+        // Pure deleveraging never needs the oracle, and stays open while paused
+        let repay_all = [BundleAction::Repay { assets: 0, shares: u128::MAX }];
+        let (projection, _) = simulate_bundle(true, &mut market, &mut position, &repay_all, 0).unwrap();
+        assert_eq!(projection.borrow_shares, 0);
+        assert!(!projection.health_checked);
+
+        // Failures match the standalone instructions
+        let borrow = [BundleAction::Borrow { assets: 2_000_000 }];
+        assert!(simulate_bundle(false, &mut market, &mut position, &borrow, 0).is_err(), "Beyond liquidity");
+        let supply = [BundleAction::Supply { assets: 1 }];
+        assert!(simulate_bundle(true, &mut market, &mut position, &supply, 0).is_err(), "Paused");
+    }
+
+    #[test]
+    fn test_accrual_priorities_rank_stale_debt_first() {
+        use morpho_solana::instructions::accrual_priorities;
+
+        let market_at = |id: u8, last_update: i64, debt: u128| {
+            let mut market = test_market();
+            market.market_id = [id; 32];
+            market.last_update = last_update;
+            market.total_borrow_assets = debt;
+            market
+        };
+        let markets = [
+            market_at(1, 900, 1_000),
+            market_at(2, 0, 0),
+            market_at(3, 100, 5),
+        ];
+
+        let ranked = accrual_priorities(&markets, 1_000);
+        let order: Vec<u8> = ranked.iter().map(|p| p.market_id[0]).collect();
+        assert_eq!(order, vec![3, 1, 2], "Idle markets trail even when oldest");
+        assert_eq!(ranked[0].seconds_since_accrual, 900);
+        assert_eq!(ranked[1].total_debt, 1_000);
+    }
+
+    #[test]
+    fn test_exposure_report_totals_per_loan_token() {
+        use anchor_lang::AnchorSerialize;
+        use morpho_solana::constants::MAX_EXPOSURE_MARKETS;
+        use morpho_solana::instructions::{ExposureReport, MarketExposure, VersionedView, ViewResponse};
+
+        let usdc = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+        let exposure = |supplied, borrowed, collateral_value| MarketExposure {
+            market_id: [0u8; 32],
+            supplied,
+            borrowed,
+            collateral_value,
+            rate_cached: false,
+        };
+
+        let mut report = ExposureReport::default();
+        report.add(usdc, exposure(1_000, 200, 500)).unwrap();
+        report.add(sol, exposure(7, 0, 0)).unwrap();
+        report.add(usdc, exposure(0, 300, 900)).unwrap();
+        assert_eq!(report.markets.len(), 3);
+        assert_eq!(report.totals.len(), 2, "One total per loan token");
+        assert_eq!(report.totals[0].loan_mint, usdc);
+        assert_eq!((report.totals[0].supplied, report.totals[0].borrowed), (1_000, 500));
+        assert_eq!(report.totals[0].collateral_value, 1_400);
+        assert_eq!(report.totals[1].supplied, 7);
+
+        // Worst case (every market a different loan token) fits in return data
+        let mut full = ExposureReport::default();
+        for _ in 0..MAX_EXPOSURE_MARKETS {
+            full.add(Pubkey::new_unique(), exposure(1, 1, 1)).unwrap();
+        }
+        let view = VersionedView::from(ViewResponse::ExposureReport(full));
+        assert!(view.try_to_vec().unwrap().len() <= 1024);
+    }
+
+    #[test]
+    fn test_view_responses_decode_uniformly() {
+        use anchor_lang::AnchorSerialize;
+        use morpho_solana::constants::VIEW_RESPONSE_VERSION;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::instructions::{
+            decode_view, BundleProjection, ProtocolStatus, VersionedView, ViewResponse,
+        };
+
+        let responses = [
+            ViewResponse::BundleProjection(BundleProjection { loan_in: 5, ..Default::default() }),
+            ViewResponse::AccrualPriorities(vec![]),
+            ViewResponse::CollateralDrift(-3),
+            ViewResponse::ProtocolStatus(ProtocolStatus { paused_markets: 2, ..Default::default() }),
+        ];
+        for response in responses {
+            let data = VersionedView::from(response.clone()).try_to_vec().unwrap();
+            assert_eq!(data[0], VIEW_RESPONSE_VERSION, "Version byte leads");
+            assert_eq!(decode_view(&data).unwrap(), response);
+        }
+
+        let mut data = VersionedView::from(ViewResponse::CollateralDrift(0)).try_to_vec().unwrap();
+        data.push(0);
+        assert_eq!(decode_view(&data).unwrap_err(), MorphoError::ViewDataMalformed.into());
+        data.pop();
+        data[0] = VIEW_RESPONSE_VERSION + 1;
+        assert_eq!(decode_view(&data).unwrap_err(), MorphoError::UnsupportedViewVersion.into());
+        assert_eq!(decode_view(&[]).unwrap_err(), MorphoError::ViewDataMalformed.into());
+    }
+
+    #[test]
+    fn test_expected_version_handshake() {
+        use morpho_solana::constants::PROGRAM_VERSION;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::instructions::check_expected_version;
+
+        check_expected_version(None).unwrap();
+        check_expected_version(Some(PROGRAM_VERSION)).unwrap();
+        let err = check_expected_version(Some(PROGRAM_VERSION + 1)).unwrap_err();
+        assert_eq!(err, MorphoError::IncompatibleVersion.into());
+    }
+
+    #[test]
+    fn test_operator_share_of_seized_collateral() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::instructions::split_seized_collateral;
+
+        assert_eq!(split_seized_collateral(1_000_000, 0).unwrap(), (1_000_000, 0));
+        assert_eq!(split_seized_collateral(1_000_000, 2_500).unwrap(), (750_000, 250_000));
+        assert_eq!(split_seized_collateral(1_000_000, 10_000).unwrap(), (0, 1_000_000));
+
+        // Dust stays with the liquidator
+        assert_eq!(split_seized_collateral(9, 5_000).unwrap(), (5, 4));
+
+        let err = split_seized_collateral(1_000_000, 10_001).unwrap_err();
+        assert_eq!(err, MorphoError::InvalidOperatorShare.into());
+    }
+
+    #[test]
+    fn test_bad_debt_rebate_bounded_by_pending_fees() {
+        use morpho_solana::instructions::bad_debt_rebate;
+
+        let mut market = test_market();
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = 1_000_000 * VIRTUAL_SHARES;
+        market.pending_fee_shares = 500 * VIRTUAL_SHARES;
+
+        // Off by default
+        assert_eq!(bad_debt_rebate(&market, 10_000, 10_000).unwrap(), (0, 0));
+
+        // 10% of the bad debt, burned from pending fee shares
+        market.bad_debt_rebate_bps = 1_000;
+        assert_eq!(bad_debt_rebate(&market, 2_000, 10_000).unwrap(), (200 * VIRTUAL_SHARES, 200));
+
+        // Capped by what the liquidator repaid
+        assert_eq!(bad_debt_rebate(&market, 2_000, 50).unwrap().1, 50);
+
+        // Capped by the pending fees
+        assert_eq!(bad_debt_rebate(&market, 100_000, 100_000).unwrap(), (market.pending_fee_shares, 500));
+
+        // Nothing to pay from
+        market.pending_fee_shares = 0;
+        assert_eq!(bad_debt_rebate(&market, 100_000, 100_000).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn test_collateral_books_track_vault() {
+        use morpho_solana::constants::SECONDS_PER_YEAR;
+        use morpho_solana::instructions::{charge_insurance_premium, reimburse_liquidation_penalty};
+
+        let mut market = test_market();
+        market.insurance_premium_bps = 500;
+        market.insurance_coverage_bps = 10_000;
+        let mut position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: true,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        // Deposit, as supply_collateral books it
+        let mut vault = 1_000_000u64;
+        position.collateral = 1_000_000;
+        market.total_collateral = 1_000_000;
+        assert_eq!(market.collateral_drift(vault).unwrap(), 0);
+
+        // A year of premium moves collateral into the pool, not out of the vault
+        let premium = charge_insurance_premium(&mut market, &mut position, SECONDS_PER_YEAR as i64).unwrap();
+        assert_eq!(premium, 50_000);
+        assert_eq!(market.collateral_drift(vault).unwrap(), 0);
+
+        // Liquidation seizes collateral out of the vault; the covered
+        // penalty moves from the pool to the position's claim
+        let seized = 100_000u128;
+        position.collateral -= seized;
+        market.total_collateral -= seized;
+        vault -= seized as u64;
+        let reimbursed = reimburse_liquidation_penalty(&mut market, &mut position, seized, calculate_lif(8500)).unwrap();
+        assert!(reimbursed > 0);
+        assert_eq!(market.total_insurance_claimable, reimbursed);
+        assert_eq!(market.collateral_drift(vault).unwrap(), 0);
+
+        // Staking moves tokens out of the vault but stays on the books
+        vault -= 200_000;
+        market.collateral_staked = 200_000;
+        assert_eq!(market.collateral_drift(vault).unwrap(), 0);
+
+        // A donation shows as surplus, a shortfall as negative drift
+        assert_eq!(market.collateral_drift(vault + 7).unwrap(), 7);
+        assert_eq!(market.collateral_drift(vault - 7).unwrap(), -7);
+    }
+
+    #[test]
+    fn test_loan_surplus_sweeps_into_fee_shares() {
+        let mut market = test_market();
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = 1_000_000 * 1_000_000;
+        market.total_borrow_assets = 400_000;
+        market.total_borrow_shares = 400_000 * 1_000_000;
+
+        // Vault plus deployed liquidity against what suppliers can withdraw
+        market.loan_deployed = 100_000;
+        assert_eq!(market.loan_surplus(500_000).unwrap(), 0);
+        assert_eq!(market.loan_surplus(500_013).unwrap(), 13);
+        // A shortfall isn't surplus
+        assert_eq!(market.loan_surplus(499_000).unwrap(), 0);
+
+        // Sweeping books the dust as supply owned by pending fee shares
+        let price_before = market.supply_share_price().unwrap();
+        let shares = market.sweep_surplus(13).unwrap();
+        assert_eq!(shares, to_shares_down(13, 1_000_000, 1_000_000 * 1_000_000).unwrap());
+        assert_eq!(market.pending_fee_shares, shares);
+        assert_eq!(market.total_supply_assets, 1_000_013);
+        assert_eq!(market.total_surplus_swept, 13);
+        assert!(market.supply_share_price().unwrap() >= price_before);
+        assert_eq!(market.loan_surplus(500_013).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_share_price_floor_trips_withdraw_only() {
+        use morpho_solana::instructions::enforce_share_price_floor;
+        use morpho_solana::interfaces::socialize_bad_debt;
+
+        let mut market = test_market();
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = 1_000_000 * VIRTUAL_SHARES;
+        market.total_borrow_assets = 500_000;
+        market.total_borrow_shares = 500_000 * VIRTUAL_SHARES;
+        let price = market.supply_share_price().unwrap();
+
+        // Off by default, even after a loss
+        socialize_bad_debt(&mut market, 10_000 * VIRTUAL_SHARES).unwrap();
+        assert!(!enforce_share_price_floor(&mut market).unwrap());
+
+        // A 5% floor tolerates the 1% loss above...
+        market.share_price_floor = price * 95 / 100;
+        assert!(!enforce_share_price_floor(&mut market).unwrap());
+        assert!(!market.withdraw_only);
+
+        // ...but not a further 10%
+        socialize_bad_debt(&mut market, 100_000 * VIRTUAL_SHARES).unwrap();
+        assert!(market.supply_share_price().unwrap() < market.share_price_floor);
+        assert!(enforce_share_price_floor(&mut market).unwrap());
+        assert!(market.withdraw_only);
+        assert!(!enforce_share_price_floor(&mut market).unwrap(), "Trips once");
+    }
+
+    #[test]
+    fn test_supply_only_market_never_lends() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::instructions::{simulate_bundle, BundleAction};
+
+        let mut market = test_market();
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = 1_000_000 * VIRTUAL_SHARES;
+        market.supply_only = true;
+        let clock = Clock { slot: 1, ..Clock::default() };
+        assert_eq!(sample_borrow_rate(&mut market, &clock, &[]).unwrap(), 0);
+
+        let mut position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        // Deposits and withdrawals work as usual
+        let supply = [BundleAction::Supply { assets: 10_000 }];
+        simulate_bundle(false, &mut market, &mut position, &supply, 1_000).unwrap();
+        let withdraw = [BundleAction::Withdraw { assets: 10_000, shares: 0 }];
+        simulate_bundle(false, &mut market.clone(), &mut position.clone(), &withdraw, 1_000).unwrap();
+
+        // Borrowing is refused outright
+        let borrow = [BundleAction::Borrow { assets: 1 }];
+        let err = simulate_bundle(false, &mut market, &mut position, &borrow, 1_000).unwrap_err();
+        assert_eq!(err, MorphoError::BorrowingDisabled.into());
+    }
+
+    #[test]
+    fn test_supply_holding_period_blocks_jit_exit() {
+        use morpho_solana::instructions::{simulate_bundle, BundleAction};
+
+        let mut market = test_market();
+        market.total_supply_assets = 1_000_000;
+        market.total_supply_shares = 1_000_000 * VIRTUAL_SHARES;
+        market.min_supply_holding_period = 3_600;
+        let mut position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        let supply = [BundleAction::Supply { assets: 10_000 }];
+        simulate_bundle(false, &mut market, &mut position, &supply, 1_000).unwrap();
+        assert_eq!(position.locked_supply_shares, position.supply_shares);
+        assert_eq!(position.supply_locked_until, 4_600);
+
+        let withdraw = [BundleAction::Withdraw { assets: 0, shares: position.supply_shares }];
+        assert!(
+            simulate_bundle(false, &mut market.clone(), &mut position.clone(), &withdraw, 4_599).is_err(),
+            "Can't leave within the holding period"
+        );
+        let (projection, _) = simulate_bundle(false, &mut market, &mut position, &withdraw, 4_600).unwrap();
+        assert_eq!(projection.supply_shares, 0);
+    }
+
+    #[test]
+    fn test_dust_supplies_cannot_keep_a_position_locked() {
+        use morpho_solana::instructions::supply::hold_new_supply;
+
+        let mut market = test_market();
+        market.min_supply_holding_period = 3_600;
+        let mut position = Position {
+            bump: 1,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 0,
+            collateral: 0,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        // The victim's own supply locks for the full period
+        hold_new_supply(&market, &mut position, 10_000 * VIRTUAL_SHARES, 1_000).unwrap();
+        assert_eq!(position.supply_locked_until, 4_600);
+
+        // Anyone may supply on its behalf; a stream of dust right before the
+        // unlock doesn't move it
+        for now in [4_000, 4_500, 4_599, 4_599, 4_599] {
+            hold_new_supply(&market, &mut position, VIRTUAL_SHARES, now).unwrap();
+        }
+        assert_eq!(position.supply_locked_until, 4_600);
+        assert_eq!(position.locked_supply_shares_at(4_600), 0);
+
+        // A sizeable supply extends the window by its weight, not to its own end
+        hold_new_supply(&market, &mut position, 10_000 * VIRTUAL_SHARES, 4_600).unwrap();
+        assert_eq!(position.supply_locked_until, 8_200);
+        hold_new_supply(&market, &mut position, 10_000 * VIRTUAL_SHARES, 6_400).unwrap();
+        assert_eq!(position.supply_locked_until, 6_400 + (1_800 + 3_600) / 2);
+    }
+}
+
+// ============================================================================
+// Constants Validation Tests
+// ============================================================================
 
 #[cfg(test)]
 mod constants_tests {
@@ -919,10 +3115,27 @@ mod constants_tests {
     }
 
     #[test]
+    #[cfg(not(feature = "localnet"))]
     fn test_flash_loan_fee() {
         assert_eq!(FLASH_LOAN_FEE_BPS, 5, "Flash loan fee should be 5 bps (0.05%)");
     }
 
+    #[test]
+    #[cfg(feature = "localnet")]
+    fn test_flash_loan_fee() {
+        assert_eq!(FLASH_LOAN_FEE_BPS, 0, "Flash loans should be free on localnet");
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "devnet", feature = "localnet")))]
+    fn test_mainnet_oracle_bounds() {
+        assert_eq!(
+            MIN_ORACLE_PRICE * 1_000_000_000, ORACLE_SCALE,
+            "Mainnet MIN_ORACLE_PRICE should mirror the 1e9 max ratio"
+        );
+        assert_eq!(MAX_BORROW_RATE_PER_SECOND, WAD * 10 / SECONDS_PER_YEAR, "1000% APY cap");
+    }
+
     #[test]
     fn test_virtual_offset() {
         assert_eq!(VIRTUAL_SHARES, 1_000_000, "VIRTUAL_SHARES should be 1e6");
@@ -1052,6 +3265,7 @@ mod integration_tests {
             accounts: morpho_accounts::EnableLltv {
                 protocol_state: protocol_state_pda,
                 owner: env.owner.pubkey(),
+                system_program: system_program::ID,
             }.to_account_metas(None),
             data: morpho_ix::EnableLltv {
                 lltv: LLTV_85_PERCENT,
@@ -1110,6 +3324,7 @@ mod integration_tests {
             accounts: morpho_accounts::EnableLltv {
                 protocol_state: protocol_state_pda,
                 owner: env.owner.pubkey(),
+                system_program: system_program::ID,
             }.to_account_metas(None),
             data: morpho_ix::EnableLltv {
                 lltv: LLTV_85_PERCENT,
@@ -1325,6 +3540,511 @@ mod integration_tests {
 mod error_tests {
     use super::*;
 
+    #[test]
+    fn test_receiver_token_account_need_not_be_an_ata() {
+        use anchor_lang::solana_program::account_info::AccountInfo;
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token::spl_token as token;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::instructions::prepare_receiver_token_account;
+
+        let mint = Pubkey::new_unique();
+        let receiver = Pubkey::new_unique();
+        let token_program = token::ID;
+        let account_of = |mint: Pubkey| {
+            let mut data = vec![0u8; token::state::Account::LEN];
+            token::state::Account {
+                mint,
+                owner: receiver,
+                state: token::state::AccountState::Initialized,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            data
+        };
+
+        // Every account's storage outlives every AccountInfo borrowing it
+        let (key, other, foreign_owner) = (Pubkey::new_unique(), Pubkey::default(), Pubkey::new_unique());
+        let mut lamports = [1u64; 8];
+        let [l0, l1, l2, l3, l4, l5, l6, l7] = &mut lamports;
+        let (mut d0, mut d1, mut d2, mut d3) = (vec![], vec![], vec![], vec![]);
+        let (mut plain_data, mut wrong_mint_data) = (account_of(mint), account_of(Pubkey::new_unique()));
+        let (mut foreign_data, mut missing_data) = (account_of(mint), vec![]);
+        *l7 = 0;
+
+        let receiver_info = AccountInfo::new(&receiver, false, false, l0, &mut d0, &other, false, 0);
+        let mint_info = AccountInfo::new(&mint, false, false, l1, &mut d1, &token_program, false, 0);
+        let program_info = AccountInfo::new(&token_program, false, false, l2, &mut d2, &other, true, 0);
+        let payer_info = AccountInfo::new(&other, true, true, l3, &mut d3, &other, false, 0);
+        let plain = AccountInfo::new(&key, false, true, l4, &mut plain_data, &token_program, false, 0);
+        let wrong_mint = AccountInfo::new(&key, false, true, l5, &mut wrong_mint_data, &token_program, false, 0);
+        let foreign = AccountInfo::new(&key, false, true, l6, &mut foreign_data, &foreign_owner, false, 0);
+        let missing = AccountInfo::new(&key, false, true, l7, &mut missing_data, &other, false, 0);
+        macro_rules! check {
+            ($account:expr) => {
+                prepare_receiver_token_account(
+                    &$account,
+                    &receiver_info,
+                    &mint_info,
+                    &payer_info,
+                    &program_info,
+                    &program_info,
+                    &program_info,
+                )
+            };
+        }
+
+        // Any existing token account of the mint, wherever it lives
+        assert!(check!(plain).is_ok());
+        assert_eq!(check!(wrong_mint).unwrap_err(), MorphoError::InvalidMint.into());
+        assert_eq!(check!(foreign).unwrap_err(), MorphoError::InvalidReceiverTokenAccount.into());
+
+        // Nothing there yet: only the receiver's ATA may be created
+        assert_eq!(check!(missing).unwrap_err(), MorphoError::InvalidReceiverTokenAccount.into());
+    }
+
+    #[test]
+    fn test_irm_sandbox_rejects_bad_return_data() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::constants::IRM_COMPUTE_BUDGET;
+        use morpho_solana::interfaces::{check_irm_compute, decode_irm_return_data};
+
+        let irm = Pubkey::new_unique();
+        let rate = WAD / 20 / 31_536_000;
+        let returned = |program: Pubkey, data: Vec<u8>| Some((program, data));
+
+        assert_eq!(decode_irm_return_data(&irm, returned(irm, rate.to_le_bytes().to_vec())).unwrap(), rate);
+
+        let err = |result: anchor_lang::Result<u128>| result.unwrap_err();
+        assert_eq!(err(decode_irm_return_data(&irm, None)), MorphoError::IrmNoReturnData.into());
+        assert_eq!(
+            err(decode_irm_return_data(&irm, returned(Pubkey::new_unique(), rate.to_le_bytes().to_vec()))),
+            MorphoError::IrmInvalidProgram.into(),
+        );
+        // Exactly 16 bytes: no truncated rates, no trailing data
+        assert_eq!(err(decode_irm_return_data(&irm, returned(irm, vec![0u8; 8]))), MorphoError::IrmInvalidReturnData.into());
+        assert_eq!(err(decode_irm_return_data(&irm, returned(irm, vec![0u8; 17]))), MorphoError::IrmInvalidReturnData.into());
+        assert_eq!(
+            err(decode_irm_return_data(&irm, returned(irm, u128::MAX.to_le_bytes().to_vec()))),
+            MorphoError::IrmRateTooHigh.into(),
+        );
+
+        assert!(check_irm_compute(200_000, 200_000 - IRM_COMPUTE_BUDGET).is_ok());
+        assert_eq!(
+            check_irm_compute(200_000, 200_000 - IRM_COMPUTE_BUDGET - 1).unwrap_err(),
+            MorphoError::IrmComputeBudgetExceeded.into(),
+        );
+    }
+
+    #[test]
+    fn test_irm_program_markets_need_their_irm() {
+        use anchor_lang::solana_program::account_info::AccountInfo;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::{get_borrow_rate_internal, get_market_borrow_rate};
+        use morpho_solana::constants::MAX_IRM_FAILURES;
+        use morpho_solana::math::{sample_borrow_rate, view_borrow_rate};
+        use morpho_solana::state::MarketAction;
+
+        let at = |slot| Clock { slot, ..Clock::default() };
+        let mut market = test_market();
+        market.irm = Pubkey::new_unique();
+        market.total_supply_assets = 1_000_000;
+        market.total_borrow_assets = 800_000;
+
+        // Built-in curve markets never look for the IRM
+        assert_eq!(
+            get_market_borrow_rate(&market, &[]).unwrap(),
+            get_borrow_rate_internal(1_000_000, 800_000).unwrap()
+        );
+
+        market.irm_cpi = true;
+        assert_eq!(get_market_borrow_rate(&market, &[]).unwrap_err(), MorphoError::InvalidIrm.into());
+
+        // Found by address but no longer a program: the IRM was closed
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![];
+        let irm_key = market.irm;
+        let irm = AccountInfo::new(&irm_key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(
+            get_market_borrow_rate(&market, std::slice::from_ref(&irm)).unwrap_err(),
+            MorphoError::IrmError.into()
+        );
+
+        // An IRM fault doesn't fail the accrual: the slot keeps the cached rate
+        market.rate_cache = 42;
+        assert_eq!(sample_borrow_rate(&mut market, &at(9), std::slice::from_ref(&irm)).unwrap(), 42);
+        assert_eq!(market.rate_cache_slot, 9);
+
+        assert_eq!(market.irm_failures, 1);
+
+        // A missing IRM is the caller's to fix, and isn't counted against it
+        assert_eq!(sample_borrow_rate(&mut market, &at(10), &[]).unwrap_err(), MorphoError::InvalidIrm.into());
+        assert_eq!(market.irm_failures, 1);
+
+        // Counted once per slot; enough failing slots in a row close the market to new positions
+        sample_borrow_rate(&mut market, &at(11), std::slice::from_ref(&irm)).unwrap();
+        sample_borrow_rate(&mut market, &at(11), std::slice::from_ref(&irm)).unwrap();
+        assert_eq!(market.irm_failures, 2);
+        assert!(!market.withdraw_only);
+        sample_borrow_rate(&mut market, &at(12), std::slice::from_ref(&irm)).unwrap();
+        assert_eq!(market.irm_failures, MAX_IRM_FAILURES);
+        assert!(market.withdraw_only);
+        assert_eq!(market.check_action(MarketAction::Borrow).unwrap_err(), MorphoError::MarketWithdrawOnly.into());
+        assert!(market.check_action(MarketAction::Withdraw).is_ok());
+        assert!(market.check_action(MarketAction::Repay).is_ok());
+
+        // Views fall back to the cached rate and say so
+        let rate = view_borrow_rate(&market, &[]).unwrap();
+        assert_eq!((rate.borrow_rate, rate.cached), (42, true));
+        market.irm_cpi = false;
+        assert!(!view_borrow_rate(&market, &[]).unwrap().cached);
+
+        // Any good sample clears the count
+        sample_borrow_rate(&mut market, &at(13), &[]).unwrap();
+        assert_eq!(market.irm_failures, 0);
+    }
+
+    #[test]
+    fn test_flash_loan_split_across_markets() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::instructions::split_flash_loan;
+
+        // Drawn in order, each market up to its liquidity
+        assert_eq!(split_flash_loan(120, &[100, 50, 200]).unwrap(), vec![100, 20, 0]);
+        assert_eq!(split_flash_loan(350, &[100, 50, 200]).unwrap(), vec![100, 50, 200]);
+        assert_eq!(split_flash_loan(30, &[0, 50]).unwrap(), vec![0, 30]);
+        assert_eq!(
+            split_flash_loan(351, &[100, 50, 200]).unwrap_err(),
+            MorphoError::InsufficientLiquidity.into()
+        );
+
+        // A market with funds deployed to an adapter lends only what its vault holds
+        let mut deployed = test_market();
+        deployed.total_supply_assets = 100;
+        deployed.loan_deployed = 60;
+        let liquidity = [deployed.vault_liquidity(40), 200];
+        assert_eq!(split_flash_loan(120, &liquidity).unwrap(), vec![40, 80]);
+    }
+
+    #[test]
+    fn test_adaptive_curve_moves_rate_at_target() {
+        use morpho_solana::interfaces::{
+            curve, w_exp, AdaptiveCurveIrm, INITIAL_RATE_AT_TARGET, MAX_RATE_AT_TARGET, MIN_RATE_AT_TARGET,
+        };
+
+        // e^x within 1%, like Morpho's second order expansion
+        assert_eq!(w_exp(0), WAD);
+        for x in [WAD as i128, -(WAD as i128), 5 * WAD as i128] {
+            let exact = (x as f64 / WAD as f64).exp();
+            let got = w_exp(x) as f64 / WAD as f64;
+            assert!((got / exact - 1.0).abs() < 0.01, "e^{} = {}", x as f64 / 1e18, got);
+        }
+        assert_eq!(w_exp(-50 * WAD as i128), 0);
+
+        // The curve spans a quarter to four times the rate at target
+        let rate = INITIAL_RATE_AT_TARGET;
+        assert_eq!(curve(rate, 0).unwrap(), rate);
+        assert_eq!(curve(rate, WAD as i128).unwrap(), rate * 4);
+        assert_eq!(curve(rate, -(WAD as i128)).unwrap(), rate / 4);
+
+        let mut irm = AdaptiveCurveIrm {
+            bump: 0,
+            market_id: [0u8; 32],
+            rate_at_target: rate,
+            last_update: 0,
+        };
+
+        // At target nothing moves
+        assert_eq!(irm.borrow_rate(WAD * 9 / 10, 365 * 86_400).unwrap(), rate);
+        assert_eq!(irm.rate_at_target, rate);
+
+        // A day at full utilization: e^(50/365) ≈ 1.147 up, priced above 4x start
+        let start = irm.last_update;
+        let full = irm.borrow_rate(WAD, start + 86_400).unwrap();
+        let growth = irm.rate_at_target as f64 / rate as f64;
+        assert!((growth - 1.147).abs() < 0.005, "growth {}", growth);
+        assert!(full > rate * 4 && full < irm.rate_at_target * 4);
+        assert_eq!(irm.last_update, start + 86_400);
+
+        // Idle for long enough, the rate at target hits its floor; busy, its ceiling
+        irm.borrow_rate(0, start + 365 * 86_400).unwrap();
+        assert_eq!(irm.rate_at_target, MIN_RATE_AT_TARGET);
+        irm.borrow_rate(WAD, start + 2 * 365 * 86_400).unwrap();
+        assert_eq!(irm.rate_at_target, MAX_RATE_AT_TARGET);
+    }
+
+    #[test]
+    fn test_adaptive_curve_markets_need_their_state() {
+        use anchor_lang::solana_program::account_info::AccountInfo;
+        use anchor_lang::AccountSerialize;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::{derive_adaptive_curve_model, get_market_borrow_rate, AdaptiveCurveIrm};
+
+        let mut market = test_market();
+        market.market_id = [7u8; 32];
+        market.irm = derive_adaptive_curve_model(&morpho_solana::ID).0;
+        market.irm_adaptive = true;
+        assert_eq!(get_market_borrow_rate(&market, &[]).unwrap_err(), MorphoError::InvalidIrm.into());
+
+        let state_of = |market_id| {
+            let mut data = vec![];
+            AdaptiveCurveIrm { bump: 0, market_id, rate_at_target: 1, last_update: 0 }
+                .try_serialize(&mut data)
+                .unwrap();
+            data
+        };
+        let key = Pubkey::new_unique();
+        let owner = morpho_solana::ID;
+        let mut lamports = 0;
+
+        // Another market's state doesn't count
+        let mut data = state_of([8u8; 32]);
+        let other = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(
+            get_market_borrow_rate(&market, std::slice::from_ref(&other)).unwrap_err(),
+            MorphoError::InvalidIrm.into()
+        );
+
+        // Its own must be writable, since sampling moves it
+        let mut lamports = 0;
+        let mut data = state_of([7u8; 32]);
+        let read_only = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(
+            get_market_borrow_rate(&market, std::slice::from_ref(&read_only)).unwrap_err(),
+            MorphoError::InvalidIrm.into()
+        );
+    }
+
+    #[test]
+    fn test_irm_switch_deviation_bound() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::constants::MAX_IRM_SWITCH_DEVIATION_BPS;
+        use morpho_solana::instructions::check_irm_switch;
+
+        let mut market = test_market();
+        let rate = 1_000_000_000u128;
+
+        // Nobody borrows: any new rate goes, even from zero
+        check_irm_switch(&market, 0, rate).unwrap();
+        check_irm_switch(&market, rate, rate * 10).unwrap();
+
+        // Borrowed: within MAX_IRM_SWITCH_DEVIATION_BPS either way
+        market.total_borrow_assets = 1;
+        let bound = rate * MAX_IRM_SWITCH_DEVIATION_BPS as u128 / BPS as u128;
+        check_irm_switch(&market, rate, rate + bound).unwrap();
+        check_irm_switch(&market, rate + bound, rate).unwrap();
+        for new_rate in [rate + bound + rate / BPS as u128, 0] {
+            assert_eq!(
+                check_irm_switch(&market, rate, new_rate).unwrap_err(),
+                MorphoError::IrmSwitchDeviation.into()
+            );
+        }
+
+        // Stable debt counts too
+        market.total_borrow_assets = 0;
+        market.total_stable_borrow_assets = 1;
+        assert!(check_irm_switch(&market, rate, rate * 2).is_err());
+    }
+
+    #[test]
+    fn test_clock_override_needs_its_authority() {
+        use anchor_lang::solana_program::account_info::AccountInfo;
+        use anchor_lang::AccountSerialize;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::state::ClockOverride;
+        use morpho_solana::time::override_timestamp;
+
+        let authority = Pubkey::new_unique();
+        let mut data = vec![];
+        ClockOverride { bump: 0, authority, unix_timestamp: 1_900_000_000 }.try_serialize(&mut data).unwrap();
+        let key = Pubkey::new_unique();
+        let program = morpho_solana::ID;
+        let stranger = Pubkey::new_unique();
+        let (mut signer_lamports, mut signer_data) = (0, vec![]);
+        let (mut stranger_lamports, mut stranger_data) = (0, vec![]);
+
+        // No override: the sysvar's time stands
+        assert_eq!(override_timestamp(&[]).unwrap(), None);
+
+        // An override-shaped account the program doesn't own is ignored
+        let (mut lamports, mut forged) = (0, data.clone());
+        let foreign = AccountInfo::new(&key, false, false, &mut lamports, &mut forged, &stranger, false, 0);
+        assert_eq!(override_timestamp(std::slice::from_ref(&foreign)).unwrap(), None);
+
+        // Only the authority that set the override may run at it
+        let mut lamports = 0;
+        let clock_override = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &program, false, 0);
+        let impostor =
+            AccountInfo::new(&stranger, true, false, &mut stranger_lamports, &mut stranger_data, &program, false, 0);
+        assert_eq!(
+            override_timestamp(&[clock_override.clone(), impostor]).unwrap_err(),
+            MorphoError::Unauthorized.into()
+        );
+        let mut signer = AccountInfo::new(&authority, false, false, &mut signer_lamports, &mut signer_data, &program, false, 0);
+        assert_eq!(
+            override_timestamp(&[clock_override.clone(), signer.clone()]).unwrap_err(),
+            MorphoError::Unauthorized.into()
+        );
+
+        // ...and it's picked up wherever it sits
+        signer.is_signer = true;
+        assert_eq!(override_timestamp(&[foreign, signer, clock_override]).unwrap(), Some(1_900_000_000));
+    }
+
+    #[test]
+    fn test_oracle_failure_modes_map_to_oracle_errors() {
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::OracleFailureMode;
+
+        assert_eq!(OracleFailureMode::from_u8(0), Some(OracleFailureMode::None));
+        assert!(OracleFailureMode::None.error().is_none());
+        assert_eq!(OracleFailureMode::from_u8(6), None, "Unknown modes are ignored");
+
+        let expected = [
+            (1, MorphoError::OracleStale),
+            (2, MorphoError::OracleInvalidReturnData),
+            (3, MorphoError::OraclePriceTooLow),
+            (4, MorphoError::OraclePriceTooHigh),
+            (5, MorphoError::InvalidOracle),
+        ];
+        for (mode, err) in expected {
+            let mode = OracleFailureMode::from_u8(mode).unwrap();
+            assert_eq!(mode as u8, OracleFailureMode::from_u8(mode as u8).unwrap() as u8);
+            assert_eq!(mode.error().map(|e| e as u32), Some(err as u32));
+        }
+    }
+
+    #[test]
+    fn test_deleveraging_ignores_failing_oracle() {
+        use anchor_lang::solana_program::account_info::AccountInfo;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::instructions::check_adjusted_health;
+
+        let oracle_key = Pubkey::new_unique();
+        let market = Market {
+            oracle: oracle_key,
+            total_supply_assets: 10_000_000,
+            total_supply_shares: 10_000_000_000_000,
+            total_borrow_assets: 5_000_000,
+            total_borrow_shares: 5_000_000_000_000,
+            ..test_market()
+        };
+        let position = Position {
+            bump: 0,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: 5_000_000_000_000,
+            collateral: 1_000,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        };
+
+        // Static oracle reporting a zero price: every read fails
+        let program_id = morpho_solana::ID;
+        let mut lamports = 0u64;
+        let mut data = [0u8; 25];
+        let oracle = AccountInfo::new(
+            &oracle_key, false, false, &mut lamports, &mut data, &program_id, false, 0,
+        );
+
+        let borrow_lltv = market.position_borrow_lltv(&position, 0);
+
+        // Repay + collateral top-up with a dead feed, or no feed at all
+        assert!(!check_adjusted_health(&market, &position, borrow_lltv, 0, Some(&oracle), 0, 100, 50, 0).unwrap());
+        assert!(!check_adjusted_health(&market, &position, borrow_lltv, 0, None, 0, 100, 50, 0).unwrap());
+
+        // Anything that can raise LTV still has to price the position
+        let err = check_adjusted_health(&market, &position, borrow_lltv, 0, Some(&oracle), 1, 0, 0, 0).unwrap_err();
+        assert_eq!(err, MorphoError::OraclePriceTooLow.into());
+        let err = check_adjusted_health(&market, &position, borrow_lltv, 0, None, 0, 0, 0, 1).unwrap_err();
+        assert_eq!(err, MorphoError::InvalidOracle.into());
+    }
+
+    #[test]
+    fn test_oracle_divergence_measure() {
+        use morpho_solana::instructions::price_divergence_bps;
+
+        // Measured against the lower feed, symmetric in argument order
+        assert_eq!(price_divergence_bps(ORACLE_SCALE, ORACLE_SCALE), 0);
+        assert_eq!(price_divergence_bps(100 * ORACLE_SCALE / 100, 103 * ORACLE_SCALE / 100), 300);
+        assert_eq!(price_divergence_bps(103 * ORACLE_SCALE / 100, 100 * ORACLE_SCALE / 100), 300);
+
+        // A feed reading zero, or a gap too large to scale, maxes out
+        assert_eq!(price_divergence_bps(0, ORACLE_SCALE), u64::MAX);
+        assert_eq!(price_divergence_bps(1, u128::MAX), u64::MAX);
+        assert_eq!(price_divergence_bps(0, 0), 0);
+    }
+
+    #[test]
+    fn test_risk_oracle_soft_cap() {
+        use anchor_lang::solana_program::account_info::AccountInfo;
+        use morpho_solana::constants::MAX_RISK_ORACLE_AGE;
+        use morpho_solana::errors::MorphoError;
+        use morpho_solana::interfaces::{enforce_risk_cap, risk_debt_cap};
+
+        let feed_key = Pubkey::new_unique();
+        let mut market = Market {
+            total_supply_assets: 10_000_000,
+            total_supply_shares: 10_000_000_000_000,
+            total_borrow_assets: 1_050_000,
+            total_borrow_shares: 1_050_000_000_000,
+            ..test_market()
+        };
+
+        // Feed reporting 1M max safe debt, published at t=1000
+        let now: i64 = 1_000;
+        let mut data = vec![0u8; 65];
+        data[9..25].copy_from_slice(&1_000_000u128.to_le_bytes());
+        data[25..33].copy_from_slice(&now.to_le_bytes());
+        let mut lamports = 0u64;
+        let owner = Pubkey::new_unique();
+        let feed = AccountInfo::new(&feed_key, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+        // No risk oracle configured: uncapped, no feed needed
+        assert!(enforce_risk_cap(&market, None, now).is_ok());
+
+        market.risk_oracle = feed_key;
+        let err = |r: anchor_lang::Result<()>| r.unwrap_err();
+        assert_eq!(err(enforce_risk_cap(&market, None, now)), MorphoError::InvalidRiskOracle.into());
+
+        // 1.05M borrowed against a 1M figure: over the hard number...
+        assert_eq!(err(enforce_risk_cap(&market, Some(&feed), now)), MorphoError::RiskCapExceeded.into());
+
+        // ...but inside a 5% owner buffer
+        market.risk_cap_buffer_bps = 500;
+        assert_eq!(risk_debt_cap(1_000_000, 500).unwrap(), 1_050_000);
+        assert!(enforce_risk_cap(&market, Some(&feed), now).is_ok());
+
+        // A figure nobody refreshed stops borrows rather than being trusted
+        assert_eq!(
+            err(enforce_risk_cap(&market, Some(&feed), now + MAX_RISK_ORACLE_AGE + 1)),
+            MorphoError::RiskOracleStale.into()
+        );
+    }
+
     #[test]
     fn test_safe_u128_to_u64_overflow() {
         let max_u64 = u64::MAX as u128;
@@ -1481,30 +4201,31 @@ mod scenario_tests {
     }
 
     /// Test bad debt socialization
+    #[test]
+    fn test_deleveraging_bundle_skips_health_check() {
+        use morpho_solana::instructions::requires_health_check;
+
+        // Net repay plus extra collateral can't raise LTV: no oracle needed
+        assert!(!requires_health_check(500, 100, 300, 50, 0));
+        // Repay alone, or collateral swapped out 1:1 with more coming in
+        assert!(!requires_health_check(500, 0, 1, 0, 0));
+        assert!(!requires_health_check(500, 0, 0, 20, 20));
+        // Position ends debt-free: nothing to check even if collateral leaves
+        assert!(!requires_health_check(0, 0, 700, 0, 1_000));
+
+        // Net new debt or net collateral out must be priced
+        assert!(requires_health_check(500, 301, 300, 1_000, 0));
+        assert!(requires_health_check(500, 0, 300, 0, 1));
+    }
+
     #[test]
     fn test_bad_debt_simulation() {
         let mut market = Market {
-            bump: 0,
-            market_id: [0u8; 32],
-            collateral_mint: Pubkey::default(),
-            loan_mint: Pubkey::default(),
-            collateral_decimals: 9,
-            loan_decimals: 6,
-            oracle: Pubkey::default(),
-            irm: Pubkey::default(),
-            lltv: 8500,
-            paused: false,
-            fee: 0,
-            total_supply_assets: 10_000_000_000_000, // 10M supplied
+            total_supply_assets: 10_000_000_000_000,
             total_supply_shares: 10_000_000_000_000_000_000,
-            total_borrow_assets: 1_000_000_000_000, // 1M borrowed
+            total_borrow_assets: 1_000_000_000_000,
             total_borrow_shares: 1_000_000_000_000_000_000,
-            last_update: 0,
-            pending_fee_shares: 0,
-            collateral_vault_bump: 0,
-            loan_vault_bump: 0,
-            flash_loan_lock: 0,
-            reserved: [0u8; 127],
+            ..test_market()
         };
 
         let initial_supply = market.total_supply_assets;
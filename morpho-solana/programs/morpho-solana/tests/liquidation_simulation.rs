@@ -0,0 +1,302 @@
+//! Liquidation Bot Simulation
+//!
+//! Spins up a book of random healthy borrowers, shocks the oracle price, and
+//! lets a bot liquidate everything it can using the same accounting as
+//! `instructions::liquidate`. After the bot pass no position may still be
+//! liquidatable and no debt may be left without collateral behind it.
+//!
+//! Also pins the liquidation capacity margin per LLTV: the largest price drop
+//! a max-LTV position can take before its collateral no longer covers
+//! `debt * LIF`, i.e. before a liquidation has to socialize bad debt.
+
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use proptest::prelude::*;
+
+use morpho_solana::constants::{BPS, LIF_BPS, ORACLE_SCALE, VIRTUAL_SHARES};
+use morpho_solana::state::{Market, Position};
+use common::test_market;
+use morpho_solana::math::*;
+use morpho_solana::interfaces::{
+    calculate_lif, calculate_seized_collateral, is_liquidatable, socialize_bad_debt,
+};
+
+// ============================================================================
+// Simulation Harness
+// ============================================================================
+
+/// 1:1 price, so collateral and debt are directly comparable
+const INITIAL_PRICE: u128 = ORACLE_SCALE;
+
+/// Largest collateral a generated position holds; small positions keep
+/// rounding effects visible in the margins
+const MAX_COLLATERAL: u128 = 340;
+
+/// LLTVs the margin table covers (Morpho Blue's standard tiers)
+const LLTV_TIERS: [u64; 8] = [3_850, 6_250, 7_700, 8_600, 9_150, 9_450, 9_650, 9_800];
+
+/// Cap on liquidation calls per position before the bot gives up
+const MAX_LIQUIDATIONS_PER_POSITION: usize = 8;
+
+struct Book {
+    market: Market,
+    positions: Vec<Position>,
+}
+
+/// Outcome of one bot pass over the book
+#[derive(Debug, Default)]
+struct BotReport {
+    liquidations: u64,
+    repaid_assets: u128,
+    seized_collateral: u128,
+    bad_debt: u128,
+}
+
+fn empty_market(lltv: u64) -> Market {
+    Market {
+        lltv,
+        ..test_market()
+    }
+}
+
+/// Open one position per `(collateral, utilization_bps)` at `INITIAL_PRICE`
+///
+/// Each borrows `utilization_bps` of its max borrow, rounded like `borrow`.
+/// Suppliers provide twice the borrowed liquidity.
+fn open_book(lltv: u64, borrowers: &[(u128, u64)]) -> Book {
+    let mut market = empty_market(lltv);
+    let mut positions = Vec::with_capacity(borrowers.len());
+
+    for &(collateral, utilization_bps) in borrowers {
+        let collateral_value = mul_div_down(collateral, INITIAL_PRICE, ORACLE_SCALE).unwrap();
+        let max_borrow = mul_div_down(collateral_value, lltv as u128, BPS as u128).unwrap();
+        let assets = mul_div_down(max_borrow, utilization_bps as u128, BPS as u128).unwrap();
+        let shares = to_shares_up(assets, market.total_borrow_assets, market.total_borrow_shares).unwrap();
+
+        market.total_borrow_assets += assets;
+        market.total_borrow_shares += shares;
+        positions.push(Position {
+            bump: 0,
+            market_id: [0u8; 32],
+            owner: Pubkey::new_unique(),
+            supply_shares: 0,
+            borrow_shares: shares,
+            collateral,
+            credit_delegated: 0,
+            locked_supply_shares: 0,
+            supply_locked_until: 0,
+            total_supplied: 0,
+            total_withdrawn: 0,
+            total_borrowed: 0,
+            total_repaid: 0,
+            times_liquidated: 0,
+            reputation_boosted: false,
+            insured: false,
+            insurance_paid_until: 0,
+            insurance_claimable: 0,
+            stable_borrow_assets: 0,
+            stable_rate: 0,
+            stable_last_update: 0,
+            last_liquidation_slot: 0,
+            last_liquidator: Pubkey::default(),
+            escrowed_supply_shares: 0,
+            escrow_unlock_at: 0,
+            opened_at: 0,
+            last_borrowed_at: 0,
+            seasoned_repaid: 0,
+            reserved: [0u8; 6],
+        });
+    }
+
+    market.total_supply_assets = market.total_borrow_assets * 2;
+    market.total_supply_shares = market.total_supply_assets * VIRTUAL_SHARES;
+    Book { market, positions }
+}
+
+fn liquidatable(market: &Market, position: &Position, price: u128) -> bool {
+    is_liquidatable(
+        position.collateral,
+        position.borrow_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+        price,
+        market.lltv,
+    )
+    .unwrap()
+}
+
+/// Largest repay whose seizure still fits in the position's collateral
+///
+/// Never below 1: a dust position is cleared by seizing its last units,
+/// which hands the leftover debt to bad-debt socialization.
+fn bot_repay_amount(market: &Market, position: &Position, price: u128) -> u128 {
+    let debt = to_assets_up(
+        position.borrow_shares,
+        market.total_borrow_assets,
+        market.total_borrow_shares,
+    )
+    .unwrap();
+    let collateral_value = mul_div_down(position.collateral, price, ORACLE_SCALE).unwrap();
+    let covered = mul_div_down(collateral_value, LIF_BPS as u128, calculate_lif(market.lltv) as u128).unwrap();
+    debt.min(covered).max(1)
+}
+
+/// Reference liquidation: the effects of `instructions::liquidate`
+fn liquidate(market: &mut Market, position: &mut Position, price: u128, repay: u128, report: &mut BotReport) {
+    let lif = calculate_lif(market.lltv);
+    let seized = calculate_seized_collateral(repay, price, lif).unwrap();
+    let seized = std::cmp::min(seized, position.collateral);
+
+    let repaid_shares = to_shares_down(repay, market.total_borrow_assets, market.total_borrow_shares).unwrap();
+    let repaid_shares = std::cmp::min(repaid_shares, position.borrow_shares);
+    let repaid_assets = to_assets_up(repaid_shares, market.total_borrow_assets, market.total_borrow_shares).unwrap();
+
+    position.borrow_shares -= repaid_shares;
+    position.collateral -= seized;
+    position.times_liquidated += 1;
+    market.total_borrow_shares -= repaid_shares;
+    market.total_borrow_assets -= repaid_assets;
+    market.total_liquidated_collateral += seized;
+
+    if position.collateral == 0 && position.borrow_shares > 0 {
+        report.bad_debt += socialize_bad_debt(market, position.borrow_shares).unwrap();
+        position.borrow_shares = 0;
+    }
+
+    report.liquidations += 1;
+    report.repaid_assets += repaid_assets;
+    report.seized_collateral += seized;
+}
+
+/// Liquidate every reachable position at `price`
+fn run_bot(book: &mut Book, price: u128) -> BotReport {
+    let mut report = BotReport::default();
+    let Book { market, positions } = book;
+
+    for position in positions.iter_mut() {
+        for _ in 0..MAX_LIQUIDATIONS_PER_POSITION {
+            if !liquidatable(market, position, price) {
+                break;
+            }
+            let repay = bot_repay_amount(market, position, price);
+            liquidate(market, position, price, repay, &mut report);
+        }
+    }
+    report
+}
+
+/// Apply a `shock_bps` drop to `INITIAL_PRICE`
+fn shocked_price(shock_bps: u64) -> u128 {
+    INITIAL_PRICE / BPS as u128 * (BPS - shock_bps) as u128
+}
+
+/// Largest price drop (bps) a position opened at `lltv` survives without bad debt
+///
+/// Bad debt needs `debt * LIF > collateral_value`, and a max-LTV position
+/// reaches that once the price falls by `1 - lltv * LIF`.
+fn bad_debt_free_shock_bps(lltv: u64) -> u64 {
+    BPS - lltv * calculate_lif(lltv) / LIF_BPS
+}
+
+/// Post-pass invariants: nothing reachable left, totals still reconcile
+fn assert_book_settled(book: &Book, price: u128, initial_supply_assets: u128, report: &BotReport) {
+    let market = &book.market;
+    let mut borrow_shares = 0u128;
+    let mut times_liquidated = 0u64;
+
+    for position in &book.positions {
+        assert!(!liquidatable(market, position, price), "bot left a liquidatable position");
+        assert!(
+            position.borrow_shares == 0 || position.collateral > 0,
+            "debt left with no collateral behind it",
+        );
+        borrow_shares += position.borrow_shares;
+        times_liquidated += position.times_liquidated;
+    }
+
+    assert_eq!(market.total_borrow_shares, borrow_shares, "borrow shares must reconcile");
+    assert_eq!(
+        market.total_supply_assets,
+        initial_supply_assets - report.bad_debt,
+        "suppliers absorb exactly the socialized bad debt",
+    );
+    assert_eq!(market.total_bad_debt, report.bad_debt, "lifetime bad debt matches the pass");
+    assert_eq!(market.total_liquidated_collateral, report.seized_collateral);
+    assert_eq!(times_liquidated, report.liquidations);
+}
+
+// ============================================================================
+// Simulations
+// ============================================================================
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    /// Any shock, any book: the bot leaves nothing liquidatable behind
+    #[test]
+    fn prop_bot_clears_all_reachable_bad_debt(
+        lltv in prop::sample::select(LLTV_TIERS.to_vec()),
+        borrowers in prop::collection::vec((1u128..=MAX_COLLATERAL, 1_000u64..=9_990), 50..300),
+        shock_bps in 0u64..9_000,
+    ) {
+        let mut book = open_book(lltv, &borrowers);
+        let initial_supply_assets = book.market.total_supply_assets;
+        for position in &book.positions {
+            prop_assert!(!liquidatable(&book.market, position, INITIAL_PRICE));
+        }
+
+        let price = shocked_price(shock_bps);
+        let report = run_bot(&mut book, price);
+        assert_book_settled(&book, price, initial_supply_assets, &report);
+    }
+
+    /// Shocks inside the capacity margin are fully absorbed by liquidators
+    #[test]
+    fn prop_shock_within_margin_leaves_no_bad_debt(
+        lltv in prop::sample::select(LLTV_TIERS.to_vec()),
+        borrowers in prop::collection::vec((250u128..=MAX_COLLATERAL, 1_000u64..=9_990), 50..300),
+        margin_used_bps in 0u64..=8_000,
+    ) {
+        let shock_bps = bad_debt_free_shock_bps(lltv) * margin_used_bps / BPS;
+        let mut book = open_book(lltv, &borrowers);
+        let initial_supply_assets = book.market.total_supply_assets;
+
+        let price = shocked_price(shock_bps);
+        let report = run_bot(&mut book, price);
+        assert_book_settled(&book, price, initial_supply_assets, &report);
+        prop_assert_eq!(report.bad_debt, 0, "shock {} bps at lltv {} stayed inside the margin", shock_bps, lltv);
+    }
+}
+
+#[test]
+fn test_liquidation_capacity_margins() {
+    let mut previous = BPS;
+    for lltv in LLTV_TIERS {
+        let margin = bad_debt_free_shock_bps(lltv);
+        assert!(margin > 0, "lltv {} has no room for liquidators", lltv);
+        // Riskier tiers leave liquidators less room
+        assert!(margin < previous, "margin must shrink as lltv rises");
+        previous = margin;
+    }
+
+    // Pin the tiers that matter most: ~10% at 86%, ~1.4% at 98%
+    assert_eq!(bad_debt_free_shock_bps(8_600), 1_024);
+    assert_eq!(bad_debt_free_shock_bps(9_800), 142);
+}
+
+#[test]
+fn test_shock_past_margin_is_socialized() {
+    let lltv = 8_600;
+    let borrowers: Vec<(u128, u64)> = (0..100u128).map(|i| (MAX_COLLATERAL - i, 9_990)).collect();
+    let mut book = open_book(lltv, &borrowers);
+    let initial_supply_assets = book.market.total_supply_assets;
+
+    // A max-LTV book 5% past its margin can't be liquidated without losses
+    let price = shocked_price(bad_debt_free_shock_bps(lltv) + 500);
+    let report = run_bot(&mut book, price);
+
+    assert!(report.liquidations as usize >= borrowers.len(), "every max-LTV position is reachable");
+    assert!(report.bad_debt > 0, "losses beyond the margin must surface as bad debt");
+    assert_book_settled(&book, price, initial_supply_assets, &report);
+}
@@ -25,6 +25,7 @@ use morpho_solana::state::{
     derive_position,
 };
 use morpho_solana::math::*;
+use morpho_solana::test_id::program_bytes;
 use morpho_solana::interfaces::calculate_lif;
 use morpho_solana::instruction as morpho_ix;
 use morpho_solana::accounts as morpho_accounts;
@@ -60,9 +61,9 @@ pub const ETH_USD_FEED: &str = "EdVCmQ9FSPcVe5YySXDPCRmc8aDQLKJ9xvYBMZPie1Vw";
 const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
 const INITIAL_BALANCE: u64 = 100 * LAMPORTS_PER_SOL;
 
-/// Morpho Program ID (must match lib.rs)
+/// Program ID for Morpho Solana (`test_id::TEST_PROGRAM_ID` under `test-id`)
 fn program_id() -> Pubkey {
-    "MorphoXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX".parse().unwrap()
+    morpho_solana::ID
 }
 
 /// Devnet test environment using Surfpool's forked state
@@ -104,8 +105,8 @@ impl DevnetTestEnv {
         
         // Load Morpho program
         let program_id = program_id();
-        let program_bytes = include_bytes!("../../../target/deploy/morpho_solana.so");
-        svm.add_program(program_id, program_bytes);
+        let program_bytes = program_bytes().expect("program binary missing; run `anchor build`");
+        svm.add_program(program_id, &program_bytes);
         
         // Parse Switchboard feed addresses
         let sol_usd_feed = SOL_USD_FEED.parse().unwrap_or(Pubkey::new_unique());
@@ -179,6 +180,7 @@ impl DevnetTestEnv {
             accounts: morpho_accounts::EnableLltv {
                 protocol_state: protocol_state_pda,
                 owner: self.owner.pubkey(),
+                system_program: system_program::ID,
             }.to_account_metas(None),
             data: morpho_ix::EnableLltv { lltv }.data(),
         };
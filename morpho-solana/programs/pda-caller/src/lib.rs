@@ -0,0 +1,217 @@
+//! PDA Caller
+//!
+//! Test-only stand-in for a vault or DAO program. Its `Vault` PDA owns
+//! Morpho positions, and every owner action reaches Morpho through CPI signed
+//! with the vault seeds. The workspace integration tests use it to check that
+//! program-owned positions can delegate, merge and close.
+
+use anchor_lang::prelude::*;
+use morpho_solana::cpi::accounts::{
+    ClosePosition as MorphoClosePosition, MergePositions as MorphoMergePositions,
+    SetAuthorization as MorphoSetAuthorization,
+};
+use morpho_solana::program::MorphoSolana;
+
+declare_id!("DBgkLGoQoBXrwf66wCZz4m6MbZQ83wH2w7ucujD7hx9o");
+
+#[program]
+pub mod pda_caller {
+    use super::*;
+
+    pub fn init_vault(ctx: Context<InitVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.bump = ctx.bumps.vault;
+        vault.authority = ctx.accounts.authority.key();
+        Ok(())
+    }
+
+    /// Grant (or update) `authorized` access to the vault's positions
+    pub fn set_authorization(
+        ctx: Context<VaultSetAuthorization>,
+        is_authorized: bool,
+        expires_at: i64,
+    ) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let seeds: &[&[u8]] = &[Vault::SEED, vault.authority.as_ref(), &[vault.bump]];
+
+        morpho_solana::cpi::set_authorization(
+            CpiContext::new_with_signer(
+                ctx.accounts.morpho_program.to_account_info(),
+                MorphoSetAuthorization {
+                    payer: ctx.accounts.authority.to_account_info(),
+                    authorizer: vault.to_account_info(),
+                    authorized: ctx.accounts.authorized.to_account_info(),
+                    authorization: ctx.accounts.authorization.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
+                &[seeds],
+            ),
+            is_authorized,
+            expires_at,
+        )
+    }
+
+    /// Fold the vault's position into `destination_position`, whose owner
+    /// must have authorized the vault
+    pub fn merge_positions(ctx: Context<VaultMergePositions>, market_id: [u8; 32]) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let seeds: &[&[u8]] = &[Vault::SEED, vault.authority.as_ref(), &[vault.bump]];
+
+        morpho_solana::cpi::merge_positions(
+            CpiContext::new_with_signer(
+                ctx.accounts.morpho_program.to_account_info(),
+                MorphoMergePositions {
+                    caller: vault.to_account_info(),
+                    protocol_state: ctx.accounts.protocol_state.to_account_info(),
+                    market: ctx.accounts.market.to_account_info(),
+                    source_position: ctx.accounts.source_position.to_account_info(),
+                    destination_position: ctx.accounts.destination_position.to_account_info(),
+                    source_authorization: None,
+                    destination_authorization: Some(
+                        ctx.accounts.destination_authorization.to_account_info(),
+                    ),
+                    oracle: None,
+                    rent_receiver: ctx.accounts.authority.to_account_info(),
+                },
+                &[seeds],
+            ),
+            market_id,
+        )
+    }
+
+    /// Close the vault's empty position, refunding rent to the authority
+    pub fn close_position(ctx: Context<VaultClosePosition>, market_id: [u8; 32]) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let seeds: &[&[u8]] = &[Vault::SEED, vault.authority.as_ref(), &[vault.bump]];
+
+        morpho_solana::cpi::close_position(
+            CpiContext::new_with_signer(
+                ctx.accounts.morpho_program.to_account_info(),
+                MorphoClosePosition {
+                    owner: vault.to_account_info(),
+                    rent_receiver: ctx.accounts.authority.to_account_info(),
+                    position: ctx.accounts.position.to_account_info(),
+                },
+                &[seeds],
+            ),
+            market_id,
+        )
+    }
+}
+
+/// Program-owned position owner
+///
+/// PDA Seeds: [b"vault", authority]
+#[account]
+pub struct Vault {
+    pub bump: u8,
+    pub authority: Pubkey,
+}
+
+impl Vault {
+    pub const SEED: &'static [u8] = b"vault";
+
+    pub fn space() -> usize {
+        8 + 1 + 32
+    }
+}
+
+/// Derive a vault PDA
+pub fn derive_vault(authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[Vault::SEED, authority.as_ref()], &crate::ID)
+}
+
+#[derive(Accounts)]
+pub struct InitVault<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Vault::space(),
+        seeds = [Vault::SEED, authority.key().as_ref()],
+        bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VaultSetAuthorization<'info> {
+    /// Pays the authorization's rent (the vault holds data, so it can't)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Vault::SEED, authority.key().as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Account being authorized, checked by Morpho
+    pub authorized: UncheckedAccount<'info>,
+
+    /// CHECK: Morpho authorization PDA, checked by Morpho
+    #[account(mut)]
+    pub authorization: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub morpho_program: Program<'info, MorphoSolana>,
+}
+
+#[derive(Accounts)]
+pub struct VaultMergePositions<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Writable because Morpho marks the merge caller mutable
+    #[account(
+        mut,
+        seeds = [Vault::SEED, authority.key().as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Checked by Morpho
+    pub protocol_state: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by Morpho
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+
+    /// CHECK: Vault's position, checked by Morpho
+    #[account(mut)]
+    pub source_position: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by Morpho
+    #[account(mut)]
+    pub destination_position: UncheckedAccount<'info>,
+
+    /// CHECK: Destination owner's authorization of the vault, checked by Morpho
+    pub destination_authorization: UncheckedAccount<'info>,
+
+    pub morpho_program: Program<'info, MorphoSolana>,
+}
+
+#[derive(Accounts)]
+pub struct VaultClosePosition<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Vault::SEED, authority.key().as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Vault's position, checked by Morpho
+    #[account(mut)]
+    pub position: UncheckedAccount<'info>,
+
+    pub morpho_program: Program<'info, MorphoSolana>,
+}
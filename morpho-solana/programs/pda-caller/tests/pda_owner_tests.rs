@@ -0,0 +1,234 @@
+//! PDA-Owned Position Tests
+//!
+//! Positions owned by another program's PDA, driven through `pda_caller`
+//! CPIs. Both programs must be built (`anchor build`) before running.
+
+use anchor_lang::{Discriminator, InstructionData, ToAccountMetas};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::system_program;
+use litesvm::LiteSVM;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+use morpho_solana::client::{self as morpho, MarketKeys};
+use morpho_solana::state::{derive_authorization, Market, Position};
+use morpho_solana::test_id::program_bytes;
+use pda_caller::{accounts as caller_accounts, derive_vault, instruction as caller_ix};
+
+const INITIAL_BALANCE: u64 = 100_000_000_000;
+
+struct Env {
+    svm: LiteSVM,
+    owner: Keypair,
+    /// Vault authority (pays rent, receives it back)
+    authority: Keypair,
+    alice: Keypair,
+    vault: Pubkey,
+    keys: MarketKeys,
+}
+
+impl Env {
+    fn new() -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program(morpho_solana::ID, &program_bytes().expect("run `anchor build` first"));
+        svm.add_program(pda_caller::ID, include_bytes!("../../../target/deploy/pda_caller.so"));
+
+        let owner = Keypair::new();
+        let authority = Keypair::new();
+        let alice = Keypair::new();
+        for kp in [&owner, &authority, &alice] {
+            svm.airdrop(&kp.pubkey(), INITIAL_BALANCE).unwrap();
+        }
+
+        let keys = MarketKeys::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            8500,
+            Pubkey::new_unique(),
+        );
+        let vault = derive_vault(&authority.pubkey()).0;
+
+        let mut env = Env { svm, owner, authority, alice, vault, keys };
+        env.send(
+            morpho::initialize(env.owner.pubkey(), env.owner.pubkey(), env.owner.pubkey()),
+            &[&env.owner.insecure_clone()],
+        )
+        .unwrap();
+        env.write_empty_market();
+        env.send(
+            Instruction {
+                program_id: pda_caller::ID,
+                accounts: caller_accounts::InitVault {
+                    authority: env.authority.pubkey(),
+                    vault: env.vault,
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: caller_ix::InitVault {}.data(),
+            },
+            &[&env.authority.insecure_clone()],
+        )
+        .unwrap();
+        env
+    }
+
+    /// Markets need an oracle and IRM to create; these tests only need the
+    /// account to exist, so write a zeroed one with the right bump
+    fn write_empty_market(&mut self) {
+        let (market, bump) = morpho_solana::state::derive_market(&morpho_solana::ID, &self.keys.market_id);
+        let mut data = vec![0u8; Market::space()];
+        data[..8].copy_from_slice(Market::DISCRIMINATOR);
+        data[8] = bump;
+        data[9..41].copy_from_slice(&self.keys.market_id);
+        self.svm
+            .set_account(
+                market,
+                Account {
+                    lamports: self.svm.minimum_balance_for_rent_exemption(data.len()),
+                    data,
+                    owner: morpho_solana::ID,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+    }
+
+    fn send(&mut self, ix: Instruction, signers: &[&Keypair]) -> Result<(), String> {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&signers[0].pubkey()),
+            signers,
+            self.svm.latest_blockhash(),
+        );
+        self.svm.send_transaction(tx).map(|_| ()).map_err(|e| format!("{:?}", e.err))
+    }
+
+    fn create_position(&mut self, owner: Pubkey) {
+        let payer = self.alice.insecure_clone();
+        self.send(morpho::create_position(payer.pubkey(), owner, &self.keys), &[&payer]).unwrap();
+    }
+
+    fn position_exists(&self, owner: &Pubkey) -> bool {
+        self.svm
+            .get_account(&self.keys.position(owner))
+            .is_some_and(|a| a.lamports > 0 && a.data.len() == Position::space())
+    }
+
+    fn vault_set_authorization(&mut self, authorized: Pubkey) -> Result<(), String> {
+        let authority = self.authority.insecure_clone();
+        self.send(
+            Instruction {
+                program_id: pda_caller::ID,
+                accounts: caller_accounts::VaultSetAuthorization {
+                    authority: authority.pubkey(),
+                    vault: self.vault,
+                    authorized,
+                    authorization: derive_authorization(&morpho_solana::ID, &self.vault, &authorized).0,
+                    system_program: system_program::ID,
+                    morpho_program: morpho_solana::ID,
+                }
+                .to_account_metas(None),
+                data: caller_ix::SetAuthorization { is_authorized: true, expires_at: 0 }.data(),
+            },
+            &[&authority],
+        )
+    }
+}
+
+#[test]
+fn test_pda_owner_closes_position_via_cpi() {
+    let mut env = Env::new();
+    let vault = env.vault;
+    env.create_position(vault);
+    assert!(env.position_exists(&vault));
+
+    // Without the vault program's signature the close is rejected
+    let alice = env.alice.insecure_clone();
+    let mut direct = morpho::close_position(vault, alice.pubkey(), &env.keys);
+    direct.accounts[0].is_signer = false;
+    assert!(env.send(direct, &[&alice]).is_err());
+
+    let authority = env.authority.insecure_clone();
+    let before = env.svm.get_account(&authority.pubkey()).unwrap().lamports;
+    env.send(
+        Instruction {
+            program_id: pda_caller::ID,
+            accounts: caller_accounts::VaultClosePosition {
+                authority: authority.pubkey(),
+                vault,
+                position: env.keys.position(&vault),
+                morpho_program: morpho_solana::ID,
+            }
+            .to_account_metas(None),
+            data: caller_ix::ClosePosition { market_id: env.keys.market_id }.data(),
+        },
+        &[&authority],
+    )
+    .unwrap();
+
+    assert!(!env.position_exists(&vault));
+    assert!(env.svm.get_account(&authority.pubkey()).unwrap().lamports > before);
+}
+
+#[test]
+fn test_pda_owner_delegates_to_wallet() {
+    let mut env = Env::new();
+    let vault = env.vault;
+    let alice = env.alice.insecure_clone();
+    env.create_position(vault);
+    env.create_position(alice.pubkey());
+
+    // Without the vault's authorization alice can't fold its position into hers
+    let merge = morpho::merge_positions(alice.pubkey(), vault, alice.pubkey(), alice.pubkey(), &env.keys);
+    assert!(env.send(merge.clone(), &[&alice]).is_err());
+
+    // The vault holds data, so its authority pays the authorization's rent
+    env.vault_set_authorization(alice.pubkey()).unwrap();
+    env.send(merge, &[&alice]).unwrap();
+
+    assert!(!env.position_exists(&vault));
+    assert!(env.position_exists(&alice.pubkey()));
+}
+
+#[test]
+fn test_pda_caller_acts_on_wallet_position() {
+    let mut env = Env::new();
+    let vault = env.vault;
+    let alice = env.alice.insecure_clone();
+    let authority = env.authority.insecure_clone();
+    env.create_position(vault);
+    env.create_position(alice.pubkey());
+
+    let merge_ix = |env: &Env| Instruction {
+        program_id: pda_caller::ID,
+        accounts: caller_accounts::VaultMergePositions {
+            authority: authority.pubkey(),
+            vault,
+            protocol_state: morpho_solana::state::derive_protocol_state(&morpho_solana::ID).0,
+            market: env.keys.market(),
+            source_position: env.keys.position(&vault),
+            destination_position: env.keys.position(&alice.pubkey()),
+            destination_authorization: derive_authorization(&morpho_solana::ID, &alice.pubkey(), &vault).0,
+            morpho_program: morpho_solana::ID,
+        }
+        .to_account_metas(None),
+        data: caller_ix::MergePositions { market_id: env.keys.market_id }.data(),
+    };
+
+    // Alice hasn't authorized the vault yet
+    let ix = merge_ix(&env);
+    assert!(env.send(ix, &[&authority]).is_err());
+
+    env.send(morpho::set_authorization(alice.pubkey(), alice.pubkey(), vault, true, 0), &[&alice])
+        .unwrap();
+    let ix = merge_ix(&env);
+    env.send(ix, &[&authority]).unwrap();
+
+    assert!(!env.position_exists(&vault));
+    assert!(env.position_exists(&alice.pubkey()));
+}
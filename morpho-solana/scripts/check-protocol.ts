@@ -21,8 +21,8 @@ async function main() {
         console.log("\n=== Protocol State ===");
         console.log("Owner:", state.owner.toBase58());
         console.log("Fee Recipient:", state.feeRecipient.toBase58());
-        console.log("LLTV Count:", state.lltvCount);
-        console.log("IRM Count:", state.irmCount);
+        console.log("LLTV Count:", state.enabledLltvs.length);
+        console.log("IRM Count:", state.enabledIrms.length);
         console.log("Paused:", state.paused);
 
         console.log("\n=== Ownership Match ===");
@@ -0,0 +1,120 @@
+//! Morpho Stress Scenario Generator
+//!
+//! Deploys the built program into LiteSVM, opens dozens of markets and
+//! thousands of borrowers, walks every oracle along a random price path and
+//! liquidates whatever goes underwater. Reports CU statistics per
+//! instruction and liquidation throughput for capacity planning.
+//!
+//! Build the program first (`anchor build`), then from the workspace root:
+//!
+//! ```text
+//! cargo run --release -p morpho-stress -- --markets 24 --positions 2000 --steps 100
+//! ```
+//!
+//! The same `--seed` reproduces the same amounts and price paths. CU figures
+//! include the compute-budget instruction every transaction carries.
+
+mod scenario;
+mod stats;
+
+use std::time::Instant;
+
+use scenario::Scenario;
+
+const USAGE: &str = "\
+usage: morpho-stress [options]
+  --program <path>        program binary (default: the workspace build)
+  --markets <n>           markets to create (default 24)
+  --positions <n>         borrowers, spread evenly across markets (default 2000)
+  --steps <n>             price steps to simulate (default 100)
+  --step-secs <n>         clock advance per step (default 3600)
+  --volatility-bps <n>    max per-step price move either way (default 300)
+  --drift-bps <n>         per-step price drift, may be negative (default -50)
+  --seed <n>              PRNG seed (default 42)
+  --verbose               print liquidations per step";
+
+/// Scenario parameters
+pub struct Config {
+    pub markets: usize,
+    pub positions: usize,
+    pub steps: usize,
+    pub step_secs: u64,
+    pub volatility_bps: u64,
+    pub drift_bps: i64,
+    pub seed: u64,
+    pub verbose: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            markets: 24,
+            positions: 2_000,
+            steps: 100,
+            step_secs: 3_600,
+            volatility_bps: 300,
+            drift_bps: -50,
+            seed: 42,
+            verbose: false,
+        }
+    }
+}
+
+fn parse_args() -> Result<(Config, String), String> {
+    let mut config = Config::default();
+    let mut program = morpho_solana::test_id::program_path().display().to_string();
+    let mut args = std::env::args().skip(1);
+
+    fn value<T: std::str::FromStr>(flag: &str, arg: Option<String>) -> Result<T, String> {
+        arg.and_then(|a| a.parse().ok())
+            .ok_or_else(|| format!("{} needs a numeric value", flag))
+    }
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--program" => program = args.next().ok_or("--program needs a path")?,
+            "--markets" => config.markets = value(&flag, args.next())?,
+            "--positions" => config.positions = value(&flag, args.next())?,
+            "--steps" => config.steps = value(&flag, args.next())?,
+            "--step-secs" => config.step_secs = value(&flag, args.next())?,
+            "--volatility-bps" => config.volatility_bps = value(&flag, args.next())?,
+            "--drift-bps" => config.drift_bps = value(&flag, args.next())?,
+            "--seed" => config.seed = value(&flag, args.next())?,
+            "--verbose" => config.verbose = true,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            other => return Err(format!("unknown option {}\n{}", other, USAGE)),
+        }
+    }
+
+    if config.markets == 0 {
+        return Err("--markets must be at least 1".to_string());
+    }
+    if config.volatility_bps >= 10_000 {
+        return Err("--volatility-bps must be below 10000".to_string());
+    }
+    Ok((config, program))
+}
+
+fn main() {
+    let (config, program_path) = parse_args().unwrap_or_else(|msg| {
+        eprintln!("{}", msg);
+        std::process::exit(2);
+    });
+    let program = std::fs::read(&program_path).unwrap_or_else(|e| {
+        eprintln!("can't read {} ({}); run `anchor build` first", program_path, e);
+        std::process::exit(1);
+    });
+
+    let started = Instant::now();
+    let mut scenario = Scenario::new(config, &program);
+    scenario.setup();
+    let setup_time = started.elapsed();
+    scenario.run();
+
+    scenario.stats.print_report();
+    println!(
+        "wall clock: setup {:.1}s, price paths {:.1}s",
+        setup_time.as_secs_f64(),
+        (started.elapsed() - setup_time).as_secs_f64()
+    );
+}
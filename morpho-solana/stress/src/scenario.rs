@@ -0,0 +1,404 @@
+//! Scenario setup and the price-path loop
+//!
+//! Token accounts, mints and oracles are written straight into the SVM
+//! rather than created through transactions: only Morpho instructions are
+//! measured, and setting up thousands of borrowers stays fast.
+
+use std::time::Instant;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use litesvm::LiteSVM;
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::solana_program::program_option::COption;
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+use morpho_solana::client::{self as morpho, position_debt, MarketKeys};
+use morpho_solana::constants::{BPS, MIN_ORACLE_PRICE, ORACLE_SCALE};
+use morpho_solana::interfaces::{is_liquidatable_with_stable_debt, max_oracle_price, prescale_price};
+use morpho_solana::math::mul_div_down;
+use morpho_solana::state::{Market, Position};
+
+use crate::stats::Stats;
+use crate::Config;
+
+const COLLATERAL_DECIMALS: u8 = 9;
+const LOAN_DECIMALS: u8 = 6;
+
+/// Collateral starts at $100 per whole token
+const INITIAL_UNIT_PRICE: u128 = 100;
+
+/// LLTV tiers markets are spread across
+const LLTV_TIERS: [u64; 3] = [7_700, 8_600, 9_150];
+
+/// Static oracles are never stale; any heartbeat in range will do
+const ORACLE_HEARTBEAT: u64 = 150;
+
+/// Borrowers open at 50-95% of their market's LLTV
+const MIN_OPEN_LTV_BPS: u64 = 5_000;
+const MAX_OPEN_LTV_BPS: u64 = 9_500;
+
+/// Suppliers provide this multiple of the book's total borrows
+const SUPPLY_HEADROOM: u128 = 2;
+
+const STATIC_ORACLE_LEN: usize = 57;
+const LAMPORTS_PER_WALLET: u64 = 10_000_000_000;
+
+/// Deterministic xorshift64* generator, so a seed reproduces a scenario
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in `lo..=hi`
+    pub fn range(&mut self, lo: u64, hi: u64) -> u64 {
+        lo + self.next_u64() % (hi - lo + 1)
+    }
+
+    /// Uniform in `-bound..=bound`
+    pub fn signed(&mut self, bound: u64) -> i64 {
+        self.range(0, 2 * bound) as i64 - bound as i64
+    }
+}
+
+struct Borrower {
+    wallet: Keypair,
+    /// Cleared once a liquidation leaves no debt behind
+    open: bool,
+}
+
+struct MarketBook {
+    keys: MarketKeys,
+    /// Prescaled oracle price
+    price: u128,
+    borrowers: Vec<Borrower>,
+}
+
+pub struct Scenario {
+    svm: LiteSVM,
+    config: Config,
+    rng: Rng,
+    owner: Keypair,
+    liquidator: Keypair,
+    collateral_mint: Pubkey,
+    loan_mint: Pubkey,
+    markets: Vec<MarketBook>,
+    pub stats: Stats,
+}
+
+impl Scenario {
+    pub fn new(config: Config, program: &[u8]) -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program(morpho_solana::ID, program);
+
+        let owner = Keypair::new();
+        let liquidator = Keypair::new();
+        for kp in [&owner, &liquidator] {
+            svm.airdrop(&kp.pubkey(), 1_000 * LAMPORTS_PER_WALLET).unwrap();
+        }
+
+        let rng = Rng::new(config.seed);
+        let mut scenario = Self {
+            svm,
+            config,
+            rng,
+            owner,
+            liquidator,
+            collateral_mint: Pubkey::new_unique(),
+            loan_mint: Pubkey::new_unique(),
+            markets: Vec::new(),
+            stats: Stats::default(),
+        };
+        scenario.write_mint(scenario.collateral_mint, COLLATERAL_DECIMALS);
+        scenario.write_mint(scenario.loan_mint, LOAN_DECIMALS);
+        scenario
+    }
+
+    // ========================================================================
+    // Setup
+    // ========================================================================
+
+    /// Initialize the protocol, then create every market and borrower
+    pub fn setup(&mut self) {
+        let owner = self.owner.insecure_clone();
+        let irm = Pubkey::new_unique();
+        self.send("initialize", morpho::initialize(owner.pubkey(), owner.pubkey(), owner.pubkey()), &[&owner])
+            .expect("initialize");
+        for lltv in LLTV_TIERS {
+            self.send("enable_lltv", morpho::enable_lltv(owner.pubkey(), lltv), &[&owner])
+                .expect("enable_lltv");
+        }
+        self.send("enable_irm", morpho::enable_irm(owner.pubkey(), irm), &[&owner])
+            .expect("enable_irm");
+
+        let liquidator = self.liquidator.pubkey();
+        self.write_token_account(liquidator, self.loan_mint, u64::MAX / 2);
+        self.write_token_account(liquidator, self.collateral_mint, 0);
+
+        let price = prescale_price(INITIAL_UNIT_PRICE * ORACLE_SCALE, COLLATERAL_DECIMALS, LOAN_DECIMALS)
+            .expect("initial price");
+        let per_market = self.config.positions.div_ceil(self.config.markets);
+        for i in 0..self.config.markets {
+            let oracle = Pubkey::new_unique();
+            self.write_oracle(oracle, price);
+            let keys = MarketKeys::new(
+                self.collateral_mint,
+                self.loan_mint,
+                oracle,
+                irm,
+                LLTV_TIERS[i % LLTV_TIERS.len()],
+                spl_token::ID,
+            );
+            self.send("create_market", morpho::create_market(owner.pubkey(), &keys, ORACLE_HEARTBEAT, 0), &[&owner])
+                .expect("create_market");
+            let count = per_market.min(self.config.positions.saturating_sub(i * per_market));
+            self.open_book(keys, price, count);
+        }
+        println!(
+            "setup: {} markets, {} borrowers",
+            self.markets.len(),
+            self.markets.iter().map(|m| m.borrowers.len()).sum::<usize>()
+        );
+    }
+
+    /// Supply liquidity to a new market and open `count` random borrowers
+    fn open_book(&mut self, keys: MarketKeys, price: u128, count: usize) {
+        // Plan first so the supplier knows how much liquidity to provide
+        let plans: Vec<(u64, u64)> = (0..count)
+            .map(|_| {
+                let collateral = self.rng.range(1, 100) * 10u64.pow(COLLATERAL_DECIMALS as u32);
+                let value = mul_div_down(collateral as u128, price, ORACLE_SCALE).unwrap();
+                let ltv = keys.lltv * self.rng.range(MIN_OPEN_LTV_BPS, MAX_OPEN_LTV_BPS) / BPS;
+                let debt = mul_div_down(value, ltv as u128, BPS as u128).unwrap();
+                (collateral, debt as u64)
+            })
+            .collect();
+        let liquidity = plans.iter().map(|&(_, debt)| debt as u128).sum::<u128>() * SUPPLY_HEADROOM;
+
+        let supplier = self.owner.insecure_clone();
+        let supplier_account = self.write_token_account(supplier.pubkey(), self.loan_mint, liquidity as u64);
+        self.send("create_position", morpho::create_position(supplier.pubkey(), supplier.pubkey(), &keys), &[&supplier])
+            .expect("create supplier position");
+        self.send(
+            "supply",
+            morpho::supply(supplier.pubkey(), supplier.pubkey(), supplier_account, &keys, liquidity, 0, false),
+            &[&supplier],
+        )
+        .expect("supply");
+
+        let mut borrowers = Vec::with_capacity(count);
+        for (collateral, debt) in plans {
+            let wallet = Keypair::new();
+            let borrower = wallet.pubkey();
+            self.svm.airdrop(&borrower, LAMPORTS_PER_WALLET).unwrap();
+            let collateral_account = self.write_token_account(borrower, self.collateral_mint, collateral);
+            self.write_token_account(borrower, self.loan_mint, 0);
+
+            self.send("create_position", morpho::create_position(borrower, borrower, &keys), &[&wallet])
+                .expect("create borrower position");
+            self.send(
+                "supply_collateral",
+                morpho::supply_collateral(borrower, borrower, collateral_account, &keys, collateral as u128),
+                &[&wallet],
+            )
+            .expect("supply_collateral");
+            let open = self
+                .send("borrow", morpho::borrow(borrower, borrower, borrower, &keys, debt as u128, 0), &[&wallet])
+                .is_ok();
+            borrowers.push(Borrower { wallet, open });
+        }
+
+        self.markets.push(MarketBook { keys, price, borrowers });
+    }
+
+    // ========================================================================
+    // Price Paths
+    // ========================================================================
+
+    /// Walk every market's price, then liquidate whatever went underwater
+    pub fn run(&mut self) {
+        for step in 0..self.config.steps {
+            self.advance_clock();
+            let mut liquidated = 0;
+            for m in 0..self.markets.len() {
+                self.move_price(m);
+                liquidated += self.liquidate_market(m);
+            }
+            self.stats.liquidations_per_step.push(liquidated);
+            if self.config.verbose {
+                println!("step {:>4}: {} liquidations", step, liquidated);
+            }
+        }
+    }
+
+    fn advance_clock(&mut self) {
+        let mut clock: Clock = self.svm.get_sysvar();
+        clock.slot += self.config.step_secs * 5 / 2;
+        clock.unix_timestamp += self.config.step_secs as i64;
+        self.svm.set_sysvar(&clock);
+        // Identical transactions (accrue_interest) must not be deduplicated
+        self.svm.expire_blockhash();
+    }
+
+    fn move_price(&mut self, m: usize) {
+        let change_bps = self.config.drift_bps + self.rng.signed(self.config.volatility_bps);
+        let book = &mut self.markets[m];
+        let scaled = book.price as i128 * (BPS as i128 + change_bps as i128) / BPS as i128;
+        // Keep the oracle readable: out-of-bounds prices revert every instruction
+        book.price = (scaled.max(0) as u128).clamp(MIN_ORACLE_PRICE, max_oracle_price());
+        let (oracle, price) = (book.keys.oracle, book.price);
+        self.write_oracle(oracle, price);
+    }
+
+    /// Accrue, then send a full liquidation for every liquidatable borrower
+    fn liquidate_market(&mut self, m: usize) -> usize {
+        let keys = self.markets[m].keys;
+        let owner = self.owner.insecure_clone();
+        let _ = self.send("accrue_interest", morpho::accrue_interest(keys.market_id, None), &[&owner]);
+        let price = self.markets[m].price;
+        let market: Market = self.load(&keys.market());
+        let liquidator = self.liquidator.insecure_clone();
+        let loan_account = get_associated_token_address(&liquidator.pubkey(), &keys.loan_mint);
+        let collateral_account = get_associated_token_address(&liquidator.pubkey(), &keys.collateral_mint);
+
+        let mut liquidated = 0;
+        for b in 0..self.markets[m].borrowers.len() {
+            if !self.markets[m].borrowers[b].open {
+                continue;
+            }
+            let borrower = self.markets[m].borrowers[b].wallet.pubkey();
+            let position: Position = self.load(&keys.position(&borrower));
+            let debt = position_debt(&market, &position).unwrap();
+            if debt == 0 {
+                self.markets[m].borrowers[b].open = false;
+                continue;
+            }
+            let underwater = is_liquidatable_with_stable_debt(
+                position.collateral,
+                position.borrow_shares,
+                position.stable_borrow_assets,
+                market.total_borrow_assets,
+                market.total_borrow_shares,
+                price,
+                market.lltv,
+            )
+            .unwrap_or(false);
+            if !underwater {
+                continue;
+            }
+
+            let ix = morpho::liquidate(liquidator.pubkey(), borrower, loan_account, collateral_account, &keys, debt);
+            if self.send("liquidate", ix, &[&liquidator]).is_ok() {
+                liquidated += 1;
+                let after: Position = self.load(&keys.position(&borrower));
+                self.markets[m].borrowers[b].open = after.borrow_shares > 0 || after.stable_borrow_assets > 0;
+            }
+        }
+        liquidated
+    }
+
+    // ========================================================================
+    // SVM Helpers
+    // ========================================================================
+
+    /// Send `ix` under the maximum CU limit, recording its cost under `name`
+    fn send(&mut self, name: &'static str, ix: Instruction, signers: &[&Keypair]) -> Result<(), String> {
+        let tx = Transaction::new_signed_with_payer(
+            &[ComputeBudgetInstruction::set_compute_unit_limit(1_400_000), ix],
+            Some(&signers[0].pubkey()),
+            signers,
+            self.svm.latest_blockhash(),
+        );
+        let start = Instant::now();
+        let result = self.svm.send_transaction(tx);
+        let elapsed = start.elapsed();
+        match result {
+            Ok(meta) => {
+                self.stats.ix(name).record_success(meta.compute_units_consumed, elapsed);
+                Ok(())
+            }
+            Err(failed) => {
+                let error = format!("{:?}", failed.err);
+                self.stats.ix(name).record_failure(error.clone(), elapsed);
+                Err(error)
+            }
+        }
+    }
+
+    fn load<T: AccountDeserialize>(&self, address: &Pubkey) -> T {
+        let account = self.svm.get_account(address).expect("account exists");
+        T::try_deserialize(&mut account.data.as_slice()).expect("account deserializes")
+    }
+
+    fn write_account(&mut self, address: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        self.svm
+            .set_account(
+                address,
+                Account {
+                    lamports: self.svm.minimum_balance_for_rent_exemption(data.len()),
+                    data,
+                    owner,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+    }
+
+    fn write_mint(&mut self, mint: Pubkey, decimals: u8) {
+        let mut data = vec![0u8; Mint::LEN];
+        Mint {
+            mint_authority: COption::Some(self.owner.pubkey()),
+            supply: u64::MAX,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        self.write_account(mint, spl_token::ID, data);
+    }
+
+    /// Write `owner`'s ATA for `mint` holding `amount`
+    fn write_token_account(&mut self, owner: Pubkey, mint: Pubkey, amount: u64) -> Pubkey {
+        let address = get_associated_token_address(&owner, &mint);
+        let mut data = vec![0u8; TokenAccount::LEN];
+        TokenAccount {
+            mint,
+            owner,
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        self.write_account(address, spl_token::ID, data);
+        address
+    }
+
+    /// Static oracle layout: discriminator(8) + bump(1) + price(16) + admin(32)
+    fn write_oracle(&mut self, oracle: Pubkey, price: u128) {
+        let mut data = vec![0u8; STATIC_ORACLE_LEN];
+        data[8] = 1;
+        data[9..25].copy_from_slice(&price.to_le_bytes());
+        data[25..57].copy_from_slice(self.owner.pubkey().as_ref());
+        self.write_account(oracle, morpho_solana::ID, data);
+    }
+}
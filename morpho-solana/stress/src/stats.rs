@@ -0,0 +1,112 @@
+//! Compute-unit and outcome tallies per instruction kind
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Samples for one instruction kind
+#[derive(Default)]
+pub struct IxStats {
+    /// CU consumed by each successful transaction
+    pub compute_units: Vec<u64>,
+    pub failures: usize,
+    /// Failure count by error, for the report
+    pub errors: BTreeMap<String, usize>,
+    /// Wall-clock time spent inside the SVM
+    pub elapsed: Duration,
+}
+
+impl IxStats {
+    pub fn record_success(&mut self, compute_units: u64, elapsed: Duration) {
+        self.compute_units.push(compute_units);
+        self.elapsed += elapsed;
+    }
+
+    pub fn record_failure(&mut self, error: String, elapsed: Duration) {
+        self.failures += 1;
+        *self.errors.entry(error).or_default() += 1;
+        self.elapsed += elapsed;
+    }
+
+    pub fn successes(&self) -> usize {
+        self.compute_units.len()
+    }
+
+    /// Successful transactions per wall-clock second
+    pub fn throughput(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 {
+            0.0
+        } else {
+            self.successes() as f64 / secs
+        }
+    }
+
+    /// (min, mean, p50, p95, max), or `None` without samples
+    pub fn summary(&self) -> Option<(u64, u64, u64, u64, u64)> {
+        if self.compute_units.is_empty() {
+            return None;
+        }
+        let mut sorted = self.compute_units.clone();
+        sorted.sort_unstable();
+        let pct = |p: usize| sorted[(sorted.len() - 1) * p / 100];
+        let mean = sorted.iter().sum::<u64>() / sorted.len() as u64;
+        Some((sorted[0], mean, pct(50), pct(95), sorted[sorted.len() - 1]))
+    }
+}
+
+/// All instruction kinds, keyed by name so the report order is stable
+#[derive(Default)]
+pub struct Stats {
+    pub by_ix: BTreeMap<&'static str, IxStats>,
+    /// Liquidations executed in each price step
+    pub liquidations_per_step: Vec<usize>,
+}
+
+impl Stats {
+    pub fn ix(&mut self, name: &'static str) -> &mut IxStats {
+        self.by_ix.entry(name).or_default()
+    }
+
+    pub fn print_report(&self) {
+        println!();
+        println!(
+            "{:<20} {:>8} {:>6} {:>8} {:>8} {:>8} {:>8} {:>8} {:>10}",
+            "instruction", "ok", "fail", "min CU", "mean CU", "p50 CU", "p95 CU", "max CU", "tx/s"
+        );
+        for (name, ix) in &self.by_ix {
+            let (min, mean, p50, p95, max) = ix.summary().unwrap_or_default();
+            println!(
+                "{:<20} {:>8} {:>6} {:>8} {:>8} {:>8} {:>8} {:>8} {:>10.0}",
+                name,
+                ix.successes(),
+                ix.failures,
+                min,
+                mean,
+                p50,
+                p95,
+                max,
+                ix.throughput()
+            );
+        }
+
+        for (name, ix) in &self.by_ix {
+            for (error, count) in &ix.errors {
+                println!("  {} failed {}x: {}", name, count, error);
+            }
+        }
+
+        let steps = &self.liquidations_per_step;
+        if !steps.is_empty() {
+            let total: usize = steps.iter().sum();
+            let peak = steps.iter().copied().max().unwrap_or(0);
+            println!();
+            println!(
+                "liquidations: {} over {} steps (mean {:.1}/step, peak {}/step)",
+                total,
+                steps.len(),
+                total as f64 / steps.len() as f64,
+                peak
+            );
+        }
+    }
+}
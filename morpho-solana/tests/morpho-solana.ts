@@ -9,6 +9,7 @@ import {
 } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
   createMint,
   createAssociatedTokenAccount,
   getAssociatedTokenAddress,
@@ -26,8 +27,11 @@ const MARKET_SEED = Buffer.from("morpho_market");
 const POSITION_SEED = Buffer.from("morpho_position");
 const COLLATERAL_VAULT_SEED = Buffer.from("morpho_collateral_vault");
 const LOAN_VAULT_SEED = Buffer.from("morpho_loan_vault");
+const LLTV_BOUNDS_SEED = Buffer.from("morpho_lltv_bounds");
 
 const LLTV_85_PERCENT = 8500;
+const ORACLE_HEARTBEAT_SLOTS = 50;
+const ORACLE_MAX_AGE_SECS = 60;
 
 // Switchboard Devnet Oracle Feeds (real on-demand pull feeds)
 // SOL/USD: https://app.switchboard.xyz/solana/devnet
@@ -93,6 +97,13 @@ function deriveLoanVaultPda(programId: PublicKey, marketId: Buffer): [PublicKey,
   );
 }
 
+function deriveLltvBoundsPda(programId: PublicKey, loanMint: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [PROGRAM_SEED_PREFIX, LLTV_BOUNDS_SEED, loanMint.toBuffer()],
+    programId
+  );
+}
+
 async function fundAccount(
   provider: anchor.AnchorProvider,
   pubkey: PublicKey,
@@ -266,6 +277,7 @@ describe("morpho-solana comprehensive e2e tests", () => {
           .accountsStrict({
             owner: provider.wallet.publicKey,
             protocolState: protocolStatePda,
+            systemProgram: SystemProgram.programId,
           })
           .rpc(); // No extra signers needed - provider wallet signs automatically
         console.log("    Enable LLTV tx:", tx);
@@ -278,8 +290,8 @@ describe("morpho-solana comprehensive e2e tests", () => {
       }
 
       const state = await program.account.protocolState.fetch(protocolStatePda);
-      if (state.lltvCount < 1) throw new Error("LLTV count should be at least 1");
-      console.log("    LLTV count:", state.lltvCount);
+      if (state.enabledLltvs.length < 1) throw new Error("LLTV count should be at least 1");
+      console.log("    LLTV count:", state.enabledLltvs.length);
     });
 
     it("1.4 Enables an IRM", async () => {
@@ -289,6 +301,7 @@ describe("morpho-solana comprehensive e2e tests", () => {
           .accountsStrict({
             owner: provider.wallet.publicKey,
             protocolState: protocolStatePda,
+            systemProgram: SystemProgram.programId,
           })
           .rpc();
         console.log("    Enable IRM tx:", tx);
@@ -301,8 +314,8 @@ describe("morpho-solana comprehensive e2e tests", () => {
       }
 
       const state = await program.account.protocolState.fetch(protocolStatePda);
-      if (state.irmCount < 1) throw new Error("IRM count should be at least 1");
-      console.log("    IRM count:", state.irmCount);
+      if (state.enabledIrms.length < 1) throw new Error("IRM count should be at least 1");
+      console.log("    IRM count:", state.enabledIrms.length);
     });
 
     it("1.5 Sets fee recipient", async () => {
@@ -497,6 +510,7 @@ describe("morpho-solana comprehensive e2e tests", () => {
     it("3.1 Creates a market", async () => {
       const [collateralVaultPda] = deriveCollateralVaultPda(program.programId, marketId);
       const [loanVaultPda] = deriveLoanVaultPda(program.programId, marketId);
+      const [lltvBoundsPda] = deriveLltvBoundsPda(program.programId, loanMint);
 
       try {
         const tx = await program.methods
@@ -505,7 +519,9 @@ describe("morpho-solana comprehensive e2e tests", () => {
             loanMint,
             oracle,
             irm.publicKey,
-            new BN(LLTV_85_PERCENT)
+            new BN(LLTV_85_PERCENT),
+            new BN(ORACLE_HEARTBEAT_SLOTS),
+            new BN(ORACLE_MAX_AGE_SECS)
           )
           .accountsStrict({
             creator: provider.wallet.publicKey,
@@ -517,8 +533,10 @@ describe("morpho-solana comprehensive e2e tests", () => {
             loanVault: loanVaultPda,
             oracle: oracle,
             irm: irm.publicKey,
+            lltvBounds: lltvBoundsPda,
             tokenProgram: TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
+            fallbackOracle: null, // Optional - no secondary feed
           })
           .rpc();
         console.log("    Create market tx:", tx);
@@ -733,11 +751,16 @@ describe("morpho-solana comprehensive e2e tests", () => {
             market: marketPda,
             position: bobPositionPda,
             loanMint: loanMint,
+            receiver: bob.publicKey,
             receiverTokenAccount: bobLoanAta,
             loanVault: loanVaultPda,
             oracle: oracle,
             authorization: null, // Optional - not using delegated borrow
             tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            riskOracle: null, // Optional - market has no risk oracle
+            fallbackOracle: null, // Optional - market has no fallback oracle
           })
           .signers([bob])
           .rpc();